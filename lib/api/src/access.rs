@@ -1,10 +1,13 @@
 use std::mem::MaybeUninit;
 
-use crate::mem_access::{WasmRef, WasmSlice};
+use crate::{
+    host_profiling,
+    mem_access::{WasmRef, WasmSlice},
+};
 
 pub(super) enum SliceCow<'a, T> {
     #[allow(dead_code)]
-    Borrowed(&'a mut [T]),
+    Borrowed(&'a mut [T], bool),
     #[allow(dead_code)]
     Owned(Vec<T>, bool),
 }
@@ -12,7 +15,7 @@ pub(super) enum SliceCow<'a, T> {
 impl<'a, T> AsRef<[T]> for SliceCow<'a, T> {
     fn as_ref(&self) -> &[T] {
         match self {
-            Self::Borrowed(buf) => buf,
+            Self::Borrowed(buf, _) => buf,
             Self::Owned(buf, _) => buf,
         }
     }
@@ -24,7 +27,10 @@ impl<'a, T> AsMut<[T]> for SliceCow<'a, T> {
         //       not leak the bytes into the memory
         // https://stackoverflow.com/questions/61114026/does-stdptrwrite-transfer-the-uninitialized-ness-of-the-bytes-it-writes
         match self {
-            Self::Borrowed(buf) => buf,
+            Self::Borrowed(buf, modified) => {
+                *modified = true;
+                buf
+            }
             Self::Owned(buf, modified) => {
                 *modified = true;
                 buf.as_mut()
@@ -114,9 +120,21 @@ where
     T: wasmer_types::ValueType,
 {
     fn drop(&mut self) {
-        if let SliceCow::Owned(buf, modified) = &self.buf {
-            if *modified {
-                self.slice.write_slice(buf.as_ref()).ok();
+        match &self.buf {
+            // Copies are read and written back through the memory buffer,
+            // which does its own accounting
+            SliceCow::Owned(buf, modified) => {
+                if *modified {
+                    self.slice.write_slice(buf.as_ref()).ok();
+                }
+            }
+            SliceCow::Borrowed(buf, modified) => {
+                let bytes = std::mem::size_of_val::<[T]>(&**buf);
+                if *modified {
+                    host_profiling::record_written(bytes);
+                } else {
+                    host_profiling::record_read(bytes);
+                }
             }
         }
     }
@@ -124,7 +142,7 @@ where
 
 pub(super) enum RefCow<'a, T> {
     #[allow(dead_code)]
-    Borrowed(&'a mut T),
+    Borrowed(&'a mut T, bool),
     #[allow(dead_code)]
     Owned(T, bool),
 }
@@ -132,7 +150,7 @@ pub(super) enum RefCow<'a, T> {
 impl<'a, T> AsRef<T> for RefCow<'a, T> {
     fn as_ref(&self) -> &T {
         match self {
-            Self::Borrowed(val) => val,
+            Self::Borrowed(val, _) => val,
             Self::Owned(val, _) => val,
         }
     }
@@ -144,7 +162,10 @@ impl<'a, T> AsMut<T> for RefCow<'a, T> {
         //       not leak the bytes into the memory
         // https://stackoverflow.com/questions/61114026/does-stdptrwrite-transfer-the-uninitialized-ness-of-the-bytes-it-writes
         match self {
-            Self::Borrowed(val) => val,
+            Self::Borrowed(val, modified) => {
+                *modified = true;
+                val
+            }
             Self::Owned(val, modified) => {
                 *modified = true;
                 val
@@ -186,9 +207,19 @@ where
     T: wasmer_types::ValueType,
 {
     fn drop(&mut self) {
-        if let RefCow::Owned(val, modified) = &self.buf {
-            if *modified {
-                self.ptr.write(*val).ok();
+        match &self.buf {
+            RefCow::Owned(val, modified) => {
+                if *modified {
+                    self.ptr.write(*val).ok();
+                }
+            }
+            RefCow::Borrowed(_, modified) => {
+                let bytes = std::mem::size_of::<T>();
+                if *modified {
+                    host_profiling::record_written(bytes);
+                } else {
+                    host_profiling::record_read(bytes);
+                }
             }
         }
     }
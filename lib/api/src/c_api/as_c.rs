@@ -128,6 +128,7 @@ pub fn result_to_value(param: &Value) -> wasm_val_t {
                 },
             },
             Value::ExternRef(_) => panic!("Creating host values from guest ExternRefs is not currently supported through wasm_c_api.") ,
+            Value::ExceptionRef(_) => panic!("Creating host values from guest ExceptionRefs is not currently supported through wasm_c_api."),
             Value::V128(_) => panic!("Creating host values from guest V128s is not currently supported through wasm_c_api."),
         }
     }
@@ -164,6 +165,7 @@ pub fn result_to_value(param: &Value) -> wasm_val_t {
                 },
             },
             Value::ExternRef(_) => panic!("Creating host values from guest ExternRefs is not currently supported through wasm_c_api.") ,
+            Value::ExceptionRef(_) => panic!("Creating host values from guest ExceptionRefs is not currently supported through wasm_c_api."),
             Value::V128(_) => panic!("Creating host values from guest V128s is not currently supported through wasm_c_api."),
         }
     }
@@ -177,6 +179,9 @@ pub fn type_to_c(type_: &Type) -> wasm_valkind_t {
         Type::F32 => wasm_valkind_enum_WASM_F32 as _,
         Type::F64 => wasm_valkind_enum_WASM_F64 as _,
         Type::FuncRef => wasm_valkind_enum_WASM_FUNCREF as _,
+        Type::ExceptionRef => {
+            panic!("ExceptionRefs are not currently supported through wasm_c_api")
+        }
         Type::ExternRef => {
             #[cfg(any(feature = "wasmi", feature = "wamr"))]
             {
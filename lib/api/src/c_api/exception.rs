@@ -0,0 +1,33 @@
+use crate::c_api::vm::VMExceptionRef;
+use crate::store::{AsStoreMut, AsStoreRef};
+use crate::{Tag, Value};
+
+/// Exceptions can't be thrown with this backend, so there is no way to get
+/// an `Exception`.
+#[derive(Debug, Clone)]
+pub enum Exception {}
+
+impl Exception {
+    pub fn tag(&self, _store: &impl AsStoreRef) -> Tag {
+        match *self {}
+    }
+
+    pub fn payload(&self, _store: &mut impl AsStoreMut) -> Vec<Value> {
+        match *self {}
+    }
+
+    pub(crate) fn vm_exceptionref(&self) -> VMExceptionRef {
+        match *self {}
+    }
+
+    pub(crate) unsafe fn from_vm_exceptionref(
+        _store: &mut impl AsStoreMut,
+        vm_exceptionref: VMExceptionRef,
+    ) -> Self {
+        match vm_exceptionref {}
+    }
+
+    pub fn is_from_store(&self, _store: &impl AsStoreRef) -> bool {
+        match *self {}
+    }
+}
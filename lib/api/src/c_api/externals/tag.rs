@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use crate::c_api::vm::VMExtern;
+use crate::store::{AsStoreMut, AsStoreRef};
+use crate::TagType;
+
+/// A tag created on the host.
+///
+/// `wasm.h` has no tags, so a module importing one fails to link, and tags
+/// exported by modules are left out of their exports.
+#[derive(Debug, Clone)]
+pub struct Tag {
+    ty: Arc<TagType>,
+}
+
+impl Tag {
+    pub fn new(_store: &mut impl AsStoreMut, ty: TagType) -> Self {
+        Self { ty: Arc::new(ty) }
+    }
+
+    pub fn ty(&self, _store: &impl AsStoreRef) -> TagType {
+        (*self.ty).clone()
+    }
+
+    pub fn is_from_store(&self, _store: &impl AsStoreRef) -> bool {
+        true
+    }
+
+    /// Tags have no `wasm_extern_t`, so this is always null.
+    pub(crate) fn to_vm_extern(&self) -> VMExtern {
+        std::ptr::null_mut()
+    }
+}
+
+impl PartialEq for Tag {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ty, &other.ty)
+    }
+}
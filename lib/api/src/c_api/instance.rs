@@ -5,7 +5,7 @@ use std::ops::DerefMut;
 use std::sync::Arc;
 use std::{mem, ptr};
 
-use wasmer_types::CompileError;
+use wasmer_types::{CompileError, ExternType, ImportError};
 
 use crate::bindings::{
     wasm_extern_t, wasm_extern_vec_new, wasm_extern_vec_new_empty,
@@ -14,7 +14,7 @@ use crate::bindings::{
     wasm_store_t, wasm_trap_t,
 };
 use crate::c_api::vm::VMInstance;
-use crate::errors::InstantiationError;
+use crate::errors::{InstantiationError, LinkError};
 use crate::exports::Exports;
 use crate::imports::Imports;
 use crate::module::Module;
@@ -113,6 +113,8 @@ impl InstanceHandle {
         let exports = exports_ty
             .iter()
             .zip(wasm_exports.into_iter())
+            // Tags have no `wasm_extern_t` counterpart, so they are skipped
+            .filter(|(export_type, _)| !matches!(export_type.ty(), ExternType::Tag(_)))
             .map(|(export_type, wasm_export)| {
                 let name = export_type.name();
                 let mut store = store.as_store_mut();
@@ -172,6 +174,17 @@ impl Instance {
         module: &Module,
         externs: &[Extern],
     ) -> Result<(Self, Exports), InstantiationError> {
+        // Tags have no `wasm_extern_t`, so they can't be imported.
+        for (import_ty, extern_) in module.imports().zip(externs) {
+            if let Extern::Tag(_) = extern_ {
+                return Err(InstantiationError::Link(LinkError::Import(
+                    import_ty.module().to_string(),
+                    import_ty.name().to_string(),
+                    ImportError::UnknownImport(import_ty.ty().clone()),
+                )));
+            }
+        }
+
         let store_ref = store.as_store_ref();
         let externs: Vec<*mut wasm_extern_t> = externs
             .iter()
@@ -192,3 +205,14 @@ impl Instance {
         ))
     }
 }
+
+impl Instance {
+    /// Globals that aren't exported can't be accessed with this backend.
+    pub(crate) fn global(
+        &self,
+        _store: &mut impl AsStoreMut,
+        _index: wasmer_types::GlobalIndex,
+    ) -> Option<crate::Global> {
+        None
+    }
+}
@@ -40,6 +40,7 @@ pub(crate) mod as_c;
 pub(crate) mod bindings;
 pub(crate) mod engine;
 pub(crate) mod errors;
+pub(crate) mod exception;
 pub(crate) mod extern_ref;
 pub(crate) mod externals;
 pub(crate) mod instance;
@@ -142,6 +142,25 @@ impl VMExternRef {
     }
 }
 
+/// Exceptions can't be thrown with this backend, so no `exnref` ever refers
+/// to one.
+pub(crate) enum VMExceptionRef {}
+
+impl VMExceptionRef {
+    /// Converts the `VMExceptionRef` into a `RawValue`.
+    pub fn into_raw(self) -> RawValue {
+        match self {}
+    }
+
+    /// Extracts a `VMExceptionRef` from a `RawValue`.
+    ///
+    /// # Safety
+    /// `raw` must be a valid `VMExceptionRef` instance.
+    pub unsafe fn from_raw(_raw: RawValue) -> Option<Self> {
+        None
+    }
+}
+
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct VMFuncRef;
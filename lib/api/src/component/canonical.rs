@@ -0,0 +1,1227 @@
+//! The canonical ABI: lifting core WebAssembly values and memory into
+//! component values, and lowering component values back.
+//!
+//! See the [canonical ABI specification] for the layout of each type.
+//!
+//! [canonical ABI specification]: https://github.com/WebAssembly/component-model/blob/main/design/mvp/CanonicalABI.md
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::types::{FuncType, ResourceType, Type};
+use super::values::{Dtor, ResourceAny, Val};
+use crate::store::StoreMut;
+use crate::{Function, FunctionType, Memory, RuntimeError, Type as ValType, Value};
+
+/// The maximum number of flat parameters before they are passed in memory.
+pub(crate) const MAX_FLAT_PARAMS: usize = 16;
+/// The maximum number of flat results before they are returned in memory.
+pub(crate) const MAX_FLAT_RESULTS: usize = 1;
+
+const UTF16_TAG: u32 = 1 << 31;
+
+/// The encoding of the strings in the memory of a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StringEncoding {
+    Utf8,
+    Utf16,
+    CompactUtf16,
+}
+
+/// The canonical options of a lifted or lowered function.
+#[derive(Debug, Clone)]
+pub(crate) struct Options {
+    pub(crate) memory: Option<Memory>,
+    pub(crate) realloc: Option<Function>,
+    pub(crate) post_return: Option<Function>,
+    pub(crate) encoding: StringEncoding,
+}
+
+/// A handle in the handle table of a component instance.
+#[derive(Debug)]
+pub(crate) struct Handle {
+    pub(crate) ty: ResourceType,
+    pub(crate) rep: u32,
+    pub(crate) owned: bool,
+    pub(crate) dtor: Option<Dtor>,
+}
+
+/// The handles to resources held by a component instance.
+///
+/// Handles are the indices of the table plus one, as `0` is never a valid
+/// handle.
+#[derive(Debug, Default)]
+pub(crate) struct HandleTable {
+    slots: Vec<Option<Handle>>,
+    free: Vec<u32>,
+}
+
+impl HandleTable {
+    pub(crate) fn insert(&mut self, handle: Handle) -> u32 {
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize] = Some(handle);
+                index + 1
+            }
+            None => {
+                self.slots.push(Some(handle));
+                self.slots.len() as u32
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, handle: u32, ty: ResourceType) -> Result<&Handle, RuntimeError> {
+        match handle
+            .checked_sub(1)
+            .and_then(|index| self.slots.get(index as usize))
+        {
+            Some(Some(entry)) if entry.ty == ty => Ok(entry),
+            Some(Some(_)) => Err(RuntimeError::new(format!(
+                "handle {handle} has the wrong resource type"
+            ))),
+            _ => Err(RuntimeError::new(format!("unknown handle index {handle}"))),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, handle: u32, ty: ResourceType) -> Result<Handle, RuntimeError> {
+        self.get(handle, ty)?;
+        let index = handle - 1;
+        self.free.push(index);
+        Ok(self.slots[index as usize].take().unwrap())
+    }
+}
+
+/// The state of a component instance used while lifting and lowering.
+#[derive(Debug)]
+pub(crate) struct InstanceState {
+    pub(crate) id: u64,
+    pub(crate) handles: Mutex<HandleTable>,
+    /// The destructors of the resources defined by the instance, and of
+    /// the host resources it imports.
+    pub(crate) dtors: Mutex<HashMap<ResourceType, Dtor>>,
+}
+
+impl InstanceState {
+    pub(crate) fn new() -> Self {
+        Self {
+            id: ResourceType::next_instance_id(),
+            handles: Mutex::default(),
+            dtors: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn dtor(&self, ty: ResourceType) -> Option<Dtor> {
+        self.dtors.lock().unwrap().get(&ty).cloned()
+    }
+
+    /// Implements `resource.new`.
+    pub(crate) fn resource_new(&self, ty: ResourceType, rep: u32) -> u32 {
+        let dtor = self.dtor(ty);
+        self.handles.lock().unwrap().insert(Handle {
+            ty,
+            rep,
+            owned: true,
+            dtor,
+        })
+    }
+
+    /// Implements `resource.rep`.
+    pub(crate) fn resource_rep(&self, ty: ResourceType, handle: u32) -> Result<u32, RuntimeError> {
+        Ok(self.handles.lock().unwrap().get(handle, ty)?.rep)
+    }
+
+    /// Implements `resource.drop`.
+    pub(crate) fn resource_drop(
+        &self,
+        store: &mut StoreMut<'_>,
+        ty: ResourceType,
+        handle: u32,
+    ) -> Result<(), RuntimeError> {
+        let handle = self.handles.lock().unwrap().remove(handle, ty)?;
+        match (handle.owned, handle.dtor) {
+            (true, Some(dtor)) => dtor.call(store, handle.rep),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The context of a lift or lower operation.
+pub(crate) struct Cx<'a, 'b> {
+    pub(crate) store: &'a mut StoreMut<'b>,
+    pub(crate) options: &'a Options,
+    pub(crate) instance: &'a InstanceState,
+    /// The handles lent to the instance for the duration of a call.
+    pub(crate) borrows: Vec<u32>,
+}
+
+impl<'a, 'b> Cx<'a, 'b> {
+    pub(crate) fn new(
+        store: &'a mut StoreMut<'b>,
+        options: &'a Options,
+        instance: &'a InstanceState,
+    ) -> Self {
+        Self {
+            store,
+            options,
+            instance,
+            borrows: Vec::new(),
+        }
+    }
+
+    /// Releases the handles lent for the duration of a call.
+    pub(crate) fn release_borrows(&mut self) {
+        let mut handles = self.instance.handles.lock().unwrap();
+        for handle in self.borrows.drain(..) {
+            let index = handle as usize - 1;
+            if handles.slots[index].take().is_some() {
+                handles.free.push(index as u32);
+            }
+        }
+    }
+
+    fn memory(&self) -> Result<&Memory, RuntimeError> {
+        self.options
+            .memory
+            .as_ref()
+            .ok_or_else(|| RuntimeError::new("canonical option `memory` is required"))
+    }
+
+    fn read_bytes(&self, ptr: usize, len: usize) -> Result<Vec<u8>, RuntimeError> {
+        let mut buf = vec![0; len];
+        self.memory()?
+            .view(self.store)
+            .read(ptr as u64, &mut buf)
+            .map_err(|_| RuntimeError::new("out of bounds memory access"))?;
+        Ok(buf)
+    }
+
+    fn read<const N: usize>(&self, ptr: usize) -> Result<[u8; N], RuntimeError> {
+        let mut buf = [0; N];
+        self.memory()?
+            .view(self.store)
+            .read(ptr as u64, &mut buf)
+            .map_err(|_| RuntimeError::new("out of bounds memory access"))?;
+        Ok(buf)
+    }
+
+    fn write(&self, ptr: usize, bytes: &[u8]) -> Result<(), RuntimeError> {
+        self.memory()?
+            .view(self.store)
+            .write(ptr as u64, bytes)
+            .map_err(|_| RuntimeError::new("out of bounds memory access"))
+    }
+
+    fn realloc(&mut self, align: usize, size: usize) -> Result<usize, RuntimeError> {
+        let realloc = self
+            .options
+            .realloc
+            .as_ref()
+            .ok_or_else(|| RuntimeError::new("canonical option `realloc` is required"))?;
+        let args = [
+            Value::I32(0),
+            Value::I32(0),
+            Value::I32(align as i32),
+            Value::I32(size as i32),
+        ];
+        let ptr = match *realloc.call(self.store, &args)? {
+            [Value::I32(ptr)] => ptr as u32 as usize,
+            _ => return Err(RuntimeError::new("invalid `realloc` result")),
+        };
+        if ptr % align != 0 {
+            return Err(RuntimeError::new("unaligned pointer returned by `realloc`"));
+        }
+        if ptr + size > self.memory()?.view(self.store).data_size() as usize {
+            return Err(RuntimeError::new(
+                "out of bounds pointer returned by `realloc`",
+            ));
+        }
+        Ok(ptr)
+    }
+}
+
+fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+fn discriminant_size(cases: usize) -> usize {
+    match cases {
+        0..=0x100 => 1,
+        0x101..=0x10000 => 2,
+        _ => 4,
+    }
+}
+
+/// Returns the cases of a variant-like type.
+fn cases(ty: &Type) -> Option<Vec<Option<&Type>>> {
+    Some(match ty {
+        Type::Variant(cases) => cases.iter().map(|(_, ty)| ty.as_ref()).collect(),
+        Type::Enum(cases) => vec![None; cases.len()],
+        Type::Option(ty) => vec![None, Some(&**ty)],
+        Type::Result { ok, err } => vec![ok.as_deref(), err.as_deref()],
+        _ => return None,
+    })
+}
+
+fn flags_size(flags: usize) -> usize {
+    match flags {
+        0 => 0,
+        1..=8 => 1,
+        9..=16 => 2,
+        _ => 4 * flags.div_ceil(32),
+    }
+}
+
+/// The alignment of a type in memory.
+pub(crate) fn align(ty: &Type) -> usize {
+    match ty {
+        Type::Bool | Type::S8 | Type::U8 => 1,
+        Type::S16 | Type::U16 => 2,
+        Type::S32 | Type::U32 | Type::Float32 | Type::Char | Type::Own(_) | Type::Borrow(_) => 4,
+        Type::S64 | Type::U64 | Type::Float64 => 8,
+        Type::String | Type::List(_) => 4,
+        Type::Record(fields) => fields.iter().map(|(_, ty)| align(ty)).max().unwrap_or(1),
+        Type::Tuple(types) => types.iter().map(align).max().unwrap_or(1),
+        Type::Flags(flags) => flags_size(flags.len()).clamp(1, 4),
+        ty => {
+            let cases = cases(ty).unwrap();
+            cases
+                .iter()
+                .flatten()
+                .map(|ty| align(ty))
+                .fold(discriminant_size(cases.len()), usize::max)
+        }
+    }
+}
+
+/// The size of a type in memory.
+pub(crate) fn size(ty: &Type) -> usize {
+    match ty {
+        Type::Bool | Type::S8 | Type::U8 => 1,
+        Type::S16 | Type::U16 => 2,
+        Type::S32 | Type::U32 | Type::Float32 | Type::Char | Type::Own(_) | Type::Borrow(_) => 4,
+        Type::S64 | Type::U64 | Type::Float64 => 8,
+        Type::String | Type::List(_) => 8,
+        Type::Record(fields) => fields_size(fields.iter().map(|(_, ty)| ty)),
+        Type::Tuple(types) => fields_size(types.iter()),
+        Type::Flags(flags) => flags_size(flags.len()),
+        ty => {
+            let cases = cases(ty).unwrap();
+            let payload = payload_offset(&cases);
+            let max_size = cases.iter().flatten().map(|ty| size(ty)).max().unwrap_or(0);
+            align_to(payload + max_size, align(ty))
+        }
+    }
+}
+
+fn fields_size<'a>(types: impl Iterator<Item = &'a Type> + Clone) -> usize {
+    let mut offset = 0;
+    let mut max_align = 1;
+    for ty in types {
+        let align = align(ty);
+        max_align = max_align.max(align);
+        offset = align_to(offset, align) + size(ty);
+    }
+    align_to(offset, max_align)
+}
+
+fn payload_offset(cases: &[Option<&Type>]) -> usize {
+    let max_align = cases
+        .iter()
+        .flatten()
+        .map(|ty| align(ty))
+        .max()
+        .unwrap_or(1);
+    align_to(discriminant_size(cases.len()), max_align)
+}
+
+/// Appends the flat core types of a type to `out`.
+pub(crate) fn flatten(ty: &Type, out: &mut Vec<ValType>) {
+    match ty {
+        Type::Bool
+        | Type::S8
+        | Type::U8
+        | Type::S16
+        | Type::U16
+        | Type::S32
+        | Type::U32
+        | Type::Char
+        | Type::Own(_)
+        | Type::Borrow(_) => out.push(ValType::I32),
+        Type::S64 | Type::U64 => out.push(ValType::I64),
+        Type::Float32 => out.push(ValType::F32),
+        Type::Float64 => out.push(ValType::F64),
+        Type::String | Type::List(_) => out.extend([ValType::I32, ValType::I32]),
+        Type::Record(fields) => fields.iter().for_each(|(_, ty)| flatten(ty, out)),
+        Type::Tuple(types) => types.iter().for_each(|ty| flatten(ty, out)),
+        Type::Flags(flags) => {
+            out.extend(std::iter::repeat(ValType::I32).take(flags.len().div_ceil(32)))
+        }
+        ty => {
+            out.push(ValType::I32);
+            out.extend(variant_payload(&cases(ty).unwrap()));
+        }
+    }
+}
+
+/// The joined flat types of the payloads of a variant.
+fn variant_payload(cases: &[Option<&Type>]) -> Vec<ValType> {
+    let mut joined: Vec<ValType> = Vec::new();
+    for ty in cases.iter().flatten() {
+        let mut flat = Vec::new();
+        flatten(ty, &mut flat);
+        for (i, ty) in flat.into_iter().enumerate() {
+            match joined.get_mut(i) {
+                Some(joined) => *joined = join(*joined, ty),
+                None => joined.push(ty),
+            }
+        }
+    }
+    joined
+}
+
+fn join(a: ValType, b: ValType) -> ValType {
+    match (a, b) {
+        (a, b) if a == b => a,
+        (ValType::I32, ValType::F32) | (ValType::F32, ValType::I32) => ValType::I32,
+        _ => ValType::I64,
+    }
+}
+
+fn flatten_types<'a>(types: impl IntoIterator<Item = &'a Type>) -> Vec<ValType> {
+    let mut flat = Vec::new();
+    for ty in types {
+        flatten(ty, &mut flat);
+    }
+    flat
+}
+
+/// The core function type of a lowered function.
+pub(crate) fn lowered_func_type(ty: &FuncType) -> FunctionType {
+    let mut params = flatten_types(ty.params.iter().map(|(_, ty)| ty));
+    if params.len() > MAX_FLAT_PARAMS {
+        params = vec![ValType::I32];
+    }
+    let mut results = flatten_types(&ty.results);
+    if results.len() > MAX_FLAT_RESULTS {
+        params.push(ValType::I32);
+        results = vec![];
+    }
+    FunctionType::new(params, results)
+}
+
+/// The core function type a lifted function must have.
+pub(crate) fn lifted_func_type(ty: &FuncType) -> FunctionType {
+    let mut params = flatten_types(ty.params.iter().map(|(_, ty)| ty));
+    if params.len() > MAX_FLAT_PARAMS {
+        params = vec![ValType::I32];
+    }
+    let mut results = flatten_types(&ty.results);
+    if results.len() > MAX_FLAT_RESULTS {
+        results = vec![ValType::I32];
+    }
+    FunctionType::new(params, results)
+}
+
+fn mismatch(ty: &Type, val: &Val) -> RuntimeError {
+    RuntimeError::new(format!(
+        "type mismatch: expected a value of type `{}`, found `{}`",
+        ty.desc(),
+        val.desc()
+    ))
+}
+
+fn next(src: &mut dyn Iterator<Item = Value>) -> Result<Value, RuntimeError> {
+    src.next()
+        .ok_or_else(|| RuntimeError::new("missing flat value"))
+}
+
+fn next_i32(src: &mut dyn Iterator<Item = Value>) -> Result<i32, RuntimeError> {
+    match next(src)? {
+        Value::I32(value) => Ok(value),
+        _ => Err(RuntimeError::new("invalid flat value")),
+    }
+}
+
+fn next_i64(src: &mut dyn Iterator<Item = Value>) -> Result<i64, RuntimeError> {
+    match next(src)? {
+        Value::I64(value) => Ok(value),
+        _ => Err(RuntimeError::new("invalid flat value")),
+    }
+}
+
+fn char_from_u32(value: u32) -> Result<char, RuntimeError> {
+    char::from_u32(value).ok_or_else(|| RuntimeError::new("invalid `char` bit pattern"))
+}
+
+/// Converts a flat value of a joined variant payload to the flat type of a
+/// case.
+fn coerce_lift(value: Value, ty: ValType) -> Value {
+    match (value, ty) {
+        (Value::I32(x), ValType::F32) => Value::F32(f32::from_bits(x as u32)),
+        (Value::I64(x), ValType::I32) => Value::I32(x as i32),
+        (Value::I64(x), ValType::F32) => Value::F32(f32::from_bits(x as u32)),
+        (Value::I64(x), ValType::F64) => Value::F64(f64::from_bits(x as u64)),
+        (value, _) => value,
+    }
+}
+
+/// Converts a flat value of a variant case to the joined flat type.
+fn coerce_lower(value: Value, ty: ValType) -> Value {
+    match (value, ty) {
+        (Value::F32(x), ValType::I32) => Value::I32(x.to_bits() as i32),
+        (Value::I32(x), ValType::I64) => Value::I64(x as u32 as i64),
+        (Value::F32(x), ValType::I64) => Value::I64(x.to_bits() as i64),
+        (Value::F64(x), ValType::I64) => Value::I64(x.to_bits() as i64),
+        (value, _) => value,
+    }
+}
+
+fn zero(ty: ValType) -> Value {
+    match ty {
+        ValType::I64 => Value::I64(0),
+        ValType::F32 => Value::F32(0.0),
+        ValType::F64 => Value::F64(0.0),
+        _ => Value::I32(0),
+    }
+}
+
+/// Wraps the case and payload of a variant-like type into a value.
+fn make_variant(ty: &Type, case: usize, payload: Option<Val>) -> Val {
+    let payload = payload.map(Box::new);
+    match ty {
+        Type::Variant(cases) => Val::Variant(cases[case].0.clone(), payload),
+        Type::Enum(cases) => Val::Enum(cases[case].clone()),
+        Type::Option(_) => Val::Option(payload),
+        Type::Result { .. } if case == 0 => Val::Result(Ok(payload)),
+        _ => Val::Result(Err(payload)),
+    }
+}
+
+/// Returns the case and payload of a value of a variant-like type.
+fn variant_case<'v>(ty: &Type, val: &'v Val) -> Result<(usize, Option<&'v Val>), RuntimeError> {
+    let (case, payload) = match (ty, val) {
+        (Type::Variant(cases), Val::Variant(name, payload)) => {
+            let case = cases
+                .iter()
+                .position(|(case, _)| case == name)
+                .ok_or_else(|| RuntimeError::new(format!("unknown variant case `{name}`")))?;
+            (case, payload.as_deref())
+        }
+        (Type::Enum(cases), Val::Enum(name)) => {
+            let case = cases
+                .iter()
+                .position(|case| case == name)
+                .ok_or_else(|| RuntimeError::new(format!("unknown enum case `{name}`")))?;
+            (case, None)
+        }
+        (Type::Option(_), Val::Option(payload)) => (payload.is_some() as usize, payload.as_deref()),
+        (Type::Result { .. }, Val::Result(Ok(payload))) => (0, payload.as_deref()),
+        (Type::Result { .. }, Val::Result(Err(payload))) => (1, payload.as_deref()),
+        _ => return Err(mismatch(ty, val)),
+    };
+    match (cases(ty).unwrap()[case], payload) {
+        (Some(_), Some(_)) | (None, None) => Ok((case, payload)),
+        (Some(_), None) => Err(RuntimeError::new("missing variant payload")),
+        (None, Some(_)) => Err(RuntimeError::new("unexpected variant payload")),
+    }
+}
+
+fn flags_bits(names: &[String], val: &Val, ty: &Type) -> Result<Vec<u32>, RuntimeError> {
+    let Val::Flags(set) = val else {
+        return Err(mismatch(ty, val));
+    };
+    let mut bits = vec![0u32; names.len().div_ceil(32)];
+    for flag in set {
+        let index = names
+            .iter()
+            .position(|name| name == flag)
+            .ok_or_else(|| RuntimeError::new(format!("unknown flag `{flag}`")))?;
+        bits[index / 32] |= 1 << (index % 32);
+    }
+    Ok(bits)
+}
+
+fn flags_from_bits(names: &[String], bits: impl Fn(usize) -> u32) -> Val {
+    Val::Flags(
+        names
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| bits(i / 32) & (1 << (i % 32)) != 0)
+            .map(|(_, name)| name.clone())
+            .collect(),
+    )
+}
+
+fn record_field<'v>(fields: &'v [(String, Val)], name: &str) -> Result<&'v Val, RuntimeError> {
+    fields
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, val)| val)
+        .ok_or_else(|| RuntimeError::new(format!("missing record field `{name}`")))
+}
+
+fn check_len(expected: usize, found: usize) -> Result<(), RuntimeError> {
+    if expected != found {
+        return Err(RuntimeError::new(format!(
+            "expected {expected} values, found {found}"
+        )));
+    }
+    Ok(())
+}
+
+impl Cx<'_, '_> {
+    /// Lifts a value of type `ty` from flat core values.
+    pub(crate) fn lift_flat(
+        &mut self,
+        ty: &Type,
+        src: &mut dyn Iterator<Item = Value>,
+    ) -> Result<Val, RuntimeError> {
+        Ok(match ty {
+            Type::Bool => Val::Bool(next_i32(src)? != 0),
+            Type::S8 => Val::S8(next_i32(src)? as i8),
+            Type::U8 => Val::U8(next_i32(src)? as u8),
+            Type::S16 => Val::S16(next_i32(src)? as i16),
+            Type::U16 => Val::U16(next_i32(src)? as u16),
+            Type::S32 => Val::S32(next_i32(src)?),
+            Type::U32 => Val::U32(next_i32(src)? as u32),
+            Type::S64 => Val::S64(next_i64(src)?),
+            Type::U64 => Val::U64(next_i64(src)? as u64),
+            Type::Float32 => match next(src)? {
+                Value::F32(x) => Val::Float32(x),
+                _ => return Err(RuntimeError::new("invalid flat value")),
+            },
+            Type::Float64 => match next(src)? {
+                Value::F64(x) => Val::Float64(x),
+                _ => return Err(RuntimeError::new("invalid flat value")),
+            },
+            Type::Char => Val::Char(char_from_u32(next_i32(src)? as u32)?),
+            Type::String => {
+                let ptr = next_i32(src)? as u32;
+                let len = next_i32(src)? as u32;
+                Val::String(self.lift_string(ptr, len)?)
+            }
+            Type::List(elem) => {
+                let ptr = next_i32(src)? as u32;
+                let len = next_i32(src)? as u32;
+                Val::List(self.lift_list(elem, ptr, len)?)
+            }
+            Type::Record(fields) => Val::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| Ok((name.clone(), self.lift_flat(ty, src)?)))
+                    .collect::<Result<_, RuntimeError>>()?,
+            ),
+            Type::Tuple(types) => Val::Tuple(
+                types
+                    .iter()
+                    .map(|ty| self.lift_flat(ty, src))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Type::Flags(names) => {
+                let bits = (0..names.len().div_ceil(32))
+                    .map(|_| next_i32(src).map(|bits| bits as u32))
+                    .collect::<Result<Vec<_>, _>>()?;
+                flags_from_bits(names, |i| bits[i])
+            }
+            Type::Own(ty) => Val::Resource(self.lift_own(*ty, next_i32(src)? as u32)?),
+            Type::Borrow(ty) => Val::Resource(self.lift_borrow(*ty, next_i32(src)? as u32)?),
+            ty => {
+                let cases = cases(ty).unwrap();
+                let case = next_i32(src)? as u32 as usize;
+                let joined = variant_payload(&cases);
+                let payload = joined
+                    .iter()
+                    .map(|_| next(src))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let case_ty = cases
+                    .get(case)
+                    .ok_or_else(|| RuntimeError::new("invalid variant discriminant"))?;
+                let payload = match case_ty {
+                    Some(case_ty) => {
+                        let mut flat = Vec::new();
+                        flatten(case_ty, &mut flat);
+                        let mut values = payload
+                            .into_iter()
+                            .zip(flat)
+                            .map(|(value, ty)| coerce_lift(value, ty));
+                        Some(self.lift_flat(case_ty, &mut values)?)
+                    }
+                    None => None,
+                };
+                make_variant(ty, case, payload)
+            }
+        })
+    }
+
+    /// Lowers a value of type `ty` to flat core values.
+    pub(crate) fn lower_flat(
+        &mut self,
+        ty: &Type,
+        val: &Val,
+        dst: &mut Vec<Value>,
+    ) -> Result<(), RuntimeError> {
+        match (ty, val) {
+            (Type::Bool, Val::Bool(x)) => dst.push(Value::I32(*x as i32)),
+            (Type::S8, Val::S8(x)) => dst.push(Value::I32(*x as i32)),
+            (Type::U8, Val::U8(x)) => dst.push(Value::I32(*x as i32)),
+            (Type::S16, Val::S16(x)) => dst.push(Value::I32(*x as i32)),
+            (Type::U16, Val::U16(x)) => dst.push(Value::I32(*x as i32)),
+            (Type::S32, Val::S32(x)) => dst.push(Value::I32(*x)),
+            (Type::U32, Val::U32(x)) => dst.push(Value::I32(*x as i32)),
+            (Type::S64, Val::S64(x)) => dst.push(Value::I64(*x)),
+            (Type::U64, Val::U64(x)) => dst.push(Value::I64(*x as i64)),
+            (Type::Float32, Val::Float32(x)) => dst.push(Value::F32(*x)),
+            (Type::Float64, Val::Float64(x)) => dst.push(Value::F64(*x)),
+            (Type::Char, Val::Char(x)) => dst.push(Value::I32(*x as i32)),
+            (Type::String, Val::String(s)) => {
+                let (ptr, len) = self.lower_string(s)?;
+                dst.extend([Value::I32(ptr as i32), Value::I32(len as i32)]);
+            }
+            (Type::List(elem), Val::List(vals)) => {
+                let (ptr, len) = self.lower_list(elem, vals)?;
+                dst.extend([Value::I32(ptr as i32), Value::I32(len as i32)]);
+            }
+            (Type::Record(fields), Val::Record(vals)) => {
+                check_len(fields.len(), vals.len())?;
+                for (name, ty) in fields {
+                    self.lower_flat(ty, record_field(vals, name)?, dst)?;
+                }
+            }
+            (Type::Tuple(types), Val::Tuple(vals)) => {
+                check_len(types.len(), vals.len())?;
+                for (ty, val) in types.iter().zip(vals) {
+                    self.lower_flat(ty, val, dst)?;
+                }
+            }
+            (Type::Flags(names), val) => {
+                let bits = flags_bits(names, val, ty)?;
+                dst.extend(bits.into_iter().map(|bits| Value::I32(bits as i32)));
+            }
+            (Type::Own(ty), Val::Resource(resource)) => {
+                dst.push(Value::I32(self.lower_own(*ty, resource)? as i32))
+            }
+            (Type::Borrow(ty), Val::Resource(resource)) => {
+                dst.push(Value::I32(self.lower_borrow(*ty, resource)? as i32))
+            }
+            (ty, val) => {
+                let cases = cases(ty).ok_or_else(|| mismatch(ty, val))?;
+                let (case, payload) = variant_case(ty, val)?;
+                dst.push(Value::I32(case as i32));
+                let mut flat = Vec::new();
+                if let (Some(case_ty), Some(payload)) = (cases[case], payload) {
+                    self.lower_flat(case_ty, payload, &mut flat)?;
+                }
+                let mut flat = flat.into_iter();
+                for ty in variant_payload(&cases) {
+                    dst.push(match flat.next() {
+                        Some(value) => coerce_lower(value, ty),
+                        None => zero(ty),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads a value of type `ty` from memory.
+    pub(crate) fn load(&mut self, ty: &Type, ptr: usize) -> Result<Val, RuntimeError> {
+        Ok(match ty {
+            Type::Bool => Val::Bool(self.read::<1>(ptr)?[0] != 0),
+            Type::S8 => Val::S8(self.read::<1>(ptr)?[0] as i8),
+            Type::U8 => Val::U8(self.read::<1>(ptr)?[0]),
+            Type::S16 => Val::S16(i16::from_le_bytes(self.read(ptr)?)),
+            Type::U16 => Val::U16(u16::from_le_bytes(self.read(ptr)?)),
+            Type::S32 => Val::S32(i32::from_le_bytes(self.read(ptr)?)),
+            Type::U32 => Val::U32(u32::from_le_bytes(self.read(ptr)?)),
+            Type::S64 => Val::S64(i64::from_le_bytes(self.read(ptr)?)),
+            Type::U64 => Val::U64(u64::from_le_bytes(self.read(ptr)?)),
+            Type::Float32 => Val::Float32(f32::from_le_bytes(self.read(ptr)?)),
+            Type::Float64 => Val::Float64(f64::from_le_bytes(self.read(ptr)?)),
+            Type::Char => Val::Char(char_from_u32(u32::from_le_bytes(self.read(ptr)?))?),
+            Type::String => {
+                let ptr_len: [u8; 8] = self.read(ptr)?;
+                let (data, len) = split_ptr_len(ptr_len);
+                Val::String(self.lift_string(data, len)?)
+            }
+            Type::List(elem) => {
+                let ptr_len: [u8; 8] = self.read(ptr)?;
+                let (data, len) = split_ptr_len(ptr_len);
+                Val::List(self.lift_list(elem, data, len)?)
+            }
+            Type::Record(fields) => {
+                let mut offset = 0;
+                let mut vals = Vec::with_capacity(fields.len());
+                for (name, ty) in fields {
+                    offset = align_to(offset, align(ty));
+                    vals.push((name.clone(), self.load(ty, ptr + offset)?));
+                    offset += size(ty);
+                }
+                Val::Record(vals)
+            }
+            Type::Tuple(types) => Val::Tuple(self.load_fields(types, ptr)?),
+            Type::Flags(names) => {
+                let bits = match flags_size(names.len()) {
+                    0 => vec![],
+                    1 => vec![self.read::<1>(ptr)?[0] as u32],
+                    2 => vec![u16::from_le_bytes(self.read(ptr)?) as u32],
+                    size => (0..size / 4)
+                        .map(|i| Ok(u32::from_le_bytes(self.read(ptr + 4 * i)?)))
+                        .collect::<Result<_, RuntimeError>>()?,
+                };
+                flags_from_bits(names, |i| bits[i])
+            }
+            Type::Own(ty) => {
+                let handle = u32::from_le_bytes(self.read(ptr)?);
+                Val::Resource(self.lift_own(*ty, handle)?)
+            }
+            Type::Borrow(ty) => {
+                let handle = u32::from_le_bytes(self.read(ptr)?);
+                Val::Resource(self.lift_borrow(*ty, handle)?)
+            }
+            ty => {
+                let cases = cases(ty).unwrap();
+                let case = match discriminant_size(cases.len()) {
+                    1 => self.read::<1>(ptr)?[0] as usize,
+                    2 => u16::from_le_bytes(self.read(ptr)?) as usize,
+                    _ => u32::from_le_bytes(self.read(ptr)?) as usize,
+                };
+                let case_ty = cases
+                    .get(case)
+                    .ok_or_else(|| RuntimeError::new("invalid variant discriminant"))?;
+                let payload = match case_ty {
+                    Some(case_ty) => Some(self.load(case_ty, ptr + payload_offset(&cases))?),
+                    None => None,
+                };
+                make_variant(ty, case, payload)
+            }
+        })
+    }
+
+    /// Stores a value of type `ty` in memory.
+    pub(crate) fn store(&mut self, ty: &Type, val: &Val, ptr: usize) -> Result<(), RuntimeError> {
+        match (ty, val) {
+            (Type::Bool, Val::Bool(x)) => self.write(ptr, &[*x as u8]),
+            (Type::S8, Val::S8(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::U8, Val::U8(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::S16, Val::S16(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::U16, Val::U16(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::S32, Val::S32(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::U32, Val::U32(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::S64, Val::S64(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::U64, Val::U64(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::Float32, Val::Float32(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::Float64, Val::Float64(x)) => self.write(ptr, &x.to_le_bytes()),
+            (Type::Char, Val::Char(x)) => self.write(ptr, &(*x as u32).to_le_bytes()),
+            (Type::String, Val::String(s)) => {
+                let (data, len) = self.lower_string(s)?;
+                self.write(ptr, &join_ptr_len(data, len))
+            }
+            (Type::List(elem), Val::List(vals)) => {
+                let (data, len) = self.lower_list(elem, vals)?;
+                self.write(ptr, &join_ptr_len(data, len))
+            }
+            (Type::Record(fields), Val::Record(vals)) => {
+                check_len(fields.len(), vals.len())?;
+                let mut offset = 0;
+                for (name, ty) in fields {
+                    offset = align_to(offset, align(ty));
+                    self.store(ty, record_field(vals, name)?, ptr + offset)?;
+                    offset += size(ty);
+                }
+                Ok(())
+            }
+            (Type::Tuple(types), Val::Tuple(vals)) => self.store_fields(types, vals, ptr),
+            (Type::Flags(names), val) => {
+                let bits = flags_bits(names, val, ty)?;
+                match flags_size(names.len()) {
+                    0 => Ok(()),
+                    1 => self.write(ptr, &[bits[0] as u8]),
+                    2 => self.write(ptr, &(bits[0] as u16).to_le_bytes()),
+                    _ => bits
+                        .iter()
+                        .enumerate()
+                        .try_for_each(|(i, bits)| self.write(ptr + 4 * i, &bits.to_le_bytes())),
+                }
+            }
+            (Type::Own(ty), Val::Resource(resource)) => {
+                let handle = self.lower_own(*ty, resource)?;
+                self.write(ptr, &handle.to_le_bytes())
+            }
+            (Type::Borrow(ty), Val::Resource(resource)) => {
+                let handle = self.lower_borrow(*ty, resource)?;
+                self.write(ptr, &handle.to_le_bytes())
+            }
+            (ty, val) => {
+                let cases = cases(ty).ok_or_else(|| mismatch(ty, val))?;
+                let (case, payload) = variant_case(ty, val)?;
+                match discriminant_size(cases.len()) {
+                    1 => self.write(ptr, &[case as u8])?,
+                    2 => self.write(ptr, &(case as u16).to_le_bytes())?,
+                    _ => self.write(ptr, &(case as u32).to_le_bytes())?,
+                }
+                match (cases[case], payload) {
+                    (Some(case_ty), Some(payload)) => {
+                        self.store(case_ty, payload, ptr + payload_offset(&cases))
+                    }
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Loads the fields of a tuple from memory.
+    pub(crate) fn load_fields(
+        &mut self,
+        types: &[Type],
+        ptr: usize,
+    ) -> Result<Vec<Val>, RuntimeError> {
+        let mut offset = 0;
+        let mut vals = Vec::with_capacity(types.len());
+        for ty in types {
+            offset = align_to(offset, align(ty));
+            vals.push(self.load(ty, ptr + offset)?);
+            offset += size(ty);
+        }
+        Ok(vals)
+    }
+
+    /// Stores the fields of a tuple in memory.
+    pub(crate) fn store_fields(
+        &mut self,
+        types: &[Type],
+        vals: &[Val],
+        ptr: usize,
+    ) -> Result<(), RuntimeError> {
+        check_len(types.len(), vals.len())?;
+        let mut offset = 0;
+        for (ty, val) in types.iter().zip(vals) {
+            offset = align_to(offset, align(ty));
+            self.store(ty, val, ptr + offset)?;
+            offset += size(ty);
+        }
+        Ok(())
+    }
+
+    fn lift_string(&mut self, ptr: u32, len: u32) -> Result<String, RuntimeError> {
+        let ptr = ptr as usize;
+        match self.options.encoding {
+            StringEncoding::Utf8 => {
+                let bytes = self.read_bytes(ptr, len as usize)?;
+                String::from_utf8(bytes).map_err(|_| RuntimeError::new("invalid UTF-8 string"))
+            }
+            StringEncoding::Utf16 => self.lift_utf16(ptr, len as usize),
+            StringEncoding::CompactUtf16 if len & UTF16_TAG != 0 => {
+                self.lift_utf16(ptr, (len & !UTF16_TAG) as usize)
+            }
+            StringEncoding::CompactUtf16 => {
+                let bytes = self.read_bytes(ptr, len as usize)?;
+                Ok(bytes.into_iter().map(char::from).collect())
+            }
+        }
+    }
+
+    fn lift_utf16(&mut self, ptr: usize, len: usize) -> Result<String, RuntimeError> {
+        if ptr % 2 != 0 {
+            return Err(RuntimeError::new("unaligned string pointer"));
+        }
+        let bytes = self.read_bytes(ptr, len * 2)?;
+        let units = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect::<Vec<_>>();
+        String::from_utf16(&units).map_err(|_| RuntimeError::new("invalid UTF-16 string"))
+    }
+
+    fn lower_string(&mut self, s: &str) -> Result<(u32, u32), RuntimeError> {
+        let (bytes, align, len) = match self.options.encoding {
+            StringEncoding::Utf8 => (s.as_bytes().to_vec(), 1, s.len() as u32),
+            StringEncoding::CompactUtf16 if s.chars().all(|c| (c as u32) < 0x100) => {
+                let bytes = s.chars().map(|c| c as u8).collect::<Vec<_>>();
+                let len = bytes.len() as u32;
+                (bytes, 2, len)
+            }
+            encoding => {
+                let units = s.encode_utf16().collect::<Vec<_>>();
+                let len = units.len() as u32;
+                let bytes = units.into_iter().flat_map(u16::to_le_bytes).collect();
+                let tag = match encoding {
+                    StringEncoding::CompactUtf16 => UTF16_TAG,
+                    _ => 0,
+                };
+                (bytes, 2, len | tag)
+            }
+        };
+        let ptr = self.realloc(align, bytes.len())?;
+        self.write(ptr, &bytes)?;
+        Ok((ptr as u32, len))
+    }
+
+    fn lift_list(&mut self, elem: &Type, ptr: u32, len: u32) -> Result<Vec<Val>, RuntimeError> {
+        let (ptr, len) = (ptr as usize, len as usize);
+        if ptr % align(elem) != 0 {
+            return Err(RuntimeError::new("unaligned list pointer"));
+        }
+        let elem_size = size(elem);
+        let end = elem_size
+            .checked_mul(len)
+            .and_then(|size| size.checked_add(ptr))
+            .ok_or_else(|| RuntimeError::new("list out of bounds"))?;
+        if end > self.memory()?.view(self.store).data_size() as usize {
+            return Err(RuntimeError::new("list out of bounds"));
+        }
+        match elem {
+            Type::U8 => Ok(self
+                .read_bytes(ptr, len)?
+                .into_iter()
+                .map(Val::U8)
+                .collect()),
+            Type::S8 => Ok(self
+                .read_bytes(ptr, len)?
+                .into_iter()
+                .map(|x| Val::S8(x as i8))
+                .collect()),
+            elem => (0..len)
+                .map(|i| self.load(elem, ptr + i * elem_size))
+                .collect(),
+        }
+    }
+
+    fn lower_list(&mut self, elem: &Type, vals: &[Val]) -> Result<(u32, u32), RuntimeError> {
+        let elem_size = size(elem);
+        let ptr = self.realloc(align(elem), elem_size * vals.len())?;
+        for (i, val) in vals.iter().enumerate() {
+            self.store(elem, val, ptr + i * elem_size)?;
+        }
+        Ok((ptr as u32, vals.len() as u32))
+    }
+
+    fn lift_own(&mut self, ty: ResourceType, handle: u32) -> Result<ResourceAny, RuntimeError> {
+        let handle = self.instance.handles.lock().unwrap().remove(handle, ty)?;
+        if !handle.owned {
+            return Err(RuntimeError::new("cannot lift a borrowed handle as owned"));
+        }
+        Ok(ResourceAny {
+            ty,
+            rep: handle.rep,
+            owned: true,
+            dtor: handle.dtor,
+        })
+    }
+
+    fn lift_borrow(&mut self, ty: ResourceType, handle: u32) -> Result<ResourceAny, RuntimeError> {
+        let rep = self.instance.handles.lock().unwrap().get(handle, ty)?.rep;
+        Ok(ResourceAny {
+            ty,
+            rep,
+            owned: false,
+            dtor: None,
+        })
+    }
+
+    fn lower_own(&mut self, ty: ResourceType, resource: &ResourceAny) -> Result<u32, RuntimeError> {
+        if resource.ty != ty {
+            return Err(RuntimeError::new("mismatched resource types"));
+        }
+        if !resource.owned {
+            return Err(RuntimeError::new(
+                "cannot lower a borrowed resource as owned",
+            ));
+        }
+        let dtor = resource.dtor.clone().or_else(|| self.instance.dtor(ty));
+        Ok(self.instance.handles.lock().unwrap().insert(Handle {
+            ty,
+            rep: resource.rep,
+            owned: true,
+            dtor,
+        }))
+    }
+
+    fn lower_borrow(
+        &mut self,
+        ty: ResourceType,
+        resource: &ResourceAny,
+    ) -> Result<u32, RuntimeError> {
+        if resource.ty != ty {
+            return Err(RuntimeError::new("mismatched resource types"));
+        }
+        // An instance receives the representation of its own resources.
+        if ty.instance() == Some(self.instance.id) {
+            return Ok(resource.rep);
+        }
+        let handle = self.instance.handles.lock().unwrap().insert(Handle {
+            ty,
+            rep: resource.rep,
+            owned: false,
+            dtor: None,
+        });
+        self.borrows.push(handle);
+        Ok(handle)
+    }
+
+    /// Lowers the arguments of a call to a lifted function.
+    pub(crate) fn lower_args(
+        &mut self,
+        ty: &FuncType,
+        args: &[Val],
+    ) -> Result<Vec<Value>, RuntimeError> {
+        check_len(ty.params.len(), args.len())?;
+        let types = ty
+            .params
+            .iter()
+            .map(|(_, ty)| ty.clone())
+            .collect::<Vec<_>>();
+        if flatten_types(&types).len() > MAX_FLAT_PARAMS {
+            let tuple = Type::Tuple(types);
+            let ptr = self.realloc(align(&tuple), size(&tuple))?;
+            let Type::Tuple(types) = tuple else {
+                unreachable!()
+            };
+            self.store_fields(&types, args, ptr)?;
+            return Ok(vec![Value::I32(ptr as i32)]);
+        }
+        let mut values = Vec::new();
+        for (ty, arg) in types.iter().zip(args) {
+            self.lower_flat(ty, arg, &mut values)?;
+        }
+        Ok(values)
+    }
+
+    /// Lifts the results of a call to a lifted function.
+    pub(crate) fn lift_results(
+        &mut self,
+        ty: &FuncType,
+        values: &[Value],
+    ) -> Result<Vec<Val>, RuntimeError> {
+        if flatten_types(&ty.results).len() > MAX_FLAT_RESULTS {
+            let ptr = match values {
+                [Value::I32(ptr)] => *ptr as u32 as usize,
+                _ => return Err(RuntimeError::new("invalid flat value")),
+            };
+            let tuple = Type::Tuple(ty.results.clone());
+            if ptr % align(&tuple) != 0 {
+                return Err(RuntimeError::new("unaligned results pointer"));
+            }
+            return self.load_fields(&ty.results, ptr);
+        }
+        let mut src = values.iter().cloned();
+        ty.results
+            .iter()
+            .map(|ty| self.lift_flat(ty, &mut src))
+            .collect()
+    }
+
+    /// Lifts the arguments of a call to a lowered function.
+    pub(crate) fn lift_args(
+        &mut self,
+        ty: &FuncType,
+        values: &[Value],
+    ) -> Result<Vec<Val>, RuntimeError> {
+        let types = ty
+            .params
+            .iter()
+            .map(|(_, ty)| ty.clone())
+            .collect::<Vec<_>>();
+        if flatten_types(&types).len() > MAX_FLAT_PARAMS {
+            let ptr = match values.first() {
+                Some(Value::I32(ptr)) => *ptr as u32 as usize,
+                _ => return Err(RuntimeError::new("invalid flat value")),
+            };
+            if ptr % align(&Type::Tuple(types.clone())) != 0 {
+                return Err(RuntimeError::new("unaligned arguments pointer"));
+            }
+            return self.load_fields(&types, ptr);
+        }
+        let mut src = values.iter().cloned();
+        types
+            .iter()
+            .map(|ty| self.lift_flat(ty, &mut src))
+            .collect()
+    }
+
+    /// Lowers the results of a call to a lowered function.
+    ///
+    /// `args` are the core arguments of the call, the last of which is the
+    /// pointer to store the results at when they don't fit in flat values.
+    pub(crate) fn lower_results(
+        &mut self,
+        ty: &FuncType,
+        results: &[Val],
+        args: &[Value],
+    ) -> Result<Vec<Value>, RuntimeError> {
+        check_len(ty.results.len(), results.len())?;
+        if flatten_types(&ty.results).len() > MAX_FLAT_RESULTS {
+            let ptr = match args.last() {
+                Some(Value::I32(ptr)) => *ptr as u32 as usize,
+                _ => return Err(RuntimeError::new("invalid flat value")),
+            };
+            if ptr % align(&Type::Tuple(ty.results.clone())) != 0 {
+                return Err(RuntimeError::new("unaligned results pointer"));
+            }
+            self.store_fields(&ty.results, results, ptr)?;
+            return Ok(vec![]);
+        }
+        let mut values = Vec::new();
+        for (ty, result) in ty.results.iter().zip(results) {
+            self.lower_flat(ty, result, &mut values)?;
+        }
+        Ok(values)
+    }
+}
+
+fn split_ptr_len(bytes: [u8; 8]) -> (u32, u32) {
+    (
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+    )
+}
+
+fn join_ptr_len(ptr: u32, len: u32) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    bytes[..4].copy_from_slice(&ptr.to_le_bytes());
+    bytes[4..].copy_from_slice(&len.to_le_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variant_layout() {
+        let ty = Type::Variant(vec![
+            ("a".to_string(), Some(Type::U8)),
+            ("b".to_string(), Some(Type::Float32)),
+            ("c".to_string(), Some(Type::U64)),
+            ("d".to_string(), None),
+        ]);
+        assert_eq!(align(&ty), 8);
+        assert_eq!(size(&ty), 16);
+        let mut flat = Vec::new();
+        flatten(&ty, &mut flat);
+        assert_eq!(flat, [ValType::I32, ValType::I64]);
+
+        let ty = Type::Result {
+            ok: Some(Box::new(Type::Float32)),
+            err: Some(Box::new(Type::U32)),
+        };
+        let mut flat = Vec::new();
+        flatten(&ty, &mut flat);
+        assert_eq!(flat, [ValType::I32, ValType::I32]);
+    }
+
+    #[test]
+    fn record_and_flags_layout() {
+        let ty = Type::Record(vec![
+            ("a".to_string(), Type::U8),
+            ("b".to_string(), Type::U32),
+            ("c".to_string(), Type::U16),
+        ]);
+        assert_eq!((size(&ty), align(&ty)), (12, 4));
+
+        let flags = |n: usize| Type::Flags((0..n).map(|i| format!("f{i}")).collect());
+        assert_eq!((size(&flags(0)), align(&flags(0))), (0, 1));
+        assert_eq!((size(&flags(8)), align(&flags(8))), (1, 1));
+        assert_eq!((size(&flags(9)), align(&flags(9))), (2, 2));
+        assert_eq!((size(&flags(33)), align(&flags(33))), (8, 4));
+    }
+}
@@ -0,0 +1,625 @@
+//! Parsing of WebAssembly components.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use wasmparser::types::{
+    ComponentAnyTypeId, ComponentDefinedType, ComponentEntityType, ComponentValType, ResourceId,
+    Types,
+};
+use wasmparser::{
+    CanonicalFunction, CanonicalOption, ComponentAlias, ComponentExternalKind, ComponentInstance,
+    ComponentOuterAliasKind, ComponentType, ComponentTypeRef, Encoding, ExternalKind, Instance,
+    Parser, Payload, PrimitiveValType, Validator,
+};
+
+use super::canonical::StringEncoding;
+use super::types::{FuncType, ResourceType, Type};
+use crate::{AsEngineRef, CompileError, Module};
+
+/// A compiled WebAssembly component.
+///
+/// A component is made of core modules, and of the instructions to
+/// instantiate and link them together, which are run when the component is
+/// instantiated with a [`Linker`](super::Linker). Only the values of
+/// components can cross their boundary, following the canonical ABI.
+#[derive(Clone)]
+pub struct Component {
+    pub(crate) inner: Arc<ComponentInner>,
+}
+
+pub(crate) struct ComponentInner {
+    pub(crate) modules: Vec<Module>,
+    pub(crate) resources: Vec<ResourceDef>,
+    pub(crate) initializers: Vec<Initializer>,
+}
+
+/// How a resource type of a component is bound at instantiation.
+#[derive(Debug)]
+pub(crate) enum ResourceDef {
+    /// An imported resource, by the path of its name in the imports.
+    Imported(Vec<String>),
+    /// A resource defined by the component.
+    Defined,
+}
+
+/// The items of an imported component instance.
+#[derive(Debug)]
+pub(crate) enum ImportItem {
+    Func(FuncType),
+    Resource(u32),
+}
+
+/// An item exported by a component or by one of its instances.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ExportItem {
+    Func(u32),
+    Instance(u32),
+    Resource(u32),
+}
+
+/// The canonical options of a lifted or lowered function, by index.
+#[derive(Debug)]
+pub(crate) struct OptionsDef {
+    pub(crate) memory: Option<u32>,
+    pub(crate) realloc: Option<u32>,
+    pub(crate) post_return: Option<u32>,
+    pub(crate) encoding: StringEncoding,
+}
+
+/// A step of the instantiation of a component.
+///
+/// Each step defines new items in the index spaces of the component, in the
+/// order of the binary.
+#[derive(Debug)]
+pub(crate) enum Initializer {
+    CoreInstantiate {
+        module: u32,
+        args: Vec<(String, u32)>,
+    },
+    CoreInstanceFromExports(Vec<(String, ExternalKind, u32)>),
+    CoreAlias {
+        instance: u32,
+        name: String,
+    },
+    ImportFunc {
+        name: String,
+        ty: FuncType,
+    },
+    ImportInstance {
+        name: String,
+        items: Vec<(String, ImportItem)>,
+    },
+    DefineResource {
+        resource: u32,
+        dtor: Option<u32>,
+    },
+    AliasFunc {
+        instance: u32,
+        name: String,
+    },
+    AliasInstance {
+        instance: u32,
+        name: String,
+    },
+    Lift {
+        core_func: u32,
+        ty: FuncType,
+        options: OptionsDef,
+    },
+    Lower {
+        func: u32,
+        ty: FuncType,
+        options: OptionsDef,
+    },
+    ResourceNew(u32),
+    ResourceDrop(u32),
+    ResourceRep(u32),
+    InstanceFromExports(Vec<(String, ExportItem)>),
+    Export {
+        name: String,
+        item: ExportItem,
+    },
+}
+
+fn unsupported(what: &str) -> CompileError {
+    CompileError::UnsupportedFeature(format!("{what} in components"))
+}
+
+impl Component {
+    /// Compiles a component from its binary or, if the `wat` feature is
+    /// enabled, its text representation.
+    pub fn new(engine: &impl AsEngineRef, bytes: impl AsRef<[u8]>) -> Result<Self, CompileError> {
+        #[cfg(feature = "wat")]
+        let bytes = wat::parse_bytes(bytes.as_ref()).map_err(|e| {
+            CompileError::Wasm(crate::WasmError::Generic(format!(
+                "Error when converting wat: {}",
+                e
+            )))
+        })?;
+        Self::from_binary(engine, bytes.as_ref())
+    }
+
+    /// Compiles a component from its binary representation.
+    pub fn from_binary(engine: &impl AsEngineRef, binary: &[u8]) -> Result<Self, CompileError> {
+        let types = Validator::new()
+            .validate_all(binary)
+            .map_err(|e| CompileError::Validate(e.to_string()))?;
+        let inner = ComponentParser::new(&types).parse(engine, binary)?;
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+}
+
+impl std::fmt::Debug for Component {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Component")
+            .field("modules", &self.inner.modules.len())
+            .finish()
+    }
+}
+
+/// Translates a validated component into its instantiation steps.
+struct ComponentParser<'a> {
+    types: &'a Types,
+    resources: Vec<ResourceDef>,
+    resource_indices: HashMap<ResourceId, u32>,
+    initializers: Vec<Initializer>,
+    /// The size of the type index space, which is only needed statically.
+    type_count: u32,
+}
+
+impl<'a> ComponentParser<'a> {
+    fn new(types: &'a Types) -> Self {
+        Self {
+            types,
+            resources: Vec::new(),
+            resource_indices: HashMap::new(),
+            initializers: Vec::new(),
+            type_count: 0,
+        }
+    }
+
+    fn parse(
+        mut self,
+        engine: &impl AsEngineRef,
+        binary: &[u8],
+    ) -> Result<ComponentInner, CompileError> {
+        let mut modules = Vec::new();
+        // The payloads of nested modules are parsed as well, and skipped.
+        let mut depth = 0;
+        for payload in Parser::new(0).parse_all(binary) {
+            let payload = payload.map_err(|e| CompileError::Validate(e.to_string()))?;
+            match payload {
+                Payload::ModuleSection {
+                    unchecked_range, ..
+                } => {
+                    if depth == 0 {
+                        modules.push(Module::from_binary(engine, &binary[unchecked_range])?);
+                    }
+                    depth += 1;
+                }
+                Payload::End(_) => depth = std::cmp::max(depth, 1) - 1,
+                _ if depth > 0 => {}
+                Payload::Version { encoding, .. } => {
+                    if encoding != Encoding::Component {
+                        return Err(CompileError::Validate(
+                            "expected a component, found a core module".to_string(),
+                        ));
+                    }
+                }
+                Payload::ComponentSection { .. } => return Err(unsupported("nested components")),
+                Payload::ComponentStartSection { .. } => {
+                    return Err(unsupported("start functions"))
+                }
+                Payload::InstanceSection(reader) => {
+                    for instance in reader {
+                        self.core_instance(instance.map_err(validate_error)?);
+                    }
+                }
+                Payload::ComponentTypeSection(reader) => {
+                    for ty in reader {
+                        self.ty(ty.map_err(validate_error)?);
+                    }
+                }
+                Payload::ComponentImportSection(reader) => {
+                    for import in reader {
+                        let import = import.map_err(validate_error)?;
+                        self.import(import.name.0, import.ty)?;
+                    }
+                }
+                Payload::ComponentAliasSection(reader) => {
+                    for alias in reader {
+                        self.alias(alias.map_err(validate_error)?)?;
+                    }
+                }
+                Payload::ComponentCanonicalSection(reader) => {
+                    for func in reader {
+                        self.canonical(func.map_err(validate_error)?)?;
+                    }
+                }
+                Payload::ComponentInstanceSection(reader) => {
+                    for instance in reader {
+                        match instance.map_err(validate_error)? {
+                            ComponentInstance::Instantiate { .. } => {
+                                return Err(unsupported("nested components"))
+                            }
+                            ComponentInstance::FromExports(exports) => {
+                                let mut items = Vec::new();
+                                for export in exports.iter() {
+                                    if let Some(item) =
+                                        self.export_item(export.kind, export.index)?
+                                    {
+                                        items.push((export.name.0.to_string(), item));
+                                    }
+                                }
+                                self.initializers
+                                    .push(Initializer::InstanceFromExports(items));
+                            }
+                        }
+                    }
+                }
+                Payload::ComponentExportSection(reader) => {
+                    for export in reader {
+                        let export = export.map_err(validate_error)?;
+                        if let Some(item) = self.export_item(export.kind, export.index)? {
+                            self.initializers.push(Initializer::Export {
+                                name: export.name.0.to_string(),
+                                item,
+                            });
+                        }
+                        self.push_export_index(export.kind);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(ComponentInner {
+            modules,
+            resources: self.resources,
+            initializers: self.initializers,
+        })
+    }
+
+    fn core_instance(&mut self, instance: Instance<'_>) {
+        self.initializers.push(match instance {
+            Instance::Instantiate { module_index, args } => Initializer::CoreInstantiate {
+                module: module_index,
+                args: args
+                    .iter()
+                    .map(|arg| (arg.name.to_string(), arg.index))
+                    .collect(),
+            },
+            Instance::FromExports(exports) => Initializer::CoreInstanceFromExports(
+                exports
+                    .iter()
+                    .map(|export| (export.name.to_string(), export.kind, export.index))
+                    .collect(),
+            ),
+        });
+    }
+
+    fn ty(&mut self, ty: ComponentType<'_>) {
+        if let ComponentType::Resource { dtor, .. } = ty {
+            let id = self.types.component_any_type_at(self.type_count);
+            let resource = self.define_resource(&[id], ResourceDef::Defined);
+            self.initializers
+                .push(Initializer::DefineResource { resource, dtor });
+        }
+        self.type_count += 1;
+    }
+
+    /// Defines a new resource, known by the type ids `ids`.
+    fn define_resource(&mut self, ids: &[ComponentAnyTypeId], def: ResourceDef) -> u32 {
+        let index = self.resources.len() as u32;
+        self.resources.push(def);
+        for id in ids {
+            if let ComponentAnyTypeId::Resource(id) = id {
+                self.resource_indices.insert(id.resource(), index);
+            }
+        }
+        index
+    }
+
+    fn import(&mut self, name: &str, ty: ComponentTypeRef) -> Result<(), CompileError> {
+        let entity = self
+            .types
+            .component_entity_type_of_import(name)
+            .expect("validated import");
+        match (ty, entity) {
+            (ComponentTypeRef::Func(_), ComponentEntityType::Func(id)) => {
+                let ty = self.func_type(&self.types[id])?;
+                self.initializers.push(Initializer::ImportFunc {
+                    name: name.to_string(),
+                    ty,
+                });
+            }
+            (ComponentTypeRef::Instance(_), ComponentEntityType::Instance(id)) => {
+                let exports = &self.types[id].exports;
+                let mut items = Vec::new();
+                // Resources are bound first, as the functions refer to them.
+                for (export, ty) in exports {
+                    if let ComponentEntityType::Type {
+                        referenced,
+                        created: created @ ComponentAnyTypeId::Resource(_),
+                    } = ty
+                    {
+                        let path = vec![name.to_string(), export.clone()];
+                        let def = ResourceDef::Imported(path);
+                        let resource = self.define_resource(&[*referenced, *created], def);
+                        items.push((export.clone(), ImportItem::Resource(resource)));
+                    }
+                }
+                for (export, ty) in exports {
+                    match ty {
+                        ComponentEntityType::Func(id) => {
+                            let ty = self.func_type(&self.types[*id])?;
+                            items.push((export.clone(), ImportItem::Func(ty)));
+                        }
+                        ComponentEntityType::Type { .. } => {}
+                        _ => return Err(unsupported("imported instances of nested items")),
+                    }
+                }
+                self.initializers.push(Initializer::ImportInstance {
+                    name: name.to_string(),
+                    items,
+                });
+            }
+            (
+                ComponentTypeRef::Type(_),
+                ComponentEntityType::Type {
+                    referenced,
+                    created,
+                },
+            ) => {
+                if let ComponentAnyTypeId::Resource(_) = created {
+                    let def = ResourceDef::Imported(vec![name.to_string()]);
+                    self.define_resource(&[referenced, created], def);
+                }
+                self.type_count += 1;
+            }
+            (ty, _) => return Err(unsupported(&format!("{} imports", ty.kind().desc()))),
+        }
+        Ok(())
+    }
+
+    fn alias(&mut self, alias: ComponentAlias<'_>) -> Result<(), CompileError> {
+        match alias {
+            ComponentAlias::InstanceExport {
+                kind,
+                instance_index,
+                name,
+            } => match kind {
+                ComponentExternalKind::Func => {
+                    self.initializers.push(Initializer::AliasFunc {
+                        instance: instance_index,
+                        name: name.to_string(),
+                    });
+                }
+                ComponentExternalKind::Instance => {
+                    self.initializers.push(Initializer::AliasInstance {
+                        instance: instance_index,
+                        name: name.to_string(),
+                    })
+                }
+                ComponentExternalKind::Type => self.type_count += 1,
+                kind => return Err(unsupported(&format!("{} aliases", kind.desc()))),
+            },
+            ComponentAlias::CoreInstanceExport {
+                kind,
+                instance_index,
+                name,
+            } => {
+                if kind == ExternalKind::Tag {
+                    return Err(unsupported("tags"));
+                }
+                self.initializers.push(Initializer::CoreAlias {
+                    instance: instance_index,
+                    name: name.to_string(),
+                });
+            }
+            ComponentAlias::Outer { kind, .. } => match kind {
+                ComponentOuterAliasKind::Type => self.type_count += 1,
+                ComponentOuterAliasKind::CoreType => {}
+                _ => return Err(unsupported("outer aliases of modules and components")),
+            },
+        }
+        Ok(())
+    }
+
+    fn canonical(&mut self, func: CanonicalFunction) -> Result<(), CompileError> {
+        let initializer = match func {
+            CanonicalFunction::Lift {
+                core_func_index,
+                type_index,
+                options,
+            } => {
+                let ComponentAnyTypeId::Func(id) = self.types.component_any_type_at(type_index)
+                else {
+                    unreachable!("validated function type")
+                };
+                Initializer::Lift {
+                    core_func: core_func_index,
+                    ty: self.func_type(&self.types[id])?,
+                    options: options_def(&options),
+                }
+            }
+            CanonicalFunction::Lower {
+                func_index,
+                options,
+            } => {
+                let id = self.types.component_function_at(func_index);
+                Initializer::Lower {
+                    func: func_index,
+                    ty: self.func_type(&self.types[id])?,
+                    options: options_def(&options),
+                }
+            }
+            CanonicalFunction::ResourceNew { resource } => {
+                Initializer::ResourceNew(self.resource_at(resource)?)
+            }
+            CanonicalFunction::ResourceDrop { resource } => {
+                Initializer::ResourceDrop(self.resource_at(resource)?)
+            }
+            CanonicalFunction::ResourceRep { resource } => {
+                Initializer::ResourceRep(self.resource_at(resource)?)
+            }
+        };
+        self.initializers.push(initializer);
+        Ok(())
+    }
+
+    fn resource_at(&self, type_index: u32) -> Result<u32, CompileError> {
+        match self.types.component_any_type_at(type_index) {
+            ComponentAnyTypeId::Resource(id) => self.resource_index(id.resource()),
+            _ => unreachable!("validated resource type"),
+        }
+    }
+
+    fn resource_index(&self, id: ResourceId) -> Result<u32, CompileError> {
+        self.resource_indices
+            .get(&id)
+            .copied()
+            .ok_or_else(|| unsupported("resources of nested instances"))
+    }
+
+    fn export_item(
+        &self,
+        kind: ComponentExternalKind,
+        index: u32,
+    ) -> Result<Option<ExportItem>, CompileError> {
+        Ok(match kind {
+            ComponentExternalKind::Func => Some(ExportItem::Func(index)),
+            ComponentExternalKind::Instance => Some(ExportItem::Instance(index)),
+            ComponentExternalKind::Type => match self.types.component_any_type_at(index) {
+                ComponentAnyTypeId::Resource(id) => {
+                    Some(ExportItem::Resource(self.resource_index(id.resource())?))
+                }
+                _ => None,
+            },
+            kind => return Err(unsupported(&format!("{} exports", kind.desc()))),
+        })
+    }
+
+    fn push_export_index(&mut self, kind: ComponentExternalKind) {
+        if kind == ComponentExternalKind::Type {
+            self.type_count += 1;
+        }
+    }
+
+    fn func_type(
+        &self,
+        ty: &wasmparser::types::ComponentFuncType,
+    ) -> Result<FuncType, CompileError> {
+        Ok(FuncType {
+            params: ty
+                .params
+                .iter()
+                .map(|(name, ty)| Ok((name.to_string(), self.val_type(*ty)?)))
+                .collect::<Result<_, CompileError>>()?,
+            results: ty
+                .results
+                .iter()
+                .map(|(_, ty)| self.val_type(*ty))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn val_type(&self, ty: ComponentValType) -> Result<Type, CompileError> {
+        let id = match ty {
+            ComponentValType::Primitive(ty) => return Ok(primitive(ty)),
+            ComponentValType::Type(id) => id,
+        };
+        let boxed = |ty| self.val_type(ty).map(Box::new);
+        Ok(match &self.types[id] {
+            ComponentDefinedType::Primitive(ty) => primitive(*ty),
+            ComponentDefinedType::Record(record) => Type::Record(
+                record
+                    .fields
+                    .iter()
+                    .map(|(name, ty)| Ok((name.to_string(), self.val_type(*ty)?)))
+                    .collect::<Result<_, CompileError>>()?,
+            ),
+            ComponentDefinedType::Variant(variant) => Type::Variant(
+                variant
+                    .cases
+                    .iter()
+                    .map(|(name, case)| {
+                        Ok((
+                            name.to_string(),
+                            case.ty.map(|ty| self.val_type(ty)).transpose()?,
+                        ))
+                    })
+                    .collect::<Result<_, CompileError>>()?,
+            ),
+            ComponentDefinedType::List(ty) => Type::List(boxed(*ty)?),
+            ComponentDefinedType::Tuple(tuple) => Type::Tuple(
+                tuple
+                    .types
+                    .iter()
+                    .map(|ty| self.val_type(*ty))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ComponentDefinedType::Flags(names) => {
+                Type::Flags(names.iter().map(|name| name.to_string()).collect())
+            }
+            ComponentDefinedType::Enum(names) => {
+                Type::Enum(names.iter().map(|name| name.to_string()).collect())
+            }
+            ComponentDefinedType::Option(ty) => Type::Option(boxed(*ty)?),
+            ComponentDefinedType::Result { ok, err } => Type::Result {
+                ok: ok.map(boxed).transpose()?,
+                err: err.map(boxed).transpose()?,
+            },
+            ComponentDefinedType::Own(id) => {
+                Type::Own(ResourceType::component(self.resource_index(id.resource())?))
+            }
+            ComponentDefinedType::Borrow(id) => {
+                Type::Borrow(ResourceType::component(self.resource_index(id.resource())?))
+            }
+        })
+    }
+}
+
+fn primitive(ty: PrimitiveValType) -> Type {
+    match ty {
+        PrimitiveValType::Bool => Type::Bool,
+        PrimitiveValType::S8 => Type::S8,
+        PrimitiveValType::U8 => Type::U8,
+        PrimitiveValType::S16 => Type::S16,
+        PrimitiveValType::U16 => Type::U16,
+        PrimitiveValType::S32 => Type::S32,
+        PrimitiveValType::U32 => Type::U32,
+        PrimitiveValType::S64 => Type::S64,
+        PrimitiveValType::U64 => Type::U64,
+        PrimitiveValType::F32 => Type::Float32,
+        PrimitiveValType::F64 => Type::Float64,
+        PrimitiveValType::Char => Type::Char,
+        PrimitiveValType::String => Type::String,
+    }
+}
+
+fn options_def(options: &[CanonicalOption]) -> OptionsDef {
+    let mut def = OptionsDef {
+        memory: None,
+        realloc: None,
+        post_return: None,
+        encoding: StringEncoding::Utf8,
+    };
+    for option in options {
+        match *option {
+            CanonicalOption::UTF8 => def.encoding = StringEncoding::Utf8,
+            CanonicalOption::UTF16 => def.encoding = StringEncoding::Utf16,
+            CanonicalOption::CompactUTF16 => def.encoding = StringEncoding::CompactUtf16,
+            CanonicalOption::Memory(index) => def.memory = Some(index),
+            CanonicalOption::Realloc(index) => def.realloc = Some(index),
+            CanonicalOption::PostReturn(index) => def.post_return = Some(index),
+        }
+    }
+    def
+}
+
+fn validate_error(e: wasmparser::BinaryReaderError) -> CompileError {
+    CompileError::Validate(e.to_string())
+}
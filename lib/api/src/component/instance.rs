@@ -0,0 +1,496 @@
+//! Instances of components and the functions they export.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::canonical::{lifted_func_type, lowered_func_type, Cx, InstanceState, Options};
+use super::component::{Component, ExportItem, ImportItem, Initializer, OptionsDef, ResourceDef};
+use super::linker::{Definition, HostDef, InstantiationError};
+use super::typed::{ComponentList, TypedFunc};
+use super::types::{FuncType, ResourceType};
+use super::values::{Dtor, Val};
+use crate::store::{AsStoreMut, StoreMut};
+use crate::{
+    ExportError, Extern, Function, FunctionEnv, FunctionEnvMut, FunctionType, Global, Imports,
+    Memory, RuntimeError, Table, Type as ValType, Value,
+};
+
+/// The signature of host functions, once bound to their environment.
+pub(crate) type HostFn =
+    dyn Fn(&mut StoreMut<'_>, &[Val]) -> Result<Vec<Val>, RuntimeError> + Send + Sync;
+
+#[derive(Clone)]
+enum FuncKind {
+    Host(Arc<HostFn>),
+    Lifted {
+        core: Function,
+        options: Options,
+        instance: Arc<InstanceState>,
+    },
+}
+
+/// A function exported by a component instance.
+#[derive(Clone)]
+pub struct Func {
+    ty: FuncType,
+    kind: FuncKind,
+}
+
+impl Func {
+    /// The type of the function.
+    pub fn ty(&self) -> &FuncType {
+        &self.ty
+    }
+
+    /// Calls the function with dynamically typed values.
+    pub fn call(
+        &self,
+        store: &mut impl AsStoreMut,
+        params: &[Val],
+    ) -> Result<Box<[Val]>, RuntimeError> {
+        let mut store = store.as_store_mut();
+        self.call_inner(&mut store, params)
+            .map(Vec::into_boxed_slice)
+    }
+
+    fn call_inner(
+        &self,
+        store: &mut StoreMut<'_>,
+        params: &[Val],
+    ) -> Result<Vec<Val>, RuntimeError> {
+        match &self.kind {
+            FuncKind::Host(func) => func(store, params),
+            FuncKind::Lifted {
+                core,
+                options,
+                instance,
+            } => {
+                let mut cx = Cx::new(store, options, instance);
+                let results = cx.lower_args(&self.ty, params).and_then(|args| {
+                    let values = core.call(cx.store, &args)?;
+                    Ok((cx.lift_results(&self.ty, &values)?, values))
+                });
+                cx.release_borrows();
+                let (results, values) = results?;
+                if let Some(post_return) = &options.post_return {
+                    post_return.call(store, &values)?;
+                }
+                Ok(results)
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Func {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Func").field("ty", &self.ty).finish()
+    }
+}
+
+/// An item exported by a component instance.
+#[derive(Debug, Clone)]
+enum Item {
+    Func(Func),
+    Instance(Exports),
+    Resource(ResourceType),
+}
+
+/// The exports of a component instance, or of one of its exported
+/// instances.
+#[derive(Debug, Clone, Default)]
+pub struct Exports {
+    items: HashMap<String, Item>,
+}
+
+impl Exports {
+    /// Returns the exported function `name`.
+    pub fn get_func(&self, name: &str) -> Result<&Func, ExportError> {
+        match self.items.get(name) {
+            Some(Item::Func(func)) => Ok(func),
+            Some(_) => Err(ExportError::IncompatibleType),
+            None => Err(ExportError::Missing(name.to_string())),
+        }
+    }
+
+    /// Returns the exported function `name`, checking that it has the
+    /// parameters `Params` and the results `Results`.
+    pub fn get_typed_func<Params, Results>(
+        &self,
+        name: &str,
+    ) -> Result<TypedFunc<Params, Results>, ExportError>
+    where
+        Params: ComponentList,
+        Results: ComponentList,
+    {
+        TypedFunc::new(self.get_func(name)?.clone()).map_err(|_| ExportError::IncompatibleType)
+    }
+
+    /// Returns the exported instance `name`.
+    pub fn get_instance(&self, name: &str) -> Result<&Self, ExportError> {
+        match self.items.get(name) {
+            Some(Item::Instance(exports)) => Ok(exports),
+            Some(_) => Err(ExportError::IncompatibleType),
+            None => Err(ExportError::Missing(name.to_string())),
+        }
+    }
+
+    /// Returns the exported resource type `name`.
+    pub fn get_resource(&self, name: &str) -> Result<ResourceType, ExportError> {
+        match self.items.get(name) {
+            Some(Item::Resource(ty)) => Ok(*ty),
+            Some(_) => Err(ExportError::IncompatibleType),
+            None => Err(ExportError::Missing(name.to_string())),
+        }
+    }
+
+    /// The names of the exports.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.items.keys().map(String::as_str)
+    }
+}
+
+/// An instance of a [`Component`].
+#[derive(Debug, Clone)]
+pub struct Instance {
+    /// The exports of the instance.
+    pub exports: Exports,
+}
+
+/// The index spaces of a component being instantiated.
+struct Instantiation<'a> {
+    component: &'a Component,
+    state: Arc<InstanceState>,
+    env: FunctionEnv<()>,
+    resources: Vec<ResourceType>,
+    core_instances: Vec<crate::Exports>,
+    core_funcs: Vec<Function>,
+    core_tables: Vec<Table>,
+    core_memories: Vec<Memory>,
+    core_globals: Vec<Global>,
+    funcs: Vec<Func>,
+    instances: Vec<Exports>,
+    exports: Exports,
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn instantiate(
+    store: &mut StoreMut<'_>,
+    component: &Component,
+    definitions: &HashMap<String, Definition>,
+) -> Result<Instance, InstantiationError> {
+    let state = Arc::new(InstanceState::new());
+    let mut resources = Vec::with_capacity(component.inner.resources.len());
+    for (index, def) in component.inner.resources.iter().enumerate() {
+        resources.push(match def {
+            ResourceDef::Defined => ResourceType::guest(state.id, index as u32),
+            ResourceDef::Imported(path) => match lookup(definitions, path)? {
+                Definition::Resource(ty, dtor) => {
+                    state.dtors.lock().unwrap().insert(*ty, dtor.clone());
+                    *ty
+                }
+                _ => {
+                    return Err(InstantiationError::IncompatibleImport(
+                        path.join("/"),
+                        "expected a resource".to_string(),
+                    ))
+                }
+            },
+        });
+    }
+
+    let mut instantiation = Instantiation {
+        component,
+        env: FunctionEnv::new(store, ()),
+        state,
+        resources,
+        core_instances: Vec::new(),
+        core_funcs: Vec::new(),
+        core_tables: Vec::new(),
+        core_memories: Vec::new(),
+        core_globals: Vec::new(),
+        funcs: Vec::new(),
+        instances: Vec::new(),
+        exports: Exports::default(),
+    };
+    for initializer in &component.inner.initializers {
+        instantiation.run(store, initializer, definitions)?;
+    }
+    Ok(Instance {
+        exports: instantiation.exports,
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn lookup<'a>(
+    definitions: &'a HashMap<String, Definition>,
+    path: &[String],
+) -> Result<&'a Definition, InstantiationError> {
+    let missing = || InstantiationError::MissingImport(path.join("/"));
+    let (name, rest) = path.split_first().ok_or_else(missing)?;
+    match (definitions.get(name), rest) {
+        (Some(def), []) => Ok(def),
+        (Some(Definition::Instance(items)), rest) => lookup(items, rest).map_err(|err| match err {
+            InstantiationError::MissingImport(_) => missing(),
+            err => err,
+        }),
+        (Some(_), _) => Err(InstantiationError::IncompatibleImport(
+            name.clone(),
+            "expected an instance".to_string(),
+        )),
+        (None, _) => Err(missing()),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn host_func(path: &str, def: &Definition, ty: &FuncType) -> Result<Func, InstantiationError> {
+    let Definition::Func(HostDef { func, typecheck }) = def else {
+        return Err(InstantiationError::IncompatibleImport(
+            path.to_string(),
+            "expected a function".to_string(),
+        ));
+    };
+    if let Some(typecheck) = typecheck {
+        typecheck(ty)
+            .map_err(|err| InstantiationError::IncompatibleImport(path.to_string(), err))?;
+    }
+    Ok(Func {
+        ty: ty.clone(),
+        kind: FuncKind::Host(func.clone()),
+    })
+}
+
+impl Instantiation<'_> {
+    #[allow(clippy::result_large_err)]
+    fn run(
+        &mut self,
+        store: &mut StoreMut<'_>,
+        initializer: &Initializer,
+        definitions: &HashMap<String, Definition>,
+    ) -> Result<(), InstantiationError> {
+        match initializer {
+            Initializer::CoreInstantiate { module, args } => {
+                let module = &self.component.inner.modules[*module as usize];
+                let mut imports = Imports::new();
+                for (name, instance) in args {
+                    for (field, item) in self.core_instances[*instance as usize].iter() {
+                        imports.define(name, field, item.clone());
+                    }
+                }
+                let instance = crate::Instance::new(store, module, &imports)?;
+                self.core_instances.push(instance.exports);
+            }
+            Initializer::CoreInstanceFromExports(exports) => {
+                let mut instance = crate::Exports::new();
+                for (name, kind, index) in exports {
+                    instance.insert(name.clone(), self.core_item(*kind, *index));
+                }
+                self.core_instances.push(instance);
+            }
+            Initializer::CoreAlias { instance, name } => {
+                let item = self.core_instances[*instance as usize]
+                    .get_extern(name)
+                    .expect("validated alias")
+                    .clone();
+                match item {
+                    Extern::Function(func) => self.core_funcs.push(func),
+                    Extern::Table(table) => self.core_tables.push(table),
+                    Extern::Memory(memory) => self.core_memories.push(memory),
+                    Extern::Global(global) => self.core_globals.push(global),
+                    Extern::Tag(_) => unreachable!("tags are rejected at compile time"),
+                }
+            }
+            Initializer::ImportFunc { name, ty } => {
+                let ty = ty.resolve(&self.resources);
+                let def = lookup(definitions, std::slice::from_ref(name))?;
+                self.funcs.push(host_func(name, def, &ty)?);
+            }
+            Initializer::ImportInstance { name, items } => {
+                let Definition::Instance(defs) = lookup(definitions, std::slice::from_ref(name))?
+                else {
+                    return Err(InstantiationError::IncompatibleImport(
+                        name.clone(),
+                        "expected an instance".to_string(),
+                    ));
+                };
+                let mut exports = Exports::default();
+                for (item_name, item) in items {
+                    let path = format!("{name}/{item_name}");
+                    let item = match item {
+                        ImportItem::Func(ty) => {
+                            let def = defs
+                                .get(item_name)
+                                .ok_or_else(|| InstantiationError::MissingImport(path.clone()))?;
+                            Item::Func(host_func(&path, def, &ty.resolve(&self.resources))?)
+                        }
+                        ImportItem::Resource(resource) => {
+                            Item::Resource(self.resources[*resource as usize])
+                        }
+                    };
+                    exports.items.insert(item_name.clone(), item);
+                }
+                self.instances.push(exports);
+            }
+            Initializer::DefineResource { resource, dtor } => {
+                if let Some(dtor) = dtor {
+                    let ty = self.resources[*resource as usize];
+                    let dtor = Dtor::Guest(self.core_funcs[*dtor as usize].clone());
+                    self.state.dtors.lock().unwrap().insert(ty, dtor);
+                }
+            }
+            Initializer::AliasFunc { instance, name } => {
+                let func = self.instances[*instance as usize]
+                    .get_func(name)
+                    .expect("validated alias")
+                    .clone();
+                self.funcs.push(func);
+            }
+            Initializer::AliasInstance { instance, name } => {
+                let instance = self.instances[*instance as usize]
+                    .get_instance(name)
+                    .expect("validated alias")
+                    .clone();
+                self.instances.push(instance);
+            }
+            Initializer::Lift {
+                core_func,
+                ty,
+                options,
+            } => {
+                let ty = ty.resolve(&self.resources);
+                let core = self.core_funcs[*core_func as usize].clone();
+                let expected = lifted_func_type(&ty);
+                if core.ty(store) != expected {
+                    return Err(RuntimeError::new(format!(
+                        "cannot lift a core function of type {} as {ty}",
+                        core.ty(store)
+                    ))
+                    .into());
+                }
+                self.funcs.push(Func {
+                    ty,
+                    kind: FuncKind::Lifted {
+                        core,
+                        options: self.options(options),
+                        instance: self.state.clone(),
+                    },
+                });
+            }
+            Initializer::Lower { func, ty, options } => {
+                let ty = ty.resolve(&self.resources);
+                let func = self.funcs[*func as usize].clone();
+                let options = self.options(options);
+                let state = self.state.clone();
+                let core_ty = lowered_func_type(&ty);
+                let lowered = Function::new_with_env(
+                    store,
+                    &self.env,
+                    core_ty,
+                    move |mut env: FunctionEnvMut<()>, args: &[Value]| {
+                        let mut store = env.as_store_mut();
+                        let params = Cx::new(&mut store, &options, &state).lift_args(&ty, args)?;
+                        let results = func.call_inner(&mut store, &params)?;
+                        Cx::new(&mut store, &options, &state).lower_results(&ty, &results, args)
+                    },
+                );
+                self.core_funcs.push(lowered);
+            }
+            Initializer::ResourceNew(resource) => {
+                let ty = self.resources[*resource as usize];
+                let state = self.state.clone();
+                self.push_resource_func(store, [ValType::I32], move |_, handle| {
+                    Ok(vec![Value::I32(state.resource_new(ty, handle) as i32)])
+                });
+            }
+            Initializer::ResourceRep(resource) => {
+                let ty = self.resources[*resource as usize];
+                let state = self.state.clone();
+                self.push_resource_func(store, [ValType::I32], move |_, handle| {
+                    Ok(vec![Value::I32(state.resource_rep(ty, handle)? as i32)])
+                });
+            }
+            Initializer::ResourceDrop(resource) => {
+                let ty = self.resources[*resource as usize];
+                let state = self.state.clone();
+                self.push_resource_func(store, [], move |store, handle| {
+                    state.resource_drop(store, ty, handle)?;
+                    Ok(vec![])
+                });
+            }
+            Initializer::InstanceFromExports(items) => {
+                let mut exports = Exports::default();
+                for (name, item) in items {
+                    exports.items.insert(name.clone(), self.item(*item));
+                }
+                self.instances.push(exports);
+            }
+            Initializer::Export { name, item } => {
+                let item = self.item(*item);
+                match &item {
+                    Item::Func(func) => self.funcs.push(func.clone()),
+                    Item::Instance(exports) => self.instances.push(exports.clone()),
+                    Item::Resource(_) => {}
+                }
+                self.exports.items.insert(name.clone(), item);
+            }
+        }
+        Ok(())
+    }
+
+    /// Defines a core function taking a handle, for the canonical resource
+    /// built-ins.
+    fn push_resource_func<const N: usize>(
+        &mut self,
+        store: &mut StoreMut<'_>,
+        results: [ValType; N],
+        func: impl Fn(&mut StoreMut<'_>, u32) -> Result<Vec<Value>, RuntimeError>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        let ty = FunctionType::new([ValType::I32], results);
+        let func = Function::new_with_env(
+            store,
+            &self.env,
+            ty,
+            move |mut env: FunctionEnvMut<()>, args: &[Value]| match args {
+                [Value::I32(handle)] => func(&mut env.as_store_mut(), *handle as u32),
+                _ => Err(RuntimeError::new("invalid handle")),
+            },
+        );
+        self.core_funcs.push(func);
+    }
+
+    fn core_item(&self, kind: wasmparser::ExternalKind, index: u32) -> Extern {
+        let index = index as usize;
+        match kind {
+            wasmparser::ExternalKind::Func => Extern::Function(self.core_funcs[index].clone()),
+            wasmparser::ExternalKind::Table => Extern::Table(self.core_tables[index].clone()),
+            wasmparser::ExternalKind::Memory => Extern::Memory(self.core_memories[index].clone()),
+            wasmparser::ExternalKind::Global => Extern::Global(self.core_globals[index].clone()),
+            wasmparser::ExternalKind::Tag => unreachable!("tags are rejected at compile time"),
+        }
+    }
+
+    fn item(&self, item: ExportItem) -> Item {
+        match item {
+            ExportItem::Func(index) => Item::Func(self.funcs[index as usize].clone()),
+            ExportItem::Instance(index) => Item::Instance(self.instances[index as usize].clone()),
+            ExportItem::Resource(index) => Item::Resource(self.resources[index as usize]),
+        }
+    }
+
+    fn options(&self, def: &OptionsDef) -> Options {
+        Options {
+            memory: def
+                .memory
+                .map(|index| self.core_memories[index as usize].clone()),
+            realloc: def
+                .realloc
+                .map(|index| self.core_funcs[index as usize].clone()),
+            post_return: def
+                .post_return
+                .map(|index| self.core_funcs[index as usize].clone()),
+            encoding: def.encoding,
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Definition of the imports of components.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use super::component::Component;
+use super::instance::{instantiate, HostFn, Instance};
+use super::typed::ComponentList;
+use super::types::{FuncType, ResourceType};
+use super::values::{Dtor, Val};
+use crate::store::{AsStoreMut, StoreHandle, StoreMut};
+use crate::vm::VMFunctionEnvironment;
+use crate::{FunctionEnv, FunctionEnvMut, RuntimeError};
+
+/// An error while instantiating a component.
+#[derive(Debug, Clone, Error)]
+pub enum InstantiationError {
+    /// An import of the component is not defined in the linker.
+    #[error("import `{0}` is not defined")]
+    MissingImport(String),
+    /// The definition of an import doesn't match its type in the component.
+    #[error("import `{0}` has the wrong type: {1}")]
+    IncompatibleImport(String, String),
+    /// A core module of the component failed to instantiate.
+    #[error(transparent)]
+    Core(#[from] crate::InstantiationError),
+    /// A trap occurred while instantiating the component.
+    #[error(transparent)]
+    Trap(#[from] RuntimeError),
+}
+
+type TypeCheck = dyn Fn(&FuncType) -> Result<(), String> + Send + Sync;
+
+/// A host function defined in a linker.
+#[derive(Clone)]
+pub(crate) struct HostDef {
+    pub(crate) func: Arc<HostFn>,
+    /// Checks the type of statically typed functions.
+    pub(crate) typecheck: Option<Arc<TypeCheck>>,
+}
+
+/// An item defined in a linker.
+#[derive(Clone)]
+pub(crate) enum Definition {
+    Func(HostDef),
+    Instance(HashMap<String, Definition>),
+    Resource(ResourceType, Dtor),
+}
+
+/// The host definitions of the imports of components.
+///
+/// Imports are defined by name, either at the root of the linker or in
+/// named instances, which is how the interfaces of a WIT world are
+/// imported. Host functions get access to the data of the
+/// [`FunctionEnv`] the linker was created with.
+///
+/// ```
+/// # use wasmer::{Store, FunctionEnv, FunctionEnvMut, RuntimeError};
+/// # use wasmer::component::{Component, Linker};
+/// # fn main() -> anyhow::Result<()> {
+/// let mut store = Store::default();
+/// let env = FunctionEnv::new(&mut store, 0u32);
+/// let mut linker = Linker::new(&env);
+/// linker.instance("host").func_wrap(
+///     "add",
+///     |mut env: FunctionEnvMut<u32>, (n,): (u32,)| -> Result<(u32,), RuntimeError> {
+///         *env.data_mut() += n;
+///         Ok((*env.data(),))
+///     },
+/// );
+///
+/// let component = Component::new(&store, r#"(component
+///     (import "host" (instance $host
+///         (export "add" (func (param "n" u32) (result u32)))))
+/// )"#)?;
+/// let instance = linker.instantiate(&mut store, &component)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Linker<T> {
+    env: FunctionEnv<T>,
+    root: HashMap<String, Definition>,
+}
+
+/// The definitions of a named instance of a [`Linker`].
+pub struct LinkerInstance<'a, T> {
+    env: &'a FunctionEnv<T>,
+    items: &'a mut HashMap<String, Definition>,
+}
+
+impl<T: Send + 'static> Linker<T> {
+    /// Creates an empty linker whose host functions use `env`.
+    pub fn new(env: &FunctionEnv<T>) -> Self {
+        Self {
+            env: env.clone(),
+            root: HashMap::new(),
+        }
+    }
+
+    /// The definitions at the root of the linker, for the imports that
+    /// are not in an instance.
+    pub fn root(&mut self) -> LinkerInstance<'_, T> {
+        LinkerInstance {
+            env: &self.env,
+            items: &mut self.root,
+        }
+    }
+
+    /// The definitions of the instance `name`, which is created if it
+    /// doesn't exist yet.
+    pub fn instance(&mut self, name: &str) -> LinkerInstance<'_, T> {
+        LinkerInstance {
+            env: &self.env,
+            items: instance_items(&mut self.root, name),
+        }
+    }
+
+    /// Instantiates a component, resolving its imports with the
+    /// definitions of this linker.
+    #[allow(clippy::result_large_err)]
+    pub fn instantiate(
+        &self,
+        store: &mut impl AsStoreMut,
+        component: &Component,
+    ) -> Result<Instance, InstantiationError> {
+        instantiate(&mut store.as_store_mut(), component, &self.root)
+    }
+}
+
+fn instance_items<'a>(
+    items: &'a mut HashMap<String, Definition>,
+    name: &str,
+) -> &'a mut HashMap<String, Definition> {
+    let item = items
+        .entry(name.to_string())
+        .and_modify(|item| {
+            if !matches!(item, Definition::Instance(_)) {
+                *item = Definition::Instance(HashMap::new());
+            }
+        })
+        .or_insert_with(|| Definition::Instance(HashMap::new()));
+    match item {
+        Definition::Instance(items) => items,
+        _ => unreachable!(),
+    }
+}
+
+/// The environment of the host functions of a linker.
+///
+/// Unlike a [`FunctionEnv`], it is `Sync` whatever `T` is, as it only holds
+/// the handle of the environment and the data is owned by the store.
+struct EnvHandle<T> {
+    handle: StoreHandle<VMFunctionEnvironment>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Send + 'static> EnvHandle<T> {
+    fn new(env: &FunctionEnv<T>) -> Self {
+        Self {
+            handle: env.handle.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn env_mut<'a>(&self, store: &'a mut StoreMut<'_>) -> FunctionEnvMut<'a, T> {
+        FunctionEnv::from_handle(self.handle.clone()).into_mut(store)
+    }
+}
+
+impl<T: Send + 'static> LinkerInstance<'_, T> {
+    /// Defines a dynamically typed host function.
+    ///
+    /// The function takes the values of the parameters of its type in the
+    /// component importing it, and returns the values of its results.
+    pub fn func_new<F>(&mut self, name: &str, func: F) -> &mut Self
+    where
+        F: Fn(FunctionEnvMut<T>, &[Val]) -> Result<Vec<Val>, RuntimeError> + Send + Sync + 'static,
+    {
+        let env = EnvHandle::new(self.env);
+        let func: Arc<HostFn> = Arc::new(move |store, params| func(env.env_mut(store), params));
+        self.items.insert(
+            name.to_string(),
+            Definition::Func(HostDef {
+                func,
+                typecheck: None,
+            }),
+        );
+        self
+    }
+
+    /// Defines a statically typed host function.
+    ///
+    /// The parameters and results are tuples of [`ComponentType`]s, which
+    /// are checked against the type of the import when the component is
+    /// instantiated.
+    ///
+    /// [`ComponentType`]: super::ComponentType
+    pub fn func_wrap<P, R, F>(&mut self, name: &str, func: F) -> &mut Self
+    where
+        P: ComponentList,
+        R: ComponentList,
+        F: Fn(FunctionEnvMut<T>, P) -> Result<R, RuntimeError> + Send + Sync + 'static,
+    {
+        let env = EnvHandle::new(self.env);
+        let func: Arc<HostFn> = Arc::new(move |store, params| {
+            let params = P::from_vals(params.to_vec())?;
+            Ok(func(env.env_mut(store), params)?.into_vals())
+        });
+        let typecheck: Arc<TypeCheck> = Arc::new(|ty: &FuncType| {
+            let params = ty
+                .params()
+                .iter()
+                .map(|(_, ty)| ty.clone())
+                .collect::<Vec<_>>();
+            P::typecheck(&params).map_err(|err| format!("parameters: {err}"))?;
+            R::typecheck(ty.results()).map_err(|err| format!("results: {err}"))
+        });
+        self.items.insert(
+            name.to_string(),
+            Definition::Func(HostDef {
+                func,
+                typecheck: Some(typecheck),
+            }),
+        );
+        self
+    }
+
+    /// Defines a host resource type.
+    ///
+    /// `dtor` is called with the `rep` of a resource when a component drops
+    /// an owned handle to it.
+    pub fn resource<F>(&mut self, name: &str, ty: ResourceType, dtor: F) -> &mut Self
+    where
+        F: Fn(FunctionEnvMut<T>, u32) -> Result<(), RuntimeError> + Send + Sync + 'static,
+    {
+        let env = EnvHandle::new(self.env);
+        let dtor = Dtor::Host(Arc::new(move |store, rep| dtor(env.env_mut(store), rep)));
+        self.items
+            .insert(name.to_string(), Definition::Resource(ty, dtor));
+        self
+    }
+
+    /// The definitions of the nested instance `name`, which is created if
+    /// it doesn't exist yet.
+    pub fn instance(&mut self, name: &str) -> LinkerInstance<'_, T> {
+        LinkerInstance {
+            env: self.env,
+            items: instance_items(self.items, name),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Linker<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names = self.root.keys().collect::<Vec<_>>();
+        names.sort();
+        f.debug_struct("Linker").field("items", &names).finish()
+    }
+}
@@ -0,0 +1,60 @@
+//! Support for the [WebAssembly component model].
+//!
+//! A [`Component`] is compiled from a component binary, such as the ones
+//! produced by `cargo component` or `wit-bindgen`, and instantiated with a
+//! [`Linker`] that defines its imports. The exports of the resulting
+//! [`Instance`] are called with component values: they are lifted from and
+//! lowered to the memory of the component following the canonical ABI.
+//!
+//! ```
+//! # use wasmer::Store;
+//! # use wasmer::component::{Component, Linker, Val};
+//! # fn main() -> anyhow::Result<()> {
+//! let mut store = Store::default();
+//! let component = Component::new(&store, r#"(component
+//!     (core module $m
+//!         (func (export "add") (param i32 i32) (result i32)
+//!             (i32.add (local.get 0) (local.get 1))))
+//!     (core instance $i (instantiate $m))
+//!     (func (export "add") (param "a" u32) (param "b" u32) (result u32)
+//!         (canon lift (core func $i "add")))
+//! )"#)?;
+//!
+//! let env = wasmer::FunctionEnv::new(&mut store, ());
+//! let instance = Linker::new(&env).instantiate(&mut store, &component)?;
+//!
+//! let add = instance.exports.get_typed_func::<(u32, u32), (u32,)>("add")?;
+//! assert_eq!(add.call(&mut store, (1, 2))?, (3,));
+//!
+//! let add = instance.exports.get_func("add")?;
+//! let results = add.call(&mut store, &[Val::U32(40), Val::U32(2)])?;
+//! assert_eq!(&*results, &[Val::U32(42)]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Nested components, component start functions and component values are
+//! not supported yet.
+//!
+//! [WebAssembly component model]: https://github.com/WebAssembly/component-model
+
+mod canonical;
+#[allow(clippy::module_inception)]
+mod component;
+mod instance;
+mod linker;
+mod typed;
+mod types;
+mod values;
+
+pub use self::component::Component;
+pub use self::instance::{Exports, Func, Instance};
+pub use self::linker::{InstantiationError, Linker, LinkerInstance};
+pub use self::typed::{ComponentList, ComponentType, TypedFunc};
+pub use self::types::{FuncType, ResourceType, Type};
+pub use self::values::{Resource, ResourceAny, ResourceTable, ResourceTableError, Val};
+
+#[doc(hidden)]
+pub mod __internal {
+    pub use super::typed::{mismatch, type_mismatch};
+}
@@ -0,0 +1,428 @@
+//! Statically typed component values and functions.
+
+use std::marker::PhantomData;
+
+use super::instance::Func;
+use super::types::{ResourceType, Type};
+use super::values::{Resource, ResourceAny, Val};
+use crate::store::AsStoreMut;
+use crate::RuntimeError;
+
+/// A Rust type that can be converted to and from a component value.
+///
+/// The trait is implemented for the primitive types, [`String`], [`Vec`],
+/// [`Option`], [`Result`], tuples and resource handles. It can be
+/// implemented for records, variants, enums and flags, which is what the
+/// types generated by `wai_bindgen_wasmer::bindgen!` do.
+pub trait ComponentType: Sized {
+    /// Checks that values of this type can be converted to and from
+    /// values of the component type `ty`.
+    fn typecheck(ty: &Type) -> Result<(), String>;
+
+    /// Converts this value to a component value.
+    fn into_val(self) -> Val;
+
+    /// Converts a component value to this type.
+    fn from_val(val: Val) -> Result<Self, RuntimeError>;
+
+    /// Checks the type of the payload of a variant case.
+    #[doc(hidden)]
+    fn typecheck_payload(ty: Option<&Type>) -> Result<(), String> {
+        match ty {
+            Some(ty) => Self::typecheck(ty),
+            None => Err("expected a case with a payload".to_string()),
+        }
+    }
+
+    /// Converts this value to the payload of a variant case.
+    #[doc(hidden)]
+    fn into_payload(self) -> Option<Box<Val>> {
+        Some(Box::new(self.into_val()))
+    }
+
+    /// Converts the payload of a variant case to this type.
+    #[doc(hidden)]
+    fn from_payload(val: Option<Box<Val>>) -> Result<Self, RuntimeError> {
+        match val {
+            Some(val) => Self::from_val(*val),
+            None => Err(RuntimeError::new("missing variant payload")),
+        }
+    }
+}
+
+/// Returns the error of a failed conversion of `val` to a value of type
+/// `expected`.
+#[doc(hidden)]
+pub fn mismatch(expected: &str, val: &Val) -> RuntimeError {
+    RuntimeError::new(format!(
+        "type mismatch: expected a value of type `{expected}`, found `{}`",
+        val.desc()
+    ))
+}
+
+/// Returns the error of a failed type check.
+#[doc(hidden)]
+pub fn type_mismatch(expected: &str, ty: &Type) -> String {
+    format!("expected `{expected}`, found `{ty}`")
+}
+
+macro_rules! primitives {
+    ($($rust:ty => $variant:ident,)*) => {$(
+        impl ComponentType for $rust {
+            fn typecheck(ty: &Type) -> Result<(), String> {
+                match ty {
+                    Type::$variant => Ok(()),
+                    ty => Err(type_mismatch(Type::$variant.desc(), ty)),
+                }
+            }
+
+            fn into_val(self) -> Val {
+                Val::$variant(self)
+            }
+
+            fn from_val(val: Val) -> Result<Self, RuntimeError> {
+                match val {
+                    Val::$variant(value) => Ok(value),
+                    val => Err(mismatch(Type::$variant.desc(), &val)),
+                }
+            }
+        }
+    )*};
+}
+
+primitives! {
+    bool => Bool,
+    i8 => S8,
+    u8 => U8,
+    i16 => S16,
+    u16 => U16,
+    i32 => S32,
+    u32 => U32,
+    i64 => S64,
+    u64 => U64,
+    f32 => Float32,
+    f64 => Float64,
+    char => Char,
+    String => String,
+}
+
+impl ComponentType for Val {
+    fn typecheck(_ty: &Type) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn into_val(self) -> Val {
+        self
+    }
+
+    fn from_val(val: Val) -> Result<Self, RuntimeError> {
+        Ok(val)
+    }
+}
+
+/// The unit type is an empty tuple, or the absence of a payload in the
+/// cases of a `result`.
+impl ComponentType for () {
+    fn typecheck(ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::Tuple(types) if types.is_empty() => Ok(()),
+            ty => Err(type_mismatch("tuple<>", ty)),
+        }
+    }
+
+    fn into_val(self) -> Val {
+        Val::Tuple(Vec::new())
+    }
+
+    fn from_val(val: Val) -> Result<Self, RuntimeError> {
+        match val {
+            Val::Tuple(vals) if vals.is_empty() => Ok(()),
+            val => Err(mismatch("tuple<>", &val)),
+        }
+    }
+
+    fn typecheck_payload(ty: Option<&Type>) -> Result<(), String> {
+        match ty {
+            None => Ok(()),
+            Some(ty) => Err(type_mismatch("a case without payload", ty)),
+        }
+    }
+
+    fn into_payload(self) -> Option<Box<Val>> {
+        None
+    }
+
+    fn from_payload(val: Option<Box<Val>>) -> Result<Self, RuntimeError> {
+        match val {
+            None => Ok(()),
+            Some(_) => Err(RuntimeError::new("unexpected variant payload")),
+        }
+    }
+}
+
+impl<T: ComponentType> ComponentType for Vec<T> {
+    fn typecheck(ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::List(elem) => T::typecheck(elem),
+            ty => Err(type_mismatch("list", ty)),
+        }
+    }
+
+    fn into_val(self) -> Val {
+        Val::List(self.into_iter().map(T::into_val).collect())
+    }
+
+    fn from_val(val: Val) -> Result<Self, RuntimeError> {
+        match val {
+            Val::List(vals) => vals.into_iter().map(T::from_val).collect(),
+            val => Err(mismatch("list", &val)),
+        }
+    }
+}
+
+impl<T: ComponentType> ComponentType for Option<T> {
+    fn typecheck(ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::Option(ty) => T::typecheck(ty),
+            ty => Err(type_mismatch("option", ty)),
+        }
+    }
+
+    fn into_val(self) -> Val {
+        Val::Option(self.map(|value| Box::new(value.into_val())))
+    }
+
+    fn from_val(val: Val) -> Result<Self, RuntimeError> {
+        match val {
+            Val::Option(val) => val.map(|val| T::from_val(*val)).transpose(),
+            val => Err(mismatch("option", &val)),
+        }
+    }
+}
+
+impl<T: ComponentType, E: ComponentType> ComponentType for Result<T, E> {
+    fn typecheck(ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::Result { ok, err } => {
+                T::typecheck_payload(ok.as_deref())?;
+                E::typecheck_payload(err.as_deref())
+            }
+            ty => Err(type_mismatch("result", ty)),
+        }
+    }
+
+    fn into_val(self) -> Val {
+        Val::Result(self.map(T::into_payload).map_err(E::into_payload))
+    }
+
+    fn from_val(val: Val) -> Result<Self, RuntimeError> {
+        match val {
+            Val::Result(Ok(val)) => Ok(Ok(T::from_payload(val)?)),
+            Val::Result(Err(val)) => Ok(Err(E::from_payload(val)?)),
+            val => Err(mismatch("result", &val)),
+        }
+    }
+}
+
+impl<T: 'static> ComponentType for Resource<T> {
+    fn typecheck(ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::Own(resource) | Type::Borrow(resource)
+                if *resource == ResourceType::host::<T>() =>
+            {
+                Ok(())
+            }
+            ty => Err(type_mismatch(std::any::type_name::<T>(), ty)),
+        }
+    }
+
+    fn into_val(self) -> Val {
+        Val::Resource(self.into_resource_any())
+    }
+
+    fn from_val(val: Val) -> Result<Self, RuntimeError> {
+        match val {
+            Val::Resource(resource) => Self::try_from_resource_any(resource),
+            val => Err(mismatch("resource", &val)),
+        }
+    }
+}
+
+impl ComponentType for ResourceAny {
+    fn typecheck(ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::Own(_) | Type::Borrow(_) => Ok(()),
+            ty => Err(type_mismatch("resource", ty)),
+        }
+    }
+
+    fn into_val(self) -> Val {
+        Val::Resource(self)
+    }
+
+    fn from_val(val: Val) -> Result<Self, RuntimeError> {
+        match val {
+            Val::Resource(resource) => Ok(resource),
+            val => Err(mismatch("resource", &val)),
+        }
+    }
+}
+
+/// A list of component values, used for the parameters and results of
+/// functions.
+pub trait ComponentList: Sized {
+    /// Checks that the list matches the component types `types`.
+    fn typecheck(types: &[Type]) -> Result<(), String>;
+
+    /// Converts the list to component values.
+    fn into_vals(self) -> Vec<Val>;
+
+    /// Converts component values to the list.
+    fn from_vals(vals: Vec<Val>) -> Result<Self, RuntimeError>;
+}
+
+impl ComponentList for () {
+    fn typecheck(types: &[Type]) -> Result<(), String> {
+        match types.len() {
+            0 => Ok(()),
+            len => Err(format!("expected 0 values, found {len}")),
+        }
+    }
+
+    fn into_vals(self) -> Vec<Val> {
+        Vec::new()
+    }
+
+    fn from_vals(vals: Vec<Val>) -> Result<Self, RuntimeError> {
+        match vals.len() {
+            0 => Ok(()),
+            len => Err(RuntimeError::new(format!("expected 0 values, found {len}"))),
+        }
+    }
+}
+
+macro_rules! tuples {
+    ($(($($t:ident),+),)*) => {$(
+        #[allow(non_snake_case)]
+        impl<$($t: ComponentType),+> ComponentType for ($($t,)+) {
+            fn typecheck(ty: &Type) -> Result<(), String> {
+                match ty {
+                    Type::Tuple(types) => <($($t,)+) as ComponentList>::typecheck(types),
+                    ty => Err(type_mismatch("tuple", ty)),
+                }
+            }
+
+            fn into_val(self) -> Val {
+                Val::Tuple(self.into_vals())
+            }
+
+            fn from_val(val: Val) -> Result<Self, RuntimeError> {
+                match val {
+                    Val::Tuple(vals) => Self::from_vals(vals),
+                    val => Err(mismatch("tuple", &val)),
+                }
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<$($t: ComponentType),+> ComponentList for ($($t,)+) {
+            fn typecheck(types: &[Type]) -> Result<(), String> {
+                let expected = [$(stringify!($t)),+].len();
+                let [$($t),+] = types else {
+                    return Err(format!("expected {expected} values, found {}", types.len()));
+                };
+                $($t::typecheck($t)?;)+
+                Ok(())
+            }
+
+            fn into_vals(self) -> Vec<Val> {
+                let ($($t,)+) = self;
+                vec![$($t.into_val()),+]
+            }
+
+            fn from_vals(vals: Vec<Val>) -> Result<Self, RuntimeError> {
+                let expected = [$(stringify!($t)),+].len();
+                let found = vals.len();
+                let Ok([$($t),+]) = <[Val; [$(stringify!($t)),+].len()]>::try_from(vals) else {
+                    return Err(RuntimeError::new(format!(
+                        "expected {expected} values, found {found}"
+                    )));
+                };
+                Ok(($($t::from_val($t)?,)+))
+            }
+        }
+    )*};
+}
+
+tuples! {
+    (A1),
+    (A1, A2),
+    (A1, A2, A3),
+    (A1, A2, A3, A4),
+    (A1, A2, A3, A4, A5),
+    (A1, A2, A3, A4, A5, A6),
+    (A1, A2, A3, A4, A5, A6, A7),
+    (A1, A2, A3, A4, A5, A6, A7, A8),
+    (A1, A2, A3, A4, A5, A6, A7, A8, A9),
+    (A1, A2, A3, A4, A5, A6, A7, A8, A9, A10),
+    (A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11),
+    (A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12),
+}
+
+/// A component function with statically known parameter and result types.
+///
+/// Created with [`Exports::get_typed_func`](super::Exports::get_typed_func).
+pub struct TypedFunc<Params, Results> {
+    func: Func,
+    _marker: PhantomData<fn(Params) -> Results>,
+}
+
+impl<Params, Results> Clone for TypedFunc<Params, Results> {
+    fn clone(&self) -> Self {
+        Self {
+            func: self.func.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Params, Results> std::fmt::Debug for TypedFunc<Params, Results> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypedFunc")
+            .field("func", &self.func)
+            .finish()
+    }
+}
+
+impl<Params: ComponentList, Results: ComponentList> TypedFunc<Params, Results> {
+    /// Checks the type of `func` and wraps it.
+    pub(crate) fn new(func: Func) -> Result<Self, String> {
+        let ty = func.ty();
+        let params = ty
+            .params()
+            .iter()
+            .map(|(_, ty)| ty.clone())
+            .collect::<Vec<_>>();
+        Params::typecheck(&params).map_err(|err| format!("parameters: {err}"))?;
+        Results::typecheck(ty.results()).map_err(|err| format!("results: {err}"))?;
+        Ok(Self {
+            func,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The dynamically typed function.
+    pub fn func(&self) -> &Func {
+        &self.func
+    }
+
+    /// Calls the function.
+    pub fn call(
+        &self,
+        store: &mut impl AsStoreMut,
+        params: Params,
+    ) -> Result<Results, RuntimeError> {
+        let results = self.func.call(store, &params.into_vals())?;
+        Results::from_vals(results.into_vec())
+    }
+}
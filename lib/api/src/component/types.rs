@@ -0,0 +1,308 @@
+//! Types of the values exchanged with components.
+
+use std::any::TypeId;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The type of a component value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    /// A boolean.
+    Bool,
+    /// A signed 8-bit integer.
+    S8,
+    /// An unsigned 8-bit integer.
+    U8,
+    /// A signed 16-bit integer.
+    S16,
+    /// An unsigned 16-bit integer.
+    U16,
+    /// A signed 32-bit integer.
+    S32,
+    /// An unsigned 32-bit integer.
+    U32,
+    /// A signed 64-bit integer.
+    S64,
+    /// An unsigned 64-bit integer.
+    U64,
+    /// A 32-bit float.
+    Float32,
+    /// A 64-bit float.
+    Float64,
+    /// A Unicode scalar value.
+    Char,
+    /// A string.
+    String,
+    /// A list of values of the same type.
+    List(Box<Type>),
+    /// A record with named fields.
+    Record(Vec<(String, Type)>),
+    /// A tuple.
+    Tuple(Vec<Type>),
+    /// A variant: a set of named cases, each with an optional payload.
+    Variant(Vec<(String, Option<Type>)>),
+    /// An enumeration: a variant without payloads.
+    Enum(Vec<String>),
+    /// An optional value.
+    Option(Box<Type>),
+    /// A result, with optional payloads for the `ok` and `err` cases.
+    Result {
+        /// The payload of the `ok` case.
+        ok: Option<Box<Type>>,
+        /// The payload of the `err` case.
+        err: Option<Box<Type>>,
+    },
+    /// A set of named flags.
+    Flags(Vec<String>),
+    /// An owned handle to a resource.
+    Own(ResourceType),
+    /// A borrowed handle to a resource.
+    Borrow(ResourceType),
+}
+
+impl Type {
+    /// Returns a copy of this type where the resources of the component
+    /// are replaced by the resources of an instance.
+    pub(crate) fn resolve(&self, resources: &[ResourceType]) -> Self {
+        let resolve_resource = |ty: &ResourceType| match ty.0 {
+            ResourceTypeKind::Component(index) => resources[index as usize],
+            _ => *ty,
+        };
+        match self {
+            Self::List(ty) => Self::List(Box::new(ty.resolve(resources))),
+            Self::Record(fields) => Self::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), ty.resolve(resources)))
+                    .collect(),
+            ),
+            Self::Tuple(types) => {
+                Self::Tuple(types.iter().map(|ty| ty.resolve(resources)).collect())
+            }
+            Self::Variant(cases) => Self::Variant(
+                cases
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), ty.as_ref().map(|ty| ty.resolve(resources))))
+                    .collect(),
+            ),
+            Self::Option(ty) => Self::Option(Box::new(ty.resolve(resources))),
+            Self::Result { ok, err } => Self::Result {
+                ok: ok.as_ref().map(|ty| Box::new(ty.resolve(resources))),
+                err: err.as_ref().map(|ty| Box::new(ty.resolve(resources))),
+            },
+            Self::Own(ty) => Self::Own(resolve_resource(ty)),
+            Self::Borrow(ty) => Self::Borrow(resolve_resource(ty)),
+            ty => ty.clone(),
+        }
+    }
+
+    pub(crate) fn desc(&self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::S8 => "s8",
+            Self::U8 => "u8",
+            Self::S16 => "s16",
+            Self::U16 => "u16",
+            Self::S32 => "s32",
+            Self::U32 => "u32",
+            Self::S64 => "s64",
+            Self::U64 => "u64",
+            Self::Float32 => "f32",
+            Self::Float64 => "f64",
+            Self::Char => "char",
+            Self::String => "string",
+            Self::List(_) => "list",
+            Self::Record(_) => "record",
+            Self::Tuple(_) => "tuple",
+            Self::Variant(_) => "variant",
+            Self::Enum(_) => "enum",
+            Self::Option(_) => "option",
+            Self::Result { .. } => "result",
+            Self::Flags(_) => "flags",
+            Self::Own(_) => "own",
+            Self::Borrow(_) => "borrow",
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::List(ty) => write!(f, "list<{ty}>"),
+            Self::Record(fields) => {
+                write!(f, "record {{")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{sep}{name}: {ty}")?;
+                }
+                write!(f, " }}")
+            }
+            Self::Tuple(types) => {
+                write!(f, "tuple<")?;
+                for (i, ty) in types.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { ", " };
+                    write!(f, "{sep}{ty}")?;
+                }
+                write!(f, ">")
+            }
+            Self::Variant(cases) => {
+                write!(f, "variant {{")?;
+                for (i, (name, ty)) in cases.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    match ty {
+                        Some(ty) => write!(f, "{sep}{name}({ty})")?,
+                        None => write!(f, "{sep}{name}")?,
+                    }
+                }
+                write!(f, " }}")
+            }
+            Self::Enum(cases) => write!(f, "enum {{ {} }}", cases.join(", ")),
+            Self::Option(ty) => write!(f, "option<{ty}>"),
+            Self::Result { ok, err } => match (ok, err) {
+                (None, None) => write!(f, "result"),
+                (Some(ok), None) => write!(f, "result<{ok}>"),
+                (None, Some(err)) => write!(f, "result<_, {err}>"),
+                (Some(ok), Some(err)) => write!(f, "result<{ok}, {err}>"),
+            },
+            Self::Flags(flags) => write!(f, "flags {{ {} }}", flags.join(", ")),
+            Self::Own(ty) => write!(f, "own<{ty}>"),
+            Self::Borrow(ty) => write!(f, "borrow<{ty}>"),
+            ty => f.write_str(ty.desc()),
+        }
+    }
+}
+
+/// The type of a component function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuncType {
+    pub(crate) params: Vec<(String, Type)>,
+    pub(crate) results: Vec<Type>,
+}
+
+impl FuncType {
+    /// Creates a new function type.
+    pub fn new(params: Vec<(String, Type)>, results: Vec<Type>) -> Self {
+        Self { params, results }
+    }
+
+    /// The named parameters of the function.
+    pub fn params(&self) -> &[(String, Type)] {
+        &self.params
+    }
+
+    /// The results of the function.
+    pub fn results(&self) -> &[Type] {
+        &self.results
+    }
+
+    pub(crate) fn resolve(&self, resources: &[ResourceType]) -> Self {
+        Self {
+            params: self
+                .params
+                .iter()
+                .map(|(name, ty)| (name.clone(), ty.resolve(resources)))
+                .collect(),
+            results: self
+                .results
+                .iter()
+                .map(|ty| ty.resolve(resources))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for FuncType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "func(")?;
+        for (i, (name, ty)) in self.params.iter().enumerate() {
+            let sep = if i == 0 { "" } else { ", " };
+            write!(f, "{sep}{name}: {ty}")?;
+        }
+        write!(f, ")")?;
+        match self.results.as_slice() {
+            [] => Ok(()),
+            [ty] => write!(f, " -> {ty}"),
+            results => {
+                write!(f, " -> (")?;
+                for (i, ty) in results.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { ", " };
+                    write!(f, "{sep}{ty}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// The identity of a resource type.
+///
+/// Resource types are either defined by the host, in which case they are
+/// identified by a Rust type, or defined by a component instance, in which
+/// case each instance has its own resource types.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceType(pub(crate) ResourceTypeKind);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ResourceTypeKind {
+    /// A resource defined by the host.
+    Host(TypeId),
+    /// A resource defined by a component instance.
+    Guest { instance: u64, index: u32 },
+    /// A resource of a component that is not instantiated yet, bound to an
+    /// actual resource type when the component is instantiated.
+    Component(u32),
+}
+
+impl ResourceType {
+    /// The resource type of host resources represented by `T`.
+    pub fn host<T: 'static>() -> Self {
+        Self(ResourceTypeKind::Host(TypeId::of::<T>()))
+    }
+
+    pub(crate) fn guest(instance: u64, index: u32) -> Self {
+        Self(ResourceTypeKind::Guest { instance, index })
+    }
+
+    pub(crate) fn component(index: u32) -> Self {
+        Self(ResourceTypeKind::Component(index))
+    }
+
+    /// Returns the id of the instance defining this resource, if it is
+    /// defined by a component instance.
+    pub(crate) fn instance(&self) -> Option<u64> {
+        match self.0 {
+            ResourceTypeKind::Guest { instance, .. } => Some(instance),
+            _ => None,
+        }
+    }
+
+    /// Allocates the id of a new component instance.
+    pub(crate) fn next_instance_id() -> u64 {
+        static NEXT_INSTANCE_ID: AtomicU64 = AtomicU64::new(0);
+        NEXT_INSTANCE_ID.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ResourceTypeKind::Host(id) => f.debug_tuple("Host").field(&id).finish(),
+            ResourceTypeKind::Guest { instance, index } => f
+                .debug_struct("Guest")
+                .field("instance", &instance)
+                .field("index", &index)
+                .finish(),
+            ResourceTypeKind::Component(index) => f.debug_tuple("Component").field(&index).finish(),
+        }
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ResourceTypeKind::Host(_) => write!(f, "host resource"),
+            ResourceTypeKind::Guest { index, .. } => write!(f, "resource {index}"),
+            ResourceTypeKind::Component(index) => write!(f, "resource {index}"),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Values exchanged with components, and the resources they refer to.
+
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use super::types::ResourceType;
+use crate::store::{AsStoreMut, StoreMut};
+use crate::{Function, RuntimeError, Value};
+
+/// A dynamically typed component value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Val {
+    /// A boolean.
+    Bool(bool),
+    /// A signed 8-bit integer.
+    S8(i8),
+    /// An unsigned 8-bit integer.
+    U8(u8),
+    /// A signed 16-bit integer.
+    S16(i16),
+    /// An unsigned 16-bit integer.
+    U16(u16),
+    /// A signed 32-bit integer.
+    S32(i32),
+    /// An unsigned 32-bit integer.
+    U32(u32),
+    /// A signed 64-bit integer.
+    S64(i64),
+    /// An unsigned 64-bit integer.
+    U64(u64),
+    /// A 32-bit float.
+    Float32(f32),
+    /// A 64-bit float.
+    Float64(f64),
+    /// A Unicode scalar value.
+    Char(char),
+    /// A string.
+    String(String),
+    /// A list.
+    List(Vec<Val>),
+    /// A record, with its fields in the order of its type.
+    Record(Vec<(String, Val)>),
+    /// A tuple.
+    Tuple(Vec<Val>),
+    /// A case of a variant, with its payload.
+    Variant(String, Option<Box<Val>>),
+    /// A case of an enumeration.
+    Enum(String),
+    /// An optional value.
+    Option(Option<Box<Val>>),
+    /// A result, with the payloads of its cases.
+    Result(Result<Option<Box<Val>>, Option<Box<Val>>>),
+    /// The flags that are set.
+    Flags(Vec<String>),
+    /// A handle to a resource.
+    Resource(ResourceAny),
+}
+
+impl Val {
+    pub(crate) fn desc(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "bool",
+            Self::S8(_) => "s8",
+            Self::U8(_) => "u8",
+            Self::S16(_) => "s16",
+            Self::U16(_) => "u16",
+            Self::S32(_) => "s32",
+            Self::U32(_) => "u32",
+            Self::S64(_) => "s64",
+            Self::U64(_) => "u64",
+            Self::Float32(_) => "f32",
+            Self::Float64(_) => "f64",
+            Self::Char(_) => "char",
+            Self::String(_) => "string",
+            Self::List(_) => "list",
+            Self::Record(_) => "record",
+            Self::Tuple(_) => "tuple",
+            Self::Variant(..) => "variant",
+            Self::Enum(_) => "enum",
+            Self::Option(_) => "option",
+            Self::Result(_) => "result",
+            Self::Flags(_) => "flags",
+            Self::Resource(_) => "resource",
+        }
+    }
+}
+
+type HostDtor = dyn Fn(&mut StoreMut<'_>, u32) -> Result<(), RuntimeError> + Send + Sync;
+
+/// The destructor of a resource, called when its last owned handle is
+/// dropped.
+#[derive(Clone)]
+pub(crate) enum Dtor {
+    /// A destructor registered with the [`Linker`](super::Linker).
+    Host(Arc<HostDtor>),
+    /// The destructor of a resource defined by a component.
+    Guest(Function),
+}
+
+impl Dtor {
+    pub(crate) fn call(&self, store: &mut StoreMut<'_>, rep: u32) -> Result<(), RuntimeError> {
+        match self {
+            Self::Host(dtor) => dtor(store, rep),
+            Self::Guest(dtor) => dtor.call(store, &[Value::I32(rep as i32)]).map(drop),
+        }
+    }
+}
+
+impl fmt::Debug for Dtor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Host(_) => f.write_str("Host"),
+            Self::Guest(func) => f.debug_tuple("Guest").field(func).finish(),
+        }
+    }
+}
+
+/// A handle to a resource of any type.
+///
+/// Resources defined by a component are received by the host as
+/// `ResourceAny`. An owned resource must be dropped with
+/// [`ResourceAny::resource_drop`] once the host is done with it, which
+/// runs its destructor.
+#[derive(Debug, Clone)]
+pub struct ResourceAny {
+    pub(crate) ty: ResourceType,
+    pub(crate) rep: u32,
+    pub(crate) owned: bool,
+    pub(crate) dtor: Option<Dtor>,
+}
+
+impl ResourceAny {
+    /// The type of the resource.
+    pub fn ty(&self) -> ResourceType {
+        self.ty
+    }
+
+    /// Whether this handle owns the resource.
+    pub fn owned(&self) -> bool {
+        self.owned
+    }
+
+    /// The representation of the resource in the component defining it.
+    pub fn rep(&self) -> u32 {
+        self.rep
+    }
+
+    /// Drops an owned resource, running its destructor.
+    ///
+    /// Dropping a borrowed resource does nothing.
+    pub fn resource_drop(self, store: &mut impl AsStoreMut) -> Result<(), RuntimeError> {
+        match (&self.dtor, self.owned) {
+            (Some(dtor), true) => dtor.call(&mut store.as_store_mut(), self.rep),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl PartialEq for ResourceAny {
+    fn eq(&self, other: &Self) -> bool {
+        self.ty == other.ty && self.rep == other.rep && self.owned == other.owned
+    }
+}
+
+/// A handle to a host resource of type `T`.
+///
+/// The resource is identified by its `rep`, which usually is the index of
+/// its state in a [`ResourceTable`].
+pub struct Resource<T> {
+    rep: u32,
+    owned: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Resource<T> {
+    /// Creates an owned handle to the resource `rep`.
+    pub fn new_own(rep: u32) -> Self {
+        Self {
+            rep,
+            owned: true,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a borrowed handle to the resource `rep`.
+    pub fn new_borrow(rep: u32) -> Self {
+        Self {
+            rep,
+            owned: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The representation of the resource.
+    pub fn rep(&self) -> u32 {
+        self.rep
+    }
+
+    /// Whether this handle owns the resource.
+    pub fn owned(&self) -> bool {
+        self.owned
+    }
+
+    /// Converts this handle to a dynamically typed one.
+    pub fn into_resource_any(self) -> ResourceAny {
+        ResourceAny {
+            ty: ResourceType::host::<T>(),
+            rep: self.rep,
+            owned: self.owned,
+            dtor: None,
+        }
+    }
+
+    /// Converts a dynamically typed handle, checking its type.
+    pub fn try_from_resource_any(resource: ResourceAny) -> Result<Self, RuntimeError> {
+        if resource.ty != ResourceType::host::<T>() {
+            return Err(RuntimeError::new("mismatched resource types"));
+        }
+        Ok(Self {
+            rep: resource.rep,
+            owned: resource.owned,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T> fmt::Debug for Resource<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resource")
+            .field("rep", &self.rep)
+            .field("owned", &self.owned)
+            .finish()
+    }
+}
+
+impl<T> PartialEq for Resource<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rep == other.rep && self.owned == other.owned
+    }
+}
+
+/// An error returned by a [`ResourceTable`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ResourceTableError {
+    /// The resource is not in the table.
+    #[error("resource not present in the table")]
+    NotPresent,
+    /// The resource in the table has a different type.
+    #[error("resource has a different type")]
+    WrongType,
+}
+
+/// A table of host resources, indexed by their `rep`.
+///
+/// It is usually kept in the environment of the host functions, which
+/// create resources with [`ResourceTable::push`] and delete them when the
+/// destructor registered with the [`Linker`](super::Linker) is called.
+#[derive(Default)]
+pub struct ResourceTable {
+    entries: Vec<Option<Box<dyn Any + Send>>>,
+    free: Vec<u32>,
+}
+
+impl ResourceTable {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resource to the table, returning an owned handle to it.
+    pub fn push<T: Send + 'static>(&mut self, value: T) -> Resource<T> {
+        let entry = Some(Box::new(value) as Box<dyn Any + Send>);
+        let rep = match self.free.pop() {
+            Some(rep) => {
+                self.entries[rep as usize] = entry;
+                rep
+            }
+            None => {
+                self.entries.push(entry);
+                (self.entries.len() - 1) as u32
+            }
+        };
+        Resource::new_own(rep)
+    }
+
+    /// Returns a reference to the state of a resource.
+    pub fn get<T: 'static>(&self, resource: &Resource<T>) -> Result<&T, ResourceTableError> {
+        self.entries
+            .get(resource.rep() as usize)
+            .and_then(Option::as_ref)
+            .ok_or(ResourceTableError::NotPresent)?
+            .downcast_ref()
+            .ok_or(ResourceTableError::WrongType)
+    }
+
+    /// Returns a mutable reference to the state of a resource.
+    pub fn get_mut<T: 'static>(
+        &mut self,
+        resource: &Resource<T>,
+    ) -> Result<&mut T, ResourceTableError> {
+        self.entries
+            .get_mut(resource.rep() as usize)
+            .and_then(Option::as_mut)
+            .ok_or(ResourceTableError::NotPresent)?
+            .downcast_mut()
+            .ok_or(ResourceTableError::WrongType)
+    }
+
+    /// Removes a resource from the table, returning its state.
+    pub fn delete<T: 'static>(&mut self, resource: Resource<T>) -> Result<T, ResourceTableError> {
+        let rep = resource.rep();
+        let entry = self
+            .entries
+            .get_mut(rep as usize)
+            .ok_or(ResourceTableError::NotPresent)?;
+        if !entry.as_ref().is_some_and(|value| value.is::<T>()) {
+            return Err(match entry {
+                Some(_) => ResourceTableError::WrongType,
+                None => ResourceTableError::NotPresent,
+            });
+        }
+        let value = entry.take().unwrap();
+        self.free.push(rep);
+        Ok(*value.downcast().unwrap())
+    }
+}
+
+impl fmt::Debug for ResourceTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResourceTable")
+            .field("len", &(self.entries.len() - self.free.len()))
+            .finish()
+    }
+}
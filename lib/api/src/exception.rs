@@ -0,0 +1,50 @@
+use crate::store::{AsStoreMut, AsStoreRef};
+
+#[cfg(feature = "wasm-c-api")]
+use crate::c_api::exception as exception_imp;
+#[cfg(feature = "js")]
+use crate::js::exception as exception_imp;
+#[cfg(feature = "jsc")]
+use crate::jsc::exception as exception_imp;
+#[cfg(feature = "sys")]
+use crate::sys::exception as exception_imp;
+use crate::vm::VMExceptionRef;
+use crate::{Tag, Value};
+
+#[derive(Debug, Clone)]
+#[repr(transparent)]
+/// An exception thrown by WebAssembly code. This is what an `exnref`
+/// refers to.
+pub struct Exception(pub(crate) exception_imp::Exception);
+
+impl Exception {
+    /// Returns the [`Tag`] this exception was thrown with.
+    pub fn tag(&self, store: &impl AsStoreRef) -> Tag {
+        self.0.tag(store)
+    }
+
+    /// Returns the values carried by this exception, whose types are the
+    /// parameters of its [`Tag`].
+    pub fn payload(&self, store: &mut impl AsStoreMut) -> Vec<Value> {
+        self.0.payload(store)
+    }
+
+    pub(crate) fn vm_exceptionref(&self) -> VMExceptionRef {
+        self.0.vm_exceptionref()
+    }
+
+    pub(crate) unsafe fn from_vm_exceptionref(
+        store: &mut impl AsStoreMut,
+        vm_exceptionref: VMExceptionRef,
+    ) -> Self {
+        Self(exception_imp::Exception::from_vm_exceptionref(
+            store,
+            vm_exceptionref,
+        ))
+    }
+
+    /// Checks whether this `Exception` can be used with the given context.
+    pub fn is_from_store(&self, store: &impl AsStoreRef) -> bool {
+        self.0.is_from_store(store)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::store::AsStoreRef;
-use crate::{Extern, Function, Global, Memory, Table, TypedFunction, WasmTypeList};
+use crate::{Extern, Function, Global, Memory, Table, Tag, TypedFunction, WasmTypeList};
 use indexmap::IndexMap;
 use std::fmt;
 use std::iter::{ExactSizeIterator, FromIterator};
@@ -128,6 +128,11 @@ impl Exports {
         self.get(name)
     }
 
+    /// Get an export as a `Tag`.
+    pub fn get_tag(&self, name: &str) -> Result<&Tag, ExportError> {
+        self.get(name)
+    }
+
     /// Get an export as a `Func`.
     pub fn get_function(&self, name: &str) -> Result<&Function, ExportError> {
         self.get(name)
@@ -251,6 +256,14 @@ where
             _ => None,
         })
     }
+
+    /// Get only the tags.
+    pub fn tags(self) -> impl Iterator<Item = (&'a String, &'a Tag)> + Sized {
+        self.iter.filter_map(|(name, export)| match export {
+            Extern::Tag(tag) => Some((name, tag)),
+            _ => None,
+        })
+    }
 }
 
 impl FromIterator<(String, Extern)> for Exports {
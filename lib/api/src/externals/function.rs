@@ -15,7 +15,11 @@ use crate::{
 };
 use wasmer_types::RawValue;
 
+#[cfg(feature = "sys")]
+use crate::native_type::IntoResult;
 use crate::native_type::WasmTypeList;
+#[cfg(feature = "sys")]
+use std::{future::Future, pin::Pin};
 
 /// The `HostFunction` trait represents the set of functions that
 /// can be used as host function. To uphold this statement, it is
@@ -202,6 +206,96 @@ impl Function {
         ))
     }
 
+    /// Creates a new async host `Function` from a function returning a
+    /// future.
+    ///
+    /// The function receives the arguments as a tuple (or a single value
+    /// for functions with one parameter), and its signature is retrieved
+    /// from the types of the arguments and of the output of the future.
+    ///
+    /// The Wasm code calling an async host function must be called with
+    /// [`Function::call_async`] or [`TypedFunction::call_async`]: the call
+    /// is suspended while the future is pending. Calling it from a
+    /// synchronous call traps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, Store};
+    /// # let mut store = Store::default();
+    /// #
+    /// let f = Function::new_typed_async(&mut store, |(a, b): (i32, i32)| async move {
+    ///     // Any async code can run here.
+    ///     a + b
+    /// });
+    /// ```
+    #[cfg(feature = "sys")]
+    pub fn new_typed_async<F, Fut, Args, Rets, RetsAsResult>(
+        store: &mut impl AsStoreMut,
+        func: F,
+    ) -> Self
+    where
+        F: Fn(Args) -> Fut + 'static + Send + Sync,
+        Fut: Future<Output = RetsAsResult> + Send + 'static,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        RetsAsResult: IntoResult<Rets>,
+    {
+        Self(function_impl::Function::new_typed_async(store, func))
+    }
+
+    /// Creates a new async host `Function` with an environment from a
+    /// function returning a boxed future.
+    ///
+    /// The future must be `Send`, since the call may be resumed on another
+    /// thread while it is pending. The [`FunctionEnvMut`] isn't, so the store
+    /// and the environment are accessed before the future is created. See
+    /// [`Function::new_typed_async`] for how async host functions are called.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionEnv, FunctionEnvMut, Store};
+    /// # let mut store = Store::default();
+    /// # let env = FunctionEnv::new(&mut store, 0u32);
+    /// #
+    /// let f = Function::new_typed_async_with_env(
+    ///     &mut store,
+    ///     &env,
+    ///     |mut env: FunctionEnvMut<u32>, delta: u32| {
+    ///         *env.data_mut() += delta;
+    ///         let total = *env.data();
+    ///         Box::pin(async move {
+    ///             // Any async code can run here.
+    ///             total
+    ///         })
+    ///     },
+    /// );
+    /// ```
+    #[cfg(feature = "sys")]
+    #[allow(clippy::type_complexity)]
+    pub fn new_typed_async_with_env<T: Send + 'static, F, Args, Rets, RetsAsResult>(
+        store: &mut impl AsStoreMut,
+        env: &FunctionEnv<T>,
+        func: F,
+    ) -> Self
+    where
+        F: for<'a> Fn(
+                FunctionEnvMut<'a, T>,
+                Args,
+            ) -> Pin<Box<dyn Future<Output = RetsAsResult> + Send + 'a>>
+            + 'static
+            + Send
+            + Sync,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        RetsAsResult: IntoResult<Rets>,
+    {
+        Self(function_impl::Function::new_typed_async_with_env(
+            store, env, func,
+        ))
+    }
+
     /// Returns the [`FunctionType`] of the `Function`.
     ///
     /// # Example
@@ -305,6 +399,22 @@ impl Function {
         self.0.call(store, params)
     }
 
+    /// Call the `Function` function asynchronously.
+    ///
+    /// The call runs on a separate fiber stack, which is suspended whenever
+    /// an async host function (see [`Function::new_typed_async`]) waits on
+    /// a pending future. The store stays borrowed until the returned future
+    /// completes. Dropping the future before it completes makes the pending
+    /// async host function trap.
+    #[cfg(feature = "sys")]
+    pub fn call_async<'a>(
+        &self,
+        store: &'a mut impl AsStoreMut,
+        params: &[Value],
+    ) -> impl Future<Output = Result<Box<[Value]>, RuntimeError>> + 'a {
+        self.0.call_async(store, params)
+    }
+
     #[doc(hidden)]
     #[allow(missing_docs)]
     pub fn call_raw(
@@ -315,6 +425,32 @@ impl Function {
         self.0.call_raw(store, params)
     }
 
+    /// Wraps this host function so that its calls are recorded by the
+    /// store's [`HostProfiler`](crate::HostProfiler) under `module` and
+    /// `name`.
+    ///
+    /// If the store doesn't have a profiler, the function is returned as is.
+    pub fn profiled(&self, store: &mut impl AsStoreMut, module: &str, name: &str) -> Self {
+        let Some(profiler) = store.as_store_ref().host_profiler().cloned() else {
+            return self.clone();
+        };
+
+        let inner = self.clone();
+        let ty = self.ty(store);
+        let env = FunctionEnv::new(store, ());
+        let (module, name) = (module.to_string(), name.to_string());
+
+        Self::new_with_env(store, &env, ty, move |mut env, args| {
+            profiler.record(&module, &name, || {
+                #[cfg(feature = "sys")]
+                let results = inner.0.call_nested(&mut env, args)?;
+                #[cfg(not(feature = "sys"))]
+                let results = inner.call(&mut env, args)?;
+                Ok(results.into_vec())
+            })
+        })
+    }
+
     pub(crate) fn vm_funcref(&self, store: &impl AsStoreRef) -> VMFuncRef {
         self.0.vm_funcref(store)
     }
@@ -9,6 +9,7 @@ use crate::sys::externals::memory as memory_impl;
 
 use super::memory_view::MemoryView;
 use crate::exports::{ExportError, Exportable};
+use crate::host_profiling;
 use crate::store::{AsStoreMut, AsStoreRef};
 use crate::vm::{VMExtern, VMExternMemory, VMMemory};
 use crate::MemoryAccessError;
@@ -347,7 +348,9 @@ pub(crate) struct MemoryBuffer<'a>(pub(crate) memory_impl::MemoryBuffer<'a>);
 impl<'a> MemoryBuffer<'a> {
     #[allow(unused)]
     pub(crate) fn read(&self, offset: u64, buf: &mut [u8]) -> Result<(), MemoryAccessError> {
-        self.0.read(offset, buf)
+        self.0.read(offset, buf)?;
+        host_profiling::record_read(buf.len());
+        Ok(())
     }
 
     #[allow(unused)]
@@ -356,11 +359,15 @@ impl<'a> MemoryBuffer<'a> {
         offset: u64,
         buf: &'b mut [MaybeUninit<u8>],
     ) -> Result<&'b mut [u8], MemoryAccessError> {
-        self.0.read_uninit(offset, buf)
+        let buf = self.0.read_uninit(offset, buf)?;
+        host_profiling::record_read(buf.len());
+        Ok(buf)
     }
 
     #[allow(unused)]
     pub(crate) fn write(&self, offset: u64, data: &[u8]) -> Result<(), MemoryAccessError> {
-        self.0.write(offset, data)
+        self.0.write(offset, data)?;
+        host_profiling::record_written(data.len());
+        Ok(())
     }
 }
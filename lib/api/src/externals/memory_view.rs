@@ -1,4 +1,5 @@
 use super::memory::{Memory, MemoryBuffer};
+use crate::host_profiling;
 use crate::store::AsStoreRef;
 use crate::MemoryAccessError;
 use std::mem::MaybeUninit;
@@ -98,7 +99,9 @@ impl<'a> MemoryView<'a> {
     /// This method is guaranteed to be safe (from the host side) in the face of
     /// concurrent writes.
     pub fn read(&self, offset: u64, buf: &mut [u8]) -> Result<(), MemoryAccessError> {
-        self.0.read(offset, buf)
+        self.0.read(offset, buf)?;
+        host_profiling::record_read(buf.len());
+        Ok(())
     }
 
     /// Safely reads a single byte from memory at the given offset
@@ -106,7 +109,9 @@ impl<'a> MemoryView<'a> {
     /// This method is guaranteed to be safe (from the host side) in the face of
     /// concurrent writes.
     pub fn read_u8(&self, offset: u64) -> Result<u8, MemoryAccessError> {
-        self.0.read_u8(offset)
+        let val = self.0.read_u8(offset)?;
+        host_profiling::record_read(1);
+        Ok(val)
     }
 
     /// Safely reads bytes from the memory at the given offset.
@@ -124,7 +129,9 @@ impl<'a> MemoryView<'a> {
         offset: u64,
         buf: &'b mut [MaybeUninit<u8>],
     ) -> Result<&'b mut [u8], MemoryAccessError> {
-        self.0.read_uninit(offset, buf)
+        let buf = self.0.read_uninit(offset, buf)?;
+        host_profiling::record_read(buf.len());
+        Ok(buf)
     }
 
     /// Safely writes bytes to the memory at the given offset.
@@ -135,7 +142,9 @@ impl<'a> MemoryView<'a> {
     /// This method is guaranteed to be safe (from the host side) in the face of
     /// concurrent reads/writes.
     pub fn write(&self, offset: u64, data: &[u8]) -> Result<(), MemoryAccessError> {
-        self.0.write(offset, data)
+        self.0.write(offset, data)?;
+        host_profiling::record_written(data.len());
+        Ok(())
     }
 
     /// Safely writes a single byte from memory at the given offset
@@ -143,7 +152,9 @@ impl<'a> MemoryView<'a> {
     /// This method is guaranteed to be safe (from the host side) in the face of
     /// concurrent writes.
     pub fn write_u8(&self, offset: u64, val: u8) -> Result<(), MemoryAccessError> {
-        self.0.write_u8(offset, val)
+        self.0.write_u8(offset, val)?;
+        host_profiling::record_written(1);
+        Ok(())
     }
 
     /// Copies the memory and returns it as a vector of bytes
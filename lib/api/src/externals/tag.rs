@@ -0,0 +1,80 @@
+#[cfg(feature = "wasm-c-api")]
+use crate::c_api::externals::tag as tag_impl;
+#[cfg(feature = "js")]
+use crate::js::externals::tag as tag_impl;
+#[cfg(feature = "jsc")]
+use crate::jsc::externals::tag as tag_impl;
+#[cfg(feature = "sys")]
+use crate::sys::externals::tag as tag_impl;
+
+use crate::exports::{ExportError, Exportable};
+#[cfg(feature = "js")]
+use crate::js::vm::VMExternTag;
+#[cfg(feature = "jsc")]
+use crate::jsc::vm::VMExternTag;
+use crate::store::{AsStoreMut, AsStoreRef};
+#[cfg(feature = "sys")]
+use crate::sys::vm::VMExternTag;
+use crate::vm::VMExtern;
+use crate::Extern;
+use crate::TagType;
+
+/// A WebAssembly `tag` instance.
+///
+/// A tag identifies a kind of exception: the values an exception carries
+/// are described by the [`TagType`] of the tag it was thrown with, and
+/// only handlers for that same tag can catch it. Two tags with the same
+/// type are still distinct tags.
+///
+/// Spec: <https://webassembly.github.io/exception-handling/core/exec/runtime.html#tag-instances>
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
+pub struct Tag(pub(crate) tag_impl::Tag);
+
+impl Tag {
+    /// Creates a new `Tag` with the provided [`TagType`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Store, Tag, TagType, Type};
+    /// # let mut store = Store::default();
+    /// #
+    /// let tag = Tag::new(&mut store, TagType::new([Type::I32]));
+    ///
+    /// assert_eq!(tag.ty(&store).params(), &[Type::I32]);
+    /// ```
+    pub fn new(store: &mut impl AsStoreMut, ty: TagType) -> Self {
+        Self(tag_impl::Tag::new(store, ty))
+    }
+
+    /// Returns the [`TagType`] of the `Tag`.
+    pub fn ty(&self, store: &impl AsStoreRef) -> TagType {
+        self.0.ty(store)
+    }
+
+    #[cfg(any(feature = "sys", feature = "js", feature = "jsc"))]
+    pub(crate) fn from_vm_extern(store: &mut impl AsStoreMut, vm_extern: VMExternTag) -> Self {
+        Self(tag_impl::Tag::from_vm_extern(store, vm_extern))
+    }
+
+    /// Checks whether this `Tag` can be used with the given context.
+    pub fn is_from_store(&self, store: &impl AsStoreRef) -> bool {
+        self.0.is_from_store(store)
+    }
+
+    pub(crate) fn to_vm_extern(&self) -> VMExtern {
+        self.0.to_vm_extern()
+    }
+}
+
+impl std::cmp::Eq for Tag {}
+
+impl<'a> Exportable<'a> for Tag {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Tag(tag) => Ok(tag),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
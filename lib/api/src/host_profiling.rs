@@ -0,0 +1,234 @@
+//! Profiling of the host functions called by WebAssembly code.
+//!
+//! Profiling is opt-in: a [`HostProfiler`] is attached to a store with
+//! [`StoreMut::set_host_profiler`], and the imports that should be profiled
+//! are wrapped with [`Imports::profiled`] or [`Function::profiled`] before
+//! instantiating a module.
+//!
+//! [`StoreMut::set_host_profiler`]: crate::StoreMut::set_host_profiler
+//! [`Imports::profiled`]: crate::Imports::profiled
+//! [`Function::profiled`]: crate::Function::profiled
+
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Statistics about the calls made to a single host function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HostCallStats {
+    /// How many times the function was called.
+    pub calls: u64,
+    /// How many of those calls trapped.
+    pub traps: u64,
+    /// The time spent inside the function, across all calls.
+    pub total_time: Duration,
+    /// The time taken by the slowest call.
+    pub max_time: Duration,
+    /// The bytes of guest memory the function read through a
+    /// [`MemoryView`](crate::MemoryView), [`WasmPtr`](crate::WasmPtr) or
+    /// [`WasmSlice`](crate::WasmSlice).
+    pub bytes_read: u64,
+    /// The bytes of guest memory the function wrote.
+    pub bytes_written: u64,
+}
+
+impl HostCallStats {
+    /// The average time taken by a call.
+    pub fn average_time(&self) -> Duration {
+        match self.calls {
+            0 => Duration::ZERO,
+            calls => {
+                let nanos = self.total_time.as_nanos() / u128::from(calls);
+                Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+            }
+        }
+    }
+
+    fn record(&mut self, elapsed: Duration, trapped: bool, traffic: MemoryTraffic) {
+        self.calls += 1;
+        self.traps += u64::from(trapped);
+        self.total_time += elapsed;
+        self.max_time = self.max_time.max(elapsed);
+        self.bytes_read += traffic.read;
+        self.bytes_written += traffic.written;
+    }
+}
+
+impl std::ops::AddAssign for HostCallStats {
+    fn add_assign(&mut self, other: Self) {
+        self.calls += other.calls;
+        self.traps += other.traps;
+        self.total_time += other.total_time;
+        self.max_time = self.max_time.max(other.max_time);
+        self.bytes_read += other.bytes_read;
+        self.bytes_written += other.bytes_written;
+    }
+}
+
+/// A snapshot of the statistics collected by a [`HostProfiler`], keyed by
+/// the module and name of each import.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostProfile {
+    stats: BTreeMap<(String, String), HostCallStats>,
+}
+
+impl HostProfile {
+    /// Returns the statistics of an import, if it has been called.
+    pub fn get(&self, module: &str, name: &str) -> Option<&HostCallStats> {
+        self.stats.get(&(module.to_string(), name.to_string()))
+    }
+
+    /// Iterates over the `(module, name, stats)` of every import that has
+    /// been called, sorted by module and name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &HostCallStats)> {
+        self.stats
+            .iter()
+            .map(|((module, name), stats)| (module.as_str(), name.as_str(), stats))
+    }
+
+    /// The number of imports that have been called.
+    pub fn len(&self) -> usize {
+        self.stats.len()
+    }
+
+    /// Returns true if no import has been called.
+    pub fn is_empty(&self) -> bool {
+        self.stats.is_empty()
+    }
+
+    /// The statistics of all imports, added together.
+    pub fn total(&self) -> HostCallStats {
+        self.stats
+            .values()
+            .fold(HostCallStats::default(), |mut total, stats| {
+                total += *stats;
+                total
+            })
+    }
+
+    /// Serializes the profile as a JSON array with an object per import.
+    ///
+    /// Times are given in nanoseconds.
+    pub fn to_json(&self) -> String {
+        let entries: Vec<_> = self
+            .iter()
+            .map(|(module, name, stats)| JsonEntry {
+                module,
+                name,
+                calls: stats.calls,
+                traps: stats.traps,
+                total_time_ns: stats.total_time.as_nanos(),
+                max_time_ns: stats.max_time.as_nanos(),
+                bytes_read: stats.bytes_read,
+                bytes_written: stats.bytes_written,
+            })
+            .collect();
+        serde_json::to_string(&entries).expect("profiles are always serializable")
+    }
+}
+
+/// The JSON representation of the statistics of an import.
+#[derive(serde::Serialize)]
+struct JsonEntry<'a> {
+    module: &'a str,
+    name: &'a str,
+    calls: u64,
+    traps: u64,
+    total_time_ns: u128,
+    max_time_ns: u128,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+/// Collects [`HostCallStats`] for the profiled host functions of one or
+/// more stores.
+///
+/// Cloning a profiler is cheap, and the clones share their statistics. This
+/// makes it possible to profile the stores of every thread of a program
+/// together.
+#[derive(Debug, Clone, Default)]
+pub struct HostProfiler {
+    stats: Arc<Mutex<BTreeMap<(String, String), HostCallStats>>>,
+}
+
+impl HostProfiler {
+    /// Creates a new profiler without any statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a snapshot of the statistics collected so far.
+    pub fn profile(&self) -> HostProfile {
+        HostProfile {
+            stats: self.stats.lock().unwrap().clone(),
+        }
+    }
+
+    /// Forgets the statistics collected so far.
+    pub fn reset(&self) {
+        self.stats.lock().unwrap().clear();
+    }
+
+    /// Runs a host function call, recording how long it took and how much
+    /// guest memory it accessed.
+    pub(crate) fn record<T, E>(
+        &self,
+        module: &str,
+        name: &str,
+        call: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let outer = TRAFFIC.with(|t| t.replace(Some(MemoryTraffic::default())));
+        let start = Instant::now();
+
+        let result = call();
+
+        let elapsed = start.elapsed();
+        let traffic = TRAFFIC.with(|t| t.replace(outer)).unwrap_or_default();
+
+        let mut stats = self.stats.lock().unwrap();
+        let key = (module.to_string(), name.to_string());
+        stats
+            .entry(key)
+            .or_default()
+            .record(elapsed, result.is_err(), traffic);
+
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct MemoryTraffic {
+    read: u64,
+    written: u64,
+}
+
+thread_local! {
+    /// The guest memory accessed by the profiled host function running on
+    /// this thread, if any.
+    static TRAFFIC: Cell<Option<MemoryTraffic>> = const { Cell::new(None) };
+}
+
+/// Counts bytes read from guest memory towards the running host function.
+#[inline]
+pub(crate) fn record_read(bytes: usize) {
+    TRAFFIC.with(|t| {
+        if let Some(mut traffic) = t.get() {
+            traffic.read += bytes as u64;
+            t.set(Some(traffic));
+        }
+    })
+}
+
+/// Counts bytes written to guest memory towards the running host function.
+#[inline]
+pub(crate) fn record_written(bytes: usize) {
+    TRAFFIC.with(|t| {
+        if let Some(mut traffic) = t.get() {
+            traffic.written += bytes as u64;
+            t.set(Some(traffic));
+        }
+    })
+}
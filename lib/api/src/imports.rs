@@ -1,7 +1,7 @@
 //! The import module contains the implementation data structures and helper functions used to
 //! manipulate and access a wasm module's imports including memories, tables, globals, and
 //! functions.
-use crate::{Exports, Extern, LinkError, Module};
+use crate::{AsStoreMut, Exports, Extern, LinkError, Module};
 use std::collections::HashMap;
 use std::fmt;
 use wasmer_types::ImportError;
@@ -73,6 +73,31 @@ impl Imports {
             .contains_key(&(module.to_string(), name.to_string()))
     }
 
+    /// Wraps every function of these imports so that its calls are recorded
+    /// by the store's [`HostProfiler`](crate::HostProfiler).
+    ///
+    /// Imports that aren't functions are kept as is, and so is everything
+    /// when the store doesn't have a profiler.
+    pub fn profiled(&self, store: &mut impl AsStoreMut) -> Self {
+        if store.as_store_ref().host_profiler().is_none() {
+            return self.clone();
+        }
+
+        let map = self
+            .map
+            .iter()
+            .map(|((module, name), ext)| {
+                let ext = match ext {
+                    Extern::Function(f) => Extern::Function(f.profiled(store, module, name)),
+                    other => other.clone(),
+                };
+                ((module.clone(), name.clone()), ext)
+            })
+            .collect();
+
+        Self { map }
+    }
+
     /// Returns true if the Imports contains namespace with the provided name.
     pub fn contains_namespace(&self, name: &str) -> bool {
         self.map.keys().any(|(k, _)| (k == name))
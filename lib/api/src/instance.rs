@@ -1,7 +1,8 @@
 use crate::exports::Exports;
 use crate::module::Module;
-use crate::{Extern, InstantiationError};
+use crate::{Extern, Global, InstantiationError};
 use std::fmt;
+use wasmer_types::{ExportIndex, GlobalIndex};
 
 use crate::imports::Imports;
 use crate::store::AsStoreMut;
@@ -104,6 +105,26 @@ impl Instance {
     pub fn module(&self) -> &Module {
         &self.module
     }
+
+    /// Gets the global at `index` in the index space of the module, where
+    /// the imported globals come first, whether it is exported or not.
+    ///
+    /// Only the `sys` backend gives access to the globals that aren't
+    /// exported: `None` is returned for them with the other backends, as
+    /// for indices that are out of bounds.
+    pub fn global(&self, store: &mut impl AsStoreMut, index: u32) -> Option<Global> {
+        let index = GlobalIndex::from_u32(index);
+        if let Some(global) = self._inner.global(store, index) {
+            return Some(global);
+        }
+        let (name, _) = self
+            .module
+            .info()
+            .exports
+            .iter()
+            .find(|(_, export)| **export == ExportIndex::Global(index))?;
+        self.exports.get_global(name).ok().cloned()
+    }
 }
 
 impl fmt::Debug for Instance {
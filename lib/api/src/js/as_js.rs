@@ -47,7 +47,7 @@ pub fn param_from_js(ty: &Type, js_val: &JsValue) -> Value {
             let big_num: u128 = js_sys::BigInt::from(js_val.clone()).try_into().unwrap();
             Value::V128(big_num)
         }
-        Type::ExternRef | Type::FuncRef => unimplemented!(
+        Type::ExternRef | Type::FuncRef | Type::ExceptionRef => unimplemented!(
             "The type `{:?}` is not yet supported in the JS Function API",
             ty
         ),
@@ -67,6 +67,8 @@ impl AsJs for Value {
             Self::FuncRef(Some(func)) => func.0.handle.function.clone().into(),
             Self::FuncRef(None) => JsValue::null(),
             Self::ExternRef(_) => unimplemented!(),
+            Self::ExceptionRef(Some(exception)) => match exception.0 {},
+            Self::ExceptionRef(None) => JsValue::null(),
         }
     }
 
@@ -169,6 +171,8 @@ impl AsJs for Extern {
             Self::Function(function) => function.0.handle.function.clone().into(),
             Self::Table(table) => table.0.handle.table.clone().into(),
             Self::Global(global) => global.0.handle.global.clone().into(),
+            // Tags can't be handed to the engine, so importing one fails to link
+            Self::Tag(_) => JsValue::undefined(),
         }
     }
 
@@ -194,6 +198,7 @@ impl AsJs for Extern {
             ExternType::Table(table_type) => {
                 Ok(Self::Table(Table::from_jsvalue(store, table_type, val)?))
             }
+            ExternType::Tag(_) => Err(JsError::new("Tags are not yet supported in Javascript")),
         }
     }
 }
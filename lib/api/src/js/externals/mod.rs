@@ -3,3 +3,4 @@ pub(crate) mod global;
 pub(crate) mod memory;
 pub(crate) mod memory_view;
 pub(crate) mod table;
+pub(crate) mod tag;
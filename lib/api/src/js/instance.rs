@@ -4,8 +4,8 @@ use crate::js::as_js::AsJs;
 use crate::js::vm::VMInstance;
 use crate::module::Module;
 use crate::store::AsStoreMut;
-use crate::Extern;
 use crate::{errors::InstantiationError, js::js_handle::JsHandle};
+use crate::{Extern, ExternType};
 use js_sys::WebAssembly;
 
 #[derive(Clone, PartialEq, Eq)]
@@ -53,6 +53,8 @@ impl Instance {
 
         let exports = module
             .exports()
+            // Tags can't be handed over by the engine, so they are skipped
+            .filter(|export_type| !matches!(export_type.ty(), ExternType::Tag(_)))
             .map(|export_type| {
                 let name = export_type.name();
                 let extern_type = export_type.ty();
@@ -74,3 +76,14 @@ impl Instance {
         Ok((instance, exports))
     }
 }
+
+impl Instance {
+    /// Globals that aren't exported can't be accessed with this backend.
+    pub(crate) fn global(
+        &self,
+        _store: &mut impl AsStoreMut,
+        _index: wasmer_types::GlobalIndex,
+    ) -> Option<crate::Global> {
+        None
+    }
+}
@@ -26,6 +26,7 @@ mod lib {
 mod as_js;
 pub(crate) mod engine;
 pub(crate) mod errors;
+pub(crate) mod exception;
 pub(crate) mod extern_ref;
 pub(crate) mod externals;
 pub(crate) mod instance;
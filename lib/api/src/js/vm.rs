@@ -5,18 +5,19 @@
 //! once the type reflection is added to the WebAssembly JS API.
 //! https://github.com/WebAssembly/js-types/
 
-use crate::externals::{Extern, Function, Global, Memory, Table, VMExternToExtern};
+use crate::externals::{Extern, Function, Global, Memory, Table, Tag, VMExternToExtern};
 use crate::store::{AsStoreMut, AsStoreRef};
 use js_sys::{
     Function as JsFunction,
     WebAssembly::{self, Memory as JsMemory, Table as JsTable},
 };
 use serde::{Deserialize, Serialize};
-use std::{any::Any, fmt};
+use std::{any::Any, fmt, sync::Arc};
 use tracing::trace;
 use wasm_bindgen::{JsCast, JsValue};
 use wasmer_types::{
-    FunctionType, GlobalType, MemoryError, MemoryType, Pages, RawValue, TableType, WASM_PAGE_SIZE,
+    FunctionType, GlobalType, MemoryError, MemoryType, Pages, RawValue, TableType, TagType,
+    WASM_PAGE_SIZE,
 };
 
 use crate::js::{js_handle::JsHandle, wasm_bindgen_polyfill::Global as JsGlobal};
@@ -194,6 +195,28 @@ impl fmt::Debug for VMFunction {
     }
 }
 
+/// A tag created on the host.
+///
+/// Tags can't be handed to the JavaScript engine yet, so a module importing
+/// one fails to link, and tags exported by modules are left out of their
+/// exports.
+#[derive(Clone, Debug)]
+pub struct VMTag {
+    pub(crate) ty: Arc<TagType>,
+}
+
+impl VMTag {
+    pub(crate) fn new(ty: TagType) -> Self {
+        Self { ty: Arc::new(ty) }
+    }
+}
+
+impl PartialEq for VMTag {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ty, &other.ty)
+    }
+}
+
 /// The value of an export passed from one instance to another.
 pub enum VMExtern {
     /// A function export value.
@@ -207,6 +230,9 @@ pub enum VMExtern {
 
     /// A global export value.
     Global(VMGlobal),
+
+    /// A tag export value.
+    Tag(VMTag),
 }
 
 impl VMExternToExtern for VMExtern {
@@ -216,6 +242,7 @@ impl VMExternToExtern for VMExtern {
             Self::Memory(m) => Extern::Memory(Memory::from_vm_extern(store, m)),
             Self::Global(g) => Extern::Global(Global::from_vm_extern(store, g)),
             Self::Table(t) => Extern::Table(Table::from_vm_extern(store, t)),
+            Self::Tag(t) => Extern::Tag(Tag::from_vm_extern(store, t)),
         }
     }
 }
@@ -266,6 +293,25 @@ impl VMExternRef {
     }
 }
 
+/// Exceptions can't be thrown with this backend, so no `exnref` ever refers
+/// to one.
+pub(crate) enum VMExceptionRef {}
+
+impl VMExceptionRef {
+    /// Converts the `VMExceptionRef` into a `RawValue`.
+    pub fn into_raw(self) -> RawValue {
+        match self {}
+    }
+
+    /// Extracts a `VMExceptionRef` from a `RawValue`.
+    ///
+    /// # Safety
+    /// `raw` must be a valid `VMExceptionRef` instance.
+    pub unsafe fn from_raw(_raw: RawValue) -> Option<Self> {
+        None
+    }
+}
+
 #[repr(C)]
 pub struct VMFunctionBody(u8);
 
@@ -294,5 +340,6 @@ pub(crate) type VMExternTable = VMTable;
 pub(crate) type VMExternMemory = VMMemory;
 pub(crate) type VMExternGlobal = VMGlobal;
 pub(crate) type VMExternFunction = VMFunction;
+pub(crate) type VMExternTag = VMTag;
 
 pub type VMFunctionCallback = *const VMFunctionBody;
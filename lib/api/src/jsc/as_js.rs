@@ -58,7 +58,7 @@ pub fn param_from_js(context: &JSContext, ty: &Type, js_val: &JSValue) -> Value
             };
             Value::V128(number)
         }
-        Type::ExternRef | Type::FuncRef => unimplemented!(
+        Type::ExternRef | Type::FuncRef | Type::ExceptionRef => unimplemented!(
             "The type `{:?}` is not yet supported in the JS Function API",
             ty
         ),
@@ -84,6 +84,8 @@ impl AsJs for Value {
             Self::FuncRef(Some(func)) => func.0.handle.function.clone().to_jsvalue(),
             Self::FuncRef(None) => JSValue::null(&context),
             Self::ExternRef(_) => unimplemented!(),
+            Self::ExceptionRef(Some(exception)) => match exception.0 {},
+            Self::ExceptionRef(None) => JSValue::null(&context),
         }
     }
 
@@ -101,12 +103,17 @@ impl AsJs for Value {
 impl AsJs for Extern {
     type DefinitionType = ExternType;
 
-    fn as_jsvalue(&self, _store: &impl AsStoreRef) -> JSValue {
+    fn as_jsvalue(&self, store: &impl AsStoreRef) -> JSValue {
         match self {
             Self::Memory(memory) => memory.0.handle.memory.clone().to_jsvalue(),
             Self::Function(function) => function.0.handle.function.clone().to_jsvalue(),
             Self::Table(table) => table.0.handle.table.clone().to_jsvalue(),
             Self::Global(global) => global.0.handle.global.clone().to_jsvalue(),
+            // Tags can't be handed to the engine, so importing one fails to link
+            Self::Tag(_) => {
+                let engine = store.as_store_ref();
+                JSValue::undefined(&engine.jsc().context())
+            }
         }
     }
 
@@ -149,6 +156,10 @@ impl AsJs for Extern {
                     VMTable::new(obj_val, table_type.clone()),
                 )))
             }
+            ExternType::Tag(_) => Err(JSValue::string(
+                &context,
+                "Tags are not yet supported in JavaScriptCore".to_string(),
+            )),
         }
     }
 }
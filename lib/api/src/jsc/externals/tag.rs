@@ -0,0 +1,32 @@
+use crate::jsc::vm::{VMExtern, VMExternTag, VMTag};
+use crate::store::{AsStoreMut, AsStoreRef};
+use crate::TagType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub(crate) handle: VMTag,
+}
+
+impl Tag {
+    pub fn new(_store: &mut impl AsStoreMut, ty: TagType) -> Self {
+        Self {
+            handle: VMTag::new(ty),
+        }
+    }
+
+    pub fn ty(&self, _store: &impl AsStoreRef) -> TagType {
+        (*self.handle.ty).clone()
+    }
+
+    pub(crate) fn from_vm_extern(_store: &mut impl AsStoreMut, vm_extern: VMExternTag) -> Self {
+        Self { handle: vm_extern }
+    }
+
+    pub fn is_from_store(&self, _store: &impl AsStoreRef) -> bool {
+        true
+    }
+
+    pub(crate) fn to_vm_extern(&self) -> VMExtern {
+        VMExtern::Tag(self.handle.clone())
+    }
+}
@@ -6,7 +6,7 @@ use crate::jsc::engine::JSC;
 use crate::jsc::vm::VMInstance;
 use crate::module::Module;
 use crate::store::AsStoreMut;
-use crate::Extern;
+use crate::{Extern, ExternType};
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct Instance {
@@ -60,6 +60,8 @@ impl Instance {
 
         let exports = exports_ty
             .iter()
+            // Tags can't be handed over by the engine, so they are skipped
+            .filter(|export_type| !matches!(export_type.ty(), ExternType::Tag(_)))
             .map(|export_type| {
                 let name = export_type.name();
                 let mut store = store.as_store_mut();
@@ -75,3 +77,14 @@ impl Instance {
         Ok((Self { _handle: instance }, exports))
     }
 }
+
+impl Instance {
+    /// Globals that aren't exported can't be accessed with this backend.
+    pub(crate) fn global(
+        &self,
+        _store: &mut impl AsStoreMut,
+        _index: wasmer_types::GlobalIndex,
+    ) -> Option<crate::Global> {
+        None
+    }
+}
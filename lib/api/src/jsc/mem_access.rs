@@ -31,7 +31,7 @@ where
         };
         Ok(Self {
             slice,
-            buf: SliceCow::Borrowed(buf),
+            buf: SliceCow::Borrowed(buf, false),
         })
     }
 }
@@ -62,7 +62,7 @@ where
         };
         Ok(Self {
             ptr,
-            buf: RefCow::Borrowed(val),
+            buf: RefCow::Borrowed(val, false),
         })
     }
 }
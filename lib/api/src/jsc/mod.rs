@@ -28,6 +28,7 @@ mod lib {
 pub(crate) mod as_js;
 pub(crate) mod engine;
 pub(crate) mod errors;
+pub(crate) mod exception;
 pub(crate) mod extern_ref;
 pub(crate) mod externals;
 pub(crate) mod instance;
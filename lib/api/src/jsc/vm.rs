@@ -5,15 +5,16 @@
 //! once the type reflection is added to the WebAssembly JS API.
 //! https://github.com/WebAssembly/js-types/
 
-use crate::externals::{Extern, Function, Global, Memory, Table, VMExternToExtern};
+use crate::externals::{Extern, Function, Global, Memory, Table, Tag, VMExternToExtern};
 use crate::store::{AsStoreMut, AsStoreRef};
 use rusty_jsc::{JSObject, JSObjectCallAsFunctionCallback, JSValue};
 use std::any::Any;
 use std::fmt;
+use std::sync::Arc;
 use tracing::trace;
 use wasmer_types::RawValue;
 use wasmer_types::{
-    FunctionType, GlobalType, MemoryError, MemoryType, Pages, TableType, WASM_PAGE_SIZE,
+    FunctionType, GlobalType, MemoryError, MemoryType, Pages, TableType, TagType, WASM_PAGE_SIZE,
 };
 
 /// Represents linear memory that is managed by the javascript runtime
@@ -173,6 +174,28 @@ impl fmt::Debug for VMFunction {
     }
 }
 
+/// A tag created on the host.
+///
+/// Tags can't be handed to the JavaScriptCore engine yet, so a module importing
+/// one fails to link, and tags exported by modules are left out of their
+/// exports.
+#[derive(Clone, Debug)]
+pub struct VMTag {
+    pub(crate) ty: Arc<TagType>,
+}
+
+impl VMTag {
+    pub(crate) fn new(ty: TagType) -> Self {
+        Self { ty: Arc::new(ty) }
+    }
+}
+
+impl PartialEq for VMTag {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ty, &other.ty)
+    }
+}
+
 /// The value of an export passed from one instance to another.
 pub enum VMExtern {
     /// A function export value.
@@ -186,6 +209,9 @@ pub enum VMExtern {
 
     /// A global export value.
     Global(VMGlobal),
+
+    /// A tag export value.
+    Tag(VMTag),
 }
 
 impl VMExternToExtern for VMExtern {
@@ -195,6 +221,7 @@ impl VMExternToExtern for VMExtern {
             Self::Memory(m) => Extern::Memory(Memory::from_vm_extern(store, m)),
             Self::Global(g) => Extern::Global(Global::from_vm_extern(store, g)),
             Self::Table(t) => Extern::Table(Table::from_vm_extern(store, t)),
+            Self::Tag(t) => Extern::Tag(Tag::from_vm_extern(store, t)),
         }
     }
 }
@@ -245,6 +272,25 @@ impl VMExternRef {
     }
 }
 
+/// Exceptions can't be thrown with this backend, so no `exnref` ever refers
+/// to one.
+pub(crate) enum VMExceptionRef {}
+
+impl VMExceptionRef {
+    /// Converts the `VMExceptionRef` into a `RawValue`.
+    pub fn into_raw(self) -> RawValue {
+        match self {}
+    }
+
+    /// Extracts a `VMExceptionRef` from a `RawValue`.
+    ///
+    /// # Safety
+    /// `raw` must be a valid `VMExceptionRef` instance.
+    pub unsafe fn from_raw(_raw: RawValue) -> Option<Self> {
+        None
+    }
+}
+
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct VMFuncRef;
@@ -270,5 +316,6 @@ pub(crate) type VMExternTable = VMTable;
 pub(crate) type VMExternMemory = VMMemory;
 pub(crate) type VMExternGlobal = VMGlobal;
 pub(crate) type VMExternFunction = VMFunction;
+pub(crate) type VMExternTag = VMTag;
 
 pub type VMFunctionCallback = JSObjectCallAsFunctionCallback;
@@ -299,7 +299,11 @@
 //! - `compilation`
 #![cfg_attr(feature = "compiler", doc = "(enabled),")]
 #![cfg_attr(not(feature = "compiler"), doc = "(disabled),")]
-//!   enables compilation with the wasmer engine.
+//!   enables compilation with the wasmer engine,
+//! - `component-model`
+#![cfg_attr(feature = "component-model", doc = "(enabled),")]
+#![cfg_attr(not(feature = "component-model"), doc = "(disabled),")]
+//!   enables the [`component`] module, to run WebAssembly components.
 //!
 #![cfg_attr(
     feature = "js",
@@ -440,10 +444,12 @@ compile_error!(
 mod access;
 mod engine;
 mod errors;
+mod exception;
 mod exports;
 mod extern_ref;
 mod externals;
 mod function_env;
+mod host_profiling;
 mod imports;
 mod instance;
 mod into_bytes;
@@ -456,6 +462,9 @@ mod typed_function;
 mod value;
 pub mod vm;
 
+#[cfg(feature = "component-model")]
+pub mod component;
+
 #[cfg(any(feature = "wasm-types-polyfill", feature = "jsc"))]
 mod module_info_polyfill;
 
@@ -489,14 +498,17 @@ mod c_api;
 pub use c_api::*;
 
 pub use crate::externals::{
-    Extern, Function, Global, HostFunction, Memory, MemoryLocation, MemoryView, SharedMemory, Table,
+    Extern, Function, Global, HostFunction, Memory, MemoryLocation, MemoryView, SharedMemory,
+    Table, Tag,
 };
 pub use access::WasmSliceAccess;
 pub use engine::{AsEngineRef, Engine, EngineRef};
 pub use errors::{AtomicsError, InstantiationError, LinkError, RuntimeError};
+pub use exception::Exception;
 pub use exports::{ExportError, Exportable, Exports, ExportsIterator};
 pub use extern_ref::ExternRef;
 pub use function_env::{FunctionEnv, FunctionEnvMut};
+pub use host_profiling::{HostCallStats, HostProfile, HostProfiler};
 pub use imports::Imports;
 pub use instance::Instance;
 pub use into_bytes::IntoBytes;
@@ -508,7 +520,10 @@ pub use store::{
     AsStoreMut, AsStoreRef, OnCalledHandler, Store, StoreId, StoreMut, StoreObjects, StoreRef,
 };
 #[cfg(feature = "sys")]
-pub use store::{TrapHandlerFn, Tunables};
+pub use store::{
+    ResourceLimitExceeded, ResourceLimiter, StoreLimits, StoreLimitsBuilder, TrapHandlerFn,
+    Tunables,
+};
 #[cfg(any(feature = "sys", feature = "jsc", feature = "wasm-c-api"))]
 pub use target_lexicon::{Architecture, CallingConvention, OperatingSystem, Triple, HOST};
 pub use typed_function::TypedFunction;
@@ -526,7 +541,7 @@ pub use wasmer_types::{
     is_wasm, Bytes, CompileError, DeserializeError, ExportIndex, ExportType, ExternType, FrameInfo,
     FunctionType, GlobalInit, GlobalType, ImportType, LocalFunctionIndex, MemoryError, MemoryType,
     MiddlewareError, Mutability, OnCalledAction, Pages, ParseCpuFeatureError, SerializeError,
-    TableType, Type, ValueType, WasmError, WasmResult, WASM_MAX_PAGES, WASM_MIN_PAGES,
+    TableType, TagType, Type, ValueType, WasmError, WasmResult, WASM_MAX_PAGES, WASM_MIN_PAGES,
     WASM_PAGE_SIZE,
 };
 #[cfg(feature = "wat")]
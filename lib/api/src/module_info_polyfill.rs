@@ -323,6 +323,8 @@ pub fn wpreftype_to_type(ty: wasmparser::RefType) -> WasmResult<Type> {
         Ok(Type::ExternRef)
     } else if ty.is_func_ref() {
         Ok(Type::FuncRef)
+    } else if ty == wasmparser::RefType::EXNREF {
+        Ok(Type::ExceptionRef)
     } else {
         Err(format!("Unsupported ref type: {:?}", ty))
     }
@@ -1,6 +1,9 @@
 use crate::engine::{AsEngineRef, Engine, EngineRef};
+use crate::host_profiling::HostProfiler;
 #[cfg(feature = "sys")]
 use crate::sys::NativeStoreExt;
+#[cfg(feature = "sys")]
+use std::sync::Arc;
 use std::{
     fmt,
     ops::{Deref, DerefMut},
@@ -10,6 +13,8 @@ pub use wasmer_compiler::Tunables;
 pub use wasmer_types::{OnCalledAction, StoreId};
 #[cfg(feature = "sys")]
 pub use wasmer_vm::TrapHandlerFn;
+#[cfg(feature = "sys")]
+pub use wasmer_vm::{ResourceLimitExceeded, ResourceLimiter, StoreLimits, StoreLimitsBuilder};
 
 #[cfg(feature = "sys")]
 pub use wasmer_vm::{StoreHandle, StoreObjects};
@@ -48,6 +53,7 @@ pub(crate) struct StoreInner {
     pub(crate) objects: StoreObjects,
     pub(crate) store: store_imp::Store,
     pub(crate) on_called: Option<OnCalledHandler>,
+    pub(crate) host_profiler: Option<HostProfiler>,
 }
 
 impl std::fmt::Debug for StoreInner {
@@ -56,6 +62,7 @@ impl std::fmt::Debug for StoreInner {
             .field("objects", &self.objects)
             .field("store", &self.store)
             .field("on_called", &"<...>")
+            .field("host_profiler", &self.host_profiler)
             .finish()
     }
 }
@@ -76,11 +83,19 @@ pub struct Store {
 impl Store {
     /// Creates a new `Store` with a specific [`Engine`].
     pub fn new(engine: impl Into<Engine>) -> Self {
+        let engine = engine.into();
+        #[allow(unused_mut)]
+        let mut objects = StoreObjects::default();
+        #[cfg(feature = "sys")]
+        objects
+            .epoch_state_mut()
+            .set_counter(engine.0.epoch_counter());
         Self {
             inner: Box::new(StoreInner {
-                objects: Default::default(),
-                store: store_imp::Store::new(engine.into()),
+                objects,
+                store: store_imp::Store::new(engine),
                 on_called: None,
+                host_profiler: None,
             }),
         }
     }
@@ -91,6 +106,68 @@ impl Store {
         self.inner.store.set_trap_handler(handler)
     }
 
+    #[cfg(feature = "sys")]
+    /// Sets the epoch deadline of this store to `delta` epochs after the
+    /// current epoch of the engine.
+    ///
+    /// The deadline is only checked by code compiled with epoch
+    /// interruption enabled. By default there is no deadline.
+    pub fn set_epoch_deadline(&mut self, delta: u64) {
+        self.as_store_mut().set_epoch_deadline(delta)
+    }
+
+    #[cfg(feature = "sys")]
+    /// Makes the WebAssembly code trap when the epoch deadline is reached.
+    ///
+    /// This is the default behavior.
+    pub fn epoch_deadline_trap(&mut self) {
+        self.as_store_mut().epoch_deadline_trap()
+    }
+
+    #[cfg(feature = "sys")]
+    /// Calls `callback` when the epoch deadline is reached.
+    ///
+    /// The callback returns the number of epochs by which the deadline is
+    /// extended, or an error which is raised as a trap.
+    pub fn epoch_deadline_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(StoreMut<'_>) -> Result<u64, Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.as_store_mut().epoch_deadline_callback(callback)
+    }
+
+    #[cfg(feature = "sys")]
+    /// Sets the [`ResourceLimiter`] consulted when the memories and tables
+    /// of this store grow, and when instances are created in it.
+    ///
+    /// The limiter only applies to the memories and tables grown and the
+    /// instances created after it is set.
+    pub fn set_resource_limiter(&mut self, limiter: impl ResourceLimiter + 'static) {
+        self.as_store_mut().set_resource_limiter(limiter)
+    }
+
+    #[cfg(feature = "sys")]
+    /// Returns the [`ResourceLimiter`] of this store, if any.
+    pub fn resource_limiter(&self) -> Option<Arc<dyn ResourceLimiter>> {
+        self.inner.objects.resource_limiter().cloned()
+    }
+
+    /// Sets the [`HostProfiler`] that records the calls made to the
+    /// profiled host functions of this store, or removes it.
+    ///
+    /// See [`Imports::profiled`](crate::Imports::profiled).
+    pub fn set_host_profiler(&mut self, profiler: Option<HostProfiler>) {
+        self.as_store_mut().set_host_profiler(profiler)
+    }
+
+    /// Returns the [`HostProfiler`] of this store, if any.
+    pub fn host_profiler(&self) -> Option<&HostProfiler> {
+        self.inner.host_profiler.as_ref()
+    }
+
     /// Returns the [`Engine`].
     pub fn engine(&self) -> &Engine {
         self.inner.store.engine()
@@ -196,6 +273,11 @@ impl<'a> StoreRef<'a> {
         a.inner.objects.id() == b.inner.objects.id()
     }
 
+    /// Returns the [`HostProfiler`] of this store, if any.
+    pub fn host_profiler(&self) -> Option<&'a HostProfiler> {
+        self.inner.host_profiler.as_ref()
+    }
+
     /// The signal handler
     #[cfg(feature = "sys")]
     #[inline]
@@ -236,6 +318,110 @@ impl<'a> StoreMut<'a> {
         Self { inner: &mut *raw }
     }
 
+    #[cfg(feature = "sys")]
+    /// Sets the epoch deadline of this store to `delta` epochs after the
+    /// current epoch of the engine.
+    pub fn set_epoch_deadline(&mut self, delta: u64) {
+        self.inner.objects.epoch_state_mut().set_deadline(delta)
+    }
+
+    #[cfg(feature = "sys")]
+    /// Makes the WebAssembly code trap when the epoch deadline is reached.
+    pub fn epoch_deadline_trap(&mut self) {
+        self.inner.objects.epoch_state_mut().set_callback(None)
+    }
+
+    #[cfg(feature = "sys")]
+    /// Calls `callback` when the epoch deadline is reached.
+    ///
+    /// The callback returns the number of epochs by which the deadline is
+    /// extended, or an error which is raised as a trap.
+    pub fn epoch_deadline_callback<F>(&mut self, mut callback: F)
+    where
+        F: FnMut(StoreMut<'_>) -> Result<u64, Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        struct RawStore(*mut StoreInner);
+        // The callback is only invoked by code running on the thread that
+        // currently owns the store.
+        unsafe impl Send for RawStore {}
+        unsafe impl Sync for RawStore {}
+        impl RawStore {
+            fn get(&self) -> *mut StoreInner {
+                self.0
+            }
+        }
+
+        let raw = RawStore(self.as_raw());
+        self.inner
+            .objects
+            .epoch_state_mut()
+            .set_callback(Some(Box::new(move || {
+                let store = unsafe { StoreMut::from_raw(raw.get()) };
+                callback(store)
+            })));
+    }
+
+    #[cfg(feature = "sys")]
+    /// Sets the [`ResourceLimiter`] consulted when the memories and tables
+    /// of this store grow, and when instances are created in it.
+    pub fn set_resource_limiter(&mut self, limiter: impl ResourceLimiter + 'static) {
+        self.set_shared_resource_limiter(Some(Arc::new(limiter)))
+    }
+
+    #[cfg(feature = "sys")]
+    /// Sets a [`ResourceLimiter`] that may be shared with other stores,
+    /// or removes the limiter of this store.
+    ///
+    /// Stores sharing a limiter share the totals it keeps track of, e.g.
+    /// the total memory of [`StoreLimits`].
+    pub fn set_shared_resource_limiter(&mut self, limiter: Option<Arc<dyn ResourceLimiter>>) {
+        self.inner.objects.set_resource_limiter(limiter)
+    }
+
+    #[cfg(feature = "sys")]
+    /// Returns the [`ResourceLimiter`] of this store, if any.
+    pub fn resource_limiter(&self) -> Option<Arc<dyn ResourceLimiter>> {
+        self.inner.objects.resource_limiter().cloned()
+    }
+
+    /// Makes this store share the resource limiter of `other`, if it has one.
+    ///
+    /// This applies the limits of a store to the stores of the threads it
+    /// spawns. Resource limiters are only supported by the `sys` backend,
+    /// elsewhere this does nothing.
+    #[allow(unused_variables)]
+    pub fn inherit_resource_limiter(&mut self, other: &impl AsStoreRef) {
+        #[cfg(feature = "sys")]
+        if let Some(limiter) = other.as_store_ref().objects().resource_limiter() {
+            self.inner
+                .objects
+                .set_resource_limiter(Some(limiter.clone()));
+        }
+    }
+
+    /// Sets the [`HostProfiler`] that records the calls made to the
+    /// profiled host functions of this store, or removes it.
+    pub fn set_host_profiler(&mut self, profiler: Option<HostProfiler>) {
+        self.inner.host_profiler = profiler;
+    }
+
+    /// Returns the [`HostProfiler`] of this store, if any.
+    pub fn host_profiler(&self) -> Option<&HostProfiler> {
+        self.inner.host_profiler.as_ref()
+    }
+
+    /// Makes this store share the host profiler of `other`, if it has one.
+    ///
+    /// This profiles the threads a store spawns along with the store itself.
+    pub fn inherit_host_profiler(&mut self, other: &impl AsStoreRef) {
+        if let Some(profiler) = other.as_store_ref().host_profiler() {
+            self.inner.host_profiler = Some(profiler.clone());
+        }
+    }
+
     // TODO: OnCalledAction is needed for asyncify. It will be refactored with https://github.com/wasmerio/wasmer/issues/3451
     /// Sets the unwind callback which will be invoked when the call finishes
     pub fn on_called<F>(&mut self, callback: F)
@@ -2,7 +2,8 @@ use std::{path::Path, sync::Arc};
 
 use shared_buffer::OwnedBuffer;
 pub use wasmer_compiler::{
-    types::target::Target, Artifact, BaseTunables, CompilerConfig, Engine, EngineBuilder, Tunables,
+    types::target::Target, Artifact, BaseTunables, CompilerConfig, Engine, EngineBuilder,
+    ProfilingAgent, Tunables,
 };
 #[cfg(feature = "compiler")]
 use wasmer_types::Features;
@@ -84,6 +85,25 @@ pub trait NativeEngineExt {
     /// Get a reference to attached Tunable of this engine
     fn tunables(&self) -> &dyn Tunables;
 
+    /// Increments the epoch of this engine.
+    ///
+    /// Stores whose epoch deadline is reached are interrupted the next
+    /// time the code compiled with epoch interruption checks it.
+    fn increment_epoch(&self);
+
+    /// Returns the current epoch of this engine.
+    fn current_epoch(&self) -> u64;
+
+    /// Sets the agent recording the compiled functions for profilers such
+    /// as `perf`, see [`ProfilingStrategy`](crate::sys::ProfilingStrategy).
+    fn set_profiler(&mut self, profiler: Option<Arc<dyn ProfilingAgent>>);
+
+    /// Sets whether the compiled functions are registered with the GDB JIT
+    /// interface, so that `gdb` and `lldb` can debug them.
+    ///
+    /// This has no effect unless the `gdb-jit` feature is enabled.
+    fn set_debug_info(&mut self, debug_info: bool);
+
     /// Load a serialized WebAssembly module from a memory mapped file and deserialize it.
     ///
     /// NOTE: you should almost always prefer [`Self::deserialize_from_mmapped_file`].
@@ -127,6 +147,22 @@ impl NativeEngineExt for crate::engine::Engine {
         self.0.tunables()
     }
 
+    fn increment_epoch(&self) {
+        self.0.increment_epoch()
+    }
+
+    fn current_epoch(&self) -> u64 {
+        self.0.current_epoch()
+    }
+
+    fn set_profiler(&mut self, profiler: Option<Arc<dyn ProfilingAgent>>) {
+        self.0.set_profiler(profiler)
+    }
+
+    fn set_debug_info(&mut self, debug_info: bool) {
+        self.0.set_debug_info(debug_info)
+    }
+
     unsafe fn deserialize_from_mmapped_file_unchecked(
         &self,
         file_ref: &Path,
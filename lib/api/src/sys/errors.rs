@@ -1,4 +1,4 @@
-use crate::{LinkError, RuntimeError};
+use crate::{AsStoreMut, Exception, LinkError, RuntimeError};
 use wasmer_vm::Trap;
 
 impl From<wasmer_compiler::LinkError> for LinkError {
@@ -22,3 +22,18 @@ impl From<Trap> for RuntimeError {
         Self::new_from_source(trap, wasm_trace, trap_code)
     }
 }
+
+impl RuntimeError {
+    /// Returns true if this error was caused by a WebAssembly exception
+    /// that was not caught by any WebAssembly frame.
+    pub fn is_exception(&self) -> bool {
+        self.inner.source.exception().is_some()
+    }
+
+    /// Returns the uncaught WebAssembly exception that caused this error,
+    /// if any.
+    pub fn to_exception(&self, store: &mut impl AsStoreMut) -> Option<Exception> {
+        let exnref = self.inner.source.exception()?;
+        Some(unsafe { Exception::from_vm_exceptionref(store, exnref) })
+    }
+}
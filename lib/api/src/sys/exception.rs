@@ -0,0 +1,51 @@
+use wasmer_vm::{StoreHandle, VMException, VMExceptionRef, VMTag};
+
+use crate::store::{AsStoreMut, AsStoreRef};
+use crate::{Tag, Value};
+
+#[derive(Debug, Clone)]
+#[repr(transparent)]
+pub struct Exception {
+    handle: StoreHandle<VMException>,
+}
+
+impl Exception {
+    pub fn tag(&self, store: &impl AsStoreRef) -> Tag {
+        let objects = store.as_store_ref().objects();
+        let handle = self.handle.get(objects).tag();
+        Tag(crate::sys::externals::tag::Tag::from_handle(unsafe {
+            StoreHandle::<VMTag>::from_internal(objects.id(), handle)
+        }))
+    }
+
+    pub fn payload(&self, store: &mut impl AsStoreMut) -> Vec<Value> {
+        let objects = store.as_store_ref().objects();
+        let exception = self.handle.get(objects);
+        let ty = exception.tag().get(objects).ty().clone();
+        let raw = exception.payload().to_vec();
+        ty.params()
+            .iter()
+            .zip(raw)
+            .map(|(ty, raw)| unsafe { Value::from_raw(store, *ty, raw) })
+            .collect()
+    }
+
+    pub(crate) fn vm_exceptionref(&self) -> VMExceptionRef {
+        VMExceptionRef(self.handle.internal_handle())
+    }
+
+    pub(crate) unsafe fn from_vm_exceptionref(
+        store: &mut impl AsStoreMut,
+        vm_exceptionref: VMExceptionRef,
+    ) -> Self {
+        // The host may hold on to the exception for as long as it wants.
+        store.objects_mut().hold_exception(vm_exceptionref);
+        Self {
+            handle: StoreHandle::from_internal(store.objects_mut().id(), vm_exceptionref.0),
+        }
+    }
+
+    pub fn is_from_store(&self, store: &impl AsStoreRef) -> bool {
+        self.handle.store_id() == store.as_store_ref().objects().id()
+    }
+}
@@ -2,15 +2,18 @@ use crate::externals::function::{HostFunction, WithEnv, WithoutEnv};
 use crate::native_type::{FromToNativeWasmType, IntoResult, NativeWasmTypeInto, WasmTypeList};
 use crate::store::{AsStoreMut, AsStoreRef, StoreInner, StoreMut};
 use crate::sys::engine::NativeEngineExt;
+use crate::sys::fiber::FiberCall;
 use crate::vm::{VMExternFunction, VMFunctionCallback};
 use crate::{FunctionEnv, FunctionEnvMut, FunctionType, RuntimeError, Value};
+use std::future::Future;
 use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
 use std::{cell::UnsafeCell, cmp::max, ffi::c_void};
 use wasmer_types::{NativeWasmType, RawValue};
 use wasmer_vm::{
     on_host_stack, raise_user_trap, resume_panic, wasmer_call_trampoline, MaybeInstanceOwned,
-    StoreHandle, VMCallerCheckedAnyfunc, VMContext, VMDynamicFunctionContext, VMExtern, VMFuncRef,
-    VMFunction, VMFunctionContext, VMFunctionKind, VMTrampoline,
+    StoreHandle, Trap, VMCallerCheckedAnyfunc, VMContext, VMDynamicFunctionContext, VMExtern,
+    VMFuncRef, VMFunction, VMFunctionContext, VMFunctionKind, VMTrampoline,
 };
 
 #[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
@@ -78,6 +81,15 @@ impl Function {
             }
             Ok(())
         };
+        Self::new_dynamic(store, function_type, wrapper)
+    }
+
+    /// Creates a new host `Function` from a wrapper reading its arguments
+    /// from, and writing its results to, a slice of raw values.
+    fn new_dynamic<F>(store: &mut impl AsStoreMut, function_type: FunctionType, wrapper: F) -> Self
+    where
+        F: Fn(*mut RawValue) -> Result<(), RuntimeError> + 'static,
+    {
         let mut host_data = Box::new(VMDynamicFunctionContext {
             address: std::ptr::null(),
             ctx: DynamicFunction { func: wrapper },
@@ -102,6 +114,7 @@ impl Function {
             type_index,
             vmctx,
             call_trampoline,
+            tail_func_ptr: std::ptr::null(),
         };
 
         let vm_function = VMFunction {
@@ -146,6 +159,7 @@ impl Function {
             type_index,
             vmctx,
             call_trampoline,
+            tail_func_ptr: std::ptr::null(),
         };
 
         let vm_function = VMFunction {
@@ -192,6 +206,7 @@ impl Function {
             type_index,
             vmctx,
             call_trampoline,
+            tail_func_ptr: std::ptr::null(),
         };
 
         let vm_function = VMFunction {
@@ -205,6 +220,72 @@ impl Function {
         }
     }
 
+    pub fn new_typed_async<F, Fut, Args, Rets, RetsAsResult>(
+        store: &mut impl AsStoreMut,
+        func: F,
+    ) -> Self
+    where
+        F: Fn(Args) -> Fut + 'static + Send + Sync,
+        Fut: Future<Output = RetsAsResult> + Send + 'static,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        RetsAsResult: IntoResult<Rets>,
+    {
+        let env = FunctionEnv::new(store, ());
+        Self::new_typed_async_with_env(store, &env, move |_env: FunctionEnvMut<()>, args| {
+            Box::pin(func(args))
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn new_typed_async_with_env<T: Send + 'static, F, Args, Rets, RetsAsResult>(
+        store: &mut impl AsStoreMut,
+        env: &FunctionEnv<T>,
+        func: F,
+    ) -> Self
+    where
+        F: for<'a> Fn(
+                FunctionEnvMut<'a, T>,
+                Args,
+            ) -> Pin<Box<dyn Future<Output = RetsAsResult> + Send + 'a>>
+            + 'static
+            + Send
+            + Sync,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        RetsAsResult: IntoResult<Rets>,
+    {
+        let function_type = FunctionType::new(Args::wasm_types(), Rets::wasm_types());
+        let func_env = env.clone();
+        let raw_store = store.as_store_mut().as_raw() as *mut u8;
+        let wrapper = move |values_vec: *mut RawValue| -> Result<(), RuntimeError> {
+            unsafe {
+                let mut store = StoreMut::from_raw(raw_store as *mut StoreInner);
+                let mut args = Args::empty_array();
+                let len = args.as_mut().len();
+                args.as_mut()
+                    .copy_from_slice(std::slice::from_raw_parts(values_vec, len));
+                let args = Args::from_array(&mut store, args);
+
+                let env = FunctionEnvMut {
+                    store_mut: StoreMut::from_raw(raw_store as *mut StoreInner),
+                    func_env: func_env.clone(),
+                };
+                let returns = wasmer_vm::block_on(func(env, args))
+                    .map_err(|err| RuntimeError::user(Box::new(err)))?
+                    .into_result()
+                    .map_err(|err| RuntimeError::user(Box::new(err)))?;
+
+                let mut returns = returns.into_array(&mut store);
+                for (i, ret) in returns.as_mut().iter().enumerate() {
+                    values_vec.add(i).write_unaligned(*ret);
+                }
+            }
+            Ok(())
+        };
+        Self::new_dynamic(store, function_type, wrapper)
+    }
+
     pub fn ty(&self, store: &impl AsStoreRef) -> FunctionType {
         self.handle
             .get(store.as_store_ref().objects())
@@ -218,6 +299,7 @@ impl Function {
         trampoline: VMTrampoline,
         params: &[Value],
         results: &mut [Value],
+        nested: bool,
     ) -> Result<(), RuntimeError> {
         let format_types_for_error_message = |items: &[Value]| {
             items
@@ -262,7 +344,7 @@ impl Function {
         }
 
         // Invoke the call
-        self.call_wasm_raw(store, trampoline, values_vec, results)?;
+        self.call_wasm_raw(store, trampoline, values_vec, results, nested)?;
         Ok(())
     }
 
@@ -272,12 +354,14 @@ impl Function {
         trampoline: VMTrampoline,
         mut params: Vec<RawValue>,
         results: &mut [Value],
+        nested: bool,
     ) -> Result<(), RuntimeError> {
         // Call the trampoline.
         let result = {
             let mut r;
             // TODO: This loop is needed for asyncify. It will be refactored with https://github.com/wasmerio/wasmer/issues/3451
             loop {
+                store.objects_mut().enter_wasm();
                 let storeref = store.as_store_ref();
                 let vm_function = self.handle.get(storeref.objects());
                 let config = storeref.engine().tunables().vmconfig();
@@ -291,6 +375,11 @@ impl Function {
                         params.as_mut_ptr() as *mut u8,
                     )
                 };
+                store.objects_mut().leave_wasm();
+                if nested {
+                    // The `on_called` callback belongs to the outermost call
+                    break;
+                }
                 let store_mut = store.as_store_mut();
                 if let Some(callback) = store_mut.inner.on_called.take() {
                     match callback(store_mut) {
@@ -310,9 +399,14 @@ impl Function {
             }
             r
         };
+        // An exception that was not caught by any Wasm frame surfaces here.
+        let exception = store.objects_mut().take_exception();
         if let Err(error) = result {
             return Err(error.into());
         }
+        if let Some(exnref) = exception {
+            return Err(Trap::uncaught_exception(exnref).into());
+        }
 
         // Load the return values out of `values_vec`.
         let signature = self.ty(store);
@@ -343,10 +437,40 @@ impl Function {
                 .call_trampoline
         };
         let mut results = vec![Value::null(); self.result_arity(store)];
-        self.call_wasm(store, trampoline, params, &mut results)?;
+        self.call_wasm(store, trampoline, params, &mut results, false)?;
         Ok(results.into_boxed_slice())
     }
 
+    /// Calls the function from within a host function, leaving the store's
+    /// `on_called` callback for the outermost call to run.
+    pub(crate) fn call_nested(
+        &self,
+        store: &mut impl AsStoreMut,
+        params: &[Value],
+    ) -> Result<Box<[Value]>, RuntimeError> {
+        let trampoline = unsafe {
+            self.handle
+                .get(store.as_store_ref().objects())
+                .anyfunc
+                .as_ptr()
+                .as_ref()
+                .call_trampoline
+        };
+        let mut results = vec![Value::null(); self.result_arity(store)];
+        self.call_wasm(store, trampoline, params, &mut results, true)?;
+        Ok(results.into_boxed_slice())
+    }
+
+    pub fn call_async<'a>(
+        &self,
+        store: &'a mut impl AsStoreMut,
+        params: &[Value],
+    ) -> FiberCall<'a, Box<[Value]>> {
+        let func = self.clone();
+        let params = params.to_vec();
+        FiberCall::new(store, move |store| func.call(store, &params))
+    }
+
     #[doc(hidden)]
     #[allow(missing_docs)]
     pub fn call_raw(
@@ -363,7 +487,7 @@ impl Function {
                 .call_trampoline
         };
         let mut results = vec![Value::null(); self.result_arity(store)];
-        self.call_wasm_raw(store, trampoline, params, &mut results)?;
+        self.call_wasm_raw(store, trampoline, params, &mut results, false)?;
         Ok(results.into_boxed_slice())
     }
 
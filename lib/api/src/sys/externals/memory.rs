@@ -53,7 +53,15 @@ impl Memory {
     where
         IntoPages: Into<Pages>,
     {
-        self.handle.get_mut(store.objects_mut()).grow(delta.into())
+        let objects = store.objects_mut();
+        assert_eq!(
+            self.handle.store_id(),
+            objects.id(),
+            "object used with the wrong context"
+        );
+        objects
+            .grow_memory(self.handle.internal_handle(), delta.into())
+            .unwrap_or_else(|trap| Err(MemoryError::Generic(trap.to_string())))
     }
 
     pub fn grow_at_least(
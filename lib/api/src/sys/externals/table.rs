@@ -102,9 +102,15 @@ impl Table {
         init: Value,
     ) -> Result<u32, RuntimeError> {
         let item = value_to_table_element(store, init)?;
-        self.handle
-            .get_mut(store.objects_mut())
-            .grow(delta, item)
+        let objects = store.objects_mut();
+        assert_eq!(
+            self.handle.store_id(),
+            objects.id(),
+            "object used with the wrong context"
+        );
+        objects
+            .grow_table(self.handle.internal_handle(), delta, item)
+            .map_err(RuntimeError::from)?
             .ok_or_else(|| RuntimeError::new(format!("failed to grow table by `{}`", delta)))
     }
 
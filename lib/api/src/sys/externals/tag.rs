@@ -0,0 +1,50 @@
+use crate::store::{AsStoreMut, AsStoreRef};
+use crate::sys::vm::VMExternTag;
+use crate::TagType;
+use wasmer_vm::{StoreHandle, VMExtern, VMTag};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
+pub struct Tag {
+    handle: StoreHandle<VMTag>,
+}
+
+impl Tag {
+    pub fn new(store: &mut impl AsStoreMut, ty: TagType) -> Self {
+        Self {
+            handle: StoreHandle::new(store.objects_mut(), VMTag::new(ty)),
+        }
+    }
+
+    pub fn ty(&self, store: &impl AsStoreRef) -> TagType {
+        self.handle.get(store.as_store_ref().objects()).ty().clone()
+    }
+
+    pub(crate) fn from_handle(handle: StoreHandle<VMTag>) -> Self {
+        Self { handle }
+    }
+
+    pub(crate) fn from_vm_extern(store: &mut impl AsStoreMut, vm_extern: VMExternTag) -> Self {
+        Self {
+            handle: unsafe {
+                StoreHandle::from_internal(store.as_store_ref().objects().id(), vm_extern)
+            },
+        }
+    }
+
+    pub fn is_from_store(&self, store: &impl AsStoreRef) -> bool {
+        self.handle.store_id() == store.as_store_ref().objects().id()
+    }
+
+    pub(crate) fn to_vm_extern(&self) -> VMExtern {
+        VMExtern::Tag(self.handle.internal_handle())
+    }
+}
+
+impl std::cmp::PartialEq for Tag {
+    fn eq(&self, other: &Self) -> bool {
+        self.handle == other.handle
+    }
+}
+
+impl std::cmp::Eq for Tag {}
@@ -0,0 +1,60 @@
+//! Calls into WebAssembly running on an async fiber.
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use wasmer_vm::{AsyncFiber, DEFAULT_ASYNC_STACK_SIZE};
+
+use crate::store::{AsStoreMut, StoreInner, StoreMut};
+use crate::RuntimeError;
+use crate::Store;
+
+/// A future running a call into WebAssembly on an [`AsyncFiber`].
+///
+/// The fiber is suspended whenever an async host function waits on a
+/// pending future, and resumed when this future is polled again. The store
+/// stays mutably borrowed until the call completes.
+pub(crate) struct FiberCall<'a, R: 'static> {
+    fiber: AsyncFiber<Result<R, RuntimeError>>,
+    _store: PhantomData<&'a mut Store>,
+}
+
+impl<'a, R: 'static> FiberCall<'a, R> {
+    pub(crate) fn new<F>(store: &'a mut impl AsStoreMut, call: F) -> Self
+    where
+        F: FnOnce(&mut StoreMut<'_>) -> Result<R, RuntimeError> + Send + 'static,
+    {
+        struct RawStore(*mut StoreInner);
+        // The store is borrowed by the `FiberCall` for as long as the fiber
+        // runs, and it is only used from the fiber.
+        unsafe impl Send for RawStore {}
+        impl RawStore {
+            fn get(&self) -> *mut StoreInner {
+                self.0
+            }
+        }
+
+        let raw = RawStore(store.as_store_mut().as_raw());
+        let fiber = AsyncFiber::new(DEFAULT_ASYNC_STACK_SIZE, move || {
+            let mut store = unsafe { StoreMut::from_raw(raw.get()) };
+            call(&mut store)
+        });
+        Self {
+            fiber,
+            _store: PhantomData,
+        }
+    }
+}
+
+impl<R: 'static> Future for FiberCall<'_, R> {
+    type Output = Result<R, RuntimeError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut()
+            .fiber
+            .resume(cx)
+            .map(|result| result.map_err(RuntimeError::from).and_then(|result| result))
+    }
+}
@@ -1,11 +1,12 @@
 use crate::errors::InstantiationError;
 use crate::exports::Exports;
 use crate::module::Module;
+use wasmer_types::{ExportIndex, GlobalIndex};
 use wasmer_vm::{StoreHandle, VMInstance};
 
 use crate::imports::Imports;
 use crate::store::AsStoreMut;
-use crate::Extern;
+use crate::{Extern, Global};
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct Instance {
@@ -74,6 +75,18 @@ impl Instance {
         Ok((instance, exports))
     }
 
+    pub(crate) fn global(&self, store: &mut impl AsStoreMut, index: GlobalIndex) -> Option<Global> {
+        let handle = self._handle.get_mut(store.objects_mut());
+        if index.as_u32() as usize >= handle.module_ref().globals.len() {
+            return None;
+        }
+        let export = handle.lookup_by_declaration(ExportIndex::Global(index));
+        match Extern::from_vm_extern(store, export) {
+            Extern::Global(global) => Some(global),
+            _ => None,
+        }
+    }
+
     fn get_exports(
         store: &mut impl AsStoreMut,
         module: &Module,
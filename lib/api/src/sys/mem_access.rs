@@ -34,7 +34,7 @@ where
         };
         Ok(Self {
             slice,
-            buf: SliceCow::Borrowed(buf),
+            buf: SliceCow::Borrowed(buf, false),
         })
     }
 }
@@ -65,7 +65,7 @@ where
         };
         Ok(Self {
             ptr,
-            buf: RefCow::Borrowed(val),
+            buf: RefCow::Borrowed(val, false),
         })
     }
 }
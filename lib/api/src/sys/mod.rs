@@ -1,7 +1,9 @@
 pub(crate) mod engine;
 pub(crate) mod errors;
+pub(crate) mod exception;
 pub(crate) mod extern_ref;
 pub(crate) mod externals;
+pub(crate) mod fiber;
 pub(crate) mod instance;
 pub(crate) mod mem_access;
 pub(crate) mod module;
@@ -17,7 +19,10 @@ pub use crate::sys::tunables::BaseTunables;
 pub use wasmer_compiler::{
     wasmparser, CompilerConfig, FunctionMiddleware, MiddlewareReaderState, ModuleMiddleware,
 };
-pub use wasmer_compiler::{Artifact, EngineBuilder, Features, Tunables};
+pub use wasmer_compiler::{
+    Artifact, EngineBuilder, Features, JitDumpAgent, LineInfo, LineTable, PerfMapAgent,
+    ProfiledFunction, ProfilingAgent, ProfilingStrategy, Tunables,
+};
 #[cfg(feature = "cranelift")]
 pub use wasmer_compiler_cranelift::{Cranelift, CraneliftOptLevel};
 #[cfg(feature = "llvm")]
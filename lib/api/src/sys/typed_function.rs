@@ -4,6 +4,8 @@ use wasmer_types::RawValue;
 use crate::native_type::NativeWasmTypeInto;
 use crate::store::{AsStoreMut, AsStoreRef};
 use crate::sys::engine::NativeEngineExt;
+use crate::sys::fiber::FiberCall;
+use std::future::Future;
 
 macro_rules! impl_native_traits {
     (  $( $x:ident ),* ) => {
@@ -50,6 +52,7 @@ macro_rules! impl_native_traits {
 
                 let mut r;
                 loop {
+                    store.objects_mut().enter_wasm();
                     let storeref = store.as_store_ref();
                     let config = storeref.engine().tunables().vmconfig();
                     r = unsafe {
@@ -62,6 +65,7 @@ macro_rules! impl_native_traits {
                             args_rets.as_mut_ptr() as *mut u8,
                         )
                     };
+                    store.objects_mut().leave_wasm();
                     let store_mut = store.as_store_mut();
                     if let Some(callback) = store_mut.inner.on_called.take() {
                         match callback(store_mut) {
@@ -73,7 +77,11 @@ macro_rules! impl_native_traits {
                     }
                     break;
                 }
+                let exception = store.objects_mut().take_exception();
                 r?;
+                if let Some(exnref) = exception {
+                    return Err(wasmer_vm::Trap::uncaught_exception(exnref).into());
+                }
 
                 let num_rets = rets_list.len();
                 if !using_rets_array && num_rets > 0 {
@@ -101,6 +109,28 @@ macro_rules! impl_native_traits {
                 // Ok(Rets::from_c_struct(results))
             }
 
+            /// Call the typed func asynchronously, on a separate fiber
+            /// stack. See [`crate::Function::call_async`].
+            #[allow(clippy::too_many_arguments)]
+            pub fn call_async<'a>(&self, store: &'a mut impl AsStoreMut, $( $x: $x, )* ) -> impl Future<Output = Result<Rets, RuntimeError>> + 'a
+            where
+                $( $x: 'static, )*
+                Rets: 'static,
+            {
+                // Ensure all parameters come from the same context.
+                let cross_store = $(!FromToNativeWasmType::is_from_store(&$x, store) ||)* false;
+                let params_list = vec![ $( $x.to_native().into_raw(store) ),* ];
+                let func = TypedFunction::<( $( $x ),* ), Rets>::new(store, self.func.clone());
+                FiberCall::new(store, move |store| {
+                    if cross_store {
+                        return Err(RuntimeError::new(
+                            "cross-`Store` values are not supported",
+                        ));
+                    }
+                    func.call_raw(store, params_list)
+                })
+            }
+
             #[doc(hidden)]
             #[allow(missing_docs)]
             #[allow(unused_mut)]
@@ -132,6 +162,7 @@ macro_rules! impl_native_traits {
 
                 let mut r;
                 loop {
+                    store.objects_mut().enter_wasm();
                     let storeref = store.as_store_ref();
                     let config = storeref.engine().tunables().vmconfig();
                     r = unsafe {
@@ -144,6 +175,7 @@ macro_rules! impl_native_traits {
                             args_rets.as_mut_ptr() as *mut u8,
                         )
                     };
+                    store.objects_mut().leave_wasm();
                     let store_mut = store.as_store_mut();
                     if let Some(callback) = store_mut.inner.on_called.take() {
                         // TODO: OnCalledAction is needed for asyncify. It will be refactored with https://github.com/wasmerio/wasmer/issues/3451
@@ -156,7 +188,11 @@ macro_rules! impl_native_traits {
                     }
                     break;
                 }
+                let exception = store.objects_mut().take_exception();
                 r?;
+                if let Some(exnref) = exception {
+                    return Err(wasmer_vm::Trap::uncaught_exception(exnref).into());
+                }
 
                 let num_rets = rets_list.len();
                 if !using_rets_array && num_rets > 0 {
@@ -1,16 +1,17 @@
 //! The `vm` module re-exports wasmer-vm types.
-use crate::externals::{Extern, Function, Global, Memory, Table, VMExternToExtern};
+use crate::externals::{Extern, Function, Global, Memory, Table, Tag, VMExternToExtern};
 use crate::store::AsStoreMut;
 use wasmer_vm::InternalStoreHandle;
 pub(crate) use wasmer_vm::{
-    VMExtern, VMExternRef, VMFuncRef, VMFunction, VMFunctionBody, VMFunctionEnvironment, VMGlobal,
-    VMInstance, VMMemory, VMTable, VMTrampoline,
+    VMExceptionRef, VMExtern, VMExternRef, VMFuncRef, VMFunction, VMFunctionBody,
+    VMFunctionEnvironment, VMGlobal, VMInstance, VMMemory, VMTable, VMTag, VMTrampoline,
 };
 
 pub(crate) type VMExternTable = InternalStoreHandle<VMTable>;
 pub(crate) type VMExternMemory = InternalStoreHandle<VMMemory>;
 pub(crate) type VMExternGlobal = InternalStoreHandle<VMGlobal>;
 pub(crate) type VMExternFunction = InternalStoreHandle<VMFunction>;
+pub(crate) type VMExternTag = InternalStoreHandle<VMTag>;
 
 pub type VMFunctionCallback = *const VMFunctionBody;
 
@@ -21,6 +22,7 @@ impl VMExternToExtern for VMExtern {
             Self::Memory(m) => Extern::Memory(Memory::from_vm_extern(store, m)),
             Self::Global(g) => Extern::Global(Global::from_vm_extern(store, g)),
             Self::Table(t) => Extern::Table(Table::from_vm_extern(store, t)),
+            Self::Tag(t) => Extern::Tag(Tag::from_vm_extern(store, t)),
         }
     }
 }
@@ -1,7 +1,7 @@
 use crate::store::AsStoreRef;
-use crate::vm::{VMExternRef, VMFuncRef};
-use crate::ExternRef;
+use crate::vm::{VMExceptionRef, VMExternRef, VMFuncRef};
 use crate::Function;
+use crate::{Exception, ExternRef};
 use std::convert::TryFrom;
 use std::fmt;
 use wasmer_types::Type;
@@ -38,6 +38,9 @@ pub enum Value {
     /// A first-class reference to a WebAssembly function.
     FuncRef(Option<Function>),
 
+    /// An `exnref` value referring to a thrown exception.
+    ExceptionRef(Option<Exception>),
+
     /// A 128-bit number
     V128(u128),
 }
@@ -81,6 +84,7 @@ impl Value {
             Self::F64(_) => Type::F64,
             Self::ExternRef(_) => Type::ExternRef,
             Self::FuncRef(_) => Type::FuncRef,
+            Self::ExceptionRef(_) => Type::ExceptionRef,
             Self::V128(_) => Type::V128,
         }
     }
@@ -98,6 +102,8 @@ impl Value {
             Self::FuncRef(None) => RawValue { funcref: 0 },
             Self::ExternRef(Some(ref e)) => e.vm_externref().into_raw(),
             Self::ExternRef(None) => RawValue { externref: 0 },
+            Self::ExceptionRef(Some(ref e)) => e.vm_exceptionref().into_raw(),
+            Self::ExceptionRef(None) => RawValue { externref: 0 },
         }
     }
 
@@ -118,6 +124,9 @@ impl Value {
             Type::ExternRef => Self::ExternRef(
                 VMExternRef::from_raw(raw).map(|e| ExternRef::from_vm_externref(store, e)),
             ),
+            Type::ExceptionRef => Self::ExceptionRef(
+                VMExceptionRef::from_raw(raw).map(|e| Exception::from_vm_exceptionref(store, e)),
+            ),
         }
     }
 
@@ -136,9 +145,11 @@ impl Value {
             | Self::F64(_)
             | Self::V128(_)
             | Self::ExternRef(None)
-            | Self::FuncRef(None) => true,
+            | Self::FuncRef(None)
+            | Self::ExceptionRef(None) => true,
             Self::ExternRef(Some(e)) => e.is_from_store(store),
             Self::FuncRef(Some(f)) => f.is_from_store(store),
+            Self::ExceptionRef(Some(e)) => e.is_from_store(store),
         }
     }
 
@@ -150,6 +161,7 @@ impl Value {
         (F64(f64) f64 unwrap_f64 *e)
         (ExternRef(&Option<ExternRef>) externref unwrap_externref e)
         (FuncRef(&Option<Function>) funcref unwrap_funcref e)
+        (ExceptionRef(&Option<Exception>) exnref unwrap_exnref e)
         (V128(u128) v128 unwrap_v128 *e)
     }
 }
@@ -165,6 +177,8 @@ impl fmt::Debug for Value {
             Self::ExternRef(Some(v)) => write!(f, "ExternRef({:?})", v),
             Self::FuncRef(None) => write!(f, "Null FuncRef"),
             Self::FuncRef(Some(v)) => write!(f, "FuncRef({:?})", v),
+            Self::ExceptionRef(None) => write!(f, "Null ExceptionRef"),
+            Self::ExceptionRef(Some(v)) => write!(f, "ExceptionRef({:?})", v),
             Self::V128(v) => write!(f, "V128({:?})", v),
         }
     }
@@ -182,6 +196,7 @@ impl fmt::Display for Value {
                 Self::F64(v) => v.to_string(),
                 Self::ExternRef(_) => "externref".to_string(),
                 Self::FuncRef(_) => "funcref".to_string(),
+                Self::ExceptionRef(_) => "exnref".to_string(),
                 Self::V128(v) => v.to_string(),
             }
         )
@@ -263,12 +278,25 @@ impl From<Option<ExternRef>> for Value {
     }
 }
 
+impl From<Exception> for Value {
+    fn from(val: Exception) -> Self {
+        Self::ExceptionRef(Some(val))
+    }
+}
+
+impl From<Option<Exception>> for Value {
+    fn from(val: Option<Exception>) -> Self {
+        Self::ExceptionRef(val)
+    }
+}
+
 const NOT_I32: &str = "Value is not of Wasm type i32";
 const NOT_I64: &str = "Value is not of Wasm type i64";
 const NOT_F32: &str = "Value is not of Wasm type f32";
 const NOT_F64: &str = "Value is not of Wasm type f64";
 const NOT_FUNCREF: &str = "Value is not of Wasm type funcref";
 const NOT_EXTERNREF: &str = "Value is not of Wasm type externref";
+const NOT_EXCEPTIONREF: &str = "Value is not of Wasm type exnref";
 
 impl TryFrom<Value> for i32 {
     type Error = &'static str;
@@ -340,6 +368,17 @@ impl TryFrom<Value> for Option<ExternRef> {
     }
 }
 
+impl TryFrom<Value> for Option<Exception> {
+    type Error = &'static str;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::ExceptionRef(e) => Ok(e),
+            _ => Err(NOT_EXCEPTIONREF),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
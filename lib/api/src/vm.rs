@@ -2,26 +2,26 @@
 
 #[cfg(feature = "js")]
 pub(crate) use crate::js::vm::{
-    VMExtern, VMExternFunction, VMExternGlobal, VMExternMemory, VMExternRef, VMExternTable,
-    VMFuncRef, VMFunctionCallback, VMFunctionEnvironment, VMInstance, VMTrampoline,
+    VMExceptionRef, VMExtern, VMExternFunction, VMExternGlobal, VMExternMemory, VMExternRef,
+    VMExternTable, VMFuncRef, VMFunctionCallback, VMFunctionEnvironment, VMInstance, VMTrampoline,
 };
 
 #[cfg(feature = "jsc")]
 pub(crate) use crate::jsc::vm::{
-    VMExtern, VMExternFunction, VMExternGlobal, VMExternMemory, VMExternRef, VMExternTable,
-    VMFuncRef, VMFunctionCallback, VMFunctionEnvironment, VMInstance, VMTrampoline,
+    VMExceptionRef, VMExtern, VMExternFunction, VMExternGlobal, VMExternMemory, VMExternRef,
+    VMExternTable, VMFuncRef, VMFunctionCallback, VMFunctionEnvironment, VMInstance, VMTrampoline,
 };
 
 #[cfg(feature = "wasm-c-api")]
 pub(crate) use crate::c_api::vm::{
-    VMExtern, VMExternFunction, VMExternGlobal, VMExternMemory, VMExternRef, VMExternTable,
-    VMFuncRef, VMFunctionCallback, VMFunctionEnvironment, VMInstance, VMTrampoline,
+    VMExceptionRef, VMExtern, VMExternFunction, VMExternGlobal, VMExternMemory, VMExternRef,
+    VMExternTable, VMFuncRef, VMFunctionCallback, VMFunctionEnvironment, VMInstance, VMTrampoline,
 };
 
 #[cfg(feature = "sys")]
 pub(crate) use crate::sys::vm::{
-    VMExtern, VMExternFunction, VMExternGlobal, VMExternMemory, VMExternRef, VMExternTable,
-    VMFuncRef, VMFunctionCallback, VMFunctionEnvironment, VMInstance, VMTrampoline,
+    VMExceptionRef, VMExtern, VMExternFunction, VMExternGlobal, VMExternMemory, VMExternRef,
+    VMExternTable, VMFuncRef, VMFunctionCallback, VMFunctionEnvironment, VMInstance, VMTrampoline,
 };
 
 #[cfg(feature = "js")]
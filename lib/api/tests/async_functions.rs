@@ -0,0 +1,217 @@
+#[cfg(all(feature = "sys", feature = "cranelift"))]
+mod sys {
+    use anyhow::Result;
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use wasmer::*;
+
+    const WAT: &str = r#"(module
+        (import "env" "sleep" (func $sleep (param i32) (result i32)))
+        (func (export "run") (param $n i32) (result i32)
+            (i32.add (call $sleep (local.get $n)) (i32.const 1)))
+    )"#;
+
+    /// A future that is pending `n` times before completing.
+    struct Pending(i32);
+
+    impl Future for Pending {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 <= 0 {
+                return Poll::Ready(());
+            }
+            self.0 -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Polls `future` to completion, returning its output and the number
+    /// of times it was pending.
+    fn run<F: Future>(future: F) -> (F::Output, usize) {
+        let waker = Arc::new(CountingWaker::default());
+        let task_waker = Waker::from(waker.clone());
+        let mut cx = Context::from_waker(&task_waker);
+        let mut future = pin!(future);
+        let mut pending = 0;
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => {
+                    assert_eq!(waker.0.load(Ordering::SeqCst), pending);
+                    return (output, pending);
+                }
+                Poll::Pending => pending += 1,
+            }
+        }
+    }
+
+    fn instance(store: &mut Store) -> Result<Instance> {
+        let module = Module::new(store, WAT)?;
+        let sleep = Function::new_typed_async(store, |n: i32| async move {
+            Pending(n).await;
+            n * 2
+        });
+        let imports = imports! {
+            "env" => {
+                "sleep" => sleep,
+            },
+        };
+        Ok(Instance::new(store, &module, &imports)?)
+    }
+
+    #[test]
+    fn typed_call_async() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instance(&mut store)?;
+        let run_fn: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "run")?;
+
+        let (result, pending) = run(run_fn.call_async(&mut store, 3));
+        assert_eq!(result?, 7);
+        assert_eq!(pending, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn dynamic_call_async() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instance(&mut store)?;
+        let run_fn = instance.exports.get_function("run")?;
+
+        let (result, pending) = run(run_fn.call_async(&mut store, &[Value::I32(2)]));
+        assert_eq!(&*result?, &[Value::I32(5)]);
+        assert_eq!(pending, 2);
+
+        // The store can be used again once the call completed.
+        let (result, _) = run(run_fn.call_async(&mut store, &[Value::I32(0)]));
+        assert_eq!(&*result?, &[Value::I32(1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn async_function_with_env() -> Result<()> {
+        let mut store = Store::default();
+        let env = FunctionEnv::new(&mut store, 0i32);
+        let module = Module::new(&store, WAT)?;
+        let sleep = Function::new_typed_async_with_env(
+            &mut store,
+            &env,
+            |mut env: FunctionEnvMut<i32>, n: i32| {
+                *env.data_mut() += n;
+                let total = *env.data();
+                Box::pin(async move {
+                    Pending(1).await;
+                    total
+                })
+            },
+        );
+        let imports = imports! {
+            "env" => {
+                "sleep" => sleep,
+            },
+        };
+        let instance = Instance::new(&mut store, &module, &imports)?;
+        let run_fn: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "run")?;
+
+        assert_eq!(run(run_fn.call_async(&mut store, 10)).0?, 11);
+        assert_eq!(run(run_fn.call_async(&mut store, 5)).0?, 16);
+        assert_eq!(*env.as_ref(&store), 15);
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct ExitCode(i32);
+
+    impl std::fmt::Display for ExitCode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "exit code {}", self.0)
+        }
+    }
+
+    impl std::error::Error for ExitCode {}
+
+    #[test]
+    fn async_function_error() -> Result<()> {
+        let mut store = Store::default();
+        let module = Module::new(&store, WAT)?;
+        let sleep = Function::new_typed_async(&mut store, |n: i32| async move {
+            Pending(1).await;
+            Err::<i32, _>(ExitCode(n))
+        });
+        let imports = imports! {
+            "env" => {
+                "sleep" => sleep,
+            },
+        };
+        let instance = Instance::new(&mut store, &module, &imports)?;
+        let run_fn: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "run")?;
+
+        let err = run(run_fn.call_async(&mut store, 42)).0.unwrap_err();
+        assert_eq!(err.downcast::<ExitCode>()?.0, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn async_function_in_sync_call() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instance(&mut store)?;
+        let run_fn: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "run")?;
+
+        let err = run_fn.call(&mut store, 1).unwrap_err();
+        assert!(err.message().contains("outside of an async call"));
+        Ok(())
+    }
+
+    #[test]
+    fn cancel_async_call() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instance(&mut store)?;
+        let run_fn: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "run")?;
+
+        {
+            let waker = Waker::from(Arc::new(CountingWaker::default()));
+            let mut cx = Context::from_waker(&waker);
+            let mut call = Box::pin(run_fn.call_async(&mut store, 100));
+            assert!(call.as_mut().poll(&mut cx).is_pending());
+            // Dropping the call unwinds the pending host function.
+        }
+
+        assert_eq!(run(run_fn.call_async(&mut store, 1)).0?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn resume_on_another_thread() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instance(&mut store)?;
+        let run_fn: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "run")?;
+
+        let waker = Waker::from(Arc::new(CountingWaker::default()));
+        let mut call = Box::pin(run_fn.call_async(&mut store, 4));
+        assert!(call
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+
+        let result = std::thread::scope(|scope| {
+            scope
+                .spawn(move || run(call).0)
+                .join()
+                .expect("thread panicked")
+        });
+        assert_eq!(result?, 9);
+        Ok(())
+    }
+}
@@ -0,0 +1,502 @@
+#[cfg(all(feature = "sys", feature = "cranelift", feature = "component-model"))]
+mod sys {
+    use anyhow::Result;
+    use wasmer::component::*;
+    use wasmer::{ExportError, FunctionEnv, FunctionEnvMut, RuntimeError, Store};
+
+    /// A bump allocator, shared by the components of these tests.
+    const LIBC: &str = r#"
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr
+                (i32.and
+                    (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+                    (i32.sub (i32.const 0) (local.get 2))))
+            (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+            (local.get $ptr))
+        (func (export "reset") (param i32)
+            (global.set $heap (i32.const 1024)))
+        (func (export "heap") (result i32)
+            (global.get $heap))
+    "#;
+
+    fn instantiate(store: &mut Store, wat: &str) -> Result<Instance> {
+        let component = Component::new(store, wat)?;
+        let env = FunctionEnv::new(store, ());
+        Ok(Linker::new(&env).instantiate(store, &component)?)
+    }
+
+    #[test]
+    fn primitives() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instantiate(
+            &mut store,
+            r#"(component
+                (core module $m
+                    (func (export "add") (param i64 i64) (result i64)
+                        (i64.add (local.get 0) (local.get 1)))
+                    (func (export "not") (param i32) (result i32)
+                        (i32.eqz (local.get 0)))
+                    (func (export "neg") (param i32) (result i32)
+                        (i32.sub (i32.const 0) (local.get 0)))
+                    (func (export "upper") (param i32) (result i32)
+                        (i32.sub (local.get 0) (i32.const 32)))
+                    (func (export "half") (param f64) (result f64)
+                        (f64.div (local.get 0) (f64.const 2))))
+                (core instance $i (instantiate $m))
+                (func (export "add") (param "a" u64) (param "b" u64) (result u64)
+                    (canon lift (core func $i "add")))
+                (func (export "not") (param "a" bool) (result bool)
+                    (canon lift (core func $i "not")))
+                (func (export "neg") (param "a" s8) (result s8)
+                    (canon lift (core func $i "neg")))
+                (func (export "upper") (param "c" char) (result char)
+                    (canon lift (core func $i "upper")))
+                (func (export "half") (param "x" float64) (result float64)
+                    (canon lift (core func $i "half")))
+            )"#,
+        )?;
+
+        let add = instance
+            .exports
+            .get_typed_func::<(u64, u64), (u64,)>("add")?;
+        assert_eq!(add.call(&mut store, (u64::MAX - 1, 1))?, (u64::MAX,));
+        let not = instance.exports.get_typed_func::<(bool,), (bool,)>("not")?;
+        assert_eq!(not.call(&mut store, (true,))?, (false,));
+        let neg = instance.exports.get_typed_func::<(i8,), (i8,)>("neg")?;
+        assert_eq!(neg.call(&mut store, (-5,))?, (5,));
+        let upper = instance
+            .exports
+            .get_typed_func::<(char,), (char,)>("upper")?;
+        assert_eq!(upper.call(&mut store, ('a',))?, ('A',));
+
+        let half = instance.exports.get_func("half")?;
+        assert_eq!(half.ty().to_string(), "func(x: f64) -> f64");
+        let results = half.call(&mut store, &[Val::Float64(5.0)])?;
+        assert_eq!(&*results, &[Val::Float64(2.5)]);
+        Ok(())
+    }
+
+    #[test]
+    fn strings_and_lists() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instantiate(
+            &mut store,
+            &format!(
+                r#"(component
+                (core module $m
+                    {LIBC}
+                    (func (export "echo") (param i32 i32) (result i32)
+                        (i32.store (i32.const 8) (local.get 0))
+                        (i32.store (i32.const 12) (local.get 1))
+                        (i32.const 8))
+                    (func (export "sum") (param $ptr i32) (param $len i32) (result i32)
+                        (local $sum i32)
+                        (block $done
+                            (loop $next
+                                (br_if $done (i32.eqz (local.get $len)))
+                                (local.set $sum
+                                    (i32.add (local.get $sum) (i32.load (local.get $ptr))))
+                                (local.set $ptr (i32.add (local.get $ptr) (i32.const 4)))
+                                (local.set $len (i32.sub (local.get $len) (i32.const 1)))
+                                (br $next)))
+                        (local.get $sum)))
+                (core instance $i (instantiate $m))
+                (func (export "echo") (param "s" string) (result string)
+                    (canon lift (core func $i "echo")
+                        (memory $i "memory") (realloc (func $i "realloc"))
+                        (post-return (func $i "reset"))))
+                (func (export "echo-bytes") (param "b" (list u8)) (result (list u8))
+                    (canon lift (core func $i "echo")
+                        (memory $i "memory") (realloc (func $i "realloc"))))
+                (func (export "sum") (param "l" (list u32)) (result u32)
+                    (canon lift (core func $i "sum")
+                        (memory $i "memory") (realloc (func $i "realloc"))))
+                (func (export "heap") (result u32)
+                    (canon lift (core func $i "heap")))
+            )"#
+            ),
+        )?;
+
+        let echo = instance
+            .exports
+            .get_typed_func::<(String,), (String,)>("echo")?;
+        let (s,) = echo.call(&mut store, ("héllo wörld".to_string(),))?;
+        assert_eq!(s, "héllo wörld");
+        // The post-return function freed the string.
+        let heap = instance.exports.get_typed_func::<(), (u32,)>("heap")?;
+        assert_eq!(heap.call(&mut store, ())?, (1024,));
+
+        let echo_bytes = instance
+            .exports
+            .get_typed_func::<(Vec<u8>,), (Vec<u8>,)>("echo-bytes")?;
+        assert_eq!(
+            echo_bytes.call(&mut store, (vec![1, 2, 3],))?,
+            (vec![1, 2, 3],)
+        );
+
+        let sum = instance
+            .exports
+            .get_typed_func::<(Vec<u32>,), (u32,)>("sum")?;
+        assert_eq!(sum.call(&mut store, ((1..=10).collect(),))?, (55,));
+        Ok(())
+    }
+
+    #[test]
+    fn records_and_variants() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instantiate(
+            &mut store,
+            r#"(component
+                (core module $m
+                    (memory (export "memory") 1)
+                    (func (export "swap") (param i32 i32) (result i32)
+                        (i32.store (i32.const 16) (local.get 1))
+                        (i32.store (i32.const 20) (local.get 0))
+                        (i32.const 16))
+                    (func (export "double") (param i32) (result i32)
+                        (i32.store8 (i32.const 32) (i32.ne (local.get 0) (i32.const 0)))
+                        (i32.store (i32.const 36) (i32.mul (local.get 0) (i32.const 2)))
+                        (i32.const 32))
+                    (func (export "unwrap-or") (param i32 i32 i32) (result i32)
+                        (select (local.get 1) (local.get 2) (local.get 0)))
+                    (func (export "flip") (param i32) (result i32)
+                        (i32.xor (local.get 0) (i32.const 7)))
+                    (func (export "id") (param i32 i64) (result i32)
+                        (i32.store8 (i32.const 48) (local.get 0))
+                        (if (i32.eqz (local.get 0))
+                            (then (i32.store (i32.const 56) (i32.wrap_i64 (local.get 1))))
+                            (else (i64.store (i32.const 56) (local.get 1))))
+                        (i32.const 48)))
+                (core instance $i (instantiate $m))
+                (type $point' (record (field "x" s32) (field "y" s32)))
+                (type $flags' (flags "a" "b" "c"))
+                (type $num' (variant (case "f" float32) (case "u" u64)))
+                (export $point "point" (type $point'))
+                (export $flags "flags" (type $flags'))
+                (export $num "num" (type $num'))
+                (func (export "swap") (param "p" $point) (result $point)
+                    (canon lift (core func $i "swap") (memory $i "memory")))
+                (func (export "double") (param "n" u32) (result (option u32))
+                    (canon lift (core func $i "double") (memory $i "memory")))
+                (func (export "unwrap-or") (param "o" (option u32)) (param "d" u32) (result u32)
+                    (canon lift (core func $i "unwrap-or")))
+                (func (export "flip") (param "f" $flags) (result $flags)
+                    (canon lift (core func $i "flip")))
+                (func (export "id") (param "n" $num) (result $num)
+                    (canon lift (core func $i "id") (memory $i "memory")))
+            )"#,
+        )?;
+
+        let point = |x, y| {
+            Val::Record(vec![
+                ("x".to_string(), Val::S32(x)),
+                ("y".to_string(), Val::S32(y)),
+            ])
+        };
+        let swap = instance.exports.get_func("swap")?;
+        assert_eq!(&*swap.call(&mut store, &[point(1, -2)])?, &[point(-2, 1)]);
+
+        let double = instance
+            .exports
+            .get_typed_func::<(u32,), (Option<u32>,)>("double")?;
+        assert_eq!(double.call(&mut store, (21,))?, (Some(42),));
+        assert_eq!(double.call(&mut store, (0,))?, (None,));
+
+        let unwrap_or = instance
+            .exports
+            .get_typed_func::<(Option<u32>, u32), (u32,)>("unwrap-or")?;
+        assert_eq!(unwrap_or.call(&mut store, (Some(1), 2))?, (1,));
+        assert_eq!(unwrap_or.call(&mut store, (None, 2))?, (2,));
+
+        let flip = instance.exports.get_func("flip")?;
+        let flags = |names: &[&str]| Val::Flags(names.iter().map(|s| s.to_string()).collect());
+        assert_eq!(
+            &*flip.call(&mut store, &[flags(&["b"])])?,
+            &[flags(&["a", "c"])]
+        );
+
+        let id = instance.exports.get_func("id")?;
+        for val in [
+            Val::Variant("f".to_string(), Some(Box::new(Val::Float32(1.5)))),
+            Val::Variant("u".to_string(), Some(Box::new(Val::U64(u64::MAX)))),
+        ] {
+            assert_eq!(&*id.call(&mut store, &[val.clone()])?, &[val]);
+        }
+
+        let err = swap.call(&mut store, &[Val::U32(1)]).unwrap_err();
+        assert!(err.message().contains("type mismatch"), "{}", err.message());
+        let err = id
+            .call(&mut store, &[Val::Variant("x".to_string(), None)])
+            .unwrap_err();
+        assert!(err.message().contains("unknown variant case"));
+        Ok(())
+    }
+
+    const HOST: &str = r#"(component
+        (import "host" (instance $host
+            (export "name" (func (result string)))
+            (export "log" (func (param "msg" string)))
+            (export "add" (func (param "a" u32) (param "b" u32) (result u32)))))
+        (core module $libc LIBC)
+        (core instance $libc (instantiate $libc))
+        (core func $name (canon lower (func $host "name")
+            (memory $libc "memory") (realloc (func $libc "realloc"))))
+        (core func $log (canon lower (func $host "log") (memory $libc "memory")))
+        (core func $add (canon lower (func $host "add")))
+        (core module $main
+            (import "libc" "memory" (memory 1))
+            (import "host" "name" (func $name (param i32)))
+            (import "host" "log" (func $log (param i32 i32)))
+            (import "host" "add" (func $add (param i32 i32) (result i32)))
+            (func (export "run") (result i32)
+                (call $name (i32.const 0))
+                (call $log (i32.load (i32.const 0)) (i32.load (i32.const 4)))
+                (call $add (i32.load (i32.const 4)) (i32.const 100))))
+        (core instance $main (instantiate $main
+            (with "libc" (instance $libc))
+            (with "host" (instance
+                (export "name" (func $name))
+                (export "log" (func $log))
+                (export "add" (func $add))))))
+        (func (export "run") (result u32) (canon lift (core func $main "run")))
+    )"#;
+
+    #[test]
+    fn host_functions() -> Result<()> {
+        let mut store = Store::default();
+        let component = Component::new(&store, HOST.replace("LIBC", LIBC))?;
+        let env = FunctionEnv::new(&mut store, Vec::<String>::new());
+        let mut linker = Linker::new(&env);
+        linker
+            .instance("host")
+            .func_new("name", |_env, _params| {
+                Ok(vec![Val::String("world!".to_string())])
+            })
+            .func_wrap(
+                "log",
+                |mut env: FunctionEnvMut<Vec<String>>, (msg,): (String,)| {
+                    env.data_mut().push(msg);
+                    Ok(())
+                },
+            )
+            .func_wrap("add", |_env, (a, b): (u32, u32)| Ok((a + b,)));
+
+        let instance = linker.instantiate(&mut store, &component)?;
+        let run = instance.exports.get_typed_func::<(), (u32,)>("run")?;
+        assert_eq!(run.call(&mut store, ())?, (106,));
+        assert_eq!(env.as_ref(&store), &["world!"]);
+        Ok(())
+    }
+
+    #[test]
+    fn host_function_errors() -> Result<()> {
+        let mut store = Store::default();
+        let component = Component::new(&store, HOST.replace("LIBC", LIBC))?;
+        let env = FunctionEnv::new(&mut store, ());
+        let mut linker = Linker::new(&env);
+        linker
+            .instance("host")
+            .func_new("name", |_env, _params| Ok(vec![Val::U32(1)]))
+            .func_new("log", |_env, _params| Ok(vec![]));
+
+        let err = linker.instantiate(&mut store, &component).unwrap_err();
+        assert!(matches!(err, InstantiationError::MissingImport(ref name) if name == "host/add"));
+
+        linker
+            .instance("host")
+            .func_wrap("add", |_env, (a,): (u64,)| Ok((a,)));
+        let err = linker.instantiate(&mut store, &component).unwrap_err();
+        assert!(
+            matches!(err, InstantiationError::IncompatibleImport(ref name, _) if name == "host/add")
+        );
+
+        linker
+            .instance("host")
+            .func_wrap("add", |_env, (a, b): (u32, u32)| Ok((a + b,)));
+        let instance = linker.instantiate(&mut store, &component)?;
+        let run = instance.exports.get_func("run")?;
+        // `name` returns a value of the wrong type.
+        let err = run.call(&mut store, &[]).unwrap_err();
+        assert!(err.message().contains("type mismatch"), "{}", err.message());
+
+        assert!(matches!(
+            instance.exports.get_typed_func::<(u32,), (u32,)>("run"),
+            Err(ExportError::IncompatibleType)
+        ));
+        assert!(matches!(
+            instance.exports.get_func("missing"),
+            Err(ExportError::Missing(_))
+        ));
+        Ok(())
+    }
+
+    struct Counter(u32);
+
+    #[derive(Default)]
+    struct Counters {
+        table: ResourceTable,
+        dropped: Vec<u32>,
+    }
+
+    #[test]
+    fn host_resources() -> Result<()> {
+        let mut store = Store::default();
+        let component = Component::new(
+            &store,
+            r#"(component
+                (import "host" (instance $host
+                    (export "counter" (type (sub resource)))
+                    (export "[constructor]counter" (func (param "init" u32) (result (own 0))))
+                    (export "[method]counter.inc" (func (param "self" (borrow 0)) (result u32)))))
+                (alias export $host "counter" (type $counter))
+                (core func $new (canon lower (func $host "[constructor]counter")))
+                (core func $inc (canon lower (func $host "[method]counter.inc")))
+                (core func $drop (canon resource.drop $counter))
+                (core module $m
+                    (import "" "new" (func $new (param i32) (result i32)))
+                    (import "" "inc" (func $inc (param i32) (result i32)))
+                    (import "" "drop" (func $drop (param i32)))
+                    (func (export "run") (result i32)
+                        (local $h i32)
+                        (local.set $h (call $new (i32.const 10)))
+                        (drop (call $inc (local.get $h)))
+                        (call $inc (local.get $h))
+                        (call $drop (local.get $h)))
+                    (func (export "peek") (param i32) (result i32)
+                        (call $inc (local.get 0))))
+                (core instance $i (instantiate $m
+                    (with "" (instance
+                        (export "new" (func $new))
+                        (export "inc" (func $inc))
+                        (export "drop" (func $drop))))))
+                (func (export "run") (result u32) (canon lift (core func $i "run")))
+                (func (export "peek") (param "c" (borrow $counter)) (result u32)
+                    (canon lift (core func $i "peek")))
+            )"#,
+        )?;
+
+        let env = FunctionEnv::new(&mut store, Counters::default());
+        let mut linker = Linker::new(&env);
+        linker
+            .instance("host")
+            .resource(
+                "counter",
+                ResourceType::host::<Counter>(),
+                |mut env: FunctionEnvMut<Counters>, rep| {
+                    let counters = env.data_mut();
+                    let Counter(value) = counters
+                        .table
+                        .delete(Resource::<Counter>::new_own(rep))
+                        .map_err(|err| RuntimeError::new(err.to_string()))?;
+                    counters.dropped.push(value);
+                    Ok(())
+                },
+            )
+            .func_wrap(
+                "[constructor]counter",
+                |mut env: FunctionEnvMut<Counters>, (init,): (u32,)| {
+                    Ok((env.data_mut().table.push(Counter(init)),))
+                },
+            )
+            .func_wrap(
+                "[method]counter.inc",
+                |mut env: FunctionEnvMut<Counters>, (counter,): (Resource<Counter>,)| {
+                    assert!(!counter.owned());
+                    let counter = env
+                        .data_mut()
+                        .table
+                        .get_mut(&counter)
+                        .map_err(|err| RuntimeError::new(err.to_string()))?;
+                    counter.0 += 1;
+                    Ok((counter.0,))
+                },
+            );
+
+        let instance = linker.instantiate(&mut store, &component)?;
+        let run = instance.exports.get_typed_func::<(), (u32,)>("run")?;
+        assert_eq!(run.call(&mut store, ())?, (12,));
+        assert_eq!(env.as_ref(&store).dropped, [12]);
+
+        // Lend a resource of the host to the component.
+        let counter = env.as_mut(&mut store).table.push(Counter(0));
+        let peek = instance
+            .exports
+            .get_typed_func::<(Resource<Counter>,), (u32,)>("peek")?;
+        let borrowed = Resource::new_borrow(counter.rep());
+        assert_eq!(peek.call(&mut store, (borrowed,))?, (1,));
+        assert!(env.as_ref(&store).table.get(&counter).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn guest_resources() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instantiate(
+            &mut store,
+            r#"(component
+                (core module $dtor
+                    (global $drops (mut i32) (i32.const 0))
+                    (func (export "dtor") (param i32)
+                        (global.set $drops (i32.add (global.get $drops) (i32.const 1))))
+                    (func (export "drops") (result i32) (global.get $drops)))
+                (core instance $dtor (instantiate $dtor))
+                (type $r (resource (rep i32) (dtor (func $dtor "dtor"))))
+                (core func $new (canon resource.new $r))
+                (core module $m
+                    (import "" "new" (func $new (param i32) (result i32)))
+                    (func (export "ctor") (param i32) (result i32)
+                        (call $new (local.get 0)))
+                    (func (export "get") (param i32) (result i32)
+                        (local.get 0)))
+                (core instance $i (instantiate $m
+                    (with "" (instance (export "new" (func $new))))))
+                (export $thing "thing" (type $r))
+                (func (export "[constructor]thing") (param "v" u32) (result (own $thing))
+                    (canon lift (core func $i "ctor")))
+                (func (export "[method]thing.get") (param "self" (borrow $thing)) (result u32)
+                    (canon lift (core func $i "get")))
+                (func (export "drops") (result u32)
+                    (canon lift (core func $dtor "drops")))
+            )"#,
+        )?;
+
+        let thing = instance.exports.get_resource("thing")?;
+        let ctor = instance
+            .exports
+            .get_typed_func::<(u32,), (ResourceAny,)>("[constructor]thing")?;
+        let get = instance
+            .exports
+            .get_typed_func::<(ResourceAny,), (u32,)>("[method]thing.get")?;
+        let drops = instance.exports.get_typed_func::<(), (u32,)>("drops")?;
+
+        let (resource,) = ctor.call(&mut store, (7,))?;
+        assert_eq!(resource.ty(), thing);
+        assert!(resource.owned());
+        assert_eq!(get.call(&mut store, (resource.clone(),))?, (7,));
+        assert_eq!(drops.call(&mut store, ())?, (0,));
+        resource.resource_drop(&mut store)?;
+        assert_eq!(drops.call(&mut store, ())?, (1,));
+
+        // Each instance has its own resource types.
+        let other = instantiate(
+            &mut store,
+            r#"(component
+                (type $r (resource (rep i32)))
+                (export "thing" (type $r)))"#,
+        )?;
+        assert_ne!(other.exports.get_resource("thing")?, thing);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_components() {
+        let store = Store::default();
+        assert!(Component::new(&store, "(module)").is_err());
+        assert!(Component::new(&store, "(component (func (export \"f\")))").is_err());
+        let err = Component::new(&store, "(component (component))").unwrap_err();
+        assert!(err.to_string().contains("nested components"), "{err}");
+    }
+}
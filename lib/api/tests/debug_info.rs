@@ -0,0 +1,77 @@
+#[cfg(all(feature = "gdb-jit", feature = "cranelift", target_os = "linux"))]
+mod sys {
+    use anyhow::Result;
+    use wasmer::sys::{Cranelift, EngineBuilder};
+    use wasmer::*;
+
+    #[repr(C)]
+    struct JitCodeEntry {
+        next_entry: *const JitCodeEntry,
+        prev_entry: *const JitCodeEntry,
+        symfile_addr: *const u8,
+        symfile_size: u64,
+    }
+
+    #[repr(C)]
+    struct JitDescriptor {
+        version: u32,
+        action_flag: u32,
+        relevant_entry: *const JitCodeEntry,
+        first_entry: *const JitCodeEntry,
+    }
+
+    extern "C" {
+        static __jit_debug_descriptor: JitDescriptor;
+    }
+
+    /// The images registered with the GDB JIT interface.
+    fn registered_images() -> Vec<Vec<u8>> {
+        let mut images = Vec::new();
+        unsafe {
+            let mut entry = __jit_debug_descriptor.first_entry;
+            while !entry.is_null() {
+                images.push(
+                    std::slice::from_raw_parts((*entry).symfile_addr, (*entry).symfile_size as _)
+                        .to_vec(),
+                );
+                entry = (*entry).next_entry;
+            }
+        }
+        images
+    }
+
+    fn contains(image: &[u8], needle: &[u8]) -> bool {
+        image.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn registers_modules_with_debuggers() -> Result<()> {
+        let engine: Engine = EngineBuilder::new(Cranelift::default())
+            .set_debug_info(true)
+            .engine()
+            .into();
+        let store = Store::new(engine);
+        let module = Module::new(
+            &store,
+            r#"(module $debugged
+                (func $answer (export "answer") (result i32)
+                    (i32.const 42)))"#,
+        )?;
+
+        let images = registered_images();
+        let image = images
+            .iter()
+            .find(|image| contains(image, b"debugged::answer\0"))
+            .expect("the module isn't registered");
+        assert_eq!(&image[..4], b"\x7fELF");
+        assert!(contains(image, b".debug_info\0"));
+
+        // The image is unregistered along with the code.
+        drop(module);
+        drop(store);
+        assert!(!registered_images()
+            .iter()
+            .any(|image| contains(image, b"debugged::answer\0")));
+        Ok(())
+    }
+}
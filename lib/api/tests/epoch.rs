@@ -0,0 +1,148 @@
+#[cfg(all(feature = "sys", feature = "cranelift"))]
+mod sys {
+    use anyhow::Result;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use wasmer::sys::{Cranelift, EngineBuilder, NativeEngineExt};
+    use wasmer::*;
+    use wasmer_types::TrapCode;
+
+    const WAT: &str = r#"(module
+        (import "env" "tick" (func $tick))
+        (func (export "spin")
+            (loop $l (br $l)))
+        (func (export "count") (param $n i32) (result i32)
+            (local $i i32)
+            (loop $l
+                (call $tick)
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $l (i32.lt_u (local.get $i) (local.get $n))))
+            (local.get $i))
+        (func (export "nop"))
+    )"#;
+
+    fn engine(epoch_interruption: bool) -> Engine {
+        let mut compiler = Cranelift::default();
+        compiler.epoch_interruption(epoch_interruption);
+        EngineBuilder::new(compiler).engine().into()
+    }
+
+    fn instance(engine: &Engine) -> Result<(Store, Instance)> {
+        let mut store = Store::new(engine.clone());
+        let module = Module::new(&store, WAT)?;
+        let tick_engine = engine.clone();
+        let tick = Function::new_typed(&mut store, move || tick_engine.increment_epoch());
+        let imports = imports! {
+            "env" => {
+                "tick" => tick,
+            },
+        };
+        let instance = Instance::new(&mut store, &module, &imports)?;
+        Ok((store, instance))
+    }
+
+    #[test]
+    fn traps_at_function_entry() -> Result<()> {
+        let engine = engine(true);
+        let (mut store, instance) = instance(&engine)?;
+        let nop: TypedFunction<(), ()> = instance.exports.get_typed_function(&store, "nop")?;
+        nop.call(&mut store)?;
+
+        store.set_epoch_deadline(1);
+        nop.call(&mut store)?;
+        engine.increment_epoch();
+        let err = nop.call(&mut store).unwrap_err();
+        assert_eq!(err.to_trap(), Some(TrapCode::Interrupt));
+        Ok(())
+    }
+
+    #[test]
+    fn interrupts_infinite_loop() -> Result<()> {
+        let engine = engine(true);
+        let (mut store, instance) = instance(&engine)?;
+        let spin: TypedFunction<(), ()> = instance.exports.get_typed_function(&store, "spin")?;
+
+        store.set_epoch_deadline(1);
+        let timer_engine = engine.clone();
+        let timer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            timer_engine.increment_epoch();
+        });
+        let err = spin.call(&mut store).unwrap_err();
+        timer.join().unwrap();
+        assert_eq!(err.to_trap(), Some(TrapCode::Interrupt));
+        Ok(())
+    }
+
+    #[test]
+    fn callback_extends_deadline() -> Result<()> {
+        let engine = engine(true);
+        let (mut store, instance) = instance(&engine)?;
+        let count: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "count")?;
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let callback_calls = calls.clone();
+        store.epoch_deadline_callback(move |_store| {
+            callback_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(1)
+        });
+        store.set_epoch_deadline(1);
+        assert_eq!(count.call(&mut store, 10)?, 10);
+        // Every tick but the last one reaches the deadline at a loop header.
+        assert_eq!(calls.load(Ordering::SeqCst), 9);
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct Timeout;
+
+    impl std::fmt::Display for Timeout {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "timeout")
+        }
+    }
+
+    impl std::error::Error for Timeout {}
+
+    #[test]
+    fn callback_can_trap() -> Result<()> {
+        let engine = engine(true);
+        let (mut store, instance) = instance(&engine)?;
+        let count: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "count")?;
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let callback_calls = calls.clone();
+        store.epoch_deadline_callback(move |_store| {
+            if callback_calls.fetch_add(1, Ordering::SeqCst) == 2 {
+                Err(Box::new(Timeout))
+            } else {
+                Ok(1)
+            }
+        });
+        store.set_epoch_deadline(1);
+        let err = count.call(&mut store, 10).unwrap_err();
+        assert!(err.downcast::<Timeout>().is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Switching back to the default behavior traps again.
+        store.epoch_deadline_trap();
+        let err = count.call(&mut store, 10).unwrap_err();
+        assert_eq!(err.to_trap(), Some(TrapCode::Interrupt));
+        Ok(())
+    }
+
+    #[test]
+    fn no_checks_unless_enabled() -> Result<()> {
+        let engine = engine(false);
+        let (mut store, instance) = instance(&engine)?;
+        let count: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "count")?;
+
+        store.set_epoch_deadline(0);
+        assert_eq!(count.call(&mut store, 10)?, 10);
+        Ok(())
+    }
+}
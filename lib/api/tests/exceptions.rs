@@ -0,0 +1,347 @@
+#[cfg(all(feature = "sys", feature = "cranelift"))]
+mod sys {
+    use anyhow::Result;
+    use wasmer::sys::{Cranelift, EngineBuilder, Features};
+    use wasmer::*;
+
+    fn store() -> Store {
+        let mut features = Features::default();
+        features.exceptions(true);
+        let engine = EngineBuilder::new(Cranelift::default())
+            .set_features(Some(features))
+            .engine();
+        Store::new(engine)
+    }
+
+    #[test]
+    fn tags_are_exported() -> Result<()> {
+        let mut store = store();
+        let module = Module::new(
+            &store,
+            r#"(module
+                (tag $e (export "e") (param i32 i64))
+            )"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+        let tag = instance.exports.get_tag("e")?;
+        assert_eq!(tag.ty(&store).params(), &[Type::I32, Type::I64]);
+        assert_eq!(instance.exports.iter().tags().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn uncaught_exception_reaches_the_host() -> Result<()> {
+        let mut store = store();
+        let module = Module::new(
+            &store,
+            r#"(module
+                (tag $e (export "e") (param i32 i64))
+                (func (export "throw") (param i32)
+                    (throw $e (local.get 0) (i64.const 7)))
+            )"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+        let throw: TypedFunction<i32, ()> = instance.exports.get_typed_function(&store, "throw")?;
+
+        let error = throw.call(&mut store, 42).unwrap_err();
+        assert!(error.is_exception());
+        let exception = error.to_exception(&mut store).unwrap();
+        assert!(exception.tag(&store) == *instance.exports.get_tag("e")?);
+        assert_eq!(
+            exception.payload(&mut store),
+            vec![Value::I32(42), Value::I64(7)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn imported_tags_are_shared_across_instances() -> Result<()> {
+        let mut store = store();
+        let tag = Tag::new(&mut store, TagType::new([Type::I32]));
+
+        let thrower = Module::new(
+            &store,
+            r#"(module
+                (tag $e (import "env" "e") (param i32))
+                (func (export "throw") (param i32)
+                    (throw $e (local.get 0)))
+            )"#,
+        )?;
+        let thrower = Instance::new(
+            &mut store,
+            &thrower,
+            &imports! { "env" => { "e" => tag.clone() } },
+        )?;
+
+        let catcher = Module::new(
+            &store,
+            r#"(module
+                (tag $e (import "env" "e") (param i32))
+                (tag $other (param i32))
+                (import "env" "throw" (func $throw (param i32)))
+                (func (export "catch") (param i32) (result i32)
+                    (block $h (result i32)
+                        (try_table (catch $e $h)
+                            (call $throw (local.get 0)))
+                        (i32.const -1))
+                    (i32.const 1)
+                    (i32.add))
+                (func (export "catch-other") (param i32) (result i32)
+                    (block $h (result i32)
+                        (try_table (catch $other $h)
+                            (call $throw (local.get 0)))
+                        (i32.const -1)))
+            )"#,
+        )?;
+        let throw = thrower.exports.get_function("throw")?.clone();
+        let catcher = Instance::new(
+            &mut store,
+            &catcher,
+            &imports! { "env" => { "e" => tag.clone(), "throw" => throw } },
+        )?;
+
+        let catch: TypedFunction<i32, i32> = catcher.exports.get_typed_function(&store, "catch")?;
+        assert_eq!(catch.call(&mut store, 41)?, 42);
+
+        let catch_other: TypedFunction<i32, i32> =
+            catcher.exports.get_typed_function(&store, "catch-other")?;
+        let error = catch_other.call(&mut store, 3).unwrap_err();
+        let exception = error.to_exception(&mut store).unwrap();
+        assert!(exception.tag(&store) == tag);
+
+        // The store is left in a clean state after an uncaught exception.
+        assert_eq!(catch.call(&mut store, 1)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn caught_exceptions_are_freed() -> Result<()> {
+        let mut store = store();
+        let module = Module::new(
+            &store,
+            r#"(module
+                (tag $e (param i32))
+                (func (export "try-table") (param $n i32) (result i32)
+                    (local $sum i32)
+                    (loop $l
+                        (block $h (result i32)
+                            (try_table (catch $e $h)
+                                (throw $e (local.get $n)))
+                            (unreachable))
+                        (local.set $sum (i32.add (local.get $sum)))
+                        (block $all
+                            (try_table (catch_all $all)
+                                (throw $e (local.get $n))))
+                        (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+                    (local.get $sum))
+                (func (export "legacy") (param $n i32) (result i32)
+                    (local $sum i32)
+                    (loop $l
+                        try
+                            (throw $e (local.get $n))
+                        catch $e
+                            (local.set $sum (i32.add (local.get $sum)))
+                        end
+                        (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+                    (local.get $sum))
+            )"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+
+        for name in ["try-table", "legacy"] {
+            let f: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, name)?;
+            assert_eq!(f.call(&mut store, 10_000)?, 50_005_000, "{name}");
+            assert_eq!(store.objects_mut().num_exceptions(), 1, "{name}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn exceptions_are_freed_when_leaving_catch_clauses() -> Result<()> {
+        let mut store = store();
+        let module = Module::new(
+            &store,
+            r#"(module
+                (tag $e (param i32))
+                (func $return (param i32) (result i32)
+                    try (result i32)
+                        (throw $e (local.get 0))
+                    catch $e
+                        (return)
+                    end)
+                (func $throw (param i32) (result i32)
+                    try (result i32)
+                        try
+                            (throw $e (local.get 0))
+                        catch $e
+                            (throw $e)
+                        end
+                        (unreachable)
+                    catch $e
+                    end)
+                (func $rethrow (param i32) (result i32)
+                    try (result i32)
+                        try
+                            (throw $e (local.get 0))
+                        catch_all
+                            (rethrow 0)
+                        end
+                        (unreachable)
+                    catch $e
+                    end)
+                (func (export "br") (param $n i32) (result i32)
+                    (local $sum i32)
+                    (loop $l
+                        (block $next
+                            try
+                                (throw $e (local.get $n))
+                            catch $e
+                                (local.set $sum (i32.add (local.get $sum)))
+                                (br $next)
+                            end)
+                        (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+                    (local.get $sum))
+                (func (export "br_if") (param $n i32) (result i32)
+                    (local $sum i32)
+                    (loop $l
+                        try
+                            (throw $e (local.get $n))
+                        catch $e
+                            (local.set $sum (i32.add (local.get $sum)))
+                            (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1))))
+                        end)
+                    (local.get $sum))
+                (func (export "br_table") (param $n i32) (result i32)
+                    (local $sum i32)
+                    (block $done
+                        (loop $l
+                            try
+                                (throw $e (local.get $n))
+                            catch $e
+                                (local.set $sum (i32.add (local.get $sum)))
+                                (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+                                (br_table $l $done (i32.eqz))
+                            end))
+                    (local.get $sum))
+                (func (export "return") (param $n i32) (result i32)
+                    (local $sum i32)
+                    (loop $l
+                        (local.set $sum (i32.add (local.get $sum) (call $return (local.get $n))))
+                        (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+                    (local.get $sum))
+                (func (export "throw") (param $n i32) (result i32)
+                    (local $sum i32)
+                    (loop $l
+                        (local.set $sum (i32.add (local.get $sum) (call $throw (local.get $n))))
+                        (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+                    (local.get $sum))
+                (func (export "rethrow") (param $n i32) (result i32)
+                    (local $sum i32)
+                    (loop $l
+                        (local.set $sum (i32.add (local.get $sum) (call $rethrow (local.get $n))))
+                        (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+                    (local.get $sum))
+            )"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+
+        for name in ["br", "br_if", "br_table", "return", "throw", "rethrow"] {
+            let f: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, name)?;
+            assert_eq!(f.call(&mut store, 1)?, 1, "{name}");
+            let num_exceptions = store.objects_mut().num_exceptions();
+            assert_eq!(f.call(&mut store, 10_000)?, 50_005_000, "{name}");
+            assert_eq!(
+                store.objects_mut().num_exceptions(),
+                num_exceptions,
+                "{name}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_exnrefs_are_collected_between_calls() -> Result<()> {
+        let mut store = store();
+        let module = Module::new(
+            &store,
+            r#"(module
+                (tag $e (param i32))
+                (func (export "run") (param $n i32)
+                    (local $last exnref)
+                    (loop $l
+                        ;; Overwrite the previous reference.
+                        (block $h (result exnref)
+                            (try_table (catch_all_ref $h)
+                                (throw $e (local.get $n)))
+                            (unreachable))
+                        (local.set $last)
+                        ;; Drop the reference right away.
+                        (block $h (result i32 exnref)
+                            (try_table (catch_ref $e $h)
+                                (throw $e (local.get $n)))
+                            (unreachable))
+                        (drop)
+                        (drop)
+                        (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1))))))
+            )"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+        let run: TypedFunction<i32, ()> = instance.exports.get_typed_function(&store, "run")?;
+
+        run.call(&mut store, 100)?;
+        let num_exceptions = store.objects_mut().num_exceptions();
+        for _ in 0..10 {
+            run.call(&mut store, 100)?;
+            assert_eq!(store.objects_mut().num_exceptions(), num_exceptions);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn referenced_exceptions_are_kept() -> Result<()> {
+        let mut store = store();
+        let module = Module::new(
+            &store,
+            r#"(module
+                (tag $e (param i32))
+                (global $kept (mut exnref) (ref.null exn))
+                (func $catch (param i32) (result i32)
+                    (block $h (result i32)
+                        (try_table (catch $e $h)
+                            (throw $e (local.get 0)))
+                        (unreachable)))
+                (func (export "run") (result i32)
+                    (local $sum i32)
+                    ;; Keep a reference to the first exception.
+                    (block $h (result i32 exnref)
+                        (try_table (catch_ref $e $h)
+                            (throw $e (i32.const 1000)))
+                        (unreachable))
+                    (global.set $kept)
+                    (drop)
+                    ;; Throw it again, then catch it without its reference.
+                    (block $h (result i32)
+                        (try_table (catch $e $h)
+                            (throw_ref (global.get $kept)))
+                        (unreachable))
+                    (local.set $sum)
+                    ;; Other exceptions can't reuse its slot.
+                    (local.set $sum (i32.add (local.get $sum) (call $catch (i32.const 1))))
+                    (local.set $sum (i32.add (local.get $sum) (call $catch (i32.const 2))))
+                    (block $h (result i32)
+                        (try_table (catch $e $h)
+                            (throw_ref (global.get $kept)))
+                        (unreachable))
+                    (local.get $sum)
+                    (i32.add))
+            )"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+        let run: TypedFunction<(), i32> = instance.exports.get_typed_function(&store, "run")?;
+        assert_eq!(run.call(&mut store)?, 2003);
+        // The exception kept in the global survives the collection done
+        // before the next call.
+        assert_eq!(run.call(&mut store)?, 2003);
+        Ok(())
+    }
+}
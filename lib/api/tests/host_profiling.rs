@@ -0,0 +1,179 @@
+#[cfg(all(feature = "sys", feature = "cranelift"))]
+mod sys {
+    use anyhow::Result;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+    use wasmer::*;
+
+    const WAT: &str = r#"(module
+        (import "env" "copy" (func $copy (param i32 i32 i32)))
+        (import "env" "fail" (func $fail))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "hello")
+        (func (export "run")
+            (call $copy (i32.const 0) (i32.const 16) (i32.const 5))
+            (call $copy (i32.const 0) (i32.const 32) (i32.const 3)))
+        (func (export "fail")
+            (call $fail))
+    )"#;
+
+    #[derive(Clone, Default)]
+    struct Env {
+        memory: Option<Memory>,
+    }
+
+    fn copy(env: FunctionEnvMut<Env>, src: u32, dst: u32, len: u32) {
+        let memory = env.data().memory.clone().unwrap();
+        let view = memory.view(&env);
+        let bytes = WasmPtr::<u8>::new(src)
+            .slice(&view, len)
+            .unwrap()
+            .read_to_vec()
+            .unwrap();
+        view.write(dst as u64, &bytes).unwrap();
+    }
+
+    fn fail() -> Result<(), RuntimeError> {
+        Err(RuntimeError::new("oops"))
+    }
+
+    fn instance(store: &mut Store) -> Result<Instance> {
+        let env = FunctionEnv::new(store, Env::default());
+        let imports = imports! {
+            "env" => {
+                "copy" => Function::new_typed_with_env(store, &env, copy),
+                "fail" => Function::new_typed(store, fail),
+            },
+        };
+        let imports = imports.profiled(store);
+
+        let module = Module::new(store, WAT)?;
+        let instance = Instance::new(store, &module, &imports)?;
+        env.as_mut(store).memory = Some(instance.exports.get_memory("memory")?.clone());
+        Ok(instance)
+    }
+
+    #[test]
+    fn host_calls_are_recorded() -> Result<()> {
+        let profiler = HostProfiler::new();
+        let mut store = Store::default();
+        store.set_host_profiler(Some(profiler.clone()));
+        let instance = instance(&mut store)?;
+
+        instance
+            .exports
+            .get_function("run")?
+            .call(&mut store, &[])?;
+        let err = instance
+            .exports
+            .get_function("fail")?
+            .call(&mut store, &[])
+            .unwrap_err();
+        assert_eq!(err.message(), "oops");
+
+        let profile = store.host_profiler().unwrap().profile();
+        assert_eq!(profile.len(), 2);
+
+        let copy = profile.get("env", "copy").unwrap();
+        assert_eq!(copy.calls, 2);
+        assert_eq!(copy.traps, 0);
+        assert_eq!(copy.bytes_read, 8);
+        assert_eq!(copy.bytes_written, 8);
+        assert!(copy.max_time <= copy.total_time);
+
+        let fail = profile.get("env", "fail").unwrap();
+        assert_eq!(fail.calls, 1);
+        assert_eq!(fail.traps, 1);
+
+        let memory = instance.exports.get_memory("memory")?;
+        let mut copied = [0; 5];
+        memory.view(&store).read(16, &mut copied)?;
+        assert_eq!(&copied, b"hello");
+
+        // The profiler's clones share its statistics
+        assert_eq!(profiler.profile(), profile);
+        Ok(())
+    }
+
+    #[test]
+    fn imports_are_left_alone_without_a_profiler() -> Result<()> {
+        let mut store = Store::default();
+        let instance = instance(&mut store)?;
+
+        instance
+            .exports
+            .get_function("run")?
+            .call(&mut store, &[])?;
+
+        assert!(store.host_profiler().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn on_called_runs_after_the_outermost_call() -> Result<()> {
+        let mut store = Store::default();
+        store.set_host_profiler(Some(HostProfiler::new()));
+
+        let called = Arc::new(AtomicBool::new(false));
+        let env = FunctionEnv::new(&mut store, called.clone());
+        let imports = imports! {
+            "env" => {
+                "yield" => Function::new_typed_with_env(
+                    &mut store,
+                    &env,
+                    |mut env: FunctionEnvMut<Arc<AtomicBool>>| {
+                        let called = env.data().clone();
+                        env.as_store_mut().on_called(move |_| {
+                            called.store(true, Ordering::SeqCst);
+                            Ok(OnCalledAction::Finish)
+                        });
+                    },
+                ),
+                "was_called" => Function::new_typed_with_env(
+                    &mut store,
+                    &env,
+                    |env: FunctionEnvMut<Arc<AtomicBool>>| -> i32 {
+                        env.data().load(Ordering::SeqCst).into()
+                    },
+                ),
+            },
+        };
+        let imports = imports.profiled(&mut store);
+
+        let module = Module::new(
+            &store,
+            r#"(module
+                (import "env" "yield" (func $yield))
+                (import "env" "was_called" (func $was_called (result i32)))
+                (func (export "run") (result i32)
+                    (call $yield)
+                    (call $was_called)))"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &imports)?;
+        let run: TypedFunction<(), i32> = instance.exports.get_typed_function(&store, "run")?;
+
+        // The callback didn't run when `yield` returned, but when `run` did
+        assert_eq!(run.call(&mut store)?, 0);
+        assert!(called.load(Ordering::SeqCst));
+        Ok(())
+    }
+
+    #[test]
+    fn profiles_serialize_to_json() -> Result<()> {
+        let mut store = Store::default();
+        store.set_host_profiler(Some(HostProfiler::new()));
+        let instance = instance(&mut store)?;
+        instance
+            .exports
+            .get_function("run")?
+            .call(&mut store, &[])?;
+
+        let json = store.host_profiler().unwrap().profile().to_json();
+
+        assert!(json.starts_with(r#"[{"module":"env","name":"copy","calls":2,"traps":0,"#));
+        assert!(json.ends_with(r#","bytes_read":8,"bytes_written":8}]"#));
+        Ok(())
+    }
+}
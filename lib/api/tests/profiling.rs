@@ -0,0 +1,110 @@
+#[cfg(all(feature = "sys", feature = "cranelift"))]
+mod sys {
+    use anyhow::Result;
+    use std::sync::{Arc, Mutex};
+    use wasmer::sys::{
+        Cranelift, EngineBuilder, NativeEngineExt, ProfiledFunction, ProfilingAgent,
+        ProfilingStrategy,
+    };
+    use wasmer::*;
+
+    const WAT: &str = r#"(module $demo
+        (func $add (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1)))
+        (func (export "double") (param i32) (result i32)
+            (call $add (local.get 0) (local.get 0)))
+    )"#;
+
+    #[derive(Default)]
+    struct Collector(Mutex<Vec<(String, usize, usize)>>);
+
+    impl ProfilingAgent for Collector {
+        fn register_function(&self, function: &ProfiledFunction<'_>) {
+            self.0.lock().unwrap().push((
+                function.name.to_string(),
+                function.address as usize,
+                function.size,
+            ));
+        }
+    }
+
+    #[test]
+    fn registers_compiled_functions() -> Result<()> {
+        let collector = Arc::new(Collector::default());
+        let engine: Engine = EngineBuilder::new(Cranelift::default())
+            .set_profiler(Some(collector.clone()))
+            .engine()
+            .into();
+        let mut store = Store::new(engine);
+        let module = Module::new(&store, WAT)?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+        let double = instance
+            .exports
+            .get_typed_function::<i32, i32>(&store, "double")?;
+        assert_eq!(double.call(&mut store, 21)?, 42);
+
+        let functions = collector.0.lock().unwrap();
+        let names: Vec<&str> = functions.iter().map(|(name, ..)| name.as_str()).collect();
+        assert_eq!(names, ["demo::add", "demo::function[1]"]);
+        for (name, address, size) in functions.iter() {
+            assert_ne!(*address, 0, "{name}");
+            assert!(*size > 0, "{name}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn profiler_is_not_required() -> Result<()> {
+        let mut engine: Engine = EngineBuilder::new(Cranelift::default()).engine().into();
+        let collector = Arc::new(Collector::default());
+        engine.set_profiler(Some(collector.clone()));
+        engine.set_profiler(None);
+        let store = Store::new(engine);
+        Module::new(&store, WAT)?;
+        assert!(collector.0.lock().unwrap().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn perf_map() -> Result<()> {
+        let engine: Engine = EngineBuilder::new(Cranelift::default())
+            .set_profiler(Some(ProfilingStrategy::PerfMap.agent()?))
+            .engine()
+            .into();
+        let store = Store::new(engine);
+        Module::new(&store, WAT)?;
+
+        let map = std::fs::read_to_string(format!("/tmp/perf-{}.map", std::process::id()))?;
+        let line = map
+            .lines()
+            .find(|line| line.ends_with(" demo::add"))
+            .expect("missing symbol");
+        let mut fields = line.split(' ');
+        let address = u64::from_str_radix(fields.next().unwrap(), 16)?;
+        let size = u64::from_str_radix(fields.next().unwrap(), 16)?;
+        assert_ne!(address, 0);
+        assert_ne!(size, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn jitdump() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::env::set_var("JITDUMPDIR", dir.path());
+        let agent = ProfilingStrategy::JitDump.agent()?;
+        std::env::remove_var("JITDUMPDIR");
+        let engine: Engine = EngineBuilder::new(Cranelift::default())
+            .set_profiler(Some(agent))
+            .engine()
+            .into();
+        let store = Store::new(engine);
+        Module::new(&store, WAT)?;
+
+        let dump = std::fs::read(dir.path().join(format!("jit-{}.dump", std::process::id())))?;
+        assert_eq!(&dump[..4], &0x4A695444u32.to_ne_bytes());
+        assert!(dump
+            .windows(b"demo::add\0".len())
+            .any(|window| window == b"demo::add\0"));
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(all(feature = "sys", feature = "cranelift"))]
+mod sys {
+    use anyhow::Result;
+    use wasmer::sys::{Cranelift, EngineBuilder, Features};
+    use wasmer::*;
+
+    const WAT: &str = r#"(module
+        (func (export "madd") (param i32 i32 i32) (result i32)
+            (i32x4.extract_lane 0
+                (f32x4.relaxed_madd
+                    (i32x4.splat (local.get 0))
+                    (i32x4.splat (local.get 1))
+                    (i32x4.splat (local.get 2)))))
+        (func (export "swizzle") (param i32) (result i32)
+            (i8x16.extract_lane_u 0
+                (i8x16.relaxed_swizzle
+                    (v128.const i8x16 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25)
+                    (i8x16.splat (local.get 0)))))
+        (func (export "laneselect") (param i32 i32 i32) (result i32)
+            (i32x4.extract_lane 0
+                (i32x4.relaxed_laneselect
+                    (i32x4.splat (local.get 0))
+                    (i32x4.splat (local.get 1))
+                    (i32x4.splat (local.get 2)))))
+    )"#;
+
+    fn instance(deterministic: bool) -> Result<(Store, Instance)> {
+        let mut compiler = Cranelift::default();
+        compiler.relaxed_simd_deterministic(deterministic);
+        let mut features = Features::default();
+        features.relaxed_simd(true);
+        let engine = EngineBuilder::new(compiler)
+            .set_features(Some(features))
+            .engine();
+        let mut store = Store::new(engine);
+        let module = Module::new(&store, WAT)?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+        Ok((store, instance))
+    }
+
+    // The bits of `(1 + 2^-23)^2 - (1 + 2^-22)`, which is `2^-46` when fused,
+    // and `0` otherwise.
+    const A: i32 = 0x3f80_0001;
+    const C: i32 = 0xbf80_0002_u32 as i32;
+    const FUSED: i32 = 0x2880_0000;
+
+    #[test]
+    fn deterministic_by_default() -> Result<()> {
+        let (mut store, instance) = instance(true)?;
+        let madd: TypedFunction<(i32, i32, i32), i32> =
+            instance.exports.get_typed_function(&store, "madd")?;
+        assert_eq!(madd.call(&mut store, A, A, C)?, FUSED);
+        let swizzle: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "swizzle")?;
+        assert_eq!(swizzle.call(&mut store, 3)?, 13);
+        assert_eq!(swizzle.call(&mut store, 16)?, 0);
+        let laneselect: TypedFunction<(i32, i32, i32), i32> =
+            instance.exports.get_typed_function(&store, "laneselect")?;
+        assert_eq!(laneselect.call(&mut store, 0x1234, 0x4321, 0x00ff)?, 0x4334);
+        Ok(())
+    }
+
+    #[test]
+    fn native_lowering() -> Result<()> {
+        let (mut store, instance) = instance(false)?;
+        let madd: TypedFunction<(i32, i32, i32), i32> =
+            instance.exports.get_typed_function(&store, "madd")?;
+        let result = madd.call(&mut store, A, A, C)?;
+        assert!(result == FUSED || result == 0, "{result:#x}");
+        let swizzle: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "swizzle")?;
+        assert_eq!(swizzle.call(&mut store, 3)?, 13);
+        // Out of range indices may select 0 or a wrapped lane.
+        assert!([0, 10].contains(&swizzle.call(&mut store, 16)?));
+        // The lanes of the mask are either all ones or all zeros.
+        let laneselect: TypedFunction<(i32, i32, i32), i32> =
+            instance.exports.get_typed_function(&store, "laneselect")?;
+        assert_eq!(laneselect.call(&mut store, 0x1234, 0x4321, -1)?, 0x1234);
+        assert_eq!(laneselect.call(&mut store, 0x1234, 0x4321, 0)?, 0x4321);
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+#[cfg(all(feature = "sys", feature = "cranelift"))]
+mod sys {
+    use anyhow::Result;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use wasmer::*;
+
+    const PAGE: usize = 0x10000;
+
+    const WAT: &str = r#"(module
+        (memory (export "memory") 1 10)
+        (table (export "table") 1 10 funcref)
+        (func (export "grow_memory") (param $delta i32) (result i32)
+            (memory.grow (local.get $delta)))
+        (func (export "grow_table") (param $delta i32) (result i32)
+            (table.grow (ref.null func) (local.get $delta)))
+    )"#;
+
+    fn instance(store: &mut Store) -> Result<Instance> {
+        let module = Module::new(store, WAT)?;
+        Ok(Instance::new(store, &module, &imports! {})?)
+    }
+
+    #[test]
+    fn memory_grow_is_denied() -> Result<()> {
+        let mut store = Store::default();
+        store.set_resource_limiter(StoreLimitsBuilder::new().memory_size(2 * PAGE).build());
+        let instance = instance(&mut store)?;
+        let grow: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "grow_memory")?;
+
+        assert_eq!(grow.call(&mut store, 1)?, 1);
+        assert_eq!(grow.call(&mut store, 1)?, -1);
+
+        let memory = instance.exports.get_memory("memory")?;
+        assert!(memory.grow(&mut store, 1).is_err());
+        assert_eq!(memory.view(&store).size(), Pages(2));
+        Ok(())
+    }
+
+    #[test]
+    fn memory_grow_traps() -> Result<()> {
+        let mut store = Store::default();
+        store.set_resource_limiter(
+            StoreLimitsBuilder::new()
+                .memory_size(2 * PAGE)
+                .trap_on_grow_failure(true)
+                .build(),
+        );
+        let instance = instance(&mut store)?;
+        let grow: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "grow_memory")?;
+
+        assert_eq!(grow.call(&mut store, 1)?, 1);
+        let err = grow.call(&mut store, 1).unwrap_err();
+        assert!(err.downcast::<ResourceLimitExceeded>().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn table_grow_is_denied() -> Result<()> {
+        let mut store = Store::default();
+        store.set_resource_limiter(StoreLimitsBuilder::new().table_elements(3).build());
+        let instance = instance(&mut store)?;
+        let grow: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "grow_table")?;
+
+        assert_eq!(grow.call(&mut store, 2)?, 1);
+        assert_eq!(grow.call(&mut store, 1)?, -1);
+
+        let table = instance.exports.get_table("table")?;
+        assert!(table.grow(&mut store, 1, Value::FuncRef(None)).is_err());
+        assert_eq!(table.size(&store), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn instance_count_is_limited() -> Result<()> {
+        let mut store = Store::default();
+        store.set_resource_limiter(StoreLimitsBuilder::new().instances(2).build());
+        let module = Module::new(&store, "(module)")?;
+
+        Instance::new(&mut store, &module, &imports! {})?;
+        Instance::new(&mut store, &module, &imports! {})?;
+        let err = Instance::new(&mut store, &module, &imports! {}).unwrap_err();
+        assert!(matches!(
+            err,
+            InstantiationError::Link(LinkError::Resource(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn total_memory_spans_instances() -> Result<()> {
+        let mut store = Store::default();
+        let limits = Arc::new(StoreLimitsBuilder::new().total_memory(3 * PAGE).build());
+        store
+            .as_store_mut()
+            .set_shared_resource_limiter(Some(limits.clone()));
+
+        let first = instance(&mut store)?;
+        let second = instance(&mut store)?;
+        assert_eq!(limits.memory_used(), 2 * PAGE);
+
+        let grow_first: TypedFunction<i32, i32> =
+            first.exports.get_typed_function(&store, "grow_memory")?;
+        let grow_second: TypedFunction<i32, i32> =
+            second.exports.get_typed_function(&store, "grow_memory")?;
+        assert_eq!(grow_first.call(&mut store, 1)?, 1);
+        assert_eq!(grow_second.call(&mut store, 1)?, -1);
+
+        // The initial memory of a third instance would exceed the limit.
+        let module = Module::new(&store, WAT)?;
+        assert!(Instance::new(&mut store, &module, &imports! {}).is_err());
+        assert_eq!(limits.memory_used(), 3 * PAGE);
+        Ok(())
+    }
+
+    #[test]
+    fn dropped_memory_is_released() -> Result<()> {
+        let limits = Arc::new(StoreLimitsBuilder::new().total_memory(3 * PAGE).build());
+        let grow_to_limit = || -> Result<()> {
+            let mut store = Store::default();
+            store
+                .as_store_mut()
+                .set_shared_resource_limiter(Some(limits.clone()));
+            let instance = instance(&mut store)?;
+            let grow: TypedFunction<i32, i32> =
+                instance.exports.get_typed_function(&store, "grow_memory")?;
+            assert_eq!(grow.call(&mut store, 2)?, 1);
+            assert_eq!(grow.call(&mut store, 1)?, -1);
+            assert_eq!(limits.memory_used(), 3 * PAGE);
+            Ok(())
+        };
+
+        // The memories of the first instance are dropped with its store,
+        // so a second instance can grow as much again.
+        grow_to_limit()?;
+        assert_eq!(limits.memory_used(), 0);
+        grow_to_limit()?;
+        assert_eq!(limits.memory_used(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn copied_memory_is_charged() -> Result<()> {
+        let mut store = Store::default();
+        let limits = Arc::new(StoreLimitsBuilder::new().total_memory(3 * PAGE).build());
+        store
+            .as_store_mut()
+            .set_shared_resource_limiter(Some(limits.clone()));
+        let module = Module::new(
+            &store,
+            r#"(module
+                (memory (export "memory") 1 10 shared)
+                (func (export "grow_memory") (param $delta i32) (result i32)
+                    (memory.grow (local.get $delta)))
+            )"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+        let memory = instance.exports.get_memory("memory")?;
+
+        // A copy, e.g. for a forked process, counts towards the same limit
+        // until it is dropped.
+        let mut other_store = Store::default();
+        memory.copy_to_store(&store, &mut other_store)?;
+        assert_eq!(limits.memory_used(), 2 * PAGE);
+        drop(other_store);
+        assert_eq!(limits.memory_used(), PAGE);
+
+        // A copy of two pages doesn't fit next to the original.
+        let grow: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "grow_memory")?;
+        assert_eq!(grow.call(&mut store, 1)?, 1);
+        let mut other_store = Store::default();
+        assert!(memory.copy_to_store(&store, &mut other_store).is_err());
+        assert_eq!(limits.memory_used(), 2 * PAGE);
+        Ok(())
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        growing: AtomicUsize,
+        failed: AtomicUsize,
+    }
+
+    impl ResourceLimiter for Recorder {
+        fn memory_growing(
+            &self,
+            _current: usize,
+            _desired: usize,
+            _maximum: Option<usize>,
+        ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+            self.growing.fetch_add(1, Ordering::SeqCst);
+            Ok(true)
+        }
+
+        fn memory_grow_failed(&self, _current: usize, _desired: usize) {
+            self.failed.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn table_growing(
+            &self,
+            _current: u32,
+            _desired: u32,
+            _maximum: Option<u32>,
+        ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(true)
+        }
+    }
+
+    /// Counts the elements of the tables it allows, and denies every memory.
+    #[derive(Debug, Default)]
+    struct TableCounter {
+        elements: AtomicUsize,
+    }
+
+    impl ResourceLimiter for TableCounter {
+        fn memory_growing(
+            &self,
+            _current: usize,
+            _desired: usize,
+            _maximum: Option<usize>,
+        ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(false)
+        }
+
+        fn table_growing(
+            &self,
+            current: u32,
+            desired: u32,
+            _maximum: Option<u32>,
+        ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+            self.elements
+                .fetch_add((desired - current) as usize, Ordering::SeqCst);
+            Ok(true)
+        }
+
+        fn table_grow_failed(&self, current: u32, desired: u32) {
+            self.elements
+                .fetch_sub((desired - current) as usize, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn tables_are_released_when_a_memory_is_denied() -> Result<()> {
+        let mut store = Store::default();
+        let counter = Arc::new(TableCounter::default());
+        store
+            .as_store_mut()
+            .set_shared_resource_limiter(Some(counter.clone()));
+
+        // The table is allowed before the memory is denied.
+        assert!(instance(&mut store).is_err());
+        assert_eq!(counter.elements.load(Ordering::SeqCst), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn custom_limiter_sees_failures() -> Result<()> {
+        let mut store = Store::default();
+        let recorder = Arc::new(Recorder::default());
+        store
+            .as_store_mut()
+            .set_shared_resource_limiter(Some(recorder.clone()));
+        let instance = instance(&mut store)?;
+        let grow: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "grow_memory")?;
+
+        // Growing past the maximum of the memory is allowed by the limiter,
+        // but fails anyway.
+        assert_eq!(grow.call(&mut store, 20)?, -1);
+        assert_eq!(recorder.growing.load(Ordering::SeqCst), 2);
+        assert_eq!(recorder.failed.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+}
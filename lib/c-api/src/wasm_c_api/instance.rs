@@ -2,6 +2,7 @@ use super::externals::{wasm_extern_t, wasm_extern_vec_t};
 use super::module::wasm_module_t;
 use super::store::{wasm_store_t, StoreRef};
 use super::trap::wasm_trap_t;
+use super::types::is_supported;
 use wasmer_api::{Extern, Instance, InstantiationError};
 
 /// Opaque type representing a WebAssembly instance.
@@ -201,6 +202,7 @@ pub unsafe extern "C" fn wasm_instance_exports(
     let extern_vec: Vec<Option<Box<wasm_extern_t>>> = instance
         .exports
         .iter()
+        .filter(|(_name, r#extern)| is_supported(&r#extern.ty(&original_instance.store.store())))
         .map(|(_name, r#extern)| {
             Some(Box::new(wasm_extern_t::new(
                 original_instance.store.clone(),
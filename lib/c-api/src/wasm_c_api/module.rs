@@ -1,5 +1,5 @@
 use super::store::wasm_store_t;
-use super::types::{wasm_byte_vec_t, wasm_exporttype_vec_t, wasm_importtype_vec_t};
+use super::types::{is_supported, wasm_byte_vec_t, wasm_exporttype_vec_t, wasm_importtype_vec_t};
 use std::ptr::NonNull;
 use wasmer_api::Module;
 
@@ -10,6 +10,24 @@ pub struct wasm_module_t {
     pub(crate) inner: Module,
 }
 
+impl wasm_module_t {
+    /// Wraps a module, rejecting it if one of its imports can't be
+    /// represented by the Wasm C API: such an import could never be
+    /// satisfied, and leaving it out would shift the position of the
+    /// following ones.
+    pub(crate) fn new(inner: Module) -> Result<Self, String> {
+        if let Some(import) = inner.imports().find(|import| !is_supported(import.ty())) {
+            return Err(format!(
+                "The import `{}::{}` uses a tag or an exnref value, which the Wasm C API doesn't support",
+                import.module(),
+                import.name()
+            ));
+        }
+
+        Ok(Self { inner })
+    }
+}
+
 /// A WebAssembly module contains stateless WebAssembly code that has
 /// already been compiled and can be instantiated multiple times.
 ///
@@ -21,6 +39,11 @@ pub struct wasm_module_t {
 /// Before the code is compiled, it will be validated using the store
 /// features.
 ///
+/// ## Errors
+///
+/// Modules importing tags or externs using `exnref` values are
+/// rejected, as the Wasm C API can't represent them.
+///
 /// # Example
 ///
 /// See the module's documentation.
@@ -34,7 +57,7 @@ pub unsafe extern "C" fn wasm_module_new(
 
     let module = c_try!(Module::from_binary(&store, bytes.as_slice()));
 
-    Some(Box::new(wasm_module_t { inner: module }))
+    Some(Box::new(c_try!(wasm_module_t::new(module))))
 }
 
 /// Deletes a WebAssembly module.
@@ -222,6 +245,7 @@ pub unsafe extern "C" fn wasm_module_exports(
     let exports = module
         .inner
         .exports()
+        .filter(|export| is_supported(export.ty()))
         .map(|export| Some(Box::new(export.into())))
         .collect();
 
@@ -460,9 +484,9 @@ pub unsafe extern "C" fn wasm_module_deserialize(
 
     let module = c_try!(Module::deserialize(&store.inner.store(), bytes.as_slice()));
 
-    Some(NonNull::new_unchecked(Box::into_raw(Box::new(
-        wasm_module_t { inner: module },
-    ))))
+    Some(NonNull::new_unchecked(Box::into_raw(Box::new(c_try!(
+        wasm_module_t::new(module)
+    )))))
 }
 
 /// Serializes a module into a binary representation that the
@@ -860,4 +884,26 @@ mod tests {
         })
         .success();
     }
+
+    #[cfg(feature = "cranelift")]
+    #[test]
+    fn test_module_importing_a_tag_is_rejected() {
+        use super::{wasm_module_t, Module};
+
+        let mut features = wasmer_api::sys::Features::default();
+        features.exceptions(true);
+        let engine: wasmer_api::Engine =
+            wasmer_api::sys::EngineBuilder::new(wasmer_compiler_cranelift::Cranelift::default())
+                .set_features(Some(features))
+                .engine()
+                .into();
+        let module = Module::new(
+            &engine,
+            r#"(module (import "env" "f" (func)) (import "env" "tag" (tag)))"#,
+        )
+        .unwrap();
+
+        let error = wasm_module_t::new(module).err().unwrap();
+        assert!(error.contains("env::tag"), "{error}");
+    }
 }
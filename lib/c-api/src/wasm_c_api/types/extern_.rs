@@ -6,7 +6,7 @@ use super::{
 use std::convert::{TryFrom, TryInto};
 use std::mem;
 use thiserror::Error;
-use wasmer_api::ExternType;
+use wasmer_api::{ExternType, Type};
 
 #[allow(non_camel_case_types)]
 pub type wasm_externkind_t = u8;
@@ -32,10 +32,30 @@ impl From<&ExternType> for wasm_externkind_enum {
             ExternType::Global(_) => Self::WASM_EXTERN_GLOBAL,
             ExternType::Table(_) => Self::WASM_EXTERN_TABLE,
             ExternType::Memory(_) => Self::WASM_EXTERN_MEMORY,
+            ExternType::Tag(_) => unreachable!("tags are never exposed by the Wasm C API"),
         }
     }
 }
 
+/// Whether an extern of this type can be exposed by the Wasm C API.
+///
+/// Tags and `exnref` values have no counterpart in `wasm.h`, so modules
+/// importing them are rejected, and exports using them are left out when
+/// listing a module's or an instance's exports.
+pub(crate) fn is_supported(extern_type: &ExternType) -> bool {
+    match extern_type {
+        ExternType::Function(function_type) => !function_type
+            .params()
+            .iter()
+            .chain(function_type.results())
+            .any(|ty| *ty == Type::ExceptionRef),
+        ExternType::Global(global_type) => global_type.ty != Type::ExceptionRef,
+        ExternType::Table(table_type) => table_type.ty != Type::ExceptionRef,
+        ExternType::Memory(_) => true,
+        ExternType::Tag(_) => false,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum WasmExternType {
     Function(WasmFunctionType),
@@ -66,6 +86,9 @@ impl wasm_externtype_t {
                 ExternType::Memory(memory_type) => {
                     WasmExternType::Memory(WasmMemoryType::new(memory_type))
                 }
+                ExternType::Tag(_) => {
+                    unreachable!("tags are never exposed by the Wasm C API")
+                }
             },
         }
     }
@@ -282,3 +305,28 @@ pub unsafe extern "C" fn wasm_memorytype_as_externtype(
 ) -> &'static wasm_externtype_t {
     &memory_type.extern_type
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_api::{FunctionType, GlobalType, MemoryType, Mutability, TableType, TagType};
+
+    #[test]
+    fn tags_and_exnref_are_not_supported() {
+        let supported = [
+            ExternType::Function(FunctionType::new([Type::I32], [Type::I64])),
+            ExternType::Global(GlobalType::new(Type::I32, Mutability::Const)),
+            ExternType::Table(TableType::new(Type::FuncRef, 0, None)),
+            ExternType::Memory(MemoryType::new(1, None, false)),
+        ];
+        let unsupported = [
+            ExternType::Tag(TagType::new([Type::I32])),
+            ExternType::Function(FunctionType::new([], [Type::ExceptionRef])),
+            ExternType::Global(GlobalType::new(Type::ExceptionRef, Mutability::Var)),
+            ExternType::Table(TableType::new(Type::ExceptionRef, 0, None)),
+        ];
+
+        assert!(supported.iter().all(is_supported));
+        assert!(!unsupported.iter().any(is_supported));
+    }
+}
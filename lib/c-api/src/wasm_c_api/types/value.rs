@@ -24,6 +24,8 @@ impl From<Type> for wasm_valkind_enum {
             Type::V128 => todo!("no v128 type in Wasm C API yet!"),
             Type::ExternRef => Self::WASM_EXTERNREF,
             Type::FuncRef => Self::WASM_FUNCREF,
+            // Externs using `exnref` are filtered out by `is_supported`
+            Type::ExceptionRef => unreachable!("exnref is never exposed by the Wasm C API"),
         }
     }
 }
@@ -177,5 +177,5 @@ pub unsafe extern "C" fn wasmer_module_new(
 
     let module = c_try!(Module::from_binary(&engine, bytes.as_slice()));
 
-    Some(Box::new(wasm_module_t { inner: module }))
+    Some(Box::new(c_try!(wasm_module_t::new(module))))
 }
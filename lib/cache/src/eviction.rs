@@ -0,0 +1,368 @@
+//! Bounding the disk usage of cache directories.
+//!
+//! Every artifact in a cache directory is a file, either directly in the
+//! directory or in a subdirectory named after the engine that compiled it.
+//! Loading an artifact bumps its modification time, which is used as its
+//! last access time, so that [`prune()`] evicts the least recently used
+//! artifacts first.
+//!
+//! Several processes can share a cache directory: artifacts are written to
+//! a temporary file and renamed, and only one process prunes at a time.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// The name of the file held while a process prunes a cache directory.
+const LOCK_FILE: &str = ".prune.lock";
+
+/// How long a lock file or a temporary file is trusted before being
+/// considered left behind by a crashed process.
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Limits on the disk usage of a cache directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheLimits {
+    /// The maximum total size of the artifacts, in bytes.
+    pub max_size: Option<u64>,
+    /// The maximum time since an artifact was last accessed.
+    pub max_age: Option<Duration>,
+}
+
+impl CacheLimits {
+    /// Whether the cache is unbounded.
+    pub fn is_unbounded(&self) -> bool {
+        self.max_size.is_none() && self.max_age.is_none()
+    }
+}
+
+/// An artifact in a cache directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// The path of the artifact.
+    pub path: PathBuf,
+    /// The engine that compiled the artifact, when the cache directory is
+    /// partitioned by engine.
+    pub engine_id: Option<String>,
+    /// The hash of the module.
+    pub module_hash: String,
+    /// The size of the artifact, in bytes.
+    pub size: u64,
+    /// When the artifact was last loaded or stored.
+    pub last_access: SystemTime,
+}
+
+/// The outcome of [`prune()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    /// The number of artifacts removed.
+    pub removed: usize,
+    /// The number of bytes freed.
+    pub freed: u64,
+    /// The number of artifacts left.
+    pub remaining: usize,
+    /// The total size of the artifacts left, in bytes.
+    pub remaining_size: u64,
+}
+
+/// Lists the artifacts of a cache directory, from the least to the most
+/// recently used.
+///
+/// A missing directory is an empty cache.
+pub fn list_entries(dir: &Path) -> io::Result<Vec<CacheEntry>> {
+    let mut entries = Vec::new();
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(e) => return Err(e),
+    };
+    for dir_entry in read_dir {
+        let dir_entry = dir_entry?;
+        let file_name = dir_entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        if file_name.starts_with('.') {
+            continue;
+        }
+        let file_type = dir_entry.file_type()?;
+        if file_type.is_file() {
+            entries.extend(entry(dir_entry.path(), None));
+        } else if file_type.is_dir() {
+            let read_dir = match fs::read_dir(dir_entry.path()) {
+                Ok(read_dir) => read_dir,
+                // Removed by another process in the meantime.
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for nested in read_dir {
+                let nested = nested?;
+                let is_hidden = nested
+                    .file_name()
+                    .to_str()
+                    .map_or(true, |name| name.starts_with('.'));
+                if !is_hidden && nested.file_type()?.is_file() {
+                    entries.extend(entry(nested.path(), Some(file_name.to_string())));
+                }
+            }
+        }
+    }
+    entries.sort_by_key(|entry| entry.last_access);
+    Ok(entries)
+}
+
+fn entry(path: PathBuf, engine_id: Option<String>) -> Option<CacheEntry> {
+    // The file may have been removed by another process in the meantime.
+    let metadata = fs::metadata(&path).ok()?;
+    let modified = metadata.modified().ok()?;
+    let last_access = match metadata.accessed() {
+        Ok(accessed) => accessed.max(modified),
+        Err(_) => modified,
+    };
+    let module_hash = path.file_stem()?.to_str()?.to_string();
+    Some(CacheEntry {
+        path,
+        engine_id,
+        module_hash,
+        size: metadata.len(),
+        last_access,
+    })
+}
+
+/// Records that an artifact was just used, so it's evicted last.
+pub fn mark_accessed(path: &Path) -> io::Result<()> {
+    OpenOptions::new()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+/// Writes an artifact, so that other processes never see it partially
+/// written.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("artifact");
+    let temp = dir.join(format!(
+        ".{file_name}.{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = File::create(&temp)
+        .and_then(|mut file| file.write_all(contents))
+        .and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Removes the artifacts of a cache directory which exceed the limits,
+/// starting with the least recently used ones.
+///
+/// If another process is already pruning the directory, nothing is removed.
+pub fn prune(dir: &Path, limits: &CacheLimits) -> io::Result<PruneStats> {
+    let Some(_lock) = PruneLock::acquire(dir)? else {
+        return Ok(PruneStats::default());
+    };
+    remove_stale_temp_files(dir);
+
+    let now = SystemTime::now();
+    let entries = list_entries(dir)?;
+    let mut stats = PruneStats {
+        remaining: entries.len(),
+        remaining_size: entries.iter().map(|entry| entry.size).sum(),
+        ..Default::default()
+    };
+    for entry in entries {
+        let expired = limits.max_age.map_or(false, |max_age| {
+            now.duration_since(entry.last_access)
+                .map_or(false, |age| age > max_age)
+        });
+        let oversized = limits
+            .max_size
+            .map_or(false, |max_size| stats.remaining_size > max_size);
+        if !expired && !oversized {
+            // The entries are sorted by last access, so the next ones are
+            // more recent and fit in the limits too.
+            break;
+        }
+        match fs::remove_file(&entry.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            // Artifacts in use can't be removed on Windows.
+            Err(_) => continue,
+        }
+        stats.removed += 1;
+        stats.freed += entry.size;
+        stats.remaining -= 1;
+        stats.remaining_size -= entry.size;
+    }
+    Ok(stats)
+}
+
+/// Removes the temporary files left behind by crashed processes.
+fn remove_stale_temp_files(dir: &Path) {
+    let is_stale = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map_or(false, |age| age > STALE_AFTER)
+    };
+    let mut dirs = vec![dir.to_path_buf()];
+    if let Ok(read_dir) = fs::read_dir(dir) {
+        dirs.extend(
+            read_dir
+                .flatten()
+                .filter(|entry| entry.file_type().map_or(false, |ty| ty.is_dir()))
+                .map(|entry| entry.path()),
+        );
+    }
+    for dir in dirs {
+        let Ok(read_dir) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let name = entry.file_name();
+            let is_temp = name.to_str().map_or(false, |name| {
+                name.starts_with('.') && name.ends_with(".tmp")
+            });
+            if is_temp && is_stale(&entry.path()) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// Makes sure only one process prunes a cache directory at a time.
+struct PruneLock {
+    path: PathBuf,
+}
+
+impl PruneLock {
+    fn acquire(dir: &Path) -> io::Result<Option<Self>> {
+        let path = dir.join(LOCK_FILE);
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Some(Self { path })),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .map_or(false, |age| age > STALE_AFTER);
+                    if !stale {
+                        return Ok(None);
+                    }
+                    // Left behind by a crashed process.
+                    let _ = fs::remove_file(&path);
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Drop for PruneLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn age(path: &Path, age: Duration) {
+        let time = SystemTime::now() - age;
+        let file = OpenOptions::new().write(true).open(path).unwrap();
+        file.set_times(fs::FileTimes::new().set_accessed(time).set_modified(time))
+            .unwrap();
+    }
+
+    #[test]
+    fn list_entries_per_engine() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("engine-a")).unwrap();
+        write_atomically(&dir.path().join("engine-a").join("1234.bin"), b"abc").unwrap();
+        write_atomically(&dir.path().join("5678"), b"de").unwrap();
+        fs::write(dir.path().join(".5678.1.0.tmp"), b"partial").unwrap();
+        age(&dir.path().join("5678"), Duration::from_secs(60));
+
+        let entries: Vec<_> = list_entries(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.engine_id, entry.module_hash, entry.size))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (None, "5678".to_string(), 2),
+                (Some("engine-a".to_string()), "1234".to_string(), 3),
+            ]
+        );
+        assert!(list_entries(&dir.path().join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn prune_by_age_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, days) in [("old", 10), ("middle", 5), ("new", 0)] {
+            let path = dir.path().join(name);
+            write_atomically(&path, &[0; 100]).unwrap();
+            age(&path, Duration::from_secs(days * 24 * 60 * 60 + 1));
+        }
+
+        let stats = prune(
+            dir.path(),
+            &CacheLimits {
+                max_size: None,
+                max_age: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+            },
+        )
+        .unwrap();
+        assert_eq!((stats.removed, stats.freed, stats.remaining), (1, 100, 2));
+        assert!(!dir.path().join("old").exists());
+
+        let stats = prune(
+            dir.path(),
+            &CacheLimits {
+                max_size: Some(150),
+                max_age: None,
+            },
+        )
+        .unwrap();
+        assert_eq!((stats.removed, stats.remaining_size), (1, 100));
+        assert!(dir.path().join("new").exists());
+        assert!(!dir.path().join(LOCK_FILE).exists());
+    }
+
+    #[test]
+    fn prune_skipped_while_locked() {
+        let dir = tempfile::tempdir().unwrap();
+        write_atomically(&dir.path().join("module"), b"abc").unwrap();
+        fs::write(dir.path().join(LOCK_FILE), b"").unwrap();
+
+        let limits = CacheLimits {
+            max_size: Some(0),
+            max_age: None,
+        };
+        assert_eq!(prune(dir.path(), &limits).unwrap(), PruneStats::default());
+        assert!(dir.path().join("module").exists());
+
+        // A lock left behind by a crashed process is ignored.
+        age(&dir.path().join(LOCK_FILE), STALE_AFTER * 2);
+        assert_eq!(prune(dir.path(), &limits).unwrap().removed, 1);
+    }
+}
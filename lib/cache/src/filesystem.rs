@@ -1,8 +1,10 @@
 #![cfg_attr(not(feature = "filesystem"), allow(unused))]
 use crate::cache::Cache;
+#[cfg(feature = "filesystem")]
+use crate::eviction::{self, CacheLimits};
 use crate::hash::Hash;
-use std::fs::{create_dir_all, File};
-use std::io::{self, Write};
+use std::fs::create_dir_all;
+use std::io;
 use std::path::PathBuf;
 use wasmer::{AsEngineRef, DeserializeError, Module, SerializeError};
 
@@ -11,6 +13,8 @@ use wasmer::{AsEngineRef, DeserializeError, Module, SerializeError};
 /// The `FileSystemCache` type implements the [`Cache`] trait, which allows it to be used
 /// generically when some sort of cache is required.
 ///
+/// The cache is unbounded by default, see [`FileSystemCache::set_limits`].
+///
 /// # Usage
 ///
 /// ```
@@ -35,6 +39,8 @@ use wasmer::{AsEngineRef, DeserializeError, Module, SerializeError};
 pub struct FileSystemCache {
     path: PathBuf,
     ext: Option<String>,
+    #[cfg(feature = "filesystem")]
+    limits: CacheLimits,
 }
 
 #[cfg(feature = "filesystem")]
@@ -46,7 +52,11 @@ impl FileSystemCache {
             let metadata = path.metadata()?;
             if metadata.is_dir() {
                 if !metadata.permissions().readonly() {
-                    Ok(Self { path, ext: None })
+                    Ok(Self {
+                        path,
+                        ext: None,
+                        limits: CacheLimits::default(),
+                    })
                 } else {
                     // This directory is readonly.
                     Err(io::Error::new(
@@ -73,7 +83,11 @@ impl FileSystemCache {
                     format!("failed to create cache directory: {}", path.display()),
                 ))
             } else {
-                Ok(Self { path, ext: None })
+                Ok(Self {
+                    path,
+                    ext: None,
+                    limits: CacheLimits::default(),
+                })
             }
         }
     }
@@ -85,6 +99,14 @@ impl FileSystemCache {
     pub fn set_cache_extension(&mut self, ext: Option<impl ToString>) {
         self.ext = ext.map(|ext| ext.to_string());
     }
+
+    /// Set the limits on the disk usage of this cache.
+    ///
+    /// When an artifact is stored, the least recently used artifacts are
+    /// evicted until the cache fits in the limits again.
+    pub fn set_limits(&mut self, limits: CacheLimits) {
+        self.limits = limits;
+    }
 }
 
 #[cfg(feature = "filesystem")]
@@ -108,6 +130,8 @@ impl Cache for FileSystemCache {
             // If an error occurs while deserializing then we can not trust it anymore
             // so delete the cache file
             let _ = std::fs::remove_file(path);
+        } else {
+            let _ = eviction::mark_accessed(&path);
         }
         ret
     }
@@ -119,10 +143,15 @@ impl Cache for FileSystemCache {
             key.to_string()
         };
         let path = self.path.join(filename);
-        let mut file = File::create(path)?;
 
         let buffer = module.serialize()?;
-        file.write_all(&buffer)?;
+        eviction::write_atomically(&path, &buffer)?;
+
+        if !self.limits.is_unbounded() {
+            // The artifact is stored anyway, so failing to evict the old
+            // ones isn't an error.
+            let _ = eviction::prune(&self.path, &self.limits);
+        }
 
         Ok(())
     }
@@ -148,4 +177,46 @@ mod tests {
         cache.store(key, &module).unwrap();
         let _restored = unsafe { cache.load(&engine, key).unwrap() };
     }
+
+    #[test]
+    fn test_fs_cache_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = wasmer::Engine::default();
+        let mut cache = FileSystemCache::new(dir.path()).unwrap();
+
+        let modules: Vec<_> = (0..3)
+            .map(|i| {
+                // An empty module with a custom section telling them apart.
+                let bytes = [0, b'a', b's', b'm', 1, 0, 0, 0, 0, 3, 1, b'x', i];
+                let module = Module::new(&engine, bytes).unwrap();
+                (Hash::generate(&bytes), module)
+            })
+            .collect();
+        cache.store(modules[0].0, &modules[0].1).unwrap();
+        let size = std::fs::metadata(dir.path().join(modules[0].0.to_string()))
+            .unwrap()
+            .len();
+        cache.set_limits(CacheLimits {
+            max_size: Some(size * 2 + size / 2),
+            max_age: None,
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cache.store(modules[1].0, &modules[1].1).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        // Using the first module makes the second one the least recently used.
+        unsafe { cache.load(&engine, modules[0].0).unwrap() };
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cache.store(modules[2].0, &modules[2].1).unwrap();
+
+        let mut hashes: Vec<_> = eviction::list_entries(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.module_hash)
+            .collect();
+        hashes.sort();
+        let mut expected = vec![modules[0].0.to_string(), modules[2].0.to_string()];
+        expected.sort();
+        assert_eq!(hashes, expected);
+    }
 }
@@ -16,6 +16,8 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
 mod cache;
+#[cfg(feature = "filesystem")]
+pub mod eviction;
 mod filesystem;
 mod hash;
 
@@ -10,6 +10,7 @@ pub mod list;
 pub mod logs;
 pub mod purge_cache;
 pub mod regions;
+pub mod run;
 pub mod secrets;
 pub mod version;
 pub mod volumes;
@@ -27,6 +28,7 @@ pub enum CmdApp {
     Info(info::CmdAppInfo),
     List(list::CmdAppList),
     Logs(logs::CmdAppLogs),
+    Run(run::CmdAppRun),
     PurgeCache(purge_cache::CmdAppPurgeCache),
     Delete(delete::CmdAppDelete),
     #[clap(subcommand)]
@@ -61,6 +63,7 @@ impl AsyncCliCommand for CmdApp {
             }
             Self::List(cmd) => cmd.run_async().await,
             Self::Logs(cmd) => cmd.run_async().await,
+            Self::Run(cmd) => cmd.run_async().await,
             Self::Delete(cmd) => cmd.run_async().await,
             Self::Version(cmd) => cmd.run_async().await,
             Self::Deploy(cmd) => cmd.run_async().await,
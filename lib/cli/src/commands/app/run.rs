@@ -0,0 +1,402 @@
+//! Run an app locally, the way Wasmer Edge would run it.
+
+use std::{
+    net::SocketAddr,
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use colored::Colorize;
+use wasmer_config::{
+    app::{AppConfigV1, HealthCheckHttpV1, HealthCheckV1, HttpRequest, HttpRequestExpect},
+    package::PackageSource,
+};
+use wasmer_wasix::runners::MappedDirectory;
+
+use crate::{
+    commands::{run::Run, AsyncCliCommand},
+    config::WasmerEnv,
+    logging::Output,
+};
+
+/// How often health checks run when the app doesn't say.
+const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How long a health check request may take when the app doesn't say.
+const DEFAULT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run an app locally, as described by its `app.yaml`.
+///
+/// The app gets its environment variables, CLI arguments, volumes and
+/// capabilities from the `app.yaml`. While it runs, its health checks are
+/// probed and its scheduled tasks are run on their schedule.
+#[derive(clap::Parser, Debug)]
+pub struct CmdAppRun {
+    #[clap(flatten)]
+    pub env: WasmerEnv,
+
+    /// The path to the directory containing the `app.yaml` file.
+    #[clap(long)]
+    pub dir: Option<PathBuf>,
+
+    /// The path to the `app.yaml` file.
+    #[clap(long, conflicts_with = "dir")]
+    pub path: Option<PathBuf>,
+
+    /// The address to serve the app on.
+    #[clap(long, default_value_t = ([127, 0, 0, 1], 8000).into())]
+    pub addr: SocketAddr,
+
+    /// The directory where the app's volumes are kept.
+    ///
+    /// Defaults to `.wasmer/state` next to the `app.yaml` file.
+    #[clap(long)]
+    pub state_dir: Option<PathBuf>,
+
+    /// Don't run the app's health checks.
+    #[clap(long)]
+    pub no_health_checks: bool,
+}
+
+impl CmdAppRun {
+    fn load_app_config(&self) -> Result<(AppConfigV1, PathBuf), anyhow::Error> {
+        let base_dir_path = match (&self.dir, &self.path) {
+            (Some(dir), _) => dir.clone(),
+            (None, Some(path)) => path.clone(),
+            (None, None) => std::env::current_dir()?,
+        };
+
+        let (app_config_path, base_dir_path) = if base_dir_path.is_file() {
+            let base_dir = base_dir_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            (base_dir_path, base_dir)
+        } else if base_dir_path.is_dir() {
+            let f = base_dir_path.join(AppConfigV1::CANONICAL_FILE_NAME);
+            (f, base_dir_path)
+        } else {
+            anyhow::bail!("No such file or directory '{}'", base_dir_path.display());
+        };
+
+        let config_str = std::fs::read_to_string(&app_config_path)
+            .with_context(|| format!("Could not read file '{}'", app_config_path.display()))?;
+        let config = AppConfigV1::parse_yaml(&config_str)
+            .with_context(|| format!("Invalid app config '{}'", app_config_path.display()))?;
+
+        Ok((config, base_dir_path))
+    }
+
+    /// Translate the app config into the options `wasmer run` would use.
+    fn prepare_run(&self, app: &AppConfigV1, base_dir: &Path) -> Result<Run, anyhow::Error> {
+        let input = match &app.package {
+            PackageSource::Path(path) => base_dir.join(path).display().to_string(),
+            other => other.to_string(),
+        };
+        let args = app.cli_args.clone().unwrap_or_default();
+        let mut run = Run::for_package(self.env.clone(), &input, args)?;
+
+        run.wcgi.addr = self.addr;
+        run.scheduled_tasks = app.scheduled_tasks.clone().unwrap_or_default();
+        run.wcgi.force_https = app
+            .redirect
+            .as_ref()
+            .and_then(|redirect| redirect.force_https)
+            .unwrap_or(false);
+
+        let mut env_vars: Vec<_> = app
+            .env
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        env_vars.sort();
+        run.wasi.env_vars.extend(env_vars);
+
+        let state_dir = self
+            .state_dir
+            .clone()
+            .unwrap_or_else(|| base_dir.join(".wasmer").join("state"));
+        for volume in app.volumes.iter().flatten() {
+            let host = volume_dir(&state_dir, &volume.name)?;
+            std::fs::create_dir_all(&host).with_context(|| {
+                format!(
+                    "Unable to create the directory for the \"{}\" volume at \"{}\"",
+                    volume.name,
+                    host.display()
+                )
+            })?;
+            run.wasi.mapped_dirs.push(MappedDirectory {
+                host,
+                guest: volume.mount.clone(),
+            });
+        }
+
+        if let Some(capabilities) = &app.capabilities {
+            if let Some(limit) = capabilities.memory.as_ref().and_then(|m| m.limit) {
+                run.wasi.memory_limit = Some(limit.as_u64());
+            }
+            if capabilities.instaboot.is_some() {
+                eprintln!(
+                    "{}: instaboot snapshots are only created on Wasmer Edge",
+                    "Warning".yellow().bold()
+                );
+            }
+            for name in capabilities.other.keys() {
+                eprintln!(
+                    "{}: ignoring the unknown \"{name}\" capability",
+                    "Warning".yellow().bold()
+                );
+            }
+        }
+
+        Ok(run)
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncCliCommand for CmdAppRun {
+    type Output = ();
+
+    async fn run_async(self) -> Result<(), anyhow::Error> {
+        let (app, base_dir) = self.load_app_config()?;
+        let run = self.prepare_run(&app, &base_dir)?;
+
+        if !self.no_health_checks {
+            let force_https = run.wcgi.force_https;
+            for HealthCheckV1::Http(check) in app.health_checks.iter().flatten().cloned() {
+                tokio::spawn(monitor_health(check, self.addr, force_https));
+            }
+        }
+
+        // Note: `wasmer run` sets up its own async runtime and exits the
+        // process once the app stops, so it gets a thread of its own.
+        let app = std::thread::spawn(move || run.execute(Output::default()));
+
+        match tokio::task::spawn_blocking(move || app.join()).await? {
+            Ok(()) => Ok(()),
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+/// Keep probing a health check, reporting whenever the app becomes healthy or
+/// unhealthy.
+async fn monitor_health(check: HealthCheckHttpV1, addr: SocketAddr, force_https: bool) {
+    let interval = parse_duration(check.interval.as_deref(), DEFAULT_HEALTH_CHECK_INTERVAL);
+    let healthy_threshold = check.healthy_threshold.unwrap_or(1).max(1);
+    let unhealthy_threshold = check.unhealthy_threshold.unwrap_or(1).max(1);
+    let path = &check.request.path;
+
+    let client = match reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!(
+                error = &e as &dyn std::error::Error,
+                "Unable to create an HTTP client"
+            );
+            return;
+        }
+    };
+
+    // Health checks only start once the app is listening.
+    while tokio::net::TcpStream::connect(addr).await.is_err() {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let mut healthy = None;
+    let mut successes = 0;
+    let mut failures = 0;
+
+    loop {
+        match probe(&client, &check.request, addr, force_https).await {
+            Ok(()) => {
+                failures = 0;
+                successes += 1;
+                if healthy != Some(true) && successes >= healthy_threshold {
+                    healthy = Some(true);
+                    eprintln!("{} Health check {path} passed", "✔".green().bold());
+                }
+            }
+            Err(e) => {
+                successes = 0;
+                failures += 1;
+                if healthy != Some(false) && failures >= unhealthy_threshold {
+                    healthy = Some(false);
+                    eprintln!(
+                        "{} Health check {path} failed {failures} time(s): {e:#}",
+                        "✘".red().bold()
+                    );
+                }
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn probe(
+    client: &reqwest::Client,
+    request: &HttpRequest,
+    addr: SocketAddr,
+    force_https: bool,
+) -> Result<(), anyhow::Error> {
+    let method = request.method.as_deref().unwrap_or("GET");
+    let method = reqwest::Method::from_bytes(method.as_bytes())
+        .with_context(|| format!("Invalid HTTP method \"{method}\""))?;
+    let path = request.path.trim_start_matches('/');
+    let timeout = parse_duration(request.timeout.as_deref(), DEFAULT_HEALTH_CHECK_TIMEOUT);
+
+    let mut builder = client
+        .request(method, format!("http://{addr}/{path}"))
+        .timeout(timeout);
+    if force_https {
+        // The app only redirects requests that didn't come through HTTPS
+        builder = builder.header("x-forwarded-proto", "https");
+    }
+    for header in request.headers.iter().flatten() {
+        builder = builder.header(&header.name, &header.value);
+    }
+    if let Some(body) = &request.body {
+        builder = builder.body(body.clone());
+    }
+
+    let response = builder.send().await.context("The request failed")?;
+    let status = response.status().as_u16();
+    let body = response
+        .text()
+        .await
+        .context("Unable to read the response body")?;
+
+    check_response(request.expect.as_ref(), status, &body)
+}
+
+/// Check a health check's response against what the app expects.
+///
+/// Without any expectations, a response is fine as long as its status is
+/// successful.
+fn check_response(
+    expect: Option<&HttpRequestExpect>,
+    status: u16,
+    body: &str,
+) -> Result<(), anyhow::Error> {
+    match expect.and_then(|e| e.status_codes.as_ref()) {
+        Some(codes) => anyhow::ensure!(
+            codes.contains(&status),
+            "Expected a status code in {codes:?}, but got {status}"
+        ),
+        None => anyhow::ensure!(
+            (200..300).contains(&status),
+            "Expected a successful status code, but got {status}"
+        ),
+    }
+
+    if let Some(text) = expect.and_then(|e| e.body_includes.as_ref()) {
+        anyhow::ensure!(
+            body.contains(text.as_str()),
+            "The response body doesn't include {text:?}"
+        );
+    }
+
+    if let Some(pattern) = expect.and_then(|e| e.body_regex.as_ref()) {
+        let regex = regex::Regex::new(pattern)
+            .with_context(|| format!("Invalid body regex {pattern:?}"))?;
+        anyhow::ensure!(
+            regex.is_match(body),
+            "The response body doesn't match {pattern:?}"
+        );
+    }
+
+    Ok(())
+}
+
+/// The host directory backing a volume.
+///
+/// Volume names come straight from the `app.yaml`, so anything that isn't a
+/// plain directory name (e.g. `/etc` or `../x`) is rejected rather than
+/// letting it point outside the state directory.
+fn volume_dir(state_dir: &Path, name: &str) -> Result<PathBuf, anyhow::Error> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(state_dir.join("volumes").join(name)),
+        _ => anyhow::bail!("Invalid volume name \"{name}\": expected a plain directory name"),
+    }
+}
+
+fn parse_duration(value: Option<&str>, default: Duration) -> Duration {
+    match value {
+        Some(value) => humantime::parse_duration(value).unwrap_or_else(|e| {
+            tracing::warn!(%value, error = &e as &dyn std::error::Error, "Invalid duration");
+            default
+        }),
+        None => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn successful_status_codes_are_healthy_by_default() {
+        assert!(check_response(None, 200, "").is_ok());
+        assert!(check_response(None, 204, "").is_ok());
+
+        let err = check_response(None, 503, "").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected a successful status code, but got 503"
+        );
+    }
+
+    #[test]
+    fn volumes_stay_inside_the_state_dir() {
+        let state_dir = Path::new("/state");
+
+        assert_eq!(
+            volume_dir(state_dir, "data").unwrap(),
+            Path::new("/state/volumes/data")
+        );
+
+        let err = volume_dir(state_dir, "/etc").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid volume name \"/etc\": expected a plain directory name"
+        );
+        assert!(volume_dir(state_dir, "../x").is_err());
+        assert!(volume_dir(state_dir, "a/b").is_err());
+        assert!(volume_dir(state_dir, "..").is_err());
+        assert!(volume_dir(state_dir, "").is_err());
+    }
+
+    #[test]
+    fn check_the_expected_response() {
+        let expect = HttpRequestExpect {
+            status_codes: Some(vec![200, 418]),
+            body_includes: Some("teapot".to_string()),
+            body_regex: Some(r"^I'm a \w+$".to_string()),
+        };
+
+        assert!(check_response(Some(&expect), 418, "I'm a teapot").is_ok());
+
+        let err = check_response(Some(&expect), 500, "I'm a teapot").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected a status code in [200, 418], but got 500"
+        );
+
+        let err = check_response(Some(&expect), 200, "I'm a kettle").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The response body doesn't include \"teapot\""
+        );
+
+        let err = check_response(Some(&expect), 200, "I'm a teapot!").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"The response body doesn't match "^I'm a \\w+$""#
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use crate::config::WasmerEnv;
 use anyhow::Result;
+use bytesize::ByteSize;
 use clap::Parser;
-use std::{fs, path::Path};
+use comfy_table::Table;
+use std::{collections::BTreeMap, fs, path::Path, time::SystemTime};
+use wasmer_cache::eviction::{self, CacheEntry, CacheLimits};
 
 #[derive(Debug, Parser)]
 /// The options for the `wasmer cache` subcommand
@@ -18,25 +21,72 @@ impl Cache {
     pub fn execute(&self) -> Result<()> {
         let cache_dir = self.env.cache_dir();
 
-        match self.cmd {
+        match &self.cmd {
             Cmd::Clean => {
                 clean(cache_dir)?;
             }
             Cmd::Dir => {
                 println!("{}", self.env.cache_dir().display());
             }
+            Cmd::Stats => {
+                stats(&cache_dir.join("compiled"))?;
+            }
+            Cmd::List => {
+                list(&cache_dir.join("compiled"))?;
+            }
+            Cmd::Prune(prune) => {
+                prune.execute(&cache_dir.join("compiled"))?;
+            }
         }
 
         Ok(())
     }
 }
 
-#[derive(Debug, Copy, Clone, Parser)]
+#[derive(Debug, Clone, Parser)]
 enum Cmd {
     /// Clear the cache
     Clean,
     /// Display the location of the cache
     Dir,
+    /// Display the number and size of the compiled modules in the cache
+    Stats,
+    /// List the compiled modules in the cache, from the least to the most
+    /// recently used
+    List,
+    /// Evict the least recently used compiled modules from the cache
+    Prune(Prune),
+}
+
+#[derive(Debug, Clone, Parser)]
+struct Prune {
+    /// Evict modules until the cache is at most this size (e.g. `10GB`)
+    #[clap(long)]
+    max_size: Option<ByteSize>,
+    /// Evict the modules not used for this long (e.g. `30d`)
+    #[clap(long)]
+    max_age: Option<humantime::Duration>,
+}
+
+impl Prune {
+    fn execute(&self, dir: &Path) -> Result<()> {
+        let limits = CacheLimits {
+            max_size: self.max_size.map(|size| size.as_u64()),
+            max_age: self.max_age.map(Into::into),
+        };
+        if limits.is_unbounded() {
+            anyhow::bail!("Either --max-size or --max-age must be specified");
+        }
+        let stats = eviction::prune(dir, &limits)?;
+        eprintln!(
+            "Evicted {} module(s), freeing {}. {} module(s) left, using {}.",
+            stats.removed,
+            ByteSize(stats.freed),
+            stats.remaining,
+            ByteSize(stats.remaining_size)
+        );
+        Ok(())
+    }
 }
 
 fn clean(cache_dir: &Path) -> Result<()> {
@@ -48,3 +98,73 @@ fn clean(cache_dir: &Path) -> Result<()> {
 
     Ok(())
 }
+
+fn stats(dir: &Path) -> Result<()> {
+    let entries = eviction::list_entries(dir)?;
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+    println!("Location: {}", dir.display());
+    println!("Modules: {}", entries.len());
+    println!("Size: {}", ByteSize(total));
+    if let (Some(oldest), Some(newest)) = (entries.first(), entries.last()) {
+        println!("Least recently used: {}", last_used(oldest));
+        println!("Most recently used: {}", last_used(newest));
+    }
+
+    let mut engines: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+    for entry in &entries {
+        let engine = engines.entry(engine_id(entry)).or_default();
+        engine.0 += 1;
+        engine.1 += entry.size;
+    }
+    if !engines.is_empty() {
+        let mut table = Table::new();
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec!["Engine", "Modules", "Size"]);
+        table.add_rows(engines.iter().map(|(engine, (count, size))| {
+            vec![
+                engine.to_string(),
+                count.to_string(),
+                ByteSize(*size).to_string(),
+            ]
+        }));
+        println!();
+        println!("{table}");
+    }
+    Ok(())
+}
+
+fn list(dir: &Path) -> Result<()> {
+    let entries = eviction::list_entries(dir)?;
+    if entries.is_empty() {
+        eprintln!("The cache is empty.");
+        return Ok(());
+    }
+    let mut table = Table::new();
+    table.load_preset(comfy_table::presets::NOTHING);
+    table.set_header(vec!["Engine", "Module hash", "Size", "Last used"]);
+    table.add_rows(entries.iter().map(|entry| {
+        vec![
+            engine_id(entry).to_string(),
+            entry.module_hash.clone(),
+            ByteSize(entry.size).to_string(),
+            last_used(entry),
+        ]
+    }));
+    println!("{table}");
+    Ok(())
+}
+
+fn engine_id(entry: &CacheEntry) -> &str {
+    entry.engine_id.as_deref().unwrap_or("-")
+}
+
+fn last_used(entry: &CacheEntry) -> String {
+    match SystemTime::now().duration_since(entry.last_access) {
+        Ok(elapsed) => {
+            // Seconds are enough, and make the output readable.
+            let elapsed = std::time::Duration::from_secs(elapsed.as_secs());
+            format!("{} ago", humantime::format_duration(elapsed))
+        }
+        Err(_) => "just now".to_string(),
+    }
+}
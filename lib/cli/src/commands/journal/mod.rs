@@ -8,6 +8,7 @@ mod import;
 mod inspect;
 #[cfg(feature = "fuse")]
 mod mount;
+mod replay;
 
 pub use compact::*;
 pub use export::*;
@@ -17,6 +18,7 @@ pub use import::*;
 pub use inspect::*;
 #[cfg(feature = "fuse")]
 pub use mount::*;
+pub use replay::*;
 
 /// Manage Journal files.
 #[derive(clap::Subcommand, Debug)]
@@ -36,6 +38,9 @@ pub enum CmdJournal {
     Mount(CmdJournalMount),
     /// Extracts an element of a journal
     Extract(CmdJournalExtract),
+    /// Replays a journal against a module to inspect the process at any event,
+    /// or diffs two journals
+    Replay(CmdJournalReplay),
 }
 
 impl CliCommand for CmdJournal {
@@ -51,6 +56,7 @@ impl CliCommand for CmdJournal {
             #[cfg(feature = "fuse")]
             Self::Mount(cmd) => cmd.run(),
             Self::Extract(cmd) => cmd.run(),
+            Self::Replay(cmd) => cmd.run(),
         }
     }
 }
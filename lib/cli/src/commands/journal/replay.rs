@@ -0,0 +1,511 @@
+use std::{
+    io::{BufRead, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context};
+use clap::Parser;
+use virtual_net::LoopbackNetworking;
+use wasmer::{Module, Store};
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    fs::Kind,
+    journal::{JournalEntry, JournalReplayer, LogFileJournal, ReadableJournal},
+    runtime::task_manager::tokio::TokioTaskManager,
+    PluggableRuntime, RewindState, Runtime, WasiThreadId,
+};
+
+use crate::{
+    commands::{run::Wasi, CliCommand},
+    store::StoreOptions,
+};
+
+/// Replays a journal against the module that recorded it, pausing at a
+/// chosen event so the state of the process can be inspected
+#[derive(Debug, Parser)]
+pub struct CmdJournalReplay {
+    #[clap(flatten)]
+    store: StoreOptions,
+
+    /// The file system and environment to replay the journal in, which
+    /// should match the ones it was recorded with. Changes to the mapped
+    /// directories are kept in memory, so the host directories are left as
+    /// they are.
+    #[clap(flatten)]
+    wasi: Wasi,
+
+    /// Path to the journal that will be replayed
+    #[clap(index = 1)]
+    journal_path: PathBuf,
+
+    /// Path to the WebAssembly module that recorded the journal (not needed
+    /// when only diffing journals)
+    #[clap(index = 2, required_unless_present = "diff")]
+    module_path: Option<PathBuf>,
+
+    /// Stop before replaying the event with this index (defaults to the end
+    /// of the journal, or to its start when stepping through it interactively)
+    #[clap(long)]
+    until: Option<usize>,
+
+    /// Print a region of memory once the replay stops, as `<address>:<length>`
+    #[clap(long = "memory", value_parser = parse_memory_region)]
+    memory_regions: Vec<Range<u64>>,
+
+    /// Print the open file descriptors once the replay stops
+    #[clap(long)]
+    fds: bool,
+
+    /// Print the state of the threads once the replay stops
+    #[clap(long)]
+    threads: bool,
+
+    /// Step through the journal interactively once the replay stops
+    #[clap(short, long)]
+    interactive: bool,
+
+    /// Compare the journal with another journal event by event, and stop
+    /// the replay at the first event that differs
+    #[clap(long)]
+    diff: Option<PathBuf>,
+}
+
+impl CliCommand for CmdJournalReplay {
+    type Output = ();
+
+    fn run(mut self) -> Result<(), anyhow::Error> {
+        let events = read_events(&self.journal_path)?;
+        // Every restart then begins with the mapped directories as they were
+        self.wasi.copy_on_write_mapped_dirs = true;
+
+        let mut until = self.until;
+        if let Some(other) = &self.diff {
+            let other_events = read_events(other)?;
+            let first_difference = print_diff(&events, &other_events);
+            until = until.or(first_difference);
+        }
+
+        let Some(module_path) = &self.module_path else {
+            return Ok(());
+        };
+
+        let wasm = std::fs::read(module_path)
+            .with_context(|| format!("Unable to read \"{}\"", module_path.display()))?;
+        let (store, _) = self.store.get_store()?;
+        let engine = store.engine().clone();
+        let module = Module::new(&engine, &wasm)
+            .with_context(|| format!("Unable to compile \"{}\"", module_path.display()))?;
+        // The same hash `wasmer run` gives a WebAssembly file, so the
+        // journal's memory and thread events apply to it
+        let module_hash = ModuleHash::xxhash(&wasm);
+
+        let tokio = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let _guard = tokio.enter();
+        let mut runtime =
+            PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio.handle().clone())));
+        runtime.set_engine(Some(engine.clone()));
+        // Sockets are replayed against an in-memory network, so replaying a
+        // journal never binds or connects sockets on the host
+        runtime.set_networking_implementation(LoopbackNetworking::new());
+        let runtime: Arc<dyn Runtime + Send + Sync> = Arc::new(runtime);
+
+        let program_name = module_path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let default_target = if self.interactive { 0 } else { events.len() };
+        let mut target = until.unwrap_or(default_target).min(events.len());
+
+        // Seeking backwards means starting again from a fresh instance
+        loop {
+            let mut store = Store::new(engine.clone());
+            let (_instance, env) = self
+                .wasi
+                .prepare(&module, program_name.clone(), Vec::new(), runtime.clone())?
+                .instantiate_ext(module.clone(), module_hash, &mut store)?;
+            let mut replayer = JournalReplayer::new(env.env.clone().into_mut(&mut store));
+
+            replay_to(&mut replayer, &events, target)?;
+            if replayer.module_hash_matches() == Some(false) {
+                eprintln!(
+                    "Warning: the journal was recorded by a different module, so memory and thread events were skipped"
+                );
+            }
+
+            if !self.interactive {
+                print_position(&replayer, &events);
+                for region in &self.memory_regions {
+                    print_memory(&replayer, region.clone())?;
+                }
+                if self.fds {
+                    print_fds(&replayer);
+                }
+                if self.threads {
+                    print_threads(&replayer);
+                }
+                return Ok(());
+            }
+
+            match repl(&mut replayer, &events)? {
+                Some(index) => target = index,
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+fn read_events(path: &Path) -> Result<Vec<JournalEntry<'static>>, anyhow::Error> {
+    let journal = LogFileJournal::new(path)
+        .with_context(|| format!("Unable to open the journal at \"{}\"", path.display()))?;
+
+    let mut events = Vec::new();
+    while let Some(event) = journal.read()? {
+        events.push(event.into_inner().into_owned());
+    }
+
+    Ok(events)
+}
+
+#[allow(clippy::result_large_err)]
+fn replay_to(
+    replayer: &mut JournalReplayer<'static, '_>,
+    events: &[JournalEntry<'static>],
+    target: usize,
+) -> Result<(), anyhow::Error> {
+    while replayer.events_replayed() < target.min(events.len()) {
+        let index = replayer.events_replayed();
+        // Safety: we are the thread that owns the store
+        unsafe { replayer.play(events[index].clone()) }
+            .with_context(|| format!("Unable to replay event #{index}"))?;
+    }
+
+    Ok(())
+}
+
+/// Step through the journal until the user quits, returning the event to
+/// restart from if they seek backwards.
+fn repl(
+    replayer: &mut JournalReplayer<'static, '_>,
+    events: &[JournalEntry<'static>],
+) -> Result<Option<usize>, anyhow::Error> {
+    print_position(replayer, events);
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(replay) ");
+        std::io::stdout().flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            return Ok(None);
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        let result = match words.as_slice() {
+            [] => Ok(()),
+            ["step" | "s"] => step(replayer, events, 1),
+            ["step" | "s", count] => count
+                .parse()
+                .map_err(anyhow::Error::from)
+                .and_then(|count| step(replayer, events, count)),
+            ["seek", index] => match index.parse::<usize>() {
+                Ok(index) if index < replayer.events_replayed() => return Ok(Some(index)),
+                Ok(index) => {
+                    replay_to(replayer, events, index).map(|_| print_position(replayer, events))
+                }
+                Err(e) => Err(e.into()),
+            },
+            ["event" | "e"] => {
+                print_event(events, replayer.events_replayed());
+                Ok(())
+            }
+            ["event" | "e", index] => index.parse().map_err(Into::into).map(|index| {
+                print_event(events, index);
+            }),
+            ["memory" | "m", region] => {
+                parse_memory_region(region).and_then(|region| print_memory(replayer, region))
+            }
+            ["fds"] => {
+                print_fds(replayer);
+                Ok(())
+            }
+            ["threads"] => {
+                print_threads(replayer);
+                Ok(())
+            }
+            ["help" | "h"] => {
+                print_help();
+                Ok(())
+            }
+            ["quit" | "q"] => return Ok(None),
+            _ => Err(anyhow::anyhow!("Unknown command, type \"help\" for help")),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Error: {e:#}");
+        }
+    }
+}
+
+fn step(
+    replayer: &mut JournalReplayer<'static, '_>,
+    events: &[JournalEntry<'static>],
+    count: usize,
+) -> Result<(), anyhow::Error> {
+    let target = replayer.events_replayed().saturating_add(count);
+    replay_to(replayer, events, target)?;
+    print_position(replayer, events);
+    Ok(())
+}
+
+fn print_help() {
+    println!("step, s [COUNT]        replay the next COUNT events (defaults to 1)");
+    println!("seek INDEX             replay (or rewind) to just before event INDEX");
+    println!("event, e [INDEX]       print an event (defaults to the next one)");
+    println!("memory, m ADDR:LEN     print a region of memory");
+    println!("fds                    print the open file descriptors");
+    println!("threads                print the state of the threads");
+    println!("quit, q                stop replaying");
+}
+
+fn print_position(replayer: &JournalReplayer<'static, '_>, events: &[JournalEntry<'static>]) {
+    let index = replayer.events_replayed();
+    println!("Replayed {index} of {} events", events.len());
+    if index < events.len() {
+        print_event(events, index);
+    }
+}
+
+fn print_event(events: &[JournalEntry<'static>], index: usize) {
+    match events.get(index) {
+        Some(event) => println!("#{index}: {event}"),
+        None => println!("#{index}: end of journal"),
+    }
+}
+
+fn print_memory(
+    replayer: &JournalReplayer<'static, '_>,
+    region: Range<u64>,
+) -> Result<(), anyhow::Error> {
+    let size = replayer.memory_size();
+    if region.start >= size && !region.is_empty() {
+        bail!(
+            "The address {:#x} is past the end of the memory ({size:#x} bytes)",
+            region.start
+        );
+    }
+
+    // The memory is read a page at a time, so a huge length doesn't allocate
+    // a huge buffer
+    let end = region.end.min(size);
+    let mut address = region.start;
+    while address < end {
+        let mut buffer = vec![0_u8; (end - address).min(4096) as usize];
+        replayer
+            .read_memory(address, &mut buffer)
+            .with_context(|| format!("Unable to read memory at {address:#x}"))?;
+        print_hex_dump(address, &buffer);
+        address += buffer.len() as u64;
+    }
+    if end < region.end {
+        println!("(the memory ends at {size:#x})");
+    }
+
+    Ok(())
+}
+
+fn print_hex_dump(address: u64, buffer: &[u8]) {
+    for (i, line) in buffer.chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|byte| format!("{byte:02x}")).collect();
+        let ascii: String = line
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        println!(
+            "{:#010x}  {:<47}  |{ascii}|",
+            address + i as u64 * 16,
+            hex.join(" ")
+        );
+    }
+}
+
+fn print_fds(replayer: &JournalReplayer<'static, '_>) {
+    println!("{:<6} {:<8} {:<10} name", "fd", "kind", "offset");
+    for (fd, entry) in replayer.file_descriptors() {
+        let (kind, path) = match &*entry.inode.read() {
+            Kind::File { path, .. } => ("file", Some(path.clone())),
+            Kind::Dir { path, .. } => ("dir", Some(path.clone())),
+            Kind::Socket { .. } => ("socket", None),
+            Kind::Pipe { .. } => ("pipe", None),
+            Kind::Epoll { .. } => ("epoll", None),
+            Kind::Root { .. } => ("root", None),
+            Kind::Symlink { .. } => ("symlink", None),
+            Kind::Buffer { .. } => ("buffer", None),
+            Kind::EventNotifications { .. } => ("event", None),
+        };
+        let offset = entry.offset.load(std::sync::atomic::Ordering::SeqCst);
+
+        match path {
+            Some(path) if !path.as_os_str().is_empty() => println!(
+                "{fd:<6} {kind:<8} {offset:<10} {} ({})",
+                entry.inode.name,
+                path.display()
+            ),
+            _ => println!("{fd:<6} {kind:<8} {offset:<10} {}", entry.inode.name),
+        }
+    }
+}
+
+fn print_threads(replayer: &JournalReplayer<'static, '_>) {
+    let main = replayer.ctx().data().tid();
+    let threads = replayer
+        .main_thread()
+        .map(|state| (main, state))
+        .into_iter()
+        .chain(replayer.threads().iter().map(|(id, state)| (*id, state)));
+
+    let mut any = false;
+    for (id, state) in threads {
+        any = true;
+        print_thread(id, state);
+    }
+    if !any {
+        println!("No thread state has been recorded yet");
+    }
+}
+
+fn print_thread(id: WasiThreadId, state: &RewindState) {
+    let layout = &state.layout;
+    println!(
+        "thread {id}: {:?}, stack {:#x}..{:#x} ({} bytes), {}",
+        state.start,
+        layout.stack_lower,
+        layout.stack_upper,
+        layout.stack_size,
+        if state.is_64bit { "64-bit" } else { "32-bit" },
+    );
+    println!(
+        "    memory stack: {} bytes, call stack: {} bytes, store data: {} bytes",
+        state.memory_stack.len(),
+        state.rewind_stack.len(),
+        state.store_data.len(),
+    );
+}
+
+/// Print the events that differ between two journals, returning the index
+/// of the first one.
+fn print_diff(left: &[JournalEntry<'static>], right: &[JournalEntry<'static>]) -> Option<usize> {
+    let mut first = None;
+    let mut count = 0;
+
+    for index in 0..left.len().max(right.len()) {
+        let (l, r) = (left.get(index), right.get(index));
+        if events_match(l, r) {
+            continue;
+        }
+
+        first.get_or_insert(index);
+        count += 1;
+        println!("#{index}:");
+        match l {
+            Some(event) => println!("-   {event}"),
+            None => println!("-   (end of journal)"),
+        }
+        match r {
+            Some(event) => println!("+   {event}"),
+            None => println!("+   (end of journal)"),
+        }
+    }
+
+    match first {
+        Some(index) => println!(
+            "The journals diverge at event #{index} ({count} of {} events differ)",
+            left.len().max(right.len())
+        ),
+        None => println!("The journals are identical"),
+    }
+
+    first
+}
+
+/// Compare two events, ignoring details that differ between otherwise
+/// identical executions (like when a snapshot was taken).
+fn events_match(left: Option<&JournalEntry<'_>>, right: Option<&JournalEntry<'_>>) -> bool {
+    match (left, right) {
+        (
+            Some(JournalEntry::SnapshotV1 { trigger: left, .. }),
+            Some(JournalEntry::SnapshotV1 { trigger: right, .. }),
+        ) => left == right,
+        (left, right) => left == right,
+    }
+}
+
+fn parse_memory_region(s: &str) -> Result<Range<u64>, anyhow::Error> {
+    let (address, length) = s
+        .split_once(':')
+        .context("Expected a memory region like \"0x1000:64\"")?;
+
+    let parse = |s: &str| match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    let address = parse(address).with_context(|| format!("Invalid address, \"{address}\""))?;
+    let length = parse(length).with_context(|| format!("Invalid length, \"{length}\""))?;
+    let end = address
+        .checked_add(length)
+        .context("The memory region extends past the end of the address space")?;
+
+    Ok(address..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use wasmer_wasix::journal::SnapshotTrigger;
+
+    use super::*;
+
+    #[test]
+    fn parse_memory_regions() {
+        assert_eq!(parse_memory_region("0x1000:16").unwrap(), 0x1000..0x1010);
+        assert_eq!(parse_memory_region("4096:0x10").unwrap(), 4096..4112);
+        assert!(parse_memory_region("0x1000").is_err());
+        assert!(parse_memory_region("foo:16").is_err());
+        assert!(parse_memory_region("0xffffffffffffffff:2").is_err());
+    }
+
+    #[test]
+    fn diff_ignores_snapshot_times() {
+        let snapshot = |trigger| JournalEntry::SnapshotV1 {
+            when: SystemTime::now(),
+            trigger,
+        };
+        let left = vec![
+            JournalEntry::InitModuleV1 {
+                wasm_hash: Box::new([1; 8]),
+            },
+            snapshot(SnapshotTrigger::Idle),
+            JournalEntry::ProcessExitV1 { exit_code: None },
+        ];
+        let mut right = left.clone();
+        right[1] = snapshot(SnapshotTrigger::Idle);
+
+        assert_eq!(print_diff(&left, &right), None);
+
+        right.push(JournalEntry::ClearEtherealV1);
+        assert_eq!(print_diff(&left, &right), Some(3));
+        right[1] = snapshot(SnapshotTrigger::FirstListen);
+        assert_eq!(print_diff(&left, &right), Some(1));
+    }
+}
@@ -194,6 +194,10 @@ impl WasmerCmd {
                 Package::Push(cmd) => cmd.run(),
                 Package::Publish(cmd) => cmd.run().map(|_| ()),
                 Package::Unpack(cmd) => cmd.execute(),
+                Package::Vendor(cmd) => cmd.run(),
+                Package::Keygen(cmd) => cmd.execute(),
+                Package::Sign(cmd) => cmd.execute(),
+                Package::Verify(cmd) => cmd.execute(),
             },
             Some(Cmd::Container(cmd)) => match cmd {
                 crate::commands::Container::Unpack(cmd) => cmd.execute(),
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use anyhow::Context;
 use dialoguer::console::{style, Emoji};
@@ -6,12 +6,19 @@ use indicatif::ProgressBar;
 use sha2::Digest;
 use wasmer_config::package::PackageHash;
 use wasmer_package::package::Package;
+use wasmer_wasix::{
+    bin_factory::{BinaryPackage, LockfileMode},
+    runtime::resolver::Source,
+};
 
-use crate::utils::load_package_manifest;
+use crate::{commands::run::Wasi, config::WasmerEnv, utils::load_package_manifest};
 
 /// Build a container from a package manifest.
 #[derive(clap::Parser, Debug)]
 pub struct PackageBuild {
+    #[clap(flatten)]
+    env: WasmerEnv,
+
     /// Output path for the package file.
     /// Defaults to current directory + [name]-[version].webc.
     #[clap(short = 'o', long)]
@@ -29,9 +36,15 @@ pub struct PackageBuild {
     /// Only checks whether the package could be built successfully
     #[clap(long)]
     check: bool,
+
+    /// Fail instead of updating the package's wasmer.lock when its
+    /// dependencies would resolve to different versions.
+    #[clap(long)]
+    locked: bool,
 }
 
 static READING_MANIFEST_EMOJI: Emoji<'_, '_> = Emoji("📖 ", "");
+static RESOLVING_EMOJI: Emoji<'_, '_> = Emoji("🔍 ", "");
 static CREATING_OUTPUT_DIRECTORY_EMOJI: Emoji<'_, '_> = Emoji("📁 ", "");
 static WRITING_PACKAGE_EMOJI: Emoji<'_, '_> = Emoji("📦 ", "");
 static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", ":-)");
@@ -39,10 +52,12 @@ static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", ":-)");
 impl PackageBuild {
     pub(crate) fn check(package_path: PathBuf) -> Self {
         PackageBuild {
+            env: WasmerEnv::default(),
             out: None,
             quiet: true,
             package: Some(package_path),
             check: true,
+            locked: false,
         }
     }
 
@@ -61,6 +76,7 @@ impl PackageBuild {
         let data = pkg.serialize().context("While validating the package")?;
         let hash = sha2::Sha256::digest(&data).into();
         let pkg_hash = PackageHash::from_sha256_bytes(hash);
+        let has_dependencies = !manifest.dependencies.is_empty();
 
         let name = if let Some(manifest_pkg) = manifest.package {
             if let Some(name) = manifest_pkg.name {
@@ -85,7 +101,7 @@ impl PackageBuild {
 
         pb.println(format!(
             "{} {}Reading manifest...",
-            style("[1/3]").bold().dim(),
+            style("[1/4]").bold().dim(),
             READING_MANIFEST_EMOJI
         ));
 
@@ -95,9 +111,26 @@ impl PackageBuild {
             return Ok((pkg, pkg_hash));
         }
 
+        pb.println(format!(
+            "{} {}Resolving dependencies...",
+            style("[2/4]").bold().dim(),
+            RESOLVING_EMOJI
+        ));
+
+        // The dependencies aren't part of the package, but resolving them
+        // checks or updates the wasmer.lock next to the manifest
+        if has_dependencies {
+            let dir = manifest_path
+                .canonicalize()?
+                .parent()
+                .context("the manifest has no parent directory")?
+                .to_path_buf();
+            tokio::runtime::Runtime::new()?.block_on(self.resolve_dependencies(dir))?;
+        }
+
         pb.println(format!(
             "{} {}Creating output directory...",
-            style("[2/3]").bold().dim(),
+            style("[3/4]").bold().dim(),
             CREATING_OUTPUT_DIRECTORY_EMOJI
         ));
 
@@ -126,7 +159,7 @@ impl PackageBuild {
 
         pb.println(format!(
             "{} {}Writing package...",
-            style("[3/3]").bold().dim(),
+            style("[4/4]").bold().dim(),
             WRITING_PACKAGE_EMOJI
         ));
 
@@ -142,6 +175,24 @@ impl PackageBuild {
         Ok((pkg, pkg_hash))
     }
 
+    async fn resolve_dependencies(&self, dir: PathBuf) -> Result<(), anyhow::Error> {
+        let client =
+            wasmer_wasix::http::default_http_client().context("No HTTP client available")?;
+        // The packages must be the ones the lockfile pins
+        let mut wasi = Wasi::default();
+        wasi.verify_package_hashes = true;
+        let source: Arc<dyn Source + Send + Sync> =
+            Arc::new(wasi.prepare_source(&self.env, Arc::new(client), webc::Version::V3)?);
+
+        let mode = if self.locked {
+            LockfileMode::Locked
+        } else {
+            LockfileMode::Update
+        };
+        BinaryPackage::resolve_dir(&dir, mode, source).await?;
+        Ok(())
+    }
+
     fn manifest_path(&self) -> Result<PathBuf, anyhow::Error> {
         let path = if let Some(p) = &self.package {
             if p.is_dir() {
@@ -207,10 +258,12 @@ description = "hello"
         std::fs::write(path.join("data").join("hello.txt"), "Hello, world!").unwrap();
 
         let cmd = PackageBuild {
+            env: WasmerEnv::default(),
             package: Some(path.to_owned()),
             out: Some(path.to_owned()),
             quiet: true,
             check: false,
+            locked: false,
         };
 
         cmd.execute().unwrap();
@@ -16,6 +16,7 @@ use wasmer_config::package::{Manifest, NamedPackageIdent, PackageHash};
 use wasmer_package::package::Package;
 
 pub mod macros;
+pub mod signing;
 pub mod wait;
 
 pub(super) fn on_error(e: anyhow::Error) -> anyhow::Error {
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use anyhow::Context;
+use wasmer_config::package::PackageHash;
+use wasmer_package::signature::{PackageSignature, PublicKey, SigningKey};
+use wasmer_wasix::runtime::resolver::WebcHash;
+
+/// Compute the [`PackageHash`] of a `*.webc` file.
+pub(crate) fn webc_hash(webc: &Path) -> Result<PackageHash, anyhow::Error> {
+    let contents =
+        std::fs::read(webc).with_context(|| format!("could not read '{}'", webc.display()))?;
+
+    Ok(PackageHash::from_sha256_bytes(
+        WebcHash::sha256(contents).as_bytes(),
+    ))
+}
+
+pub(crate) fn load_public_key(path: &Path) -> Result<PublicKey, anyhow::Error> {
+    std::fs::read_to_string(path)
+        .with_context(|| format!("could not read '{}'", path.display()))?
+        .parse()
+        .with_context(|| format!("'{}' isn't a valid public key", path.display()))
+}
+
+pub(crate) fn load_signing_key(path: &Path) -> Result<SigningKey, anyhow::Error> {
+    std::fs::read_to_string(path)
+        .with_context(|| format!("could not read '{}'", path.display()))?
+        .parse()
+        .with_context(|| format!("'{}' isn't a valid secret key", path.display()))
+}
+
+/// Read a signature file, returning `None` if it doesn't exist.
+pub(crate) fn read_signature(path: &Path) -> Result<Option<PackageSignature>, anyhow::Error> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("could not read '{}'", path.display()));
+        }
+    };
+
+    let signature = contents
+        .parse()
+        .with_context(|| format!("'{}' isn't a valid signature", path.display()))?;
+
+    Ok(Some(signature))
+}
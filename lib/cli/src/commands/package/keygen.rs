@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use wasmer_package::signature::SigningKey;
+
+/// Generate an ed25519 key pair for signing packages.
+///
+/// This writes a secret key (`<name>.key`) for `wasmer package sign`, and a
+/// public key (`<name>.pub`) to share with anyone that needs to verify
+/// your packages.
+#[derive(clap::Parser, Debug)]
+pub struct PackageKeygen {
+    /// The directory to write the keys to.
+    #[clap(short = 'o', long, default_value = ".")]
+    pub out_dir: PathBuf,
+
+    /// The name of the key files.
+    #[clap(long, default_value = "wasmer")]
+    pub name: String,
+
+    /// Overwrite existing keys.
+    #[clap(long)]
+    pub force: bool,
+
+    /// Run the keygen command without any output
+    #[clap(long)]
+    pub quiet: bool,
+}
+
+impl PackageKeygen {
+    pub(crate) fn execute(&self) -> Result<(), anyhow::Error> {
+        let secret_path = self.out_dir.join(format!("{}.key", self.name));
+        let public_path = self.out_dir.join(format!("{}.pub", self.name));
+
+        if !self.force {
+            for path in [&secret_path, &public_path] {
+                if path.exists() {
+                    anyhow::bail!(
+                        "'{}' already exists (use --force to overwrite it)",
+                        path.display()
+                    );
+                }
+            }
+        }
+
+        let key = SigningKey::generate()?;
+
+        std::fs::create_dir_all(&self.out_dir).with_context(|| {
+            format!(
+                "could not create the directory '{}'",
+                self.out_dir.display()
+            )
+        })?;
+        write_secret(&secret_path, &key.to_file_contents())?;
+        std::fs::write(&public_path, key.public_key().to_file_contents())
+            .with_context(|| format!("could not write '{}'", public_path.display()))?;
+
+        if !self.quiet {
+            eprintln!(
+                "Generated key {} in '{}' (public key: '{}')",
+                key.id(),
+                secret_path.display(),
+                public_path.display(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a secret key so only the current user can read it.
+fn write_secret(path: &Path, contents: &str) -> Result<(), anyhow::Error> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut f = options
+        .open(path)
+        .with_context(|| format!("could not create '{}'", path.display()))?;
+    std::io::Write::write_all(&mut f, contents.as_bytes())
+        .with_context(|| format!("could not write '{}'", path.display()))
+}
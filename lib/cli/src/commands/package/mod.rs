@@ -1,13 +1,19 @@
 mod build;
 mod common;
 mod download;
+mod keygen;
 pub mod publish;
 mod push;
+mod sign;
 mod tag;
 mod unpack;
+mod vendor;
+mod verify;
 
 pub use build::PackageBuild;
+pub(crate) use common::signing::{load_public_key, read_signature, webc_hash};
 pub use common::wait::PublishWait;
+pub(crate) use vendor::DEFAULT_VENDOR_DIR;
 
 /// Package related commands.
 #[derive(clap::Subcommand, Debug)]
@@ -22,4 +28,8 @@ pub enum Package {
     Push(push::PackagePush),
     Publish(publish::PackagePublish),
     Unpack(unpack::PackageUnpack),
+    Vendor(vendor::PackageVendor),
+    Keygen(keygen::PackageKeygen),
+    Sign(sign::PackageSign),
+    Verify(verify::PackageVerify),
 }
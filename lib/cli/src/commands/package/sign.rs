@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use wasmer_package::signature::signature_path;
+
+use super::common::signing::{load_signing_key, webc_hash};
+
+/// Sign a `*.webc` file.
+///
+/// The signature is written next to the package (e.g. `python.webc.sig`),
+/// where `wasmer run` and the package loader look for it.
+#[derive(clap::Parser, Debug)]
+pub struct PackageSign {
+    /// The secret key to sign with, as created by `wasmer package keygen`.
+    #[clap(short, long)]
+    pub key: PathBuf,
+
+    /// Where to write the signature.
+    ///
+    /// Defaults to the package's path with a `.sig` extension added.
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Run the sign command without any output
+    #[clap(long)]
+    pub quiet: bool,
+
+    /// Path to the package.
+    pub package_path: PathBuf,
+}
+
+impl PackageSign {
+    pub(crate) fn execute(&self) -> Result<(), anyhow::Error> {
+        let key = load_signing_key(&self.key)?;
+        let hash = webc_hash(&self.package_path)?;
+
+        let signature = key.sign(&hash)?;
+
+        let output = self
+            .output
+            .clone()
+            .unwrap_or_else(|| signature_path(&self.package_path));
+        std::fs::write(&output, signature.to_file_contents(&hash))
+            .with_context(|| format!("could not write '{}'", output.display()))?;
+
+        if !self.quiet {
+            eprintln!(
+                "Signed '{}' ({hash}) with key {} to '{}'",
+                self.package_path.display(),
+                key.id(),
+                output.display(),
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use dialoguer::console::{style, Emoji};
+use indicatif::ProgressBar;
+use wasmer_config::package::{PackageHash, PackageId};
+use wasmer_wasix::{
+    bin_factory::{BinaryPackage, LockfileMode},
+    runtime::resolver::{Source, VendorIndex, VendoredPackage, WebcHash},
+};
+
+use crate::{
+    commands::{run::Wasi, AsyncCliCommand},
+    config::WasmerEnv,
+};
+
+/// The directory next to a `wasmer.toml` that dependencies are vendored
+/// into by default.
+pub(crate) const DEFAULT_VENDOR_DIR: &str = "vendor";
+
+static RESOLVING_EMOJI: Emoji<'_, '_> = Emoji("🔍 ", "");
+static DOWNLOADING_EMOJI: Emoji<'_, '_> = Emoji("🌐 ", "");
+static WRITING_INDEX_EMOJI: Emoji<'_, '_> = Emoji("📦 ", "");
+
+/// Download every dependency of a package into a directory, so it can be
+/// run without network access using `wasmer run --offline`.
+#[derive(clap::Parser, Debug)]
+pub struct PackageVendor {
+    #[clap(flatten)]
+    pub env: WasmerEnv,
+
+    /// The directory to vendor the dependencies into.
+    ///
+    /// Defaults to a `vendor` directory next to the package's wasmer.toml.
+    #[clap(short = 'o', long)]
+    pub out_dir: Option<PathBuf>,
+
+    /// Fail instead of updating the package's wasmer.lock when its
+    /// dependencies would resolve to different versions.
+    #[clap(long)]
+    pub locked: bool,
+
+    /// Run the vendor command without any output
+    #[clap(long)]
+    pub quiet: bool,
+
+    /// The directory containing the package's wasmer.toml.
+    #[clap(default_value = ".")]
+    pub package_path: PathBuf,
+}
+
+#[async_trait::async_trait]
+impl AsyncCliCommand for PackageVendor {
+    type Output = VendorIndex;
+
+    async fn run_async(self) -> Result<Self::Output, anyhow::Error> {
+        let pb = if self.quiet {
+            ProgressBar::hidden()
+        } else {
+            ProgressBar::new_spinner()
+        };
+
+        pb.println(format!(
+            "{} {}Resolving dependencies...",
+            style("[1/3]").bold().dim(),
+            RESOLVING_EMOJI,
+        ));
+
+        let dir = self.package_path.canonicalize().with_context(|| {
+            format!(
+                "could not find the package at '{}'",
+                self.package_path.display()
+            )
+        })?;
+        let out_dir = self
+            .out_dir
+            .clone()
+            .unwrap_or_else(|| dir.join(DEFAULT_VENDOR_DIR));
+
+        let client =
+            wasmer_wasix::http::default_http_client().context("No HTTP client available")?;
+        let client = Arc::new(client);
+        // Vendoring always goes through the lockfile, so the downloads must
+        // match the hashes it pins
+        let mut wasi = Wasi::default();
+        wasi.verify_package_hashes = true;
+        let source: Arc<dyn Source + Send + Sync> =
+            Arc::new(wasi.prepare_source(&self.env, client.clone(), webc::Version::V3)?);
+        let loader = wasi
+            .prepare_package_loader(&self.env, client)
+            .context("Unable to prepare the package loader")?;
+
+        let mode = if self.locked {
+            LockfileMode::Locked
+        } else {
+            LockfileMode::Update
+        };
+        let (_, resolution) = BinaryPackage::resolve_dir(&dir, mode, source).await?;
+
+        pb.println(format!(
+            "{} {}Downloading packages...",
+            style("[2/3]").bold().dim(),
+            DOWNLOADING_EMOJI,
+        ));
+
+        std::fs::create_dir_all(&out_dir).with_context(|| {
+            format!(
+                "could not create the vendor directory '{}'",
+                out_dir.display()
+            )
+        })?;
+
+        let mut index = VendorIndex::default();
+        for node in resolution.graph.graph().node_weights() {
+            // Only the root package isn't downloaded from anywhere
+            let Some(dist) = &node.dist else {
+                continue;
+            };
+
+            let path = PathBuf::from(vendored_file_name(&node.id));
+            let dest = out_dir.join(&path);
+
+            if is_vendored(&dest, dist.webc_sha256) {
+                pb.set_message(format!("{} is already vendored", node.id));
+            } else {
+                pb.set_message(format!("Downloading {}", node.id));
+                let webc = loader
+                    .download(dist)
+                    .await
+                    .with_context(|| format!("Unable to download \"{}\"", node.id))?;
+                std::fs::write(&dest, &webc)
+                    .with_context(|| format!("could not write '{}'", dest.display()))?;
+            }
+
+            index.packages.push(VendoredPackage {
+                id: node.id.clone(),
+                hash: PackageHash::from_sha256_bytes(dist.webc_sha256.as_bytes()),
+                path,
+            });
+        }
+        index.packages.sort_by(|a, b| a.id.cmp(&b.id));
+
+        pb.println(format!(
+            "{} {}Writing the index...",
+            style("[3/3]").bold().dim(),
+            WRITING_INDEX_EMOJI,
+        ));
+
+        index.save(&out_dir)?;
+
+        pb.finish_and_clear();
+        if !self.quiet {
+            eprintln!(
+                "Vendored {} packages into '{}'",
+                index.packages.len(),
+                out_dir.display()
+            );
+        }
+
+        Ok(index)
+    }
+}
+
+/// The name of a vendored package's `*.webc` file, following the
+/// `namespace--name@version.webc` convention of `wasmer package download`.
+fn vendored_file_name(id: &PackageId) -> String {
+    match id {
+        PackageId::Named(named) => {
+            format!(
+                "{}@{}.webc",
+                named.full_name.replace('/', "--"),
+                named.version
+            )
+        }
+        PackageId::Hash(hash) => match hash.as_sha256() {
+            Some(sha256) => format!("sha256-{sha256}.webc"),
+            None => format!("{hash}.webc"),
+        },
+    }
+}
+
+fn is_vendored(path: &Path, expected: WebcHash) -> bool {
+    std::fs::read(path).is_ok_and(|webc| WebcHash::sha256(webc) == expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vendored_file_names() {
+        let named = PackageId::new_named("wasmer/python", "3.12.0".parse().unwrap());
+        let hash: PackageHash =
+            "sha256:c355cd53795b9b481f7eb2b5f4f6c8cf73631bdc343723a579d671e32db70b3c"
+                .parse()
+                .unwrap();
+
+        assert_eq!(vendored_file_name(&named), "wasmer--python@3.12.0.webc");
+        assert_eq!(
+            vendored_file_name(&PackageId::Hash(hash)),
+            "sha256-c355cd53795b9b481f7eb2b5f4f6c8cf73631bdc343723a579d671e32db70b3c.webc"
+        );
+    }
+}
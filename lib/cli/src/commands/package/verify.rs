@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+
+use wasmer_package::signature::signature_path;
+use wasmer_wasix::runtime::package_loader::builtin_loader::TrustPolicy;
+
+use super::common::signing::{load_public_key, read_signature, webc_hash};
+
+/// Check that a `*.webc` file was signed by a trusted key.
+#[derive(clap::Parser, Debug)]
+pub struct PackageVerify {
+    /// A public key to trust, as created by `wasmer package keygen`.
+    #[clap(short, long = "key", name = "PUBLIC_KEY", required = true)]
+    pub keys: Vec<PathBuf>,
+
+    /// The signature to check.
+    ///
+    /// Defaults to the package's path with a `.sig` extension added.
+    #[clap(short, long)]
+    pub signature: Option<PathBuf>,
+
+    /// Run the verify command without any output
+    #[clap(long)]
+    pub quiet: bool,
+
+    /// Path to the package.
+    pub package_path: PathBuf,
+}
+
+impl PackageVerify {
+    pub(crate) fn execute(&self) -> Result<(), anyhow::Error> {
+        let trusted_keys = self
+            .keys
+            .iter()
+            .map(|path| load_public_key(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let hash = webc_hash(&self.package_path)?;
+        let signature_path = self
+            .signature
+            .clone()
+            .unwrap_or_else(|| signature_path(&self.package_path));
+        let signature = read_signature(&signature_path)?;
+
+        let policy = TrustPolicy::RequireTrusted { trusted_keys };
+        policy.check(
+            &self.package_path.display().to_string(),
+            &hash,
+            signature.as_ref(),
+        )?;
+
+        if !self.quiet {
+            if let Some(signature) = signature {
+                eprintln!(
+                    "'{}' was signed by key {}",
+                    self.package_path.display(),
+                    signature.key_id(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
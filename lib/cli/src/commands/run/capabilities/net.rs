@@ -1,7 +1,7 @@
 use std::{
     fmt::Display,
     net::{IpAddr, SocketAddr},
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::OnceLock,
     time::Duration,
@@ -12,9 +12,9 @@ use anyhow::Context;
 use colored::Colorize;
 use dialoguer::theme::ColorfulTheme;
 use virtual_net::{
-    DynVirtualNetworking, IpCidr, IpRoute, NetworkError, Result, StreamSecurity,
+    DynVirtualNetworking, IpCidr, IpRoute, NetworkError, Result, StreamSecurity, UnixSockets,
     UnsupportedVirtualNetworking, VirtualIcmpSocket, VirtualNetworking, VirtualRawSocket,
-    VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket,
+    VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket, VirtualUnixDatagramSocket,
 };
 
 /// A custom implementation of the [`virtual_net::VirtualNetwork`] that asks users if they want to
@@ -305,4 +305,36 @@ impl VirtualNetworking for AskingNetworking {
     ) -> Result<Vec<IpAddr>> {
         call!(self, resolve, host, port, dns_server);
     }
+
+    // Unix domain sockets are local to the guests, unless they have been
+    // explicitly mapped to the host, so they don't need networking to be
+    // enabled.
+
+    /// Listens for connections on a Unix domain socket bound to a path
+    async fn listen_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualTcpListener + Sync>> {
+        self.capable.listen_unix(sockets, path).await
+    }
+
+    /// Opens a connection to the Unix domain socket bound to a path
+    async fn connect_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualTcpSocket + Sync>> {
+        self.capable.connect_unix(sockets, path).await
+    }
+
+    /// Opens a datagram Unix domain socket, bound to a path unless it is
+    /// `None`
+    async fn bind_unix_datagram(
+        &self,
+        sockets: &UnixSockets,
+        path: Option<&Path>,
+    ) -> Result<Box<dyn VirtualUnixDatagramSocket + Sync>> {
+        self.capable.bind_unix_datagram(sockets, path).await
+    }
 }
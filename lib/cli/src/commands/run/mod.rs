@@ -1,8 +1,12 @@
 #![allow(missing_docs, unused)]
 
 mod capabilities;
+mod syscall_stats;
+mod value;
 mod wasi;
 
+pub(crate) use self::wasi::Wasi;
+
 use std::{
     collections::{hash_map::DefaultHasher, BTreeMap},
     fmt::{Binary, Display},
@@ -31,24 +35,30 @@ use wasmer::{
 
 #[cfg(feature = "compiler")]
 use wasmer_compiler::ArtifactBuild;
-use wasmer_config::package::PackageSource as PackageSpecifier;
+use wasmer_config::{
+    app::AppScheduledTask,
+    package::{PackageSource as PackageSpecifier, LOCKFILE_NAME},
+};
 use wasmer_package::utils::from_disk;
 use wasmer_types::ModuleHash;
 
 #[cfg(feature = "journal")]
 use wasmer_wasix::journal::{LogFileJournal, SnapshotTrigger};
 use wasmer_wasix::{
-    bin_factory::BinaryPackage,
+    bin_factory::{BinaryPackage, LockfileMode},
     journal::CompactingLogFileJournal,
     runners::{
         dcgi::{DcgiInstanceFactory, DcgiRunner},
         dproxy::DProxyRunner,
+        scheduler::{AppJobExecutor, Scheduler},
         wasi::WasiRunner,
         wcgi::{self, AbortHandle, NoOpWcgiCallbacks, WcgiRunner},
         MappedCommand, MappedDirectory, Runner,
     },
     runtime::{
-        module_cache::CacheError, package_loader::PackageLoader, resolver::QueryError,
+        module_cache::CacheError,
+        package_loader::PackageLoader,
+        resolver::{QueryError, VENDOR_INDEX_FILE_NAME},
         task_manager::VirtualTaskManagerExt,
     },
     Runtime, WasiError,
@@ -57,8 +67,8 @@ use webc::metadata::Manifest;
 use webc::Container;
 
 use crate::{
-    commands::run::wasi::Wasi, common::HashAlgorithm, config::WasmerEnv, error::PrettyError,
-    logging::Output, store::StoreOptions,
+    commands::package::DEFAULT_VENDOR_DIR, common::HashAlgorithm, config::WasmerEnv,
+    error::PrettyError, logging::Output, store::StoreOptions,
 };
 
 const TICK: Duration = Duration::from_millis(250);
@@ -71,9 +81,9 @@ pub struct Run {
     #[clap(flatten)]
     store: StoreOptions,
     #[clap(flatten)]
-    wasi: crate::commands::run::Wasi,
+    pub(crate) wasi: crate::commands::run::Wasi,
     #[clap(flatten)]
-    wcgi: WcgiOptions,
+    pub(crate) wcgi: WcgiOptions,
     /// Set the default stack size (default is 1048576)
     #[clap(long = "stack-size")]
     stack_size: Option<usize>,
@@ -81,10 +91,16 @@ pub struct Run {
     #[clap(short, long, aliases = &["command", "command-name"])]
     entrypoint: Option<String>,
     /// The function to invoke.
+    ///
+    /// Its arguments are given after the input, such as `42`, `-0x1`,
+    /// `0x1.8p3`, `nan:0x200000`, `i32x4:1,2,3,4` or `null`.
     #[clap(short, long)]
     invoke: Option<String>,
+    /// Print the results of the invoked function as JSON.
+    #[clap(long, requires = "invoke")]
+    json: bool,
     /// Generate a coredump at this path if a WebAssembly trap occurs
-    #[clap(name = "COREDUMP_PATH", long)]
+    #[clap(long, value_name = "COREDUMP_PATH")]
     coredump_on_trap: Option<PathBuf>,
     /// The file, URL, or package to run.
     #[clap(value_parser = PackageSource::infer)]
@@ -94,6 +110,14 @@ pub struct Run {
     /// Hashing algorithm to be used for module hash
     #[clap(long, value_enum)]
     hash_algorithm: Option<HashAlgorithm>,
+    /// Fail instead of updating the package's wasmer.lock when its
+    /// dependencies would resolve to different versions. Only valid when
+    /// running a package directory.
+    #[clap(long)]
+    locked: bool,
+    /// Tasks to run periodically while the package runs.
+    #[clap(skip)]
+    pub(crate) scheduled_tasks: Vec<AppScheduledTask>,
 }
 
 impl Run {
@@ -104,6 +128,13 @@ impl Run {
 
     #[tracing::instrument(level = "debug", name = "wasmer_run", skip_all)]
     fn execute_inner(mut self, output: Output) -> Result<(), Error> {
+        if self.locked && !matches!(self.input, PackageSource::Dir(_)) {
+            bail!(
+                "--locked can only be used with a package directory, and \"{}\" isn't one",
+                self.input
+            );
+        }
+
         let pb = ProgressBar::new_spinner();
         pb.set_draw_target(output.draw_target());
         pb.enable_steady_tick(TICK);
@@ -145,6 +176,20 @@ impl Run {
         #[cfg(not(feature = "sys"))]
         let engine = store.engine().clone();
 
+        if let PackageSource::Dir(dir) = &self.input {
+            let vendor_dir = dir.join(DEFAULT_VENDOR_DIR);
+            if vendor_dir.join(VENDOR_INDEX_FILE_NAME).exists() {
+                self.wasi.vendor_dirs.push(vendor_dir);
+            }
+            // The packages must be the ones the lockfile pins
+            self.wasi.verify_package_hashes = self.locked || dir.join(LOCKFILE_NAME).exists();
+        }
+        if let PackageSource::File(path) = &self.input {
+            if let TargetOnDisk::LocalWebc = TargetOnDisk::from_file(path)? {
+                self.wasi.check_local_webc(path)?;
+            }
+        }
+
         let runtime = self.wasi.prepare_runtime(
             engine,
             &self.env,
@@ -159,7 +204,14 @@ impl Run {
         let runtime: Arc<dyn Runtime + Send + Sync> = monitoring_runtime.runtime.clone();
         let monitoring_runtime: Arc<dyn Runtime + Send + Sync> = monitoring_runtime;
 
-        let target = self.input.resolve_target(&monitoring_runtime, &pb)?;
+        let lockfile_mode = if self.locked {
+            LockfileMode::Locked
+        } else {
+            LockfileMode::Update
+        };
+        let target = self
+            .input
+            .resolve_target(&monitoring_runtime, lockfile_mode, &pb)?;
 
         if let ExecutableTarget::Package(ref pkg) = target {
             self.wasi
@@ -190,8 +242,10 @@ impl Run {
             }
         }
 
-        if let Err(e) = &result {
-            self.maybe_save_coredump(e);
+        if self.wasi.syscall_stats {
+            let profile = self.wasi.host_profiler.profile();
+            let syscalls = profile.iter().map(|(_, name, stats)| (name, *stats));
+            eprint!("{}", syscall_stats::summary(syscalls));
         }
 
         result
@@ -229,6 +283,10 @@ impl Run {
 
         let uses = self.load_injected_packages(&runtime)?;
 
+        if !self.scheduled_tasks.is_empty() {
+            self.start_scheduler(pkg, &runtime)?;
+        }
+
         if DcgiRunner::can_run_command(cmd.metadata())? {
             self.run_dcgi(id, pkg, uses, runtime)
         } else if DProxyRunner::can_run_command(cmd.metadata())? {
@@ -245,6 +303,30 @@ impl Run {
         }
     }
 
+    /// Run the scheduled tasks in the background, against the package's
+    /// commands and the address it is served on.
+    fn start_scheduler(
+        &self,
+        pkg: &BinaryPackage,
+        runtime: &Arc<dyn Runtime + Send + Sync>,
+    ) -> Result<(), Error> {
+        let runner = self.build_wasi_runner(runtime)?;
+        let base_url = Url::parse(&format!("http://{}/", self.wcgi.addr))?;
+
+        let mut executor = AppJobExecutor::new(pkg.clone(), runner, Arc::clone(runtime));
+        executor
+            .with_base_url(base_url)
+            .with_force_https(self.wcgi.force_https);
+
+        let mut scheduler = Scheduler::new(Arc::new(executor), runtime.task_manager().clone());
+        scheduler.with_tasks(self.scheduled_tasks.iter().cloned());
+        scheduler
+            .spawn()
+            .context("Unable to start the scheduled tasks")?;
+
+        Ok(())
+    }
+
     #[tracing::instrument(level = "debug", skip_all)]
     fn load_injected_packages(
         &self,
@@ -305,6 +387,7 @@ impl Run {
             .envs(self.wasi.env_vars.clone())
             .map_directories(self.wasi.mapped_dirs.clone())
             .callbacks(Callbacks::new(self.wcgi.addr))
+            .force_https(self.wcgi.force_https)
             .inject_packages(uses);
         *config.capabilities() = self.wasi.capabilities();
         if self.wasi.forward_host_env {
@@ -377,16 +460,22 @@ impl Run {
             }
         };
 
-        let return_values = invoke_function(&instance, store, entry_function, &self.args)?;
+        let return_values = match invoke_function(&instance, store, entry_function, &self.args) {
+            Ok(values) => values,
+            Err(e) => {
+                self.maybe_save_coredump(&e, &instance, store);
+                return Err(e);
+            }
+        };
 
-        println!(
-            "{}",
-            return_values
-                .iter()
-                .map(|val| val.to_string())
-                .collect::<Vec<String>>()
-                .join(" ")
-        );
+        if self.json {
+            let results: Vec<_> = return_values.iter().map(value::value_to_json).collect();
+            println!("{}", serde_json::Value::Array(results));
+        } else {
+            for value in return_values.iter() {
+                println!("{}", value::format_value(value));
+            }
+        }
 
         Ok(())
     }
@@ -417,6 +506,11 @@ impl Run {
             runner.with_entry_function(entry_function);
         }
 
+        #[cfg(feature = "coredump")]
+        if let Some(ref path) = self.coredump_on_trap {
+            runner.with_coredump_on_trap(path);
+        }
+
         #[cfg(feature = "journal")]
         {
             for trigger in self.wasi.snapshot_on.iter().cloned() {
@@ -462,11 +556,16 @@ impl Run {
         )
     }
 
+    /// Save a coredump for a trap raised by a module that runs without WASI.
+    ///
+    /// WASI programs write their coredumps from within the runtime, which
+    /// also records the stacks of the other threads.
     #[allow(unused_variables)]
-    fn maybe_save_coredump(&self, e: &Error) {
+    fn maybe_save_coredump(&self, e: &Error, instance: &Instance, store: &mut Store) {
         #[cfg(feature = "coredump")]
         if let Some(coredump) = &self.coredump_on_trap {
-            if let Err(e) = generate_coredump(e, self.input.to_string(), coredump) {
+            if let Err(e) = generate_coredump(e, self.input.to_string(), coredump, instance, store)
+            {
                 tracing::warn!(
                     error = &*e as &dyn std::error::Error,
                     coredump_path=%coredump.display(),
@@ -507,10 +606,38 @@ impl Run {
             stack_size: None,
             entrypoint: Some(original_executable.to_string()),
             invoke: None,
+            json: false,
             coredump_on_trap: None,
             input: PackageSource::infer(executable)?,
             args: args.to_vec(),
             hash_algorithm: None,
+            locked: false,
+            scheduled_tasks: Vec::new(),
+        })
+    }
+
+    /// Create a [`Run`] for a file, directory or package with the default
+    /// options.
+    pub(crate) fn for_package(
+        env: WasmerEnv,
+        input: &str,
+        args: Vec<String>,
+    ) -> Result<Self, Error> {
+        Ok(Run {
+            env,
+            store: StoreOptions::default(),
+            wasi: Wasi::default(),
+            wcgi: WcgiOptions::default(),
+            stack_size: None,
+            entrypoint: None,
+            invoke: None,
+            json: false,
+            coredump_on_trap: None,
+            input: PackageSource::infer(input)?,
+            args,
+            hash_algorithm: None,
+            locked: false,
+            scheduled_tasks: Vec::new(),
         })
     }
 }
@@ -536,7 +663,7 @@ fn invoke_function(
         .iter()
         .zip(func_ty.params().iter())
         .map(|(arg, param_type)| {
-            parse_value(arg, *param_type)
+            value::parse_value(arg, *param_type)
                 .with_context(|| format!("Unable to convert {arg:?} to {param_type:?}"))
         })
         .collect::<Result<Vec<_>, _>>()?;
@@ -546,18 +673,6 @@ fn invoke_function(
     Ok(return_values)
 }
 
-fn parse_value(s: &str, ty: wasmer_types::Type) -> Result<Value, Error> {
-    let value = match ty {
-        Type::I32 => Value::I32(s.parse()?),
-        Type::I64 => Value::I64(s.parse()?),
-        Type::F32 => Value::F32(s.parse()?),
-        Type::F64 => Value::F64(s.parse()?),
-        Type::V128 => Value::V128(s.parse()?),
-        _ => bail!("There is no known conversion from {s:?} to {ty:?}"),
-    };
-    Ok(value)
-}
-
 /// The input that was passed in via the command-line.
 #[derive(Debug, Clone, PartialEq)]
 enum PackageSource {
@@ -595,11 +710,12 @@ impl PackageSource {
     fn resolve_target(
         &self,
         rt: &Arc<dyn Runtime + Send + Sync>,
+        lockfile_mode: LockfileMode,
         pb: &ProgressBar,
     ) -> Result<ExecutableTarget, Error> {
         match self {
             PackageSource::File(path) => ExecutableTarget::from_file(path, rt, pb),
-            PackageSource::Dir(d) => ExecutableTarget::from_dir(d, rt, pb),
+            PackageSource::Dir(d) => ExecutableTarget::from_dir(d, rt, lockfile_mode, pb),
             PackageSource::Package(pkg) => {
                 pb.set_message("Loading from the registry");
                 let inner_pck = pkg.clone();
@@ -687,6 +803,7 @@ impl ExecutableTarget {
     fn from_dir(
         dir: &Path,
         runtime: &Arc<dyn Runtime + Send + Sync>,
+        lockfile_mode: LockfileMode,
         pb: &ProgressBar,
     ) -> Result<Self, Error> {
         pb.set_message(format!("Loading \"{}\" into memory", dir.display()));
@@ -695,7 +812,10 @@ impl ExecutableTarget {
         let pkg = runtime.task_manager().spawn_and_block_on({
             let path = dir.to_path_buf();
 
-            async move { BinaryPackage::from_dir(&path, inner_runtime.as_ref()).await }
+            async move {
+                BinaryPackage::from_dir_with_lockfile(&path, lockfile_mode, inner_runtime.as_ref())
+                    .await
+            }
         })??;
 
         Ok(ExecutableTarget::Package(pkg))
@@ -755,7 +875,13 @@ impl ExecutableTarget {
 }
 
 #[cfg(feature = "coredump")]
-fn generate_coredump(err: &Error, source_name: String, coredump_path: &Path) -> Result<(), Error> {
+fn generate_coredump(
+    err: &Error,
+    source_name: String,
+    coredump_path: &Path,
+    instance: &Instance,
+    store: &mut Store,
+) -> Result<(), Error> {
     let err: &wasmer::RuntimeError = match err.downcast_ref() {
         Some(e) => e,
         None => {
@@ -764,27 +890,18 @@ fn generate_coredump(err: &Error, source_name: String, coredump_path: &Path) ->
         }
     };
 
-    let mut coredump_builder =
-        wasm_coredump_builder::CoredumpBuilder::new().executable_name(&source_name);
-
-    let mut thread_builder = wasm_coredump_builder::ThreadBuilder::new().thread_name("main");
-
-    for frame in err.trace() {
-        let coredump_frame = wasm_coredump_builder::FrameBuilder::new()
-            .codeoffset(frame.func_offset() as u32)
-            .funcidx(frame.func_index())
-            .build();
-        thread_builder.add_frame(coredump_frame);
-    }
-
-    coredump_builder.add_thread(thread_builder.build());
+    let memory = instance
+        .exports
+        .iter()
+        .memories()
+        .map(|(_, memory)| memory.clone())
+        .next();
 
-    let coredump = coredump_builder
-        .serialize()
-        .map_err(Error::msg)
-        .context("Coredump serializing failed")?;
+    let mut coredump = wasmer_wasix::coredump::Coredump::new(source_name);
+    coredump.capture(store, instance, memory.as_ref());
+    coredump.add_stack("main", err);
 
-    std::fs::write(coredump_path, &coredump).with_context(|| {
+    coredump.save(coredump_path).with_context(|| {
         format!(
             "Unable to save the coredump to \"{}\"",
             coredump_path.display()
@@ -799,12 +916,16 @@ pub(crate) struct WcgiOptions {
     /// The address to serve on.
     #[clap(long, short, env, default_value_t = ([127, 0, 0, 1], 8000).into())]
     pub(crate) addr: SocketAddr,
+    /// Redirect requests that weren't made over HTTPS to HTTPS.
+    #[clap(skip)]
+    pub(crate) force_https: bool,
 }
 
 impl Default for WcgiOptions {
     fn default() -> Self {
         Self {
             addr: ([127, 0, 0, 1], 8000).into(),
+            force_https: false,
         }
     }
 }
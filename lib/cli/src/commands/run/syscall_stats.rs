@@ -0,0 +1,115 @@
+//! The summary printed by `wasmer run --syscall-stats`.
+
+use std::{collections::HashMap, fmt::Write};
+
+use wasmer::HostCallStats;
+
+const SEPARATOR: &str =
+    "------ ----------- ----------- ----------- --------- -------- ------------ ------------ ----------------";
+
+/// Format the statistics of each syscall as a table, in the style of
+/// `strace -c`.
+///
+/// Syscalls imported from several WASI versions are counted together, and
+/// the ones the program spent the most time in come first.
+pub(crate) fn summary<'a>(syscalls: impl IntoIterator<Item = (&'a str, HostCallStats)>) -> String {
+    let mut merged: HashMap<&str, HostCallStats> = HashMap::new();
+    for (name, stats) in syscalls {
+        *merged.entry(name).or_default() += stats;
+    }
+
+    let mut rows: Vec<_> = merged.into_iter().collect();
+    rows.sort_by(|(a_name, a), (b_name, b)| {
+        b.total_time
+            .cmp(&a.total_time)
+            .then(b.calls.cmp(&a.calls))
+            .then(a_name.cmp(b_name))
+    });
+
+    let mut total = HostCallStats::default();
+    for (_, stats) in &rows {
+        total += *stats;
+    }
+    let total_secs = total.total_time.as_secs_f64();
+
+    let mut table = String::new();
+    let _ = writeln!(
+        table,
+        "{:>6} {:>11} {:>11} {:>11} {:>9} {:>8} {:>12} {:>12} syscall",
+        "% time", "seconds", "usecs/call", "max usecs", "calls", "traps", "mem read", "mem written"
+    );
+    let _ = writeln!(table, "{SEPARATOR}");
+
+    for (name, stats) in &rows {
+        let share = if total_secs > 0.0 {
+            100.0 * stats.total_time.as_secs_f64() / total_secs
+        } else {
+            0.0
+        };
+        let _ = writeln!(
+            table,
+            "{:>6.2} {:>11.6} {:>11} {:>11} {:>9} {:>8} {:>12} {:>12} {name}",
+            share,
+            stats.total_time.as_secs_f64(),
+            stats.average_time().as_micros(),
+            stats.max_time.as_micros(),
+            stats.calls,
+            stats.traps,
+            stats.bytes_read,
+            stats.bytes_written,
+        );
+    }
+
+    let _ = writeln!(table, "{SEPARATOR}");
+    let _ = writeln!(
+        table,
+        "{:>6.2} {:>11.6} {:>11} {:>11} {:>9} {:>8} {:>12} {:>12} total",
+        100.0,
+        total_secs,
+        total.average_time().as_micros(),
+        total.max_time.as_micros(),
+        total.calls,
+        total.traps,
+        total.bytes_read,
+        total.bytes_written,
+    );
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn stats(calls: u64, micros: u64, bytes_written: u64) -> HostCallStats {
+        HostCallStats {
+            calls,
+            total_time: Duration::from_micros(micros),
+            max_time: Duration::from_micros(micros / calls),
+            bytes_written,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn summarize_syscalls() {
+        let table = summary([
+            ("fd_write", stats(3, 300, 12)),
+            ("clock_time_get", stats(1, 100, 8)),
+            ("fd_write", stats(1, 600, 4)),
+        ]);
+
+        let expected = [
+            "% time     seconds  usecs/call   max usecs     calls    traps     mem read  mem written syscall",
+            SEPARATOR,
+            " 90.00    0.000900         225         600         4        0            0           16 fd_write",
+            " 10.00    0.000100         100         100         1        0            0            8 clock_time_get",
+            SEPARATOR,
+            "100.00    0.001000         200         600         5        0            0           24 total",
+            "",
+        ];
+        assert_eq!(table, expected.join("\n"));
+    }
+}
@@ -0,0 +1,510 @@
+//! Parsing of the arguments and printing of the results of `--invoke`.
+//!
+//! Values are written the way they are in the text format, without the
+//! `*.const` instruction:
+//!
+//! - integers are decimal or hexadecimal (`0x2a`), and may be signed or
+//!   unsigned (`-1` and `4294967295` are the same `i32`)
+//! - floats are decimal, hexadecimal (`0x1.8p3`), `inf` or `nan`, with an
+//!   optional payload for NaNs (`nan:0x200000`)
+//! - a `v128` is either a single integer or a list of lanes prefixed with
+//!   their shape, such as `i32x4:1,2,3,4` or `f64x2:0.5,-inf`
+//! - references can only be `null`
+//!
+//! Digits may be separated by `_`.
+
+use anyhow::{bail, Context, Error};
+use wasmer::{Type, Value};
+
+/// Parses a value of the given type.
+pub(crate) fn parse_value(s: &str, ty: Type) -> Result<Value, Error> {
+    let value = match ty {
+        Type::I32 => Value::I32(parse_int(s, 32)? as u32 as i32),
+        Type::I64 => Value::I64(parse_int(s, 64)? as u64 as i64),
+        Type::F32 => Value::F32(f32::from_bits(parse_f32(s)?)),
+        Type::F64 => Value::F64(f64::from_bits(parse_f64(s)?)),
+        Type::V128 => Value::V128(parse_v128(s)?),
+        Type::FuncRef => {
+            parse_null(s, ty)?;
+            Value::FuncRef(None)
+        }
+        Type::ExternRef => {
+            parse_null(s, ty)?;
+            Value::ExternRef(None)
+        }
+        Type::ExceptionRef => {
+            parse_null(s, ty)?;
+            Value::ExceptionRef(None)
+        }
+    };
+    Ok(value)
+}
+
+/// Formats a value along with its type (e.g. `i32 42`), in a form that
+/// [`parse_value()`] accepts.
+pub(crate) fn format_value(value: &Value) -> String {
+    format!("{} {}", type_name(value.ty()), format_bare(value))
+}
+
+/// Converts a value to JSON, as an object with its `type` and `value`.
+///
+/// Integers and finite floats are JSON numbers. Other floats and `v128`s are
+/// strings in the format accepted by [`parse_value()`], and references are
+/// `null` or a string naming their type.
+pub(crate) fn value_to_json(value: &Value) -> serde_json::Value {
+    let json = match value {
+        Value::I32(v) => serde_json::Value::from(*v),
+        Value::I64(v) => serde_json::Value::from(*v),
+        Value::F32(v) if v.is_finite() => serde_json::Value::from(*v),
+        Value::F64(v) if v.is_finite() => serde_json::Value::from(*v),
+        Value::FuncRef(None) | Value::ExternRef(None) | Value::ExceptionRef(None) => {
+            serde_json::Value::Null
+        }
+        other => serde_json::Value::from(format_bare(other)),
+    };
+
+    serde_json::json!({
+        "type": type_name(value.ty()),
+        "value": json,
+    })
+}
+
+/// The name of a type in the text format.
+fn type_name(ty: Type) -> &'static str {
+    match ty {
+        Type::I32 => "i32",
+        Type::I64 => "i64",
+        Type::F32 => "f32",
+        Type::F64 => "f64",
+        Type::V128 => "v128",
+        Type::ExternRef => "externref",
+        Type::FuncRef => "funcref",
+        Type::ExceptionRef => "exnref",
+    }
+}
+
+fn format_bare(value: &Value) -> String {
+    match value {
+        Value::I32(v) => v.to_string(),
+        Value::I64(v) => v.to_string(),
+        Value::F32(v) => format_float(v.to_bits().into(), 32),
+        Value::F64(v) => format_float(v.to_bits(), 64),
+        Value::V128(v) => format!("{v:#034x}"),
+        Value::FuncRef(None) | Value::ExternRef(None) | Value::ExceptionRef(None) => {
+            "null".to_string()
+        }
+        Value::FuncRef(Some(_)) => "<function>".to_string(),
+        Value::ExternRef(Some(_)) => "<externref>".to_string(),
+        Value::ExceptionRef(Some(_)) => "<exception>".to_string(),
+    }
+}
+
+/// Parses an integer of `bits` bits, which may be written signed or unsigned,
+/// and returns its bit pattern.
+fn parse_int(s: &str, bits: u32) -> Result<u64, Error> {
+    let digits = s.replace('_', "");
+    let (negative, digits) = split_sign(&digits);
+    let magnitude = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => digits.parse::<u128>(),
+    }
+    .with_context(|| format!("{s:?} isn't a valid integer"))?;
+
+    let max = 1u128 << bits;
+    if negative {
+        // Down to the smallest signed value
+        if magnitude > max / 2 {
+            bail!("{s:?} is too small for a {bits}-bit integer");
+        }
+        Ok(((max - magnitude) % max) as u64)
+    } else {
+        // Up to the largest unsigned value
+        if magnitude >= max {
+            bail!("{s:?} is too large for a {bits}-bit integer");
+        }
+        Ok(magnitude as u64)
+    }
+}
+
+fn parse_f32(s: &str) -> Result<u32, Error> {
+    Ok(parse_float(s, 32)? as u32)
+}
+
+fn parse_f64(s: &str) -> Result<u64, Error> {
+    parse_float(s, 64)
+}
+
+/// Parses a float of `bits` bits and returns its bit pattern, so that NaN
+/// payloads are preserved.
+fn parse_float(s: &str, bits: u32) -> Result<u64, Error> {
+    let (mantissa_bits, exponent_mask) = match bits {
+        32 => (23, 0xff_u64 << 23),
+        _ => (52, 0x7ff_u64 << 52),
+    };
+    let mantissa_mask = (1u64 << mantissa_bits) - 1;
+    let sign_bit = 1u64 << (bits - 1);
+
+    let text = s.replace('_', "");
+    let (negative, text) = split_sign(&text);
+    let sign = if negative { sign_bit } else { 0 };
+
+    let lower = text.to_ascii_lowercase();
+    let magnitude = if lower == "nan" {
+        // The canonical NaN
+        exponent_mask | (1 << (mantissa_bits - 1))
+    } else if let Some(payload) = lower.strip_prefix("nan:0x") {
+        let payload = u64::from_str_radix(payload, 16)
+            .with_context(|| format!("{s:?} has an invalid NaN payload"))?;
+        if payload == 0 || payload > mantissa_mask {
+            bail!("the NaN payload of {s:?} must be between 0x1 and {mantissa_mask:#x}");
+        }
+        exponent_mask | payload
+    } else if lower == "inf" || lower == "infinity" {
+        exponent_mask
+    } else {
+        let magnitude = match lower.strip_prefix("0x") {
+            Some(hex) => parse_hex_float(hex, bits),
+            // Decimals are parsed at their own width so they are rounded once
+            None if bits == 32 => lower
+                .parse::<f32>()
+                .map(|v| v.to_bits().into())
+                .map_err(Error::from),
+            None => lower.parse::<f64>().map(f64::to_bits).map_err(Error::from),
+        }
+        .with_context(|| format!("{s:?} isn't a valid float"))?;
+        if magnitude == exponent_mask {
+            bail!("{s:?} is out of range for an f{bits}");
+        }
+        magnitude
+    };
+
+    Ok(sign | magnitude)
+}
+
+/// Parses the digits of a hexadecimal float after the `0x`, such as
+/// `1.8p3`, as a positive float of `bits` bits and returns its bit pattern.
+///
+/// Digits that don't fit in the mantissa are rounded to the nearest value,
+/// ties to even, like the text format does. Values that are too large
+/// become infinity.
+fn parse_hex_float(s: &str, bits: u32) -> Result<u64, Error> {
+    let (mantissa_bits, bias): (i64, i64) = match bits {
+        32 => (23, 127),
+        _ => (52, 1023),
+    };
+    let precision = mantissa_bits + 1;
+
+    let (digits, exponent) = match s.split_once('p') {
+        Some((digits, exponent)) => (digits, exponent.parse::<i32>()?),
+        None => (s, 0),
+    };
+    if digits.is_empty() || digits == "." {
+        bail!("missing digits");
+    }
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+
+    // The value is `significand * 2^exponent`, where `sticky` tells whether
+    // any of the digits that didn't fit in `significand` are set
+    let mut significand = 0u64;
+    let mut sticky = false;
+    let mut exponent = i64::from(exponent) - 4 * frac.len() as i64;
+    for c in int.chars().chain(frac.chars()) {
+        let digit = c
+            .to_digit(16)
+            .with_context(|| format!("invalid digit {c:?}"))?;
+        if significand >> 60 == 0 {
+            significand = (significand << 4) | u64::from(digit);
+        } else {
+            sticky |= digit != 0;
+            exponent += 4;
+        }
+    }
+    if significand == 0 {
+        return Ok(0);
+    }
+
+    // Keep `precision` bits, or fewer for subnormals, whose last bit is
+    // worth 2^(1 - bias - mantissa_bits)
+    let width = 64 - i64::from(significand.leading_zeros());
+    let subnormal_shift = 1 - bias - mantissa_bits - exponent;
+    let shift = (width - precision).max(subnormal_shift);
+    let mut mantissa = if shift <= 0 {
+        significand << -shift
+    } else if shift > 65 {
+        // Less than a quarter of the smallest subnormal
+        0
+    } else {
+        let significand = u128::from(significand);
+        let rest = significand & ((1u128 << shift) - 1);
+        let half = 1u128 << (shift - 1);
+        let truncated = (significand >> shift) as u64;
+        let round_up = rest > half || (rest == half && (sticky || truncated & 1 == 1));
+        truncated + u64::from(round_up)
+    };
+    let mut exponent = exponent + shift;
+    if mantissa == 1u64 << precision {
+        mantissa >>= 1;
+        exponent += 1;
+    }
+
+    if mantissa < 1u64 << mantissa_bits {
+        // A subnormal, or zero
+        return Ok(mantissa);
+    }
+    let biased = exponent + mantissa_bits + bias;
+    let max_biased = 2 * bias + 1;
+    if biased >= max_biased {
+        // Infinity
+        return Ok((max_biased as u64) << mantissa_bits);
+    }
+    Ok(((biased as u64) << mantissa_bits) | (mantissa & ((1u64 << mantissa_bits) - 1)))
+}
+
+/// Parses a `v128`, either as a single integer or as lanes prefixed by their
+/// shape.
+fn parse_v128(s: &str) -> Result<u128, Error> {
+    let Some((shape, lanes)) = s.split_once(':') else {
+        let digits = s.replace('_', "");
+        let value = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+            Some(hex) => u128::from_str_radix(hex, 16),
+            None => digits.parse(),
+        };
+        return value.with_context(|| format!("{s:?} isn't a valid v128"));
+    };
+
+    let (lane_bits, count) = match shape {
+        "i8x16" => (8, 16),
+        "i16x8" => (16, 8),
+        "i32x4" | "f32x4" => (32, 4),
+        "i64x2" | "f64x2" => (64, 2),
+        _ => bail!("unknown v128 shape {shape:?}, expected one of i8x16, i16x8, i32x4, i64x2, f32x4 or f64x2"),
+    };
+    let lanes: Vec<&str> = lanes
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|lane| !lane.is_empty())
+        .collect();
+    if lanes.len() != count {
+        bail!(
+            "a {shape} needs {count} lanes, but {} were given",
+            lanes.len()
+        );
+    }
+
+    let mut value = 0u128;
+    for (i, lane) in lanes.iter().enumerate() {
+        let bits = match shape {
+            "f32x4" => parse_f32(lane)?.into(),
+            "f64x2" => parse_f64(lane)?,
+            _ => parse_int(lane, lane_bits)?,
+        };
+        // Lane 0 holds the lowest bytes
+        value |= u128::from(bits) << (i as u32 * lane_bits);
+    }
+    Ok(value)
+}
+
+fn parse_null(s: &str, ty: Type) -> Result<(), Error> {
+    match s {
+        "null" | "ref.null" => Ok(()),
+        _ => bail!(
+            "only null references can be passed as a {}, expected \"null\"",
+            type_name(ty)
+        ),
+    }
+}
+
+fn split_sign(s: &str) -> (bool, &str) {
+    if let Some(rest) = s.strip_prefix('-') {
+        (true, rest)
+    } else {
+        (false, s.strip_prefix('+').unwrap_or(s))
+    }
+}
+
+/// Formats the bit pattern of a float so that it parses back to the same
+/// bits.
+fn format_float(bits: u64, width: u32) -> String {
+    let (mantissa_bits, exponent_mask) = match width {
+        32 => (23, 0xff_u64 << 23),
+        _ => (52, 0x7ff_u64 << 52),
+    };
+    let mantissa_mask = (1u64 << mantissa_bits) - 1;
+    let sign = if bits >> (width - 1) & 1 == 1 {
+        "-"
+    } else {
+        ""
+    };
+
+    if bits & exponent_mask == exponent_mask {
+        let payload = bits & mantissa_mask;
+        return if payload == 0 {
+            format!("{sign}inf")
+        } else if payload == 1 << (mantissa_bits - 1) {
+            format!("{sign}nan")
+        } else {
+            format!("{sign}nan:{payload:#x}")
+        };
+    }
+
+    if width == 32 {
+        format!("{:?}", f32::from_bits(bits as u32))
+    } else {
+        format!("{:?}", f64::from_bits(bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str, ty: Type) -> Value {
+        parse_value(s, ty).unwrap()
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(parse("42", Type::I32), Value::I32(42));
+        assert_eq!(parse("-1", Type::I32), Value::I32(-1));
+        assert_eq!(parse("4294967295", Type::I32), Value::I32(-1));
+        assert_eq!(parse("0xffff_ffff", Type::I32), Value::I32(-1));
+        assert_eq!(parse("-0x80000000", Type::I32), Value::I32(i32::MIN));
+        assert_eq!(parse("0x7fffffffffffffff", Type::I64), Value::I64(i64::MAX));
+        assert_eq!(parse("18446744073709551615", Type::I64), Value::I64(-1));
+
+        assert!(parse_value("4294967296", Type::I32).is_err());
+        assert!(parse_value("-2147483649", Type::I32).is_err());
+        assert!(parse_value("1.5", Type::I32).is_err());
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(parse("1.5", Type::F32), Value::F32(1.5));
+        assert_eq!(parse("-0x1.8p3", Type::F64), Value::F64(-12.0));
+        assert_eq!(parse("0x1p-149", Type::F32), Value::F32(f32::from_bits(1)));
+        assert_eq!(parse("-inf", Type::F64), Value::F64(f64::NEG_INFINITY));
+
+        let nan = parse("nan", Type::F32).unwrap_f32();
+        assert_eq!(nan.to_bits(), 0x7fc0_0000);
+        let nan = parse("-nan:0x1", Type::F64).unwrap_f64();
+        assert_eq!(nan.to_bits(), 0xfff0_0000_0000_0001);
+
+        assert!(parse_value("nan:0x800000", Type::F32).is_err());
+        assert!(parse_value("1e39", Type::F32).is_err());
+        assert!(parse_value("0x1p128", Type::F32).is_err());
+    }
+
+    #[test]
+    fn hex_floats_are_rounded_to_nearest_even() {
+        let f32_bits = |s| parse(s, Type::F32).unwrap_f32().to_bits();
+        let f64_bits = |s| parse(s, Type::F64).unwrap_f64().to_bits();
+
+        // Halfway between 1 and the next float, which is odd
+        assert_eq!(f64_bits("0x1.00000000000008p0"), 0x3ff0_0000_0000_0000);
+        // Halfway between two floats, the even one being above
+        assert_eq!(f64_bits("0x1.00000000000018p0"), 0x3ff0_0000_0000_0002);
+        // Just above halfway
+        assert_eq!(
+            f64_bits("0x1.000000000000080000001p0"),
+            0x3ff0_0000_0000_0001
+        );
+        assert_eq!(
+            f64_bits("0xffff_ffff_ffff_ffff_ff"),
+            2f64.powi(72).to_bits()
+        );
+
+        // Rounded once to the width of the float, not to an f64 first
+        assert_eq!(f32_bits("0x1.000001p0"), 0x3f80_0000);
+        assert_eq!(f32_bits("0x1.00000100000000001p0"), 0x3f80_0001);
+
+        // Subnormals
+        assert_eq!(f64_bits("0x1p-1075"), 0);
+        assert_eq!(f64_bits("0x1.8p-1075"), 1);
+        assert_eq!(f32_bits("0x1.fffffep-127"), 0x0080_0000);
+
+        assert_eq!(f32_bits("0x1.fffffe7p127"), f32::MAX.to_bits());
+        assert!(parse_value("0x1.ffffffp127", Type::F32).is_err());
+        assert!(parse_value("0x1.fffffffffffff8p1023", Type::F64).is_err());
+    }
+
+    #[test]
+    fn vectors() {
+        assert_eq!(parse("0x1", Type::V128), Value::V128(1));
+        assert_eq!(
+            parse("i32x4:1,2,3,-1", Type::V128),
+            Value::V128(0xffffffff_00000003_00000002_00000001)
+        );
+        assert_eq!(
+            parse("f32x4:1.0 0 0 0", Type::V128),
+            Value::V128(0x3f80_0000)
+        );
+
+        assert!(parse_value("i32x4:1,2,3", Type::V128).is_err());
+        assert!(parse_value("i8x16:256,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0", Type::V128).is_err());
+    }
+
+    #[test]
+    fn references() {
+        assert!(matches!(parse("null", Type::FuncRef), Value::FuncRef(None)));
+        assert!(matches!(
+            parse("ref.null", Type::ExternRef),
+            Value::ExternRef(None)
+        ));
+        assert!(parse_value("0", Type::FuncRef).is_err());
+    }
+
+    #[test]
+    fn values_are_formatted_with_their_type() {
+        assert_eq!(format_value(&Value::I32(-1)), "i32 -1");
+        assert_eq!(format_value(&Value::I64(1 << 40)), "i64 1099511627776");
+        assert_eq!(format_value(&Value::F32(1.5)), "f32 1.5");
+        assert_eq!(format_value(&Value::F64(-0.0)), "f64 -0.0");
+        assert_eq!(format_value(&Value::F64(f64::NEG_INFINITY)), "f64 -inf");
+        assert_eq!(format_value(&Value::F32(f32::NAN)), "f32 nan");
+        assert_eq!(
+            format_value(&Value::F64(f64::from_bits(0x7ff0_0000_0000_0001))),
+            "f64 nan:0x1"
+        );
+        assert_eq!(
+            format_value(&Value::V128(1)),
+            "v128 0x00000000000000000000000000000001"
+        );
+        assert_eq!(format_value(&Value::FuncRef(None)), "funcref null");
+    }
+
+    #[test]
+    fn formatted_values_parse_back() {
+        let values = [
+            Value::I32(-7),
+            Value::I64(i64::MIN),
+            Value::F32(0.1),
+            Value::F32(f32::from_bits(0xffa0_0000)),
+            Value::F64(1e300),
+            Value::F64(f64::INFINITY),
+            Value::V128(u128::MAX),
+            Value::FuncRef(None),
+        ];
+
+        for value in values {
+            let formatted = format_value(&value);
+            let (_, bare) = formatted.split_once(' ').unwrap();
+            let parsed = parse_value(bare, value.ty()).unwrap();
+            assert_eq!(format_value(&parsed), formatted);
+        }
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            value_to_json(&Value::I32(-1)),
+            serde_json::json!({ "type": "i32", "value": -1 })
+        );
+        assert_eq!(
+            value_to_json(&Value::F64(f64::NAN)),
+            serde_json::json!({ "type": "f64", "value": "nan" })
+        );
+        assert_eq!(
+            value_to_json(&Value::ExternRef(None)),
+            serde_json::json!({ "type": "externref", "value": null })
+        );
+    }
+}
@@ -11,10 +11,16 @@ use bytes::Bytes;
 use clap::Parser;
 use tokio::runtime::Handle;
 use url::Url;
-use virtual_fs::{DeviceFile, FileSystem, PassthruFileSystem, RootFileSystemBuilder};
+use virtual_fs::{
+    DeviceFile, FileSystem, OverlayFileSystem, PassthruFileSystem, RootFileSystemBuilder,
+};
 use virtual_net::ruleset::Ruleset;
-use wasmer::{Engine, Function, Instance, Memory32, Memory64, Module, RuntimeError, Store, Value};
+use wasmer::{
+    Engine, Function, HostProfiler, Instance, Memory32, Memory64, Module, RuntimeError, Store,
+    Value,
+};
 use wasmer_config::package::PackageSource as PackageSpecifier;
+use wasmer_package::signature::signature_path;
 use wasmer_types::ModuleHash;
 #[cfg(feature = "journal")]
 use wasmer_wasix::journal::{LogFileJournal, SnapshotTrigger};
@@ -30,9 +36,13 @@ use wasmer_wasix::{
     runners::{MappedCommand, MappedDirectory},
     runtime::{
         module_cache::{FileSystemCache, ModuleCache},
-        package_loader::{BuiltinPackageLoader, PackageLoader},
+        package_loader::{
+            builtin_loader::{HashIntegrityValidationMode, TrustPolicy},
+            BuiltinPackageLoader, PackageLoader,
+        },
         resolver::{
-            BackendSource, FileSystemSource, InMemorySource, MultiSource, Source, WebSource,
+            BackendSource, FileSystemSource, InMemorySource, MultiSource, Source, VendoredSource,
+            WebSource,
         },
         task_manager::{
             tokio::{RuntimeOrHandle, TokioTaskManager},
@@ -46,8 +56,9 @@ use wasmer_wasix::{
 };
 
 use crate::{
+    commands::package::{load_public_key, read_signature, webc_hash},
     config::{UserRegistry, WasmerEnv},
-    utils::{parse_envvar, parse_mapdir},
+    utils::{parse_envvar, parse_mapdir, parse_unix_socket_mapping},
 };
 
 use super::{
@@ -72,6 +83,18 @@ pub struct Wasi {
     )]
     pub(crate) mapped_dirs: Vec<MappedDirectory>,
 
+    /// Map a guest path to a host Unix domain socket, which the Wasm module
+    /// can then listen on or connect to. When the guest path is a directory,
+    /// all the sockets within it are mapped.
+    ///
+    /// Unix domain sockets are otherwise only visible to the Wasm modules.
+    #[clap(
+        long = "map-unix-socket",
+        name = "GUEST_PATH:HOST_PATH",
+        value_parser=parse_unix_socket_mapping,
+    )]
+    pub(crate) unix_socket_mappings: Vec<(PathBuf, PathBuf)>,
+
     /// Pass custom environment variables
     #[clap(
         long = "env",
@@ -93,6 +116,45 @@ pub struct Wasi {
     #[clap(long = "include-webc", name = "WEBC")]
     pub(super) include_webcs: Vec<PathBuf>,
 
+    /// Directories created by `wasmer package vendor` that packages are
+    /// resolved from before the registry.
+    ///
+    /// The `vendor` directory of a package being run from a directory is
+    /// always used.
+    #[clap(long = "vendor-dir", name = "VENDOR_DIR")]
+    pub(crate) vendor_dirs: Vec<PathBuf>,
+
+    /// Don't access the network when resolving packages, and only use
+    /// vendored, included or local packages.
+    #[clap(long)]
+    pub(crate) offline: bool,
+
+    /// Public keys (as created by `wasmer package keygen`) to trust package
+    /// signatures from.
+    ///
+    /// Packages that aren't signed by one of these keys are run with a
+    /// warning, unless `--require-signatures` is also set.
+    #[clap(long = "trusted-key", name = "PUBLIC_KEY")]
+    pub(crate) trusted_keys: Vec<PathBuf>,
+
+    /// Refuse to run packages that aren't signed by a trusted key.
+    #[clap(long, requires = "PUBLIC_KEY")]
+    pub(crate) require_signatures: bool,
+
+    /// Fail when a package doesn't match the hash the registry expects.
+    ///
+    /// Set when a `wasmer.lock` is in use, so the packages are the ones it
+    /// pins.
+    #[clap(skip)]
+    pub(crate) verify_package_hashes: bool,
+
+    /// Keep the changes made to mapped directories in memory, leaving the
+    /// host directories untouched.
+    ///
+    /// Set when replaying a journal, which can be restarted from scratch.
+    #[clap(skip)]
+    pub(crate) copy_on_write_mapped_dirs: bool,
+
     /// List of injected atoms
     #[clap(long = "map-command", name = "MAPCMD")]
     pub(super) map_commands: Vec<String>,
@@ -197,6 +259,20 @@ pub struct Wasi {
     /// Require WASI modules to only import 1 version of WASI.
     #[clap(long = "deny-multiple-wasi-versions")]
     pub deny_multiple_wasi_versions: bool,
+
+    /// Print a summary of the WASIX syscalls made by the program when it
+    /// exits.
+    #[clap(long)]
+    pub syscall_stats: bool,
+
+    /// The maximum total size of the memories of the program, in bytes,
+    /// across all of its instances and threads.
+    #[clap(skip)]
+    pub(crate) memory_limit: Option<u64>,
+
+    /// Collects the syscall statistics of every store of the program.
+    #[clap(skip)]
+    pub(crate) host_profiler: HostProfiler,
 }
 
 pub struct RunProperties {
@@ -362,7 +438,14 @@ impl Wasi {
             if !mapped_dirs.is_empty() {
                 // TODO: should we expose the common ancestor instead of root?
                 let fs_backing: Arc<dyn FileSystem + Send + Sync> =
-                    Arc::new(PassthruFileSystem::new(default_fs_backing()));
+                    if self.copy_on_write_mapped_dirs {
+                        Arc::new(OverlayFileSystem::new(
+                            virtual_fs::mem_fs::FileSystem::default(),
+                            [PassthruFileSystem::new(default_fs_backing())],
+                        ))
+                    } else {
+                        Arc::new(PassthruFileSystem::new(default_fs_backing()))
+                    };
                 for MappedDirectory { host, guest } in self.mapped_dirs.clone() {
                     let host = if !host.is_absolute() {
                         Path::new("/").join(host)
@@ -586,11 +669,14 @@ impl Wasi {
             .map(|ruleset| Ruleset::from_str(&ruleset))
             .transpose()?;
 
-        let network = if let Some(ruleset) = ruleset {
+        let mut network = if let Some(ruleset) = ruleset {
             virtual_net::host::LocalNetworking::with_ruleset(ruleset)
         } else {
             virtual_net::host::LocalNetworking::default()
         };
+        for (guest, host) in &self.unix_socket_mappings {
+            network.map_unix_socket(guest, host);
+        }
 
         if has_networking {
             rt.set_networking_implementation(network);
@@ -625,15 +711,29 @@ impl Wasi {
         let registry = self.prepare_source(env, client, preferred_webc_version)?;
 
         let cache_dir = env.cache_dir().join("compiled");
-        let module_cache = wasmer_wasix::runtime::module_cache::in_memory()
-            .with_fallback(FileSystemCache::new(cache_dir, tokio_task_manager));
+        let module_cache = wasmer_wasix::runtime::module_cache::in_memory().with_fallback(
+            FileSystemCache::new(cache_dir, tokio_task_manager).with_limits(env.cache_limits()),
+        );
 
         rt.set_package_loader(package_loader)
             .set_module_cache(module_cache)
             .set_source(registry)
             .set_engine(Some(engine));
 
-        Ok(rt)
+        #[cfg(feature = "sys")]
+        let memory_limiter = self.memory_limit.map(|limit| {
+            let limits = wasmer::StoreLimitsBuilder::new()
+                .total_memory(usize::try_from(limit).unwrap_or(usize::MAX))
+                .build();
+            Arc::new(limits) as Arc<dyn wasmer::ResourceLimiter>
+        });
+
+        Ok(StoreConfigRuntime {
+            inner: rt,
+            #[cfg(feature = "sys")]
+            memory_limiter,
+            host_profiler: self.syscall_stats.then(|| self.host_profiler.clone()),
+        })
     }
 
     /// Helper function for instantiating a module with Wasi imports for the `Run` command.
@@ -664,23 +764,63 @@ impl Wasi {
         })
     }
 
-    fn prepare_package_loader(
+    pub(crate) fn prepare_package_loader(
         &self,
         env: &WasmerEnv,
         client: Arc<dyn HttpClient + Send + Sync>,
-    ) -> Result<impl PackageLoader> {
+    ) -> Result<BuiltinPackageLoader> {
         let checkout_dir = env.cache_dir().join("checkouts");
         let tokens = tokens_by_authority(env)?;
 
-        let loader = BuiltinPackageLoader::new()
+        let mut loader = BuiltinPackageLoader::new()
             .with_cache_dir(checkout_dir)
             .with_shared_http_client(client)
-            .with_tokens(tokens);
+            .with_tokens(tokens)
+            .with_trust_policy(self.trust_policy()?);
+        if self.verify_package_hashes {
+            loader =
+                loader.with_hash_validation_mode(HashIntegrityValidationMode::FailOnHashMismatch);
+        }
 
         Ok(loader)
     }
 
-    fn prepare_source(
+    /// The [`TrustPolicy`] described by `--trusted-key` and
+    /// `--require-signatures`.
+    pub(crate) fn trust_policy(&self) -> Result<TrustPolicy> {
+        let trusted_keys = self
+            .trusted_keys
+            .iter()
+            .map(|path| load_public_key(path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let policy = if self.require_signatures {
+            TrustPolicy::RequireTrusted { trusted_keys }
+        } else if !trusted_keys.is_empty() {
+            TrustPolicy::WarnUntrusted { trusted_keys }
+        } else {
+            TrustPolicy::AllowUnsigned
+        };
+
+        Ok(policy)
+    }
+
+    /// Check a local `*.webc` file against the [`TrustPolicy`], the same way
+    /// the package loader checks the packages it loads.
+    pub(crate) fn check_local_webc(&self, path: &Path) -> Result<()> {
+        let policy = self.trust_policy()?;
+        if !policy.is_enforced() {
+            return Ok(());
+        }
+
+        let hash = webc_hash(path)?;
+        let signature = read_signature(&signature_path(path))?;
+        policy.check(&path.display().to_string(), &hash, signature.as_ref())?;
+
+        Ok(())
+    }
+
+    pub(crate) fn prepare_source(
         &self,
         env: &WasmerEnv,
         client: Arc<dyn HttpClient + Send + Sync>,
@@ -698,6 +838,21 @@ impl Wasi {
         }
         source.add_source(preloaded);
 
+        for dir in &self.vendor_dirs {
+            let vendored = VendoredSource::new(dir).with_context(|| {
+                format!(
+                    "Unable to load the vendored packages in \"{}\"",
+                    dir.display()
+                )
+            })?;
+            source.add_source(vendored);
+        }
+
+        if self.offline {
+            source.add_source(FileSystemSource::default());
+            return Ok(source);
+        }
+
         let graphql_endpoint = self.graphql_endpoint(env)?;
         let cache_dir = env.cache_dir().join("queries");
         let mut wapm_source = BackendSource::new(graphql_endpoint, Arc::clone(&client))
@@ -781,3 +936,76 @@ fn tokens_by_authority(env: &WasmerEnv) -> Result<HashMap<String, String>> {
 
     Ok(tokens)
 }
+
+/// A [`Runtime`] which applies the per-store options of the CLI to every
+/// store it creates: the memory limit and syscall profiling.
+#[derive(Debug)]
+struct StoreConfigRuntime<R> {
+    inner: R,
+    /// Enforces the memory limit, shared by every store so the limit
+    /// applies to the program as a whole.
+    #[cfg(feature = "sys")]
+    memory_limiter: Option<Arc<dyn wasmer::ResourceLimiter>>,
+    host_profiler: Option<HostProfiler>,
+}
+
+impl<R: Runtime + Send + Sync> Runtime for StoreConfigRuntime<R> {
+    fn networking(&self) -> &virtual_net::DynVirtualNetworking {
+        self.inner.networking()
+    }
+
+    fn task_manager(&self) -> &Arc<dyn wasmer_wasix::VirtualTaskManager> {
+        self.inner.task_manager()
+    }
+
+    fn package_loader(&self) -> Arc<dyn PackageLoader + Send + Sync> {
+        self.inner.package_loader()
+    }
+
+    fn module_cache(&self) -> Arc<dyn ModuleCache + Send + Sync> {
+        self.inner.module_cache()
+    }
+
+    fn source(&self) -> Arc<dyn Source + Send + Sync> {
+        self.inner.source()
+    }
+
+    fn engine(&self) -> Engine {
+        self.inner.engine()
+    }
+
+    fn new_store(&self) -> Store {
+        #[allow(unused_mut)]
+        let mut store = self.inner.new_store();
+
+        #[cfg(feature = "sys")]
+        if let Some(limiter) = &self.memory_limiter {
+            use wasmer::AsStoreMut;
+            store
+                .as_store_mut()
+                .set_shared_resource_limiter(Some(limiter.clone()));
+        }
+
+        if let Some(profiler) = &self.host_profiler {
+            store.set_host_profiler(Some(profiler.clone()));
+        }
+
+        store
+    }
+
+    fn http_client(&self) -> Option<&wasmer_wasix::http::DynHttpClient> {
+        self.inner.http_client()
+    }
+
+    fn tty(&self) -> Option<&(dyn TtyBridge + Send + Sync)> {
+        self.inner.tty()
+    }
+
+    fn journals(&self) -> &'_ Vec<Arc<DynJournal>> {
+        self.inner.journals()
+    }
+
+    fn active_journal(&self) -> Option<&'_ DynJournal> {
+        self.inner.active_journal()
+    }
+}
@@ -4,6 +4,7 @@ use lazy_static::lazy_static;
 use std::path::{Path, PathBuf};
 use url::Url;
 use wasmer_backend_api::WasmerClient;
+use wasmer_cache::eviction::CacheLimits;
 
 lazy_static! {
     pub static ref DEFAULT_WASMER_CLI_USER_AGENT: String =
@@ -24,6 +25,16 @@ pub struct WasmerEnv {
     #[clap(long, env = "WASMER_CACHE_DIR", default_value = super::DEFAULT_WASMER_CACHE_DIR.as_os_str())]
     pub(crate) cache_dir: PathBuf,
 
+    /// The maximum size of the compiled modules cache (e.g. `10GB`), above
+    /// which the least recently used modules are evicted.
+    #[clap(long, env = "WASMER_CACHE_MAX_SIZE")]
+    pub(crate) cache_max_size: Option<bytesize::ByteSize>,
+
+    /// The maximum time since a compiled module was last used (e.g. `30d`),
+    /// after which it's evicted from the cache.
+    #[clap(long, env = "WASMER_CACHE_MAX_AGE")]
+    pub(crate) cache_max_age: Option<humantime::Duration>,
+
     /// The registry to fetch packages from (inferred from the environment by
     /// default)
     #[clap(long, env = "WASMER_REGISTRY")]
@@ -47,6 +58,8 @@ impl WasmerEnv {
             registry,
             token,
             cache_dir,
+            cache_max_size: None,
+            cache_max_age: None,
         }
     }
 
@@ -112,6 +125,14 @@ impl WasmerEnv {
         &self.cache_dir
     }
 
+    /// The limits on the disk usage of the compiled modules cache.
+    pub fn cache_limits(&self) -> CacheLimits {
+        CacheLimits {
+            max_size: self.cache_max_size.map(|size| size.as_u64()),
+            max_age: self.cache_max_age.map(Into::into),
+        }
+    }
+
     /// Retrieve the specified token.
     ///
     /// NOTE: In contrast to [`Self::token`], this will not fall back to loading
@@ -171,6 +192,8 @@ impl Default for WasmerEnv {
         Self {
             wasmer_dir: super::DEFAULT_WASMER_DIR.clone(),
             cache_dir: super::DEFAULT_WASMER_CACHE_DIR.clone(),
+            cache_max_size: None,
+            cache_max_age: None,
             registry: None,
             token: None,
         }
@@ -241,6 +264,8 @@ mod tests {
             wasmer_dir: temp.path().to_path_buf(),
             registry: None,
             cache_dir: temp.path().join("cache").to_path_buf(),
+            cache_max_size: None,
+            cache_max_age: None,
             token: None,
         };
 
@@ -261,6 +286,8 @@ mod tests {
             wasmer_dir: temp.path().to_path_buf(),
             registry: None,
             cache_dir: temp.path().join("cache").to_path_buf(),
+            cache_max_size: None,
+            cache_max_age: None,
             token: Some("asdf".to_string()),
         };
 
@@ -280,6 +307,8 @@ mod tests {
             wasmer_dir: temp.path().to_path_buf(),
             registry: Some(UserRegistry::from("wasmer.wtf")),
             cache_dir: temp.path().join("cache").to_path_buf(),
+            cache_max_size: None,
+            cache_max_age: None,
             token: None,
         };
 
@@ -300,6 +329,8 @@ mod tests {
             wasmer_dir: temp.path().to_path_buf(),
             registry: Some(UserRegistry::from("wasmer.wtf")),
             cache_dir: temp.path().join("cache").to_path_buf(),
+            cache_max_size: None,
+            cache_max_age: None,
             token: Some("asdf".to_string()),
         };
 
@@ -321,6 +352,8 @@ mod tests {
             wasmer_dir: temp.path().to_path_buf(),
             registry: None,
             cache_dir: expected_cache_dir.clone(),
+            cache_max_size: None,
+            cache_max_age: None,
             token: None,
         };
 
@@ -356,6 +389,8 @@ mod tests {
                 wasmer_dir: temp.path().to_path_buf(),
                 registry: Some(UserRegistry::from(input)),
                 cache_dir: temp.path().join("cache").to_path_buf(),
+                cache_max_size: None,
+                cache_max_age: None,
                 token: None,
             };
 
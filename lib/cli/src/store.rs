@@ -10,7 +10,7 @@ use std::path::PathBuf;
 use std::string::ToString;
 use std::sync::Arc;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 #[cfg(feature = "sys")]
 use wasmer::sys::Features;
 use wasmer::*;
@@ -88,6 +88,19 @@ pub struct CompilerOptions {
     #[clap(long)]
     llvm_debug_dir: Option<PathBuf>,
 
+    /// Record the compiled functions so that `perf` and `samply` can
+    /// symbolicate them.
+    ///
+    /// `perfmap` writes `/tmp/perf-<pid>.map`, `jitdump` writes
+    /// `jit-<pid>.dump` (in `$JITDUMPDIR` if set) including line info.
+    #[clap(long, value_name = "perfmap|jitdump")]
+    profile: Option<wasmer::sys::ProfilingStrategy>,
+
+    /// Register the compiled functions with the GDB JIT interface, with the
+    /// DWARF of the module, so that `gdb` and `lldb` can debug them.
+    #[clap(long, short = 'g')]
+    debug_info: bool,
+
     #[clap(flatten)]
     features: WasmFeatures,
 }
@@ -164,9 +177,22 @@ impl CompilerOptions {
         compiler_config: Box<dyn CompilerConfig>,
     ) -> Result<Engine> {
         let features = self.get_features(compiler_config.default_features_for_target(&target))?;
+        let profiler = match self.profile {
+            Some(strategy) => Some(
+                strategy
+                    .agent()
+                    .with_context(|| format!("failed to set up the {strategy} profiler"))?,
+            ),
+            None => None,
+        };
+        if self.debug_info && !cfg!(feature = "gdb-jit") {
+            bail!("Debug info requires wasmer to be built with the `gdb-jit` feature");
+        }
         let engine: Engine = wasmer_compiler::EngineBuilder::new(compiler_config)
             .set_features(Some(features))
             .set_target(Some(target))
+            .set_profiler(profiler)
+            .set_debug_info(self.debug_info)
             .engine();
 
         Ok(engine)
@@ -226,6 +252,7 @@ impl CompilerOptions {
                             Type::V128 => "v".to_string(),
                             Type::ExternRef => "e".to_string(),
                             Type::FuncRef => "r".to_string(),
+                            Type::ExceptionRef => "x".to_string(),
                         })
                         .collect::<Vec<_>>()
                         .join("")
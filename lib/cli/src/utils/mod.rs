@@ -48,6 +48,25 @@ pub fn parse_mapdir(entry: &str) -> Result<MappedDirectory> {
     }
 }
 
+/// Parses the mapping of a guest path to a host Unix domain socket.
+pub fn parse_unix_socket_mapping(entry: &str) -> Result<(PathBuf, PathBuf)> {
+    match entry.split_once(':') {
+        Some((guest, host)) if !guest.is_empty() && !host.is_empty() => {
+            if !guest.starts_with('/') {
+                bail!("The guest path of a Unix domain socket must be absolute. Found {guest}");
+            }
+            // The socket doesn't exist yet when the guest listens on it, so
+            // it can't be canonicalized.
+            let host = std::env::current_dir()?.join(host);
+            Ok((PathBuf::from(guest), host))
+        }
+        _ => bail!(
+            "Unix domain socket mappings must consist of two paths separated by a `:`. Found {}",
+            &entry
+        ),
+    }
+}
+
 /// Parses an environment variable.
 pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
     let entry = entry.trim();
@@ -289,6 +308,20 @@ c: c
         }
     }
 
+    #[test]
+    fn test_parse_unix_socket_mapping() {
+        assert_eq!(
+            parse_unix_socket_mapping("/run/pg.sock:/tmp/pg.sock").unwrap(),
+            ("/run/pg.sock".into(), "/tmp/pg.sock".into())
+        );
+        assert_eq!(
+            parse_unix_socket_mapping("/run/pg.sock:pg.sock").unwrap().1,
+            std::env::current_dir().unwrap().join("pg.sock")
+        );
+        assert!(parse_unix_socket_mapping("/run/pg.sock").is_err());
+        assert!(parse_unix_socket_mapping("run/pg.sock:/tmp/pg.sock").is_err());
+    }
+
     #[test]
     fn test_parse_envvar() {
         assert_eq!(
@@ -8,7 +8,8 @@ use crate::{
     config::Cranelift,
     func_environ::{get_function_name, FuncEnvironment},
     trampoline::{
-        make_trampoline_dynamic_function, make_trampoline_function_call, FunctionBuilderContext,
+        make_trampoline_dynamic_function, make_trampoline_function_call,
+        make_trampoline_function_entry, FunctionBuilderContext,
     },
     translator::{
         compiled_function_unwind_info, irlibcall_to_libcall, irreloc_to_relocationkind,
@@ -17,6 +18,7 @@ use crate::{
 };
 use cranelift_codegen::{
     ir::{self, ExternalName, UserFuncName},
+    isa::CallConv,
     Context, FinalizedMachReloc, FinalizedRelocTarget, MachTrap,
 };
 
@@ -84,7 +86,7 @@ impl Compiler for CraneliftCompiler {
     ) -> Result<Compilation, CompileError> {
         let isa = self
             .config()
-            .isa(target)
+            .isa(target, &compile_info.features)
             .map_err(|error| CompileError::Codegen(error.to_string()))?;
         let frontend_config = isa.frontend_config();
         let memory_styles = &compile_info.memory_styles;
@@ -133,11 +135,13 @@ impl Compiler for CraneliftCompiler {
                 let func_index = module.func_index(i);
                 let mut context = Context::new();
                 let mut func_env = FuncEnvironment::new(
-                    isa.frontend_config(),
                     module,
                     &signatures,
                     &memory_styles,
                     table_styles,
+                    &compile_info.features,
+                    &*isa,
+                    &self.config,
                 );
                 context.func.name = match get_function_name(func_index) {
                     ExternalName::User(nameref) => {
@@ -152,6 +156,9 @@ impl Compiler for CraneliftCompiler {
                     _ => UserFuncName::default(),
                 };
                 context.func.signature = signatures[module.functions[func_index]].clone();
+                if compile_info.features.tail_call {
+                    context.func.signature.call_conv = CallConv::Tail;
+                }
                 // if generate_debug_info {
                 //     context.func.collect_debug_info();
                 // }
@@ -245,11 +252,13 @@ impl Compiler for CraneliftCompiler {
                 let func_index = module.func_index(*i);
                 let mut context = Context::new();
                 let mut func_env = FuncEnvironment::new(
-                    isa.frontend_config(),
                     module,
                     &signatures,
                     memory_styles,
                     table_styles,
+                    &compile_info.features,
+                    &*isa,
+                    &self.config,
                 );
                 context.func.name = match get_function_name(func_index) {
                     ExternalName::User(nameref) => {
@@ -264,6 +273,9 @@ impl Compiler for CraneliftCompiler {
                     _ => UserFuncName::default(),
                 };
                 context.func.signature = signatures[module.functions[func_index]].clone();
+                if compile_info.features.tail_call {
+                    context.func.signature.call_conv = CallConv::Tail;
+                }
                 // if generate_debug_info {
                 //     context.func.collect_debug_info();
                 // }
@@ -419,17 +431,35 @@ impl Compiler for CraneliftCompiler {
             .into_iter()
             .collect::<PrimaryMap<FunctionIndex, FunctionBody>>();
 
+        // function entry trampolines (only when the function bodies use
+        // the `tail` calling convention)
+        let function_entry_trampolines = if compile_info.features.tail_call {
+            let mut cx = FunctionBuilderContext::new();
+            function_body_inputs
+                .keys()
+                .map(|local_index| {
+                    let func_index = module.func_index(local_index);
+                    let func_type = &module.signatures[module.functions[func_index]];
+                    make_trampoline_function_entry(&*isa, module, &mut cx, func_index, func_type)
+                        .map(|section| custom_sections.push(section))
+                })
+                .collect::<Result<PrimaryMap<LocalFunctionIndex, SectionIndex>, CompileError>>()?
+        } else {
+            PrimaryMap::new()
+        };
+
         Ok(Compilation {
             functions: functions.into_iter().collect(),
             custom_sections,
             function_call_trampolines,
             dynamic_function_trampolines,
+            function_entry_trampolines,
             debug: dwarf,
         })
     }
 }
 
-fn mach_reloc_to_reloc(module: &ModuleInfo, reloc: &FinalizedMachReloc) -> Relocation {
+pub(crate) fn mach_reloc_to_reloc(module: &ModuleInfo, reloc: &FinalizedMachReloc) -> Relocation {
     let FinalizedMachReloc {
         offset,
         kind,
@@ -483,12 +513,10 @@ fn translate_ir_trapcode(trap: ir::TrapCode) -> TrapCode {
         ir::TrapCode::IntegerDivisionByZero => TrapCode::IntegerDivisionByZero,
         ir::TrapCode::BadConversionToInteger => TrapCode::BadConversionToInteger,
         ir::TrapCode::UnreachableCodeReached => TrapCode::UnreachableCodeReached,
-        ir::TrapCode::Interrupt => unimplemented!("Interrupts not supported"),
-        ir::TrapCode::NullReference | ir::TrapCode::NullI31Ref => {
-            unimplemented!("Null reference not supported")
-        }
+        ir::TrapCode::Interrupt => TrapCode::Interrupt,
+        ir::TrapCode::NullReference => TrapCode::NullReference,
+        ir::TrapCode::NullI31Ref => TrapCode::NullReference,
         ir::TrapCode::User(_user_code) => unimplemented!("User trap code not supported"),
-        // ir::TrapCode::Interrupt => TrapCode::Interrupt,
         // ir::TrapCode::User(user_code) => TrapCode::User(user_code),
     }
 }
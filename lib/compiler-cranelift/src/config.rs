@@ -9,6 +9,7 @@ use wasmer_compiler::{
     types::target::{Architecture, CpuFeature, Target},
     Compiler, CompilerConfig, Engine, EngineBuilder, ModuleMiddleware,
 };
+use wasmer_types::Features;
 
 // Runtime Environment
 
@@ -36,6 +37,8 @@ pub struct Cranelift {
     enable_nan_canonicalization: bool,
     enable_verifier: bool,
     enable_pic: bool,
+    enable_relaxed_simd_deterministic: bool,
+    enable_epoch_interruption: bool,
     opt_level: CraneliftOptLevel,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
@@ -50,6 +53,8 @@ impl Cranelift {
             enable_verifier: false,
             opt_level: CraneliftOptLevel::Speed,
             enable_pic: false,
+            enable_relaxed_simd_deterministic: true,
+            enable_epoch_interruption: false,
             middlewares: vec![],
         }
     }
@@ -63,14 +68,48 @@ impl Cranelift {
         self
     }
 
+    /// Whether the relaxed SIMD operators produce the same results on
+    /// every platform.
+    ///
+    /// When enabled (the default), each relaxed operator behaves like its
+    /// non-relaxed counterpart, e.g. `f32x4.relaxed_madd` is always fused
+    /// and `i8x16.relaxed_swizzle` behaves like `i8x16.swizzle`.
+    ///
+    /// When disabled, the operators are lowered to the fastest native
+    /// instructions of the target, e.g. `f32x4.relaxed_madd` is unfused on
+    /// x86-64 CPUs without FMA, so results may differ between hosts.
+    pub fn relaxed_simd_deterministic(&mut self, enable: bool) -> &mut Self {
+        self.enable_relaxed_simd_deterministic = enable;
+        self
+    }
+
+    /// Whether the relaxed SIMD operators are lowered deterministically.
+    pub(crate) fn is_relaxed_simd_deterministic(&self) -> bool {
+        self.enable_relaxed_simd_deterministic
+    }
+
+    /// Enable epoch-based interruption.
+    ///
+    /// Compiled code checks the epoch deadline of its store at every
+    /// function entry and loop header.
+    pub fn epoch_interruption(&mut self, enable: bool) -> &mut Self {
+        self.enable_epoch_interruption = enable;
+        self
+    }
+
+    /// Whether epoch deadline checks are emitted.
+    pub(crate) fn is_epoch_interruption(&self) -> bool {
+        self.enable_epoch_interruption
+    }
+
     /// The optimization levels when optimizing the IR.
     pub fn opt_level(&mut self, opt_level: CraneliftOptLevel) -> &mut Self {
         self.opt_level = opt_level;
         self
     }
 
-    /// Generates the ISA for the provided target
-    pub fn isa(&self, target: &Target) -> CodegenResult<Arc<dyn TargetIsa>> {
+    /// Generates the ISA for the provided target and WebAssembly features
+    pub fn isa(&self, target: &Target, features: &Features) -> CodegenResult<Arc<dyn TargetIsa>> {
         let mut builder =
             lookup(target.triple().clone()).expect("construct Cranelift ISA for triple");
         // Cpu Features
@@ -121,11 +160,11 @@ impl Cranelift {
             builder.enable("has_lzcnt").expect("should be valid flag");
         }
 
-        builder.finish(self.flags(target))
+        builder.finish(self.flags(target, features))
     }
 
     /// Generates the flags for the compiler
-    pub fn flags(&self, target: &Target) -> settings::Flags {
+    pub fn flags(&self, target: &Target, features: &Features) -> settings::Flags {
         let mut flags = settings::builder();
 
         // Enable probestack
@@ -163,6 +202,13 @@ impl Cranelift {
             .set("enable_safepoints", "true")
             .expect("should be valid flag");
 
+        // Tail calls rely on the frame pointers being present.
+        if features.tail_call {
+            flags
+                .enable("preserve_frame_pointers")
+                .expect("should be valid flag");
+        }
+
         flags
             .set(
                 "opt_level",
@@ -200,6 +246,10 @@ impl CompilerConfig for Cranelift {
         self.enable_nan_canonicalization = enable;
     }
 
+    fn epoch_interruption(&mut self, enable: bool) {
+        self.enable_epoch_interruption = enable;
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(CraneliftCompiler::new(*self))
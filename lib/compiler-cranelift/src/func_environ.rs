@@ -2,6 +2,7 @@
 // Attributions: https://github.com/wasmerio/wasmer/blob/main/docs/ATTRIBUTIONS.md
 
 use crate::{
+    config::Cranelift,
     heap::{Heap, HeapData, HeapStyle},
     table::{TableData, TableSize},
     translator::{FuncEnvironment as BaseFuncEnvironment, GlobalVariable, TargetEnvironment},
@@ -15,18 +16,21 @@ use cranelift_codegen::{
         types::*,
         AbiParam, ArgumentPurpose, Function, InstBuilder, MemFlags, Signature,
     },
-    isa::TargetFrontendConfig,
+    isa::{CallConv, TargetFrontendConfig, TargetIsa},
 };
 use cranelift_frontend::FunctionBuilder;
 use std::convert::TryFrom;
 use wasmer_compiler::wasmparser::HeapType;
 use wasmer_types::{
     entity::{EntityRef, PrimaryMap, SecondaryMap},
-    FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, MemoryStyle,
-    ModuleInfo, SignatureIndex, TableIndex, TableStyle, Type as WasmerType, VMBuiltinFunctionIndex,
-    VMOffsets, WasmError, WasmResult,
+    Features, FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex,
+    MemoryStyle, ModuleInfo, SignatureIndex, TableIndex, TableStyle, TagIndex, Type as WasmerType,
+    VMBuiltinFunctionIndex, VMOffsets, WasmError, WasmResult,
 };
 
+/// The size of a `RawValue`, the unit in which exception payloads are stored.
+const RAW_VALUE_SIZE: usize = 16;
+
 /// Compute an `ir::ExternalName` for a given wasm function index.
 pub fn get_function_name(func_index: FunctionIndex) -> ir::ExternalName {
     ir::ExternalName::user(ir::UserExternalNameRef::from_u32(func_index.as_u32()))
@@ -119,6 +123,18 @@ pub struct FuncEnvironment<'module_environment> {
     /// The external function signature for implementing wasm's `memory32.atomic.notify`.
     memory32_atomic_notify_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's `throw`.
+    throw_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `throw_ref`.
+    throw_ref_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing epoch deadline checks.
+    epoch_deadline_reached_sig: Option<ir::SigRef>,
+
+    /// The WebAssembly features enabled for this module.
+    features: &'module_environment Features,
+
     /// Offsets to struct fields accessed by JIT code.
     offsets: VMOffsets,
 
@@ -129,16 +145,25 @@ pub struct FuncEnvironment<'module_environment> {
     tables: SecondaryMap<TableIndex, Option<TableData>>,
 
     table_styles: &'module_environment PrimaryMap<TableIndex, TableStyle>,
+
+    /// The target ISA, used to pick the lowering of relaxed SIMD operators.
+    isa: &'module_environment dyn TargetIsa,
+
+    /// The compiler configuration.
+    config: &'module_environment Cranelift,
 }
 
 impl<'module_environment> FuncEnvironment<'module_environment> {
     pub fn new(
-        target_config: TargetFrontendConfig,
         module: &'module_environment ModuleInfo,
         signatures: &'module_environment PrimaryMap<SignatureIndex, ir::Signature>,
         memory_styles: &'module_environment PrimaryMap<MemoryIndex, MemoryStyle>,
         table_styles: &'module_environment PrimaryMap<TableIndex, TableStyle>,
+        features: &'module_environment Features,
+        isa: &'module_environment dyn TargetIsa,
+        config: &'module_environment Cranelift,
     ) -> Self {
+        let target_config = isa.frontend_config();
         Self {
             target_config,
             module,
@@ -164,10 +189,16 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             memory32_atomic_wait32_sig: None,
             memory32_atomic_wait64_sig: None,
             memory32_atomic_notify_sig: None,
+            throw_sig: None,
+            throw_ref_sig: None,
+            epoch_deadline_reached_sig: None,
+            features,
             offsets: VMOffsets::new(target_config.pointer_bytes(), module),
             memory_styles,
             tables: Default::default(),
             table_styles,
+            isa,
+            config,
         }
     }
 
@@ -913,6 +944,151 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         }
     }
 
+    fn get_throw_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.throw_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Tag index
+                    AbiParam::new(I32),
+                    // Values
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.throw_sig = Some(sig);
+        sig
+    }
+
+    fn get_throw_func(&mut self, func: &mut Function) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.get_throw_sig(func);
+        (sig, VMBuiltinFunctionIndex::get_throw_index())
+    }
+
+    fn get_throw_ref_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.throw_ref_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Exception reference
+                    AbiParam::new(self.reference_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.throw_ref_sig = Some(sig);
+        sig
+    }
+
+    fn get_throw_ref_func(&mut self, func: &mut Function) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.get_throw_ref_sig(func);
+        (sig, VMBuiltinFunctionIndex::get_throw_ref_index())
+    }
+
+    fn get_drop_exception_func(
+        &mut self,
+        func: &mut Function,
+    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        // Same signature as `throw_ref`: the vmctx and an exception reference.
+        let sig = self.get_throw_ref_sig(func);
+        (sig, VMBuiltinFunctionIndex::get_drop_exception_index())
+    }
+
+    fn get_expose_exception_func(
+        &mut self,
+        func: &mut Function,
+    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        // Same signature as `throw_ref`: the vmctx and an exception reference.
+        let sig = self.get_throw_ref_sig(func);
+        (sig, VMBuiltinFunctionIndex::get_expose_exception_index())
+    }
+
+    fn get_epoch_deadline_reached_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.epoch_deadline_reached_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![AbiParam::special(
+                    self.pointer_type(),
+                    ArgumentPurpose::VMContext,
+                )],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.epoch_deadline_reached_sig = Some(sig);
+        sig
+    }
+
+    fn get_epoch_deadline_reached_func(
+        &mut self,
+        func: &mut Function,
+    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.get_epoch_deadline_reached_sig(func);
+        (
+            sig,
+            VMBuiltinFunctionIndex::get_epoch_deadline_reached_index(),
+        )
+    }
+
+    /// Emits a check of the epoch deadline of the store, calling the
+    /// `epoch_deadline_reached` builtin once the current epoch reaches it.
+    fn translate_epoch_check(&mut self, builder: &mut FunctionBuilder) {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx(builder.func);
+        let base = builder.ins().global_value(pointer_type, vmctx);
+
+        let mut readonly = ir::MemFlags::trusted();
+        readonly.set_readonly();
+
+        let offset = i32::try_from(self.offsets.vmctx_epoch_state()).unwrap();
+        let state = builder.ins().load(pointer_type, readonly, base, offset);
+        // The deadline and the epoch can change during the execution, so
+        // they must be reloaded at every check.
+        let offset = i32::from(self.offsets.vmepoch_state_deadline());
+        let deadline = builder
+            .ins()
+            .load(I64, ir::MemFlags::trusted(), state, offset);
+        let offset = i32::from(self.offsets.vmepoch_state_counter());
+        let counter = builder.ins().load(pointer_type, readonly, state, offset);
+        let epoch = builder.ins().load(I64, ir::MemFlags::trusted(), counter, 0);
+
+        let reached = builder
+            .ins()
+            .icmp(IntCC::UnsignedGreaterThanOrEqual, epoch, deadline);
+        let reached_block = builder.create_block();
+        let continuation_block = builder.create_block();
+        builder.set_cold_block(reached_block);
+        builder
+            .ins()
+            .brif(reached, reached_block, &[], continuation_block, &[]);
+
+        builder.switch_to_block(reached_block);
+        builder.seal_block(reached_block);
+        let (func_sig, func_idx) = self.get_epoch_deadline_reached_func(builder.func);
+        let mut pos = builder.cursor();
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins().call_indirect(func_sig, func_addr, &[vmctx]);
+        builder.ins().jump(continuation_block, &[]);
+
+        builder.switch_to_block(continuation_block);
+        builder.seal_block(continuation_block);
+    }
+
+    /// Loads the address of the exception state of the store.
+    fn translate_load_exception_state(&mut self, builder: &mut FunctionBuilder) -> ir::Value {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx(builder.func);
+        let base = builder.ins().global_value(pointer_type, vmctx);
+
+        let mut mem_flags = ir::MemFlags::trusted();
+        mem_flags.set_readonly();
+
+        let offset = i32::try_from(self.offsets.vmctx_exception_state()).unwrap();
+        builder.ins().load(pointer_type, mem_flags, base, offset)
+    }
+
     /// Translates load of builtin function and returns a pair of values `vmctx`
     /// and address of the loaded function.
     fn translate_load_builtin_function_address(
@@ -936,6 +1112,114 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         (base, func_addr)
     }
 
+    /// The signature of the function bodies with the given signature index.
+    ///
+    /// When tail calls are enabled, function bodies use the `tail` calling
+    /// convention, and are called with the native calling convention through
+    /// their entry trampolines.
+    fn body_signature(&self, index: SignatureIndex) -> ir::Signature {
+        let mut signature = self.signatures[index].clone();
+        if self.features.tail_call {
+            signature.call_conv = CallConv::Tail;
+        }
+        signature
+    }
+
+    /// Get the anyfunc pointer for the element `callee` of the table
+    /// `table_index`, trapping if it is null or if its signature doesn't
+    /// match `sig_index`.
+    fn get_checked_anyfunc(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        callee: ir::Value,
+    ) -> ir::Value {
+        let pointer_type = self.pointer_type();
+
+        // Get the anyfunc pointer (the funcref) from the table.
+        let anyfunc_ptr = self.get_or_init_funcref_table_elem(builder, table_index, callee);
+
+        // check if the funcref is null
+        builder
+            .ins()
+            .trapz(anyfunc_ptr, ir::TrapCode::IndirectCallToNull);
+
+        // If necessary, check the signature.
+        match self.table_styles[table_index] {
+            TableStyle::CallerChecksSignature => {
+                let sig_id_size = self.offsets.size_of_vmshared_signature_index();
+                let sig_id_type = ir::Type::int(u16::from(sig_id_size) * 8).unwrap();
+                let vmctx = self.vmctx(builder.func);
+                let base = builder.ins().global_value(pointer_type, vmctx);
+                let offset =
+                    i32::try_from(self.offsets.vmctx_vmshared_signature_id(sig_index)).unwrap();
+
+                // Load the caller ID.
+                let mut mem_flags = ir::MemFlags::trusted();
+                mem_flags.set_readonly();
+                let caller_sig_id = builder.ins().load(sig_id_type, mem_flags, base, offset);
+
+                // Load the callee ID.
+                let mem_flags = ir::MemFlags::trusted();
+                let callee_sig_id = builder.ins().load(
+                    sig_id_type,
+                    mem_flags,
+                    anyfunc_ptr,
+                    i32::from(self.offsets.vmcaller_checked_anyfunc_type_index()),
+                );
+
+                // Check that they match.
+                let cmp = builder
+                    .ins()
+                    .icmp(IntCC::Equal, callee_sig_id, caller_sig_id);
+                builder.ins().trapz(cmp, ir::TrapCode::BadSignature);
+            }
+        }
+
+        anyfunc_ptr
+    }
+
+    /// Tail call the function at `tail_func_addr` with the `tail` calling
+    /// convention if it is not null.
+    ///
+    /// Otherwise, the callee can only be called with the native calling
+    /// convention, so call `func_addr` and return its results. That's the
+    /// case of host functions and of functions compiled without tail calls.
+    /// The frame of the caller is not replaced then, so a chain of tail calls
+    /// only runs in constant stack space when it doesn't go through such a
+    /// function.
+    fn translate_return_call_or_call(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        sig_index: SignatureIndex,
+        sig_ref: ir::SigRef,
+        func_addr: ir::Value,
+        tail_func_addr: ir::Value,
+        call_args: &[ir::Value],
+    ) {
+        let tail_sig_ref = builder.import_signature(self.body_signature(sig_index));
+        let tail_call_block = builder.create_block();
+        let call_block = builder.create_block();
+        builder
+            .ins()
+            .brif(tail_func_addr, tail_call_block, &[], call_block, &[]);
+        builder.seal_block(tail_call_block);
+        builder.seal_block(call_block);
+
+        builder.switch_to_block(tail_call_block);
+        builder
+            .ins()
+            .return_call_indirect(tail_sig_ref, tail_func_addr, call_args);
+
+        // A pending exception is propagated to the caller, which checks for
+        // it after its own call.
+        builder.switch_to_block(call_block);
+        let call = builder.ins().call_indirect(sig_ref, func_addr, call_args);
+        let results = builder.inst_results(call).to_vec();
+        builder.ins().return_(&results);
+    }
+
     fn get_or_init_funcref_table_elem(
         &mut self,
         builder: &mut FunctionBuilder,
@@ -1058,15 +1342,19 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
                 wasmer_compiler::wasmparser::AbstractHeapType::Extern => {
                     pos.ins().null(self.reference_type())
                 }
+                wasmer_compiler::wasmparser::AbstractHeapType::Exn => {
+                    pos.ins().null(self.reference_type())
+                }
                 _ => {
                     return Err(WasmError::Unsupported(
-                        "`ref.null T` that is not a `funcref` or an `externref`".into(),
+                        "`ref.null T` that is not a `funcref`, an `externref` or an `exnref`"
+                            .into(),
                     ))
                 }
             },
             HeapType::Concrete(_) => {
                 return Err(WasmError::Unsupported(
-                    "`ref.null T` that is not a `funcref` or an `externref`".into(),
+                    "`ref.null T` that is not a `funcref`, an `externref` or an `exnref`".into(),
                 ))
             }
         })
@@ -1240,7 +1528,9 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
                 WasmerType::F32 => ir::types::F32,
                 WasmerType::F64 => ir::types::F64,
                 WasmerType::V128 => ir::types::I8X16,
-                WasmerType::FuncRef | WasmerType::ExternRef => self.reference_type(),
+                WasmerType::FuncRef | WasmerType::ExternRef | WasmerType::ExceptionRef => {
+                    self.reference_type()
+                }
             },
         })
     }
@@ -1259,7 +1549,14 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         index: FunctionIndex,
     ) -> WasmResult<ir::FuncRef> {
         let sigidx = self.module.functions[index];
-        let signature = func.import_signature(self.signatures[sigidx].clone());
+        // Imported functions are always called with the native calling
+        // convention.
+        let signature = if self.module.is_imported_function(index) {
+            self.signatures[sigidx].clone()
+        } else {
+            self.body_signature(sigidx)
+        };
+        let signature = func.import_signature(signature);
         let name = get_function_name(index);
         Ok(func.import_function(ir::ExtFuncData {
             name,
@@ -1278,18 +1575,10 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         call_args: &[ir::Value],
     ) -> WasmResult<ir::Inst> {
         let pointer_type = self.pointer_type();
+        let anyfunc_ptr = self.get_checked_anyfunc(builder, table_index, sig_index, callee);
 
-        // Get the anyfunc pointer (the funcref) from the table.
-        let anyfunc_ptr = self.get_or_init_funcref_table_elem(builder, table_index, callee);
-
-        // Dereference table_entry_addr to get the function address.
+        // Dereference the anyfunc to get the function address.
         let mem_flags = ir::MemFlags::trusted();
-
-        // check if the funcref is null
-        builder
-            .ins()
-            .trapz(anyfunc_ptr, ir::TrapCode::IndirectCallToNull);
-
         let func_addr = builder.ins().load(
             pointer_type,
             mem_flags,
@@ -1297,38 +1586,6 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
             i32::from(self.offsets.vmcaller_checked_anyfunc_func_ptr()),
         );
 
-        // If necessary, check the signature.
-        match self.table_styles[table_index] {
-            TableStyle::CallerChecksSignature => {
-                let sig_id_size = self.offsets.size_of_vmshared_signature_index();
-                let sig_id_type = ir::Type::int(u16::from(sig_id_size) * 8).unwrap();
-                let vmctx = self.vmctx(builder.func);
-                let base = builder.ins().global_value(pointer_type, vmctx);
-                let offset =
-                    i32::try_from(self.offsets.vmctx_vmshared_signature_id(sig_index)).unwrap();
-
-                // Load the caller ID.
-                let mut mem_flags = ir::MemFlags::trusted();
-                mem_flags.set_readonly();
-                let caller_sig_id = builder.ins().load(sig_id_type, mem_flags, base, offset);
-
-                // Load the callee ID.
-                let mem_flags = ir::MemFlags::trusted();
-                let callee_sig_id = builder.ins().load(
-                    sig_id_type,
-                    mem_flags,
-                    anyfunc_ptr,
-                    i32::from(self.offsets.vmcaller_checked_anyfunc_type_index()),
-                );
-
-                // Check that they match.
-                let cmp = builder
-                    .ins()
-                    .icmp(IntCC::Equal, callee_sig_id, caller_sig_id);
-                builder.ins().trapz(cmp, ir::TrapCode::BadSignature);
-            }
-        }
-
         let mut real_call_args = Vec::with_capacity(call_args.len() + 2);
 
         // First append the callee vmctx address.
@@ -1406,6 +1663,119 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
             .call_indirect(sig_ref, func_addr, &real_call_args))
     }
 
+    fn tail_calls_enabled(&self) -> bool {
+        self.features.tail_call
+    }
+
+    fn translate_return_call(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        callee_index: FunctionIndex,
+        callee: ir::FuncRef,
+        call_args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let mut real_call_args = Vec::with_capacity(call_args.len() + 2);
+
+        // Handle direct tail calls to locally-defined functions.
+        if !self.module.is_imported_function(callee_index) {
+            let caller_vmctx = builder
+                .func
+                .special_param(ArgumentPurpose::VMContext)
+                .unwrap();
+            real_call_args.push(caller_vmctx);
+            real_call_args.extend_from_slice(call_args);
+
+            builder.ins().return_call(callee, &real_call_args);
+            return Ok(());
+        }
+
+        // Handle tail calls to imported functions, through their tail
+        // call entry point if they have one.
+        let pointer_type = self.pointer_type();
+        let sig_index = self.module.functions[callee_index];
+        let sig_ref = builder.func.dfg.ext_funcs[callee].signature;
+        let vmctx = self.vmctx(builder.func);
+        let base = builder.ins().global_value(pointer_type, vmctx);
+
+        let mem_flags = ir::MemFlags::trusted();
+
+        let body_offset =
+            i32::try_from(self.offsets.vmctx_vmfunction_import_body(callee_index)).unwrap();
+        let func_addr = builder
+            .ins()
+            .load(pointer_type, mem_flags, base, body_offset);
+        let tail_body_offset =
+            i32::try_from(self.offsets.vmctx_vmfunction_import_tail_body(callee_index)).unwrap();
+        let tail_func_addr = builder
+            .ins()
+            .load(pointer_type, mem_flags, base, tail_body_offset);
+
+        let vmctx_offset =
+            i32::try_from(self.offsets.vmctx_vmfunction_import_vmctx(callee_index)).unwrap();
+        let vmctx = builder
+            .ins()
+            .load(pointer_type, mem_flags, base, vmctx_offset);
+        real_call_args.push(vmctx);
+        real_call_args.extend_from_slice(call_args);
+
+        self.translate_return_call_or_call(
+            builder,
+            sig_index,
+            sig_ref,
+            func_addr,
+            tail_func_addr,
+            &real_call_args,
+        );
+        Ok(())
+    }
+
+    fn translate_return_call_indirect(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        sig_ref: ir::SigRef,
+        callee: ir::Value,
+        call_args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let pointer_type = self.pointer_type();
+        let anyfunc_ptr = self.get_checked_anyfunc(builder, table_index, sig_index, callee);
+
+        let mem_flags = ir::MemFlags::trusted();
+        let func_addr = builder.ins().load(
+            pointer_type,
+            mem_flags,
+            anyfunc_ptr,
+            i32::from(self.offsets.vmcaller_checked_anyfunc_func_ptr()),
+        );
+        let tail_func_addr = builder.ins().load(
+            pointer_type,
+            mem_flags,
+            anyfunc_ptr,
+            i32::from(self.offsets.vmcaller_checked_anyfunc_tail_func_ptr()),
+        );
+
+        let mut real_call_args = Vec::with_capacity(call_args.len() + 2);
+        let vmctx = builder.ins().load(
+            pointer_type,
+            mem_flags,
+            anyfunc_ptr,
+            i32::from(self.offsets.vmcaller_checked_anyfunc_vmctx()),
+        );
+        real_call_args.push(vmctx);
+        real_call_args.extend_from_slice(call_args);
+
+        self.translate_return_call_or_call(
+            builder,
+            sig_index,
+            sig_ref,
+            func_addr,
+            tail_func_addr,
+            &real_call_args,
+        );
+        Ok(())
+    }
+
     fn translate_memory_grow(
         &mut self,
         mut pos: FuncCursor<'_>,
@@ -1649,6 +2019,211 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
     }
 
+    fn translate_loop_header(&mut self, builder: &mut FunctionBuilder) -> WasmResult<()> {
+        if self.config.is_epoch_interruption() {
+            self.translate_epoch_check(builder);
+        }
+        Ok(())
+    }
+
+    fn before_translate_function(&mut self, builder: &mut FunctionBuilder) -> WasmResult<()> {
+        if self.config.is_epoch_interruption() {
+            self.translate_epoch_check(builder);
+        }
+        Ok(())
+    }
+
+    fn exceptions_enabled(&self) -> bool {
+        self.features.exceptions
+    }
+
+    fn relaxed_simd_deterministic(&self) -> bool {
+        self.config.is_relaxed_simd_deterministic()
+    }
+
+    fn has_native_fma(&self) -> bool {
+        self.isa.has_native_fma()
+    }
+
+    fn is_x86(&self) -> bool {
+        self.isa.triple().architecture == target_lexicon::Architecture::X86_64
+    }
+
+    fn use_x86_blendv_for_relaxed_laneselect(&self, ty: ir::Type) -> bool {
+        self.isa.has_x86_blendv_lowering(ty)
+    }
+
+    fn use_x86_pshufb_for_relaxed_swizzle(&self) -> bool {
+        self.isa.has_x86_pshufb_lowering()
+    }
+
+    fn use_x86_pmulhrsw_for_relaxed_q15mul(&self) -> bool {
+        self.isa.has_x86_pmulhrsw_lowering()
+    }
+
+    fn use_x86_pmaddubsw_for_dot(&self) -> bool {
+        self.isa.has_x86_pmaddubsw_lowering()
+    }
+
+    fn get_tag_params(&self, tag_index: TagIndex) -> &[WasmerType] {
+        self.module.signatures[self.module.tags[tag_index]].params()
+    }
+
+    fn translate_throw(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        tag_index: TagIndex,
+        args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let pointer_type = self.pointer_type();
+        // The payload is passed to the runtime as an array of `RawValue`s.
+        let values = if args.is_empty() {
+            builder.ins().iconst(pointer_type, 0)
+        } else {
+            let slot = builder.create_sized_stack_slot(ir::StackSlotData::new(
+                ir::StackSlotKind::ExplicitSlot,
+                (args.len() * RAW_VALUE_SIZE) as u32,
+                4,
+            ));
+            for (i, arg) in args.iter().enumerate() {
+                builder
+                    .ins()
+                    .stack_store(*arg, slot, (i * RAW_VALUE_SIZE) as i32);
+            }
+            builder.ins().stack_addr(pointer_type, slot, 0)
+        };
+
+        let (func_sig, func_idx) = self.get_throw_func(builder.func);
+        let tag_index_arg = builder.ins().iconst(I32, tag_index.as_u32() as i64);
+        let mut pos = builder.cursor();
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, tag_index_arg, values]);
+        Ok(())
+    }
+
+    fn translate_throw_ref(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exnref: ir::Value,
+    ) -> WasmResult<()> {
+        let (func_sig, func_idx) = self.get_throw_ref_func(builder.func);
+        let mut pos = builder.cursor();
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, exnref]);
+        Ok(())
+    }
+
+    fn translate_drop_exception(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exnref: ir::Value,
+    ) -> WasmResult<()> {
+        let (func_sig, func_idx) = self.get_drop_exception_func(builder.func);
+        let mut pos = builder.cursor();
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, exnref]);
+        Ok(())
+    }
+
+    fn translate_expose_exception(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exnref: ir::Value,
+    ) -> WasmResult<()> {
+        let (func_sig, func_idx) = self.get_expose_exception_func(builder.func);
+        let mut pos = builder.cursor();
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, exnref]);
+        Ok(())
+    }
+
+    fn translate_exception_pending(
+        &mut self,
+        builder: &mut FunctionBuilder,
+    ) -> WasmResult<ir::Value> {
+        let pointer_type = self.pointer_type();
+        let state = self.translate_load_exception_state(builder);
+        let offset = i32::from(self.offsets.vmexception_state_exnref());
+        Ok(builder
+            .ins()
+            .load(pointer_type, MemFlags::trusted(), state, offset))
+    }
+
+    fn translate_exception_tag_matches(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        tag_index: TagIndex,
+    ) -> WasmResult<ir::Value> {
+        let pointer_type = self.pointer_type();
+        let state = self.translate_load_exception_state(builder);
+        let offset = i32::from(self.offsets.vmexception_state_tag());
+        let thrown = builder.ins().load(I32, MemFlags::trusted(), state, offset);
+
+        let vmctx = self.vmctx(builder.func);
+        let base = builder.ins().global_value(pointer_type, vmctx);
+        let mut mem_flags = ir::MemFlags::trusted();
+        mem_flags.set_readonly();
+        let offset = i32::try_from(self.offsets.vmctx_vmshared_tag_id(tag_index)).unwrap();
+        let expected = builder.ins().load(I32, mem_flags, base, offset);
+
+        Ok(builder.ins().icmp(IntCC::Equal, thrown, expected))
+    }
+
+    fn translate_exception_payload(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        tag_index: TagIndex,
+    ) -> WasmResult<Vec<ir::Value>> {
+        let pointer_type = self.pointer_type();
+        let state = self.translate_load_exception_state(builder);
+        let offset = i32::from(self.offsets.vmexception_state_payload());
+        let payload = builder
+            .ins()
+            .load(pointer_type, MemFlags::trusted(), state, offset);
+
+        let params = self.module.signatures[self.module.tags[tag_index]].params();
+        let mut values = Vec::with_capacity(params.len());
+        for (i, param) in params.iter().enumerate() {
+            let ty = match param {
+                WasmerType::I32 => I32,
+                WasmerType::I64 => I64,
+                WasmerType::F32 => F32,
+                WasmerType::F64 => F64,
+                WasmerType::V128 => I8X16,
+                WasmerType::FuncRef | WasmerType::ExternRef | WasmerType::ExceptionRef => {
+                    self.reference_type()
+                }
+            };
+            values.push(builder.ins().load(
+                ty,
+                MemFlags::trusted(),
+                payload,
+                (i * RAW_VALUE_SIZE) as i32,
+            ));
+        }
+        Ok(values)
+    }
+
+    fn translate_catch_exception(
+        &mut self,
+        builder: &mut FunctionBuilder,
+    ) -> WasmResult<ir::Value> {
+        let state = self.translate_load_exception_state(builder);
+        let offset = i32::from(self.offsets.vmexception_state_exnref());
+        let exnref = builder
+            .ins()
+            .load(self.reference_type(), MemFlags::trusted(), state, offset);
+        let zero = builder.ins().iconst(self.pointer_type(), 0);
+        builder
+            .ins()
+            .store(MemFlags::trusted(), zero, state, offset);
+        Ok(exnref)
+    }
+
     fn get_global_type(&self, global_index: GlobalIndex) -> Option<WasmerType> {
         Some(self.module.globals.get(global_index)?.ty)
     }
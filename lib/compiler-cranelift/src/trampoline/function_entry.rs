@@ -0,0 +1,80 @@
+//! A trampoline generator for entering Wasm functions whose bodies use the
+//! `tail` calling convention.
+//!
+//! Function bodies that can be tail called use a calling convention that
+//! the host, and code compiled without tail calls, don't know about. Their
+//! entry trampolines are used instead wherever a function pointer escapes
+//! the module: they take the arguments with the native calling convention,
+//! call the body and return its results.
+use crate::compiler::mach_reloc_to_reloc;
+use crate::func_environ::get_function_name;
+use crate::translator::signature_to_cranelift_ir;
+use cranelift_codegen::{
+    ir::{self, InstBuilder},
+    isa::{CallConv, TargetIsa},
+    Context,
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use wasmer_compiler::types::section::{CustomSection, CustomSectionProtection, SectionBody};
+use wasmer_types::{CompileError, FunctionIndex, FunctionType, ModuleInfo};
+
+/// Create the entry trampoline of the local function `func_index`, whose
+/// body uses the `tail` calling convention.
+pub fn make_trampoline_function_entry(
+    isa: &dyn TargetIsa,
+    module: &ModuleInfo,
+    fn_builder_ctx: &mut FunctionBuilderContext,
+    func_index: FunctionIndex,
+    func_type: &FunctionType,
+) -> Result<CustomSection, CompileError> {
+    let frontend_config = isa.frontend_config();
+    let signature = signature_to_cranelift_ir(func_type, frontend_config);
+    let mut body_signature = signature.clone();
+    body_signature.call_conv = CallConv::Tail;
+
+    let mut context = Context::new();
+    context.func = ir::Function::with_name_signature(ir::UserFuncName::user(0, 0), signature);
+
+    {
+        let mut builder = FunctionBuilder::new(&mut context.func, fn_builder_ctx);
+        let block0 = builder.create_block();
+
+        builder.append_block_params_for_function_params(block0);
+        builder.switch_to_block(block0);
+        builder.seal_block(block0);
+
+        let body_signature = builder.import_signature(body_signature);
+        let body = builder.import_function(ir::ExtFuncData {
+            name: get_function_name(func_index),
+            signature: body_signature,
+            colocated: true,
+        });
+
+        let args = builder.func.dfg.block_params(block0).to_vec();
+        let call = builder.ins().call(body, &args);
+        let results = builder.func.dfg.inst_results(call).to_vec();
+        builder.ins().return_(&results);
+        builder.finalize()
+    }
+
+    let mut code_buf = Vec::new();
+
+    context
+        .compile_and_emit(isa, &mut code_buf, &mut Default::default())
+        .map_err(|error| CompileError::Codegen(error.inner.to_string()))?;
+
+    let relocations = context
+        .compiled_code()
+        .unwrap()
+        .buffer
+        .relocs()
+        .iter()
+        .map(|r| mach_reloc_to_reloc(module, r))
+        .collect();
+
+    Ok(CustomSection {
+        protection: CustomSectionProtection::ReadExecute,
+        bytes: SectionBody::new_with_vec(code_buf),
+        relocations,
+    })
+}
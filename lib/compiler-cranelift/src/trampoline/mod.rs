@@ -2,8 +2,10 @@
 
 mod dynamic_function;
 mod function_call;
+mod function_entry;
 
 pub use self::dynamic_function::make_trampoline_dynamic_function;
 pub use self::function_call::make_trampoline_function_call;
+pub use self::function_entry::make_trampoline_function_entry;
 
 pub use cranelift_frontend::FunctionBuilderContext;
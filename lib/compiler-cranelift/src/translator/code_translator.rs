@@ -77,7 +77,9 @@
 mod bounds_checks;
 
 use super::func_environ::{FuncEnvironment, GlobalVariable};
-use super::func_state::{ControlStackFrame, ElseData, FuncTranslationState};
+use super::func_state::{
+    CatchClause, ControlStackFrame, ElseData, ExceptionHandler, FuncTranslationState,
+};
 use super::translation_utils::{block_with_params, f32_translation, f64_translation};
 use crate::{hash_map, HashMap};
 use core::convert::TryFrom;
@@ -85,7 +87,7 @@ use cranelift_codegen::ir::condcodes::{FloatCC, IntCC};
 use cranelift_codegen::ir::immediates::Offset32;
 use cranelift_codegen::ir::types::*;
 use cranelift_codegen::ir::{
-    self, AtomicRmwOp, ConstantData, InstBuilder, JumpTableData, MemFlags, Value, ValueLabel,
+    self, AtomicRmwOp, Block, ConstantData, InstBuilder, JumpTableData, MemFlags, Value, ValueLabel,
 };
 use cranelift_codegen::packed_option::ReservedValue;
 use cranelift_frontend::{FunctionBuilder, Variable};
@@ -93,10 +95,10 @@ use itertools::Itertools;
 use smallvec::SmallVec;
 use std::vec::Vec;
 
-use wasmer_compiler::wasmparser::{MemArg, Operator};
+use wasmer_compiler::wasmparser::{Catch, MemArg, Operator};
 use wasmer_compiler::{from_binaryreadererror_wasmerror, wasm_unsupported, ModuleTranslationState};
 use wasmer_types::{
-    FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, TableIndex, WasmResult,
+    FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, TableIndex, TagIndex, WasmResult,
 };
 
 /// Given a `Reachability<T>`, unwrap the inner `T` or, when unreachable, set
@@ -286,7 +288,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 .extend_from_slice(builder.block_params(loop_body));
 
             builder.switch_to_block(loop_body);
-            environ.translate_loop_header(builder.cursor())?;
+            environ.translate_loop_header(builder)?;
         }
         Operator::If { blockty } => {
             let val = state.pop1();
@@ -433,6 +435,15 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             let frame = state.control_stack.pop().unwrap();
             let next_block = frame.following_code();
             let return_count = frame.num_return_values();
+            // The exception caught by a legacy catch clause can't be
+            // rethrown once its end is reached.
+            if let ControlStackFrame::Try {
+                caught_exception: Some(exnref),
+                ..
+            } = frame
+            {
+                environ.translate_drop_exception(builder, exnref)?;
+            }
             let return_args = state.peekn_mut(return_count);
 
             canonicalise_then_jump(builder, next_block, return_args);
@@ -443,6 +454,16 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             // since we truncate the stack back to the original height
             // below.
 
+            // Exceptions not caught by a `try` are dispatched to the
+            // enclosing handler.
+            if let ControlStackFrame::Try {
+                dispatch: Some(dispatch),
+                ..
+            } = frame
+            {
+                translate_try_end(dispatch, builder, state, environ)?;
+            }
+
             builder.switch_to_block(next_block);
             builder.seal_block(next_block);
 
@@ -490,12 +511,15 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 };
                 (return_count, frame.br_destination())
             };
+            translate_leave_catches(i, builder, state, environ)?;
             let destination_args = state.peekn(return_count);
             canonicalise_then_jump(builder, br_destination, destination_args);
             state.popn(return_count);
             state.reachable = false;
         }
-        Operator::BrIf { relative_depth } => translate_br_if(*relative_depth, builder, state),
+        Operator::BrIf { relative_depth } => {
+            translate_br_if(*relative_depth, builder, state, environ)?;
+        }
         Operator::BrTable { targets } => {
             let default = targets.default();
             let mut min_depth = default;
@@ -514,9 +538,18 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                     min_depth_frame.num_return_values()
                 }
             };
+            // Branches leaving legacy catch clauses release their exceptions
+            // on the way, so their edges are split too.
+            let mut leaves_catches =
+                catches_left(state.control_stack.len() - 1 - (default as usize), state);
+            for depth in targets.targets() {
+                let depth = depth.map_err(from_binaryreadererror_wasmerror)?;
+                leaves_catches |=
+                    catches_left(state.control_stack.len() - 1 - (depth as usize), state);
+            }
             let val = state.pop1();
             let mut data = Vec::with_capacity(targets.len() as usize);
-            if jump_args_count == 0 {
+            if jump_args_count == 0 && !leaves_catches {
                 // No jump arguments
                 for depth in targets.targets() {
                     let depth = depth.map_err(from_binaryreadererror_wasmerror)?;
@@ -569,12 +602,13 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 for (depth, dest_block) in dest_block_sequence {
                     builder.switch_to_block(dest_block);
                     builder.seal_block(dest_block);
+                    let i = state.control_stack.len() - 1 - depth;
                     let real_dest_block = {
-                        let i = state.control_stack.len() - 1 - depth;
                         let frame = &mut state.control_stack[i];
                         frame.set_branched_to_exit();
                         frame.br_destination()
                     };
+                    translate_leave_catches(i, builder, state, environ)?;
                     let destination_args = state.peekn_mut(return_count);
                     canonicalise_then_jump(builder, real_dest_block, destination_args);
                 }
@@ -587,6 +621,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 let frame = &mut state.control_stack[0];
                 frame.num_return_values()
             };
+            translate_leave_catches(0, builder, state, environ)?;
             {
                 let return_args = state.peekn_mut(return_count);
                 environ.handle_before_return(return_args, builder);
@@ -597,17 +632,106 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             state.reachable = false;
         }
 
-        /********************************** Exception handing **********************************/
-        Operator::Try { .. }
-        | Operator::Catch { .. }
-        | Operator::Throw { .. }
-        | Operator::Rethrow { .. }
-        | Operator::Delegate { .. }
-        | Operator::CatchAll => {
-            return Err(wasm_unsupported!(
-                "proposed exception handling operator {:?}",
-                op
-            ));
+        /********************************** Exception handing **********************************
+         *  Exceptions are not implemented with native unwinding. Instead, a thrown exception
+         *  is recorded in the exception state of the store, and every call is followed by a
+         *  check of that state. When an exception is pending, control is transferred to the
+         *  dispatch block of the innermost handler, which compares the tag of the exception
+         *  against its catch clauses, or, if there is no handler, to a block returning to the
+         *  caller, which does the same check after its own call.
+         ***********************************************************************************/
+        Operator::Try { blockty } => {
+            let (params, results) = module_translation_state.blocktype_params_results(blockty)?;
+            let next = block_with_params(builder, results.iter(), environ)?;
+            let dispatch = builder.create_block();
+            state.handlers.push(ExceptionHandler {
+                dispatch,
+                frame: state.control_stack.len(),
+                catches: Vec::new(),
+            });
+            state.push_try(next, Some(dispatch), params.len(), results.len());
+        }
+        Operator::TryTable { try_table } => {
+            let (params, results) =
+                module_translation_state.blocktype_params_results(&try_table.ty)?;
+            let next = block_with_params(builder, results.iter(), environ)?;
+            let dispatch = builder.create_block();
+            // The labels of the catch clauses are relative to the block
+            // enclosing the `try_table`.
+            let depth = state.control_stack.len() - 1;
+            let catches = try_table
+                .catches
+                .iter()
+                .map(|catch| match *catch {
+                    Catch::One { tag, label } => CatchClause {
+                        tag: Some(TagIndex::from_u32(tag)),
+                        with_ref: false,
+                        target: depth - label as usize,
+                    },
+                    Catch::OneRef { tag, label } => CatchClause {
+                        tag: Some(TagIndex::from_u32(tag)),
+                        with_ref: true,
+                        target: depth - label as usize,
+                    },
+                    Catch::All { label } => CatchClause {
+                        tag: None,
+                        with_ref: false,
+                        target: depth - label as usize,
+                    },
+                    Catch::AllRef { label } => CatchClause {
+                        tag: None,
+                        with_ref: true,
+                        target: depth - label as usize,
+                    },
+                })
+                .collect();
+            state.handlers.push(ExceptionHandler {
+                dispatch,
+                frame: state.control_stack.len(),
+                catches,
+            });
+            state.push_try(next, Some(dispatch), params.len(), results.len());
+        }
+        Operator::Catch { tag_index } => {
+            translate_catch(
+                Some(TagIndex::from_u32(*tag_index)),
+                builder,
+                state,
+                environ,
+            )?;
+        }
+        Operator::CatchAll => {
+            translate_catch(None, builder, state, environ)?;
+        }
+        Operator::Delegate { relative_depth } => {
+            translate_delegate(*relative_depth, builder, state, environ)?;
+        }
+        Operator::Throw { tag_index } => {
+            let tag_index = TagIndex::from_u32(*tag_index);
+            let num_args = environ.get_tag_params(tag_index).len();
+            environ.translate_throw(builder, tag_index, state.peekn(num_args))?;
+            state.popn(num_args);
+            jump_to_exception_target(builder, state, environ)?;
+            state.reachable = false;
+        }
+        Operator::ThrowRef => {
+            let exnref = state.pop1();
+            environ.translate_throw_ref(builder, exnref)?;
+            jump_to_exception_target(builder, state, environ)?;
+            state.reachable = false;
+        }
+        Operator::Rethrow { relative_depth } => {
+            let i = state.control_stack.len() - 1 - (*relative_depth as usize);
+            let exnref = match state.control_stack[i] {
+                ControlStackFrame::Try {
+                    caught_exception: Some(exnref),
+                    ..
+                } => exnref,
+                _ => unreachable!("rethrow must target a catch clause"),
+            };
+            environ.translate_throw_ref(builder, exnref)?;
+            jump_to_exception_target(builder, state, environ)?;
+            state.reachable = false;
         }
         /************************************ Calls ****************************************
          * The call instructions pop off their arguments from the stack and append their
@@ -642,6 +766,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             );
             state.popn(num_args);
             state.pushn(inst_results);
+            translate_exception_check(builder, state, environ)?;
         }
         Operator::CallIndirect {
             type_index,
@@ -674,6 +799,58 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             );
             state.popn(num_args);
             state.pushn(inst_results);
+            translate_exception_check(builder, state, environ)?;
+        }
+        Operator::ReturnCall { function_index } => {
+            if !environ.tail_calls_enabled() {
+                return Err(wasm_unsupported!("proposed tail-call operator {:?}", op));
+            }
+            let (fref, num_args) = state.get_direct_func(builder.func, *function_index, environ)?;
+            translate_leave_catches(0, builder, state, environ)?;
+
+            // Bitcast any vector arguments to their default type, I8X16, before calling.
+            let args = state.peekn_mut(num_args);
+            bitcast_wasm_params(
+                environ,
+                builder.func.dfg.ext_funcs[fref].signature,
+                args,
+                builder,
+            );
+
+            environ.translate_return_call(
+                builder,
+                FunctionIndex::from_u32(*function_index),
+                fref,
+                args,
+            )?;
+            state.popn(num_args);
+            state.reachable = false;
+        }
+        Operator::ReturnCallIndirect {
+            type_index,
+            table_index,
+        } => {
+            if !environ.tail_calls_enabled() {
+                return Err(wasm_unsupported!("proposed tail-call operator {:?}", op));
+            }
+            let (sigref, num_args) = state.get_indirect_sig(builder.func, *type_index, environ)?;
+            let callee = state.pop1();
+            translate_leave_catches(0, builder, state, environ)?;
+
+            // Bitcast any vector arguments to their default type, I8X16, before calling.
+            let args = state.peekn_mut(num_args);
+            bitcast_wasm_params(environ, sigref, args, builder);
+
+            environ.translate_return_call_indirect(
+                builder,
+                TableIndex::from_u32(*table_index),
+                SignatureIndex::from_u32(*type_index),
+                sigref,
+                callee,
+                state.peekn(num_args),
+            )?;
+            state.popn(num_args);
+            state.reachable = false;
         }
         /******************************* Memory management ***********************************
          * Memory management is handled by environment. It is usually translated into calls to
@@ -2163,35 +2340,148 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             let b_high = builder.ins().uwiden_high(b);
             state.push1(builder.ins().imul(a_high, b_high));
         }
-        Operator::ReturnCall { .. } | Operator::ReturnCallIndirect { .. } => {
-            return Err(wasm_unsupported!("proposed tail-call operator {:?}", op));
+        Operator::I8x16RelaxedSwizzle => {
+            let (a, b) = pop2_with_bitcast(state, I8X16, builder);
+            state.push1(
+                if environ.relaxed_simd_deterministic()
+                    || !environ.use_x86_pshufb_for_relaxed_swizzle()
+                {
+                    // Deterministic semantics match `i8x16.swizzle`.
+                    builder.ins().swizzle(a, b)
+                } else {
+                    builder.ins().x86_pshufb(a, b)
+                },
+            )
         }
-        Operator::I8x16RelaxedSwizzle
-        | Operator::I32x4RelaxedTruncF32x4S
-        | Operator::I32x4RelaxedTruncF32x4U
-        | Operator::I32x4RelaxedTruncF64x2SZero
-        | Operator::I32x4RelaxedTruncF64x2UZero
-        | Operator::F32x4RelaxedNmadd
-        | Operator::F32x4RelaxedMadd
-        | Operator::I8x16RelaxedLaneselect
+        Operator::F32x4RelaxedMadd | Operator::F64x2RelaxedMadd => {
+            let (a, b, c) = pop3_with_bitcast(state, type_of(op), builder);
+            state.push1(
+                if environ.relaxed_simd_deterministic() || environ.has_native_fma() {
+                    // Deterministic semantics are a fused multiply-add.
+                    builder.ins().fma(a, b, c)
+                } else {
+                    let mul = builder.ins().fmul(a, b);
+                    builder.ins().fadd(mul, c)
+                },
+            )
+        }
+        Operator::F32x4RelaxedNmadd | Operator::F64x2RelaxedNmadd => {
+            let (a, b, c) = pop3_with_bitcast(state, type_of(op), builder);
+            let a = builder.ins().fneg(a);
+            state.push1(
+                if environ.relaxed_simd_deterministic() || environ.has_native_fma() {
+                    // Deterministic semantics are a fused multiply-add.
+                    builder.ins().fma(a, b, c)
+                } else {
+                    let mul = builder.ins().fmul(a, b);
+                    builder.ins().fadd(mul, c)
+                },
+            )
+        }
+        Operator::I8x16RelaxedLaneselect
         | Operator::I16x8RelaxedLaneselect
         | Operator::I32x4RelaxedLaneselect
-        | Operator::I64x2RelaxedLaneselect
-        | Operator::F32x4RelaxedMin
-        | Operator::F32x4RelaxedMax
-        | Operator::F64x2RelaxedMin
-        | Operator::F64x2RelaxedMax
-        | Operator::F64x2RelaxedMadd
-        | Operator::F64x2RelaxedNmadd
-        | Operator::I16x8RelaxedDotI8x16I7x16S
-        | Operator::I32x4RelaxedDotI8x16I7x16AddS
-        | Operator::I16x8RelaxedQ15mulrS => {
-            return Err(wasm_unsupported!("proposed relaxed-simd operator {:?}", op));
+        | Operator::I64x2RelaxedLaneselect => {
+            let ty = type_of(op);
+            let (a, b, c) = pop3_with_bitcast(state, ty, builder);
+            // The CLIF operand ordering is different from the wasm one.
+            state.push1(
+                if environ.relaxed_simd_deterministic()
+                    || !environ.use_x86_blendv_for_relaxed_laneselect(ty)
+                {
+                    // Deterministic semantics match `v128.bitselect`.
+                    builder.ins().bitselect(c, a, b)
+                } else {
+                    builder.ins().x86_blendv(c, a, b)
+                },
+            )
         }
-        Operator::TryTable { .. } | Operator::ThrowRef => {
-            return Err(wasm_unsupported!(
-                "exceptions are not supported (operator: {op:?})"
-            ));
+        Operator::F32x4RelaxedMin | Operator::F64x2RelaxedMin => {
+            let ty = type_of(op);
+            let (a, b) = pop2_with_bitcast(state, ty, builder);
+            state.push1(
+                if environ.relaxed_simd_deterministic() || !environ.is_x86() {
+                    // Deterministic semantics match `fmin`.
+                    builder.ins().fmin(a, b)
+                } else {
+                    // Same as `pmin`, which maps to a single `minps` on x86.
+                    let cmp = builder.ins().fcmp(FloatCC::LessThan, b, a);
+                    let cmp = optionally_bitcast_vector(cmp, ty, builder);
+                    builder.ins().bitselect(cmp, b, a)
+                },
+            )
+        }
+        Operator::F32x4RelaxedMax | Operator::F64x2RelaxedMax => {
+            let ty = type_of(op);
+            let (a, b) = pop2_with_bitcast(state, ty, builder);
+            state.push1(
+                if environ.relaxed_simd_deterministic() || !environ.is_x86() {
+                    // Deterministic semantics match `fmax`.
+                    builder.ins().fmax(a, b)
+                } else {
+                    // Same as `pmax`, which maps to a single `maxps` on x86.
+                    let cmp = builder.ins().fcmp(FloatCC::LessThan, a, b);
+                    let cmp = optionally_bitcast_vector(cmp, ty, builder);
+                    builder.ins().bitselect(cmp, b, a)
+                },
+            )
+        }
+        Operator::I32x4RelaxedTruncF32x4S => {
+            let a = pop1_with_bitcast(state, F32X4, builder);
+            state.push1(
+                if environ.relaxed_simd_deterministic() || !environ.is_x86() {
+                    // Deterministic semantics match `i32x4.trunc_sat_f32x4_s`.
+                    builder.ins().fcvt_to_sint_sat(I32X4, a)
+                } else {
+                    builder.ins().x86_cvtt2dq(I32X4, a)
+                },
+            )
+        }
+        Operator::I32x4RelaxedTruncF32x4U => {
+            let a = pop1_with_bitcast(state, F32X4, builder);
+            state.push1(builder.ins().fcvt_to_uint_sat(I32X4, a))
+        }
+        Operator::I32x4RelaxedTruncF64x2SZero => {
+            let a = pop1_with_bitcast(state, F64X2, builder);
+            let converted_a = builder.ins().fcvt_to_sint_sat(I64X2, a);
+            let handle = builder.func.dfg.constants.insert(vec![0u8; 16].into());
+            let zero = builder.ins().vconst(I64X2, handle);
+
+            state.push1(builder.ins().snarrow(converted_a, zero));
+        }
+        Operator::I32x4RelaxedTruncF64x2UZero => {
+            let a = pop1_with_bitcast(state, F64X2, builder);
+            let converted_a = builder.ins().fcvt_to_uint_sat(I64X2, a);
+            let handle = builder.func.dfg.constants.insert(vec![0u8; 16].into());
+            let zero = builder.ins().vconst(I64X2, handle);
+
+            state.push1(builder.ins().uunarrow(converted_a, zero));
+        }
+        Operator::I16x8RelaxedQ15mulrS => {
+            let (a, b) = pop2_with_bitcast(state, I16X8, builder);
+            state.push1(
+                if environ.relaxed_simd_deterministic()
+                    || !environ.use_x86_pmulhrsw_for_relaxed_q15mul()
+                {
+                    // Deterministic semantics match `i16x8.q15mulr_sat_s`.
+                    builder.ins().sqmul_round_sat(a, b)
+                } else {
+                    builder.ins().x86_pmulhrsw(a, b)
+                },
+            )
+        }
+        Operator::I16x8RelaxedDotI8x16I7x16S => {
+            let (a, b) = pop2_with_bitcast(state, I8X16, builder);
+            state.push1(relaxed_dot_i8x16_i7x16(environ, builder, a, b))
+        }
+        Operator::I32x4RelaxedDotI8x16I7x16AddS => {
+            let c = pop1_with_bitcast(state, I32X4, builder);
+            let (a, b) = pop2_with_bitcast(state, I8X16, builder);
+            let dot = relaxed_dot_i8x16_i7x16(environ, builder, a, b);
+            let dotlo = builder.ins().swiden_low(dot);
+            let dothi = builder.ins().swiden_high(dot);
+            let dot32 = builder.ins().iadd_pairwise(dotlo, dothi);
+            state.push1(builder.ins().iadd(dot32, c))
         }
         Operator::RefEq
         | Operator::StructNew { .. }
@@ -2312,6 +2602,20 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
         Operator::Loop { blockty: _ } | Operator::Block { blockty: _ } => {
             state.push_block(ir::Block::reserved_value(), 0, 0);
         }
+        Operator::Try { blockty: _ } | Operator::TryTable { try_table: _ } => {
+            // The `try` isn't reachable, so no exception is ever dispatched
+            // to it.
+            state.push_try(ir::Block::reserved_value(), None, 0, 0);
+        }
+        Operator::Catch { tag_index } => {
+            translate_catch(Some(TagIndex::from_u32(tag_index)), builder, state, environ)?;
+        }
+        Operator::CatchAll => {
+            translate_catch(None, builder, state, environ)?;
+        }
+        Operator::Delegate { relative_depth } => {
+            translate_delegate(relative_depth, builder, state, environ)?;
+        }
         Operator::Else => {
             let i = state.control_stack.len() - 1;
             match state.control_stack[i] {
@@ -2369,12 +2673,18 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
             }
         }
         Operator::End => {
-            let stack = &mut state.stack;
-            let control_stack = &mut state.control_stack;
-            let frame = control_stack.pop().unwrap();
+            let frame = state.control_stack.pop().unwrap();
 
             // Pop unused parameters from stack.
-            frame.truncate_value_stack_to_original_size(stack);
+            frame.truncate_value_stack_to_original_size(&mut state.stack);
+
+            if let ControlStackFrame::Try {
+                dispatch: Some(dispatch),
+                ..
+            } = frame
+            {
+                translate_try_end(dispatch, builder, state, environ)?;
+            }
 
             let reachable_anyway = match frame {
                 // If it is a loop we also have to seal the body loop block
@@ -2411,7 +2721,9 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
 
                 // And add the return values of the block but only if the next block is reachable
                 // (which corresponds to testing if the stack depth is 1)
-                stack.extend_from_slice(builder.block_params(frame.following_code()));
+                state
+                    .stack
+                    .extend_from_slice(builder.block_params(frame.following_code()));
                 state.reachable = true;
             }
         }
@@ -2423,6 +2735,325 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
     Ok(())
 }
 
+/// Returns the block a pending exception is branched to from the current
+/// position: the dispatch block of the innermost handler, or the block
+/// propagating the exception to the caller if there is none. The second value
+/// is the first frame of the control stack left on the way.
+fn exception_target(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+) -> (Block, usize) {
+    match state.handlers.last() {
+        Some(handler) => (handler.dispatch, handler.frame + 1),
+        None => (propagate_block(builder, state), 0),
+    }
+}
+
+/// Branches to the block a pending exception is dispatched from, releasing
+/// the exceptions of the legacy catch clauses left on the way.
+fn jump_to_exception_target<FE: FuncEnvironment + ?Sized>(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let (target, first_frame) = exception_target(builder, state);
+    translate_leave_catches(first_frame, builder, state, environ)?;
+    builder.ins().jump(target, &[]);
+    Ok(())
+}
+
+/// Whether leaving the frames of the control stack from `first_frame` on
+/// leaves a legacy catch clause.
+fn catches_left(first_frame: usize, state: &FuncTranslationState) -> bool {
+    state.control_stack[first_frame..].iter().any(|frame| {
+        matches!(
+            frame,
+            ControlStackFrame::Try {
+                caught_exception: Some(_),
+                ..
+            }
+        )
+    })
+}
+
+/// Releases the exceptions caught by the legacy catch clauses of the frames
+/// of the control stack from `first_frame` on, which control is leaving.
+///
+/// Nothing can rethrow them anymore. The runtime keeps the exception that is
+/// being propagated, in case it is one of them.
+fn translate_leave_catches<FE: FuncEnvironment + ?Sized>(
+    first_frame: usize,
+    builder: &mut FunctionBuilder,
+    state: &FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    for frame in &state.control_stack[first_frame..] {
+        if let ControlStackFrame::Try {
+            caught_exception: Some(exnref),
+            ..
+        } = *frame
+        {
+            environ.translate_drop_exception(builder, exnref)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the block propagating a pending exception to the caller, creating
+/// it if necessary. It is filled in by `parse_function_body`.
+pub(crate) fn propagate_block(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+) -> Block {
+    *state
+        .propagate_block
+        .get_or_insert_with(|| builder.create_block())
+}
+
+/// Checks for a pending exception after a call, branching to the current
+/// exception handler if there is one.
+fn translate_exception_check<FE: FuncEnvironment + ?Sized>(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    if !environ.exceptions_enabled() {
+        return Ok(());
+    }
+    let pending = environ.translate_exception_pending(builder)?;
+    let (target, first_frame) = exception_target(builder, state);
+    let continuation = builder.create_block();
+    if catches_left(first_frame, state) {
+        let landing = builder.create_block();
+        builder.ins().brif(pending, landing, &[], continuation, &[]);
+        builder.seal_block(landing);
+        builder.switch_to_block(landing);
+        translate_leave_catches(first_frame, builder, state, environ)?;
+        builder.ins().jump(target, &[]);
+    } else {
+        builder.ins().brif(pending, target, &[], continuation, &[]);
+    }
+    builder.seal_block(continuation);
+    builder.switch_to_block(continuation);
+    Ok(())
+}
+
+/// Translates a legacy `catch` clause, or a `catch_all` clause if `tag` is
+/// `None`, ending the `try` body or the previous catch clause.
+fn translate_catch<FE: FuncEnvironment + ?Sized>(
+    tag: Option<TagIndex>,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let i = state.control_stack.len() - 1;
+    let (destination, num_return_values, dispatch, caught_exception) = match state.control_stack[i]
+    {
+        ControlStackFrame::Try {
+            destination,
+            num_return_values,
+            dispatch,
+            caught_exception,
+            ..
+        } => (destination, num_return_values, dispatch, caught_exception),
+        _ => unreachable!(),
+    };
+
+    if state.reachable {
+        // The previous catch clause can't rethrow its exception anymore.
+        if let Some(exnref) = caught_exception {
+            environ.translate_drop_exception(builder, exnref)?;
+        }
+        canonicalise_then_jump(builder, destination, state.peekn(num_return_values));
+        state.control_stack[i].set_branched_to_exit();
+    }
+    state.control_stack[i].truncate_value_stack_to_catch_params(&mut state.stack);
+
+    // The body of the `try` has been translated, so it won't dispatch
+    // exceptions to it anymore.
+    if state
+        .handlers
+        .last()
+        .map_or(false, |handler| handler.frame == i)
+    {
+        let handler = state.handlers.pop().unwrap();
+        builder.seal_block(handler.dispatch);
+    }
+
+    let dispatch = match dispatch {
+        Some(dispatch) => dispatch,
+        None => {
+            // The `try` isn't reachable, neither are its catch clauses.
+            state.reachable = false;
+            return Ok(());
+        }
+    };
+
+    builder.switch_to_block(dispatch);
+    let (payload, next) = match tag {
+        Some(tag) => {
+            let matches = environ.translate_exception_tag_matches(builder, tag)?;
+            let landing = builder.create_block();
+            let next = builder.create_block();
+            builder.ins().brif(matches, landing, &[], next, &[]);
+            builder.seal_block(landing);
+            builder.seal_block(next);
+            builder.switch_to_block(landing);
+            (
+                environ.translate_exception_payload(builder, tag)?,
+                Some(next),
+            )
+        }
+        None => (Vec::new(), None),
+    };
+    let exnref = environ.translate_catch_exception(builder)?;
+
+    if let ControlStackFrame::Try {
+        ref mut dispatch,
+        ref mut caught_exception,
+        ..
+    } = state.control_stack[i]
+    {
+        *dispatch = next;
+        *caught_exception = Some(exnref);
+    }
+    state.pushn(&payload);
+    state.reachable = true;
+    Ok(())
+}
+
+/// Translates a `delegate`, which ends a legacy `try` by forwarding the
+/// exceptions thrown in its body to the handler of the given label.
+fn translate_delegate<FE: FuncEnvironment + ?Sized>(
+    relative_depth: u32,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let frame = state.control_stack.pop().unwrap();
+    let (destination, num_return_values, dispatch) = match frame {
+        ControlStackFrame::Try {
+            destination,
+            num_return_values,
+            dispatch,
+            ..
+        } => (destination, num_return_values, dispatch),
+        _ => unreachable!(),
+    };
+
+    let reachable = state.reachable;
+    if reachable {
+        canonicalise_then_jump(builder, destination, state.peekn(num_return_values));
+    }
+    frame.truncate_value_stack_to_original_size(&mut state.stack);
+
+    if let Some(dispatch) = dispatch {
+        let handler = state.handlers.pop().unwrap();
+        debug_assert_eq!(handler.dispatch, dispatch);
+        builder.seal_block(dispatch);
+        builder.switch_to_block(dispatch);
+
+        // Only the handlers enclosing the target label can catch the
+        // delegated exceptions.
+        let target_frame = state.control_stack.len() - 1 - relative_depth as usize;
+        let (target, first_frame) = match state
+            .handlers
+            .iter()
+            .rev()
+            .find(|handler| handler.frame <= target_frame)
+        {
+            Some(handler) => (handler.dispatch, handler.frame + 1),
+            None => (propagate_block(builder, state), 0),
+        };
+        translate_leave_catches(first_frame, builder, state, environ)?;
+        builder.ins().jump(target, &[]);
+    }
+
+    if reachable || frame.exit_is_branched_to() {
+        builder.switch_to_block(destination);
+        builder.seal_block(destination);
+        state
+            .stack
+            .extend_from_slice(builder.block_params(destination));
+        state.reachable = true;
+    } else {
+        state.reachable = false;
+    }
+    Ok(())
+}
+
+/// Translates the end of a `try` or a `try_table` whose frame was just
+/// popped from the control stack: `dispatch` is the block where the
+/// exceptions that weren't caught yet are dispatched from.
+///
+/// This leaves the builder in a filled block.
+fn translate_try_end<FE: FuncEnvironment + ?Sized>(
+    dispatch: Block,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let mut catches = Vec::new();
+    if state
+        .handlers
+        .last()
+        .map_or(false, |handler| handler.frame == state.control_stack.len())
+    {
+        let handler = state.handlers.pop().unwrap();
+        debug_assert_eq!(handler.dispatch, dispatch);
+        builder.seal_block(dispatch);
+        catches = handler.catches;
+    }
+    builder.switch_to_block(dispatch);
+
+    for clause in catches {
+        let destination = {
+            let frame = &mut state.control_stack[clause.target];
+            frame.set_branched_to_exit();
+            frame.br_destination()
+        };
+        match clause.tag {
+            Some(tag) => {
+                let matches = environ.translate_exception_tag_matches(builder, tag)?;
+                let landing = builder.create_block();
+                let next = builder.create_block();
+                builder.ins().brif(matches, landing, &[], next, &[]);
+                builder.seal_block(landing);
+                builder.seal_block(next);
+
+                builder.switch_to_block(landing);
+                let mut args = environ.translate_exception_payload(builder, tag)?;
+                let exnref = environ.translate_catch_exception(builder)?;
+                if clause.with_ref {
+                    environ.translate_expose_exception(builder, exnref)?;
+                    args.push(exnref);
+                } else {
+                    environ.translate_drop_exception(builder, exnref)?;
+                }
+                canonicalise_then_jump(builder, destination, &args);
+
+                builder.switch_to_block(next);
+            }
+            None => {
+                // Every exception is caught, so the following clauses are
+                // never reached.
+                let exnref = environ.translate_catch_exception(builder)?;
+                let args: &[ir::Value] = if clause.with_ref {
+                    environ.translate_expose_exception(builder, exnref)?;
+                    &[exnref]
+                } else {
+                    environ.translate_drop_exception(builder, exnref)?;
+                    &[]
+                };
+                canonicalise_then_jump(builder, destination, args);
+                return Ok(());
+            }
+        }
+    }
+
+    jump_to_exception_target(builder, state, environ)
+}
+
 /// This function is a generalized helper for validating that a wasm-supplied
 /// heap address is in-bounds.
 ///
@@ -2978,18 +3609,33 @@ fn translate_vector_fcmp(
     state.push1(builder.ins().fcmp(cc, bitcast_a, bitcast_b))
 }
 
-fn translate_br_if(
+fn translate_br_if<FE: FuncEnvironment + ?Sized>(
     relative_depth: u32,
     builder: &mut FunctionBuilder,
     state: &mut FuncTranslationState,
-) {
+    environ: &mut FE,
+) -> WasmResult<()> {
     let val = state.pop1();
-    let (br_destination, inputs) = translate_br_if_args(relative_depth, state);
     let next_block = builder.create_block();
-    canonicalise_brif(builder, val, br_destination, inputs, next_block, &[]);
+    let i = state.control_stack.len() - 1 - (relative_depth as usize);
+    if catches_left(i, state) {
+        // The exceptions of the catch clauses left are only released when the
+        // branch is taken.
+        let taken_block = builder.create_block();
+        builder.ins().brif(val, taken_block, &[], next_block, &[]);
+        builder.seal_block(taken_block);
+        builder.switch_to_block(taken_block);
+        translate_leave_catches(i, builder, state, environ)?;
+        let (br_destination, inputs) = translate_br_if_args(relative_depth, state);
+        canonicalise_then_jump(builder, br_destination, inputs);
+    } else {
+        let (br_destination, inputs) = translate_br_if_args(relative_depth, state);
+        canonicalise_brif(builder, val, br_destination, inputs, next_block, &[]);
+    }
 
     builder.seal_block(next_block); // The only predecessor is the current block.
     builder.switch_to_block(next_block);
+    Ok(())
 }
 
 fn translate_br_if_args(
@@ -3013,6 +3659,29 @@ fn translate_br_if_args(
     (br_destination, inputs)
 }
 
+/// Computes the `i16x8` pairwise dot product of the `i8x16` vectors `a` and `b`,
+/// used by the relaxed dot product operators.
+fn relaxed_dot_i8x16_i7x16<FE: FuncEnvironment + ?Sized>(
+    environ: &FE,
+    builder: &mut FunctionBuilder,
+    a: Value,
+    b: Value,
+) -> Value {
+    if environ.relaxed_simd_deterministic() || !environ.use_x86_pmaddubsw_for_dot() {
+        // Deterministic semantics are a signed multiplication of all the lanes, with
+        // wrapping pairwise additions.
+        let alo = builder.ins().swiden_low(a);
+        let blo = builder.ins().swiden_low(b);
+        let lo = builder.ins().imul(alo, blo);
+        let ahi = builder.ins().swiden_high(a);
+        let bhi = builder.ins().swiden_high(b);
+        let hi = builder.ins().imul(ahi, bhi);
+        builder.ins().iadd_pairwise(lo, hi)
+    } else {
+        builder.ins().x86_pmaddubsw(a, b)
+    }
+}
+
 /// Determine the returned value type of a WebAssembly operator
 fn type_of(operator: &Operator) -> Type {
     match operator {
@@ -3063,7 +3732,8 @@ fn type_of(operator: &Operator) -> Type {
         | Operator::I8x16MaxU
         | Operator::I8x16AvgrU
         | Operator::I8x16Bitmask
-        | Operator::I8x16Popcnt => I8X16,
+        | Operator::I8x16Popcnt
+        | Operator::I8x16RelaxedLaneselect => I8X16,
 
         Operator::I16x8Splat
         | Operator::V128Load16Splat { .. }
@@ -3100,7 +3770,8 @@ fn type_of(operator: &Operator) -> Type {
         | Operator::I16x8MaxU
         | Operator::I16x8AvgrU
         | Operator::I16x8Mul
-        | Operator::I16x8Bitmask => I16X8,
+        | Operator::I16x8Bitmask
+        | Operator::I16x8RelaxedLaneselect => I16X8,
 
         Operator::I32x4Splat
         | Operator::V128Load32Splat { .. }
@@ -3134,7 +3805,8 @@ fn type_of(operator: &Operator) -> Type {
         | Operator::I32x4Bitmask
         | Operator::I32x4TruncSatF32x4S
         | Operator::I32x4TruncSatF32x4U
-        | Operator::V128Load32Zero { .. } => I32X4,
+        | Operator::V128Load32Zero { .. }
+        | Operator::I32x4RelaxedLaneselect => I32X4,
 
         Operator::I64x2Splat
         | Operator::V128Load64Splat { .. }
@@ -3158,7 +3830,8 @@ fn type_of(operator: &Operator) -> Type {
         | Operator::I64x2Sub
         | Operator::I64x2Mul
         | Operator::I64x2Bitmask
-        | Operator::V128Load64Zero { .. } => I64X2,
+        | Operator::V128Load64Zero { .. }
+        | Operator::I64x2RelaxedLaneselect => I64X2,
 
         Operator::F32x4Splat
         | Operator::F32x4ExtractLane { .. }
@@ -3185,7 +3858,11 @@ fn type_of(operator: &Operator) -> Type {
         | Operator::F32x4Ceil
         | Operator::F32x4Floor
         | Operator::F32x4Trunc
-        | Operator::F32x4Nearest => F32X4,
+        | Operator::F32x4Nearest
+        | Operator::F32x4RelaxedMin
+        | Operator::F32x4RelaxedMax
+        | Operator::F32x4RelaxedMadd
+        | Operator::F32x4RelaxedNmadd => F32X4,
 
         Operator::F64x2Splat
         | Operator::F64x2ExtractLane { .. }
@@ -3210,7 +3887,11 @@ fn type_of(operator: &Operator) -> Type {
         | Operator::F64x2Ceil
         | Operator::F64x2Floor
         | Operator::F64x2Trunc
-        | Operator::F64x2Nearest => F64X2,
+        | Operator::F64x2Nearest
+        | Operator::F64x2RelaxedMin
+        | Operator::F64x2RelaxedMax
+        | Operator::F64x2RelaxedMadd
+        | Operator::F64x2RelaxedNmadd => F64X2,
 
         _ => unimplemented!(
             "Currently only SIMD instructions are mapped to their return type; the \
@@ -3338,6 +4019,21 @@ fn pop2_with_bitcast(
     (bitcast_a, bitcast_b)
 }
 
+/// A helper for popping and bitcasting three values; since SIMD values can lose their type by
+/// using v128 (i.e. CLIF's I8x16) we must re-type the values using a bitcast to avoid CLIF
+/// typing issues.
+fn pop3_with_bitcast(
+    state: &mut FuncTranslationState,
+    needed_type: Type,
+    builder: &mut FunctionBuilder,
+) -> (Value, Value, Value) {
+    let (a, b, c) = state.pop3();
+    let bitcast_a = optionally_bitcast_vector(a, needed_type, builder);
+    let bitcast_b = optionally_bitcast_vector(b, needed_type, builder);
+    let bitcast_c = optionally_bitcast_vector(c, needed_type, builder);
+    (bitcast_a, bitcast_b, bitcast_c)
+}
+
 pub fn bitcast_arguments<'a>(
     builder: &FunctionBuilder,
     arguments: &'a mut [Value],
@@ -17,7 +17,7 @@ use wasmer_compiler::wasmparser::{HeapType, Operator};
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::{
     FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, SignatureIndex,
-    TableIndex, Type as WasmerType, WasmResult,
+    TableIndex, TagIndex, Type as WasmerType, WasmResult,
 };
 
 /// The value of a WebAssembly global variable.
@@ -214,6 +214,48 @@ pub trait FuncEnvironment: TargetEnvironment {
         call_args: &[ir::Value],
     ) -> WasmResult<ir::Inst>;
 
+    /// Whether the tail call proposal is enabled.
+    ///
+    /// When it is, the functions of the module use the `tail` calling
+    /// convention, so they can be the target of a `return_call`.
+    fn tail_calls_enabled(&self) -> bool {
+        false
+    }
+
+    /// Translate a `return_call` WebAssembly instruction at `pos`.
+    ///
+    /// Insert instructions at `pos` for a direct tail call to the function
+    /// `callee_index`, terminating the current block.
+    ///
+    /// The function reference `callee` was previously created by `make_direct_func()`.
+    fn translate_return_call(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        _callee_index: FunctionIndex,
+        callee: ir::FuncRef,
+        call_args: &[ir::Value],
+    ) -> WasmResult<()> {
+        builder.ins().return_call(callee, call_args);
+        Ok(())
+    }
+
+    /// Translate a `return_call_indirect` WebAssembly instruction at `pos`.
+    ///
+    /// Insert instructions at `pos` for an indirect tail call to the function
+    /// `callee` in the table `table_index` with WebAssembly signature
+    /// `sig_index`, terminating the current block.
+    ///
+    /// The signature `sig_ref` was previously created by `make_indirect_sig()`.
+    fn translate_return_call_indirect(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        sig_ref: ir::SigRef,
+        callee: ir::Value,
+        call_args: &[ir::Value],
+    ) -> WasmResult<()>;
+
     /// Translate a `memory.grow` WebAssembly instruction.
     ///
     /// The `index` provided identifies the linear memory to grow, and `heap` is the heap reference
@@ -449,15 +491,136 @@ pub trait FuncEnvironment: TargetEnvironment {
         count: ir::Value,
     ) -> WasmResult<ir::Value>;
 
+    /// Whether the exception handling proposal is enabled.
+    ///
+    /// When it is, every call is followed by a check for a pending exception,
+    /// so exceptions can be propagated to the caller.
+    fn exceptions_enabled(&self) -> bool {
+        false
+    }
+
+    /// Whether the relaxed SIMD operators must produce the same results on
+    /// every platform.
+    ///
+    /// When they must, each relaxed operator is translated like its
+    /// non-relaxed counterpart.
+    fn relaxed_simd_deterministic(&self) -> bool {
+        true
+    }
+
+    /// Whether the target has a native fused multiply-add instruction.
+    fn has_native_fma(&self) -> bool {
+        false
+    }
+
+    /// Whether the target is x86, where some relaxed operators are lowered
+    /// with the semantics of the native instructions.
+    fn is_x86(&self) -> bool {
+        false
+    }
+
+    /// Whether `x86_blendv` can be used for `relaxed_laneselect` on the
+    /// given type.
+    fn use_x86_blendv_for_relaxed_laneselect(&self, _ty: ir::Type) -> bool {
+        false
+    }
+
+    /// Whether `x86_pshufb` can be used for `i8x16.relaxed_swizzle`.
+    fn use_x86_pshufb_for_relaxed_swizzle(&self) -> bool {
+        false
+    }
+
+    /// Whether `x86_pmulhrsw` can be used for `i16x8.relaxed_q15mulr_s`.
+    fn use_x86_pmulhrsw_for_relaxed_q15mul(&self) -> bool {
+        false
+    }
+
+    /// Whether `x86_pmaddubsw` can be used for the relaxed dot products.
+    fn use_x86_pmaddubsw_for_dot(&self) -> bool {
+        false
+    }
+
+    /// Get the types of the values carried by the tag at the given index.
+    fn get_tag_params(&self, tag_index: TagIndex) -> &[WasmerType];
+
+    /// Translate a `throw` WebAssembly instruction, making `args` the payload
+    /// of a new pending exception with the given tag.
+    fn translate_throw(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        tag_index: TagIndex,
+        args: &[ir::Value],
+    ) -> WasmResult<()>;
+
+    /// Translate a `throw_ref` WebAssembly instruction, making the exception
+    /// referenced by `exnref` pending again. Traps if `exnref` is null.
+    fn translate_throw_ref(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exnref: ir::Value,
+    ) -> WasmResult<()>;
+
+    /// Release an exception that was caught, once its `exnref` is known to
+    /// not be used anymore, so that the runtime can reuse its storage.
+    fn translate_drop_exception(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exnref: ir::Value,
+    ) -> WasmResult<()>;
+
+    /// Record that the `exnref` of a caught exception is handed out to the
+    /// code, so that the runtime keeps the exception while it may be held.
+    fn translate_expose_exception(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exnref: ir::Value,
+    ) -> WasmResult<()>;
+
+    /// Load the raw reference of the pending exception.
+    ///
+    /// The returned pointer-sized integer is zero if no exception is pending.
+    fn translate_exception_pending(
+        &mut self,
+        builder: &mut FunctionBuilder,
+    ) -> WasmResult<ir::Value>;
+
+    /// Check whether the pending exception was thrown with the given tag.
+    ///
+    /// Returns an `i8` that is non-zero if it was.
+    fn translate_exception_tag_matches(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        tag_index: TagIndex,
+    ) -> WasmResult<ir::Value>;
+
+    /// Load the values carried by the pending exception, which is known to
+    /// have been thrown with the given tag.
+    fn translate_exception_payload(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        tag_index: TagIndex,
+    ) -> WasmResult<Vec<ir::Value>>;
+
+    /// Stop propagating the pending exception, returning its `exnref`.
+    fn translate_catch_exception(&mut self, builder: &mut FunctionBuilder)
+        -> WasmResult<ir::Value>;
+
     /// Emit code at the beginning of every wasm loop.
     ///
     /// This can be used to insert explicit interrupt or safepoint checking at
     /// the beginnings of loops.
-    fn translate_loop_header(&mut self, _pos: FuncCursor) -> WasmResult<()> {
+    fn translate_loop_header(&mut self, _builder: &mut FunctionBuilder) -> WasmResult<()> {
         // By default, don't emit anything.
         Ok(())
     }
 
+    /// Optional callback for the `FuncEnvironment` performing this translation
+    /// to emit code at the beginning of the function body, after the locals
+    /// are declared.
+    fn before_translate_function(&mut self, _builder: &mut FunctionBuilder) -> WasmResult<()> {
+        Ok(())
+    }
+
     /// Optional callback for the `FunctionEnvMutironment` performing this translation to maintain
     /// internal state or prepare custom state for the operator to translate
     fn before_translate_operator(
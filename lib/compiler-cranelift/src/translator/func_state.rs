@@ -14,7 +14,7 @@ use crate::heap::Heap;
 use crate::{HashMap, Occupied, Vacant};
 use cranelift_codegen::ir::{self, Block, Inst, Value};
 use std::vec::Vec;
-use wasmer_types::{FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, WasmResult};
+use wasmer_types::{FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, TagIndex, WasmResult};
 
 /// Information about the presence of an associated `else` for an `if`, or the
 /// lack thereof.
@@ -46,8 +46,8 @@ pub enum ElseData {
     },
 }
 
-/// A control stack frame can be an `if`, a `block` or a `loop`, each one having the following
-/// fields:
+/// A control stack frame can be an `if`, a `block`, a `loop` or a `try`, each one having the
+/// following fields:
 ///
 /// - `destination`: reference to the `Block` that will hold the code after the control block;
 /// - `num_return_values`: number of values returned by the control block;
@@ -92,6 +92,49 @@ pub enum ControlStackFrame {
         num_return_values: usize,
         original_stack_size: usize,
     },
+    Try {
+        destination: Block,
+        num_param_values: usize,
+        num_return_values: usize,
+        original_stack_size: usize,
+        exit_is_branched_to: bool,
+        /// The block that exceptions not caught by the catch clauses
+        /// translated so far are dispatched to. This is `None` once a
+        /// `catch_all` clause was translated, or if the `try` itself is
+        /// unreachable.
+        dispatch: Option<Block>,
+        /// The `exnref` caught by the legacy `catch` clause being translated,
+        /// used by `rethrow`.
+        caught_exception: Option<Value>,
+    },
+}
+
+/// A catch clause of a `try_table`, with its target resolved to an index in
+/// the control stack.
+#[derive(Debug)]
+pub struct CatchClause {
+    /// The tag to catch, or `None` to catch all exceptions.
+    pub tag: Option<TagIndex>,
+    /// Whether the `exnref` is passed to the target along with the payload.
+    pub with_ref: bool,
+    /// The index of the target frame in the control stack.
+    pub target: usize,
+}
+
+/// An exception handler that is active at this point of the translation.
+///
+/// Calls and `throw`s branch to the `dispatch` block of the innermost
+/// handler when an exception is pending.
+#[derive(Debug)]
+pub struct ExceptionHandler {
+    /// The block the pending exception is dispatched from.
+    pub dispatch: Block,
+    /// The index in the control stack of the `try` or `try_table` frame this
+    /// handler belongs to.
+    pub frame: usize,
+    /// The catch clauses of a `try_table`. Legacy `try` blocks translate
+    /// their catch clauses as they come, so this is empty for them.
+    pub catches: Vec<CatchClause>,
 }
 
 /// Helper methods for the control stack objects.
@@ -106,6 +149,9 @@ impl ControlStackFrame {
             }
             | Self::Loop {
                 num_return_values, ..
+            }
+            | Self::Try {
+                num_return_values, ..
             } => num_return_values,
         }
     }
@@ -119,6 +165,9 @@ impl ControlStackFrame {
             }
             | Self::Loop {
                 num_param_values, ..
+            }
+            | Self::Try {
+                num_param_values, ..
             } => num_param_values,
         }
     }
@@ -126,12 +175,15 @@ impl ControlStackFrame {
         match *self {
             Self::If { destination, .. }
             | Self::Block { destination, .. }
-            | Self::Loop { destination, .. } => destination,
+            | Self::Loop { destination, .. }
+            | Self::Try { destination, .. } => destination,
         }
     }
     pub fn br_destination(&self) -> Block {
         match *self {
-            Self::If { destination, .. } | Self::Block { destination, .. } => destination,
+            Self::If { destination, .. }
+            | Self::Block { destination, .. }
+            | Self::Try { destination, .. } => destination,
             Self::Loop { header, .. } => header,
         }
     }
@@ -150,12 +202,16 @@ impl ControlStackFrame {
             | Self::Loop {
                 original_stack_size,
                 ..
+            }
+            | Self::Try {
+                original_stack_size,
+                ..
             } => original_stack_size,
         }
     }
     pub fn is_loop(&self) -> bool {
         match *self {
-            Self::If { .. } | Self::Block { .. } => false,
+            Self::If { .. } | Self::Block { .. } | Self::Try { .. } => false,
             Self::Loop { .. } => true,
         }
     }
@@ -169,6 +225,10 @@ impl ControlStackFrame {
             | Self::Block {
                 exit_is_branched_to,
                 ..
+            }
+            | Self::Try {
+                exit_is_branched_to,
+                ..
             } => exit_is_branched_to,
             Self::Loop { .. } => false,
         }
@@ -183,6 +243,10 @@ impl ControlStackFrame {
             | Self::Block {
                 ref mut exit_is_branched_to,
                 ..
+            }
+            | Self::Try {
+                ref mut exit_is_branched_to,
+                ..
             } => *exit_is_branched_to = true,
             Self::Loop { .. } => {}
         }
@@ -195,6 +259,13 @@ impl ControlStackFrame {
         stack.truncate(self.original_stack_size());
     }
 
+    /// Pop values from the value stack so that it is left at the input of a
+    /// `catch` clause, before the payload of the exception is pushed.
+    pub fn truncate_value_stack_to_catch_params(&self, stack: &mut Vec<Value>) {
+        debug_assert!(matches!(self, &Self::Try { .. }));
+        stack.truncate(self.original_stack_size());
+    }
+
     /// Pop values from the value stack so that it is left at the state it was
     /// before this control-flow frame.
     pub fn truncate_value_stack_to_original_size(&self, stack: &mut Vec<Value>) {
@@ -230,6 +301,11 @@ pub struct FuncTranslationState {
     /// Is the current translation state still reachable? This is false when translating operators
     /// like End, Return, or Unreachable.
     pub(crate) reachable: bool,
+    /// A stack of the exception handlers active at this point in the input wasm function.
+    pub(crate) handlers: Vec<ExceptionHandler>,
+    /// The block returning to the caller of the function with an exception
+    /// still pending, created the first time an exception can escape the function.
+    pub(crate) propagate_block: Option<Block>,
 
     // Map of global variables that have already been created by `FuncEnvironment::make_global`.
     globals: HashMap<GlobalIndex, GlobalVariable>,
@@ -267,6 +343,8 @@ impl FuncTranslationState {
             //metadata_stack: Vec::new(),
             control_stack: Vec::new(),
             reachable: true,
+            handlers: Vec::new(),
+            propagate_block: None,
             globals: HashMap::new(),
             heaps: HashMap::new(),
             signatures: HashMap::new(),
@@ -277,7 +355,9 @@ impl FuncTranslationState {
     fn clear(&mut self) {
         debug_assert!(self.stack.is_empty());
         debug_assert!(self.control_stack.is_empty());
+        debug_assert!(self.handlers.is_empty());
         self.reachable = true;
+        self.propagate_block = None;
         self.globals.clear();
         self.heaps.clear();
         self.signatures.clear();
@@ -409,6 +489,26 @@ impl FuncTranslationState {
         });
     }
 
+    /// Push a `try` or a `try_table` on the control stack.
+    pub(crate) fn push_try(
+        &mut self,
+        following_code: Block,
+        dispatch: Option<Block>,
+        num_param_types: usize,
+        num_result_types: usize,
+    ) {
+        debug_assert!(num_param_types <= self.stack.len());
+        self.control_stack.push(ControlStackFrame::Try {
+            destination: following_code,
+            original_stack_size: self.stack.len() - num_param_types,
+            num_param_values: num_param_types,
+            num_return_values: num_result_types,
+            exit_is_branched_to: false,
+            dispatch,
+            caught_exception: None,
+        });
+    }
+
     /// Push an if on the control stack.
     pub(crate) fn push_if(
         &mut self,
@@ -10,7 +10,7 @@
 use super::code_translator::translate_operator;
 use super::func_environ::{FuncEnvironment, ReturnMode};
 use super::func_state::FuncTranslationState;
-use super::translation_utils::get_vmctx_value_label;
+use super::translation_utils::{get_vmctx_value_label, is_exn_ref};
 use crate::translator::code_translator::bitcast_wasm_returns;
 use cranelift_codegen::entity::EntityRef;
 use cranelift_codegen::ir::{self, Block, InstBuilder, ValueLabel};
@@ -196,7 +196,7 @@ fn declare_locals<FE: FuncEnvironment + ?Sized>(
             builder.ins().vconst(ir::types::I8X16, constant_handle)
         }
         Ref(ty) => {
-            if ty.is_func_ref() || ty.is_extern_ref() {
+            if ty.is_func_ref() || ty.is_extern_ref() || is_exn_ref(&ty) {
                 builder.ins().null(environ.reference_type())
             } else {
                 return Err(wasm_unsupported!("unsupported reference type: {:?}", ty));
@@ -231,6 +231,8 @@ fn parse_function_body<FE: FuncEnvironment + ?Sized>(
     // The control stack is initialized with a single block representing the whole function.
     debug_assert_eq!(state.control_stack.len(), 1, "State not initialized");
 
+    environ.before_translate_function(builder)?;
+
     // Keep going until the final `End` operator which pops the outermost block.
     while !state.control_stack.is_empty() {
         builder.set_srcloc(cur_srcloc(reader));
@@ -257,6 +259,26 @@ fn parse_function_body<FE: FuncEnvironment + ?Sized>(
         }
     }
 
+    // Exceptions that aren't caught in this function are propagated to the caller, which checks
+    // for them after the call. The values returned along with them are never used.
+    if let Some(propagate_block) = state.propagate_block {
+        builder.switch_to_block(propagate_block);
+        builder.seal_block(propagate_block);
+        let return_types: Vec<ir::Type> = builder
+            .func
+            .signature
+            .returns
+            .iter()
+            .filter(|arg| arg.purpose == ir::ArgumentPurpose::Normal)
+            .map(|arg| arg.value_type)
+            .collect();
+        let return_values: Vec<ir::Value> = return_types
+            .into_iter()
+            .map(|ty| default_value(builder, ty))
+            .collect();
+        builder.ins().return_(&return_values);
+    }
+
     // Discard any remaining values on the stack. Either we just returned them,
     // or the end of the function is unreachable.
     state.stack.clear();
@@ -267,6 +289,22 @@ fn parse_function_body<FE: FuncEnvironment + ?Sized>(
     Ok(())
 }
 
+/// Build a zero value of the given type.
+fn default_value(builder: &mut FunctionBuilder, ty: ir::Type) -> ir::Value {
+    if ty.is_ref() {
+        builder.ins().null(ty)
+    } else if ty.is_vector() {
+        let constant_handle = builder.func.dfg.constants.insert([0; 16].to_vec().into());
+        builder.ins().vconst(ty, constant_handle)
+    } else if ty == ir::types::F32 {
+        builder.ins().f32const(ir::immediates::Ieee32::with_bits(0))
+    } else if ty == ir::types::F64 {
+        builder.ins().f64const(ir::immediates::Ieee64::with_bits(0))
+    } else {
+        builder.ins().iconst(ty, 0)
+    }
+}
+
 /// Get the current source location from a reader.
 fn cur_srcloc(reader: &dyn FunctionBinaryReader) -> ir::SourceLoc {
     // We record source locations as byte code offsets relative to the beginning of the file.
@@ -54,7 +54,7 @@ pub fn type_to_irtype(ty: Type, target_config: TargetFrontendConfig) -> WasmResu
         Type::F32 => Ok(ir::types::F32),
         Type::F64 => Ok(ir::types::F64),
         Type::V128 => Ok(ir::types::I8X16),
-        Type::ExternRef | Type::FuncRef => reference_type(target_config),
+        Type::ExternRef | Type::FuncRef | Type::ExceptionRef => reference_type(target_config),
         // ty => Err(wasm_unsupported!("type_to_type: wasm type {:?}", ty)),
     }
 }
@@ -71,6 +71,8 @@ pub fn irlibcall_to_libcall(libcall: ir::LibCall) -> LibCall {
         ir::LibCall::TruncF64 => LibCall::TruncF64,
         ir::LibCall::NearestF32 => LibCall::NearestF32,
         ir::LibCall::NearestF64 => LibCall::NearestF64,
+        ir::LibCall::FmaF32 => LibCall::FmaF32,
+        ir::LibCall::FmaF64 => LibCall::FmaF64,
         _ => panic!("Unsupported libcall"),
     }
 }
@@ -112,7 +114,7 @@ pub fn block_with_params<'a, PE: TargetEnvironment + ?Sized>(
                 builder.append_block_param(block, ir::types::F64);
             }
             wasmparser::ValType::Ref(ty) => {
-                if ty.is_extern_ref() || ty.is_func_ref() {
+                if ty.is_extern_ref() || ty.is_func_ref() || is_exn_ref(ty) {
                     builder.append_block_param(block, environ.reference_type());
                 } else {
                     return Err(WasmError::Unsupported(format!(
@@ -129,6 +131,17 @@ pub fn block_with_params<'a, PE: TargetEnvironment + ?Sized>(
     Ok(block)
 }
 
+/// Whether the given `wasmparser` reference type is an `exnref`.
+pub fn is_exn_ref(ty: &wasmparser::RefType) -> bool {
+    matches!(
+        ty.heap_type(),
+        wasmparser::HeapType::Abstract {
+            ty: wasmparser::AbstractHeapType::Exn,
+            ..
+        }
+    )
+}
+
 /// Turns a `wasmparser` `f32` into a `Cranelift` one.
 pub fn f32_translation(x: wasmparser::Ieee32) -> ir::immediates::Ieee32 {
     ir::immediates::Ieee32::with_bits(x.bits())
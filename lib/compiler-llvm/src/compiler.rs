@@ -375,6 +375,7 @@ impl Compiler for LLVMCompiler {
             custom_sections: module_custom_sections,
             function_call_trampolines,
             dynamic_function_trampolines,
+            function_entry_trampolines: PrimaryMap::new(),
             debug: dwarf,
         })
     }
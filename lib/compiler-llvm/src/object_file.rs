@@ -123,6 +123,10 @@ where
         "wasmer_vm_imported_memory32_atomic_notify".to_string(),
         LibCall::ImportedMemory32AtomicNotify,
     );
+    libcalls.insert(
+        "wasmer_vm_epoch_deadline_reached".to_string(),
+        LibCall::EpochDeadlineReached,
+    );
 
     let elf = object::File::parse(contents).map_err(map_object_err)?;
 
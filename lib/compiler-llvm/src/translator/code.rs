@@ -222,6 +222,10 @@ impl FuncTranslator {
             &func_attrs,
         );
 
+        if config.enable_epoch_interruption {
+            fcg.emit_epoch_check()?;
+        }
+
         while fcg.state.has_control_frames() {
             let pos = reader.current_position() as u32;
             let op = reader.read_operator()?;
@@ -779,6 +783,68 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         Ok(())
     }
 
+    /// Emits a check of the epoch deadline of the store, calling the
+    /// `epoch_deadline_reached` builtin once the current epoch reaches it.
+    fn emit_epoch_check(&mut self) -> Result<(), CompileError> {
+        let (deadline_ptr, counter_ptr) = self.ctx.epoch_state(self.intrinsics)?;
+
+        // The deadline and the epoch can change during the execution, so
+        // they must not be hoisted out of loops.
+        let deadline =
+            err!(self
+                .builder
+                .build_load(self.intrinsics.i64_ty, deadline_ptr, "epoch_deadline"));
+        deadline
+            .as_instruction_value()
+            .unwrap()
+            .set_volatile(true)
+            .unwrap();
+        let epoch = err!(self
+            .builder
+            .build_load(self.intrinsics.i64_ty, counter_ptr, "epoch"));
+        epoch
+            .as_instruction_value()
+            .unwrap()
+            .set_volatile(true)
+            .unwrap();
+
+        let reached = err!(self.builder.build_int_compare(
+            IntPredicate::UGE,
+            epoch.into_int_value(),
+            deadline.into_int_value(),
+            "epoch_deadline_reached",
+        ));
+        let reached = err!(self.builder.build_call(
+            self.intrinsics.expect_i1,
+            &[reached.into(), self.intrinsics.i1_ty.const_zero().into()],
+            "epoch_deadline_reached_expect",
+        ))
+        .try_as_basic_value()
+        .left()
+        .unwrap()
+        .into_int_value();
+
+        let reached_block = self
+            .context
+            .append_basic_block(self.function, "epoch_deadline_reached_block");
+        let continue_block = self
+            .context
+            .append_basic_block(self.function, "epoch_deadline_continue_block");
+        err!(self
+            .builder
+            .build_conditional_branch(reached, reached_block, continue_block));
+        self.builder.position_at_end(reached_block);
+        err!(self.builder.build_call(
+            self.intrinsics.epoch_deadline_reached,
+            &[self.ctx.basic().into()],
+            "",
+        ));
+        err!(self.builder.build_unconditional_branch(continue_block));
+        self.builder.position_at_end(continue_block);
+
+        Ok(())
+    }
+
     fn trap_if_zero(&self, value: IntValue) -> Result<(), CompileError> {
         let int_type = value.get_type();
         let should_trap = err!(self.builder.build_int_compare(
@@ -1566,6 +1632,10 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     self.state.push1(phi.as_basic_value());
                 }
 
+                if self.config.enable_epoch_interruption {
+                    self.emit_epoch_check()?;
+                }
+
                 /*
                 if self.track_state {
                     if let Some(offset) = opcode_offset {
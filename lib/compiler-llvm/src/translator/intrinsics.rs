@@ -220,6 +220,7 @@ pub struct Intrinsics<'ctx> {
     pub data_drop: FunctionValue<'ctx>,
     pub func_ref: FunctionValue<'ctx>,
     pub elem_drop: FunctionValue<'ctx>,
+    pub epoch_deadline_reached: FunctionValue<'ctx>,
     pub memory_copy: FunctionValue<'ctx>,
     pub imported_memory_copy: FunctionValue<'ctx>,
     pub memory_fill: FunctionValue<'ctx>,
@@ -971,6 +972,11 @@ impl<'ctx> Intrinsics<'ctx> {
                 void_ty.fn_type(&[ctx_ptr_ty_basic_md, i32_ty_basic_md], false),
                 None,
             ),
+            epoch_deadline_reached: module.add_function(
+                "wasmer_vm_epoch_deadline_reached",
+                void_ty.fn_type(&[ctx_ptr_ty_basic_md], false),
+                None,
+            ),
             throw_trap: module.add_function(
                 "wasmer_vm_raise_trap",
                 void_ty.fn_type(&[i32_ty_basic_md], false),
@@ -1158,6 +1164,7 @@ pub struct CtxType<'ctx, 'a> {
     cached_functions: HashMap<FunctionIndex, FunctionCache<'ctx>>,
     cached_memory_grow: HashMap<MemoryIndex, PointerValue<'ctx>>,
     cached_memory_size: HashMap<MemoryIndex, PointerValue<'ctx>>,
+    cached_epoch_state: Option<(PointerValue<'ctx>, PointerValue<'ctx>)>,
 
     offsets: VMOffsets,
 }
@@ -1183,6 +1190,7 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
             cached_functions: HashMap::new(),
             cached_memory_grow: HashMap::new(),
             cached_memory_size: HashMap::new(),
+            cached_epoch_state: None,
 
             // TODO: pointer width
             offsets: VMOffsets::new(8, wasm_module),
@@ -1193,6 +1201,46 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
         self.ctx_ptr_value.as_basic_value_enum()
     }
 
+    /// Returns pointers to the epoch deadline of the store and to the
+    /// epoch counter of the engine.
+    pub fn epoch_state(
+        &mut self,
+        intrinsics: &Intrinsics<'ctx>,
+    ) -> Result<(PointerValue<'ctx>, PointerValue<'ctx>), CompileError> {
+        if let Some(cached) = self.cached_epoch_state {
+            return Ok(cached);
+        }
+        let (offsets, cache_builder, ctx_ptr_value) =
+            (&self.offsets, &self.cache_builder, &self.ctx_ptr_value);
+
+        let offset = intrinsics
+            .i32_ty
+            .const_int(offsets.vmctx_epoch_state().into(), false);
+        let state_ptr_ptr = unsafe {
+            err!(cache_builder.build_gep(intrinsics.i8_ty, *ctx_ptr_value, &[offset], ""))
+        };
+        let state_ptr = err!(cache_builder.build_load(intrinsics.ptr_ty, state_ptr_ptr, ""))
+            .into_pointer_value();
+
+        let offset = intrinsics
+            .i32_ty
+            .const_int(offsets.vmepoch_state_deadline().into(), false);
+        let deadline_ptr = unsafe {
+            err!(cache_builder.build_gep(intrinsics.i8_ty, state_ptr, &[offset], "deadline_ptr"))
+        };
+        let offset = intrinsics
+            .i32_ty
+            .const_int(offsets.vmepoch_state_counter().into(), false);
+        let counter_ptr_ptr =
+            unsafe { err!(cache_builder.build_gep(intrinsics.i8_ty, state_ptr, &[offset], "")) };
+        let counter_ptr =
+            err!(cache_builder.build_load(intrinsics.ptr_ty, counter_ptr_ptr, "counter_ptr"))
+                .into_pointer_value();
+
+        self.cached_epoch_state = Some((deadline_ptr, counter_ptr));
+        Ok((deadline_ptr, counter_ptr))
+    }
+
     pub fn memory(
         &mut self,
         index: MemoryIndex,
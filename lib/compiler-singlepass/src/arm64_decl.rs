@@ -287,7 +287,7 @@ impl ArgumentRegisterAllocator {
                             None
                         }
                     }
-                    Type::F32 | Type::F64 => {
+                    Type::F32 | Type::F64 | Type::V128 => {
                         if self.n_neons < NEON_SEQ.len() {
                             let neon = NEON_SEQ[self.n_neons];
                             self.n_neons += 1;
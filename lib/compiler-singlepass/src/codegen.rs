@@ -13,7 +13,7 @@ use crate::{
 #[cfg(feature = "unwind")]
 use gimli::write::Address;
 use smallvec::{smallvec, SmallVec};
-use std::{cmp, iter};
+use std::{cmp, collections::BTreeSet, iter};
 
 use wasmer_compiler::{
     types::{
@@ -23,8 +23,8 @@ use wasmer_compiler::{
         target::CallingConvention,
     },
     wasmparser::{
-        BlockType as WpTypeOrFuncType, HeapType as WpHeapType, Operator, RefType as WpRefType,
-        ValType as WpType,
+        BlockType as WpTypeOrFuncType, HeapType as WpHeapType, MemArg, Operator,
+        RefType as WpRefType, ValType as WpType,
     },
     FunctionBodyData,
 };
@@ -100,6 +100,20 @@ pub struct FuncGen<'a, M: Machine> {
 
     /// Calling convention to use.
     calling_convention: CallingConvention,
+
+    /// Tail calls, whose frame teardown and jump to the callee are generated
+    /// at the end of the function. Indirect tail calls have no relocation
+    /// target: their callee address is held in the tail call GPR.
+    tail_calls: Vec<(Label, Option<RelocationTarget>)>,
+
+    /// Size of the stack parameters area the body of every local function is
+    /// entered with, so that it can tail call any function of the module.
+    /// Zero when no such area is needed.
+    stack_params_area: usize,
+
+    /// Stack offsets of the value stack slots holding a `v128`. Those slots
+    /// are 16 bytes wide, all the others are 8 bytes wide.
+    v128_stack_slots: BTreeSet<usize>,
 }
 
 struct SpecialLabelSet {
@@ -252,6 +266,7 @@ fn type_to_wp_type(ty: Type) -> WpType {
         Type::V128 => WpType::V128,
         Type::ExternRef => WpType::Ref(WpRefType::new(true, WpHeapType::EXTERN).unwrap()),
         Type::FuncRef => WpType::Ref(WpRefType::new(true, WpHeapType::FUNC).unwrap()),
+        Type::ExceptionRef => WpType::Ref(WpRefType::EXNREF),
     }
 }
 
@@ -288,14 +303,20 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 WpType::Ref(ty) if ty.is_extern_ref() || ty.is_func_ref() => {
                     self.machine.pick_gpr().map(Location::GPR)
                 }
+                // v128 values always live on the stack.
+                WpType::V128 => None,
                 _ => codegen_error!("can't acquire location for type {:?}", ty),
             };
 
             let loc = if let Some(x) = loc {
                 x
             } else {
-                self.stack_offset.0 += 8;
-                delta_stack_offset += 8;
+                let size = if *ty == WpType::V128 { 16 } else { 8 };
+                self.stack_offset.0 += size;
+                delta_stack_offset += size;
+                if *ty == WpType::V128 {
+                    self.v128_stack_slots.insert(self.stack_offset.0);
+                }
                 self.machine.local_on_stack(self.stack_offset.0 as i32)
             };
             if let Location::GPR(x) = loc {
@@ -323,6 +344,38 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         Ok(ret)
     }
 
+    /// Size in bytes of the value stack slot at the given stack offset.
+    fn stack_slot_size(&self, offset: usize) -> usize {
+        if self.v128_stack_slots.contains(&offset) {
+            16
+        } else {
+            8
+        }
+    }
+
+    /// Splits the location of a `v128` in the locations of its low and high 64 bits.
+    #[allow(clippy::type_complexity)]
+    fn v128_halves(
+        loc: Location<M::GPR, M::SIMD>,
+    ) -> Result<[Location<M::GPR, M::SIMD>; 2], CompileError> {
+        match loc {
+            Location::Memory(reg, x) => {
+                Ok([Location::Memory(reg, x), Location::Memory(reg, x + 8)])
+            }
+            _ => codegen_error!("singlepass v128 location not in memory: {:?}", loc),
+        }
+    }
+
+    /// Checks if a location is a value stack slot holding a `v128`.
+    fn is_v128_location(&self, loc: Location<M::GPR, M::SIMD>) -> bool {
+        match loc {
+            Location::Memory(reg, x) if reg == self.machine.local_pointer() && x < 0 => {
+                self.v128_stack_slots.contains(&((-x) as usize))
+            }
+            _ => false,
+        }
+    }
+
     /// Releases locations used for stack value.
     fn release_locations(
         &mut self,
@@ -355,8 +408,10 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                                 self.stack_offset.0
                             );
                         }
-                        self.stack_offset.0 -= 8;
-                        delta_stack_offset += 8;
+                        let size = self.stack_slot_size(offset);
+                        self.v128_stack_slots.remove(&offset);
+                        self.stack_offset.0 -= size;
+                        delta_stack_offset += size;
                         self.state
                             .stack_values
                             .pop()
@@ -406,8 +461,10 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                                 self.stack_offset.0
                             );
                         }
-                        self.stack_offset.0 -= 8;
-                        delta_stack_offset += 8;
+                        let size = self.stack_slot_size(offset);
+                        self.v128_stack_slots.remove(&offset);
+                        self.stack_offset.0 -= size;
+                        delta_stack_offset += size;
                         self.state.stack_values.pop().ok_or_else(|| {
                             CompileError::Codegen("Pop with values stack empty".to_owned())
                         })?;
@@ -467,8 +524,10 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     if offset != self.stack_offset.0 {
                         codegen_error!("Invalid memory offset {}!={}", offset, self.stack_offset.0);
                     }
-                    self.stack_offset.0 -= 8;
-                    delta_stack_offset += 8;
+                    let size = self.stack_slot_size(offset);
+                    self.v128_stack_slots.remove(&offset);
+                    self.stack_offset.0 -= size;
+                    delta_stack_offset += size;
                     self.state.stack_values.pop().ok_or_else(|| {
                         CompileError::Codegen("Pop on empty value stack".to_owned())
                     })?;
@@ -510,8 +569,9 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     if offset != stack_offset {
                         codegen_error!("Invalid memory offset {}!={}", offset, self.stack_offset.0);
                     }
-                    stack_offset -= 8;
-                    delta_stack_offset += 8;
+                    let size = self.stack_slot_size(offset);
+                    stack_offset -= size;
+                    delta_stack_offset += size;
                 }
             }
         }
@@ -558,12 +618,26 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         let callee_saved_regs_size = static_area_size;
 
         // Now we can determine concrete locations for locals.
-        let locations: Vec<Location<M::GPR, M::SIMD>> = (0..n)
+        let index_locations: Vec<Location<M::GPR, M::SIMD>> = (0..n)
             .map(|i| self.machine.get_local_location(i, callee_saved_regs_size))
             .collect();
 
+        // A v128 local doesn't fit in the location of its index, so it gets a 16 bytes slot
+        // below all the other locals instead.
+        let mut locations = index_locations.clone();
+        let mut v128_locations = vec![];
+        for (i, location) in locations.iter_mut().enumerate() {
+            if self.local_types[i] == WpType::V128 {
+                *location = self.machine.local_on_stack(
+                    (callee_saved_regs_size + num_mem_slots * 8 + 16 * (v128_locations.len() + 1))
+                        as i32,
+                );
+                v128_locations.push((i, *location));
+            }
+        }
+
         // Add size of locals on stack.
-        static_area_size += num_mem_slots * 8;
+        static_area_size += num_mem_slots * 8 + 16 * v128_locations.len();
 
         // Allocate save area, without actually writing to it.
         static_area_size = self.machine.round_stack_adjust(static_area_size);
@@ -576,13 +650,16 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             .step_by(NATIVE_PAGE_SIZE / 8)
             .skip(1)
         {
-            self.machine.zero_location(Size::S64, locations[i])?;
+            self.machine.zero_location(Size::S64, index_locations[i])?;
+        }
+        for (_, location) in v128_locations.iter().step_by(NATIVE_PAGE_SIZE / 16) {
+            self.machine.zero_location(Size::S64, *location)?;
         }
 
         self.machine.adjust_stack(static_area_size as _)?;
 
         // Save callee-saved registers.
-        for loc in locations.iter() {
+        for loc in index_locations.iter() {
             if let Location::GPR(x) = *loc {
                 self.stack_offset.0 += 8;
                 self.machine.move_local(self.stack_offset.0 as i32, *loc)?;
@@ -630,21 +707,37 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         // Locals are allocated on the stack from higher address to lower address,
         // so we won't skip the stack guard page here.
         let mut stack_offset: usize = 0;
+        let mut param_slot = 1;
         for (i, param) in sig.params().iter().enumerate() {
             let sz = match *param {
                 Type::I32 | Type::F32 => Size::S32,
                 Type::I64 | Type::F64 => Size::S64,
                 Type::ExternRef | Type::FuncRef => Size::S64,
+                Type::V128 => {
+                    // A v128 is passed in two consecutive 64 bits parameters.
+                    for half in Self::v128_halves(locations[i])? {
+                        let loc = self.machine.get_call_param_location(
+                            param_slot,
+                            Size::S64,
+                            &mut stack_offset,
+                            calling_convention,
+                        );
+                        self.machine.move_location(Size::S64, loc, half)?;
+                        param_slot += 1;
+                    }
+                    continue;
+                }
                 _ => codegen_error!("singlepass init_local unimplemented"),
             };
             let loc = self.machine.get_call_param_location(
-                i + 1,
+                param_slot,
                 sz,
                 &mut stack_offset,
                 calling_convention,
             );
             self.machine
                 .move_location_extend(sz, false, loc, Size::S64, locations[i])?;
+            param_slot += 1;
         }
 
         // Load vmctx into it's GPR.
@@ -658,12 +751,19 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         // Initialize all normal locals to zero.
         let mut init_stack_loc_cnt = 0;
         let mut last_stack_loc = Location::Memory(self.machine.local_pointer(), i32::MAX);
-        for location in locations.iter().take(n).skip(sig.params().len()) {
+        for (i, location) in index_locations
+            .iter()
+            .enumerate()
+            .take(n)
+            .skip(sig.params().len())
+        {
             match location {
                 Location::Memory(_, _) => {
                     init_stack_loc_cnt += 1;
                     last_stack_loc = cmp::min(last_stack_loc, *location);
                 }
+                // The register of a v128 local is not used, and must be left untouched.
+                Location::GPR(_) if self.local_types[i] == WpType::V128 => {}
                 Location::GPR(_) => {
                     self.machine.zero_location(Size::S64, *location)?;
                 }
@@ -674,6 +774,11 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             self.machine
                 .init_stack_loc(init_stack_loc_cnt, last_stack_loc)?;
         }
+        for (i, location) in v128_locations.iter() {
+            if *i >= sig.params().len() {
+                self.machine.v128_const(0, *location)?;
+            }
+        }
 
         // Add the size of all locals allocated to stack.
         self.stack_offset.0 += static_area_size - callee_saved_regs_size;
@@ -699,9 +804,10 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             .pop_location(Location::GPR(self.machine.get_vmctx_reg()))?;
 
         // Restore callee-saved registers.
-        for loc in self.locals.iter().rev() {
-            if let Location::GPR(_) = *loc {
-                self.machine.pop_location(*loc)?;
+        // Keep this consistent with the "Save callee-saved registers" code of `init_locals`.
+        for i in (0..self.locals.len()).rev() {
+            if let Location::GPR(x) = self.machine.get_local_location(i, 0) {
+                self.machine.pop_location(Location::GPR(x))?;
             }
         }
         Ok(())
@@ -739,6 +845,157 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         Ok(I2O1 { loc_a, loc_b, ret })
     }
 
+    /// Pushes the result of a SIMD operator on the value stack.
+    fn v128_push_result(&mut self, ty: WpType) -> Result<Location<M::GPR, M::SIMD>, CompileError> {
+        let ret = self.acquire_locations(
+            &[(ty, MachineValue::WasmStack(self.value_stack.len()))],
+            false,
+        )?[0];
+        self.value_stack.push(ret);
+        if ty.is_float() {
+            self.fp_stack
+                .push(FloatValue::new(self.value_stack.len() - 1));
+        }
+        Ok(ret)
+    }
+
+    fn op_v128_unop(&mut self, op: V128UnOp) -> Result<(), CompileError> {
+        let loc = self.pop_value_released()?;
+        let ret = self.v128_push_result(WpType::V128)?;
+        self.machine.v128_unop(op, loc, ret)
+    }
+
+    fn op_v128_binop(&mut self, op: V128BinOp) -> Result<(), CompileError> {
+        let loc_b = self.pop_value_released()?;
+        let loc_a = self.pop_value_released()?;
+        let ret = self.v128_push_result(WpType::V128)?;
+        self.machine.v128_binop(op, loc_a, loc_b, ret)
+    }
+
+    fn op_v128_shift(&mut self, op: V128ShiftOp) -> Result<(), CompileError> {
+        let amount = self.pop_value_released()?;
+        let loc = self.pop_value_released()?;
+        let ret = self.v128_push_result(WpType::V128)?;
+        self.machine.v128_shift(op, loc, amount, ret)
+    }
+
+    fn op_v128_test(&mut self, op: V128TestOp) -> Result<(), CompileError> {
+        let loc = self.pop_value_released()?;
+        let ret = self.v128_push_result(WpType::I32)?;
+        self.machine.v128_test(op, loc, ret)
+    }
+
+    fn op_v128_splat(&mut self, shape: V128Shape) -> Result<(), CompileError> {
+        let loc = self.pop_value_released()?;
+        if matches!(shape, V128Shape::F32x4 | V128Shape::F64x2) {
+            self.fp_stack.pop1()?;
+        }
+        let ret = self.v128_push_result(WpType::V128)?;
+        self.machine.v128_splat(shape, loc, ret)
+    }
+
+    fn op_v128_extract_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        signed: bool,
+    ) -> Result<(), CompileError> {
+        let loc = self.pop_value_released()?;
+        let ty = match shape {
+            V128Shape::I64x2 => WpType::I64,
+            V128Shape::F32x4 => WpType::F32,
+            V128Shape::F64x2 => WpType::F64,
+            _ => WpType::I32,
+        };
+        let ret = self.v128_push_result(ty)?;
+        self.machine
+            .v128_extract_lane(shape, lane, signed, loc, ret)
+    }
+
+    fn op_v128_replace_lane(&mut self, shape: V128Shape, lane: u8) -> Result<(), CompileError> {
+        let value = self.pop_value_released()?;
+        if matches!(shape, V128Shape::F32x4 | V128Shape::F64x2) {
+            self.fp_stack.pop1()?;
+        }
+        let loc = self.pop_value_released()?;
+        let ret = self.v128_push_result(WpType::V128)?;
+        self.machine.v128_replace_lane(shape, lane, loc, value, ret)
+    }
+
+    fn op_v128_load(&mut self, kind: V128LoadKind, memarg: &MemArg) -> Result<(), CompileError> {
+        let target = self.pop_value_released()?;
+        let ret = self.v128_push_result(WpType::V128)?;
+        self.op_memory(
+            |this, need_check, imported_memories, offset, heap_access_oob, unaligned_atomic| {
+                this.machine.v128_load(
+                    kind,
+                    target,
+                    memarg,
+                    ret,
+                    need_check,
+                    imported_memories,
+                    offset,
+                    heap_access_oob,
+                    unaligned_atomic,
+                )
+            },
+        )
+    }
+
+    fn op_v128_load_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        memarg: &MemArg,
+    ) -> Result<(), CompileError> {
+        let loc = self.pop_value_released()?;
+        let target = self.pop_value_released()?;
+        let ret = self.v128_push_result(WpType::V128)?;
+        self.op_memory(
+            |this, need_check, imported_memories, offset, heap_access_oob, unaligned_atomic| {
+                this.machine.v128_load_lane(
+                    shape,
+                    lane,
+                    target,
+                    memarg,
+                    loc,
+                    ret,
+                    need_check,
+                    imported_memories,
+                    offset,
+                    heap_access_oob,
+                    unaligned_atomic,
+                )
+            },
+        )
+    }
+
+    fn op_v128_save_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        memarg: &MemArg,
+    ) -> Result<(), CompileError> {
+        let target_value = self.pop_value_released()?;
+        let target_addr = self.pop_value_released()?;
+        self.op_memory(
+            |this, need_check, imported_memories, offset, heap_access_oob, unaligned_atomic| {
+                this.machine.v128_save_lane(
+                    shape,
+                    lane,
+                    target_value,
+                    memarg,
+                    target_addr,
+                    need_check,
+                    imported_memories,
+                    offset,
+                    heap_access_oob,
+                    unaligned_atomic,
+                )
+            },
+        )
+    }
+
     fn mark_trappable(&mut self) {
         let state_diff_id = self.get_state_diff();
         let offset = self.machine.assembler_get_offset().0;
@@ -784,15 +1041,34 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         cb: F,
         params: I,
         params_type: J,
+    ) -> Result<(), CompileError> {
+        self.emit_call_native_with_stack_area(cb, params, params_type, 0)
+    }
+
+    /// Emits a Native ABI call sequence passing a stack parameters area of at least
+    /// `stack_params_area` bytes, as the bodies of local functions expect.
+    ///
+    /// The caller MUST NOT hold any temporary registers allocated by `acquire_temp_gpr` when calling
+    /// this function.
+    fn emit_call_native_with_stack_area<
+        I: Iterator<Item = Location<M::GPR, M::SIMD>>,
+        J: Iterator<Item = WpType>,
+        F: FnOnce(&mut Self) -> Result<(), CompileError>,
+    >(
+        &mut self,
+        cb: F,
+        params: I,
+        params_type: J,
+        stack_params_area: usize,
     ) -> Result<(), CompileError> {
         // Values pushed in this function are above the shadow region.
         self.state.stack_values.push(MachineValue::ExplicitShadow);
 
-        let params: Vec<_> = params.collect();
+        let (params, params_type) = Self::split_v128_params(params, params_type)?;
         let params_size: Vec<_> = params_type
+            .iter()
             .map(|x| match x {
                 WpType::F32 | WpType::I32 => Size::S32,
-                WpType::V128 => unimplemented!(),
                 _ => Size::S64,
             })
             .collect();
@@ -849,6 +1125,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 calling_convention,
             ));
         }
+        stack_offset = stack_offset.max(stack_params_area);
 
         // Align stack to 16 bytes.
         let stack_unaligned =
@@ -1011,6 +1288,161 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         Ok(())
     }
 
+    /// Splits the `v128` parameters of a Native ABI call in two 64 bits parameters, low half
+    /// first, which is how they are passed.
+    #[allow(clippy::type_complexity)]
+    fn split_v128_params<
+        I: Iterator<Item = Location<M::GPR, M::SIMD>>,
+        J: Iterator<Item = WpType>,
+    >(
+        params: I,
+        params_type: J,
+    ) -> Result<(Vec<Location<M::GPR, M::SIMD>>, Vec<WpType>), CompileError> {
+        let mut locations = vec![];
+        let mut types = vec![];
+        for (param, ty) in params.zip(params_type) {
+            if ty == WpType::V128 {
+                locations.extend(Self::v128_halves(param)?);
+                types.extend([WpType::I64, WpType::I64]);
+            } else {
+                locations.push(param);
+                types.push(ty);
+            }
+        }
+        Ok((locations, types))
+    }
+
+    /// Emits a Native ABI tail call sequence.
+    ///
+    /// The parameters are moved where the callee expects them once the current frame is
+    /// torn down, then `cb` can set up the target of the call. The frame teardown and the
+    /// jump to `target` (or to the address in the tail call GPR if `None`) are generated at
+    /// the end of the function.
+    ///
+    /// The stack parameters of any callee fit in the area the current function was
+    /// entered with, which is `stack_params_area` bytes large.
+    fn emit_tail_call_native<
+        I: Iterator<Item = Location<M::GPR, M::SIMD>>,
+        J: Iterator<Item = WpType>,
+        F: FnOnce(&mut Self) -> Result<(), CompileError>,
+    >(
+        &mut self,
+        cb: F,
+        target: Option<RelocationTarget>,
+        params: I,
+        params_type: J,
+    ) -> Result<(), CompileError> {
+        let (params, params_type) = Self::split_v128_params(params, params_type)?;
+        let params_size: Vec<_> = params_type
+            .iter()
+            .map(|x| match x {
+                WpType::F32 | WpType::I32 => Size::S32,
+                _ => Size::S64,
+            })
+            .collect();
+
+        // mark the GPR used for Call as used
+        self.machine
+            .reserve_unused_temp_gpr(self.machine.get_grp_for_call());
+
+        let calling_convention = self.calling_convention;
+
+        // The parameters go where the current function got its own, stack parameters included.
+        let mut stack_offset: usize = 0;
+        let args: Vec<_> = params_size
+            .iter()
+            .enumerate()
+            .map(|(i, sz)| {
+                self.machine.get_call_param_location(
+                    1 + i,
+                    *sz,
+                    &mut stack_offset,
+                    calling_convention,
+                )
+            })
+            .collect();
+
+        #[allow(clippy::type_complexity)]
+        let mut call_movs: Vec<(Location<M::GPR, M::SIMD>, M::GPR)> = vec![];
+        // Prepare register & stack parameters.
+        for (i, param) in params.iter().enumerate().rev() {
+            let loc = args[i];
+            match loc {
+                Location::GPR(x) => {
+                    call_movs.push((*param, x));
+                }
+                Location::Memory(_, _) => {
+                    self.machine
+                        .move_location_for_native(params_size[i], *param, loc)?;
+                }
+                _ => {
+                    return Err(CompileError::Codegen(
+                        "emit_tail_call_native loc: unreachable code".to_owned(),
+                    ))
+                }
+            }
+        }
+
+        // Sort register moves so that register are not overwritten before read.
+        Self::sort_call_movs(&mut call_movs);
+
+        // Emit register moves.
+        for (loc, gpr) in call_movs {
+            if loc != Location::GPR(gpr) {
+                self.machine
+                    .move_location(Size::S64, loc, Location::GPR(gpr))?;
+            }
+        }
+
+        // Put vmctx as the first parameter.
+        self.machine.move_location(
+            Size::S64,
+            Location::GPR(self.machine.get_vmctx_reg()),
+            self.machine
+                .get_simple_param_location(0, calling_convention),
+        )?; // vmctx
+
+        // release the GPR used for call
+        self.machine.release_gpr(self.machine.get_grp_for_call());
+        cb(self)?;
+
+        let label = self.machine.get_label();
+        self.machine.jmp_unconditionnal(label)?;
+        self.tail_calls.push((label, target));
+        Ok(())
+    }
+
+    /// Emits the return from the current function, jumping to its epilog.
+    fn emit_return(&mut self) -> Result<(), CompileError> {
+        let frame = &self.control_stack[0];
+        if !frame.returns.is_empty() {
+            if frame.returns.len() != 1 {
+                return Err(CompileError::Codegen(
+                    "Return: incorrect frame.returns".to_owned(),
+                ));
+            }
+            let first_return = frame.returns[0];
+            let loc = *self.value_stack.last().unwrap();
+            let canonicalize = if first_return.is_float() {
+                let fp = self.fp_stack.peek1()?;
+                self.machine.arch_supports_canonicalize_nan()
+                    && self.config.enable_nan_canonicalization
+                    && fp.canonicalization.is_some()
+            } else {
+                false
+            };
+            self.machine
+                .emit_function_return_value(first_return, canonicalize, loc)?;
+        }
+        let frame = &self.control_stack[0];
+        let frame_depth = frame.value_stack_depth;
+        let label = frame.label;
+        self.release_locations_keep_state(frame_depth)?;
+        self.machine.jmp_unconditionnal(label)?;
+        self.unreachable_depth = 1;
+        Ok(())
+    }
+
     /// Emits a Native ABI call sequence, specialized for labels as the call target.
     fn _emit_call_native_label<
         I: Iterator<Item = Location<M::GPR, M::SIMD>>,
@@ -1072,6 +1504,65 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         id
     }
 
+    /// Emits a check of the epoch deadline of the store, calling the
+    /// `epoch_deadline_reached` builtin once the current epoch reaches it.
+    fn emit_epoch_check(&mut self) -> Result<(), CompileError> {
+        let deadline = self.machine.acquire_temp_gpr().ok_or_else(|| {
+            CompileError::Codegen("singlepass cannot acquire temp gpr".to_owned())
+        })?;
+        let epoch = self.machine.acquire_temp_gpr().ok_or_else(|| {
+            CompileError::Codegen("singlepass cannot acquire temp gpr".to_owned())
+        })?;
+        let not_reached = self.machine.get_label();
+
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(
+                self.machine.get_vmctx_reg(),
+                self.vmoffsets.vmctx_epoch_state() as i32,
+            ),
+            Location::GPR(deadline),
+        )?;
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(deadline, self.vmoffsets.vmepoch_state_counter() as i32),
+            Location::GPR(epoch),
+        )?;
+        self.machine
+            .move_location(Size::S64, Location::Memory(epoch, 0), Location::GPR(epoch))?;
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(deadline, self.vmoffsets.vmepoch_state_deadline() as i32),
+            Location::GPR(deadline),
+        )?;
+        self.machine
+            .location_cmp(Size::S64, Location::GPR(epoch), Location::GPR(deadline))?;
+        self.machine.jmp_on_above(not_reached)?;
+        self.machine.release_gpr(epoch);
+        self.machine.release_gpr(deadline);
+
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(
+                self.machine.get_vmctx_reg(),
+                self.vmoffsets.vmctx_builtin_function(
+                    VMBuiltinFunctionIndex::get_epoch_deadline_reached_index(),
+                ) as i32,
+            ),
+            Location::GPR(self.machine.get_grp_for_call()),
+        )?;
+        self.emit_call_native(
+            |this| {
+                this.machine
+                    .emit_call_register(this.machine.get_grp_for_call())
+            },
+            // [vmctx]
+            iter::empty(),
+            iter::empty(),
+        )?;
+        self.machine.emit_label(not_reached)
+    }
+
     fn emit_head(&mut self) -> Result<(), CompileError> {
         self.machine.emit_function_prolog()?;
 
@@ -1110,12 +1601,14 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             state_diff_id,
         });
 
-        // TODO: Full preemption by explicit signal checking
-
         // We insert set StackOverflow as the default trap that can happen
         // anywhere in the function prologue.
         self.machine.insert_stackoverflow();
 
+        if self.config.enable_epoch_interruption {
+            self.emit_epoch_check()?;
+        }
+
         if self.state.wasm_inst_offset != usize::MAX {
             return Err(CompileError::Codegen(
                 "emit_head: wasm_inst_offset not usize::MAX".to_owned(),
@@ -1135,6 +1628,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         local_types_excluding_arguments: &[WpType],
         machine: M,
         calling_convention: CallingConvention,
+        stack_params_area: usize,
     ) -> Result<FuncGen<'a, M>, CompileError> {
         let func_index = module.func_index(local_func_index);
         let sig_index = module.functions[func_index];
@@ -1189,6 +1683,9 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             relocations: vec![],
             special_labels,
             calling_convention,
+            tail_calls: vec![],
+            stack_params_area,
+            v128_stack_slots: BTreeSet::new(),
         };
         fg.emit_head()?;
         Ok(fg)
@@ -1274,7 +1771,11 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     Location::Memory(tmp, 0)
                 };
 
-                self.machine.emit_relaxed_mov(Size::S64, src, loc)?;
+                if ty == WpType::V128 {
+                    self.machine.v128_move(src, loc)?;
+                } else {
+                    self.machine.emit_relaxed_mov(Size::S64, src, loc)?;
+                }
 
                 self.machine.release_gpr(tmp);
             }
@@ -1305,7 +1806,9 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 };
                 let ty = type_to_wp_type(self.module.globals[global_index].ty);
                 let loc = self.pop_value_released()?;
-                if ty.is_float() {
+                if ty == WpType::V128 {
+                    self.machine.v128_move(loc, dst)?;
+                } else if ty.is_float() {
                     let fp = self.fp_stack.pop1()?;
                     if self.machine.arch_supports_canonicalize_nan()
                         && self.config.enable_nan_canonicalization
@@ -1330,12 +1833,20 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             }
             Operator::LocalGet { local_index } => {
                 let local_index = local_index as usize;
+                let ty = match self.local_types[local_index] {
+                    WpType::V128 => WpType::V128,
+                    _ => WpType::I64,
+                };
                 let ret = self.acquire_locations(
-                    &[(WpType::I64, MachineValue::WasmStack(self.value_stack.len()))],
+                    &[(ty, MachineValue::WasmStack(self.value_stack.len()))],
                     false,
                 )?[0];
-                self.machine
-                    .emit_relaxed_mov(Size::S64, self.locals[local_index], ret)?;
+                if ty == WpType::V128 {
+                    self.machine.v128_move(self.locals[local_index], ret)?;
+                } else {
+                    self.machine
+                        .emit_relaxed_mov(Size::S64, self.locals[local_index], ret)?;
+                }
                 self.value_stack.push(ret);
                 if self.local_types[local_index].is_float() {
                     self.fp_stack
@@ -1346,7 +1857,9 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let local_index = local_index as usize;
                 let loc = self.pop_value_released()?;
 
-                if self.local_types[local_index].is_float() {
+                if self.local_types[local_index] == WpType::V128 {
+                    self.machine.v128_move(loc, self.locals[local_index])
+                } else if self.local_types[local_index].is_float() {
                     let fp = self.fp_stack.pop1()?;
                     if self.machine.arch_supports_canonicalize_nan()
                         && self.config.enable_nan_canonicalization
@@ -1374,7 +1887,9 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let local_index = local_index as usize;
                 let loc = *self.value_stack.last().unwrap();
 
-                if self.local_types[local_index].is_float() {
+                if self.local_types[local_index] == WpType::V128 {
+                    self.machine.v128_move(loc, self.locals[local_index])
+                } else if self.local_types[local_index].is_float() {
                     let fp = self.fp_stack.peek1()?;
                     if self.machine.arch_supports_canonicalize_nan()
                         && self.config.enable_nan_canonicalization
@@ -2631,7 +3146,8 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 self.machine.convert_f64_i64(loc, false, ret)?;
             }
 
-            Operator::Call { function_index } => {
+            Operator::Call { function_index } | Operator::ReturnCall { function_index } => {
+                let tail_call = matches!(op, Operator::ReturnCall { .. });
                 let function_index = function_index as usize;
 
                 let sig_index = *self
@@ -2684,7 +3200,26 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 };
                 let calling_convention = self.calling_convention;
 
-                self.emit_call_native(
+                if tail_call {
+                    self.emit_tail_call_native(
+                        |_| Ok(()),
+                        Some(reloc_target),
+                        params.iter().copied(),
+                        param_types.iter().copied(),
+                    )?;
+
+                    self.release_locations_only_stack(&params)?;
+                    self.unreachable_depth = 1;
+                    return Ok(());
+                }
+
+                // Local functions are called past their entry trampoline.
+                let stack_params_area = if function_index < self.module.num_imported_functions {
+                    0
+                } else {
+                    self.stack_params_area
+                };
+                self.emit_call_native_with_stack_area(
                     |this| {
                         let offset = this
                             .machine
@@ -2698,6 +3233,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     },
                     params.iter().copied(),
                     param_types.iter().copied(),
+                    stack_params_area,
                 )?;
 
                 self.release_locations_only_stack(&params)?;
@@ -2711,7 +3247,10 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                         false,
                     )?[0];
                     self.value_stack.push(ret);
-                    if return_types[0].is_float() {
+                    if return_types[0] == WpType::V128 {
+                        self.machine
+                            .v128_move(Location::SIMD(self.machine.get_simd_for_ret()), ret)?;
+                    } else if return_types[0].is_float() {
                         self.machine.move_location(
                             Size::S64,
                             Location::SIMD(self.machine.get_simd_for_ret()),
@@ -2731,7 +3270,12 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             Operator::CallIndirect {
                 type_index,
                 table_index,
+            }
+            | Operator::ReturnCallIndirect {
+                type_index,
+                table_index,
             } => {
+                let tail_call = matches!(op, Operator::ReturnCallIndirect { .. });
                 // TODO: removed restriction on always being table idx 0;
                 // does any code depend on this?
                 let table_index = TableIndex::new(table_index as _);
@@ -2890,8 +3434,69 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     self.vmoffsets.vmcaller_checked_anyfunc_func_ptr() as usize;
                 let vmcaller_checked_anyfunc_vmctx =
                     self.vmoffsets.vmcaller_checked_anyfunc_vmctx() as usize;
+                let vmcaller_checked_anyfunc_tail_func_ptr =
+                    self.vmoffsets.vmcaller_checked_anyfunc_tail_func_ptr() as usize;
                 let calling_convention = self.calling_convention;
 
+                if tail_call {
+                    let gpr_for_tail_call = self.machine.get_gpr_for_tail_call();
+                    self.emit_tail_call_native(
+                        |this| {
+                            // We set the context pointer, then the address to jump to
+                            let callee_vmctx = this
+                                .machine
+                                .get_simple_param_location(0, calling_convention);
+                            this.machine.move_location(
+                                Size::S64,
+                                Location::Memory(
+                                    gpr_for_call,
+                                    vmcaller_checked_anyfunc_vmctx as i32,
+                                ),
+                                callee_vmctx,
+                            )?;
+                            let native_entry = this.machine.get_label();
+                            let done = this.machine.get_label();
+                            if this.stack_params_area > 0 {
+                                // The functions of this instance are jumped to past their
+                                // entry trampoline, so they keep our stack parameters area
+                                // and a chain of tail calls runs in constant stack space.
+                                this.machine.location_cmp(
+                                    Size::S64,
+                                    Location::GPR(this.machine.get_vmctx_reg()),
+                                    callee_vmctx,
+                                )?;
+                                this.machine.jmp_on_different(native_entry)?;
+                                this.machine.move_location(
+                                    Size::S64,
+                                    Location::Memory(
+                                        gpr_for_call,
+                                        vmcaller_checked_anyfunc_tail_func_ptr as i32,
+                                    ),
+                                    Location::GPR(gpr_for_tail_call),
+                                )?;
+                                this.machine.jmp_unconditionnal(done)?;
+                            }
+                            this.machine.emit_label(native_entry)?;
+                            this.machine.move_location(
+                                Size::S64,
+                                Location::Memory(
+                                    gpr_for_call,
+                                    vmcaller_checked_anyfunc_func_ptr as i32,
+                                ),
+                                Location::GPR(gpr_for_tail_call),
+                            )?;
+                            this.machine.emit_label(done)
+                        },
+                        None,
+                        params.iter().copied(),
+                        param_types.iter().copied(),
+                    )?;
+
+                    self.release_locations_only_stack(&params)?;
+                    self.unreachable_depth = 1;
+                    return Ok(());
+                }
+
                 self.emit_call_native(
                     |this| {
                         if this.machine.arch_requires_indirect_call_trampoline() {
@@ -2939,7 +3544,10 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                         false,
                     )?[0];
                     self.value_stack.push(ret);
-                    if return_types[0].is_float() {
+                    if return_types[0] == WpType::V128 {
+                        self.machine
+                            .v128_move(Location::SIMD(self.machine.get_simd_for_ret()), ret)?;
+                    } else if return_types[0].is_float() {
                         self.machine.move_location(
                             Size::S64,
                             Location::SIMD(self.machine.get_simd_for_ret()),
@@ -3027,6 +3635,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             // `TypedSelect` must be used for extern refs so ref counting should
             // be done with TypedSelect. But otherwise they're the same.
             Operator::TypedSelect { .. } | Operator::Select => {
+                let is_v128 = self.is_v128_location(self.value_stack[self.value_stack.len() - 2]);
                 let cond = self.pop_value_released()?;
                 let v_b = self.pop_value_released()?;
                 let v_a = self.pop_value_released()?;
@@ -3043,7 +3652,10 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                         None
                     };
                 let ret = self.acquire_locations(
-                    &[(WpType::I64, MachineValue::WasmStack(self.value_stack.len()))],
+                    &[(
+                        if is_v128 { WpType::V128 } else { WpType::I64 },
+                        MachineValue::WasmStack(self.value_stack.len()),
+                    )],
                     false,
                 )?[0];
                 self.value_stack.push(ret);
@@ -3054,6 +3666,14 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 self.machine
                     .emit_relaxed_cmp(Size::S32, Location::Imm32(0), cond)?;
                 self.machine.jmp_on_equal(zero_label)?;
+                if is_v128 {
+                    self.machine.v128_move(v_a, ret)?;
+                    self.machine.jmp_unconditionnal(end_label)?;
+                    self.machine.emit_label(zero_label)?;
+                    self.machine.v128_move(v_b, ret)?;
+                    self.machine.emit_label(end_label)?;
+                    return Ok(());
+                }
                 match cncl {
                     Some((Some(fp), _))
                         if self.machine.arch_supports_canonicalize_nan()
@@ -3131,7 +3751,9 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 });
                 self.machine.emit_label(label)?;
 
-                // TODO: Re-enable interrupt signal check without branching
+                if self.config.enable_epoch_interruption {
+                    self.emit_epoch_check()?;
+                }
             }
             Operator::Nop => {}
             Operator::MemorySize { mem } => {
@@ -3990,32 +4612,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 self.unreachable_depth = 1;
             }
             Operator::Return => {
-                let frame = &self.control_stack[0];
-                if !frame.returns.is_empty() {
-                    if frame.returns.len() != 1 {
-                        return Err(CompileError::Codegen(
-                            "Return: incorrect frame.returns".to_owned(),
-                        ));
-                    }
-                    let first_return = frame.returns[0];
-                    let loc = *self.value_stack.last().unwrap();
-                    let canonicalize = if first_return.is_float() {
-                        let fp = self.fp_stack.peek1()?;
-                        self.machine.arch_supports_canonicalize_nan()
-                            && self.config.enable_nan_canonicalization
-                            && fp.canonicalization.is_some()
-                    } else {
-                        false
-                    };
-                    self.machine
-                        .emit_function_return_value(first_return, canonicalize, loc)?;
-                }
-                let frame = &self.control_stack[0];
-                let frame_depth = frame.value_stack_depth;
-                let label = frame.label;
-                self.release_locations_keep_state(frame_depth)?;
-                self.machine.jmp_unconditionnal(label)?;
-                self.unreachable_depth = 1;
+                self.emit_return()?;
             }
             Operator::Br { relative_depth } => {
                 let frame =
@@ -4243,11 +4840,16 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                             )],
                             false,
                         )?[0];
-                        self.machine.move_location(
-                            Size::S64,
-                            Location::GPR(self.machine.get_gpr_for_ret()),
-                            loc,
-                        )?;
+                        if frame.returns[0] == WpType::V128 {
+                            self.machine
+                                .v128_move(Location::SIMD(self.machine.get_simd_for_ret()), loc)?;
+                        } else {
+                            self.machine.move_location(
+                                Size::S64,
+                                Location::GPR(self.machine.get_gpr_for_ret()),
+                                loc,
+                            )?;
+                        }
                         self.value_stack.push(loc);
                         if frame.returns[0].is_float() {
                             self.fp_stack
@@ -6617,6 +7219,401 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     ret,
                 )?;
             }
+            Operator::V128Load { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Full, memarg)?;
+            }
+            Operator::V128Load8x8S { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Extend8x8S, memarg)?;
+            }
+            Operator::V128Load8x8U { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Extend8x8U, memarg)?;
+            }
+            Operator::V128Load16x4S { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Extend16x4S, memarg)?;
+            }
+            Operator::V128Load16x4U { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Extend16x4U, memarg)?;
+            }
+            Operator::V128Load32x2S { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Extend32x2S, memarg)?;
+            }
+            Operator::V128Load32x2U { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Extend32x2U, memarg)?;
+            }
+            Operator::V128Load8Splat { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Splat(V128Shape::I8x16), memarg)?;
+            }
+            Operator::V128Load16Splat { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Splat(V128Shape::I16x8), memarg)?;
+            }
+            Operator::V128Load32Splat { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Splat(V128Shape::I32x4), memarg)?;
+            }
+            Operator::V128Load64Splat { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Splat(V128Shape::I64x2), memarg)?;
+            }
+            Operator::V128Load32Zero { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Zero32, memarg)?;
+            }
+            Operator::V128Load64Zero { ref memarg } => {
+                self.op_v128_load(V128LoadKind::Zero64, memarg)?;
+            }
+            Operator::V128Store { ref memarg } => {
+                let target_value = self.pop_value_released()?;
+                let target_addr = self.pop_value_released()?;
+                self.op_memory(
+                    |this,
+                     need_check,
+                     imported_memories,
+                     offset,
+                     heap_access_oob,
+                     unaligned_atomic| {
+                        this.machine.v128_save(
+                            target_value,
+                            memarg,
+                            target_addr,
+                            need_check,
+                            imported_memories,
+                            offset,
+                            heap_access_oob,
+                            unaligned_atomic,
+                        )
+                    },
+                )?;
+            }
+            Operator::V128Load8Lane { ref memarg, lane } => {
+                self.op_v128_load_lane(V128Shape::I8x16, lane, memarg)?;
+            }
+            Operator::V128Load16Lane { ref memarg, lane } => {
+                self.op_v128_load_lane(V128Shape::I16x8, lane, memarg)?;
+            }
+            Operator::V128Load32Lane { ref memarg, lane } => {
+                self.op_v128_load_lane(V128Shape::I32x4, lane, memarg)?;
+            }
+            Operator::V128Load64Lane { ref memarg, lane } => {
+                self.op_v128_load_lane(V128Shape::I64x2, lane, memarg)?;
+            }
+            Operator::V128Store8Lane { ref memarg, lane } => {
+                self.op_v128_save_lane(V128Shape::I8x16, lane, memarg)?;
+            }
+            Operator::V128Store16Lane { ref memarg, lane } => {
+                self.op_v128_save_lane(V128Shape::I16x8, lane, memarg)?;
+            }
+            Operator::V128Store32Lane { ref memarg, lane } => {
+                self.op_v128_save_lane(V128Shape::I32x4, lane, memarg)?;
+            }
+            Operator::V128Store64Lane { ref memarg, lane } => {
+                self.op_v128_save_lane(V128Shape::I64x2, lane, memarg)?;
+            }
+            Operator::V128Const { value } => {
+                let ret = self.v128_push_result(WpType::V128)?;
+                self.machine
+                    .v128_const(u128::from_le_bytes(*value.bytes()), ret)?;
+            }
+            Operator::I8x16Shuffle { lanes } => {
+                let loc_b = self.pop_value_released()?;
+                let loc_a = self.pop_value_released()?;
+                let ret = self.v128_push_result(WpType::V128)?;
+                self.machine.v128_shuffle(lanes, loc_a, loc_b, ret)?;
+            }
+            Operator::I8x16ExtractLaneS { lane } => {
+                self.op_v128_extract_lane(V128Shape::I8x16, lane, true)?;
+            }
+            Operator::I8x16ExtractLaneU { lane } => {
+                self.op_v128_extract_lane(V128Shape::I8x16, lane, false)?;
+            }
+            Operator::I8x16ReplaceLane { lane } => {
+                self.op_v128_replace_lane(V128Shape::I8x16, lane)?;
+            }
+            Operator::I16x8ExtractLaneS { lane } => {
+                self.op_v128_extract_lane(V128Shape::I16x8, lane, true)?;
+            }
+            Operator::I16x8ExtractLaneU { lane } => {
+                self.op_v128_extract_lane(V128Shape::I16x8, lane, false)?;
+            }
+            Operator::I16x8ReplaceLane { lane } => {
+                self.op_v128_replace_lane(V128Shape::I16x8, lane)?;
+            }
+            Operator::I32x4ExtractLane { lane } => {
+                self.op_v128_extract_lane(V128Shape::I32x4, lane, false)?;
+            }
+            Operator::I32x4ReplaceLane { lane } => {
+                self.op_v128_replace_lane(V128Shape::I32x4, lane)?;
+            }
+            Operator::I64x2ExtractLane { lane } => {
+                self.op_v128_extract_lane(V128Shape::I64x2, lane, false)?;
+            }
+            Operator::I64x2ReplaceLane { lane } => {
+                self.op_v128_replace_lane(V128Shape::I64x2, lane)?;
+            }
+            Operator::F32x4ExtractLane { lane } => {
+                self.op_v128_extract_lane(V128Shape::F32x4, lane, false)?;
+            }
+            Operator::F32x4ReplaceLane { lane } => {
+                self.op_v128_replace_lane(V128Shape::F32x4, lane)?;
+            }
+            Operator::F64x2ExtractLane { lane } => {
+                self.op_v128_extract_lane(V128Shape::F64x2, lane, false)?;
+            }
+            Operator::F64x2ReplaceLane { lane } => {
+                self.op_v128_replace_lane(V128Shape::F64x2, lane)?;
+            }
+            Operator::I8x16Splat => self.op_v128_splat(V128Shape::I8x16)?,
+            Operator::I16x8Splat => self.op_v128_splat(V128Shape::I16x8)?,
+            Operator::I32x4Splat => self.op_v128_splat(V128Shape::I32x4)?,
+            Operator::I64x2Splat => self.op_v128_splat(V128Shape::I64x2)?,
+            Operator::F32x4Splat => self.op_v128_splat(V128Shape::F32x4)?,
+            Operator::F64x2Splat => self.op_v128_splat(V128Shape::F64x2)?,
+            Operator::V128Bitselect => {
+                let mask = self.pop_value_released()?;
+                let loc_b = self.pop_value_released()?;
+                let loc_a = self.pop_value_released()?;
+                let ret = self.v128_push_result(WpType::V128)?;
+                self.machine.v128_bitselect(loc_a, loc_b, mask, ret)?;
+            }
+            Operator::V128Not => self.op_v128_unop(V128UnOp::Not)?,
+            Operator::I8x16Abs => self.op_v128_unop(V128UnOp::I8x16Abs)?,
+            Operator::I8x16Neg => self.op_v128_unop(V128UnOp::I8x16Neg)?,
+            Operator::I8x16Popcnt => self.op_v128_unop(V128UnOp::I8x16Popcnt)?,
+            Operator::I16x8Abs => self.op_v128_unop(V128UnOp::I16x8Abs)?,
+            Operator::I16x8Neg => self.op_v128_unop(V128UnOp::I16x8Neg)?,
+            Operator::I16x8ExtendLowI8x16S => self.op_v128_unop(V128UnOp::I16x8ExtendLowI8x16S)?,
+            Operator::I16x8ExtendHighI8x16S => {
+                self.op_v128_unop(V128UnOp::I16x8ExtendHighI8x16S)?
+            }
+            Operator::I16x8ExtendLowI8x16U => self.op_v128_unop(V128UnOp::I16x8ExtendLowI8x16U)?,
+            Operator::I16x8ExtendHighI8x16U => {
+                self.op_v128_unop(V128UnOp::I16x8ExtendHighI8x16U)?
+            }
+            Operator::I16x8ExtAddPairwiseI8x16S => {
+                self.op_v128_unop(V128UnOp::I16x8ExtAddPairwiseI8x16S)?
+            }
+            Operator::I16x8ExtAddPairwiseI8x16U => {
+                self.op_v128_unop(V128UnOp::I16x8ExtAddPairwiseI8x16U)?
+            }
+            Operator::I32x4Abs => self.op_v128_unop(V128UnOp::I32x4Abs)?,
+            Operator::I32x4Neg => self.op_v128_unop(V128UnOp::I32x4Neg)?,
+            Operator::I32x4ExtendLowI16x8S => self.op_v128_unop(V128UnOp::I32x4ExtendLowI16x8S)?,
+            Operator::I32x4ExtendHighI16x8S => {
+                self.op_v128_unop(V128UnOp::I32x4ExtendHighI16x8S)?
+            }
+            Operator::I32x4ExtendLowI16x8U => self.op_v128_unop(V128UnOp::I32x4ExtendLowI16x8U)?,
+            Operator::I32x4ExtendHighI16x8U => {
+                self.op_v128_unop(V128UnOp::I32x4ExtendHighI16x8U)?
+            }
+            Operator::I32x4ExtAddPairwiseI16x8S => {
+                self.op_v128_unop(V128UnOp::I32x4ExtAddPairwiseI16x8S)?
+            }
+            Operator::I32x4ExtAddPairwiseI16x8U => {
+                self.op_v128_unop(V128UnOp::I32x4ExtAddPairwiseI16x8U)?
+            }
+            Operator::I32x4TruncSatF32x4S => self.op_v128_unop(V128UnOp::I32x4TruncSatF32x4S)?,
+            Operator::I32x4TruncSatF32x4U => self.op_v128_unop(V128UnOp::I32x4TruncSatF32x4U)?,
+            Operator::I32x4TruncSatF64x2SZero => {
+                self.op_v128_unop(V128UnOp::I32x4TruncSatF64x2SZero)?
+            }
+            Operator::I32x4TruncSatF64x2UZero => {
+                self.op_v128_unop(V128UnOp::I32x4TruncSatF64x2UZero)?
+            }
+            Operator::I64x2Abs => self.op_v128_unop(V128UnOp::I64x2Abs)?,
+            Operator::I64x2Neg => self.op_v128_unop(V128UnOp::I64x2Neg)?,
+            Operator::I64x2ExtendLowI32x4S => self.op_v128_unop(V128UnOp::I64x2ExtendLowI32x4S)?,
+            Operator::I64x2ExtendHighI32x4S => {
+                self.op_v128_unop(V128UnOp::I64x2ExtendHighI32x4S)?
+            }
+            Operator::I64x2ExtendLowI32x4U => self.op_v128_unop(V128UnOp::I64x2ExtendLowI32x4U)?,
+            Operator::I64x2ExtendHighI32x4U => {
+                self.op_v128_unop(V128UnOp::I64x2ExtendHighI32x4U)?
+            }
+            Operator::F32x4Abs => self.op_v128_unop(V128UnOp::F32x4Abs)?,
+            Operator::F32x4Neg => self.op_v128_unop(V128UnOp::F32x4Neg)?,
+            Operator::F32x4Sqrt => self.op_v128_unop(V128UnOp::F32x4Sqrt)?,
+            Operator::F32x4Ceil => self.op_v128_unop(V128UnOp::F32x4Ceil)?,
+            Operator::F32x4Floor => self.op_v128_unop(V128UnOp::F32x4Floor)?,
+            Operator::F32x4Trunc => self.op_v128_unop(V128UnOp::F32x4Trunc)?,
+            Operator::F32x4Nearest => self.op_v128_unop(V128UnOp::F32x4Nearest)?,
+            Operator::F32x4ConvertI32x4S => self.op_v128_unop(V128UnOp::F32x4ConvertI32x4S)?,
+            Operator::F32x4ConvertI32x4U => self.op_v128_unop(V128UnOp::F32x4ConvertI32x4U)?,
+            Operator::F32x4DemoteF64x2Zero => self.op_v128_unop(V128UnOp::F32x4DemoteF64x2Zero)?,
+            Operator::F64x2Abs => self.op_v128_unop(V128UnOp::F64x2Abs)?,
+            Operator::F64x2Neg => self.op_v128_unop(V128UnOp::F64x2Neg)?,
+            Operator::F64x2Sqrt => self.op_v128_unop(V128UnOp::F64x2Sqrt)?,
+            Operator::F64x2Ceil => self.op_v128_unop(V128UnOp::F64x2Ceil)?,
+            Operator::F64x2Floor => self.op_v128_unop(V128UnOp::F64x2Floor)?,
+            Operator::F64x2Trunc => self.op_v128_unop(V128UnOp::F64x2Trunc)?,
+            Operator::F64x2Nearest => self.op_v128_unop(V128UnOp::F64x2Nearest)?,
+            Operator::F64x2ConvertLowI32x4S => {
+                self.op_v128_unop(V128UnOp::F64x2ConvertLowI32x4S)?
+            }
+            Operator::F64x2ConvertLowI32x4U => {
+                self.op_v128_unop(V128UnOp::F64x2ConvertLowI32x4U)?
+            }
+            Operator::F64x2PromoteLowF32x4 => self.op_v128_unop(V128UnOp::F64x2PromoteLowF32x4)?,
+            Operator::V128And => self.op_v128_binop(V128BinOp::And)?,
+            Operator::V128AndNot => self.op_v128_binop(V128BinOp::AndNot)?,
+            Operator::V128Or => self.op_v128_binop(V128BinOp::Or)?,
+            Operator::V128Xor => self.op_v128_binop(V128BinOp::Xor)?,
+            Operator::I8x16Swizzle => self.op_v128_binop(V128BinOp::I8x16Swizzle)?,
+            Operator::I8x16Eq => self.op_v128_binop(V128BinOp::I8x16Eq)?,
+            Operator::I8x16Ne => self.op_v128_binop(V128BinOp::I8x16Ne)?,
+            Operator::I8x16LtS => self.op_v128_binop(V128BinOp::I8x16LtS)?,
+            Operator::I8x16LtU => self.op_v128_binop(V128BinOp::I8x16LtU)?,
+            Operator::I8x16GtS => self.op_v128_binop(V128BinOp::I8x16GtS)?,
+            Operator::I8x16GtU => self.op_v128_binop(V128BinOp::I8x16GtU)?,
+            Operator::I8x16LeS => self.op_v128_binop(V128BinOp::I8x16LeS)?,
+            Operator::I8x16LeU => self.op_v128_binop(V128BinOp::I8x16LeU)?,
+            Operator::I8x16GeS => self.op_v128_binop(V128BinOp::I8x16GeS)?,
+            Operator::I8x16GeU => self.op_v128_binop(V128BinOp::I8x16GeU)?,
+            Operator::I16x8Eq => self.op_v128_binop(V128BinOp::I16x8Eq)?,
+            Operator::I16x8Ne => self.op_v128_binop(V128BinOp::I16x8Ne)?,
+            Operator::I16x8LtS => self.op_v128_binop(V128BinOp::I16x8LtS)?,
+            Operator::I16x8LtU => self.op_v128_binop(V128BinOp::I16x8LtU)?,
+            Operator::I16x8GtS => self.op_v128_binop(V128BinOp::I16x8GtS)?,
+            Operator::I16x8GtU => self.op_v128_binop(V128BinOp::I16x8GtU)?,
+            Operator::I16x8LeS => self.op_v128_binop(V128BinOp::I16x8LeS)?,
+            Operator::I16x8LeU => self.op_v128_binop(V128BinOp::I16x8LeU)?,
+            Operator::I16x8GeS => self.op_v128_binop(V128BinOp::I16x8GeS)?,
+            Operator::I16x8GeU => self.op_v128_binop(V128BinOp::I16x8GeU)?,
+            Operator::I32x4Eq => self.op_v128_binop(V128BinOp::I32x4Eq)?,
+            Operator::I32x4Ne => self.op_v128_binop(V128BinOp::I32x4Ne)?,
+            Operator::I32x4LtS => self.op_v128_binop(V128BinOp::I32x4LtS)?,
+            Operator::I32x4LtU => self.op_v128_binop(V128BinOp::I32x4LtU)?,
+            Operator::I32x4GtS => self.op_v128_binop(V128BinOp::I32x4GtS)?,
+            Operator::I32x4GtU => self.op_v128_binop(V128BinOp::I32x4GtU)?,
+            Operator::I32x4LeS => self.op_v128_binop(V128BinOp::I32x4LeS)?,
+            Operator::I32x4LeU => self.op_v128_binop(V128BinOp::I32x4LeU)?,
+            Operator::I32x4GeS => self.op_v128_binop(V128BinOp::I32x4GeS)?,
+            Operator::I32x4GeU => self.op_v128_binop(V128BinOp::I32x4GeU)?,
+            Operator::I64x2Eq => self.op_v128_binop(V128BinOp::I64x2Eq)?,
+            Operator::I64x2Ne => self.op_v128_binop(V128BinOp::I64x2Ne)?,
+            Operator::I64x2LtS => self.op_v128_binop(V128BinOp::I64x2LtS)?,
+            Operator::I64x2GtS => self.op_v128_binop(V128BinOp::I64x2GtS)?,
+            Operator::I64x2LeS => self.op_v128_binop(V128BinOp::I64x2LeS)?,
+            Operator::I64x2GeS => self.op_v128_binop(V128BinOp::I64x2GeS)?,
+            Operator::F32x4Eq => self.op_v128_binop(V128BinOp::F32x4Eq)?,
+            Operator::F32x4Ne => self.op_v128_binop(V128BinOp::F32x4Ne)?,
+            Operator::F32x4Lt => self.op_v128_binop(V128BinOp::F32x4Lt)?,
+            Operator::F32x4Gt => self.op_v128_binop(V128BinOp::F32x4Gt)?,
+            Operator::F32x4Le => self.op_v128_binop(V128BinOp::F32x4Le)?,
+            Operator::F32x4Ge => self.op_v128_binop(V128BinOp::F32x4Ge)?,
+            Operator::F64x2Eq => self.op_v128_binop(V128BinOp::F64x2Eq)?,
+            Operator::F64x2Ne => self.op_v128_binop(V128BinOp::F64x2Ne)?,
+            Operator::F64x2Lt => self.op_v128_binop(V128BinOp::F64x2Lt)?,
+            Operator::F64x2Gt => self.op_v128_binop(V128BinOp::F64x2Gt)?,
+            Operator::F64x2Le => self.op_v128_binop(V128BinOp::F64x2Le)?,
+            Operator::F64x2Ge => self.op_v128_binop(V128BinOp::F64x2Ge)?,
+            Operator::I8x16NarrowI16x8S => self.op_v128_binop(V128BinOp::I8x16NarrowI16x8S)?,
+            Operator::I8x16NarrowI16x8U => self.op_v128_binop(V128BinOp::I8x16NarrowI16x8U)?,
+            Operator::I8x16Add => self.op_v128_binop(V128BinOp::I8x16Add)?,
+            Operator::I8x16AddSatS => self.op_v128_binop(V128BinOp::I8x16AddSatS)?,
+            Operator::I8x16AddSatU => self.op_v128_binop(V128BinOp::I8x16AddSatU)?,
+            Operator::I8x16Sub => self.op_v128_binop(V128BinOp::I8x16Sub)?,
+            Operator::I8x16SubSatS => self.op_v128_binop(V128BinOp::I8x16SubSatS)?,
+            Operator::I8x16SubSatU => self.op_v128_binop(V128BinOp::I8x16SubSatU)?,
+            Operator::I8x16MinS => self.op_v128_binop(V128BinOp::I8x16MinS)?,
+            Operator::I8x16MinU => self.op_v128_binop(V128BinOp::I8x16MinU)?,
+            Operator::I8x16MaxS => self.op_v128_binop(V128BinOp::I8x16MaxS)?,
+            Operator::I8x16MaxU => self.op_v128_binop(V128BinOp::I8x16MaxU)?,
+            Operator::I8x16AvgrU => self.op_v128_binop(V128BinOp::I8x16AvgrU)?,
+            Operator::I16x8NarrowI32x4S => self.op_v128_binop(V128BinOp::I16x8NarrowI32x4S)?,
+            Operator::I16x8NarrowI32x4U => self.op_v128_binop(V128BinOp::I16x8NarrowI32x4U)?,
+            Operator::I16x8Q15MulrSatS => self.op_v128_binop(V128BinOp::I16x8Q15MulrSatS)?,
+            Operator::I16x8Add => self.op_v128_binop(V128BinOp::I16x8Add)?,
+            Operator::I16x8AddSatS => self.op_v128_binop(V128BinOp::I16x8AddSatS)?,
+            Operator::I16x8AddSatU => self.op_v128_binop(V128BinOp::I16x8AddSatU)?,
+            Operator::I16x8Sub => self.op_v128_binop(V128BinOp::I16x8Sub)?,
+            Operator::I16x8SubSatS => self.op_v128_binop(V128BinOp::I16x8SubSatS)?,
+            Operator::I16x8SubSatU => self.op_v128_binop(V128BinOp::I16x8SubSatU)?,
+            Operator::I16x8Mul => self.op_v128_binop(V128BinOp::I16x8Mul)?,
+            Operator::I16x8MinS => self.op_v128_binop(V128BinOp::I16x8MinS)?,
+            Operator::I16x8MinU => self.op_v128_binop(V128BinOp::I16x8MinU)?,
+            Operator::I16x8MaxS => self.op_v128_binop(V128BinOp::I16x8MaxS)?,
+            Operator::I16x8MaxU => self.op_v128_binop(V128BinOp::I16x8MaxU)?,
+            Operator::I16x8AvgrU => self.op_v128_binop(V128BinOp::I16x8AvgrU)?,
+            Operator::I16x8ExtMulLowI8x16S => {
+                self.op_v128_binop(V128BinOp::I16x8ExtMulLowI8x16S)?
+            }
+            Operator::I16x8ExtMulHighI8x16S => {
+                self.op_v128_binop(V128BinOp::I16x8ExtMulHighI8x16S)?
+            }
+            Operator::I16x8ExtMulLowI8x16U => {
+                self.op_v128_binop(V128BinOp::I16x8ExtMulLowI8x16U)?
+            }
+            Operator::I16x8ExtMulHighI8x16U => {
+                self.op_v128_binop(V128BinOp::I16x8ExtMulHighI8x16U)?
+            }
+            Operator::I32x4Add => self.op_v128_binop(V128BinOp::I32x4Add)?,
+            Operator::I32x4Sub => self.op_v128_binop(V128BinOp::I32x4Sub)?,
+            Operator::I32x4Mul => self.op_v128_binop(V128BinOp::I32x4Mul)?,
+            Operator::I32x4MinS => self.op_v128_binop(V128BinOp::I32x4MinS)?,
+            Operator::I32x4MinU => self.op_v128_binop(V128BinOp::I32x4MinU)?,
+            Operator::I32x4MaxS => self.op_v128_binop(V128BinOp::I32x4MaxS)?,
+            Operator::I32x4MaxU => self.op_v128_binop(V128BinOp::I32x4MaxU)?,
+            Operator::I32x4DotI16x8S => self.op_v128_binop(V128BinOp::I32x4DotI16x8S)?,
+            Operator::I32x4ExtMulLowI16x8S => {
+                self.op_v128_binop(V128BinOp::I32x4ExtMulLowI16x8S)?
+            }
+            Operator::I32x4ExtMulHighI16x8S => {
+                self.op_v128_binop(V128BinOp::I32x4ExtMulHighI16x8S)?
+            }
+            Operator::I32x4ExtMulLowI16x8U => {
+                self.op_v128_binop(V128BinOp::I32x4ExtMulLowI16x8U)?
+            }
+            Operator::I32x4ExtMulHighI16x8U => {
+                self.op_v128_binop(V128BinOp::I32x4ExtMulHighI16x8U)?
+            }
+            Operator::I64x2Add => self.op_v128_binop(V128BinOp::I64x2Add)?,
+            Operator::I64x2Sub => self.op_v128_binop(V128BinOp::I64x2Sub)?,
+            Operator::I64x2Mul => self.op_v128_binop(V128BinOp::I64x2Mul)?,
+            Operator::I64x2ExtMulLowI32x4S => {
+                self.op_v128_binop(V128BinOp::I64x2ExtMulLowI32x4S)?
+            }
+            Operator::I64x2ExtMulHighI32x4S => {
+                self.op_v128_binop(V128BinOp::I64x2ExtMulHighI32x4S)?
+            }
+            Operator::I64x2ExtMulLowI32x4U => {
+                self.op_v128_binop(V128BinOp::I64x2ExtMulLowI32x4U)?
+            }
+            Operator::I64x2ExtMulHighI32x4U => {
+                self.op_v128_binop(V128BinOp::I64x2ExtMulHighI32x4U)?
+            }
+            Operator::F32x4Add => self.op_v128_binop(V128BinOp::F32x4Add)?,
+            Operator::F32x4Sub => self.op_v128_binop(V128BinOp::F32x4Sub)?,
+            Operator::F32x4Mul => self.op_v128_binop(V128BinOp::F32x4Mul)?,
+            Operator::F32x4Div => self.op_v128_binop(V128BinOp::F32x4Div)?,
+            Operator::F32x4Min => self.op_v128_binop(V128BinOp::F32x4Min)?,
+            Operator::F32x4Max => self.op_v128_binop(V128BinOp::F32x4Max)?,
+            Operator::F32x4PMin => self.op_v128_binop(V128BinOp::F32x4PMin)?,
+            Operator::F32x4PMax => self.op_v128_binop(V128BinOp::F32x4PMax)?,
+            Operator::F64x2Add => self.op_v128_binop(V128BinOp::F64x2Add)?,
+            Operator::F64x2Sub => self.op_v128_binop(V128BinOp::F64x2Sub)?,
+            Operator::F64x2Mul => self.op_v128_binop(V128BinOp::F64x2Mul)?,
+            Operator::F64x2Div => self.op_v128_binop(V128BinOp::F64x2Div)?,
+            Operator::F64x2Min => self.op_v128_binop(V128BinOp::F64x2Min)?,
+            Operator::F64x2Max => self.op_v128_binop(V128BinOp::F64x2Max)?,
+            Operator::F64x2PMin => self.op_v128_binop(V128BinOp::F64x2PMin)?,
+            Operator::F64x2PMax => self.op_v128_binop(V128BinOp::F64x2PMax)?,
+            Operator::I8x16Shl => self.op_v128_shift(V128ShiftOp::I8x16Shl)?,
+            Operator::I8x16ShrS => self.op_v128_shift(V128ShiftOp::I8x16ShrS)?,
+            Operator::I8x16ShrU => self.op_v128_shift(V128ShiftOp::I8x16ShrU)?,
+            Operator::I16x8Shl => self.op_v128_shift(V128ShiftOp::I16x8Shl)?,
+            Operator::I16x8ShrS => self.op_v128_shift(V128ShiftOp::I16x8ShrS)?,
+            Operator::I16x8ShrU => self.op_v128_shift(V128ShiftOp::I16x8ShrU)?,
+            Operator::I32x4Shl => self.op_v128_shift(V128ShiftOp::I32x4Shl)?,
+            Operator::I32x4ShrS => self.op_v128_shift(V128ShiftOp::I32x4ShrS)?,
+            Operator::I32x4ShrU => self.op_v128_shift(V128ShiftOp::I32x4ShrU)?,
+            Operator::I64x2Shl => self.op_v128_shift(V128ShiftOp::I64x2Shl)?,
+            Operator::I64x2ShrS => self.op_v128_shift(V128ShiftOp::I64x2ShrS)?,
+            Operator::I64x2ShrU => self.op_v128_shift(V128ShiftOp::I64x2ShrU)?,
+            Operator::V128AnyTrue => self.op_v128_test(V128TestOp::AnyTrue)?,
+            Operator::I8x16AllTrue => self.op_v128_test(V128TestOp::I8x16AllTrue)?,
+            Operator::I16x8AllTrue => self.op_v128_test(V128TestOp::I16x8AllTrue)?,
+            Operator::I32x4AllTrue => self.op_v128_test(V128TestOp::I32x4AllTrue)?,
+            Operator::I64x2AllTrue => self.op_v128_test(V128TestOp::I64x2AllTrue)?,
+            Operator::I8x16Bitmask => self.op_v128_test(V128TestOp::I8x16Bitmask)?,
+            Operator::I16x8Bitmask => self.op_v128_test(V128TestOp::I16x8Bitmask)?,
+            Operator::I32x4Bitmask => self.op_v128_test(V128TestOp::I32x4Bitmask)?,
+            Operator::I64x2Bitmask => self.op_v128_test(V128TestOp::I64x2Bitmask)?,
             _ => {
                 return Err(CompileError::Codegen(format!(
                     "not yet implemented: {:?}",
@@ -6632,6 +7629,24 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         mut self,
         data: &FunctionBodyData,
     ) -> Result<(CompiledFunction, Option<UnwindFrame>), CompileError> {
+        // Generate the frame teardown of tail calls, followed by the jump to their callee.
+        for (label, target) in std::mem::take(&mut self.tail_calls) {
+            self.machine.emit_label(label)?;
+            self.finalize_locals(self.calling_convention)?;
+            self.machine.emit_function_epilog()?;
+            match target {
+                Some(reloc_target) => {
+                    let mut relocations = self
+                        .machine
+                        .emit_jmp_with_reloc(self.calling_convention, reloc_target)?;
+                    self.relocations.append(&mut relocations);
+                }
+                None => self
+                    .machine
+                    .emit_jmp_register(self.machine.get_gpr_for_tail_call())?,
+            }
+        }
+
         // Generate actual code for special labels.
         self.machine
             .emit_label(self.special_labels.integer_division_by_zero)?;
@@ -116,6 +116,307 @@ pub enum Size {
     S64,
 }
 
+/// Shape of the lanes of a `v128` value.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum V128Shape {
+    I8x16,
+    I16x8,
+    I32x4,
+    I64x2,
+    F32x4,
+    F64x2,
+}
+
+impl V128Shape {
+    /// Size of a lane, in bytes.
+    pub fn lane_bytes(self) -> usize {
+        match self {
+            V128Shape::I8x16 => 1,
+            V128Shape::I16x8 => 2,
+            V128Shape::I32x4 | V128Shape::F32x4 => 4,
+            V128Shape::I64x2 | V128Shape::F64x2 => 8,
+        }
+    }
+
+    /// Size of a lane, as an operand size.
+    pub fn lane_size(self) -> Size {
+        match self.lane_bytes() {
+            1 => Size::S8,
+            2 => Size::S16,
+            4 => Size::S32,
+            _ => Size::S64,
+        }
+    }
+}
+
+/// SIMD operators taking one `v128` operand and producing a `v128`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum V128UnOp {
+    Not,
+    I8x16Abs,
+    I8x16Neg,
+    I8x16Popcnt,
+    I16x8Abs,
+    I16x8Neg,
+    I16x8ExtendLowI8x16S,
+    I16x8ExtendHighI8x16S,
+    I16x8ExtendLowI8x16U,
+    I16x8ExtendHighI8x16U,
+    I16x8ExtAddPairwiseI8x16S,
+    I16x8ExtAddPairwiseI8x16U,
+    I32x4Abs,
+    I32x4Neg,
+    I32x4ExtendLowI16x8S,
+    I32x4ExtendHighI16x8S,
+    I32x4ExtendLowI16x8U,
+    I32x4ExtendHighI16x8U,
+    I32x4ExtAddPairwiseI16x8S,
+    I32x4ExtAddPairwiseI16x8U,
+    I32x4TruncSatF32x4S,
+    I32x4TruncSatF32x4U,
+    I32x4TruncSatF64x2SZero,
+    I32x4TruncSatF64x2UZero,
+    I64x2Abs,
+    I64x2Neg,
+    I64x2ExtendLowI32x4S,
+    I64x2ExtendHighI32x4S,
+    I64x2ExtendLowI32x4U,
+    I64x2ExtendHighI32x4U,
+    F32x4Abs,
+    F32x4Neg,
+    F32x4Sqrt,
+    F32x4Ceil,
+    F32x4Floor,
+    F32x4Trunc,
+    F32x4Nearest,
+    F32x4ConvertI32x4S,
+    F32x4ConvertI32x4U,
+    F32x4DemoteF64x2Zero,
+    F64x2Abs,
+    F64x2Neg,
+    F64x2Sqrt,
+    F64x2Ceil,
+    F64x2Floor,
+    F64x2Trunc,
+    F64x2Nearest,
+    F64x2ConvertLowI32x4S,
+    F64x2ConvertLowI32x4U,
+    F64x2PromoteLowF32x4,
+}
+
+/// SIMD operators taking two `v128` operands and producing a `v128`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum V128BinOp {
+    And,
+    AndNot,
+    Or,
+    Xor,
+    I8x16Swizzle,
+    I8x16Eq,
+    I8x16Ne,
+    I8x16LtS,
+    I8x16LtU,
+    I8x16GtS,
+    I8x16GtU,
+    I8x16LeS,
+    I8x16LeU,
+    I8x16GeS,
+    I8x16GeU,
+    I16x8Eq,
+    I16x8Ne,
+    I16x8LtS,
+    I16x8LtU,
+    I16x8GtS,
+    I16x8GtU,
+    I16x8LeS,
+    I16x8LeU,
+    I16x8GeS,
+    I16x8GeU,
+    I32x4Eq,
+    I32x4Ne,
+    I32x4LtS,
+    I32x4LtU,
+    I32x4GtS,
+    I32x4GtU,
+    I32x4LeS,
+    I32x4LeU,
+    I32x4GeS,
+    I32x4GeU,
+    I64x2Eq,
+    I64x2Ne,
+    I64x2LtS,
+    I64x2GtS,
+    I64x2LeS,
+    I64x2GeS,
+    F32x4Eq,
+    F32x4Ne,
+    F32x4Lt,
+    F32x4Gt,
+    F32x4Le,
+    F32x4Ge,
+    F64x2Eq,
+    F64x2Ne,
+    F64x2Lt,
+    F64x2Gt,
+    F64x2Le,
+    F64x2Ge,
+    I8x16NarrowI16x8S,
+    I8x16NarrowI16x8U,
+    I8x16Add,
+    I8x16AddSatS,
+    I8x16AddSatU,
+    I8x16Sub,
+    I8x16SubSatS,
+    I8x16SubSatU,
+    I8x16MinS,
+    I8x16MinU,
+    I8x16MaxS,
+    I8x16MaxU,
+    I8x16AvgrU,
+    I16x8NarrowI32x4S,
+    I16x8NarrowI32x4U,
+    I16x8Q15MulrSatS,
+    I16x8Add,
+    I16x8AddSatS,
+    I16x8AddSatU,
+    I16x8Sub,
+    I16x8SubSatS,
+    I16x8SubSatU,
+    I16x8Mul,
+    I16x8MinS,
+    I16x8MinU,
+    I16x8MaxS,
+    I16x8MaxU,
+    I16x8AvgrU,
+    I16x8ExtMulLowI8x16S,
+    I16x8ExtMulHighI8x16S,
+    I16x8ExtMulLowI8x16U,
+    I16x8ExtMulHighI8x16U,
+    I32x4Add,
+    I32x4Sub,
+    I32x4Mul,
+    I32x4MinS,
+    I32x4MinU,
+    I32x4MaxS,
+    I32x4MaxU,
+    I32x4DotI16x8S,
+    I32x4ExtMulLowI16x8S,
+    I32x4ExtMulHighI16x8S,
+    I32x4ExtMulLowI16x8U,
+    I32x4ExtMulHighI16x8U,
+    I64x2Add,
+    I64x2Sub,
+    I64x2Mul,
+    I64x2ExtMulLowI32x4S,
+    I64x2ExtMulHighI32x4S,
+    I64x2ExtMulLowI32x4U,
+    I64x2ExtMulHighI32x4U,
+    F32x4Add,
+    F32x4Sub,
+    F32x4Mul,
+    F32x4Div,
+    F32x4Min,
+    F32x4Max,
+    F32x4PMin,
+    F32x4PMax,
+    F64x2Add,
+    F64x2Sub,
+    F64x2Mul,
+    F64x2Div,
+    F64x2Min,
+    F64x2Max,
+    F64x2PMin,
+    F64x2PMax,
+}
+
+/// SIMD shifts of a `v128` by a scalar `i32` amount.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum V128ShiftOp {
+    I8x16Shl,
+    I8x16ShrS,
+    I8x16ShrU,
+    I16x8Shl,
+    I16x8ShrS,
+    I16x8ShrU,
+    I32x4Shl,
+    I32x4ShrS,
+    I32x4ShrU,
+    I64x2Shl,
+    I64x2ShrS,
+    I64x2ShrU,
+}
+
+impl V128ShiftOp {
+    /// Shape of the lanes being shifted.
+    pub fn shape(self) -> V128Shape {
+        match self {
+            V128ShiftOp::I8x16Shl | V128ShiftOp::I8x16ShrS | V128ShiftOp::I8x16ShrU => {
+                V128Shape::I8x16
+            }
+            V128ShiftOp::I16x8Shl | V128ShiftOp::I16x8ShrS | V128ShiftOp::I16x8ShrU => {
+                V128Shape::I16x8
+            }
+            V128ShiftOp::I32x4Shl | V128ShiftOp::I32x4ShrS | V128ShiftOp::I32x4ShrU => {
+                V128Shape::I32x4
+            }
+            V128ShiftOp::I64x2Shl | V128ShiftOp::I64x2ShrS | V128ShiftOp::I64x2ShrU => {
+                V128Shape::I64x2
+            }
+        }
+    }
+}
+
+/// SIMD operators reducing a `v128` to an `i32`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum V128TestOp {
+    AnyTrue,
+    I8x16AllTrue,
+    I16x8AllTrue,
+    I32x4AllTrue,
+    I64x2AllTrue,
+    I8x16Bitmask,
+    I16x8Bitmask,
+    I32x4Bitmask,
+    I64x2Bitmask,
+}
+
+/// The different ways to load a `v128` from memory.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum V128LoadKind {
+    /// Load 128 bits.
+    Full,
+    /// Load 8 lanes of 8 bits and sign extend them.
+    Extend8x8S,
+    /// Load 8 lanes of 8 bits and zero extend them.
+    Extend8x8U,
+    /// Load 4 lanes of 16 bits and sign extend them.
+    Extend16x4S,
+    /// Load 4 lanes of 16 bits and zero extend them.
+    Extend16x4U,
+    /// Load 2 lanes of 32 bits and sign extend them.
+    Extend32x2S,
+    /// Load 2 lanes of 32 bits and zero extend them.
+    Extend32x2U,
+    /// Load a single lane and copy it to all lanes.
+    Splat(V128Shape),
+    /// Load 32 bits in the first lane and zero the rest.
+    Zero32,
+    /// Load 64 bits in the first lane and zero the rest.
+    Zero64,
+}
+
+impl V128LoadKind {
+    /// Number of bytes read from memory.
+    pub fn access_size(self) -> usize {
+        match self {
+            V128LoadKind::Full => 16,
+            V128LoadKind::Splat(shape) => shape.lane_bytes(),
+            V128LoadKind::Zero32 => 4,
+            _ => 8,
+        }
+    }
+}
+
 /// A kind of suspend offset.
 #[allow(dead_code)]
 #[derive(Clone, Copy, Debug)]
@@ -8,7 +8,8 @@ use crate::config::Singlepass;
 use crate::dwarf::WriterRelocate;
 use crate::machine::Machine;
 use crate::machine::{
-    gen_import_call_trampoline, gen_std_dynamic_import_trampoline, gen_std_trampoline,
+    gen_function_entry_trampoline, gen_import_call_trampoline, gen_std_dynamic_import_trampoline,
+    gen_std_trampoline, max_stack_params_size,
 };
 use crate::machine_arm64::MachineARM64;
 use crate::machine_x64::MachineX86_64;
@@ -137,6 +138,14 @@ impl Compiler for SinglepassCompiler {
             .collect::<Result<Vec<_>, _>>()?
             .into_iter()
             .collect();
+        // A tail call passes its stack parameters in the area the caller got its own
+        // from, so with tail calls the bodies are entered with an area large enough for
+        // any function of the module, set up by an entry trampoline.
+        let stack_params_area = if compile_info.features.tail_call {
+            max_stack_params_size(module.signatures.values(), target, calling_convention)?
+        } else {
+            0
+        };
         let (functions, fdes): (Vec<CompiledFunction>, Vec<_>) = function_body_inputs
             .iter()
             .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>()
@@ -173,6 +182,7 @@ impl Compiler for SinglepassCompiler {
                             &locals,
                             machine,
                             calling_convention,
+                            stack_params_area,
                         )?;
                         while generator.has_control_frames() {
                             generator.set_srcloc(reader.original_position() as u32);
@@ -194,6 +204,7 @@ impl Compiler for SinglepassCompiler {
                             &locals,
                             machine,
                             calling_convention,
+                            stack_params_area,
                         )?;
                         while generator.has_control_frames() {
                             generator.set_srcloc(reader.original_position() as u32);
@@ -236,6 +247,26 @@ impl Compiler for SinglepassCompiler {
             .into_iter()
             .collect::<PrimaryMap<FunctionIndex, FunctionBody>>();
 
+        let function_entry_trampolines = if stack_params_area > 0 {
+            function_body_inputs
+                .keys()
+                .map(|local_index| {
+                    let func_type =
+                        &module.signatures[module.functions[module.func_index(local_index)]];
+                    gen_function_entry_trampoline(
+                        local_index,
+                        func_type,
+                        stack_params_area,
+                        target,
+                        calling_convention,
+                    )
+                    .map(|section| custom_sections.push(section))
+                })
+                .collect::<Result<PrimaryMap<LocalFunctionIndex, SectionIndex>, CompileError>>()?
+        } else {
+            PrimaryMap::new()
+        };
+
         #[cfg(feature = "unwind")]
         let dwarf = if let Some((mut dwarf_frametable, cie_id)) = dwarf_frametable {
             for fde in fdes.into_iter().flatten() {
@@ -260,6 +291,7 @@ impl Compiler for SinglepassCompiler {
             custom_sections,
             function_call_trampolines,
             dynamic_function_trampolines,
+            function_entry_trampolines,
             debug: dwarf,
         })
     }
@@ -12,6 +12,7 @@ use wasmer_types::Features;
 #[derive(Debug, Clone)]
 pub struct Singlepass {
     pub(crate) enable_nan_canonicalization: bool,
+    pub(crate) enable_epoch_interruption: bool,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
 }
@@ -22,6 +23,7 @@ impl Singlepass {
     pub fn new() -> Self {
         Self {
             enable_nan_canonicalization: true,
+            enable_epoch_interruption: false,
             middlewares: vec![],
         }
     }
@@ -30,6 +32,15 @@ impl Singlepass {
         self.enable_nan_canonicalization = enable;
         self
     }
+
+    /// Enable epoch-based interruption.
+    ///
+    /// Compiled code checks the epoch deadline of its store at every
+    /// function entry and loop header.
+    pub fn epoch_interruption(&mut self, enable: bool) -> &mut Self {
+        self.enable_epoch_interruption = enable;
+        self
+    }
 }
 
 impl CompilerConfig for Singlepass {
@@ -38,6 +49,10 @@ impl CompilerConfig for Singlepass {
         // PIC code.
     }
 
+    fn epoch_interruption(&mut self, enable: bool) {
+        self.enable_epoch_interruption = enable;
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(SinglepassCompiler::new(*self))
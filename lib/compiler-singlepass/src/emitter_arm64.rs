@@ -3,7 +3,11 @@ pub use crate::{
     location::{Multiplier, Reg},
     machine::{Label, Offset},
 };
-use crate::{codegen_error, common_decl::Size, location::Location as AbstractLocation};
+use crate::{
+    codegen_error,
+    common_decl::{Size, V128Shape},
+    location::Location as AbstractLocation,
+};
 use dynasm::dynasm;
 pub use dynasmrt::aarch64::{encode_logical_immediate_32bit, encode_logical_immediate_64bit};
 use dynasmrt::{
@@ -86,6 +90,138 @@ pub enum GPROrMemory {
     Memory(GPR, i32),
 }
 
+/// NEON instructions with two 128 bits vector operands, all using the same lane arrangement.
+#[derive(Copy, Clone, Debug)]
+pub enum NeonOp {
+    Add,
+    Sub,
+    Mul,
+    Sqadd,
+    Uqadd,
+    Sqsub,
+    Uqsub,
+    Smin,
+    Umin,
+    Smax,
+    Umax,
+    Urhadd,
+    Sqrdmulh,
+    Addp,
+    Zip1,
+    Cmeq,
+    Cmgt,
+    Cmge,
+    Cmhi,
+    Cmhs,
+    Sshl,
+    Ushl,
+    Fadd,
+    Fsub,
+    Fmul,
+    Fdiv,
+    Fmin,
+    Fmax,
+    Fcmeq,
+    Fcmgt,
+    Fcmge,
+    /// Bitwise, the arrangement is ignored.
+    And,
+    /// Bitwise, the arrangement is ignored.
+    Bic,
+    /// Bitwise, the arrangement is ignored.
+    Orr,
+    /// Bitwise, the arrangement is ignored.
+    Eor,
+    /// Bitwise, the arrangement is ignored.
+    Bsl,
+    /// Byte table lookup in a single register, the arrangement is ignored.
+    Tbl,
+}
+
+/// NEON instructions with one 128 bits vector operand, using the same lane arrangement
+/// for the source and the destination.
+#[derive(Copy, Clone, Debug)]
+pub enum NeonUnOp {
+    /// Bitwise, the arrangement is ignored.
+    Not,
+    Cnt,
+    Abs,
+    Neg,
+    Fabs,
+    Fneg,
+    Fsqrt,
+    Frintp,
+    Frintm,
+    Frintz,
+    Frintn,
+    Scvtf,
+    Ucvtf,
+    Fcvtzs,
+    Fcvtzu,
+}
+
+/// NEON instructions producing lanes twice as wide as the lanes of their operand.
+/// The lane arrangement given to the emitter is the one of the operand.
+#[derive(Copy, Clone, Debug)]
+pub enum NeonWidenOp {
+    /// Sign extends the low half.
+    Sxtl,
+    /// Sign extends the high half.
+    Sxtl2,
+    /// Zero extends the low half.
+    Uxtl,
+    /// Zero extends the high half.
+    Uxtl2,
+    Saddlp,
+    Uaddlp,
+    /// Converts the low half from single to double precision.
+    Fcvtl,
+}
+
+/// NEON instructions producing lanes half as wide as the lanes of their operand.
+/// The lane arrangement given to the emitter is the one of the operand.
+/// The non-`2` variants write the low half of the destination and zero its high half,
+/// the `2` variants write the high half and keep the low half.
+#[derive(Copy, Clone, Debug)]
+pub enum NeonNarrowOp {
+    Sqxtn,
+    Sqxtn2,
+    Sqxtun,
+    Sqxtun2,
+    Uqxtn,
+    /// Converts from double to single precision.
+    Fcvtn,
+}
+
+/// NEON widening multiplications. The lane arrangement given to the emitter is the one
+/// of the operands.
+#[derive(Copy, Clone, Debug)]
+pub enum NeonLongOp {
+    /// Multiplies the low halves, signed.
+    Smull,
+    /// Multiplies the high halves, signed.
+    Smull2,
+    /// Multiplies the low halves, unsigned.
+    Umull,
+    /// Multiplies the high halves, unsigned.
+    Umull2,
+}
+
+/// NEON shifts of all the lanes by an immediate.
+#[derive(Copy, Clone, Debug)]
+pub enum NeonShiftOp {
+    Ushr,
+}
+
+/// NEON reductions of all the lanes to a scalar in the lowest lane of the destination.
+#[derive(Copy, Clone, Debug)]
+pub enum NeonReduceOp {
+    Umaxv,
+    Uminv,
+    /// Sum of the lanes (`ADDV`, or `ADDP` for 64 bits lanes).
+    Addv,
+}
+
 #[allow(unused)]
 pub trait EmitterARM64 {
     fn get_label(&mut self) -> Label;
@@ -484,6 +620,103 @@ pub trait EmitterARM64 {
     ) -> Result<(), CompileError>;
     fn emit_cnt(&mut self, src: NEON, dst: NEON) -> Result<(), CompileError>;
     fn emit_addv(&mut self, src: NEON, dst: NEON) -> Result<(), CompileError>;
+
+    /// Emits `dst = src1 op src2` on full 128 bits vectors.
+    fn emit_neon(
+        &mut self,
+        op: NeonOp,
+        shape: V128Shape,
+        src1: NEON,
+        src2: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    fn emit_neon_unop(
+        &mut self,
+        op: NeonUnOp,
+        shape: V128Shape,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    fn emit_neon_widen(
+        &mut self,
+        op: NeonWidenOp,
+        shape: V128Shape,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    fn emit_neon_narrow(
+        &mut self,
+        op: NeonNarrowOp,
+        shape: V128Shape,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    fn emit_neon_long(
+        &mut self,
+        op: NeonLongOp,
+        shape: V128Shape,
+        src1: NEON,
+        src2: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    fn emit_neon_shift_imm(
+        &mut self,
+        op: NeonShiftOp,
+        shape: V128Shape,
+        imm: u32,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    fn emit_neon_reduce(
+        &mut self,
+        op: NeonReduceOp,
+        shape: V128Shape,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    /// Emits `dst = src1:src2 >> (imm * 8)`, extracting a vector from a pair of vectors.
+    fn emit_neon_ext(
+        &mut self,
+        src1: NEON,
+        src2: NEON,
+        imm: u32,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    /// Copies a full 128 bits vector.
+    fn emit_neon_mov(&mut self, src: NEON, dst: NEON) -> Result<(), CompileError>;
+    /// Sets all the bits of `dst` to zero, or to one when `ones` is set.
+    fn emit_neon_movi(&mut self, ones: bool, dst: NEON) -> Result<(), CompileError>;
+    /// Copies a general purpose register to all the lanes of `dst`.
+    fn emit_neon_dup(&mut self, shape: V128Shape, src: GPR, dst: NEON) -> Result<(), CompileError>;
+    /// Copies a general purpose register to a lane of `dst`.
+    fn emit_neon_ins(
+        &mut self,
+        shape: V128Shape,
+        src: GPR,
+        lane: u32,
+        dst: NEON,
+    ) -> Result<(), CompileError>;
+    /// Copies a lane of `src` to a general purpose register, zero extending it.
+    fn emit_neon_umov(
+        &mut self,
+        shape: V128Shape,
+        src: NEON,
+        lane: u32,
+        dst: GPR,
+    ) -> Result<(), CompileError>;
+    /// Copies a lane of `src` to a 32 bits general purpose register, sign extending it.
+    fn emit_neon_smov(
+        &mut self,
+        shape: V128Shape,
+        src: NEON,
+        lane: u32,
+        dst: GPR,
+    ) -> Result<(), CompileError>;
+    /// Loads a lane from `[addr]` and copies it to all the lanes of `dst`.
+    fn emit_ld1r(&mut self, shape: V128Shape, addr: GPR, dst: NEON) -> Result<(), CompileError>;
+    fn emit_ldur_q(&mut self, dst: NEON, addr: GPR, offset: i32) -> Result<(), CompileError>;
+    fn emit_stur_q(&mut self, src: NEON, addr: GPR, offset: i32) -> Result<(), CompileError>;
+
     fn emit_read_fpcr(&mut self, reg: GPR) -> Result<(), CompileError>;
     fn emit_write_fpcr(&mut self, reg: GPR) -> Result<(), CompileError>;
     fn emit_read_fpsr(&mut self, reg: GPR) -> Result<(), CompileError>;
@@ -505,6 +738,81 @@ pub trait EmitterARM64 {
     }
 }
 
+/// Emits a NEON instruction with three vector operands, picking the lane arrangements
+/// (destination, first source, second source) from `$shape`.
+macro_rules! neon3 {
+    ($a:expr, $name:ident, $shape:expr, $d:expr, $n:expr, $m:expr, bh) => {
+        neon3!($a, $name, $shape, $d, $n, $m, [
+            V128Shape::I8x16 => B16, B16, B16;
+            V128Shape::I16x8 => H8, H8, H8
+        ])
+    };
+    ($a:expr, $name:ident, $shape:expr, $d:expr, $n:expr, $m:expr, bhs) => {
+        neon3!($a, $name, $shape, $d, $n, $m, [
+            V128Shape::I8x16 => B16, B16, B16;
+            V128Shape::I16x8 => H8, H8, H8;
+            V128Shape::I32x4 | V128Shape::F32x4 => S4, S4, S4
+        ])
+    };
+    ($a:expr, $name:ident, $shape:expr, $d:expr, $n:expr, $m:expr, bhsd) => {
+        neon3!($a, $name, $shape, $d, $n, $m, [
+            V128Shape::I8x16 => B16, B16, B16;
+            V128Shape::I16x8 => H8, H8, H8;
+            V128Shape::I32x4 | V128Shape::F32x4 => S4, S4, S4;
+            V128Shape::I64x2 | V128Shape::F64x2 => D2, D2, D2
+        ])
+    };
+    ($a:expr, $name:ident, $shape:expr, $d:expr, $n:expr, $m:expr, sd) => {
+        neon3!($a, $name, $shape, $d, $n, $m, [
+            V128Shape::I32x4 | V128Shape::F32x4 => S4, S4, S4;
+            V128Shape::I64x2 | V128Shape::F64x2 => D2, D2, D2
+        ])
+    };
+    ($a:expr, $name:ident, $shape:expr, $d:expr, $n:expr, $m:expr,
+     [$($sh:pat => $da:ident, $na:ident, $ma:ident);*]) => {
+        match $shape {
+            $($sh => dynasm!($a ; $name V($d).$da, V($n).$na, V($m).$ma),)*
+            #[allow(unreachable_patterns)]
+            _ => codegen_error!(
+                "singlepass can't emit {} {:?}",
+                stringify!($name).to_uppercase(),
+                $shape
+            ),
+        }
+    };
+}
+
+/// Emits a NEON instruction with two vector operands, picking the lane arrangements
+/// (destination, source) from `$shape`.
+macro_rules! neon2 {
+    ($a:expr, $name:ident, $shape:expr, $d:expr, $n:expr, bhsd) => {
+        neon2!($a, $name, $shape, $d, $n, [
+            V128Shape::I8x16 => B16, B16;
+            V128Shape::I16x8 => H8, H8;
+            V128Shape::I32x4 | V128Shape::F32x4 => S4, S4;
+            V128Shape::I64x2 | V128Shape::F64x2 => D2, D2
+        ])
+    };
+    ($a:expr, $name:ident, $shape:expr, $d:expr, $n:expr, sd) => {
+        neon2!($a, $name, $shape, $d, $n, [
+            V128Shape::I32x4 | V128Shape::F32x4 => S4, S4;
+            V128Shape::I64x2 | V128Shape::F64x2 => D2, D2
+        ])
+    };
+    ($a:expr, $name:ident, $shape:expr, $d:expr, $n:expr,
+     [$($sh:pat => $da:ident, $na:ident);*]) => {
+        match $shape {
+            $($sh => dynasm!($a ; $name V($d).$da, V($n).$na),)*
+            #[allow(unreachable_patterns)]
+            _ => codegen_error!(
+                "singlepass can't emit {} {:?}",
+                stringify!($name).to_uppercase(),
+                $shape
+            ),
+        }
+    };
+}
+
 impl EmitterARM64 for Assembler {
     fn get_label(&mut self) -> DynamicLabel {
         self.new_dynamic_label()
@@ -3276,6 +3584,365 @@ impl EmitterARM64 for Assembler {
         Ok(())
     }
 
+    fn emit_neon(
+        &mut self,
+        op: NeonOp,
+        shape: V128Shape,
+        src1: NEON,
+        src2: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src1.into_index() as u32;
+        let m = src2.into_index() as u32;
+        match op {
+            NeonOp::Add => neon3!(self, add, shape, d, n, m, bhsd),
+            NeonOp::Sub => neon3!(self, sub, shape, d, n, m, bhsd),
+            NeonOp::Mul => neon3!(self, mul, shape, d, n, m, bhs),
+            NeonOp::Sqadd => neon3!(self, sqadd, shape, d, n, m, bhsd),
+            NeonOp::Uqadd => neon3!(self, uqadd, shape, d, n, m, bhsd),
+            NeonOp::Sqsub => neon3!(self, sqsub, shape, d, n, m, bhsd),
+            NeonOp::Uqsub => neon3!(self, uqsub, shape, d, n, m, bhsd),
+            NeonOp::Smin => neon3!(self, smin, shape, d, n, m, bhs),
+            NeonOp::Umin => neon3!(self, umin, shape, d, n, m, bhs),
+            NeonOp::Smax => neon3!(self, smax, shape, d, n, m, bhs),
+            NeonOp::Umax => neon3!(self, umax, shape, d, n, m, bhs),
+            NeonOp::Urhadd => neon3!(self, urhadd, shape, d, n, m, bhs),
+            NeonOp::Sqrdmulh => neon3!(self, sqrdmulh, shape, d, n, m, [
+                V128Shape::I16x8 => H8, H8, H8;
+                V128Shape::I32x4 => S4, S4, S4
+            ]),
+            NeonOp::Addp => neon3!(self, addp, shape, d, n, m, bhsd),
+            NeonOp::Zip1 => neon3!(self, zip1, shape, d, n, m, bhsd),
+            NeonOp::Cmeq => neon3!(self, cmeq, shape, d, n, m, bhsd),
+            NeonOp::Cmgt => neon3!(self, cmgt, shape, d, n, m, bhsd),
+            NeonOp::Cmge => neon3!(self, cmge, shape, d, n, m, bhsd),
+            NeonOp::Cmhi => neon3!(self, cmhi, shape, d, n, m, bhsd),
+            NeonOp::Cmhs => neon3!(self, cmhs, shape, d, n, m, bhsd),
+            NeonOp::Sshl => neon3!(self, sshl, shape, d, n, m, bhsd),
+            NeonOp::Ushl => neon3!(self, ushl, shape, d, n, m, bhsd),
+            NeonOp::Fadd => neon3!(self, fadd, shape, d, n, m, sd),
+            NeonOp::Fsub => neon3!(self, fsub, shape, d, n, m, sd),
+            NeonOp::Fmul => neon3!(self, fmul, shape, d, n, m, sd),
+            NeonOp::Fdiv => neon3!(self, fdiv, shape, d, n, m, sd),
+            NeonOp::Fmin => neon3!(self, fmin, shape, d, n, m, sd),
+            NeonOp::Fmax => neon3!(self, fmax, shape, d, n, m, sd),
+            NeonOp::Fcmeq => neon3!(self, fcmeq, shape, d, n, m, sd),
+            NeonOp::Fcmgt => neon3!(self, fcmgt, shape, d, n, m, sd),
+            NeonOp::Fcmge => neon3!(self, fcmge, shape, d, n, m, sd),
+            NeonOp::And => dynasm!(self ; and V(d).B16, V(n).B16, V(m).B16),
+            NeonOp::Bic => dynasm!(self ; bic V(d).B16, V(n).B16, V(m).B16),
+            NeonOp::Orr => dynasm!(self ; orr V(d).B16, V(n).B16, V(m).B16),
+            NeonOp::Eor => dynasm!(self ; eor V(d).B16, V(n).B16, V(m).B16),
+            NeonOp::Bsl => dynasm!(self ; bsl V(d).B16, V(n).B16, V(m).B16),
+            NeonOp::Tbl => dynasm!(self ; tbl V(d).B16, {V(n).B16 * 1}, V(m).B16),
+        }
+        Ok(())
+    }
+    fn emit_neon_unop(
+        &mut self,
+        op: NeonUnOp,
+        shape: V128Shape,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match op {
+            NeonUnOp::Not => dynasm!(self ; mvn V(d).B16, V(n).B16),
+            NeonUnOp::Cnt => dynasm!(self ; cnt V(d).B16, V(n).B16),
+            NeonUnOp::Abs => neon2!(self, abs, shape, d, n, bhsd),
+            NeonUnOp::Neg => neon2!(self, neg, shape, d, n, bhsd),
+            NeonUnOp::Fabs => neon2!(self, fabs, shape, d, n, sd),
+            NeonUnOp::Fneg => neon2!(self, fneg, shape, d, n, sd),
+            NeonUnOp::Fsqrt => neon2!(self, fsqrt, shape, d, n, sd),
+            NeonUnOp::Frintp => neon2!(self, frintp, shape, d, n, sd),
+            NeonUnOp::Frintm => neon2!(self, frintm, shape, d, n, sd),
+            NeonUnOp::Frintz => neon2!(self, frintz, shape, d, n, sd),
+            NeonUnOp::Frintn => neon2!(self, frintn, shape, d, n, sd),
+            NeonUnOp::Scvtf => neon2!(self, scvtf, shape, d, n, sd),
+            NeonUnOp::Ucvtf => neon2!(self, ucvtf, shape, d, n, sd),
+            NeonUnOp::Fcvtzs => neon2!(self, fcvtzs, shape, d, n, sd),
+            NeonUnOp::Fcvtzu => neon2!(self, fcvtzu, shape, d, n, sd),
+        }
+        Ok(())
+    }
+    fn emit_neon_widen(
+        &mut self,
+        op: NeonWidenOp,
+        shape: V128Shape,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match op {
+            NeonWidenOp::Sxtl => neon2!(self, sxtl, shape, d, n, [
+                V128Shape::I8x16 => H8, B8;
+                V128Shape::I16x8 => S4, H4;
+                V128Shape::I32x4 => D2, S2
+            ]),
+            NeonWidenOp::Sxtl2 => neon2!(self, sxtl2, shape, d, n, [
+                V128Shape::I8x16 => H8, B16;
+                V128Shape::I16x8 => S4, H8;
+                V128Shape::I32x4 => D2, S4
+            ]),
+            NeonWidenOp::Uxtl => neon2!(self, uxtl, shape, d, n, [
+                V128Shape::I8x16 => H8, B8;
+                V128Shape::I16x8 => S4, H4;
+                V128Shape::I32x4 => D2, S2
+            ]),
+            NeonWidenOp::Uxtl2 => neon2!(self, uxtl2, shape, d, n, [
+                V128Shape::I8x16 => H8, B16;
+                V128Shape::I16x8 => S4, H8;
+                V128Shape::I32x4 => D2, S4
+            ]),
+            NeonWidenOp::Saddlp => neon2!(self, saddlp, shape, d, n, [
+                V128Shape::I8x16 => H8, B16;
+                V128Shape::I16x8 => S4, H8
+            ]),
+            NeonWidenOp::Uaddlp => neon2!(self, uaddlp, shape, d, n, [
+                V128Shape::I8x16 => H8, B16;
+                V128Shape::I16x8 => S4, H8
+            ]),
+            NeonWidenOp::Fcvtl => neon2!(self, fcvtl, shape, d, n, [
+                V128Shape::F32x4 => D2, S2
+            ]),
+        }
+        Ok(())
+    }
+    fn emit_neon_narrow(
+        &mut self,
+        op: NeonNarrowOp,
+        shape: V128Shape,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match op {
+            NeonNarrowOp::Sqxtn => neon2!(self, sqxtn, shape, d, n, [
+                V128Shape::I16x8 => B8, H8;
+                V128Shape::I32x4 => H4, S4;
+                V128Shape::I64x2 => S2, D2
+            ]),
+            NeonNarrowOp::Sqxtn2 => neon2!(self, sqxtn2, shape, d, n, [
+                V128Shape::I16x8 => B16, H8;
+                V128Shape::I32x4 => H8, S4
+            ]),
+            NeonNarrowOp::Sqxtun => neon2!(self, sqxtun, shape, d, n, [
+                V128Shape::I16x8 => B8, H8;
+                V128Shape::I32x4 => H4, S4
+            ]),
+            NeonNarrowOp::Sqxtun2 => neon2!(self, sqxtun2, shape, d, n, [
+                V128Shape::I16x8 => B16, H8;
+                V128Shape::I32x4 => H8, S4
+            ]),
+            NeonNarrowOp::Uqxtn => neon2!(self, uqxtn, shape, d, n, [
+                V128Shape::I64x2 => S2, D2
+            ]),
+            NeonNarrowOp::Fcvtn => neon2!(self, fcvtn, shape, d, n, [
+                V128Shape::F64x2 => S2, D2
+            ]),
+        }
+        Ok(())
+    }
+    fn emit_neon_long(
+        &mut self,
+        op: NeonLongOp,
+        shape: V128Shape,
+        src1: NEON,
+        src2: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src1.into_index() as u32;
+        let m = src2.into_index() as u32;
+        match op {
+            NeonLongOp::Smull => neon3!(self, smull, shape, d, n, m, [
+                V128Shape::I8x16 => H8, B8, B8;
+                V128Shape::I16x8 => S4, H4, H4;
+                V128Shape::I32x4 => D2, S2, S2
+            ]),
+            NeonLongOp::Smull2 => neon3!(self, smull2, shape, d, n, m, [
+                V128Shape::I8x16 => H8, B16, B16;
+                V128Shape::I16x8 => S4, H8, H8;
+                V128Shape::I32x4 => D2, S4, S4
+            ]),
+            NeonLongOp::Umull => neon3!(self, umull, shape, d, n, m, [
+                V128Shape::I8x16 => H8, B8, B8;
+                V128Shape::I16x8 => S4, H4, H4;
+                V128Shape::I32x4 => D2, S2, S2
+            ]),
+            NeonLongOp::Umull2 => neon3!(self, umull2, shape, d, n, m, [
+                V128Shape::I8x16 => H8, B16, B16;
+                V128Shape::I16x8 => S4, H8, H8;
+                V128Shape::I32x4 => D2, S4, S4
+            ]),
+        }
+        Ok(())
+    }
+    fn emit_neon_shift_imm(
+        &mut self,
+        op: NeonShiftOp,
+        shape: V128Shape,
+        imm: u32,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match (op, shape) {
+            (NeonShiftOp::Ushr, V128Shape::I8x16) => dynasm!(self ; ushr V(d).B16, V(n).B16, imm),
+            (NeonShiftOp::Ushr, V128Shape::I16x8) => dynasm!(self ; ushr V(d).H8, V(n).H8, imm),
+            (NeonShiftOp::Ushr, V128Shape::I32x4 | V128Shape::F32x4) => {
+                dynasm!(self ; ushr V(d).S4, V(n).S4, imm)
+            }
+            (NeonShiftOp::Ushr, V128Shape::I64x2 | V128Shape::F64x2) => {
+                dynasm!(self ; ushr V(d).D2, V(n).D2, imm)
+            }
+        }
+        Ok(())
+    }
+    fn emit_neon_reduce(
+        &mut self,
+        op: NeonReduceOp,
+        shape: V128Shape,
+        src: NEON,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match (op, shape) {
+            (NeonReduceOp::Umaxv, V128Shape::I8x16) => dynasm!(self ; umaxv B(d), V(n).B16),
+            (NeonReduceOp::Umaxv, V128Shape::I16x8) => dynasm!(self ; umaxv H(d), V(n).H8),
+            (NeonReduceOp::Umaxv, V128Shape::I32x4) => dynasm!(self ; umaxv S(d), V(n).S4),
+            (NeonReduceOp::Uminv, V128Shape::I8x16) => dynasm!(self ; uminv B(d), V(n).B16),
+            (NeonReduceOp::Uminv, V128Shape::I16x8) => dynasm!(self ; uminv H(d), V(n).H8),
+            (NeonReduceOp::Uminv, V128Shape::I32x4) => dynasm!(self ; uminv S(d), V(n).S4),
+            (NeonReduceOp::Addv, V128Shape::I8x16) => dynasm!(self ; addv B(d), V(n).B16),
+            (NeonReduceOp::Addv, V128Shape::I16x8) => dynasm!(self ; addv H(d), V(n).H8),
+            (NeonReduceOp::Addv, V128Shape::I32x4) => dynasm!(self ; addv S(d), V(n).S4),
+            (NeonReduceOp::Addv, V128Shape::I64x2) => dynasm!(self ; addp D(d), V(n).D2),
+            _ => codegen_error!("singlepass can't emit {:?} {:?}", op, shape),
+        }
+        Ok(())
+    }
+    fn emit_neon_ext(
+        &mut self,
+        src1: NEON,
+        src2: NEON,
+        imm: u32,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src1.into_index() as u32;
+        let m = src2.into_index() as u32;
+        dynasm!(self ; ext V(d).B16, V(n).B16, V(m).B16, imm);
+        Ok(())
+    }
+    fn emit_neon_mov(&mut self, src: NEON, dst: NEON) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        dynasm!(self ; mov V(d).B16, V(n).B16);
+        Ok(())
+    }
+    fn emit_neon_movi(&mut self, ones: bool, dst: NEON) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        if ones {
+            dynasm!(self ; movi V(d).D2, 0xffff_ffff_ffff_ffff);
+        } else {
+            dynasm!(self ; movi V(d).D2, 0);
+        }
+        Ok(())
+    }
+    fn emit_neon_dup(&mut self, shape: V128Shape, src: GPR, dst: NEON) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match shape {
+            V128Shape::I8x16 => dynasm!(self ; dup V(d).B16, W(n)),
+            V128Shape::I16x8 => dynasm!(self ; dup V(d).H8, W(n)),
+            V128Shape::I32x4 | V128Shape::F32x4 => dynasm!(self ; dup V(d).S4, W(n)),
+            V128Shape::I64x2 | V128Shape::F64x2 => dynasm!(self ; dup V(d).D2, X(n)),
+        }
+        Ok(())
+    }
+    fn emit_neon_ins(
+        &mut self,
+        shape: V128Shape,
+        src: GPR,
+        lane: u32,
+        dst: NEON,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match shape {
+            V128Shape::I8x16 => dynasm!(self ; ins V(d).B[lane], W(n)),
+            V128Shape::I16x8 => dynasm!(self ; ins V(d).H[lane], W(n)),
+            V128Shape::I32x4 | V128Shape::F32x4 => dynasm!(self ; ins V(d).S[lane], W(n)),
+            V128Shape::I64x2 | V128Shape::F64x2 => dynasm!(self ; ins V(d).D[lane], X(n)),
+        }
+        Ok(())
+    }
+    fn emit_neon_umov(
+        &mut self,
+        shape: V128Shape,
+        src: NEON,
+        lane: u32,
+        dst: GPR,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match shape {
+            V128Shape::I8x16 => dynasm!(self ; umov W(d), V(n).B[lane]),
+            V128Shape::I16x8 => dynasm!(self ; umov W(d), V(n).H[lane]),
+            V128Shape::I32x4 | V128Shape::F32x4 => dynasm!(self ; umov W(d), V(n).S[lane]),
+            V128Shape::I64x2 | V128Shape::F64x2 => dynasm!(self ; umov X(d), V(n).D[lane]),
+        }
+        Ok(())
+    }
+    fn emit_neon_smov(
+        &mut self,
+        shape: V128Shape,
+        src: NEON,
+        lane: u32,
+        dst: GPR,
+    ) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let n = src.into_index() as u32;
+        match shape {
+            V128Shape::I8x16 => dynasm!(self ; smov W(d), V(n).B[lane]),
+            V128Shape::I16x8 => dynasm!(self ; smov W(d), V(n).H[lane]),
+            _ => codegen_error!("singlepass can't emit SMOV {:?}", shape),
+        }
+        Ok(())
+    }
+    fn emit_ld1r(&mut self, shape: V128Shape, addr: GPR, dst: NEON) -> Result<(), CompileError> {
+        let d = dst.into_index() as u32;
+        let addr = addr.into_index() as u32;
+        match shape {
+            V128Shape::I8x16 => dynasm!(self ; ld1r {V(d).B16 * 1}, [X(addr)]),
+            V128Shape::I16x8 => dynasm!(self ; ld1r {V(d).H8 * 1}, [X(addr)]),
+            V128Shape::I32x4 | V128Shape::F32x4 => dynasm!(self ; ld1r {V(d).S4 * 1}, [X(addr)]),
+            V128Shape::I64x2 | V128Shape::F64x2 => dynasm!(self ; ld1r {V(d).D2 * 1}, [X(addr)]),
+        }
+        Ok(())
+    }
+    fn emit_ldur_q(&mut self, dst: NEON, addr: GPR, offset: i32) -> Result<(), CompileError> {
+        assert!((-255..=255).contains(&offset));
+        let d = dst.into_index() as u32;
+        let addr = addr.into_index() as u32;
+        dynasm!(self ; ldur Q(d), [X(addr), offset]);
+        Ok(())
+    }
+    fn emit_stur_q(&mut self, src: NEON, addr: GPR, offset: i32) -> Result<(), CompileError> {
+        assert!((-255..=255).contains(&offset));
+        let s = src.into_index() as u32;
+        let addr = addr.into_index() as u32;
+        dynasm!(self ; stur Q(s), [X(addr), offset]);
+        Ok(())
+    }
+
     fn emit_fmov(
         &mut self,
         src_size: Size,
@@ -3335,7 +4002,13 @@ pub fn gen_std_trampoline_arm64(
         ; mov X(args as u32), x2
     );
 
-    let stack_args = sig.params().len().saturating_sub(7); //1st arg is ctx, not an actual arg
+    // A v128 param takes two consecutive 64 bits slots.
+    let n_slots: usize = sig
+        .params()
+        .iter()
+        .map(|ty| if *ty == Type::V128 { 2 } else { 1 })
+        .sum();
+    let stack_args = n_slots.saturating_sub(7); //1st arg is ctx, not an actual arg
     let mut stack_offset = stack_args as u32 * 8;
     if stack_args > 0 {
         if stack_offset % 16 != 0 {
@@ -3348,23 +4021,30 @@ pub fn gen_std_trampoline_arm64(
     // Move arguments to their locations.
     // `callee_vmctx` is already in the first argument register, so no need to move.
     let mut caller_stack_offset: i32 = 0;
+    let mut halves = vec![];
     for (i, param) in sig.params().iter().enumerate() {
-        let sz = match *param {
-            Type::I32 | Type::F32 => Size::S32,
-            Type::I64 | Type::F64 => Size::S64,
-            Type::ExternRef => Size::S64,
-            Type::FuncRef => Size::S64,
+        match *param {
+            Type::I32 | Type::F32 => halves.push((Size::S32, i * 16)),
+            Type::I64 | Type::F64 => halves.push((Size::S64, i * 16)),
+            Type::ExternRef => halves.push((Size::S64, i * 16)),
+            Type::FuncRef => halves.push((Size::S64, i * 16)),
+            Type::V128 => {
+                halves.push((Size::S64, i * 16));
+                halves.push((Size::S64, i * 16 + 8));
+            }
             _ => codegen_error!(
                 "singlepass unsupported param type for trampoline {:?}",
                 *param
             ),
-        };
+        }
+    }
+    for (i, (sz, arg_offset)) in halves.into_iter().enumerate() {
         match i {
             0..=6 => {
                 a.emit_ldr(
                     sz,
                     Location::GPR(GPR::from_index(i + 1).unwrap()),
-                    Location::Memory(args, (i * 16) as i32),
+                    Location::Memory(args, arg_offset as i32),
                 )?;
             }
             _ => {
@@ -3389,7 +4069,7 @@ pub fn gen_std_trampoline_arm64(
                 a.emit_ldr(
                     sz,
                     Location::GPR(GPR::X16),
-                    Location::Memory(args, (i * 16) as i32),
+                    Location::Memory(args, arg_offset as i32),
                 )?;
                 a.emit_str(
                     sz,
@@ -3417,7 +4097,9 @@ pub fn gen_std_trampoline_arm64(
     dynasm!(a  ; blr X(fptr as u32));
 
     // Write return value.
-    if !sig.results().is_empty() {
+    if sig.results().first() == Some(&Type::V128) {
+        a.emit_stur_q(NEON::V0, args, 0)?;
+    } else if !sig.results().is_empty() {
         a.emit_str(Size::S64, Location::GPR(GPR::X0), Location::Memory(args, 0))?;
     }
 
@@ -3482,8 +4164,22 @@ pub fn gen_std_dynamic_import_trampoline_arm64(
 
         for (i, ty) in sig.params().iter().enumerate() {
             let source_loc = match argalloc.next(*ty, calling_convention) {
+                Some(ARM64Register::NEON(neon)) if *ty == Type::V128 => {
+                    // Store the whole register, `STUR` only reaches small offsets.
+                    a.emit_add(
+                        Size::S64,
+                        Location::GPR(GPR::XzrSp),
+                        Location::Imm32((i * 16) as u32),
+                        Location::GPR(GPR::X26),
+                    )?;
+                    a.emit_stur_q(neon, GPR::X26, 0)?;
+                    continue;
+                }
                 Some(ARM64Register::GPR(gpr)) => Location::GPR(gpr),
                 Some(ARM64Register::NEON(neon)) => Location::SIMD(neon),
+                None if *ty == Type::V128 => codegen_error!(
+                    "singlepass doesn't support v128 params passed on the stack for dynamic imports"
+                ),
                 None => {
                     let sz = match calling_convention {
                         CallingConvention::AppleAarch64 => match *ty {
@@ -3547,7 +4243,10 @@ pub fn gen_std_dynamic_import_trampoline_arm64(
     a.emit_call_register(GPR::X26)?;
 
     // Fetch return value.
-    if !sig.results().is_empty() {
+    if sig.results().first() == Some(&Type::V128) {
+        assert_eq!(sig.results().len(), 1);
+        a.emit_ldur_q(NEON::V0, GPR::XzrSp, 0)?;
+    } else if !sig.results().is_empty() {
         assert_eq!(sig.results().len(), 1);
         a.emit_ldr(
             Size::S64,
@@ -3604,21 +4303,27 @@ pub fn gen_import_call_trampoline_arm64(
 
     // Singlepass internally treats all arguments as integers
     // For the standard System V calling convention requires
-    //  floating point arguments to be passed in NEON registers.
+    //  floating point and vector arguments to be passed in NEON registers.
     //  Translation is expensive, so only do it if needed.
     if sig
         .params()
         .iter()
-        .any(|&x| x == Type::F32 || x == Type::F64)
+        .any(|&x| x == Type::F32 || x == Type::F64 || x == Type::V128)
     {
         #[allow(clippy::match_single_binding)]
         match calling_convention {
             _ => {
+                // A v128 param takes two consecutive 64 bits slots.
+                let n_slots: usize = sig
+                    .params()
+                    .iter()
+                    .map(|ty| if *ty == Type::V128 { 2 } else { 1 })
+                    .sum();
                 // Allocate stack space for arguments.
-                let stack_offset: i32 = if sig.params().len() > 7 {
+                let stack_offset: i32 = if n_slots > 7 {
                     7 * 8
                 } else {
-                    (sig.params().len() as i32) * 8
+                    (n_slots as i32) * 8
                 };
                 let stack_offset = if stack_offset & 15 != 0 {
                     stack_offset + 8
@@ -3657,7 +4362,7 @@ pub fn gen_import_call_trampoline_arm64(
                 let mut param_locations = vec![];
                 /* Clippy is wrong about using `i` to index `PARAM_REGS` here. */
                 #[allow(clippy::needless_range_loop)]
-                for i in 0..sig.params().len() {
+                for i in 0..n_slots {
                     let loc = match i {
                         0..=6 => {
                             let loc = Location::Memory(GPR::XzrSp, (i * 8) as i32);
@@ -3673,11 +4378,25 @@ pub fn gen_import_call_trampoline_arm64(
                 let mut caller_stack_offset: i32 = 0;
                 let mut argalloc = ArgumentRegisterAllocator::default();
                 argalloc.next(Type::I64, calling_convention).unwrap(); // skip VMContext
-                for (i, ty) in sig.params().iter().enumerate() {
-                    let prev_loc = param_locations[i];
+                let mut slot = 0;
+                for ty in sig.params().iter() {
+                    let prev_loc = param_locations[slot];
+                    slot += 1;
                     let targ = match argalloc.next(*ty, calling_convention) {
+                        Some(ARM64Register::NEON(neon)) if *ty == Type::V128 => {
+                            // Rebuild the vector from its two halves.
+                            let hi_loc = param_locations[slot];
+                            slot += 1;
+                            a.emit_ldr(Size::S64, Location::SIMD(neon), prev_loc)?;
+                            a.emit_ldr(Size::S64, Location::GPR(GPR::X16), hi_loc)?;
+                            a.emit_neon_ins(V128Shape::I64x2, GPR::X16, 1, neon)?;
+                            continue;
+                        }
                         Some(ARM64Register::GPR(gpr)) => Location::GPR(gpr),
                         Some(ARM64Register::NEON(neon)) => Location::SIMD(neon),
+                        None if *ty == Type::V128 => codegen_error!(
+                            "singlepass doesn't support v128 params passed on the stack for imports"
+                        ),
                         None => {
                             // No register can be allocated. Put this argument on the stack.
                             a.emit_ldr(Size::S64, Location::GPR(GPR::X16), prev_loc)?;
@@ -64,6 +64,139 @@ pub enum Precision {
     Double,
 }
 
+/// Legacy SSE instructions taking two XMM registers, used for v128 operators.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SseOp {
+    Movdqa,
+    Pand,
+    Pandn,
+    Por,
+    Pxor,
+    Paddb,
+    Paddw,
+    Paddd,
+    Paddq,
+    Paddsb,
+    Paddsw,
+    Paddusb,
+    Paddusw,
+    Psubb,
+    Psubw,
+    Psubd,
+    Psubq,
+    Psubsb,
+    Psubsw,
+    Psubusb,
+    Psubusw,
+    Pmullw,
+    Pmulld,
+    Pmuldq,
+    Pmuludq,
+    Pmulhrsw,
+    Pmaddwd,
+    Pmaddubsw,
+    Pavgb,
+    Pavgw,
+    Pminsb,
+    Pminsw,
+    Pminsd,
+    Pminub,
+    Pminuw,
+    Pminud,
+    Pmaxsb,
+    Pmaxsw,
+    Pmaxsd,
+    Pmaxub,
+    Pmaxuw,
+    Pmaxud,
+    Pabsb,
+    Pabsw,
+    Pabsd,
+    Pcmpeqb,
+    Pcmpeqw,
+    Pcmpeqd,
+    Pcmpeqq,
+    Pcmpgtb,
+    Pcmpgtw,
+    Pcmpgtd,
+    Pcmpgtq,
+    Packsswb,
+    Packssdw,
+    Packuswb,
+    Packusdw,
+    Punpcklbw,
+    Punpckhbw,
+    Punpckldq,
+    Punpcklqdq,
+    Pshufb,
+    Pmovsxbw,
+    Pmovzxbw,
+    Pmovsxwd,
+    Pmovzxwd,
+    Pmovsxdq,
+    Pmovzxdq,
+    Psllw,
+    Pslld,
+    Psllq,
+    Psrlw,
+    Psrld,
+    Psrlq,
+    Psraw,
+    Psrad,
+    Ptest,
+    Addps,
+    Addpd,
+    Subps,
+    Subpd,
+    Mulps,
+    Mulpd,
+    Divps,
+    Divpd,
+    Minps,
+    Minpd,
+    Maxps,
+    Maxpd,
+    Sqrtps,
+    Sqrtpd,
+    Andps,
+    Andpd,
+    Andnps,
+    Andnpd,
+    Orps,
+    Orpd,
+    Xorps,
+    Xorpd,
+    Cvtdq2ps,
+    Cvttps2dq,
+    Cvtdq2pd,
+    Cvttpd2dq,
+    Cvtpd2ps,
+    Cvtps2pd,
+}
+
+/// Legacy SSE instructions taking two XMM registers and an 8 bits immediate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SseImmOp {
+    Pshufd,
+    Pshuflw,
+    Shufps,
+    Cmpps,
+    Cmppd,
+    Roundps,
+    Roundpd,
+}
+
+/// Legacy SSE shifts of an XMM register by an immediate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SseShiftOp {
+    Pslld,
+    Psllq,
+    Psrlw,
+    Psrld,
+    Psrlq,
+    Psrad,
+}
+
 #[allow(unused)]
 pub trait EmitterX64 {
     fn get_simd_arch(&self) -> Option<&CpuFeature>;
@@ -346,6 +479,21 @@ pub trait EmitterX64 {
         dst: XMM,
     ) -> Result<(), CompileError>;
 
+    fn emit_sse(&mut self, op: SseOp, src: XMM, dst: XMM) -> Result<(), CompileError>;
+    fn emit_sse_imm(
+        &mut self,
+        op: SseImmOp,
+        imm: u8,
+        src: XMM,
+        dst: XMM,
+    ) -> Result<(), CompileError>;
+    fn emit_sse_shift_imm(&mut self, op: SseShiftOp, imm: u8, dst: XMM)
+        -> Result<(), CompileError>;
+    fn emit_movdqu(&mut self, src: XMMOrMemory, dst: XMMOrMemory) -> Result<(), CompileError>;
+    fn emit_pinsr(&mut self, sz: Size, src: GPR, lane: u8, dst: XMM) -> Result<(), CompileError>;
+    fn emit_pextr(&mut self, sz: Size, src: XMM, lane: u8, dst: GPR) -> Result<(), CompileError>;
+    fn emit_movmsk(&mut self, sz: Size, src: XMM, dst: GPR) -> Result<(), CompileError>;
+
     fn emit_test_gpr_64(&mut self, reg: GPR) -> Result<(), CompileError>;
 
     fn emit_ud2(&mut self) -> Result<(), CompileError>;
@@ -2372,6 +2520,193 @@ impl EmitterX64 for AssemblerX64 {
         Ok(())
     }
 
+    fn emit_sse(&mut self, op: SseOp, src: XMM, dst: XMM) -> Result<(), CompileError> {
+        match op {
+            SseOp::Movdqa => dynasm!(self ; movdqa Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pand => dynasm!(self ; pand Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pandn => dynasm!(self ; pandn Rx(dst as u8), Rx(src as u8)),
+            SseOp::Por => dynasm!(self ; por Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pxor => dynasm!(self ; pxor Rx(dst as u8), Rx(src as u8)),
+            SseOp::Paddb => dynasm!(self ; paddb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Paddw => dynasm!(self ; paddw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Paddd => dynasm!(self ; paddd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Paddq => dynasm!(self ; paddq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Paddsb => dynasm!(self ; paddsb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Paddsw => dynasm!(self ; paddsw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Paddusb => dynasm!(self ; paddusb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Paddusw => dynasm!(self ; paddusw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psubb => dynasm!(self ; psubb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psubw => dynasm!(self ; psubw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psubd => dynasm!(self ; psubd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psubq => dynasm!(self ; psubq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psubsb => dynasm!(self ; psubsb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psubsw => dynasm!(self ; psubsw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psubusb => dynasm!(self ; psubusb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psubusw => dynasm!(self ; psubusw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmullw => dynasm!(self ; pmullw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmulld => dynasm!(self ; pmulld Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmuldq => dynasm!(self ; pmuldq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmuludq => dynasm!(self ; pmuludq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmulhrsw => dynasm!(self ; pmulhrsw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmaddwd => dynasm!(self ; pmaddwd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmaddubsw => dynasm!(self ; pmaddubsw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pavgb => dynasm!(self ; pavgb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pavgw => dynasm!(self ; pavgw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pminsb => dynasm!(self ; pminsb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pminsw => dynasm!(self ; pminsw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pminsd => dynasm!(self ; pminsd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pminub => dynasm!(self ; pminub Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pminuw => dynasm!(self ; pminuw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pminud => dynasm!(self ; pminud Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmaxsb => dynasm!(self ; pmaxsb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmaxsw => dynasm!(self ; pmaxsw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmaxsd => dynasm!(self ; pmaxsd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmaxub => dynasm!(self ; pmaxub Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmaxuw => dynasm!(self ; pmaxuw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmaxud => dynasm!(self ; pmaxud Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pabsb => dynasm!(self ; pabsb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pabsw => dynasm!(self ; pabsw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pabsd => dynasm!(self ; pabsd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pcmpeqb => dynasm!(self ; pcmpeqb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pcmpeqw => dynasm!(self ; pcmpeqw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pcmpeqd => dynasm!(self ; pcmpeqd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pcmpeqq => dynasm!(self ; pcmpeqq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pcmpgtb => dynasm!(self ; pcmpgtb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pcmpgtw => dynasm!(self ; pcmpgtw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pcmpgtd => dynasm!(self ; pcmpgtd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pcmpgtq => dynasm!(self ; pcmpgtq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Packsswb => dynasm!(self ; packsswb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Packssdw => dynasm!(self ; packssdw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Packuswb => dynasm!(self ; packuswb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Packusdw => dynasm!(self ; packusdw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Punpcklbw => dynasm!(self ; punpcklbw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Punpckhbw => dynasm!(self ; punpckhbw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Punpckldq => dynasm!(self ; punpckldq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Punpcklqdq => dynasm!(self ; punpcklqdq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pshufb => dynasm!(self ; pshufb Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmovsxbw => dynasm!(self ; pmovsxbw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmovzxbw => dynasm!(self ; pmovzxbw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmovsxwd => dynasm!(self ; pmovsxwd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmovzxwd => dynasm!(self ; pmovzxwd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmovsxdq => dynasm!(self ; pmovsxdq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pmovzxdq => dynasm!(self ; pmovzxdq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psllw => dynasm!(self ; psllw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Pslld => dynasm!(self ; pslld Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psllq => dynasm!(self ; psllq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psrlw => dynasm!(self ; psrlw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psrld => dynasm!(self ; psrld Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psrlq => dynasm!(self ; psrlq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psraw => dynasm!(self ; psraw Rx(dst as u8), Rx(src as u8)),
+            SseOp::Psrad => dynasm!(self ; psrad Rx(dst as u8), Rx(src as u8)),
+            SseOp::Ptest => dynasm!(self ; ptest Rx(dst as u8), Rx(src as u8)),
+            SseOp::Addps => dynasm!(self ; addps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Addpd => dynasm!(self ; addpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Subps => dynasm!(self ; subps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Subpd => dynasm!(self ; subpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Mulps => dynasm!(self ; mulps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Mulpd => dynasm!(self ; mulpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Divps => dynasm!(self ; divps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Divpd => dynasm!(self ; divpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Minps => dynasm!(self ; minps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Minpd => dynasm!(self ; minpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Maxps => dynasm!(self ; maxps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Maxpd => dynasm!(self ; maxpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Sqrtps => dynasm!(self ; sqrtps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Sqrtpd => dynasm!(self ; sqrtpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Andps => dynasm!(self ; andps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Andpd => dynasm!(self ; andpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Andnps => dynasm!(self ; andnps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Andnpd => dynasm!(self ; andnpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Orps => dynasm!(self ; orps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Orpd => dynasm!(self ; orpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Xorps => dynasm!(self ; xorps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Xorpd => dynasm!(self ; xorpd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Cvtdq2ps => dynasm!(self ; cvtdq2ps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Cvttps2dq => dynasm!(self ; cvttps2dq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Cvtdq2pd => dynasm!(self ; cvtdq2pd Rx(dst as u8), Rx(src as u8)),
+            SseOp::Cvttpd2dq => dynasm!(self ; cvttpd2dq Rx(dst as u8), Rx(src as u8)),
+            SseOp::Cvtpd2ps => dynasm!(self ; cvtpd2ps Rx(dst as u8), Rx(src as u8)),
+            SseOp::Cvtps2pd => dynasm!(self ; cvtps2pd Rx(dst as u8), Rx(src as u8)),
+        }
+        Ok(())
+    }
+    fn emit_sse_imm(
+        &mut self,
+        op: SseImmOp,
+        imm: u8,
+        src: XMM,
+        dst: XMM,
+    ) -> Result<(), CompileError> {
+        match op {
+            SseImmOp::Pshufd => dynasm!(self ; pshufd Rx(dst as u8), Rx(src as u8), imm as i8),
+            SseImmOp::Pshuflw => dynasm!(self ; pshuflw Rx(dst as u8), Rx(src as u8), imm as i8),
+            SseImmOp::Shufps => dynasm!(self ; shufps Rx(dst as u8), Rx(src as u8), imm as i8),
+            SseImmOp::Cmpps => dynasm!(self ; cmpps Rx(dst as u8), Rx(src as u8), imm as i8),
+            SseImmOp::Cmppd => dynasm!(self ; cmppd Rx(dst as u8), Rx(src as u8), imm as i8),
+            SseImmOp::Roundps => dynasm!(self ; roundps Rx(dst as u8), Rx(src as u8), imm as i8),
+            SseImmOp::Roundpd => dynasm!(self ; roundpd Rx(dst as u8), Rx(src as u8), imm as i8),
+        }
+        Ok(())
+    }
+    fn emit_sse_shift_imm(
+        &mut self,
+        op: SseShiftOp,
+        imm: u8,
+        dst: XMM,
+    ) -> Result<(), CompileError> {
+        match op {
+            SseShiftOp::Pslld => dynasm!(self ; pslld Rx(dst as u8), imm as i8),
+            SseShiftOp::Psllq => dynasm!(self ; psllq Rx(dst as u8), imm as i8),
+            SseShiftOp::Psrlw => dynasm!(self ; psrlw Rx(dst as u8), imm as i8),
+            SseShiftOp::Psrld => dynasm!(self ; psrld Rx(dst as u8), imm as i8),
+            SseShiftOp::Psrlq => dynasm!(self ; psrlq Rx(dst as u8), imm as i8),
+            SseShiftOp::Psrad => dynasm!(self ; psrad Rx(dst as u8), imm as i8),
+        }
+        Ok(())
+    }
+    fn emit_movdqu(&mut self, src: XMMOrMemory, dst: XMMOrMemory) -> Result<(), CompileError> {
+        match (src, dst) {
+            (XMMOrMemory::XMM(src), XMMOrMemory::XMM(dst)) => {
+                dynasm!(self ; movdqu Rx(dst as u8), Rx(src as u8))
+            }
+            (XMMOrMemory::Memory(base, disp), XMMOrMemory::XMM(dst)) => {
+                dynasm!(self ; movdqu Rx(dst as u8), [Rq(base as u8) + disp])
+            }
+            (XMMOrMemory::XMM(src), XMMOrMemory::Memory(base, disp)) => {
+                dynasm!(self ; movdqu [Rq(base as u8) + disp], Rx(src as u8))
+            }
+            _ => codegen_error!("singlepass can't emit MOVDQU {:?} {:?}", src, dst),
+        };
+        Ok(())
+    }
+    fn emit_pinsr(&mut self, sz: Size, src: GPR, lane: u8, dst: XMM) -> Result<(), CompileError> {
+        match sz {
+            Size::S8 => dynasm!(self ; pinsrb Rx(dst as u8), Rd(src as u8), lane as i8),
+            Size::S16 => dynasm!(self ; pinsrw Rx(dst as u8), Rd(src as u8), lane as i8),
+            Size::S32 => dynasm!(self ; pinsrd Rx(dst as u8), Rd(src as u8), lane as i8),
+            Size::S64 => dynasm!(self ; pinsrq Rx(dst as u8), Rq(src as u8), lane as i8),
+        }
+        Ok(())
+    }
+    fn emit_pextr(&mut self, sz: Size, src: XMM, lane: u8, dst: GPR) -> Result<(), CompileError> {
+        match sz {
+            Size::S8 => dynasm!(self ; pextrb Rd(dst as u8), Rx(src as u8), lane as i8),
+            Size::S16 => dynasm!(self ; pextrw Rd(dst as u8), Rx(src as u8), lane as i8),
+            Size::S32 => dynasm!(self ; pextrd Rd(dst as u8), Rx(src as u8), lane as i8),
+            Size::S64 => dynasm!(self ; pextrq Rq(dst as u8), Rx(src as u8), lane as i8),
+        }
+        Ok(())
+    }
+    fn emit_movmsk(&mut self, sz: Size, src: XMM, dst: GPR) -> Result<(), CompileError> {
+        match sz {
+            Size::S8 => dynasm!(self ; pmovmskb Rd(dst as u8), Rx(src as u8)),
+            Size::S32 => dynasm!(self ; movmskps Rd(dst as u8), Rx(src as u8)),
+            Size::S64 => dynasm!(self ; movmskpd Rd(dst as u8), Rx(src as u8)),
+            _ => codegen_error!("singlepass can't emit MOVMSK {:?}", sz),
+        }
+        Ok(())
+    }
+
     fn emit_test_gpr_64(&mut self, reg: GPR) -> Result<(), CompileError> {
         dynasm!(self ; test Rq(reg as u8), Rq(reg as u8));
         Ok(())
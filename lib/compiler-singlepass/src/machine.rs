@@ -12,13 +12,14 @@ use wasmer_compiler::{
         address_map::InstructionAddressMap,
         function::FunctionBody,
         relocation::{Relocation, RelocationTarget},
-        section::CustomSection,
+        section::{CustomSection, CustomSectionProtection, SectionBody},
         target::{Architecture, CallingConvention, Target},
     },
     wasmparser::{MemArg, ValType as WpType},
 };
 use wasmer_types::{
-    CompileError, FunctionIndex, FunctionType, TrapCode, TrapInformation, VMOffsets,
+    CompileError, FunctionIndex, FunctionType, LocalFunctionIndex, TrapCode, TrapInformation, Type,
+    VMOffsets,
 };
 pub type Label = DynamicLabel;
 pub type Offset = AssemblyOffset;
@@ -285,6 +286,11 @@ pub trait Machine {
     fn emit_call_register(&mut self, register: Self::GPR) -> Result<(), CompileError>;
     /// Emit a call to a label
     fn emit_call_label(&mut self, label: Label) -> Result<(), CompileError>;
+    /// get the gpr holding the target of an indirect tail call.
+    /// It's not used to pass parameters and is left untouched by the function epilog
+    fn get_gpr_for_tail_call(&self) -> Self::GPR;
+    /// Emit a jump using the value in register
+    fn emit_jmp_register(&mut self, register: Self::GPR) -> Result<(), CompileError>;
     /// Does an trampoline is neededfor indirect call
     fn arch_requires_indirect_call_trampoline(&self) -> bool;
     /// indirect call with trampoline
@@ -1152,6 +1158,12 @@ pub trait Machine {
         calling_convention: CallingConvention,
         reloc_target: RelocationTarget,
     ) -> Result<Vec<Relocation>, CompileError>;
+    /// emit a jump to a function address (for tail calls), using appropriate relocation
+    fn emit_jmp_with_reloc(
+        &mut self,
+        calling_convention: CallingConvention,
+        reloc_target: RelocationTarget,
+    ) -> Result<Vec<Relocation>, CompileError>;
     /// Add with location directly from the stack
     fn emit_binop_add64(
         &mut self,
@@ -2380,6 +2392,150 @@ pub trait Machine {
         ret: Location<Self::GPR, Self::SIMD>,
     ) -> Result<(), CompileError>;
 
+    /// Copy a v128 value. Each location is either in memory or the SIMD register used for
+    /// return values.
+    fn v128_move(
+        &mut self,
+        src: Location<Self::GPR, Self::SIMD>,
+        dst: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Materialize a v128 constant
+    fn v128_const(
+        &mut self,
+        value: u128,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Copy a scalar to all the lanes of a v128
+    fn v128_splat(
+        &mut self,
+        shape: V128Shape,
+        loc: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Extract a lane of a v128 to a scalar. Lanes of 8 or 16 bits are sign extended if
+    /// `signed` is set.
+    fn v128_extract_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        signed: bool,
+        loc: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Replace a lane of a v128 with a scalar
+    fn v128_replace_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        loc: Location<Self::GPR, Self::SIMD>,
+        value: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Select bytes from two v128 values
+    fn v128_shuffle(
+        &mut self,
+        lanes: [u8; 16],
+        loc_a: Location<Self::GPR, Self::SIMD>,
+        loc_b: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Unary SIMD operator
+    fn v128_unop(
+        &mut self,
+        op: V128UnOp,
+        loc: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Binary SIMD operator
+    fn v128_binop(
+        &mut self,
+        op: V128BinOp,
+        loc_a: Location<Self::GPR, Self::SIMD>,
+        loc_b: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Select the bits of `loc_a` or `loc_b` according to the bits of `mask`
+    fn v128_bitselect(
+        &mut self,
+        loc_a: Location<Self::GPR, Self::SIMD>,
+        loc_b: Location<Self::GPR, Self::SIMD>,
+        mask: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Shift all the lanes of a v128
+    fn v128_shift(
+        &mut self,
+        op: V128ShiftOp,
+        loc: Location<Self::GPR, Self::SIMD>,
+        amount: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// Reduce a v128 to an i32
+    fn v128_test(
+        &mut self,
+        op: V128TestOp,
+        loc: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+    ) -> Result<(), CompileError>;
+    /// v128 load
+    #[allow(clippy::too_many_arguments)]
+    fn v128_load(
+        &mut self,
+        kind: V128LoadKind,
+        addr: Location<Self::GPR, Self::SIMD>,
+        memarg: &MemArg,
+        ret: Location<Self::GPR, Self::SIMD>,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError>;
+    /// v128 save
+    #[allow(clippy::too_many_arguments)]
+    fn v128_save(
+        &mut self,
+        value: Location<Self::GPR, Self::SIMD>,
+        memarg: &MemArg,
+        addr: Location<Self::GPR, Self::SIMD>,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError>;
+    /// Load a single lane of a v128, the other lanes are copied from `loc`
+    #[allow(clippy::too_many_arguments)]
+    fn v128_load_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        addr: Location<Self::GPR, Self::SIMD>,
+        memarg: &MemArg,
+        loc: Location<Self::GPR, Self::SIMD>,
+        ret: Location<Self::GPR, Self::SIMD>,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError>;
+    /// Save a single lane of a v128
+    #[allow(clippy::too_many_arguments)]
+    fn v128_save_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        value: Location<Self::GPR, Self::SIMD>,
+        memarg: &MemArg,
+        addr: Location<Self::GPR, Self::SIMD>,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError>;
+
     /// Standard function Trampoline generation
     fn gen_std_trampoline(
         &self,
@@ -2472,6 +2628,156 @@ pub fn gen_import_call_trampoline(
     }
 }
 
+/// Size of the stack area used to pass the parameters of a function of type `sig` in a Native
+/// ABI call.
+fn stack_params_size<M: Machine>(
+    machine: &M,
+    sig: &FunctionType,
+    calling_convention: CallingConvention,
+) -> usize {
+    let mut stack_offset: usize = 0;
+    // A v128 is passed as two 64 bits parameters.
+    let params_size = sig.params().iter().flat_map(|ty| match ty {
+        Type::V128 => vec![Size::S64, Size::S64],
+        Type::I32 | Type::F32 => vec![Size::S32],
+        _ => vec![Size::S64],
+    });
+    for (i, sz) in params_size.enumerate() {
+        machine.get_param_location(1 + i, sz, &mut stack_offset, calling_convention);
+    }
+    stack_offset
+}
+
+/// Size of the largest stack area used to pass the parameters of a function of one of the
+/// types of the module in a Native ABI call.
+pub fn max_stack_params_size<'a>(
+    signatures: impl Iterator<Item = &'a FunctionType>,
+    target: &Target,
+    calling_convention: CallingConvention,
+) -> Result<usize, CompileError> {
+    match target.triple().architecture {
+        Architecture::X86_64 => {
+            let machine = MachineX86_64::new(Some(target.clone()))?;
+            Ok(signatures
+                .map(|sig| stack_params_size(&machine, sig, calling_convention))
+                .max()
+                .unwrap_or(0))
+        }
+        Architecture::Aarch64(_) => {
+            let machine = MachineARM64::new(Some(target.clone()));
+            Ok(signatures
+                .map(|sig| stack_params_size(&machine, sig, calling_convention))
+                .max()
+                .unwrap_or(0))
+        }
+        _ => Err(CompileError::UnsupportedTarget(
+            "singlepass unimplemented arch for max_stack_params_size".to_owned(),
+        )),
+    }
+}
+
+/// Generates the Native ABI entry of a local function compiled with tail calls.
+///
+/// A tail call reuses the stack parameters area the current function received, so function
+/// bodies always expect an area of `stack_params_area` bytes, enough for any function of the
+/// module. The entry copies the `sig` stack parameters passed by its caller into an area of
+/// that size and calls the body.
+pub fn gen_function_entry_trampoline(
+    index: LocalFunctionIndex,
+    sig: &FunctionType,
+    stack_params_area: usize,
+    target: &Target,
+    calling_convention: CallingConvention,
+) -> Result<CustomSection, CompileError> {
+    match target.triple().architecture {
+        Architecture::X86_64 => {
+            let machine = MachineX86_64::new(Some(target.clone()))?;
+            emit_function_entry_trampoline(
+                machine,
+                index,
+                sig,
+                stack_params_area,
+                calling_convention,
+            )
+        }
+        Architecture::Aarch64(_) => {
+            let machine = MachineARM64::new(Some(target.clone()));
+            emit_function_entry_trampoline(
+                machine,
+                index,
+                sig,
+                stack_params_area,
+                calling_convention,
+            )
+        }
+        _ => Err(CompileError::UnsupportedTarget(
+            "singlepass unimplemented arch for gen_function_entry_trampoline".to_owned(),
+        )),
+    }
+}
+
+fn emit_function_entry_trampoline<M: Machine>(
+    mut machine: M,
+    index: LocalFunctionIndex,
+    sig: &FunctionType,
+    stack_params_area: usize,
+    calling_convention: CallingConvention,
+) -> Result<CustomSection, CompileError> {
+    // Where the stack parameters are found once the frame is set up, and where they are
+    // passed to the body. The first stack parameter never needs any alignment.
+    let first_stack_param = (0..)
+        .map(|i| {
+            (
+                machine.get_call_param_location(i, Size::S64, &mut 0, calling_convention),
+                machine.get_param_location(i, Size::S64, &mut 0, calling_convention),
+            )
+        })
+        .find(|(from, _)| matches!(from, Location::Memory(_, _)));
+    let (from_base, from_offset, to_base, to_offset) = match first_stack_param {
+        Some((Location::Memory(from_base, from_offset), Location::Memory(to_base, to_offset))) => {
+            (from_base, from_offset, to_base, to_offset)
+        }
+        _ => codegen_error!("emit_function_entry_trampoline: no stack parameter location"),
+    };
+    let stack_padding: usize = match calling_convention {
+        CallingConvention::WindowsFastcall => 32,
+        _ => 0,
+    };
+
+    machine.emit_function_prolog()?;
+    // The body is called with a 16 bytes aligned stack.
+    let stack_params_area = (stack_params_area + 15) & !15;
+    machine.adjust_stack((stack_params_area + stack_padding) as u32)?;
+
+    // Register parameters are left untouched, stack ones are copied 8 bytes at a time.
+    let tmp = machine.get_gpr_for_tail_call();
+    for offset in (0..stack_params_size(&machine, sig, calling_convention)).step_by(8) {
+        machine.move_location(
+            Size::S64,
+            Location::Memory(from_base, from_offset + offset as i32),
+            Location::GPR(tmp),
+        )?;
+        machine.move_location(
+            Size::S64,
+            Location::GPR(tmp),
+            Location::Memory(to_base, to_offset + (stack_padding + offset) as i32),
+        )?;
+    }
+
+    let relocations =
+        machine.emit_call_with_reloc(calling_convention, RelocationTarget::LocalFunc(index))?;
+    machine.emit_function_epilog()?;
+    machine.emit_ret()?;
+
+    let mut contents = machine.assembler_finalize()?;
+    contents.shrink_to_fit();
+    Ok(CustomSection {
+        protection: CustomSectionProtection::ReadExecute,
+        bytes: SectionBody::new_with_vec(contents),
+        relocations,
+    })
+}
+
 // Constants for the bounds of truncation operations. These are the least or
 // greatest exact floats in either f32 or f64 representation less-than (for
 // least) or greater-than (for greatest) the i32 or i64 or u32 or u64
@@ -1134,6 +1134,97 @@ impl MachineARM64 {
         Ok(())
     }
 
+    fn acquire_temp_simd_v128(&mut self) -> Result<NEON, CompileError> {
+        self.acquire_temp_simd()
+            .ok_or_else(|| CompileError::Codegen("singlepass cannot acquire temp simd".to_owned()))
+    }
+
+    fn acquire_temp_gpr_v128(&mut self) -> Result<GPR, CompileError> {
+        self.acquire_temp_gpr()
+            .ok_or_else(|| CompileError::Codegen("singlepass cannot acquire temp gpr".to_owned()))
+    }
+
+    /// Computes `base + disp` in X17, for v128 accesses out of the range of `LDUR`/`STUR`.
+    fn v128_address(&mut self, base: GPR, disp: i32) -> Result<(), CompileError> {
+        let tmp = GPR::X17;
+        if disp < 0 {
+            self.assembler
+                .emit_mov_imm(Location::GPR(tmp), (-disp) as u64)?;
+            self.assembler.emit_sub(
+                Size::S64,
+                Location::GPR(base),
+                Location::GPR(tmp),
+                Location::GPR(tmp),
+            )
+        } else {
+            self.assembler
+                .emit_mov_imm(Location::GPR(tmp), disp as u64)?;
+            self.assembler.emit_add(
+                Size::S64,
+                Location::GPR(base),
+                Location::GPR(tmp),
+                Location::GPR(tmp),
+            )
+        }
+    }
+
+    /// Loads a v128 value in a NEON register.
+    fn v128_to_neon(&mut self, loc: Location, dst: NEON) -> Result<(), CompileError> {
+        match loc {
+            Location::Memory(base, disp) if (-255..=255).contains(&disp) => {
+                self.assembler.emit_ldur_q(dst, base, disp)
+            }
+            Location::Memory(base, disp) => {
+                self.v128_address(base, disp)?;
+                self.assembler.emit_ldur_q(dst, GPR::X17, 0)
+            }
+            Location::SIMD(x) if x == dst => Ok(()),
+            Location::SIMD(x) => self.assembler.emit_neon_mov(x, dst),
+            _ => codegen_error!("singlepass can't load v128 from {:?}", loc),
+        }
+    }
+
+    /// Stores a NEON register to the location of a v128 value.
+    fn neon_to_v128(&mut self, src: NEON, loc: Location) -> Result<(), CompileError> {
+        match loc {
+            Location::Memory(base, disp) if (-255..=255).contains(&disp) => {
+                self.assembler.emit_stur_q(src, base, disp)
+            }
+            Location::Memory(base, disp) => {
+                self.v128_address(base, disp)?;
+                self.assembler.emit_stur_q(src, GPR::X17, 0)
+            }
+            Location::SIMD(x) if x == src => Ok(()),
+            Location::SIMD(x) => self.assembler.emit_neon_mov(src, x),
+            _ => codegen_error!("singlepass can't store v128 to {:?}", loc),
+        }
+    }
+
+    /// Materializes a 128 bits constant in a NEON register.
+    fn v128_const_to_neon(&mut self, value: u128, dst: NEON) -> Result<(), CompileError> {
+        if value == 0 || value == u128::MAX {
+            return self.assembler.emit_neon_movi(value != 0, dst);
+        }
+        let tmp = self.acquire_temp_gpr_v128()?;
+        self.assembler
+            .emit_mov_imm(Location::GPR(tmp), value as u64)?;
+        // Writing the low 64 bits zeroes the high ones.
+        self.assembler.emit_fmov(
+            Size::S64,
+            Location::GPR(tmp),
+            Size::S64,
+            Location::SIMD(dst),
+        )?;
+        if (value >> 64) != 0 {
+            self.assembler
+                .emit_mov_imm(Location::GPR(tmp), (value >> 64) as u64)?;
+            self.assembler
+                .emit_neon_ins(V128Shape::I64x2, tmp, 1, dst)?;
+        }
+        self.release_gpr(tmp);
+        Ok(())
+    }
+
     /*fn emit_compare_and_swap<F: FnOnce(&mut Self, GPR, GPR)>(
         &mut self,
         _loc: Location,
@@ -2366,7 +2457,9 @@ impl Machine for MachineARM64 {
         canonicalize: bool,
         loc: Location,
     ) -> Result<(), CompileError> {
-        if canonicalize {
+        if ty == WpType::V128 {
+            self.v128_move(loc, Location::SIMD(NEON::V0))?;
+        } else if canonicalize {
             self.canonicalize_nan(
                 match ty {
                     WpType::F32 => Size::S32,
@@ -2459,6 +2552,12 @@ impl Machine for MachineARM64 {
     fn emit_call_label(&mut self, label: Label) -> Result<(), CompileError> {
         self.assembler.emit_call_label(label)
     }
+    fn get_gpr_for_tail_call(&self) -> GPR {
+        GPR::X16
+    }
+    fn emit_jmp_register(&mut self, reg: GPR) -> Result<(), CompileError> {
+        self.assembler.emit_b_register(reg)
+    }
     fn get_gpr_for_ret(&self) -> GPR {
         GPR::X0
     }
@@ -4904,6 +5003,25 @@ impl Machine for MachineARM64 {
         Ok(relocations)
     }
 
+    fn emit_jmp_with_reloc(
+        &mut self,
+        _calling_convention: CallingConvention,
+        reloc_target: RelocationTarget,
+    ) -> Result<Vec<Relocation>, CompileError> {
+        let mut relocations = vec![];
+        let next = self.get_label();
+        let reloc_at = self.assembler.get_offset().0;
+        self.emit_label(next)?; // this is to be sure the current imm26 value is 0
+        self.assembler.emit_b_label(next)?;
+        relocations.push(Relocation {
+            kind: RelocationKind::Arm64Call,
+            reloc_target,
+            offset: reloc_at as u32,
+            addend: 0,
+        });
+        Ok(relocations)
+    }
+
     fn emit_binop_add64(
         &mut self,
         loc_a: Location,
@@ -8490,6 +8608,796 @@ impl Machine for MachineARM64 {
         )
     }
 
+    fn v128_move(&mut self, src: Location, dst: Location) -> Result<(), CompileError> {
+        if src == dst {
+            return Ok(());
+        }
+        match (src, dst) {
+            (Location::SIMD(x), _) => self.neon_to_v128(x, dst),
+            (_, Location::SIMD(x)) => self.v128_to_neon(src, x),
+            _ => {
+                let tmp = self.acquire_temp_simd_v128()?;
+                self.v128_to_neon(src, tmp)?;
+                self.neon_to_v128(tmp, dst)?;
+                self.release_simd(tmp);
+                Ok(())
+            }
+        }
+    }
+    fn v128_const(&mut self, value: u128, ret: Location) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_simd_v128()?;
+        self.v128_const_to_neon(value, tmp)?;
+        self.neon_to_v128(tmp, ret)?;
+        self.release_simd(tmp);
+        Ok(())
+    }
+    fn v128_splat(
+        &mut self,
+        shape: V128Shape,
+        loc: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let sz = match shape {
+            V128Shape::I64x2 | V128Shape::F64x2 => Size::S64,
+            _ => Size::S32,
+        };
+        self.move_location(sz, loc, Location::GPR(tmp))?;
+        self.assembler.emit_neon_dup(shape, tmp, t0)?;
+        self.neon_to_v128(t0, ret)?;
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_extract_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        signed: bool,
+        loc: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(loc, t0)?;
+        match shape {
+            V128Shape::I8x16 | V128Shape::I16x8 if signed => {
+                self.assembler.emit_neon_smov(shape, t0, lane as u32, tmp)?
+            }
+            _ => self.assembler.emit_neon_umov(shape, t0, lane as u32, tmp)?,
+        }
+        let sz = match shape {
+            V128Shape::I64x2 | V128Shape::F64x2 => Size::S64,
+            _ => Size::S32,
+        };
+        self.move_location(sz, Location::GPR(tmp), ret)?;
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_replace_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        loc: Location,
+        value: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let sz = match shape {
+            V128Shape::I64x2 | V128Shape::F64x2 => Size::S64,
+            _ => Size::S32,
+        };
+        self.move_location(sz, value, Location::GPR(tmp))?;
+        self.v128_to_neon(loc, t0)?;
+        self.assembler.emit_neon_ins(shape, tmp, lane as u32, t0)?;
+        self.neon_to_v128(t0, ret)?;
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_shuffle(
+        &mut self,
+        lanes: [u8; 16],
+        loc_a: Location,
+        loc_b: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let t2 = self.acquire_temp_simd_v128()?;
+        let t3 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(loc_a, t0)?;
+        self.v128_to_neon(loc_b, t1)?;
+        // `TBL` yields 0 for out of range indices, so look up each operand separately
+        // and merge the results.
+        let indices_a = lanes.map(|l| if l < 16 { l } else { 0xff });
+        let indices_b = lanes.map(|l| if l < 16 { 0xff } else { l - 16 });
+        self.v128_const_to_neon(u128::from_le_bytes(indices_a), t2)?;
+        self.assembler
+            .emit_neon(NeonOp::Tbl, V128Shape::I8x16, t0, t2, t3)?;
+        self.v128_const_to_neon(u128::from_le_bytes(indices_b), t2)?;
+        self.assembler
+            .emit_neon(NeonOp::Tbl, V128Shape::I8x16, t1, t2, t2)?;
+        self.assembler
+            .emit_neon(NeonOp::Orr, V128Shape::I8x16, t2, t3, t3)?;
+        self.neon_to_v128(t3, ret)?;
+        self.release_simd(t3);
+        self.release_simd(t2);
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_unop(
+        &mut self,
+        op: V128UnOp,
+        loc: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        use V128Shape::*;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(loc, t0)?;
+        let asm = &mut self.assembler;
+        match op {
+            V128UnOp::Not => asm.emit_neon_unop(NeonUnOp::Not, I8x16, t0, t1)?,
+            V128UnOp::I8x16Abs => asm.emit_neon_unop(NeonUnOp::Abs, I8x16, t0, t1)?,
+            V128UnOp::I8x16Neg => asm.emit_neon_unop(NeonUnOp::Neg, I8x16, t0, t1)?,
+            V128UnOp::I8x16Popcnt => asm.emit_neon_unop(NeonUnOp::Cnt, I8x16, t0, t1)?,
+            V128UnOp::I16x8Abs => asm.emit_neon_unop(NeonUnOp::Abs, I16x8, t0, t1)?,
+            V128UnOp::I16x8Neg => asm.emit_neon_unop(NeonUnOp::Neg, I16x8, t0, t1)?,
+            V128UnOp::I16x8ExtendLowI8x16S => {
+                asm.emit_neon_widen(NeonWidenOp::Sxtl, I8x16, t0, t1)?
+            }
+            V128UnOp::I16x8ExtendHighI8x16S => {
+                asm.emit_neon_widen(NeonWidenOp::Sxtl2, I8x16, t0, t1)?
+            }
+            V128UnOp::I16x8ExtendLowI8x16U => {
+                asm.emit_neon_widen(NeonWidenOp::Uxtl, I8x16, t0, t1)?
+            }
+            V128UnOp::I16x8ExtendHighI8x16U => {
+                asm.emit_neon_widen(NeonWidenOp::Uxtl2, I8x16, t0, t1)?
+            }
+            V128UnOp::I16x8ExtAddPairwiseI8x16S => {
+                asm.emit_neon_widen(NeonWidenOp::Saddlp, I8x16, t0, t1)?
+            }
+            V128UnOp::I16x8ExtAddPairwiseI8x16U => {
+                asm.emit_neon_widen(NeonWidenOp::Uaddlp, I8x16, t0, t1)?
+            }
+            V128UnOp::I32x4Abs => asm.emit_neon_unop(NeonUnOp::Abs, I32x4, t0, t1)?,
+            V128UnOp::I32x4Neg => asm.emit_neon_unop(NeonUnOp::Neg, I32x4, t0, t1)?,
+            V128UnOp::I32x4ExtendLowI16x8S => {
+                asm.emit_neon_widen(NeonWidenOp::Sxtl, I16x8, t0, t1)?
+            }
+            V128UnOp::I32x4ExtendHighI16x8S => {
+                asm.emit_neon_widen(NeonWidenOp::Sxtl2, I16x8, t0, t1)?
+            }
+            V128UnOp::I32x4ExtendLowI16x8U => {
+                asm.emit_neon_widen(NeonWidenOp::Uxtl, I16x8, t0, t1)?
+            }
+            V128UnOp::I32x4ExtendHighI16x8U => {
+                asm.emit_neon_widen(NeonWidenOp::Uxtl2, I16x8, t0, t1)?
+            }
+            V128UnOp::I32x4ExtAddPairwiseI16x8S => {
+                asm.emit_neon_widen(NeonWidenOp::Saddlp, I16x8, t0, t1)?
+            }
+            V128UnOp::I32x4ExtAddPairwiseI16x8U => {
+                asm.emit_neon_widen(NeonWidenOp::Uaddlp, I16x8, t0, t1)?
+            }
+            // `FCVTZS`/`FCVTZU` saturate and convert NaN to 0, as required.
+            V128UnOp::I32x4TruncSatF32x4S => asm.emit_neon_unop(NeonUnOp::Fcvtzs, F32x4, t0, t1)?,
+            V128UnOp::I32x4TruncSatF32x4U => asm.emit_neon_unop(NeonUnOp::Fcvtzu, F32x4, t0, t1)?,
+            V128UnOp::I32x4TruncSatF64x2SZero => {
+                asm.emit_neon_unop(NeonUnOp::Fcvtzs, F64x2, t0, t1)?;
+                asm.emit_neon_narrow(NeonNarrowOp::Sqxtn, I64x2, t1, t1)?;
+            }
+            V128UnOp::I32x4TruncSatF64x2UZero => {
+                asm.emit_neon_unop(NeonUnOp::Fcvtzu, F64x2, t0, t1)?;
+                asm.emit_neon_narrow(NeonNarrowOp::Uqxtn, I64x2, t1, t1)?;
+            }
+            V128UnOp::I64x2Abs => asm.emit_neon_unop(NeonUnOp::Abs, I64x2, t0, t1)?,
+            V128UnOp::I64x2Neg => asm.emit_neon_unop(NeonUnOp::Neg, I64x2, t0, t1)?,
+            V128UnOp::I64x2ExtendLowI32x4S => {
+                asm.emit_neon_widen(NeonWidenOp::Sxtl, I32x4, t0, t1)?
+            }
+            V128UnOp::I64x2ExtendHighI32x4S => {
+                asm.emit_neon_widen(NeonWidenOp::Sxtl2, I32x4, t0, t1)?
+            }
+            V128UnOp::I64x2ExtendLowI32x4U => {
+                asm.emit_neon_widen(NeonWidenOp::Uxtl, I32x4, t0, t1)?
+            }
+            V128UnOp::I64x2ExtendHighI32x4U => {
+                asm.emit_neon_widen(NeonWidenOp::Uxtl2, I32x4, t0, t1)?
+            }
+            V128UnOp::F32x4Abs => asm.emit_neon_unop(NeonUnOp::Fabs, F32x4, t0, t1)?,
+            V128UnOp::F32x4Neg => asm.emit_neon_unop(NeonUnOp::Fneg, F32x4, t0, t1)?,
+            V128UnOp::F32x4Sqrt => asm.emit_neon_unop(NeonUnOp::Fsqrt, F32x4, t0, t1)?,
+            V128UnOp::F32x4Ceil => asm.emit_neon_unop(NeonUnOp::Frintp, F32x4, t0, t1)?,
+            V128UnOp::F32x4Floor => asm.emit_neon_unop(NeonUnOp::Frintm, F32x4, t0, t1)?,
+            V128UnOp::F32x4Trunc => asm.emit_neon_unop(NeonUnOp::Frintz, F32x4, t0, t1)?,
+            V128UnOp::F32x4Nearest => asm.emit_neon_unop(NeonUnOp::Frintn, F32x4, t0, t1)?,
+            V128UnOp::F32x4ConvertI32x4S => asm.emit_neon_unop(NeonUnOp::Scvtf, F32x4, t0, t1)?,
+            V128UnOp::F32x4ConvertI32x4U => asm.emit_neon_unop(NeonUnOp::Ucvtf, F32x4, t0, t1)?,
+            V128UnOp::F32x4DemoteF64x2Zero => {
+                asm.emit_neon_narrow(NeonNarrowOp::Fcvtn, F64x2, t0, t1)?
+            }
+            V128UnOp::F64x2Abs => asm.emit_neon_unop(NeonUnOp::Fabs, F64x2, t0, t1)?,
+            V128UnOp::F64x2Neg => asm.emit_neon_unop(NeonUnOp::Fneg, F64x2, t0, t1)?,
+            V128UnOp::F64x2Sqrt => asm.emit_neon_unop(NeonUnOp::Fsqrt, F64x2, t0, t1)?,
+            V128UnOp::F64x2Ceil => asm.emit_neon_unop(NeonUnOp::Frintp, F64x2, t0, t1)?,
+            V128UnOp::F64x2Floor => asm.emit_neon_unop(NeonUnOp::Frintm, F64x2, t0, t1)?,
+            V128UnOp::F64x2Trunc => asm.emit_neon_unop(NeonUnOp::Frintz, F64x2, t0, t1)?,
+            V128UnOp::F64x2Nearest => asm.emit_neon_unop(NeonUnOp::Frintn, F64x2, t0, t1)?,
+            V128UnOp::F64x2ConvertLowI32x4S => {
+                asm.emit_neon_widen(NeonWidenOp::Sxtl, I32x4, t0, t1)?;
+                asm.emit_neon_unop(NeonUnOp::Scvtf, F64x2, t1, t1)?;
+            }
+            V128UnOp::F64x2ConvertLowI32x4U => {
+                asm.emit_neon_widen(NeonWidenOp::Uxtl, I32x4, t0, t1)?;
+                asm.emit_neon_unop(NeonUnOp::Ucvtf, F64x2, t1, t1)?;
+            }
+            V128UnOp::F64x2PromoteLowF32x4 => {
+                asm.emit_neon_widen(NeonWidenOp::Fcvtl, F32x4, t0, t1)?
+            }
+        }
+        self.neon_to_v128(t1, ret)?;
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_binop(
+        &mut self,
+        op: V128BinOp,
+        loc_a: Location,
+        loc_b: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        use V128Shape::*;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let t2 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(loc_a, t0)?;
+        self.v128_to_neon(loc_b, t1)?;
+
+        // Most operators map to a single instruction, possibly with swapped operands
+        // and an inverted result.
+        let simple = match op {
+            V128BinOp::And => Some((NeonOp::And, I8x16, false, false)),
+            V128BinOp::AndNot => Some((NeonOp::Bic, I8x16, false, false)),
+            V128BinOp::Or => Some((NeonOp::Orr, I8x16, false, false)),
+            V128BinOp::Xor => Some((NeonOp::Eor, I8x16, false, false)),
+            // `TBL` yields 0 for out of range indices, as required.
+            V128BinOp::I8x16Swizzle => Some((NeonOp::Tbl, I8x16, false, false)),
+            V128BinOp::I8x16Eq => Some((NeonOp::Cmeq, I8x16, false, false)),
+            V128BinOp::I8x16Ne => Some((NeonOp::Cmeq, I8x16, false, true)),
+            V128BinOp::I8x16LtS => Some((NeonOp::Cmgt, I8x16, true, false)),
+            V128BinOp::I8x16LtU => Some((NeonOp::Cmhi, I8x16, true, false)),
+            V128BinOp::I8x16GtS => Some((NeonOp::Cmgt, I8x16, false, false)),
+            V128BinOp::I8x16GtU => Some((NeonOp::Cmhi, I8x16, false, false)),
+            V128BinOp::I8x16LeS => Some((NeonOp::Cmge, I8x16, true, false)),
+            V128BinOp::I8x16LeU => Some((NeonOp::Cmhs, I8x16, true, false)),
+            V128BinOp::I8x16GeS => Some((NeonOp::Cmge, I8x16, false, false)),
+            V128BinOp::I8x16GeU => Some((NeonOp::Cmhs, I8x16, false, false)),
+            V128BinOp::I16x8Eq => Some((NeonOp::Cmeq, I16x8, false, false)),
+            V128BinOp::I16x8Ne => Some((NeonOp::Cmeq, I16x8, false, true)),
+            V128BinOp::I16x8LtS => Some((NeonOp::Cmgt, I16x8, true, false)),
+            V128BinOp::I16x8LtU => Some((NeonOp::Cmhi, I16x8, true, false)),
+            V128BinOp::I16x8GtS => Some((NeonOp::Cmgt, I16x8, false, false)),
+            V128BinOp::I16x8GtU => Some((NeonOp::Cmhi, I16x8, false, false)),
+            V128BinOp::I16x8LeS => Some((NeonOp::Cmge, I16x8, true, false)),
+            V128BinOp::I16x8LeU => Some((NeonOp::Cmhs, I16x8, true, false)),
+            V128BinOp::I16x8GeS => Some((NeonOp::Cmge, I16x8, false, false)),
+            V128BinOp::I16x8GeU => Some((NeonOp::Cmhs, I16x8, false, false)),
+            V128BinOp::I32x4Eq => Some((NeonOp::Cmeq, I32x4, false, false)),
+            V128BinOp::I32x4Ne => Some((NeonOp::Cmeq, I32x4, false, true)),
+            V128BinOp::I32x4LtS => Some((NeonOp::Cmgt, I32x4, true, false)),
+            V128BinOp::I32x4LtU => Some((NeonOp::Cmhi, I32x4, true, false)),
+            V128BinOp::I32x4GtS => Some((NeonOp::Cmgt, I32x4, false, false)),
+            V128BinOp::I32x4GtU => Some((NeonOp::Cmhi, I32x4, false, false)),
+            V128BinOp::I32x4LeS => Some((NeonOp::Cmge, I32x4, true, false)),
+            V128BinOp::I32x4LeU => Some((NeonOp::Cmhs, I32x4, true, false)),
+            V128BinOp::I32x4GeS => Some((NeonOp::Cmge, I32x4, false, false)),
+            V128BinOp::I32x4GeU => Some((NeonOp::Cmhs, I32x4, false, false)),
+            V128BinOp::I64x2Eq => Some((NeonOp::Cmeq, I64x2, false, false)),
+            V128BinOp::I64x2Ne => Some((NeonOp::Cmeq, I64x2, false, true)),
+            V128BinOp::I64x2LtS => Some((NeonOp::Cmgt, I64x2, true, false)),
+            V128BinOp::I64x2GtS => Some((NeonOp::Cmgt, I64x2, false, false)),
+            V128BinOp::I64x2LeS => Some((NeonOp::Cmge, I64x2, true, false)),
+            V128BinOp::I64x2GeS => Some((NeonOp::Cmge, I64x2, false, false)),
+            V128BinOp::F32x4Eq => Some((NeonOp::Fcmeq, F32x4, false, false)),
+            V128BinOp::F32x4Ne => Some((NeonOp::Fcmeq, F32x4, false, true)),
+            V128BinOp::F32x4Lt => Some((NeonOp::Fcmgt, F32x4, true, false)),
+            V128BinOp::F32x4Gt => Some((NeonOp::Fcmgt, F32x4, false, false)),
+            V128BinOp::F32x4Le => Some((NeonOp::Fcmge, F32x4, true, false)),
+            V128BinOp::F32x4Ge => Some((NeonOp::Fcmge, F32x4, false, false)),
+            V128BinOp::F64x2Eq => Some((NeonOp::Fcmeq, F64x2, false, false)),
+            V128BinOp::F64x2Ne => Some((NeonOp::Fcmeq, F64x2, false, true)),
+            V128BinOp::F64x2Lt => Some((NeonOp::Fcmgt, F64x2, true, false)),
+            V128BinOp::F64x2Gt => Some((NeonOp::Fcmgt, F64x2, false, false)),
+            V128BinOp::F64x2Le => Some((NeonOp::Fcmge, F64x2, true, false)),
+            V128BinOp::F64x2Ge => Some((NeonOp::Fcmge, F64x2, false, false)),
+            V128BinOp::I8x16Add => Some((NeonOp::Add, I8x16, false, false)),
+            V128BinOp::I8x16AddSatS => Some((NeonOp::Sqadd, I8x16, false, false)),
+            V128BinOp::I8x16AddSatU => Some((NeonOp::Uqadd, I8x16, false, false)),
+            V128BinOp::I8x16Sub => Some((NeonOp::Sub, I8x16, false, false)),
+            V128BinOp::I8x16SubSatS => Some((NeonOp::Sqsub, I8x16, false, false)),
+            V128BinOp::I8x16SubSatU => Some((NeonOp::Uqsub, I8x16, false, false)),
+            V128BinOp::I8x16MinS => Some((NeonOp::Smin, I8x16, false, false)),
+            V128BinOp::I8x16MinU => Some((NeonOp::Umin, I8x16, false, false)),
+            V128BinOp::I8x16MaxS => Some((NeonOp::Smax, I8x16, false, false)),
+            V128BinOp::I8x16MaxU => Some((NeonOp::Umax, I8x16, false, false)),
+            V128BinOp::I8x16AvgrU => Some((NeonOp::Urhadd, I8x16, false, false)),
+            V128BinOp::I16x8Q15MulrSatS => Some((NeonOp::Sqrdmulh, I16x8, false, false)),
+            V128BinOp::I16x8Add => Some((NeonOp::Add, I16x8, false, false)),
+            V128BinOp::I16x8AddSatS => Some((NeonOp::Sqadd, I16x8, false, false)),
+            V128BinOp::I16x8AddSatU => Some((NeonOp::Uqadd, I16x8, false, false)),
+            V128BinOp::I16x8Sub => Some((NeonOp::Sub, I16x8, false, false)),
+            V128BinOp::I16x8SubSatS => Some((NeonOp::Sqsub, I16x8, false, false)),
+            V128BinOp::I16x8SubSatU => Some((NeonOp::Uqsub, I16x8, false, false)),
+            V128BinOp::I16x8Mul => Some((NeonOp::Mul, I16x8, false, false)),
+            V128BinOp::I16x8MinS => Some((NeonOp::Smin, I16x8, false, false)),
+            V128BinOp::I16x8MinU => Some((NeonOp::Umin, I16x8, false, false)),
+            V128BinOp::I16x8MaxS => Some((NeonOp::Smax, I16x8, false, false)),
+            V128BinOp::I16x8MaxU => Some((NeonOp::Umax, I16x8, false, false)),
+            V128BinOp::I16x8AvgrU => Some((NeonOp::Urhadd, I16x8, false, false)),
+            V128BinOp::I32x4Add => Some((NeonOp::Add, I32x4, false, false)),
+            V128BinOp::I32x4Sub => Some((NeonOp::Sub, I32x4, false, false)),
+            V128BinOp::I32x4Mul => Some((NeonOp::Mul, I32x4, false, false)),
+            V128BinOp::I32x4MinS => Some((NeonOp::Smin, I32x4, false, false)),
+            V128BinOp::I32x4MinU => Some((NeonOp::Umin, I32x4, false, false)),
+            V128BinOp::I32x4MaxS => Some((NeonOp::Smax, I32x4, false, false)),
+            V128BinOp::I32x4MaxU => Some((NeonOp::Umax, I32x4, false, false)),
+            V128BinOp::I64x2Add => Some((NeonOp::Add, I64x2, false, false)),
+            V128BinOp::I64x2Sub => Some((NeonOp::Sub, I64x2, false, false)),
+            // `FMIN`/`FMAX` propagate NaNs and order -0 below +0, as required.
+            V128BinOp::F32x4Add => Some((NeonOp::Fadd, F32x4, false, false)),
+            V128BinOp::F32x4Sub => Some((NeonOp::Fsub, F32x4, false, false)),
+            V128BinOp::F32x4Mul => Some((NeonOp::Fmul, F32x4, false, false)),
+            V128BinOp::F32x4Div => Some((NeonOp::Fdiv, F32x4, false, false)),
+            V128BinOp::F32x4Min => Some((NeonOp::Fmin, F32x4, false, false)),
+            V128BinOp::F32x4Max => Some((NeonOp::Fmax, F32x4, false, false)),
+            V128BinOp::F64x2Add => Some((NeonOp::Fadd, F64x2, false, false)),
+            V128BinOp::F64x2Sub => Some((NeonOp::Fsub, F64x2, false, false)),
+            V128BinOp::F64x2Mul => Some((NeonOp::Fmul, F64x2, false, false)),
+            V128BinOp::F64x2Div => Some((NeonOp::Fdiv, F64x2, false, false)),
+            V128BinOp::F64x2Min => Some((NeonOp::Fmin, F64x2, false, false)),
+            V128BinOp::F64x2Max => Some((NeonOp::Fmax, F64x2, false, false)),
+            _ => None,
+        };
+
+        if let Some((neon_op, shape, swap, invert)) = simple {
+            let (n, m) = if swap { (t1, t0) } else { (t0, t1) };
+            self.assembler.emit_neon(neon_op, shape, n, m, t2)?;
+            if invert {
+                self.assembler
+                    .emit_neon_unop(NeonUnOp::Not, I8x16, t2, t2)?;
+            }
+        } else {
+            match op {
+                V128BinOp::I8x16NarrowI16x8S | V128BinOp::I16x8NarrowI32x4S => {
+                    let shape = if op == V128BinOp::I8x16NarrowI16x8S {
+                        I16x8
+                    } else {
+                        I32x4
+                    };
+                    self.assembler
+                        .emit_neon_narrow(NeonNarrowOp::Sqxtn, shape, t0, t2)?;
+                    self.assembler
+                        .emit_neon_narrow(NeonNarrowOp::Sqxtn2, shape, t1, t2)?;
+                }
+                V128BinOp::I8x16NarrowI16x8U | V128BinOp::I16x8NarrowI32x4U => {
+                    let shape = if op == V128BinOp::I8x16NarrowI16x8U {
+                        I16x8
+                    } else {
+                        I32x4
+                    };
+                    self.assembler
+                        .emit_neon_narrow(NeonNarrowOp::Sqxtun, shape, t0, t2)?;
+                    self.assembler
+                        .emit_neon_narrow(NeonNarrowOp::Sqxtun2, shape, t1, t2)?;
+                }
+                V128BinOp::I32x4DotI16x8S => {
+                    let t3 = self.acquire_temp_simd_v128()?;
+                    self.assembler
+                        .emit_neon_long(NeonLongOp::Smull, I16x8, t0, t1, t2)?;
+                    self.assembler
+                        .emit_neon_long(NeonLongOp::Smull2, I16x8, t0, t1, t3)?;
+                    self.assembler.emit_neon(NeonOp::Addp, I32x4, t2, t3, t2)?;
+                    self.release_simd(t3);
+                }
+                V128BinOp::I16x8ExtMulLowI8x16S
+                | V128BinOp::I16x8ExtMulHighI8x16S
+                | V128BinOp::I16x8ExtMulLowI8x16U
+                | V128BinOp::I16x8ExtMulHighI8x16U
+                | V128BinOp::I32x4ExtMulLowI16x8S
+                | V128BinOp::I32x4ExtMulHighI16x8S
+                | V128BinOp::I32x4ExtMulLowI16x8U
+                | V128BinOp::I32x4ExtMulHighI16x8U
+                | V128BinOp::I64x2ExtMulLowI32x4S
+                | V128BinOp::I64x2ExtMulHighI32x4S
+                | V128BinOp::I64x2ExtMulLowI32x4U
+                | V128BinOp::I64x2ExtMulHighI32x4U => {
+                    let (long_op, shape) = match op {
+                        V128BinOp::I16x8ExtMulLowI8x16S => (NeonLongOp::Smull, I8x16),
+                        V128BinOp::I16x8ExtMulHighI8x16S => (NeonLongOp::Smull2, I8x16),
+                        V128BinOp::I16x8ExtMulLowI8x16U => (NeonLongOp::Umull, I8x16),
+                        V128BinOp::I16x8ExtMulHighI8x16U => (NeonLongOp::Umull2, I8x16),
+                        V128BinOp::I32x4ExtMulLowI16x8S => (NeonLongOp::Smull, I16x8),
+                        V128BinOp::I32x4ExtMulHighI16x8S => (NeonLongOp::Smull2, I16x8),
+                        V128BinOp::I32x4ExtMulLowI16x8U => (NeonLongOp::Umull, I16x8),
+                        V128BinOp::I32x4ExtMulHighI16x8U => (NeonLongOp::Umull2, I16x8),
+                        V128BinOp::I64x2ExtMulLowI32x4S => (NeonLongOp::Smull, I32x4),
+                        V128BinOp::I64x2ExtMulHighI32x4S => (NeonLongOp::Smull2, I32x4),
+                        V128BinOp::I64x2ExtMulLowI32x4U => (NeonLongOp::Umull, I32x4),
+                        _ => (NeonLongOp::Umull2, I32x4),
+                    };
+                    self.assembler.emit_neon_long(long_op, shape, t0, t1, t2)?;
+                }
+                V128BinOp::I64x2Mul => {
+                    // NEON has no 64 bits lanes multiplication.
+                    let tmp0 = self.acquire_temp_gpr_v128()?;
+                    let tmp1 = self.acquire_temp_gpr_v128()?;
+                    for lane in 0..2 {
+                        self.assembler.emit_neon_umov(I64x2, t0, lane, tmp0)?;
+                        self.assembler.emit_neon_umov(I64x2, t1, lane, tmp1)?;
+                        self.assembler.emit_mul(
+                            Size::S64,
+                            Location::GPR(tmp0),
+                            Location::GPR(tmp1),
+                            Location::GPR(tmp0),
+                        )?;
+                        self.assembler.emit_neon_ins(I64x2, tmp0, lane, t2)?;
+                    }
+                    self.release_gpr(tmp1);
+                    self.release_gpr(tmp0);
+                }
+                V128BinOp::F32x4PMin | V128BinOp::F64x2PMin => {
+                    // b < a ? b : a
+                    let shape = if op == V128BinOp::F32x4PMin {
+                        F32x4
+                    } else {
+                        F64x2
+                    };
+                    self.assembler.emit_neon(NeonOp::Fcmgt, shape, t0, t1, t2)?;
+                    self.assembler.emit_neon(NeonOp::Bsl, shape, t1, t0, t2)?;
+                }
+                V128BinOp::F32x4PMax | V128BinOp::F64x2PMax => {
+                    // a < b ? b : a
+                    let shape = if op == V128BinOp::F32x4PMax {
+                        F32x4
+                    } else {
+                        F64x2
+                    };
+                    self.assembler.emit_neon(NeonOp::Fcmgt, shape, t1, t0, t2)?;
+                    self.assembler.emit_neon(NeonOp::Bsl, shape, t1, t0, t2)?;
+                }
+                _ => codegen_error!("singlepass v128_binop unreachable {:?}", op),
+            }
+        }
+
+        self.neon_to_v128(t2, ret)?;
+        self.release_simd(t2);
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_bitselect(
+        &mut self,
+        loc_a: Location,
+        loc_b: Location,
+        mask: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let t2 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(loc_a, t0)?;
+        self.v128_to_neon(loc_b, t1)?;
+        self.v128_to_neon(mask, t2)?;
+        self.assembler
+            .emit_neon(NeonOp::Bsl, V128Shape::I8x16, t0, t1, t2)?;
+        self.neon_to_v128(t2, ret)?;
+        self.release_simd(t2);
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_shift(
+        &mut self,
+        op: V128ShiftOp,
+        loc: Location,
+        amount: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let shape = op.shape();
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        self.move_location(Size::S32, amount, Location::GPR(tmp))?;
+        self.assembler.emit_and(
+            Size::S32,
+            Location::GPR(tmp),
+            Location::Imm32((shape.lane_bytes() * 8 - 1) as u32),
+            Location::GPR(tmp),
+        )?;
+        self.v128_to_neon(loc, t0)?;
+        // `SSHL`/`USHL` shift right by negative amounts.
+        self.assembler.emit_neon_dup(shape, tmp, t1)?;
+        let neon_op = match op {
+            V128ShiftOp::I8x16Shl
+            | V128ShiftOp::I16x8Shl
+            | V128ShiftOp::I32x4Shl
+            | V128ShiftOp::I64x2Shl => NeonOp::Ushl,
+            V128ShiftOp::I8x16ShrS
+            | V128ShiftOp::I16x8ShrS
+            | V128ShiftOp::I32x4ShrS
+            | V128ShiftOp::I64x2ShrS => {
+                self.assembler
+                    .emit_neon_unop(NeonUnOp::Neg, shape, t1, t1)?;
+                NeonOp::Sshl
+            }
+            V128ShiftOp::I8x16ShrU
+            | V128ShiftOp::I16x8ShrU
+            | V128ShiftOp::I32x4ShrU
+            | V128ShiftOp::I64x2ShrU => {
+                self.assembler
+                    .emit_neon_unop(NeonUnOp::Neg, shape, t1, t1)?;
+                NeonOp::Ushl
+            }
+        };
+        self.assembler.emit_neon(neon_op, shape, t0, t1, t0)?;
+        self.neon_to_v128(t0, ret)?;
+        self.release_simd(t1);
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_test(
+        &mut self,
+        op: V128TestOp,
+        loc: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        use V128Shape::*;
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(loc, t0)?;
+        match op {
+            V128TestOp::AnyTrue
+            | V128TestOp::I8x16AllTrue
+            | V128TestOp::I16x8AllTrue
+            | V128TestOp::I32x4AllTrue
+            | V128TestOp::I64x2AllTrue => {
+                // Reduce to a scalar that is zero when the result is false.
+                let (reduce_op, shape, cond) = match op {
+                    V128TestOp::AnyTrue => (NeonReduceOp::Umaxv, I32x4, Condition::Ne),
+                    V128TestOp::I8x16AllTrue => (NeonReduceOp::Uminv, I8x16, Condition::Ne),
+                    V128TestOp::I16x8AllTrue => (NeonReduceOp::Uminv, I16x8, Condition::Ne),
+                    V128TestOp::I32x4AllTrue => (NeonReduceOp::Uminv, I32x4, Condition::Ne),
+                    _ => {
+                        // Look for a zero lane instead.
+                        self.assembler.emit_neon_movi(false, t1)?;
+                        self.assembler.emit_neon(NeonOp::Cmeq, I64x2, t0, t1, t0)?;
+                        (NeonReduceOp::Umaxv, I32x4, Condition::Eq)
+                    }
+                };
+                self.assembler.emit_neon_reduce(reduce_op, shape, t0, t1)?;
+                self.assembler.emit_neon_umov(shape, t1, 0, tmp)?;
+                self.assembler
+                    .emit_cmp(Size::S32, Location::Imm32(0), Location::GPR(tmp))?;
+                self.assembler
+                    .emit_cset(Size::S32, Location::GPR(tmp), cond)?;
+            }
+            V128TestOp::I8x16Bitmask
+            | V128TestOp::I16x8Bitmask
+            | V128TestOp::I32x4Bitmask
+            | V128TestOp::I64x2Bitmask => {
+                let shape = match op {
+                    V128TestOp::I8x16Bitmask => I8x16,
+                    V128TestOp::I16x8Bitmask => I16x8,
+                    V128TestOp::I32x4Bitmask => I32x4,
+                    _ => I64x2,
+                };
+                let bits = shape.lane_bytes() * 8;
+                let lanes = 16 / shape.lane_bytes();
+                // Move the sign bit of each lane to bit `lane index` and sum the lanes.
+                // For 8 bits lanes, the bits of the high half go to a second byte.
+                let shifts =
+                    (0..lanes).fold(0u128, |acc, i| acc | (((i % 8) as u128) << (i * bits)));
+                self.assembler.emit_neon_shift_imm(
+                    NeonShiftOp::Ushr,
+                    shape,
+                    bits as u32 - 1,
+                    t0,
+                    t0,
+                )?;
+                self.v128_const_to_neon(shifts, t1)?;
+                self.assembler.emit_neon(NeonOp::Ushl, shape, t0, t1, t0)?;
+                let shape = if shape == I8x16 {
+                    self.assembler.emit_neon_ext(t0, t0, 8, t1)?;
+                    self.assembler.emit_neon(NeonOp::Zip1, I8x16, t0, t1, t0)?;
+                    I16x8
+                } else {
+                    shape
+                };
+                self.assembler
+                    .emit_neon_reduce(NeonReduceOp::Addv, shape, t0, t1)?;
+                self.assembler.emit_neon_umov(shape, t1, 0, tmp)?;
+            }
+        }
+        self.move_location(Size::S32, Location::GPR(tmp), ret)?;
+        self.release_simd(t1);
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_load(
+        &mut self,
+        kind: V128LoadKind,
+        addr: Location,
+        memarg: &MemArg,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            kind.access_size(),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            unaligned_atomic,
+            |this, addr| match kind {
+                V128LoadKind::Full => this.assembler.emit_ldur_q(t0, addr, 0),
+                V128LoadKind::Splat(shape) => this.assembler.emit_ld1r(shape, addr, t0),
+                V128LoadKind::Zero32 => this.assembler.emit_ldr(
+                    Size::S32,
+                    Location::SIMD(t0),
+                    Location::Memory(addr, 0),
+                ),
+                _ => this.assembler.emit_ldr(
+                    Size::S64,
+                    Location::SIMD(t0),
+                    Location::Memory(addr, 0),
+                ),
+            },
+        )?;
+        let extend = match kind {
+            V128LoadKind::Extend8x8S => Some((NeonWidenOp::Sxtl, V128Shape::I8x16)),
+            V128LoadKind::Extend8x8U => Some((NeonWidenOp::Uxtl, V128Shape::I8x16)),
+            V128LoadKind::Extend16x4S => Some((NeonWidenOp::Sxtl, V128Shape::I16x8)),
+            V128LoadKind::Extend16x4U => Some((NeonWidenOp::Uxtl, V128Shape::I16x8)),
+            V128LoadKind::Extend32x2S => Some((NeonWidenOp::Sxtl, V128Shape::I32x4)),
+            V128LoadKind::Extend32x2U => Some((NeonWidenOp::Uxtl, V128Shape::I32x4)),
+            _ => None,
+        };
+        if let Some((widen_op, shape)) = extend {
+            self.assembler.emit_neon_widen(widen_op, shape, t0, t0)?;
+        }
+        self.neon_to_v128(t0, ret)?;
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_save(
+        &mut self,
+        value: Location,
+        memarg: &MemArg,
+        addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(value, t0)?;
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            16,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            unaligned_atomic,
+            |this, addr| this.assembler.emit_stur_q(t0, addr, 0),
+        )?;
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_load_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        addr: Location,
+        memarg: &MemArg,
+        loc: Location,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(loc, t0)?;
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            shape.lane_bytes(),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            unaligned_atomic,
+            |this, addr| {
+                let src = Location::Memory(addr, 0);
+                match shape.lane_size() {
+                    Size::S8 => this.assembler.emit_ldrb(Size::S32, Location::GPR(tmp), src),
+                    Size::S16 => this.assembler.emit_ldrh(Size::S32, Location::GPR(tmp), src),
+                    sz => this.assembler.emit_ldr(sz, Location::GPR(tmp), src),
+                }
+            },
+        )?;
+        self.assembler.emit_neon_ins(shape, tmp, lane as u32, t0)?;
+        self.neon_to_v128(t0, ret)?;
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_save_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        value: Location,
+        memarg: &MemArg,
+        addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        self.v128_to_neon(value, t0)?;
+        self.assembler.emit_neon_umov(shape, t0, lane as u32, tmp)?;
+        self.release_simd(t0);
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            shape.lane_bytes(),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            unaligned_atomic,
+            |this, addr| {
+                let dst = Location::Memory(addr, 0);
+                match shape.lane_size() {
+                    Size::S8 => this.assembler.emit_strb(Size::S32, Location::GPR(tmp), dst),
+                    Size::S16 => this.assembler.emit_strh(Size::S32, Location::GPR(tmp), dst),
+                    sz => this.assembler.emit_str(sz, Location::GPR(tmp), dst),
+                }
+            },
+        )?;
+        self.release_gpr(tmp);
+        Ok(())
+    }
     fn gen_std_trampoline(
         &self,
         sig: &FunctionType,
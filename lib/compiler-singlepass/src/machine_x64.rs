@@ -626,6 +626,735 @@ impl MachineX86_64 {
         Ok(())
     }
 
+    fn acquire_temp_simd_v128(&mut self) -> Result<XMM, CompileError> {
+        self.acquire_temp_simd()
+            .ok_or_else(|| CompileError::Codegen("singlepass cannot acquire temp simd".to_owned()))
+    }
+
+    fn acquire_temp_gpr_v128(&mut self) -> Result<GPR, CompileError> {
+        self.acquire_temp_gpr()
+            .ok_or_else(|| CompileError::Codegen("singlepass cannot acquire temp gpr".to_owned()))
+    }
+
+    /// Loads a v128 value in an XMM register.
+    fn v128_to_xmm(&mut self, loc: Location, dst: XMM) -> Result<(), CompileError> {
+        match loc {
+            Location::Memory(base, disp) => self
+                .assembler
+                .emit_movdqu(XMMOrMemory::Memory(base, disp), XMMOrMemory::XMM(dst)),
+            Location::SIMD(x) if x == dst => Ok(()),
+            Location::SIMD(x) => self.assembler.emit_sse(SseOp::Movdqa, x, dst),
+            _ => codegen_error!("singlepass can't load v128 from {:?}", loc),
+        }
+    }
+
+    /// Stores an XMM register to the location of a v128 value.
+    fn xmm_to_v128(&mut self, src: XMM, loc: Location) -> Result<(), CompileError> {
+        match loc {
+            Location::Memory(base, disp) => self
+                .assembler
+                .emit_movdqu(XMMOrMemory::XMM(src), XMMOrMemory::Memory(base, disp)),
+            Location::SIMD(x) if x == src => Ok(()),
+            Location::SIMD(x) => self.assembler.emit_sse(SseOp::Movdqa, src, x),
+            _ => codegen_error!("singlepass can't store v128 to {:?}", loc),
+        }
+    }
+
+    /// Materializes a 128 bits constant in an XMM register.
+    fn v128_const_to_xmm(&mut self, value: u128, dst: XMM) -> Result<(), CompileError> {
+        if value == 0 {
+            return self.assembler.emit_sse(SseOp::Pxor, dst, dst);
+        }
+        if value == u128::MAX {
+            return self.assembler.emit_sse(SseOp::Pcmpeqd, dst, dst);
+        }
+        let tmp = self.acquire_temp_gpr_v128()?;
+        self.assembler
+            .emit_mov(Size::S64, Location::Imm64(value as u64), Location::GPR(tmp))?;
+        self.assembler
+            .emit_mov(Size::S64, Location::GPR(tmp), Location::SIMD(dst))?;
+        self.assembler.emit_mov(
+            Size::S64,
+            Location::Imm64((value >> 64) as u64),
+            Location::GPR(tmp),
+        )?;
+        self.assembler.emit_pinsr(Size::S64, tmp, 1, dst)?;
+        self.release_gpr(tmp);
+        Ok(())
+    }
+
+    /// Materializes a constant with all the lanes set to `value`.
+    fn v128_splat_const_to_xmm(
+        &mut self,
+        shape: V128Shape,
+        value: u64,
+        dst: XMM,
+    ) -> Result<(), CompileError> {
+        let bits = shape.lane_bytes() * 8;
+        let lane = value as u128 & (u128::MAX >> (128 - bits));
+        let splat = (0..128 / bits).fold(0u128, |acc, i| acc | (lane << (i * bits)));
+        self.v128_const_to_xmm(splat, dst)
+    }
+
+    /// Copies the lowest lane of `dst` to all its lanes. `tmp` is clobbered.
+    fn v128_broadcast_xmm(
+        &mut self,
+        shape: V128Shape,
+        dst: XMM,
+        tmp: XMM,
+    ) -> Result<(), CompileError> {
+        match shape {
+            V128Shape::I8x16 => {
+                self.assembler.emit_sse(SseOp::Pxor, tmp, tmp)?;
+                self.assembler.emit_sse(SseOp::Pshufb, tmp, dst)
+            }
+            V128Shape::I16x8 => {
+                self.assembler
+                    .emit_sse_imm(SseImmOp::Pshuflw, 0, dst, dst)?;
+                self.assembler.emit_sse(SseOp::Punpcklqdq, dst, dst)
+            }
+            V128Shape::I32x4 | V128Shape::F32x4 => {
+                self.assembler.emit_sse_imm(SseImmOp::Pshufd, 0, dst, dst)
+            }
+            V128Shape::I64x2 | V128Shape::F64x2 => {
+                self.assembler.emit_sse(SseOp::Punpcklqdq, dst, dst)
+            }
+        }
+    }
+
+    /// Inverts all the bits of `dst`. `tmp` is clobbered.
+    fn v128_not_xmm(&mut self, dst: XMM, tmp: XMM) -> Result<(), CompileError> {
+        self.assembler.emit_sse(SseOp::Pcmpeqd, tmp, tmp)?;
+        self.assembler.emit_sse(SseOp::Pxor, tmp, dst)
+    }
+
+    /// Location of a lane of a v128 value held in memory.
+    fn v128_lane_location(
+        loc: Location,
+        shape: V128Shape,
+        lane: u8,
+    ) -> Result<Location, CompileError> {
+        match loc {
+            Location::Memory(base, disp) => Ok(Location::Memory(
+                base,
+                disp + (lane as usize * shape.lane_bytes()) as i32,
+            )),
+            _ => codegen_error!("singlepass v128 lane of {:?}", loc),
+        }
+    }
+
+    /// Computes `v128_binop` from `a` in `t0` and `b` in `t1`, and returns the register
+    /// holding the result. `t2` can be clobbered.
+    fn v128_binop_xmm(
+        &mut self,
+        op: V128BinOp,
+        t0: XMM,
+        t1: XMM,
+        t2: XMM,
+    ) -> Result<XMM, CompileError> {
+        let simple = match op {
+            V128BinOp::And => Some(SseOp::Pand),
+            V128BinOp::Or => Some(SseOp::Por),
+            V128BinOp::Xor => Some(SseOp::Pxor),
+            V128BinOp::I8x16Eq => Some(SseOp::Pcmpeqb),
+            V128BinOp::I16x8Eq => Some(SseOp::Pcmpeqw),
+            V128BinOp::I32x4Eq => Some(SseOp::Pcmpeqd),
+            V128BinOp::I64x2Eq => Some(SseOp::Pcmpeqq),
+            V128BinOp::I8x16GtS => Some(SseOp::Pcmpgtb),
+            V128BinOp::I16x8GtS => Some(SseOp::Pcmpgtw),
+            V128BinOp::I32x4GtS => Some(SseOp::Pcmpgtd),
+            V128BinOp::I64x2GtS => Some(SseOp::Pcmpgtq),
+            V128BinOp::I8x16NarrowI16x8S => Some(SseOp::Packsswb),
+            V128BinOp::I8x16NarrowI16x8U => Some(SseOp::Packuswb),
+            V128BinOp::I16x8NarrowI32x4S => Some(SseOp::Packssdw),
+            V128BinOp::I16x8NarrowI32x4U => Some(SseOp::Packusdw),
+            V128BinOp::I8x16Add => Some(SseOp::Paddb),
+            V128BinOp::I8x16AddSatS => Some(SseOp::Paddsb),
+            V128BinOp::I8x16AddSatU => Some(SseOp::Paddusb),
+            V128BinOp::I8x16Sub => Some(SseOp::Psubb),
+            V128BinOp::I8x16SubSatS => Some(SseOp::Psubsb),
+            V128BinOp::I8x16SubSatU => Some(SseOp::Psubusb),
+            V128BinOp::I8x16MinS => Some(SseOp::Pminsb),
+            V128BinOp::I8x16MinU => Some(SseOp::Pminub),
+            V128BinOp::I8x16MaxS => Some(SseOp::Pmaxsb),
+            V128BinOp::I8x16MaxU => Some(SseOp::Pmaxub),
+            V128BinOp::I8x16AvgrU => Some(SseOp::Pavgb),
+            V128BinOp::I16x8Add => Some(SseOp::Paddw),
+            V128BinOp::I16x8AddSatS => Some(SseOp::Paddsw),
+            V128BinOp::I16x8AddSatU => Some(SseOp::Paddusw),
+            V128BinOp::I16x8Sub => Some(SseOp::Psubw),
+            V128BinOp::I16x8SubSatS => Some(SseOp::Psubsw),
+            V128BinOp::I16x8SubSatU => Some(SseOp::Psubusw),
+            V128BinOp::I16x8Mul => Some(SseOp::Pmullw),
+            V128BinOp::I16x8MinS => Some(SseOp::Pminsw),
+            V128BinOp::I16x8MinU => Some(SseOp::Pminuw),
+            V128BinOp::I16x8MaxS => Some(SseOp::Pmaxsw),
+            V128BinOp::I16x8MaxU => Some(SseOp::Pmaxuw),
+            V128BinOp::I16x8AvgrU => Some(SseOp::Pavgw),
+            V128BinOp::I32x4Add => Some(SseOp::Paddd),
+            V128BinOp::I32x4Sub => Some(SseOp::Psubd),
+            V128BinOp::I32x4Mul => Some(SseOp::Pmulld),
+            V128BinOp::I32x4MinS => Some(SseOp::Pminsd),
+            V128BinOp::I32x4MinU => Some(SseOp::Pminud),
+            V128BinOp::I32x4MaxS => Some(SseOp::Pmaxsd),
+            V128BinOp::I32x4MaxU => Some(SseOp::Pmaxud),
+            V128BinOp::I32x4DotI16x8S => Some(SseOp::Pmaddwd),
+            V128BinOp::I64x2Add => Some(SseOp::Paddq),
+            V128BinOp::I64x2Sub => Some(SseOp::Psubq),
+            V128BinOp::F32x4Add => Some(SseOp::Addps),
+            V128BinOp::F32x4Sub => Some(SseOp::Subps),
+            V128BinOp::F32x4Mul => Some(SseOp::Mulps),
+            V128BinOp::F32x4Div => Some(SseOp::Divps),
+            V128BinOp::F64x2Add => Some(SseOp::Addpd),
+            V128BinOp::F64x2Sub => Some(SseOp::Subpd),
+            V128BinOp::F64x2Mul => Some(SseOp::Mulpd),
+            V128BinOp::F64x2Div => Some(SseOp::Divpd),
+            _ => None,
+        };
+        if let Some(sse_op) = simple {
+            self.assembler.emit_sse(sse_op, t1, t0)?;
+            return Ok(t0);
+        }
+
+        match op {
+            V128BinOp::AndNot => {
+                self.assembler.emit_sse(SseOp::Pandn, t0, t1)?;
+                Ok(t1)
+            }
+            V128BinOp::I8x16Swizzle => {
+                // Indices above 15 get their top bit set, so `pshufb` zeroes their lane.
+                self.v128_splat_const_to_xmm(V128Shape::I8x16, 0x70, t2)?;
+                self.assembler.emit_sse(SseOp::Paddusb, t2, t1)?;
+                self.assembler.emit_sse(SseOp::Pshufb, t1, t0)?;
+                Ok(t0)
+            }
+            V128BinOp::I8x16Ne | V128BinOp::I16x8Ne | V128BinOp::I32x4Ne | V128BinOp::I64x2Ne => {
+                let eq = match op {
+                    V128BinOp::I8x16Ne => SseOp::Pcmpeqb,
+                    V128BinOp::I16x8Ne => SseOp::Pcmpeqw,
+                    V128BinOp::I32x4Ne => SseOp::Pcmpeqd,
+                    _ => SseOp::Pcmpeqq,
+                };
+                self.assembler.emit_sse(eq, t1, t0)?;
+                self.v128_not_xmm(t0, t2)?;
+                Ok(t0)
+            }
+            V128BinOp::I8x16LtS
+            | V128BinOp::I16x8LtS
+            | V128BinOp::I32x4LtS
+            | V128BinOp::I64x2LtS
+            | V128BinOp::I8x16GeS
+            | V128BinOp::I16x8GeS
+            | V128BinOp::I32x4GeS
+            | V128BinOp::I64x2GeS => {
+                let gt = match op {
+                    V128BinOp::I8x16LtS | V128BinOp::I8x16GeS => SseOp::Pcmpgtb,
+                    V128BinOp::I16x8LtS | V128BinOp::I16x8GeS => SseOp::Pcmpgtw,
+                    V128BinOp::I32x4LtS | V128BinOp::I32x4GeS => SseOp::Pcmpgtd,
+                    _ => SseOp::Pcmpgtq,
+                };
+                // a < b <=> b > a
+                self.assembler.emit_sse(gt, t0, t1)?;
+                if matches!(
+                    op,
+                    V128BinOp::I8x16GeS
+                        | V128BinOp::I16x8GeS
+                        | V128BinOp::I32x4GeS
+                        | V128BinOp::I64x2GeS
+                ) {
+                    self.v128_not_xmm(t1, t2)?;
+                }
+                Ok(t1)
+            }
+            V128BinOp::I8x16LeS
+            | V128BinOp::I16x8LeS
+            | V128BinOp::I32x4LeS
+            | V128BinOp::I64x2LeS => {
+                let gt = match op {
+                    V128BinOp::I8x16LeS => SseOp::Pcmpgtb,
+                    V128BinOp::I16x8LeS => SseOp::Pcmpgtw,
+                    V128BinOp::I32x4LeS => SseOp::Pcmpgtd,
+                    _ => SseOp::Pcmpgtq,
+                };
+                self.assembler.emit_sse(gt, t1, t0)?;
+                self.v128_not_xmm(t0, t2)?;
+                Ok(t0)
+            }
+            V128BinOp::I8x16GeU
+            | V128BinOp::I16x8GeU
+            | V128BinOp::I32x4GeU
+            | V128BinOp::I8x16LtU
+            | V128BinOp::I16x8LtU
+            | V128BinOp::I32x4LtU
+            | V128BinOp::I8x16LeU
+            | V128BinOp::I16x8LeU
+            | V128BinOp::I32x4LeU
+            | V128BinOp::I8x16GtU
+            | V128BinOp::I16x8GtU
+            | V128BinOp::I32x4GtU => {
+                // a >= b <=> max(a, b) == a, and a <= b <=> min(a, b) == a
+                let (minmax, eq) = match op {
+                    V128BinOp::I8x16GeU | V128BinOp::I8x16LtU => (SseOp::Pmaxub, SseOp::Pcmpeqb),
+                    V128BinOp::I16x8GeU | V128BinOp::I16x8LtU => (SseOp::Pmaxuw, SseOp::Pcmpeqw),
+                    V128BinOp::I32x4GeU | V128BinOp::I32x4LtU => (SseOp::Pmaxud, SseOp::Pcmpeqd),
+                    V128BinOp::I8x16LeU | V128BinOp::I8x16GtU => (SseOp::Pminub, SseOp::Pcmpeqb),
+                    V128BinOp::I16x8LeU | V128BinOp::I16x8GtU => (SseOp::Pminuw, SseOp::Pcmpeqw),
+                    _ => (SseOp::Pminud, SseOp::Pcmpeqd),
+                };
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t2)?;
+                self.assembler.emit_sse(minmax, t1, t2)?;
+                self.assembler.emit_sse(eq, t2, t0)?;
+                if matches!(
+                    op,
+                    V128BinOp::I8x16LtU
+                        | V128BinOp::I16x8LtU
+                        | V128BinOp::I32x4LtU
+                        | V128BinOp::I8x16GtU
+                        | V128BinOp::I16x8GtU
+                        | V128BinOp::I32x4GtU
+                ) {
+                    self.v128_not_xmm(t0, t2)?;
+                }
+                Ok(t0)
+            }
+            V128BinOp::F32x4Eq
+            | V128BinOp::F32x4Ne
+            | V128BinOp::F32x4Lt
+            | V128BinOp::F32x4Le
+            | V128BinOp::F64x2Eq
+            | V128BinOp::F64x2Ne
+            | V128BinOp::F64x2Lt
+            | V128BinOp::F64x2Le => {
+                let (cmp, imm) = match op {
+                    V128BinOp::F32x4Eq => (SseImmOp::Cmpps, 0),
+                    V128BinOp::F32x4Lt => (SseImmOp::Cmpps, 1),
+                    V128BinOp::F32x4Le => (SseImmOp::Cmpps, 2),
+                    V128BinOp::F32x4Ne => (SseImmOp::Cmpps, 4),
+                    V128BinOp::F64x2Eq => (SseImmOp::Cmppd, 0),
+                    V128BinOp::F64x2Lt => (SseImmOp::Cmppd, 1),
+                    V128BinOp::F64x2Le => (SseImmOp::Cmppd, 2),
+                    _ => (SseImmOp::Cmppd, 4),
+                };
+                self.assembler.emit_sse_imm(cmp, imm, t1, t0)?;
+                Ok(t0)
+            }
+            V128BinOp::F32x4Gt | V128BinOp::F32x4Ge | V128BinOp::F64x2Gt | V128BinOp::F64x2Ge => {
+                // a > b <=> b < a
+                let (cmp, imm) = match op {
+                    V128BinOp::F32x4Gt => (SseImmOp::Cmpps, 1),
+                    V128BinOp::F32x4Ge => (SseImmOp::Cmpps, 2),
+                    V128BinOp::F64x2Gt => (SseImmOp::Cmppd, 1),
+                    _ => (SseImmOp::Cmppd, 2),
+                };
+                self.assembler.emit_sse_imm(cmp, imm, t0, t1)?;
+                Ok(t1)
+            }
+            V128BinOp::I16x8Q15MulrSatS => {
+                // `pmulhrsw` only overflows for 0x8000 * 0x8000, giving 0x8000 instead of 0x7fff.
+                self.assembler.emit_sse(SseOp::Pmulhrsw, t1, t0)?;
+                self.v128_splat_const_to_xmm(V128Shape::I16x8, 0x8000, t2)?;
+                self.assembler.emit_sse(SseOp::Pcmpeqw, t0, t2)?;
+                self.assembler.emit_sse(SseOp::Pxor, t2, t0)?;
+                Ok(t0)
+            }
+            V128BinOp::I64x2Mul => {
+                let tmp_a = self.acquire_temp_gpr_v128()?;
+                let tmp_b = self.acquire_temp_gpr_v128()?;
+                for lane in 0..2 {
+                    self.assembler.emit_pextr(Size::S64, t0, lane, tmp_a)?;
+                    self.assembler.emit_pextr(Size::S64, t1, lane, tmp_b)?;
+                    self.assembler.emit_imul(
+                        Size::S64,
+                        Location::GPR(tmp_b),
+                        Location::GPR(tmp_a),
+                    )?;
+                    self.assembler.emit_pinsr(Size::S64, tmp_a, lane, t2)?;
+                }
+                self.release_gpr(tmp_b);
+                self.release_gpr(tmp_a);
+                Ok(t2)
+            }
+            V128BinOp::I16x8ExtMulLowI8x16S
+            | V128BinOp::I16x8ExtMulHighI8x16S
+            | V128BinOp::I16x8ExtMulLowI8x16U
+            | V128BinOp::I16x8ExtMulHighI8x16U
+            | V128BinOp::I32x4ExtMulLowI16x8S
+            | V128BinOp::I32x4ExtMulHighI16x8S
+            | V128BinOp::I32x4ExtMulLowI16x8U
+            | V128BinOp::I32x4ExtMulHighI16x8U => {
+                let (high, extend, mul) = match op {
+                    V128BinOp::I16x8ExtMulLowI8x16S => (false, SseOp::Pmovsxbw, SseOp::Pmullw),
+                    V128BinOp::I16x8ExtMulHighI8x16S => (true, SseOp::Pmovsxbw, SseOp::Pmullw),
+                    V128BinOp::I16x8ExtMulLowI8x16U => (false, SseOp::Pmovzxbw, SseOp::Pmullw),
+                    V128BinOp::I16x8ExtMulHighI8x16U => (true, SseOp::Pmovzxbw, SseOp::Pmullw),
+                    V128BinOp::I32x4ExtMulLowI16x8S => (false, SseOp::Pmovsxwd, SseOp::Pmulld),
+                    V128BinOp::I32x4ExtMulHighI16x8S => (true, SseOp::Pmovsxwd, SseOp::Pmulld),
+                    V128BinOp::I32x4ExtMulLowI16x8U => (false, SseOp::Pmovzxwd, SseOp::Pmulld),
+                    _ => (true, SseOp::Pmovzxwd, SseOp::Pmulld),
+                };
+                for t in [t0, t1] {
+                    if high {
+                        self.assembler.emit_sse_imm(SseImmOp::Pshufd, 0xee, t, t)?;
+                    }
+                    self.assembler.emit_sse(extend, t, t)?;
+                }
+                self.assembler.emit_sse(mul, t1, t0)?;
+                Ok(t0)
+            }
+            V128BinOp::I64x2ExtMulLowI32x4S
+            | V128BinOp::I64x2ExtMulHighI32x4S
+            | V128BinOp::I64x2ExtMulLowI32x4U
+            | V128BinOp::I64x2ExtMulHighI32x4U => {
+                // Move the lanes to the low half of each 64 bits lane, then multiply them.
+                let (shuffle, mul) = match op {
+                    V128BinOp::I64x2ExtMulLowI32x4S => (0x50, SseOp::Pmuldq),
+                    V128BinOp::I64x2ExtMulHighI32x4S => (0xfa, SseOp::Pmuldq),
+                    V128BinOp::I64x2ExtMulLowI32x4U => (0x50, SseOp::Pmuludq),
+                    _ => (0xfa, SseOp::Pmuludq),
+                };
+                self.assembler
+                    .emit_sse_imm(SseImmOp::Pshufd, shuffle, t0, t0)?;
+                self.assembler
+                    .emit_sse_imm(SseImmOp::Pshufd, shuffle, t1, t1)?;
+                self.assembler.emit_sse(mul, t1, t0)?;
+                Ok(t0)
+            }
+            V128BinOp::F32x4Min | V128BinOp::F64x2Min => {
+                // `minps` returns its second operand if any is NaN, and doesn't order -0 and
+                // +0: compute it both ways and merge the results.
+                let (min, or, andn, cmp, shift, nan_shift) = if op == V128BinOp::F32x4Min {
+                    (
+                        SseOp::Minps,
+                        SseOp::Orps,
+                        SseOp::Andnps,
+                        SseImmOp::Cmpps,
+                        SseShiftOp::Psrld,
+                        10,
+                    )
+                } else {
+                    (
+                        SseOp::Minpd,
+                        SseOp::Orpd,
+                        SseOp::Andnpd,
+                        SseImmOp::Cmppd,
+                        SseShiftOp::Psrlq,
+                        13,
+                    )
+                };
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t2)?;
+                self.assembler.emit_sse(min, t1, t2)?;
+                self.assembler.emit_sse(min, t0, t1)?;
+                self.assembler.emit_sse(or, t1, t2)?;
+                // Canonicalize NaNs.
+                self.assembler.emit_sse(SseOp::Movdqa, t2, t0)?;
+                self.assembler.emit_sse_imm(cmp, 3, t1, t0)?;
+                self.assembler.emit_sse(or, t0, t2)?;
+                self.assembler.emit_sse_shift_imm(shift, nan_shift, t0)?;
+                self.assembler.emit_sse(andn, t2, t0)?;
+                Ok(t0)
+            }
+            V128BinOp::F32x4Max | V128BinOp::F64x2Max => {
+                let (max, xor, or, sub, andn, cmp, shift, nan_shift) = if op == V128BinOp::F32x4Max
+                {
+                    (
+                        SseOp::Maxps,
+                        SseOp::Xorps,
+                        SseOp::Orps,
+                        SseOp::Subps,
+                        SseOp::Andnps,
+                        SseImmOp::Cmpps,
+                        SseShiftOp::Psrld,
+                        10,
+                    )
+                } else {
+                    (
+                        SseOp::Maxpd,
+                        SseOp::Xorpd,
+                        SseOp::Orpd,
+                        SseOp::Subpd,
+                        SseOp::Andnpd,
+                        SseImmOp::Cmppd,
+                        SseShiftOp::Psrlq,
+                        13,
+                    )
+                };
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t2)?;
+                self.assembler.emit_sse(max, t1, t2)?;
+                self.assembler.emit_sse(max, t0, t1)?;
+                // The results only differ for NaNs and zeros of different signs.
+                self.assembler.emit_sse(xor, t2, t1)?;
+                self.assembler.emit_sse(or, t1, t2)?;
+                self.assembler.emit_sse(SseOp::Movdqa, t2, t0)?;
+                self.assembler.emit_sse(sub, t1, t0)?;
+                // Canonicalize NaNs.
+                self.assembler.emit_sse_imm(cmp, 3, t2, t2)?;
+                self.assembler.emit_sse_shift_imm(shift, nan_shift, t2)?;
+                self.assembler.emit_sse(andn, t0, t2)?;
+                Ok(t2)
+            }
+            V128BinOp::F32x4PMin => {
+                self.assembler.emit_sse(SseOp::Minps, t0, t1)?;
+                Ok(t1)
+            }
+            V128BinOp::F32x4PMax => {
+                self.assembler.emit_sse(SseOp::Maxps, t0, t1)?;
+                Ok(t1)
+            }
+            V128BinOp::F64x2PMin => {
+                self.assembler.emit_sse(SseOp::Minpd, t0, t1)?;
+                Ok(t1)
+            }
+            V128BinOp::F64x2PMax => {
+                self.assembler.emit_sse(SseOp::Maxpd, t0, t1)?;
+                Ok(t1)
+            }
+            _ => codegen_error!("singlepass v128_binop unreachable"),
+        }
+    }
+
+    /// Computes `v128_unop` from the operand in `t0`, and returns the register holding
+    /// the result. `t1` and `t2` can be clobbered.
+    fn v128_unop_xmm(
+        &mut self,
+        op: V128UnOp,
+        t0: XMM,
+        t1: XMM,
+        t2: XMM,
+    ) -> Result<XMM, CompileError> {
+        let simple = match op {
+            V128UnOp::I8x16Abs => Some(SseOp::Pabsb),
+            V128UnOp::I16x8Abs => Some(SseOp::Pabsw),
+            V128UnOp::I32x4Abs => Some(SseOp::Pabsd),
+            V128UnOp::I16x8ExtendLowI8x16S => Some(SseOp::Pmovsxbw),
+            V128UnOp::I16x8ExtendLowI8x16U => Some(SseOp::Pmovzxbw),
+            V128UnOp::I32x4ExtendLowI16x8S => Some(SseOp::Pmovsxwd),
+            V128UnOp::I32x4ExtendLowI16x8U => Some(SseOp::Pmovzxwd),
+            V128UnOp::I64x2ExtendLowI32x4S => Some(SseOp::Pmovsxdq),
+            V128UnOp::I64x2ExtendLowI32x4U => Some(SseOp::Pmovzxdq),
+            V128UnOp::F32x4Sqrt => Some(SseOp::Sqrtps),
+            V128UnOp::F64x2Sqrt => Some(SseOp::Sqrtpd),
+            V128UnOp::F32x4ConvertI32x4S => Some(SseOp::Cvtdq2ps),
+            V128UnOp::F32x4DemoteF64x2Zero => Some(SseOp::Cvtpd2ps),
+            V128UnOp::F64x2ConvertLowI32x4S => Some(SseOp::Cvtdq2pd),
+            V128UnOp::F64x2PromoteLowF32x4 => Some(SseOp::Cvtps2pd),
+            _ => None,
+        };
+        if let Some(sse_op) = simple {
+            self.assembler.emit_sse(sse_op, t0, t0)?;
+            return Ok(t0);
+        }
+        let round = match op {
+            V128UnOp::F32x4Nearest => Some((SseImmOp::Roundps, 0)),
+            V128UnOp::F32x4Floor => Some((SseImmOp::Roundps, 1)),
+            V128UnOp::F32x4Ceil => Some((SseImmOp::Roundps, 2)),
+            V128UnOp::F32x4Trunc => Some((SseImmOp::Roundps, 3)),
+            V128UnOp::F64x2Nearest => Some((SseImmOp::Roundpd, 0)),
+            V128UnOp::F64x2Floor => Some((SseImmOp::Roundpd, 1)),
+            V128UnOp::F64x2Ceil => Some((SseImmOp::Roundpd, 2)),
+            V128UnOp::F64x2Trunc => Some((SseImmOp::Roundpd, 3)),
+            _ => None,
+        };
+        if let Some((sse_op, mode)) = round {
+            self.assembler.emit_sse_imm(sse_op, mode, t0, t0)?;
+            return Ok(t0);
+        }
+
+        match op {
+            V128UnOp::Not => {
+                self.v128_not_xmm(t0, t1)?;
+                Ok(t0)
+            }
+            V128UnOp::I8x16Neg | V128UnOp::I16x8Neg | V128UnOp::I32x4Neg | V128UnOp::I64x2Neg => {
+                let sub = match op {
+                    V128UnOp::I8x16Neg => SseOp::Psubb,
+                    V128UnOp::I16x8Neg => SseOp::Psubw,
+                    V128UnOp::I32x4Neg => SseOp::Psubd,
+                    _ => SseOp::Psubq,
+                };
+                self.assembler.emit_sse(SseOp::Pxor, t1, t1)?;
+                self.assembler.emit_sse(sub, t0, t1)?;
+                Ok(t1)
+            }
+            V128UnOp::I64x2Abs => {
+                // Sign mask, then (x ^ mask) - mask.
+                self.assembler.emit_sse(SseOp::Pxor, t1, t1)?;
+                self.assembler.emit_sse(SseOp::Pcmpgtq, t0, t1)?;
+                self.assembler.emit_sse(SseOp::Pxor, t1, t0)?;
+                self.assembler.emit_sse(SseOp::Psubq, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I8x16Popcnt => {
+                // Look up the number of bits of each nibble in a table.
+                const TABLE: u128 = 0x0403_0302_0302_0201_0302_0201_0201_0100;
+                self.v128_splat_const_to_xmm(V128Shape::I8x16, 0x0f, t2)?;
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t1)?;
+                self.assembler
+                    .emit_sse_shift_imm(SseShiftOp::Psrlw, 4, t1)?;
+                self.assembler.emit_sse(SseOp::Pand, t2, t1)?;
+                self.assembler.emit_sse(SseOp::Pand, t2, t0)?;
+                self.v128_const_to_xmm(TABLE, t2)?;
+                self.assembler.emit_sse(SseOp::Pshufb, t0, t2)?;
+                self.v128_const_to_xmm(TABLE, t0)?;
+                self.assembler.emit_sse(SseOp::Pshufb, t1, t0)?;
+                self.assembler.emit_sse(SseOp::Paddb, t2, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I16x8ExtendHighI8x16S
+            | V128UnOp::I16x8ExtendHighI8x16U
+            | V128UnOp::I32x4ExtendHighI16x8S
+            | V128UnOp::I32x4ExtendHighI16x8U
+            | V128UnOp::I64x2ExtendHighI32x4S
+            | V128UnOp::I64x2ExtendHighI32x4U => {
+                let extend = match op {
+                    V128UnOp::I16x8ExtendHighI8x16S => SseOp::Pmovsxbw,
+                    V128UnOp::I16x8ExtendHighI8x16U => SseOp::Pmovzxbw,
+                    V128UnOp::I32x4ExtendHighI16x8S => SseOp::Pmovsxwd,
+                    V128UnOp::I32x4ExtendHighI16x8U => SseOp::Pmovzxwd,
+                    V128UnOp::I64x2ExtendHighI32x4S => SseOp::Pmovsxdq,
+                    _ => SseOp::Pmovzxdq,
+                };
+                self.assembler
+                    .emit_sse_imm(SseImmOp::Pshufd, 0xee, t0, t0)?;
+                self.assembler.emit_sse(extend, t0, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I16x8ExtAddPairwiseI8x16S => {
+                // `pmaddubsw` multiplies unsigned bytes of its destination by signed bytes.
+                self.v128_splat_const_to_xmm(V128Shape::I8x16, 1, t1)?;
+                self.assembler.emit_sse(SseOp::Pmaddubsw, t0, t1)?;
+                Ok(t1)
+            }
+            V128UnOp::I16x8ExtAddPairwiseI8x16U => {
+                self.v128_splat_const_to_xmm(V128Shape::I8x16, 1, t1)?;
+                self.assembler.emit_sse(SseOp::Pmaddubsw, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I32x4ExtAddPairwiseI16x8S => {
+                self.v128_splat_const_to_xmm(V128Shape::I16x8, 1, t1)?;
+                self.assembler.emit_sse(SseOp::Pmaddwd, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I32x4ExtAddPairwiseI16x8U => {
+                // Bias the lanes to signed values, add them, then remove the bias.
+                self.v128_splat_const_to_xmm(V128Shape::I16x8, 0x8000, t1)?;
+                self.assembler.emit_sse(SseOp::Pxor, t1, t0)?;
+                self.v128_splat_const_to_xmm(V128Shape::I16x8, 1, t1)?;
+                self.assembler.emit_sse(SseOp::Pmaddwd, t1, t0)?;
+                self.v128_splat_const_to_xmm(V128Shape::I32x4, 0x1_0000, t1)?;
+                self.assembler.emit_sse(SseOp::Paddd, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I32x4TruncSatF32x4S => {
+                // Zero the NaN lanes.
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t1)?;
+                self.assembler.emit_sse_imm(SseImmOp::Cmpps, 0, t0, t1)?;
+                self.assembler.emit_sse(SseOp::Andps, t1, t0)?;
+                // Top bit of t1 set for positive lanes.
+                self.assembler.emit_sse(SseOp::Pxor, t0, t1)?;
+                // Overflows give 0x80000000, turn it to 0x7fffffff for positive lanes.
+                self.assembler.emit_sse(SseOp::Cvttps2dq, t0, t0)?;
+                self.assembler.emit_sse(SseOp::Pand, t0, t1)?;
+                self.assembler
+                    .emit_sse_shift_imm(SseShiftOp::Psrad, 31, t1)?;
+                self.assembler.emit_sse(SseOp::Pxor, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I32x4TruncSatF32x4U => {
+                // Clamp NaN and negative lanes to 0.
+                self.assembler.emit_sse(SseOp::Xorps, t2, t2)?;
+                self.assembler.emit_sse(SseOp::Maxps, t2, t0)?;
+                // t2 = 2^31
+                self.assembler.emit_sse(SseOp::Pcmpeqd, t2, t2)?;
+                self.assembler
+                    .emit_sse_shift_imm(SseShiftOp::Psrld, 1, t2)?;
+                self.assembler.emit_sse(SseOp::Cvtdq2ps, t2, t2)?;
+                // Lanes below 2^31.
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t1)?;
+                self.assembler.emit_sse(SseOp::Cvttps2dq, t1, t1)?;
+                // Lanes above 2^31, saturated to 0x7fffffff past 2^32.
+                self.assembler.emit_sse(SseOp::Subps, t2, t0)?;
+                self.assembler.emit_sse_imm(SseImmOp::Cmpps, 2, t0, t2)?;
+                self.assembler.emit_sse(SseOp::Cvttps2dq, t0, t0)?;
+                self.assembler.emit_sse(SseOp::Pxor, t2, t0)?;
+                self.assembler.emit_sse(SseOp::Pxor, t2, t2)?;
+                self.assembler.emit_sse(SseOp::Pmaxsd, t2, t0)?;
+                self.assembler.emit_sse(SseOp::Paddd, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I32x4TruncSatF64x2SZero => {
+                // Clamp to i32::MAX, with NaN lanes to 0.
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t1)?;
+                self.assembler.emit_sse_imm(SseImmOp::Cmppd, 0, t0, t1)?;
+                self.v128_splat_const_to_xmm(V128Shape::F64x2, 0x41df_ffff_ffc0_0000, t2)?;
+                self.assembler.emit_sse(SseOp::Andpd, t2, t1)?;
+                self.assembler.emit_sse(SseOp::Minpd, t1, t0)?;
+                self.assembler.emit_sse(SseOp::Cvttpd2dq, t0, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::I32x4TruncSatF64x2UZero => {
+                // Clamp to [0, u32::MAX], then use the low bits of `x + 2^52`.
+                self.assembler.emit_sse(SseOp::Xorpd, t1, t1)?;
+                self.assembler.emit_sse(SseOp::Maxpd, t1, t0)?;
+                self.v128_splat_const_to_xmm(V128Shape::F64x2, 0x41ef_ffff_ffe0_0000, t2)?;
+                self.assembler.emit_sse(SseOp::Minpd, t2, t0)?;
+                self.assembler.emit_sse_imm(SseImmOp::Roundpd, 3, t0, t0)?;
+                self.v128_splat_const_to_xmm(V128Shape::F64x2, 0x4330_0000_0000_0000, t2)?;
+                self.assembler.emit_sse(SseOp::Addpd, t2, t0)?;
+                self.assembler
+                    .emit_sse_imm(SseImmOp::Shufps, 0x88, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::F32x4Abs | V128UnOp::F64x2Abs => {
+                let shift = if op == V128UnOp::F32x4Abs {
+                    SseShiftOp::Psrld
+                } else {
+                    SseShiftOp::Psrlq
+                };
+                self.assembler.emit_sse(SseOp::Pcmpeqd, t1, t1)?;
+                self.assembler.emit_sse_shift_imm(shift, 1, t1)?;
+                self.assembler.emit_sse(SseOp::Pand, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::F32x4Neg | V128UnOp::F64x2Neg => {
+                let (shift, amount) = if op == V128UnOp::F32x4Neg {
+                    (SseShiftOp::Pslld, 31)
+                } else {
+                    (SseShiftOp::Psllq, 63)
+                };
+                self.assembler.emit_sse(SseOp::Pcmpeqd, t1, t1)?;
+                self.assembler.emit_sse_shift_imm(shift, amount, t1)?;
+                self.assembler.emit_sse(SseOp::Pxor, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::F32x4ConvertI32x4U => {
+                // Convert the low 16 bits and the high 16 bits separately, so that both
+                // conversions are exact.
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t1)?;
+                self.assembler
+                    .emit_sse_shift_imm(SseShiftOp::Pslld, 16, t1)?;
+                self.assembler
+                    .emit_sse_shift_imm(SseShiftOp::Psrld, 16, t1)?;
+                self.assembler.emit_sse(SseOp::Psubd, t1, t0)?;
+                self.assembler.emit_sse(SseOp::Cvtdq2ps, t1, t1)?;
+                self.assembler
+                    .emit_sse_shift_imm(SseShiftOp::Psrld, 1, t0)?;
+                self.assembler.emit_sse(SseOp::Cvtdq2ps, t0, t0)?;
+                self.assembler.emit_sse(SseOp::Addps, t0, t0)?;
+                self.assembler.emit_sse(SseOp::Addps, t1, t0)?;
+                Ok(t0)
+            }
+            V128UnOp::F64x2ConvertLowI32x4U => {
+                // Build the doubles `2^52 + x`, then subtract `2^52`.
+                self.v128_splat_const_to_xmm(V128Shape::I32x4, 0x4330_0000, t1)?;
+                self.assembler.emit_sse(SseOp::Punpckldq, t1, t0)?;
+                self.v128_splat_const_to_xmm(V128Shape::F64x2, 0x4330_0000_0000_0000, t1)?;
+                self.assembler.emit_sse(SseOp::Subpd, t1, t0)?;
+                Ok(t0)
+            }
+            _ => codegen_error!("singlepass v128_unop unreachable"),
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn emit_compare_and_swap<F: FnOnce(&mut Self, GPR, GPR) -> Result<(), CompileError>>(
         &mut self,
@@ -2539,7 +3268,9 @@ impl Machine for MachineX86_64 {
         canonicalize: bool,
         loc: Location,
     ) -> Result<(), CompileError> {
-        if canonicalize {
+        if ty == WpType::V128 {
+            self.v128_move(loc, Location::SIMD(XMM::XMM0))
+        } else if canonicalize {
             self.canonicalize_nan(
                 match ty {
                     WpType::F32 => Size::S32,
@@ -2656,6 +3387,12 @@ impl Machine for MachineX86_64 {
     fn emit_call_label(&mut self, label: Label) -> Result<(), CompileError> {
         self.assembler.emit_call_label(label)
     }
+    fn get_gpr_for_tail_call(&self) -> GPR {
+        GPR::RAX
+    }
+    fn emit_jmp_register(&mut self, reg: GPR) -> Result<(), CompileError> {
+        self.assembler.emit_jmp_location(Location::GPR(reg))
+    }
     fn get_gpr_for_ret(&self) -> GPR {
         GPR::RAX
     }
@@ -4563,6 +5300,25 @@ impl Machine for MachineX86_64 {
         Ok(relocations)
     }
 
+    fn emit_jmp_with_reloc(
+        &mut self,
+        _calling_convention: CallingConvention,
+        reloc_target: RelocationTarget,
+    ) -> Result<Vec<Relocation>, CompileError> {
+        let mut relocations = vec![];
+        let next = self.get_label();
+        let reloc_at = self.assembler.get_offset().0 + 1; // skip E9
+        self.assembler.emit_jmp(Condition::None, next)?;
+        self.emit_label(next)?;
+        relocations.push(Relocation {
+            kind: RelocationKind::X86CallPCRel4,
+            reloc_target,
+            offset: reloc_at as u32,
+            addend: -4,
+        });
+        Ok(relocations)
+    }
+
     fn emit_binop_add64(
         &mut self,
         loc_a: Location,
@@ -7834,6 +8590,522 @@ impl Machine for MachineX86_64 {
         self.emit_relaxed_avx(AssemblerX64::emit_vdivss, loc_a, loc_b, ret)
     }
 
+    fn v128_move(&mut self, src: Location, dst: Location) -> Result<(), CompileError> {
+        if src == dst {
+            return Ok(());
+        }
+        match (src, dst) {
+            (Location::SIMD(x), _) => self.xmm_to_v128(x, dst),
+            (_, Location::SIMD(x)) => self.v128_to_xmm(src, x),
+            _ => {
+                let tmp = self.acquire_temp_simd_v128()?;
+                self.v128_to_xmm(src, tmp)?;
+                self.xmm_to_v128(tmp, dst)?;
+                self.release_simd(tmp);
+                Ok(())
+            }
+        }
+    }
+    fn v128_const(&mut self, value: u128, ret: Location) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_simd_v128()?;
+        self.v128_const_to_xmm(value, tmp)?;
+        self.xmm_to_v128(tmp, ret)?;
+        self.release_simd(tmp);
+        Ok(())
+    }
+    fn v128_splat(
+        &mut self,
+        shape: V128Shape,
+        loc: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let sz = match shape {
+            V128Shape::I64x2 | V128Shape::F64x2 => Size::S64,
+            _ => Size::S32,
+        };
+        self.move_location(sz, loc, Location::GPR(tmp))?;
+        self.assembler
+            .emit_mov(sz, Location::GPR(tmp), Location::SIMD(t0))?;
+        self.v128_broadcast_xmm(shape, t0, t1)?;
+        self.xmm_to_v128(t0, ret)?;
+        self.release_simd(t1);
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_extract_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        signed: bool,
+        loc: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let src = Self::v128_lane_location(loc, shape, lane)?;
+        match shape {
+            V128Shape::I8x16 | V128Shape::I16x8 => {
+                let tmp = self.acquire_temp_gpr_v128()?;
+                if signed {
+                    self.assembler.emit_movsx(
+                        shape.lane_size(),
+                        src,
+                        Size::S32,
+                        Location::GPR(tmp),
+                    )?;
+                } else {
+                    self.assembler.emit_movzx(
+                        shape.lane_size(),
+                        src,
+                        Size::S32,
+                        Location::GPR(tmp),
+                    )?;
+                }
+                self.move_location(Size::S32, Location::GPR(tmp), ret)?;
+                self.release_gpr(tmp);
+                Ok(())
+            }
+            _ => self.emit_relaxed_mov(shape.lane_size(), src, ret),
+        }
+    }
+    fn v128_replace_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        loc: Location,
+        value: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let sz = match shape {
+            V128Shape::I64x2 | V128Shape::F64x2 => Size::S64,
+            _ => Size::S32,
+        };
+        self.move_location(sz, value, Location::GPR(tmp))?;
+        self.v128_move(loc, ret)?;
+        let dst = Self::v128_lane_location(ret, shape, lane)?;
+        self.assembler
+            .emit_mov(shape.lane_size(), Location::GPR(tmp), dst)?;
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_shuffle(
+        &mut self,
+        lanes: [u8; 16],
+        loc_a: Location,
+        loc_b: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        // Select the bytes of each operand with `pshufb`, zeroing the bytes coming from
+        // the other one, then merge them.
+        let (mut mask_a, mut mask_b) = (0u128, 0u128);
+        for (i, lane) in lanes.iter().enumerate() {
+            let (byte_a, byte_b) = if *lane < 16 {
+                (*lane, 0x80)
+            } else {
+                (0x80, *lane - 16)
+            };
+            mask_a |= (byte_a as u128) << (i * 8);
+            mask_b |= (byte_b as u128) << (i * 8);
+        }
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let t2 = self.acquire_temp_simd_v128()?;
+        self.v128_to_xmm(loc_a, t0)?;
+        self.v128_to_xmm(loc_b, t1)?;
+        self.v128_const_to_xmm(mask_a, t2)?;
+        self.assembler.emit_sse(SseOp::Pshufb, t2, t0)?;
+        self.v128_const_to_xmm(mask_b, t2)?;
+        self.assembler.emit_sse(SseOp::Pshufb, t2, t1)?;
+        self.assembler.emit_sse(SseOp::Por, t1, t0)?;
+        self.xmm_to_v128(t0, ret)?;
+        self.release_simd(t2);
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_unop(
+        &mut self,
+        op: V128UnOp,
+        loc: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let t2 = self.acquire_temp_simd_v128()?;
+        self.v128_to_xmm(loc, t0)?;
+        let result = self.v128_unop_xmm(op, t0, t1, t2)?;
+        self.xmm_to_v128(result, ret)?;
+        self.release_simd(t2);
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_binop(
+        &mut self,
+        op: V128BinOp,
+        loc_a: Location,
+        loc_b: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let t2 = self.acquire_temp_simd_v128()?;
+        self.v128_to_xmm(loc_a, t0)?;
+        self.v128_to_xmm(loc_b, t1)?;
+        let result = self.v128_binop_xmm(op, t0, t1, t2)?;
+        self.xmm_to_v128(result, ret)?;
+        self.release_simd(t2);
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_bitselect(
+        &mut self,
+        loc_a: Location,
+        loc_b: Location,
+        mask: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let t2 = self.acquire_temp_simd_v128()?;
+        self.v128_to_xmm(loc_a, t0)?;
+        self.v128_to_xmm(loc_b, t1)?;
+        self.v128_to_xmm(mask, t2)?;
+        self.assembler.emit_sse(SseOp::Pand, t2, t0)?;
+        self.assembler.emit_sse(SseOp::Pandn, t1, t2)?;
+        self.assembler.emit_sse(SseOp::Por, t2, t0)?;
+        self.xmm_to_v128(t0, ret)?;
+        self.release_simd(t2);
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_shift(
+        &mut self,
+        op: V128ShiftOp,
+        loc: Location,
+        amount: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let shape = op.shape();
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        let t2 = self.acquire_temp_simd_v128()?;
+        // The shift amount is taken modulo the lane width.
+        self.move_location(Size::S32, amount, Location::GPR(tmp))?;
+        self.assembler.emit_and(
+            Size::S32,
+            Location::Imm32(shape.lane_bytes() as u32 * 8 - 1),
+            Location::GPR(tmp),
+        )?;
+        if op == V128ShiftOp::I8x16ShrS {
+            // Shift the bytes in the high half of 16 bits lanes.
+            self.assembler
+                .emit_add(Size::S32, Location::Imm32(8), Location::GPR(tmp))?;
+        }
+        self.assembler
+            .emit_mov(Size::S32, Location::GPR(tmp), Location::SIMD(t1))?;
+        self.v128_to_xmm(loc, t0)?;
+        let result = match op {
+            V128ShiftOp::I8x16Shl => {
+                self.assembler.emit_sse(SseOp::Psllw, t1, t0)?;
+                // Clear the bits shifted from the neighbouring byte.
+                self.assembler.emit_sse(SseOp::Pcmpeqw, t2, t2)?;
+                self.assembler.emit_sse(SseOp::Psllw, t1, t2)?;
+                self.assembler.emit_sse(SseOp::Pxor, t1, t1)?;
+                self.assembler.emit_sse(SseOp::Pshufb, t1, t2)?;
+                self.assembler.emit_sse(SseOp::Pand, t2, t0)?;
+                t0
+            }
+            V128ShiftOp::I8x16ShrU => {
+                self.assembler.emit_sse(SseOp::Psrlw, t1, t0)?;
+                self.assembler.emit_sse(SseOp::Pcmpeqw, t2, t2)?;
+                self.assembler.emit_sse(SseOp::Psrlw, t1, t2)?;
+                self.v128_splat_const_to_xmm(V128Shape::I8x16, 1, t1)?;
+                self.assembler.emit_sse(SseOp::Pshufb, t1, t2)?;
+                self.assembler.emit_sse(SseOp::Pand, t2, t0)?;
+                t0
+            }
+            V128ShiftOp::I8x16ShrS => {
+                self.assembler.emit_sse(SseOp::Movdqa, t0, t2)?;
+                self.assembler.emit_sse(SseOp::Punpcklbw, t2, t2)?;
+                self.assembler.emit_sse(SseOp::Punpckhbw, t0, t0)?;
+                self.assembler.emit_sse(SseOp::Psraw, t1, t2)?;
+                self.assembler.emit_sse(SseOp::Psraw, t1, t0)?;
+                self.assembler.emit_sse(SseOp::Packsswb, t0, t2)?;
+                t2
+            }
+            V128ShiftOp::I64x2ShrS => {
+                // ((x >>> n) ^ m) - m with m = 0x8000000000000000 >>> n
+                self.v128_splat_const_to_xmm(V128Shape::I64x2, 1 << 63, t2)?;
+                self.assembler.emit_sse(SseOp::Psrlq, t1, t2)?;
+                self.assembler.emit_sse(SseOp::Psrlq, t1, t0)?;
+                self.assembler.emit_sse(SseOp::Pxor, t2, t0)?;
+                self.assembler.emit_sse(SseOp::Psubq, t2, t0)?;
+                t0
+            }
+            _ => {
+                let sse_op = match op {
+                    V128ShiftOp::I16x8Shl => SseOp::Psllw,
+                    V128ShiftOp::I16x8ShrS => SseOp::Psraw,
+                    V128ShiftOp::I16x8ShrU => SseOp::Psrlw,
+                    V128ShiftOp::I32x4Shl => SseOp::Pslld,
+                    V128ShiftOp::I32x4ShrS => SseOp::Psrad,
+                    V128ShiftOp::I32x4ShrU => SseOp::Psrld,
+                    V128ShiftOp::I64x2Shl => SseOp::Psllq,
+                    _ => SseOp::Psrlq,
+                };
+                self.assembler.emit_sse(sse_op, t1, t0)?;
+                t0
+            }
+        };
+        self.xmm_to_v128(result, ret)?;
+        self.release_simd(t2);
+        self.release_simd(t1);
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_test(
+        &mut self,
+        op: V128TestOp,
+        loc: Location,
+        ret: Location,
+    ) -> Result<(), CompileError> {
+        let tmp = self.acquire_temp_gpr_v128()?;
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        self.v128_to_xmm(loc, t0)?;
+        match op {
+            V128TestOp::AnyTrue => {
+                self.assembler.emit_sse(SseOp::Ptest, t0, t0)?;
+                self.assembler.emit_set(Condition::NotEqual, tmp)?;
+                self.assembler
+                    .emit_and(Size::S32, Location::Imm32(0xff), Location::GPR(tmp))?;
+            }
+            V128TestOp::I8x16AllTrue
+            | V128TestOp::I16x8AllTrue
+            | V128TestOp::I32x4AllTrue
+            | V128TestOp::I64x2AllTrue => {
+                let eq = match op {
+                    V128TestOp::I8x16AllTrue => SseOp::Pcmpeqb,
+                    V128TestOp::I16x8AllTrue => SseOp::Pcmpeqw,
+                    V128TestOp::I32x4AllTrue => SseOp::Pcmpeqd,
+                    _ => SseOp::Pcmpeqq,
+                };
+                // No lane equal to zero.
+                self.assembler.emit_sse(SseOp::Pxor, t1, t1)?;
+                self.assembler.emit_sse(eq, t0, t1)?;
+                self.assembler.emit_sse(SseOp::Ptest, t1, t1)?;
+                self.assembler.emit_set(Condition::Equal, tmp)?;
+                self.assembler
+                    .emit_and(Size::S32, Location::Imm32(0xff), Location::GPR(tmp))?;
+            }
+            V128TestOp::I8x16Bitmask => self.assembler.emit_movmsk(Size::S8, t0, tmp)?,
+            V128TestOp::I16x8Bitmask => {
+                self.assembler.emit_sse(SseOp::Packsswb, t0, t0)?;
+                self.assembler.emit_movmsk(Size::S8, t0, tmp)?;
+                self.assembler
+                    .emit_and(Size::S32, Location::Imm32(0xff), Location::GPR(tmp))?;
+            }
+            V128TestOp::I32x4Bitmask => self.assembler.emit_movmsk(Size::S32, t0, tmp)?,
+            V128TestOp::I64x2Bitmask => self.assembler.emit_movmsk(Size::S64, t0, tmp)?,
+        }
+        self.move_location(Size::S32, Location::GPR(tmp), ret)?;
+        self.release_simd(t1);
+        self.release_simd(t0);
+        self.release_gpr(tmp);
+        Ok(())
+    }
+    fn v128_load(
+        &mut self,
+        kind: V128LoadKind,
+        addr: Location,
+        memarg: &MemArg,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        let t1 = self.acquire_temp_simd_v128()?;
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            kind.access_size(),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            unaligned_atomic,
+            |this, addr| {
+                let src = Location::Memory(addr, 0);
+                match kind {
+                    V128LoadKind::Full => this
+                        .assembler
+                        .emit_movdqu(XMMOrMemory::Memory(addr, 0), XMMOrMemory::XMM(t0))?,
+                    V128LoadKind::Zero32 => {
+                        this.assembler
+                            .emit_mov(Size::S32, src, Location::SIMD(t0))?
+                    }
+                    V128LoadKind::Splat(shape) => {
+                        let tmp = this.acquire_temp_gpr_v128()?;
+                        this.assembler
+                            .emit_mov(shape.lane_size(), src, Location::GPR(tmp))?;
+                        this.assembler.emit_mov(
+                            Size::S64,
+                            Location::GPR(tmp),
+                            Location::SIMD(t0),
+                        )?;
+                        this.release_gpr(tmp);
+                        this.v128_broadcast_xmm(shape, t0, t1)?;
+                    }
+                    _ => {
+                        this.assembler
+                            .emit_mov(Size::S64, src, Location::SIMD(t0))?;
+                        let extend = match kind {
+                            V128LoadKind::Extend8x8S => Some(SseOp::Pmovsxbw),
+                            V128LoadKind::Extend8x8U => Some(SseOp::Pmovzxbw),
+                            V128LoadKind::Extend16x4S => Some(SseOp::Pmovsxwd),
+                            V128LoadKind::Extend16x4U => Some(SseOp::Pmovzxwd),
+                            V128LoadKind::Extend32x2S => Some(SseOp::Pmovsxdq),
+                            V128LoadKind::Extend32x2U => Some(SseOp::Pmovzxdq),
+                            _ => None,
+                        };
+                        if let Some(extend) = extend {
+                            this.assembler.emit_sse(extend, t0, t0)?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        self.xmm_to_v128(t0, ret)?;
+        self.release_simd(t1);
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_save(
+        &mut self,
+        value: Location,
+        memarg: &MemArg,
+        addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        self.v128_to_xmm(value, t0)?;
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            16,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            unaligned_atomic,
+            |this, addr| {
+                this.assembler
+                    .emit_movdqu(XMMOrMemory::XMM(t0), XMMOrMemory::Memory(addr, 0))
+            },
+        )?;
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_load_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        addr: Location,
+        memarg: &MemArg,
+        loc: Location,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError> {
+        let t0 = self.acquire_temp_simd_v128()?;
+        self.v128_to_xmm(loc, t0)?;
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            shape.lane_bytes(),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            unaligned_atomic,
+            |this, addr| {
+                let tmp = this.acquire_temp_gpr_v128()?;
+                this.assembler.emit_mov(
+                    shape.lane_size(),
+                    Location::Memory(addr, 0),
+                    Location::GPR(tmp),
+                )?;
+                this.assembler
+                    .emit_pinsr(shape.lane_size(), tmp, lane, t0)?;
+                this.release_gpr(tmp);
+                Ok(())
+            },
+        )?;
+        self.xmm_to_v128(t0, ret)?;
+        self.release_simd(t0);
+        Ok(())
+    }
+    fn v128_save_lane(
+        &mut self,
+        shape: V128Shape,
+        lane: u8,
+        value: Location,
+        memarg: &MemArg,
+        addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        unaligned_atomic: Label,
+    ) -> Result<(), CompileError> {
+        let src = Self::v128_lane_location(value, shape, lane)?;
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            shape.lane_bytes(),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            unaligned_atomic,
+            |this, addr| {
+                let tmp = this.acquire_temp_gpr_v128()?;
+                this.assembler
+                    .emit_mov(shape.lane_size(), src, Location::GPR(tmp))?;
+                this.assembler.emit_mov(
+                    shape.lane_size(),
+                    Location::GPR(tmp),
+                    Location::Memory(addr, 0),
+                )?;
+                this.release_gpr(tmp);
+                Ok(())
+            },
+        )
+    }
+
     fn gen_std_trampoline(
         &self,
         sig: &FunctionType,
@@ -7842,9 +9114,16 @@ impl Machine for MachineX86_64 {
         // the cpu feature here is irrelevant
         let mut a = AssemblerX64::new(0, None)?;
 
+        // A v128 is passed in two consecutive parameters: one for each 64 bits half.
+        let n_params: usize = sig
+            .params()
+            .iter()
+            .map(|ty| if *ty == Type::V128 { 2 } else { 1 })
+            .sum();
+
         // Calculate stack offset.
         let mut stack_offset: u32 = 0;
-        for (i, _param) in sig.params().iter().enumerate() {
+        for i in 0..n_params {
             if let Location::Memory(_, _) =
                 self.get_simple_param_location(1 + i, calling_convention)
             {
@@ -7888,8 +9167,12 @@ impl Machine for MachineX86_64 {
         // `callee_vmctx` is already in the first argument register, so no need to move.
         {
             let mut n_stack_args: usize = 0;
-            for (i, _param) in sig.params().iter().enumerate() {
-                let src_loc = Location::Memory(GPR::R14, (i * 16) as _); // args_rets[i]
+            let halves = sig.params().iter().enumerate().flat_map(|(i, ty)| {
+                let n = if *ty == Type::V128 { 2 } else { 1 };
+                (0..n).map(move |half| i * 16 + half * 8)
+            });
+            for (i, src_offset) in halves.enumerate() {
+                let src_loc = Location::Memory(GPR::R14, src_offset as _); // args_rets[i]
                 let dst_loc = self.get_simple_param_location(1 + i, calling_convention);
 
                 match dst_loc {
@@ -7926,7 +9209,12 @@ impl Machine for MachineX86_64 {
         )?;
 
         // Write return value.
-        if !sig.results().is_empty() {
+        if sig.results() == [Type::V128] {
+            a.emit_movdqu(
+                XMMOrMemory::XMM(XMM::XMM0),
+                XMMOrMemory::Memory(GPR::R14, 0),
+            )?;
+        } else if !sig.results().is_empty() {
             a.emit_mov(
                 Size::S64,
                 Location::GPR(GPR::RAX),
@@ -7957,6 +9245,16 @@ impl Machine for MachineX86_64 {
         // the cpu feature here is irrelevant
         let mut a = AssemblerX64::new(0, None)?;
 
+        if calling_convention == CallingConvention::WindowsFastcall
+            && sig
+                .params()
+                .iter()
+                .chain(sig.results())
+                .any(|ty| *ty == Type::V128)
+        {
+            codegen_error!("singlepass doesn't support v128 with the Windows calling convention");
+        }
+
         // Allocate argument array.
         let stack_offset: usize = 16 * std::cmp::max(sig.params().len(), sig.results().len()) + 8; // 16 bytes each + 8 bytes sysv call padding
         let stack_padding: usize = match calling_convention {
@@ -7978,8 +9276,18 @@ impl Machine for MachineX86_64 {
 
             for (i, ty) in sig.params().iter().enumerate() {
                 let source_loc = match argalloc.next(*ty, calling_convention) {
+                    Some(X64Register::XMM(xmm)) if *ty == Type::V128 => {
+                        a.emit_movdqu(
+                            XMMOrMemory::XMM(xmm),
+                            XMMOrMemory::Memory(GPR::RSP, (stack_padding + i * 16) as _),
+                        )?;
+                        continue;
+                    }
                     Some(X64Register::GPR(gpr)) => Location::GPR(gpr),
                     Some(X64Register::XMM(xmm)) => Location::SIMD(xmm),
+                    None if *ty == Type::V128 => {
+                        codegen_error!("singlepass doesn't support v128 parameters on the stack")
+                    }
                     None => {
                         a.emit_mov(
                             Size::S64,
@@ -8045,7 +9353,12 @@ impl Machine for MachineX86_64 {
         a.emit_call_location(Location::GPR(GPR::RAX))?;
 
         // Fetch return value.
-        if !sig.results().is_empty() {
+        if sig.results() == [Type::V128] {
+            a.emit_movdqu(
+                XMMOrMemory::Memory(GPR::RSP, stack_padding as i32),
+                XMMOrMemory::XMM(XMM::XMM0),
+            )?;
+        } else if !sig.results().is_empty() {
             assert_eq!(sig.results().len(), 1);
             a.emit_mov(
                 Size::S64,
@@ -8091,12 +9404,19 @@ impl Machine for MachineX86_64 {
         // For the standard System V calling convention requires
         //  floating point arguments to be passed in XMM registers.
         //  Translation is expensive, so only do it if needed.
+        //  A v128 is passed internally as two consecutive integer arguments and
+        //  has to be packed back into a single XMM register.
         if sig
             .params()
             .iter()
-            .any(|&x| x == Type::F32 || x == Type::F64)
+            .any(|&x| x == Type::F32 || x == Type::F64 || x == Type::V128)
         {
             match calling_convention {
+                CallingConvention::WindowsFastcall if sig.params().contains(&Type::V128) => {
+                    codegen_error!(
+                        "singlepass doesn't support v128 with the Windows calling convention"
+                    );
+                }
                 CallingConvention::WindowsFastcall => {
                     let mut param_locations: Vec<Location> = vec![];
                     static PARAM_REGS: &[GPR] = &[GPR::RDX, GPR::R8, GPR::R9];
@@ -8124,12 +9444,17 @@ impl Machine for MachineX86_64 {
                 }
                 _ => {
                     let mut param_locations = vec![];
+                    let n_slots: usize = sig
+                        .params()
+                        .iter()
+                        .map(|ty| if *ty == Type::V128 { 2 } else { 1 })
+                        .sum();
 
                     // Allocate stack space for arguments.
-                    let stack_offset: i32 = if sig.params().len() > 5 {
+                    let stack_offset: i32 = if n_slots > 5 {
                         5 * 8
                     } else {
-                        (sig.params().len() as i32) * 8
+                        (n_slots as i32) * 8
                     };
                     if stack_offset > 0 {
                         a.emit_sub(
@@ -8142,7 +9467,7 @@ impl Machine for MachineX86_64 {
                     // Store all arguments to the stack to prevent overwrite.
                     static PARAM_REGS: &[GPR] = &[GPR::RSI, GPR::RDX, GPR::RCX, GPR::R8, GPR::R9];
                     #[allow(clippy::needless_range_loop)]
-                    for i in 0..sig.params().len() {
+                    for i in 0..n_slots {
                         let loc = match i {
                             0..=4 => {
                                 let loc = Location::Memory(GPR::RSP, (i * 8) as i32);
@@ -8160,9 +9485,24 @@ impl Machine for MachineX86_64 {
                     let mut argalloc = ArgumentRegisterAllocator::default();
                     argalloc.next(Type::I64, calling_convention).unwrap(); // skip VMContext
                     let mut caller_stack_offset: i32 = 0;
-                    for (i, ty) in sig.params().iter().enumerate() {
-                        let prev_loc = param_locations[i];
+                    let mut slot = 0;
+                    for ty in sig.params() {
+                        let prev_loc = param_locations[slot];
+                        slot += 1;
                         let targ = match argalloc.next(*ty, calling_convention) {
+                            Some(X64Register::XMM(xmm)) if *ty == Type::V128 => {
+                                let hi_loc = param_locations[slot];
+                                slot += 1;
+                                a.emit_mov(Size::S64, prev_loc, Location::SIMD(xmm))?;
+                                a.emit_mov(Size::S64, hi_loc, Location::GPR(GPR::RAX))?;
+                                a.emit_pinsr(Size::S64, GPR::RAX, 1, xmm)?;
+                                continue;
+                            }
+                            None if *ty == Type::V128 => {
+                                codegen_error!(
+                                    "singlepass doesn't support v128 parameters on the stack"
+                                );
+                            }
                             Some(X64Register::GPR(gpr)) => Location::GPR(gpr),
                             Some(X64Register::XMM(xmm)) => Location::SIMD(xmm),
                             None => {
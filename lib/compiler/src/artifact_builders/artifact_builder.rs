@@ -140,6 +140,7 @@ impl ArtifactBuild {
             dynamic_function_trampolines: compilation.dynamic_function_trampolines,
             custom_sections,
             custom_section_relocations,
+            function_entry_trampolines: compilation.function_entry_trampolines,
             debug: compilation.debug,
             libcall_trampolines,
             libcall_trampoline_len,
@@ -188,6 +189,13 @@ impl ArtifactBuild {
         &self.serializable.compilation.custom_section_relocations
     }
 
+    /// Get Function Entry Trampolines ref
+    pub fn get_function_entry_trampolines_ref(
+        &self,
+    ) -> &PrimaryMap<LocalFunctionIndex, SectionIndex> {
+        &self.serializable.compilation.function_entry_trampolines
+    }
+
     /// Get LibCall Trampoline Section Index
     pub fn get_libcall_trampolines(&self) -> SectionIndex {
         self.serializable.compilation.libcall_trampolines
@@ -396,6 +404,20 @@ impl ArtifactBuildFromArchive {
             .custom_section_relocations
     }
 
+    /// Get Function Entry Trampolines ref
+    pub fn get_function_entry_trampolines_ref(
+        &self,
+    ) -> PrimaryMap<LocalFunctionIndex, SectionIndex> {
+        rkyv::deserialize::<_, RkyvError>(
+            &self
+                .cell
+                .borrow_dependent()
+                .compilation
+                .function_entry_trampolines,
+        )
+        .unwrap()
+    }
+
     /// Get LibCall Trampoline Section Index
     pub fn get_libcall_trampolines(&self) -> SectionIndex {
         rkyv::deserialize::<_, RkyvError>(
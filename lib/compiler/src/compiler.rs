@@ -42,6 +42,16 @@ pub trait CompilerConfig {
         // in case they create an IR that they can verify.
     }
 
+    /// Enable epoch-based interruption.
+    ///
+    /// Compiled code checks the epoch deadline of its store at every
+    /// function entry and loop header, so that long-running code can be
+    /// interrupted by incrementing the epoch of the engine.
+    fn epoch_interruption(&mut self, _enable: bool) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they support epoch-based interruption.
+    }
+
     /// Gets the custom compiler config
     fn compiler(self: Box<Self>) -> Box<dyn Compiler>;
 
@@ -84,6 +94,7 @@ pub trait Compiler: Send {
         wasm_features.set(WasmFeatures::MULTI_MEMORY, features.multi_memory);
         wasm_features.set(WasmFeatures::MEMORY64, features.memory64);
         wasm_features.set(WasmFeatures::EXCEPTIONS, features.exceptions);
+        wasm_features.set(WasmFeatures::LEGACY_EXCEPTIONS, features.exceptions);
         wasm_features.set(WasmFeatures::EXTENDED_CONST, features.extended_const);
         wasm_features.set(WasmFeatures::RELAXED_SIMD, features.relaxed_simd);
         wasm_features.set(WasmFeatures::MUTABLE_GLOBAL, true);
@@ -6,6 +6,9 @@ use std::sync::{
     Arc,
 };
 
+#[cfg(feature = "gdb-jit")]
+use crate::engine::gdb_jit::{build_image, DebugModuleInfo, GdbJitImageRegistration};
+use crate::types::function::CompiledFunctionFrameInfo;
 use crate::{
     engine::link::link_module,
     lib::std::vec::IntoIter,
@@ -13,8 +16,8 @@ use crate::{
     serialize::{MetadataHeader, SerializableModule},
     types::target::{CpuFeature, Target},
     ArtifactBuild, ArtifactBuildFromArchive, ArtifactCreate, Engine, EngineInner, Features,
-    FrameInfosVariant, FunctionExtent, GlobalFrameInfoRegistration, InstantiationError,
-    ModuleEnvironment, Tunables,
+    FrameInfosVariant, FunctionExtent, GlobalFrameInfoRegistration, InstantiationError, LineInfo,
+    LineTable, LinkError, ModuleEnvironment, ProfiledFunction, Tunables,
 };
 #[cfg(any(feature = "static-artifact-create", feature = "static-artifact-load"))]
 use crate::{serialize::SerializableCompilation, types::symbols::ModuleMetadata};
@@ -31,16 +34,17 @@ use std::mem;
 use crate::object::{emit_compilation, emit_data, get_object_for_target, Object};
 
 use wasmer_types::{
+    entity::EntityRef,
     entity::{BoxedSlice, PrimaryMap},
     ArchivedDataInitializerLocation, ArchivedOwnedDataInitializer, CompileError, DataInitializer,
     DataInitializerLike, DataInitializerLocation, DataInitializerLocationLike, DeserializeError,
     FunctionIndex, HashAlgorithm, LocalFunctionIndex, MemoryIndex, ModuleInfo,
-    OwnedDataInitializer, SerializeError, SignatureIndex, TableIndex,
+    OwnedDataInitializer, SerializeError, SignatureIndex, TableIndex, WASM_PAGE_SIZE,
 };
 
 use wasmer_vm::{
-    FunctionBodyPtr, InstanceAllocator, MemoryStyle, StoreObjects, TableStyle, TrapHandlerFn,
-    VMConfig, VMExtern, VMInstance, VMSharedSignatureIndex, VMTrampoline,
+    FunctionBodyPtr, InstanceAllocator, LinearMemory, MemoryStyle, StoreObjects, TableStyle, Trap,
+    TrapHandlerFn, VMConfig, VMExtern, VMInstance, VMSharedSignatureIndex, VMTrampoline,
 };
 
 #[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
@@ -55,6 +59,10 @@ pub struct AllocatedArtifact {
     // so the GloabelFrameInfo and MMap stays in sync and get dropped at the same time
     frame_info_registration: Option<GlobalFrameInfoRegistration>,
     finished_functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
+    // The function bodies, when they can't be called with the native calling
+    // convention and `finished_functions` points to their entry trampolines
+    // instead. Tail calls enter the bodies directly.
+    finished_function_tail_entries: BoxedSlice<LocalFunctionIndex, Option<FunctionBodyPtr>>,
 
     #[cfg_attr(feature = "artifact-size", loupe(skip))]
     finished_function_call_trampolines: BoxedSlice<SignatureIndex, VMTrampoline>,
@@ -107,6 +115,7 @@ pub struct Artifact {
 /// module, corresponding to `ArtifactBuildVariant::Plain`, or loaded
 /// from an archive, corresponding to `ArtifactBuildVariant::Archived`.
 #[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
+#[allow(clippy::large_enum_variant)]
 pub enum ArtifactBuildVariant {
     Plain(ArtifactBuild),
     Archived(ArtifactBuildFromArchive),
@@ -145,10 +154,20 @@ impl Artifact {
             hash_algorithm,
         )?;
 
-        Self::from_parts(
+        // The source lines of the functions are only known when the module
+        // is compiled, as DWARF addresses are relative to its code section.
+        let code_section_offset = if inner_engine.profiler().is_some() || inner_engine.debug_info()
+        {
+            code_section_offset(data)
+        } else {
+            None
+        };
+
+        Self::from_parts_with_code_section(
             &mut inner_engine,
             ArtifactBuildVariant::Plain(artifact),
             engine.target(),
+            code_section_offset,
         )
         .map_err(|e| match e {
             DeserializeError::Compiler(c) => c,
@@ -284,6 +303,15 @@ impl Artifact {
         engine_inner: &mut EngineInner,
         artifact: ArtifactBuildVariant,
         target: &Target,
+    ) -> Result<Self, DeserializeError> {
+        Self::from_parts_with_code_section(engine_inner, artifact, target, None)
+    }
+
+    fn from_parts_with_code_section(
+        engine_inner: &mut EngineInner,
+        artifact: ArtifactBuildVariant,
+        target: &Target,
+        code_section_offset: Option<u64>,
     ) -> Result<Self, DeserializeError> {
         if !target.is_native() {
             return Ok(Self {
@@ -386,19 +414,52 @@ impl Artifact {
             None => None,
         };
 
+        let function_entry_trampolines = match &artifact {
+            ArtifactBuildVariant::Plain(p) => p.get_function_entry_trampolines_ref().clone(),
+            ArtifactBuildVariant::Archived(a) => a.get_function_entry_trampolines_ref(),
+        };
+
         // Make all code compiled thus far executable.
         engine_inner.publish_compiled_code();
 
         engine_inner.publish_eh_frame(eh_frame)?;
 
+        if engine_inner.profiler().is_some() || engine_inner.debug_info() {
+            let frame_infos = match &artifact {
+                ArtifactBuildVariant::Plain(p) => Some(p.get_frame_info_ref()),
+                ArtifactBuildVariant::Archived(_) => None,
+            };
+            register_functions(
+                engine_inner,
+                module_info,
+                &finished_functions,
+                frame_infos,
+                code_section_offset,
+            );
+        }
+
         let finished_function_lengths = finished_functions
             .values()
             .map(|extent| extent.length)
             .collect::<PrimaryMap<LocalFunctionIndex, usize>>()
             .into_boxed_slice();
+        let finished_function_tail_entries = finished_functions
+            .keys()
+            .map(|index| {
+                function_entry_trampolines
+                    .get(index)
+                    .map(|_| finished_functions[index].ptr)
+            })
+            .collect::<PrimaryMap<LocalFunctionIndex, Option<FunctionBodyPtr>>>()
+            .into_boxed_slice();
         let finished_functions = finished_functions
-            .values()
-            .map(|extent| extent.ptr)
+            .iter()
+            .map(
+                |(index, extent)| match function_entry_trampolines.get(index) {
+                    Some(section_index) => FunctionBodyPtr(*custom_sections[*section_index] as _),
+                    None => extent.ptr,
+                },
+            )
             .collect::<PrimaryMap<LocalFunctionIndex, FunctionBodyPtr>>()
             .into_boxed_slice();
         let finished_function_call_trampolines =
@@ -414,6 +475,7 @@ impl Artifact {
                 frame_info_registered: false,
                 frame_info_registration: None,
                 finished_functions,
+                finished_function_tail_entries,
                 finished_function_call_trampolines,
                 finished_dynamic_function_trampolines,
                 signatures,
@@ -437,6 +499,78 @@ impl Artifact {
     }
 }
 
+/// The offset of the code section of a module.
+#[cfg(feature = "compiler")]
+fn code_section_offset(data: &[u8]) -> Option<u64> {
+    use wasmparser::{Parser, Payload};
+
+    for payload in Parser::new(0).parse_all(data) {
+        if let Payload::CodeSectionStart { range, .. } = payload.ok()? {
+            return Some(range.start as u64);
+        }
+    }
+    None
+}
+
+/// Records the functions of a module for the profiler and registers them
+/// with debuggers, as configured in the engine.
+fn register_functions(
+    engine_inner: &mut EngineInner,
+    module_info: &ModuleInfo,
+    functions: &PrimaryMap<LocalFunctionIndex, FunctionExtent>,
+    frame_infos: Option<&PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>>,
+    code_section_offset: Option<u64>,
+) {
+    let line_table = frame_infos
+        .and(code_section_offset)
+        .and_then(|offset| LineTable::new(module_info, offset));
+    let module_name = module_info.name.as_deref().unwrap_or("wasm");
+    let symbols: Vec<(String, Vec<LineInfo>)> = functions
+        .iter()
+        .map(|(index, _)| {
+            let func_index = module_info.func_index(index);
+            let name = match module_info.function_names.get(&func_index) {
+                Some(name) => format!("{module_name}::{name}"),
+                None => format!("{module_name}::function[{}]", func_index.index()),
+            };
+            let lines = match (&line_table, frame_infos) {
+                (Some(line_table), Some(frame_infos)) => {
+                    line_table.lines(&frame_infos[index].address_map)
+                }
+                _ => Vec::new(),
+            };
+            (name, lines)
+        })
+        .collect();
+    let profiled_functions: Vec<ProfiledFunction> = functions
+        .values()
+        .zip(&symbols)
+        .map(|(extent, (name, lines))| ProfiledFunction {
+            name,
+            address: extent.ptr.0 as *const u8,
+            size: extent.length,
+            lines,
+        })
+        .collect();
+
+    if let Some(profiler) = engine_inner.profiler() {
+        for function in &profiled_functions {
+            profiler.register_function(function);
+        }
+    }
+    #[cfg(feature = "gdb-jit")]
+    if engine_inner.debug_info() {
+        let module = DebugModuleInfo {
+            name: module_name,
+            language: line_table.as_ref().and_then(|table| table.language()),
+            comp_dir: line_table.as_ref().and_then(|table| table.comp_dir()),
+        };
+        if let Some(image) = build_image(&module, &profiled_functions) {
+            engine_inner.register_gdb_jit_image(GdbJitImageRegistration::register(image));
+        }
+    }
+}
+
 impl PartialEq for Artifact {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -657,6 +791,14 @@ impl Artifact {
             .finished_functions
             .values()
             .copied()
+            .zip(
+                self.allocated
+                    .as_ref()
+                    .expect("It must be allocated")
+                    .finished_function_tail_entries
+                    .values()
+                    .copied(),
+            )
             .zip(
                 self.allocated
                     .as_ref()
@@ -665,7 +807,10 @@ impl Artifact {
                     .values()
                     .copied(),
             )
-            .map(|(ptr, length)| FunctionExtent { ptr, length })
+            .map(|((ptr, tail_entry), length)| FunctionExtent {
+                ptr: tail_entry.unwrap_or(ptr),
+                length,
+            })
             .collect::<PrimaryMap<LocalFunctionIndex, _>>()
             .into_boxed_slice();
 
@@ -714,6 +859,19 @@ impl Artifact {
             .finished_functions
     }
 
+    /// Returns the entry points of the functions allocated in memory for
+    /// tail calls, if they differ from the ones in
+    /// [`Artifact::finished_functions`].
+    pub fn finished_function_tail_entries(
+        &self,
+    ) -> &BoxedSlice<LocalFunctionIndex, Option<FunctionBodyPtr>> {
+        &self
+            .allocated
+            .as_ref()
+            .expect("It must be allocated")
+            .finished_function_tail_entries
+    }
+
     /// Returns the function call trampolines allocated in memory of this
     /// `Artifact`, ready to be run.
     pub fn finished_function_call_trampolines(&self) -> &BoxedSlice<SignatureIndex, VMTrampoline> {
@@ -786,6 +944,8 @@ impl Artifact {
         )
         .map_err(InstantiationError::Link)?;
 
+        Self::check_resource_limits(&module, context).map_err(InstantiationError::Link)?;
+
         // Get pointers to where metadata about local memories should live in VM memory.
         // Get pointers to where metadata about local tables should live in VM memory.
 
@@ -798,8 +958,24 @@ impl Artifact {
                 self.memory_styles(),
                 &memory_definition_locations,
             )
-            .map_err(InstantiationError::Link)?
+            .map_err(|err| {
+                if let Some(limiter) = context.resource_limiter() {
+                    for ty in module.memories.values().skip(module.num_imported_memories) {
+                        limiter.memory_grow_failed(0, ty.minimum.0 as usize * WASM_PAGE_SIZE);
+                    }
+                }
+                Self::release_tables(&module, context, usize::MAX);
+                InstantiationError::Link(err)
+            })?
             .into_boxed_slice();
+        if let Some(limiter) = context.resource_limiter().cloned() {
+            let memory_types = module.memories.values().skip(module.num_imported_memories);
+            for (memory, ty) in finished_memories.values().zip(memory_types) {
+                memory
+                    .get_mut(context)
+                    .reserve(&limiter, ty.minimum.0 as usize * WASM_PAGE_SIZE);
+            }
+        }
         let finished_tables = tunables
             .create_tables(
                 context,
@@ -807,11 +983,17 @@ impl Artifact {
                 self.table_styles(),
                 &table_definition_locations,
             )
-            .map_err(InstantiationError::Link)?
+            .map_err(|err| {
+                Self::release_tables(&module, context, usize::MAX);
+                InstantiationError::Link(err)
+            })?
             .into_boxed_slice();
         let finished_globals = tunables
             .create_globals(context, &module)
-            .map_err(InstantiationError::Link)?
+            .map_err(|err| {
+                Self::release_tables(&module, context, usize::MAX);
+                InstantiationError::Link(err)
+            })?
             .into_boxed_slice();
 
         let handle = VMInstance::new(
@@ -819,6 +1001,7 @@ impl Artifact {
             module,
             context,
             self.finished_functions().clone(),
+            self.finished_function_tail_entries().clone(),
             self.finished_function_call_trampolines().clone(),
             finished_memories,
             finished_tables,
@@ -830,6 +1013,95 @@ impl Artifact {
         Ok(handle)
     }
 
+    /// Checks the instance and the memories and tables it defines against
+    /// the resource limiter of the store, if any.
+    ///
+    /// Local memories and tables are reported as growing from zero to
+    /// their minimum size.
+    #[allow(clippy::result_large_err)]
+    fn check_resource_limits(module: &ModuleInfo, context: &StoreObjects) -> Result<(), LinkError> {
+        let Some(limiter) = context.resource_limiter() else {
+            return Ok(());
+        };
+
+        let num_memories = module.memories.len() - module.num_imported_memories;
+        let num_tables = module.tables.len() - module.num_imported_tables;
+        if context.num_instances() + 1 > limiter.instances() {
+            return Err(LinkError::Resource(format!(
+                "instance count limit of {} reached",
+                limiter.instances()
+            )));
+        }
+        if context.num_memories() + num_memories > limiter.memories() {
+            return Err(LinkError::Resource(format!(
+                "memory count limit of {} reached",
+                limiter.memories()
+            )));
+        }
+        if context.num_tables() + num_tables > limiter.tables() {
+            return Err(LinkError::Resource(format!(
+                "table count limit of {} reached",
+                limiter.tables()
+            )));
+        }
+
+        for (index, ty) in module
+            .tables
+            .values()
+            .skip(module.num_imported_tables)
+            .enumerate()
+        {
+            let denied = match limiter.table_growing(0, ty.minimum, ty.maximum) {
+                Ok(true) => continue,
+                Ok(false) => LinkError::Resource(format!(
+                    "table of {} elements denied by the resource limiter",
+                    ty.minimum
+                )),
+                Err(err) => LinkError::Trap(Trap::user(err)),
+            };
+            // The tables allowed so far will not be created.
+            Self::release_tables(module, context, index);
+            return Err(denied);
+        }
+
+        let local_memories = || module.memories.values().skip(module.num_imported_memories);
+        for (index, ty) in local_memories().enumerate() {
+            let minimum = ty.minimum.0 as usize * WASM_PAGE_SIZE;
+            let maximum = ty
+                .maximum
+                .map(|maximum| maximum.0 as usize * WASM_PAGE_SIZE);
+            let denied = match limiter.memory_growing(0, minimum, maximum) {
+                Ok(true) => continue,
+                Ok(false) => LinkError::Resource(format!(
+                    "memory of {minimum} bytes denied by the resource limiter"
+                )),
+                Err(err) => LinkError::Trap(Trap::user(err)),
+            };
+            // The memories and tables allowed so far will not be created.
+            for ty in local_memories().take(index) {
+                limiter.memory_grow_failed(0, ty.minimum.0 as usize * WASM_PAGE_SIZE);
+            }
+            Self::release_tables(module, context, num_tables);
+            return Err(denied);
+        }
+        Ok(())
+    }
+
+    /// Tells the resource limiter of the store, if any, that the first
+    /// `count` local tables it allowed will not be created.
+    fn release_tables(module: &ModuleInfo, context: &StoreObjects, count: usize) {
+        if let Some(limiter) = context.resource_limiter() {
+            for ty in module
+                .tables
+                .values()
+                .skip(module.num_imported_tables)
+                .take(count)
+            {
+                limiter.table_grow_failed(0, ty.minimum);
+            }
+        }
+    }
+
     /// Finishes the instantiation of a just created `VMInstance`.
     ///
     /// # Safety
@@ -1168,6 +1440,11 @@ impl Artifact {
             .map(|_| 0)
             .collect::<PrimaryMap<LocalFunctionIndex, usize>>()
             .into_boxed_slice();
+        let finished_function_tail_entries = finished_functions
+            .values()
+            .map(|_| None)
+            .collect::<PrimaryMap<LocalFunctionIndex, Option<FunctionBodyPtr>>>()
+            .into_boxed_slice();
 
         Ok(Self {
             id: Default::default(),
@@ -1176,6 +1453,7 @@ impl Artifact {
                 frame_info_registered: false,
                 frame_info_registration: None,
                 finished_functions: finished_functions.into_boxed_slice(),
+                finished_function_tail_entries,
                 finished_function_call_trampolines: finished_function_call_trampolines
                     .into_boxed_slice(),
                 finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
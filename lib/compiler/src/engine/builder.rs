@@ -1,5 +1,9 @@
 use super::Engine;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::ProfilingAgent;
 use crate::{types::target::Target, CompilerConfig};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
 use wasmer_types::{Features, HashAlgorithm};
 
 /// The Builder contents of `Engine`
@@ -12,6 +16,12 @@ pub struct EngineBuilder {
     features: Option<Features>,
     /// The hashing algorithm
     hash_algorithm: Option<HashAlgorithm>,
+    /// The agent recording the compiled functions for profilers
+    #[cfg(not(target_arch = "wasm32"))]
+    profiler: Option<Arc<dyn ProfilingAgent>>,
+    /// Whether the compiled functions are registered with debuggers
+    #[cfg(not(target_arch = "wasm32"))]
+    debug_info: bool,
 }
 
 impl EngineBuilder {
@@ -25,6 +35,10 @@ impl EngineBuilder {
             target: None,
             features: None,
             hash_algorithm: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profiler: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            debug_info: false,
         }
     }
 
@@ -35,6 +49,10 @@ impl EngineBuilder {
             target: None,
             features: None,
             hash_algorithm: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profiler: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            debug_info: false,
         }
     }
 
@@ -56,6 +74,22 @@ impl EngineBuilder {
         self
     }
 
+    /// Set the agent recording the compiled functions for profilers, see
+    /// [`ProfilingStrategy::agent`](crate::ProfilingStrategy::agent)
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_profiler(mut self, profiler: Option<Arc<dyn ProfilingAgent>>) -> Self {
+        self.profiler = profiler;
+        self
+    }
+
+    /// Set whether the compiled functions are registered with the GDB JIT
+    /// interface, with the DWARF of the modules translated to native code
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_debug_info(mut self, debug_info: bool) -> Self {
+        self.debug_info = debug_info;
+        self
+    }
+
     /// Build the `Engine` for this configuration
     #[cfg(feature = "compiler")]
     pub fn engine(self) -> Engine {
@@ -67,17 +101,36 @@ impl EngineBuilder {
             let mut engine = Engine::new(compiler_config, target, features);
 
             engine.set_hash_algorithm(self.hash_algorithm);
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                engine.set_profiler(self.profiler);
+                engine.set_debug_info(self.debug_info);
+            }
 
             engine
         } else {
-            Engine::headless()
+            #[allow(unused_mut)]
+            let mut engine = Engine::headless();
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                engine.set_profiler(self.profiler);
+                engine.set_debug_info(self.debug_info);
+            }
+            engine
         }
     }
 
     /// Build the `Engine` for this configuration
     #[cfg(not(feature = "compiler"))]
     pub fn engine(self) -> Engine {
-        Engine::headless()
+        #[allow(unused_mut)]
+        let mut engine = Engine::headless();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            engine.set_profiler(self.profiler);
+            engine.set_debug_info(self.debug_info);
+        }
+        engine
     }
 
     /// The Wasm features
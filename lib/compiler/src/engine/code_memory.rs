@@ -2,6 +2,8 @@
 // Attributions: https://github.com/wasmerio/wasmer/blob/main/docs/ATTRIBUTIONS.md
 
 //! Memory management for executable code.
+#[cfg(feature = "gdb-jit")]
+use super::gdb_jit::GdbJitImageRegistration;
 use super::unwind::UnwindRegistry;
 use crate::{
     types::{
@@ -28,6 +30,8 @@ const DATA_SECTION_ALIGNMENT: usize = 64;
 pub struct CodeMemory {
     // frame info is placed first, to ensure it's dropped before the mmap
     frame_info_registration: Option<GlobalFrameInfoRegistration>,
+    #[cfg(feature = "gdb-jit")]
+    gdb_jit_registration: Option<GdbJitImageRegistration>,
     unwind_registry: UnwindRegistry,
     mmap: Mmap,
     start_of_nonexecutable_pages: usize,
@@ -41,6 +45,8 @@ impl CodeMemory {
             mmap: Mmap::new(),
             start_of_nonexecutable_pages: 0,
             frame_info_registration: None,
+            #[cfg(feature = "gdb-jit")]
+            gdb_jit_registration: None,
         }
     }
 
@@ -234,6 +240,13 @@ impl CodeMemory {
     pub fn register_frame_info(&mut self, frame_info: GlobalFrameInfoRegistration) {
         self.frame_info_registration = Some(frame_info);
     }
+
+    /// Register the debugger image, so it's unregistered when the memory
+    /// gets freed
+    #[cfg(feature = "gdb-jit")]
+    pub fn register_gdb_jit_image(&mut self, registration: GdbJitImageRegistration) {
+        self.gdb_jit_registration = Some(registration);
+    }
 }
 
 fn round_up(size: usize, multiple: usize) -> usize {
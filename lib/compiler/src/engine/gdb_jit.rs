@@ -0,0 +1,521 @@
+//! Registration of the compiled code with the GDB JIT interface, so that
+//! debuggers such as `gdb` and `lldb` can set breakpoints in, step through
+//! and backtrace WebAssembly functions.
+//!
+//! For every module, an in-memory ELF image is built with a symbol per
+//! function and native DWARF, translated from the `.debug_*` custom sections
+//! of the module: the compile unit, a subprogram per function and the line
+//! table. Variable locations aren't translated.
+//!
+//! See <https://sourceware.org/gdb/current/onlinedocs/gdb.html/JIT-Interface.html>.
+
+use std::ptr;
+use std::sync::Mutex;
+
+use gimli::write::{
+    Address, AttributeValue, DwarfUnit, EndianVec, LineProgram, LineString, Sections,
+};
+use gimli::{Encoding, Format, LineEncoding, LittleEndian};
+use object::elf;
+use object::write::elf::{FileHeader, ProgramHeader, SectionHeader, Sym, Writer};
+use object::Endianness;
+
+use super::profiling::ProfiledFunction;
+
+#[repr(u32)]
+#[allow(dead_code)]
+enum JitAction {
+    NoAction = 0,
+    RegisterFn = 1,
+    UnregisterFn = 2,
+}
+
+#[repr(C)]
+struct JitCodeEntry {
+    next_entry: *mut JitCodeEntry,
+    prev_entry: *mut JitCodeEntry,
+    symfile_addr: *const u8,
+    symfile_size: u64,
+}
+
+#[repr(C)]
+struct JitDescriptor {
+    version: u32,
+    action_flag: u32,
+    relevant_entry: *mut JitCodeEntry,
+    first_entry: *mut JitCodeEntry,
+}
+
+/// The descriptor read by the debugger.
+#[no_mangle]
+#[used]
+static mut __jit_debug_descriptor: JitDescriptor = JitDescriptor {
+    version: 1,
+    action_flag: JitAction::NoAction as u32,
+    relevant_entry: ptr::null_mut(),
+    first_entry: ptr::null_mut(),
+};
+
+/// The function the debugger sets a breakpoint on, to be notified when the
+/// list of entries changes.
+#[no_mangle]
+#[inline(never)]
+extern "C" fn __jit_debug_register_code() {
+    // Make sure the call isn't optimized away.
+    std::hint::black_box(());
+}
+
+/// Guards the list of entries of the descriptor.
+static DESCRIPTOR_LOCK: Mutex<()> = Mutex::new(());
+
+/// An image registered with the GDB JIT interface, which is unregistered
+/// when dropped.
+pub struct GdbJitImageRegistration {
+    entry: *mut JitCodeEntry,
+    // The image must outlive its registration.
+    _image: Box<[u8]>,
+}
+
+// SAFETY: the entry is only accessed when holding `DESCRIPTOR_LOCK`.
+unsafe impl Send for GdbJitImageRegistration {}
+unsafe impl Sync for GdbJitImageRegistration {}
+
+impl GdbJitImageRegistration {
+    /// Registers an ELF image with the debugger.
+    pub fn register(image: Vec<u8>) -> Self {
+        let image = image.into_boxed_slice();
+        let entry = Box::into_raw(Box::new(JitCodeEntry {
+            next_entry: ptr::null_mut(),
+            prev_entry: ptr::null_mut(),
+            symfile_addr: image.as_ptr(),
+            symfile_size: image.len() as u64,
+        }));
+        let _guard = DESCRIPTOR_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // SAFETY: the descriptor and the entries are guarded by the lock.
+        unsafe {
+            let descriptor = ptr::addr_of_mut!(__jit_debug_descriptor);
+            let first = (*descriptor).first_entry;
+            (*entry).next_entry = first;
+            if !first.is_null() {
+                (*first).prev_entry = entry;
+            }
+            (*descriptor).first_entry = entry;
+            (*descriptor).relevant_entry = entry;
+            (*descriptor).action_flag = JitAction::RegisterFn as u32;
+            __jit_debug_register_code();
+            (*descriptor).action_flag = JitAction::NoAction as u32;
+            (*descriptor).relevant_entry = ptr::null_mut();
+        }
+        Self {
+            entry,
+            _image: image,
+        }
+    }
+}
+
+impl Drop for GdbJitImageRegistration {
+    fn drop(&mut self) {
+        let _guard = DESCRIPTOR_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // SAFETY: the descriptor and the entries are guarded by the lock, and
+        // the entry is still linked in the list.
+        unsafe {
+            let descriptor = ptr::addr_of_mut!(__jit_debug_descriptor);
+            let entry = self.entry;
+            let next = (*entry).next_entry;
+            let prev = (*entry).prev_entry;
+            if prev.is_null() {
+                (*descriptor).first_entry = next;
+            } else {
+                (*prev).next_entry = next;
+            }
+            if !next.is_null() {
+                (*next).prev_entry = prev;
+            }
+            (*descriptor).relevant_entry = entry;
+            (*descriptor).action_flag = JitAction::UnregisterFn as u32;
+            __jit_debug_register_code();
+            (*descriptor).action_flag = JitAction::NoAction as u32;
+            (*descriptor).relevant_entry = ptr::null_mut();
+            drop(Box::from_raw(entry));
+        }
+    }
+}
+
+/// The debug info of a module, used to fill in its compile unit.
+#[derive(Debug, Clone, Default)]
+pub struct DebugModuleInfo<'a> {
+    /// The name of the module.
+    pub name: &'a str,
+    /// The source language of the module, if it has DWARF.
+    pub language: Option<gimli::DwLang>,
+    /// The directory the module was compiled in, if it has DWARF.
+    pub comp_dir: Option<&'a str>,
+}
+
+/// Builds the ELF image describing the compiled functions of a module.
+///
+/// Returns `None` if the module has no functions, or if the host
+/// architecture isn't supported.
+pub fn build_image(
+    module: &DebugModuleInfo<'_>,
+    functions: &[ProfiledFunction<'_>],
+) -> Option<Vec<u8>> {
+    let e_machine = if cfg!(target_arch = "x86_64") {
+        elf::EM_X86_64
+    } else if cfg!(target_arch = "aarch64") {
+        elf::EM_AARCH64
+    } else if cfg!(target_arch = "riscv64") {
+        elf::EM_RISCV
+    } else {
+        return None;
+    };
+    let start = functions.iter().map(|f| f.address as u64).min()?;
+    let end = functions
+        .iter()
+        .map(|f| f.address as u64 + f.size as u64)
+        .max()?;
+    let dwarf = build_dwarf(module, functions, start, end).ok()?;
+
+    let mut image = Vec::new();
+    let mut writer = Writer::new(Endianness::Little, true, &mut image);
+    writer.reserve_file_header();
+    writer.reserve_program_headers(1);
+
+    writer.reserve_null_section_index();
+    let text_name = writer.add_section_name(b".text");
+    let text_index = writer.reserve_section_index();
+    let debug_sections: Vec<_> = dwarf
+        .iter()
+        .map(|(name, data)| {
+            (
+                writer.add_section_name(name.as_bytes()),
+                writer.reserve_section_index(),
+                data,
+            )
+        })
+        .collect();
+
+    writer.reserve_null_symbol_index();
+    let symbols: Vec<_> = functions
+        .iter()
+        .map(|function| {
+            writer.reserve_symbol_index(Some(text_index));
+            (writer.add_string(function.name.as_bytes()), function)
+        })
+        .collect();
+    writer.reserve_symtab_section_index();
+    writer.reserve_strtab_section_index();
+    writer.reserve_shstrtab_section_index();
+
+    writer.reserve_symtab();
+    let debug_offsets: Vec<_> = debug_sections
+        .iter()
+        .map(|(_, _, data)| writer.reserve(data.len(), 1))
+        .collect();
+    writer.reserve_strtab();
+    writer.reserve_shstrtab();
+    writer.reserve_section_headers();
+
+    writer
+        .write_file_header(&FileHeader {
+            os_abi: elf::ELFOSABI_NONE,
+            abi_version: 0,
+            e_type: elf::ET_EXEC,
+            e_machine,
+            e_entry: 0,
+            e_flags: 0,
+        })
+        .ok()?;
+    writer.write_align_program_headers();
+    writer.write_program_header(&ProgramHeader {
+        p_type: elf::PT_LOAD,
+        p_flags: elf::PF_R | elf::PF_X,
+        p_offset: 0,
+        p_vaddr: start,
+        p_paddr: start,
+        p_filesz: 0,
+        p_memsz: end - start,
+        p_align: 1,
+    });
+
+    writer.write_null_symbol();
+    for (name, function) in &symbols {
+        writer.write_symbol(&Sym {
+            name: Some(*name),
+            section: Some(text_index),
+            st_info: (elf::STB_GLOBAL << 4) | elf::STT_FUNC,
+            st_other: elf::STV_DEFAULT,
+            st_shndx: 0,
+            st_value: function.address as u64,
+            st_size: function.size as u64,
+        });
+    }
+    for (_, _, data) in &debug_sections {
+        writer.write(data);
+    }
+    writer.write_strtab();
+    writer.write_shstrtab();
+
+    writer.write_null_section_header();
+    writer.write_section_header(&SectionHeader {
+        name: Some(text_name),
+        sh_type: elf::SHT_NOBITS,
+        sh_flags: (elf::SHF_ALLOC | elf::SHF_EXECINSTR).into(),
+        sh_addr: start,
+        sh_offset: 0,
+        sh_size: end - start,
+        sh_link: 0,
+        sh_info: 0,
+        sh_addralign: 16,
+        sh_entsize: 0,
+    });
+    for ((name, _, data), offset) in debug_sections.iter().zip(debug_offsets) {
+        writer.write_section_header(&SectionHeader {
+            name: Some(*name),
+            sh_type: elf::SHT_PROGBITS,
+            sh_flags: 0,
+            sh_addr: 0,
+            sh_offset: offset as u64,
+            sh_size: data.len() as u64,
+            sh_link: 0,
+            sh_info: 0,
+            sh_addralign: 1,
+            sh_entsize: 0,
+        });
+    }
+    writer.write_symtab_section_header(1);
+    writer.write_strtab_section_header();
+    writer.write_shstrtab_section_header();
+
+    debug_assert_eq!(writer.reserved_len(), writer.len());
+    Some(image)
+}
+
+/// Writes the native DWARF sections of the functions of a module.
+fn build_dwarf(
+    module: &DebugModuleInfo<'_>,
+    functions: &[ProfiledFunction<'_>],
+    start: u64,
+    end: u64,
+) -> gimli::write::Result<Vec<(&'static str, Vec<u8>)>> {
+    let encoding = Encoding {
+        format: Format::Dwarf32,
+        version: 4,
+        address_size: 8,
+    };
+    let mut dwarf = DwarfUnit::new(encoding);
+    let comp_dir = module.comp_dir.unwrap_or_default();
+
+    let mut program = LineProgram::new(
+        encoding,
+        LineEncoding::default(),
+        LineString::String(comp_dir.as_bytes().to_vec()),
+        LineString::String(module.name.as_bytes().to_vec()),
+        None,
+    );
+    for function in functions {
+        if function.lines.is_empty() {
+            continue;
+        }
+        program.begin_sequence(Some(Address::Constant(function.address as u64)));
+        for line in function.lines {
+            let (dir, file) = match line.file.rsplit_once('/') {
+                Some((dir, file)) => (dir, file),
+                None => ("", line.file.as_str()),
+            };
+            let dir = if dir.is_empty() {
+                program.default_directory()
+            } else {
+                program.add_directory(LineString::String(dir.as_bytes().to_vec()))
+            };
+            let file = program.add_file(LineString::String(file.as_bytes().to_vec()), dir, None);
+            let row = program.row();
+            row.address_offset = line.code_offset as u64;
+            row.file = file;
+            row.line = line.line.into();
+            row.column = line.column.into();
+            row.is_statement = true;
+            program.generate_row();
+        }
+        program.end_sequence(function.size as u64);
+    }
+    if !program.is_empty() {
+        dwarf.unit.line_program = program;
+    }
+
+    let root = dwarf.unit.root();
+    let unit = dwarf.unit.get_mut(root);
+    unit.set(
+        gimli::DW_AT_producer,
+        AttributeValue::String(b"wasmer".to_vec()),
+    );
+    unit.set(
+        gimli::DW_AT_name,
+        AttributeValue::String(module.name.as_bytes().to_vec()),
+    );
+    if let Some(comp_dir) = module.comp_dir {
+        unit.set(
+            gimli::DW_AT_comp_dir,
+            AttributeValue::String(comp_dir.as_bytes().to_vec()),
+        );
+    }
+    if let Some(language) = module.language {
+        unit.set(gimli::DW_AT_language, AttributeValue::Language(language));
+    }
+    unit.set(
+        gimli::DW_AT_low_pc,
+        AttributeValue::Address(Address::Constant(start)),
+    );
+    unit.set(gimli::DW_AT_high_pc, AttributeValue::Udata(end - start));
+
+    for function in functions {
+        let id = dwarf.unit.add(root, gimli::DW_TAG_subprogram);
+        let subprogram = dwarf.unit.get_mut(id);
+        subprogram.set(
+            gimli::DW_AT_name,
+            AttributeValue::String(function.name.as_bytes().to_vec()),
+        );
+        subprogram.set(gimli::DW_AT_external, AttributeValue::Flag(true));
+        subprogram.set(
+            gimli::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(function.address as u64)),
+        );
+        subprogram.set(
+            gimli::DW_AT_high_pc,
+            AttributeValue::Udata(function.size as u64),
+        );
+    }
+
+    let mut sections = Sections::new(EndianVec::new(LittleEndian));
+    dwarf.write(&mut sections)?;
+    let mut result = Vec::new();
+    sections.for_each(|id, data| {
+        if !data.slice().is_empty() {
+            result.push((id.name(), data.slice().to_vec()));
+        }
+        Ok::<_, gimli::write::Error>(())
+    })?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LineInfo;
+    use gimli::EndianSlice;
+    use object::{Object, ObjectSection, ObjectSymbol};
+
+    #[test]
+    fn translates_line_tables() {
+        let code = [0u8; 64];
+        let lines = [
+            LineInfo {
+                code_offset: 0,
+                file: "/src/lib.rs".to_string(),
+                line: 3,
+                column: 5,
+            },
+            LineInfo {
+                code_offset: 12,
+                file: "/src/lib.rs".to_string(),
+                line: 4,
+                column: 9,
+            },
+        ];
+        let functions = [
+            ProfiledFunction {
+                name: "demo::add",
+                address: code.as_ptr(),
+                size: 32,
+                lines: &lines,
+            },
+            ProfiledFunction {
+                name: "demo::function[1]",
+                address: code[32..].as_ptr(),
+                size: 32,
+                lines: &[],
+            },
+        ];
+        let module = DebugModuleInfo {
+            name: "demo",
+            language: Some(gimli::DW_LANG_Rust),
+            comp_dir: Some("/src"),
+        };
+        let image = build_image(&module, &functions).unwrap();
+
+        let object = object::File::parse(&*image).unwrap();
+        let symbols: Vec<_> = object
+            .symbols()
+            .filter(|symbol| symbol.kind() == object::SymbolKind::Text)
+            .map(|symbol| (symbol.name().unwrap().to_string(), symbol.address()))
+            .collect();
+        assert_eq!(
+            symbols,
+            [
+                ("demo::add".to_string(), code.as_ptr() as u64),
+                ("demo::function[1]".to_string(), code.as_ptr() as u64 + 32),
+            ]
+        );
+
+        let dwarf = gimli::Dwarf::load(|id| {
+            let data = object
+                .section_by_name(id.name())
+                .map_or(&[][..], |section| section.data().unwrap());
+            Ok::<_, gimli::Error>(EndianSlice::new(data, LittleEndian))
+        })
+        .unwrap();
+        let header = dwarf.units().next().unwrap().unwrap();
+        let unit = dwarf.unit(header).unwrap();
+        let mut rows = unit.line_program.clone().unwrap().rows();
+        let mut result = Vec::new();
+        while let Some((_, row)) = rows.next_row().unwrap() {
+            if !row.end_sequence() {
+                result.push((row.address(), row.line().unwrap().get()));
+            }
+        }
+        let base = code.as_ptr() as u64;
+        assert_eq!(result, [(base, 3), (base + 12, 4)]);
+
+        let mut subprograms = Vec::new();
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            if entry.tag() == gimli::DW_TAG_subprogram {
+                let name = entry.attr_value(gimli::DW_AT_name).unwrap().unwrap();
+                subprograms.push(
+                    dwarf
+                        .attr_string(&unit, name)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned(),
+                );
+            }
+        }
+        assert_eq!(subprograms, ["demo::add", "demo::function[1]"]);
+    }
+
+    #[test]
+    fn registers_images() {
+        fn entries() -> usize {
+            let _guard = DESCRIPTOR_LOCK.lock().unwrap();
+            let mut count = 0;
+            // SAFETY: the entries are guarded by the lock.
+            unsafe {
+                let mut entry = (*ptr::addr_of!(__jit_debug_descriptor)).first_entry;
+                while !entry.is_null() {
+                    count += 1;
+                    entry = (*entry).next_entry;
+                }
+            }
+            count
+        }
+
+        let before = entries();
+        let first = GdbJitImageRegistration::register(vec![1]);
+        let second = GdbJitImageRegistration::register(vec![2]);
+        assert_eq!(entries(), before + 2);
+        drop(first);
+        assert_eq!(entries(), before + 1);
+        drop(second);
+        assert_eq!(entries(), before);
+    }
+}
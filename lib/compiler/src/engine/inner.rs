@@ -1,5 +1,7 @@
 //! Universal compilation.
 
+#[cfg(all(feature = "gdb-jit", not(target_arch = "wasm32")))]
+use crate::engine::gdb_jit::GdbJitImageRegistration;
 use crate::{engine::builder::EngineBuilder, types::target::Target};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::{
@@ -7,7 +9,8 @@ use crate::{
         function::FunctionBodyLike,
         section::{CustomSectionLike, CustomSectionProtection, SectionIndex},
     },
-    Artifact, BaseTunables, CodeMemory, FunctionExtent, GlobalFrameInfoRegistration, Tunables,
+    Artifact, BaseTunables, CodeMemory, FunctionExtent, GlobalFrameInfoRegistration,
+    ProfilingAgent, Tunables,
 };
 #[cfg(feature = "compiler")]
 use crate::{Compiler, CompilerConfig};
@@ -17,7 +20,7 @@ use shared_buffer::OwnedBuffer;
 
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
-use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering::SeqCst};
 use std::sync::{Arc, Mutex};
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -44,6 +47,7 @@ pub struct Engine {
     tunables: Arc<dyn Tunables + Send + Sync>,
     name: String,
     hash_algorithm: Option<HashAlgorithm>,
+    epoch: Arc<AtomicU64>,
 }
 
 impl Engine {
@@ -66,6 +70,10 @@ impl Engine {
                 code_memory: vec![],
                 #[cfg(not(target_arch = "wasm32"))]
                 signatures: SignatureRegistry::new(),
+                #[cfg(not(target_arch = "wasm32"))]
+                profiler: None,
+                #[cfg(not(target_arch = "wasm32"))]
+                debug_info: false,
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
@@ -73,6 +81,7 @@ impl Engine {
             tunables: Arc::new(tunables),
             name,
             hash_algorithm: None,
+            epoch: Arc::new(AtomicU64::new(0)),
         }
     }
 
@@ -136,6 +145,10 @@ impl Engine {
                 code_memory: vec![],
                 #[cfg(not(target_arch = "wasm32"))]
                 signatures: SignatureRegistry::new(),
+                #[cfg(not(target_arch = "wasm32"))]
+                profiler: None,
+                #[cfg(not(target_arch = "wasm32"))]
+                debug_info: false,
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
@@ -143,9 +156,48 @@ impl Engine {
             tunables: Arc::new(tunables),
             name: "engine-headless".to_string(),
             hash_algorithm: None,
+            epoch: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Increments the epoch of this engine.
+    ///
+    /// Code compiled with epoch interruption enabled checks the epoch
+    /// against the deadline of its store, so incrementing it from another
+    /// thread is a cheap way to interrupt long-running WebAssembly code.
+    pub fn increment_epoch(&self) {
+        self.epoch.fetch_add(1, SeqCst);
+    }
+
+    /// Returns the current epoch of this engine.
+    pub fn current_epoch(&self) -> u64 {
+        self.epoch.load(SeqCst)
+    }
+
+    /// Returns the epoch counter shared by all the stores of this engine.
+    pub fn epoch_counter(&self) -> Arc<AtomicU64> {
+        self.epoch.clone()
+    }
+
+    /// Sets the agent recording the compiled functions for profilers.
+    ///
+    /// Only the functions compiled or deserialized afterwards are recorded.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<dyn ProfilingAgent>>) {
+        self.inner_mut().profiler = profiler;
+    }
+
+    /// Sets whether the compiled functions are registered with the GDB JIT
+    /// interface, along with the DWARF of the modules translated to native
+    /// code, so that `gdb` and `lldb` can debug them.
+    ///
+    /// Only the functions compiled or deserialized afterwards are registered,
+    /// and only if the `gdb-jit` feature is enabled.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_debug_info(&mut self, debug_info: bool) {
+        self.inner_mut().debug_info = debug_info;
+    }
+
     /// Get reference to `EngineInner`.
     pub fn inner(&self) -> std::sync::MutexGuard<'_, EngineInner> {
         self.inner.lock().unwrap()
@@ -318,6 +370,12 @@ pub struct EngineInner {
     /// performantly.
     #[cfg(not(target_arch = "wasm32"))]
     signatures: SignatureRegistry,
+    /// The agent recording the compiled functions for profilers.
+    #[cfg(not(target_arch = "wasm32"))]
+    profiler: Option<Arc<dyn ProfilingAgent>>,
+    /// Whether the compiled functions are registered with debuggers.
+    #[cfg(not(target_arch = "wasm32"))]
+    debug_info: bool,
 }
 
 impl EngineInner {
@@ -464,6 +522,28 @@ impl EngineInner {
         Ok(())
     }
 
+    /// The agent recording the compiled functions for profilers.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn profiler(&self) -> Option<&Arc<dyn ProfilingAgent>> {
+        self.profiler.as_ref()
+    }
+
+    /// Whether the compiled functions are registered with debuggers.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn debug_info(&self) -> bool {
+        cfg!(feature = "gdb-jit") && self.debug_info
+    }
+
+    /// Register an image with the GDB JIT interface, so it's unregistered
+    /// when the code memory gets freed.
+    #[cfg(all(feature = "gdb-jit", not(target_arch = "wasm32")))]
+    pub(crate) fn register_gdb_jit_image(&mut self, registration: GdbJitImageRegistration) {
+        self.code_memory
+            .last_mut()
+            .unwrap()
+            .register_gdb_jit_image(registration);
+    }
+
     /// Shared signature registry.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn signatures(&self) -> &SignatureRegistry {
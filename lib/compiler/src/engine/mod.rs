@@ -16,6 +16,9 @@ mod builder;
 #[cfg(feature = "translator")]
 #[cfg(not(target_arch = "wasm32"))]
 mod code_memory;
+#[cfg(all(feature = "translator", feature = "gdb-jit"))]
+#[cfg(not(target_arch = "wasm32"))]
+mod gdb_jit;
 #[cfg(feature = "translator")]
 mod inner;
 #[cfg(feature = "translator")]
@@ -23,6 +26,9 @@ mod inner;
 mod link;
 #[cfg(feature = "translator")]
 #[cfg(not(target_arch = "wasm32"))]
+mod profiling;
+#[cfg(feature = "translator")]
+#[cfg(not(target_arch = "wasm32"))]
 mod unwind;
 
 pub use self::error::{InstantiationError, LinkError};
@@ -46,3 +52,9 @@ pub use self::inner::{Engine, EngineInner};
 #[cfg(feature = "translator")]
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::link::link_module;
+#[cfg(feature = "translator")]
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::profiling::{
+    JitDumpAgent, LineInfo, LineTable, PerfMapAgent, ProfiledFunction, ProfilingAgent,
+    ProfilingStrategy,
+};
@@ -0,0 +1,481 @@
+//! Profiling agents, telling external profilers such as `perf` or
+//! `samply` about the functions compiled by the engine.
+//!
+//! Without them, the frames of WebAssembly functions show up as anonymous
+//! addresses in the profiles.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use gimli::{EndianSlice, LittleEndian};
+use wasmer_types::ModuleInfo;
+
+use crate::types::address_map::FunctionAddressMap;
+
+/// The format in which the compiled functions are recorded for profilers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfilingStrategy {
+    /// Write the symbols to `/tmp/perf-<pid>.map`, which `perf` and
+    /// `samply` read to name the addresses of JIT-compiled code.
+    PerfMap,
+    /// Write jitdump records to `jit-<pid>.dump`, to be merged into
+    /// `perf` profiles with `perf inject --jit`. Unlike perf maps,
+    /// jitdump records contain the code of the functions and their
+    /// source lines.
+    JitDump,
+}
+
+impl ProfilingStrategy {
+    /// Creates the agent recording the compiled functions in this format.
+    pub fn agent(&self) -> io::Result<Arc<dyn ProfilingAgent>> {
+        Ok(match self {
+            Self::PerfMap => Arc::new(PerfMapAgent::new()?),
+            Self::JitDump => Arc::new(JitDumpAgent::new()?),
+        })
+    }
+}
+
+impl FromStr for ProfilingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "perfmap" => Ok(Self::PerfMap),
+            "jitdump" => Ok(Self::JitDump),
+            _ => Err(format!(
+                "unknown profiling strategy `{s}`, expected `perfmap` or `jitdump`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ProfilingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PerfMap => "perfmap",
+            Self::JitDump => "jitdump",
+        })
+    }
+}
+
+/// The source line of some generated code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineInfo {
+    /// The offset of the code in the function.
+    pub code_offset: usize,
+    /// The source file.
+    pub file: String,
+    /// The line in the file, starting at 1.
+    pub line: u32,
+    /// The column in the line, or 0 if unknown.
+    pub column: u32,
+}
+
+/// A function published by the engine.
+#[derive(Debug, Clone)]
+pub struct ProfiledFunction<'a> {
+    /// The name of the function.
+    pub name: &'a str,
+    /// The address of its code.
+    pub address: *const u8,
+    /// The size of its code.
+    pub size: usize,
+    /// The source lines of its code, sorted by offset, if the module has
+    /// DWARF debug info.
+    pub lines: &'a [LineInfo],
+}
+
+/// Records the functions published by an engine for a profiler.
+///
+/// Errors while recording are ignored, as profiling is best effort and
+/// must not make the compilation fail.
+pub trait ProfilingAgent: Send + Sync {
+    /// Records a function, whose code is executable from now on.
+    fn register_function(&self, function: &ProfiledFunction<'_>);
+}
+
+/// Writes the symbols of the functions to `/tmp/perf-<pid>.map`.
+pub struct PerfMapAgent {
+    file: Mutex<File>,
+}
+
+impl PerfMapAgent {
+    /// Creates the perf map of the current process.
+    pub fn new() -> io::Result<Self> {
+        let path = format!("/tmp/perf-{}.map", std::process::id());
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl ProfilingAgent for PerfMapAgent {
+    fn register_function(&self, function: &ProfiledFunction<'_>) {
+        let mut file = self.file.lock().unwrap();
+        // Each entry is written at once, as several processes may append
+        // to the same map.
+        let entry = format!(
+            "{:x} {:x} {}\n",
+            function.address as usize, function.size, function.name
+        );
+        let _ = file.write_all(entry.as_bytes());
+    }
+}
+
+/// Writes jitdump records to `jit-<pid>.dump`, in the directory given by
+/// the `JITDUMPDIR` environment variable or the current directory.
+///
+/// See `tools/perf/Documentation/jitdump-specification.txt` in the Linux
+/// sources for the format.
+pub struct JitDumpAgent {
+    file: Mutex<File>,
+    code_index: AtomicU64,
+    /// The mapping of the file, which tells `perf record` where the records
+    /// of this process are.
+    #[cfg(unix)]
+    _marker: JitDumpMarker,
+}
+
+const JITDUMP_MAGIC: u32 = 0x4A69_5444;
+const JITDUMP_VERSION: u32 = 1;
+const JIT_CODE_LOAD: u32 = 0;
+const JIT_CODE_DEBUG_INFO: u32 = 2;
+
+impl JitDumpAgent {
+    /// Creates the jitdump file of the current process.
+    pub fn new() -> io::Result<Self> {
+        if !cfg!(unix) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "jitdump profiling is only supported on Unix",
+            ));
+        }
+        let dir =
+            std::env::var_os("JITDUMPDIR").map_or_else(|| ".".into(), std::path::PathBuf::from);
+        let path = dir.join(format!("jit-{}.dump", std::process::id()));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+
+        let mut header = Vec::with_capacity(40);
+        header.extend_from_slice(&JITDUMP_MAGIC.to_ne_bytes());
+        header.extend_from_slice(&JITDUMP_VERSION.to_ne_bytes());
+        header.extend_from_slice(&40u32.to_ne_bytes());
+        header.extend_from_slice(&elf_machine().to_ne_bytes());
+        header.extend_from_slice(&0u32.to_ne_bytes());
+        header.extend_from_slice(&std::process::id().to_ne_bytes());
+        header.extend_from_slice(&timestamp().to_ne_bytes());
+        header.extend_from_slice(&0u64.to_ne_bytes());
+        file.write_all(&header)?;
+
+        Ok(Self {
+            #[cfg(unix)]
+            _marker: JitDumpMarker::new(&file)?,
+            file: Mutex::new(file),
+            code_index: AtomicU64::new(0),
+        })
+    }
+
+    fn record(id: u32, body: &[u8]) -> Vec<u8> {
+        let mut record = Vec::with_capacity(16 + body.len());
+        record.extend_from_slice(&id.to_ne_bytes());
+        record.extend_from_slice(&((16 + body.len()) as u32).to_ne_bytes());
+        record.extend_from_slice(&timestamp().to_ne_bytes());
+        record.extend_from_slice(body);
+        record
+    }
+}
+
+impl ProfilingAgent for JitDumpAgent {
+    fn register_function(&self, function: &ProfiledFunction<'_>) {
+        let address = function.address as u64;
+        let mut records = Vec::new();
+
+        // The debug info of some code must be written before it is loaded.
+        if !function.lines.is_empty() {
+            let mut body = Vec::new();
+            body.extend_from_slice(&address.to_ne_bytes());
+            body.extend_from_slice(&(function.lines.len() as u64).to_ne_bytes());
+            for line in function.lines {
+                body.extend_from_slice(&(address + line.code_offset as u64).to_ne_bytes());
+                body.extend_from_slice(&line.line.to_ne_bytes());
+                // The discriminator, as DWARF columns have no place in the
+                // records.
+                body.extend_from_slice(&0u32.to_ne_bytes());
+                body.extend_from_slice(line.file.as_bytes());
+                body.push(0);
+            }
+            records.extend(Self::record(JIT_CODE_DEBUG_INFO, &body));
+        }
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&std::process::id().to_ne_bytes());
+        body.extend_from_slice(&thread_id().to_ne_bytes());
+        body.extend_from_slice(&address.to_ne_bytes());
+        body.extend_from_slice(&address.to_ne_bytes());
+        body.extend_from_slice(&(function.size as u64).to_ne_bytes());
+        let code_index = self.code_index.fetch_add(1, Ordering::Relaxed);
+        body.extend_from_slice(&code_index.to_ne_bytes());
+        body.extend_from_slice(function.name.as_bytes());
+        body.push(0);
+        // SAFETY: the engine only registers functions whose code is
+        // published, so `size` bytes are readable at `address`.
+        let code = unsafe { std::slice::from_raw_parts(function.address, function.size) };
+        body.extend_from_slice(code);
+        records.extend(Self::record(JIT_CODE_LOAD, &body));
+
+        let _ = self.file.lock().unwrap().write_all(&records);
+    }
+}
+
+#[cfg(unix)]
+struct JitDumpMarker {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is never accessed, it only exists for `perf record`.
+#[cfg(unix)]
+unsafe impl Send for JitDumpMarker {}
+#[cfg(unix)]
+unsafe impl Sync for JitDumpMarker {}
+
+#[cfg(unix)]
+impl JitDumpMarker {
+    fn new(file: &File) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = region::page::size();
+        // SAFETY: mapping a file has no effect on the memory of the process
+        // besides the new mapping, which is released on drop.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_EXEC,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for JitDumpMarker {
+    fn drop(&mut self) {
+        // SAFETY: the mapping was created in `new`.
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+fn elf_machine() -> u32 {
+    if cfg!(target_arch = "x86_64") {
+        62
+    } else if cfg!(target_arch = "aarch64") {
+        183
+    } else if cfg!(target_arch = "riscv64") {
+        243
+    } else if cfg!(target_arch = "x86") {
+        3
+    } else {
+        0
+    }
+}
+
+/// The timestamp of the records, which must use the clock of
+/// `perf record -k mono`.
+fn timestamp() -> u64 {
+    #[cfg(unix)]
+    {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: `ts` is a valid `timespec`.
+        unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+        ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+    }
+    #[cfg(not(unix))]
+    {
+        0
+    }
+}
+
+fn thread_id() -> u32 {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: `gettid` has no preconditions.
+        unsafe { libc::syscall(libc::SYS_gettid) as u32 }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        std::process::id()
+    }
+}
+
+/// A row of the DWARF line table of a module.
+#[derive(Debug, Clone)]
+struct LineRow {
+    /// The offset in the code section.
+    address: u64,
+    file: Arc<str>,
+    line: u32,
+    column: u32,
+}
+
+/// The DWARF line table of a module, mapping the offsets of its bytecode
+/// to source lines.
+#[derive(Debug, Clone)]
+pub struct LineTable {
+    rows: Vec<LineRow>,
+    code_section_offset: u64,
+    language: Option<gimli::DwLang>,
+    comp_dir: Option<String>,
+}
+
+impl LineTable {
+    /// Parses the `.debug_line` section of a module, if it has one.
+    ///
+    /// `code_section_offset` is the offset of the code section in the
+    /// module, which DWARF addresses are relative to.
+    pub fn new(module: &ModuleInfo, code_section_offset: u64) -> Option<Self> {
+        let section = |name: &str| {
+            module
+                .custom_sections
+                .get(name)
+                .map_or(&[][..], |index| &*module.custom_sections_data[*index])
+        };
+        if section(".debug_line").is_empty() {
+            return None;
+        }
+        let dwarf = gimli::Dwarf::load(|id| {
+            Ok::<_, gimli::Error>(EndianSlice::new(section(id.name()), LittleEndian))
+        })
+        .ok()?;
+
+        let mut rows = Vec::new();
+        let mut language = None;
+        let mut comp_dir = None;
+        let mut first_unit = true;
+        let mut units = dwarf.units();
+        while let Ok(Some(header)) = units.next() {
+            let Ok(unit) = dwarf.unit(header) else {
+                continue;
+            };
+            if first_unit {
+                first_unit = false;
+                let mut entries = unit.entries();
+                if let Ok(Some((_, root))) = entries.next_dfs() {
+                    if let Ok(Some(gimli::AttributeValue::Language(lang))) =
+                        root.attr_value(gimli::DW_AT_language)
+                    {
+                        language = Some(lang);
+                    }
+                }
+                comp_dir = unit.comp_dir.map(|dir| dir.to_string_lossy().into_owned());
+            }
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+            let mut program_rows = program.rows();
+            while let Ok(Some((header, row))) = program_rows.next_row() {
+                if row.end_sequence() {
+                    continue;
+                }
+                let Some(file) = row.file(header) else {
+                    continue;
+                };
+                let mut path = String::new();
+                if let Some(dir) = file.directory(header) {
+                    if let Ok(dir) = dwarf.attr_string(&unit, dir) {
+                        path.push_str(&dir.to_string_lossy());
+                        if !path.is_empty() && !path.ends_with('/') {
+                            path.push('/');
+                        }
+                    }
+                }
+                if let Ok(name) = dwarf.attr_string(&unit, file.path_name()) {
+                    path.push_str(&name.to_string_lossy());
+                }
+                rows.push(LineRow {
+                    address: row.address(),
+                    file: path.into(),
+                    line: row.line().map_or(0, |line| line.get() as u32),
+                    column: match row.column() {
+                        gimli::ColumnType::LeftEdge => 0,
+                        gimli::ColumnType::Column(column) => column.get() as u32,
+                    },
+                });
+            }
+        }
+        rows.sort_by_key(|row| row.address);
+        Some(Self {
+            rows,
+            code_section_offset,
+            language,
+            comp_dir,
+        })
+    }
+
+    /// The source language of the first compile unit.
+    pub fn language(&self) -> Option<gimli::DwLang> {
+        self.language
+    }
+
+    /// The compilation directory of the first compile unit.
+    pub fn comp_dir(&self) -> Option<&str> {
+        self.comp_dir.as_deref()
+    }
+
+    /// The file and line of the instruction at the given offset in the
+    /// module.
+    pub fn location(&self, offset: u64) -> Option<(&str, u32)> {
+        self.row(offset).map(|row| (&*row.file, row.line))
+    }
+
+    fn row(&self, offset: u64) -> Option<&LineRow> {
+        let address = offset.checked_sub(self.code_section_offset)?;
+        let index = self.rows.partition_point(|row| row.address <= address);
+        index.checked_sub(1).map(|index| &self.rows[index])
+    }
+
+    /// The source lines of the code of a function.
+    pub fn lines(&self, address_map: &FunctionAddressMap) -> Vec<LineInfo> {
+        let mut lines: Vec<LineInfo> = Vec::new();
+        for instruction in &address_map.instructions {
+            if instruction.srcloc.is_default() {
+                continue;
+            }
+            let Some(row) = self.row(instruction.srcloc.bits() as u64) else {
+                continue;
+            };
+            if let Some(last) = lines.last() {
+                if *last.file == *row.file && last.line == row.line && last.column == row.column {
+                    continue;
+                }
+            }
+            lines.push(LineInfo {
+                code_offset: instruction.code_offset,
+                file: row.file.to_string(),
+                line: row.line,
+                column: row.column,
+            });
+        }
+        lines
+    }
+}
@@ -11,6 +11,8 @@ pub fn get_trace_and_trapcode(trap: &Trap) -> (Vec<FrameInfo>, Option<TrapCode>)
         Trap::User(_err) => (wasm_trace(&info, None, &Backtrace::new_unresolved()), None),
         // A trap caused by the VM being Out of Memory
         Trap::OOM { backtrace } => (wasm_trace(&info, None, backtrace), None),
+        // An exception thrown by Wasm code that reached the host
+        Trap::UncaughtException { backtrace, .. } => (wasm_trace(&info, None, backtrace), None),
         // A trap caused by an error on the generated machine code for a Wasm function
         Trap::Wasm {
             pc,
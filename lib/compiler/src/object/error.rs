@@ -17,6 +17,9 @@ pub enum ObjectError {
     /// The object was provided a not-supported architecture
     #[error("Error when writing the object: {0}")]
     Write(#[from] ObjectWriteError),
+    /// The compilation uses a feature that object files can't represent
+    #[error("{0} are not supported in object files")]
+    UnsupportedFeature(String),
     /// The module provided could not be serialized into bytes
     #[error("Error when serializing the given module: {0}")]
     Serialize(#[from] wasmer_types::SerializeError),
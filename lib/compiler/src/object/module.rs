@@ -139,6 +139,13 @@ pub fn emit_compilation(
     symbol_registry: &impl SymbolRegistry,
     triple: &Triple,
 ) -> Result<(), ObjectError> {
+    if !compilation.function_entry_trampolines.is_empty() {
+        // The symbols of the functions would point to bodies that can't be
+        // called with the native calling convention.
+        return Err(ObjectError::UnsupportedFeature(
+            "Function entry trampolines".to_string(),
+        ));
+    }
     let mut function_bodies = PrimaryMap::with_capacity(compilation.functions.len());
     let mut function_relocations = PrimaryMap::with_capacity(compilation.functions.len());
     for (_, func) in compilation.functions.into_iter() {
@@ -34,6 +34,8 @@ pub struct SerializableCompilation {
     pub dynamic_function_trampolines: PrimaryMap<FunctionIndex, FunctionBody>,
     pub custom_sections: PrimaryMap<SectionIndex, CustomSection>,
     pub custom_section_relocations: PrimaryMap<SectionIndex, Vec<Relocation>>,
+    // Custom sections containing the native entry points of the functions, if any.
+    pub function_entry_trampolines: PrimaryMap<LocalFunctionIndex, SectionIndex>,
     // The section indices corresponding to the Dwarf debug info
     pub debug: Option<Dwarf>,
     // Custom section containing libcall trampolines.
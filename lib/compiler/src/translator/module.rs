@@ -8,7 +8,7 @@ use super::error::from_binaryreadererror_wasmerror;
 use super::sections::{
     parse_data_section, parse_element_section, parse_export_section, parse_function_section,
     parse_global_section, parse_import_section, parse_memory_section, parse_name_section,
-    parse_start_section, parse_table_section, parse_type_section,
+    parse_start_section, parse_table_section, parse_tag_section, parse_type_section,
 };
 use super::state::ModuleTranslationState;
 use wasmer_types::WasmResult;
@@ -97,8 +97,8 @@ pub fn translate_module<'data>(
                 unimplemented!("module linking not implemented. It will only be implemented if/when browsers support it")
             }
 
-            Payload::TagSection(_) => {
-                unimplemented!("exception handling not implemented yet")
+            Payload::TagSection(tags) => {
+                parse_tag_section(tags, environ)?;
             }
 
             Payload::CustomSection(sectionreader) => {
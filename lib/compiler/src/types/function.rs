@@ -188,6 +188,16 @@ pub struct Compilation {
     /// Note: Dynamic function trampolines are only compiled for imported function types.
     pub dynamic_function_trampolines: PrimaryMap<FunctionIndex, FunctionBody>,
 
+    /// Executable custom sections holding the native entry points of the
+    /// functions, for compilers using a calling convention other than the
+    /// native one for the function bodies (for example, to support tail
+    /// calls).
+    ///
+    /// Each entry trampoline is called with the native calling convention,
+    /// calls the function body and returns its results. It is empty if the
+    /// function bodies can be called directly.
+    pub function_entry_trampolines: PrimaryMap<LocalFunctionIndex, SectionIndex>,
+
     /// Section ids corresponding to the Dwarf debug info
     pub debug: Option<Dwarf>,
 }
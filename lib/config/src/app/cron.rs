@@ -0,0 +1,353 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::job::JobTimezone;
+
+/// A cron expression, such as `*/15 9-17 * * mon-fri`.
+///
+/// The five fields are the minute (0-59), hour (0-23), day of the month
+/// (1-31), month (1-12 or `jan`-`dec`) and day of the week (0-7 or
+/// `sun`-`sat`, where both 0 and 7 are Sunday). Each field is a `*`, a value,
+/// a range (`1-5`) or a comma-separated list of those, each optionally
+/// followed by a step (`*/10`, `0-30/5`).
+///
+/// Like in Vixie cron, when both the day of the month and the day of the week
+/// are restricted, a day matches if either of them does.
+///
+/// The nicknames `@yearly` (or `@annually`), `@monthly`, `@weekly`, `@daily`
+/// (or `@midnight`) and `@hourly` are also accepted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronExpression {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAYS_OF_WEEK: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+impl CronExpression {
+    /// Get the first time matching the expression that is strictly after
+    /// `after`.
+    ///
+    /// Times are in seconds since the Unix epoch, and the expression is
+    /// evaluated in the local time of `timezone`. Local times skipped by a
+    /// daylight saving time change never match, and local times that repeat
+    /// match once. Returns [`None`] if nothing matches in the next few years,
+    /// e.g. for `0 0 31 2 *`.
+    pub fn next_after(&self, after: i64, timezone: JobTimezone) -> Option<i64> {
+        // Work in local time, starting from the next whole minute.
+        let local_after = after + i64::from(timezone.utc_offset_at(after));
+        let mut t = local_after.div_euclid(60) * 60 + 60;
+        let limit = t + 5 * 366 * SECONDS_PER_DAY;
+
+        while t < limit {
+            let days = t.div_euclid(SECONDS_PER_DAY);
+            let seconds_of_day = t.rem_euclid(SECONDS_PER_DAY);
+            let (year, month, day) = civil_from_days(days);
+
+            if !is_set(self.months, month) {
+                // Skip to the first day of the next month
+                let (year, month) = if month == 12 {
+                    (year + 1, 1)
+                } else {
+                    (year, month + 1)
+                };
+                t = days_from_civil(year, month, 1) * SECONDS_PER_DAY;
+                continue;
+            }
+
+            if !self.matches_day(day, weekday_from_days(days)) {
+                t = (days + 1) * SECONDS_PER_DAY;
+                continue;
+            }
+
+            let hour = (seconds_of_day / 3600) as u32;
+            if !is_set(self.hours, hour) {
+                t = days * SECONDS_PER_DAY + i64::from(hour + 1) * 3600;
+                continue;
+            }
+
+            let minute = (seconds_of_day % 3600 / 60) as u32;
+            if !is_set(self.minutes, minute) {
+                t += 60;
+                continue;
+            }
+
+            match timezone.first_instant_after(t, after) {
+                Some(instant) => return Some(instant),
+                None => t += 60,
+            }
+        }
+
+        None
+    }
+
+    fn matches_day(&self, day: u32, weekday: u32) -> bool {
+        let day_of_month = is_set(self.days_of_month, day);
+        let day_of_week = is_set(self.days_of_week, weekday);
+
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => day_of_week,
+            (false, true) => day_of_month,
+            (false, false) => day_of_month || day_of_week,
+        }
+    }
+}
+
+impl FromStr for CronExpression {
+    type Err = CronParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source = s.trim();
+        let expanded = match source.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            _ => source,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(CronParseError::new(
+                source,
+                format!("expected 5 fields, found {}", fields.len()),
+            ));
+        };
+
+        let parse = |field: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(field, min, max, names)
+                .map_err(|reason| CronParseError::new(source, reason))
+        };
+
+        let mut days_of_week_mask = parse(days_of_week, 0, 7, &DAYS_OF_WEEK)?;
+        // Both 0 and 7 are Sunday
+        if is_set(days_of_week_mask, 7) {
+            days_of_week_mask |= 1;
+        }
+
+        Ok(CronExpression {
+            source: source.to_string(),
+            minutes: parse(minutes, 0, 59, &[])?,
+            hours: parse(hours, 0, 23, &[])?,
+            days_of_month: parse(days_of_month, 1, 31, &[])?,
+            months: parse(months, 1, 12, &MONTHS)?,
+            days_of_week: days_of_week_mask,
+            any_day_of_month: days_of_month.starts_with('*'),
+            any_day_of_week: days_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl Display for CronExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// The error returned when a [`CronExpression`] is invalid.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("invalid cron expression '{expression}': {reason}")]
+pub struct CronParseError {
+    expression: String,
+    reason: String,
+}
+
+impl CronParseError {
+    fn new(expression: &str, reason: impl Into<String>) -> Self {
+        CronParseError {
+            expression: expression.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Parse a field of a cron expression into a bitmask of the values it
+/// matches.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut mask = 0;
+
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step '{step}'"))?;
+                if step == 0 {
+                    return Err("the step can't be 0".to_string());
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, names)?,
+                parse_value(end, min, max, names)?,
+            )
+        } else {
+            let start = parse_value(range, min, max, names)?;
+            // "5/10" means every 10 starting from 5
+            let end = if item.contains('/') { max } else { start };
+            (start, end)
+        };
+
+        if start > end {
+            return Err(format!("invalid range '{range}'"));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let parsed = match names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+    {
+        Some(index) => index as u32 + min,
+        None => value
+            .parse()
+            .map_err(|_| format!("invalid value '{value}'"))?,
+    };
+
+    if parsed < min || parsed > max {
+        return Err(format!("{parsed} is not between {min} and {max}"));
+    }
+
+    Ok(parsed)
+}
+
+fn is_set(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) date.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Convert a (year, month, day) date to days since the Unix epoch.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The day of the week (0 is Sunday) of a number of days since the Unix
+/// epoch, which was a Thursday.
+fn weekday_from_days(days: i64) -> u32 {
+    (days + 4).rem_euclid(7) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-02-28T23:59:30Z, a Wednesday.
+    const START: i64 = 1_709_164_770;
+
+    fn utc(year: i64, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+        days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60
+    }
+
+    fn next(expression: &str, after: i64) -> i64 {
+        expression
+            .parse::<CronExpression>()
+            .unwrap()
+            .next_after(after, JobTimezone::UTC)
+            .unwrap()
+    }
+
+    #[test]
+    fn civil_dates_roundtrip() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(days_from_civil(2024, 2, 29), 19782);
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+        assert_eq!(weekday_from_days(19782), 4);
+    }
+
+    #[test]
+    fn next_matching_times() {
+        assert_eq!(next("* * * * *", START), utc(2024, 2, 29, 0, 0));
+        assert_eq!(next("*/15 * * * *", START), utc(2024, 2, 29, 0, 0));
+        assert_eq!(next("5/15 * * * *", START), utc(2024, 2, 29, 0, 5));
+        assert_eq!(next("30 9 * * *", START), utc(2024, 2, 29, 9, 30));
+        assert_eq!(next("0 0 29 2 *", START), utc(2024, 2, 29, 0, 0));
+        assert_eq!(next("0 0 29 2 *", START + 60), utc(2028, 2, 29, 0, 0));
+        assert_eq!(next("0 12 * * sat,sun", START), utc(2024, 3, 2, 12, 0));
+        assert_eq!(next("0 12 * * 7", START), utc(2024, 3, 3, 12, 0));
+        assert_eq!(next("@monthly", START), utc(2024, 3, 1, 0, 0));
+        assert_eq!(next("0 0 1 jan *", START), utc(2025, 1, 1, 0, 0));
+        // Either the day of the month or the day of the week
+        assert_eq!(next("0 0 15 * fri", START), utc(2024, 3, 1, 0, 0));
+    }
+
+    #[test]
+    fn timezones_shift_the_schedule() {
+        let daily: CronExpression = "0 9 * * *".parse().unwrap();
+
+        let next = daily
+            .next_after(START, JobTimezone::from_utc_offset(2 * 3600))
+            .unwrap();
+
+        assert_eq!(next, utc(2024, 2, 29, 7, 0));
+    }
+
+    #[test]
+    fn impossible_dates_never_match() {
+        let expression: CronExpression = "0 0 31 2 *".parse().unwrap();
+
+        assert_eq!(expression.next_after(START, JobTimezone::UTC), None);
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        let inputs = [
+            ("* * * *", "expected 5 fields, found 4"),
+            ("60 * * * *", "60 is not between 0 and 59"),
+            ("* * 0 * *", "0 is not between 1 and 31"),
+            ("* * * foo *", "invalid value 'foo'"),
+            ("*/0 * * * *", "the step can't be 0"),
+            ("* 5-1 * * *", "invalid range '5-1'"),
+        ];
+
+        for (input, reason) in inputs {
+            let err = input.parse::<CronExpression>().unwrap_err();
+            assert_eq!(err, CronParseError::new(input, reason), "{input}");
+        }
+    }
+}
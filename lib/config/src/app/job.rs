@@ -0,0 +1,589 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr, time::Duration};
+
+use chrono::{LocalResult, Offset, TimeZone};
+
+use super::cron::{CronExpression, CronParseError};
+
+/// A task that runs periodically alongside an app.
+#[derive(
+    serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Debug, PartialEq, Eq,
+)]
+pub struct AppScheduledTask {
+    pub name: String,
+
+    #[serde(flatten)]
+    pub spec: CronJobSpecV1,
+}
+
+/// When and how a scheduled task runs.
+#[derive(
+    serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Debug, PartialEq, Eq,
+)]
+pub struct CronJobSpecV1 {
+    /// When the task runs.
+    ///
+    /// Either a cron expression (`*/15 * * * *`, `@daily`, ...) or an
+    /// interval (`30s`, `10m`, `1day`, ...).
+    #[schemars(with = "String")]
+    pub schedule: JobSchedule,
+
+    /// The timezone cron expressions are evaluated in.
+    ///
+    /// Format: Europe/Paris, America/New_York, UTC, UTC+2, UTC-05:30, ...
+    ///
+    /// Named zones follow daylight saving time, while offsets from UTC are
+    /// fixed.
+    ///
+    /// Defaults to UTC.
+    #[schemars(with = "Option<String>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<JobTimezone>,
+
+    /// What the task does.
+    pub invoke: JobAction,
+
+    /// How long a run may take before it's cancelled.
+    ///
+    /// Format: 1s, 5m, 11h, ...
+    #[schemars(with = "Option<String>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<PrettyDuration>,
+
+    /// How many times a failed run is retried.
+    ///
+    /// Defaults to 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+
+    /// How long to wait before the first retry of a failed run.
+    ///
+    /// The delay doubles after each retry.
+    ///
+    /// Format: 1s, 5m, 11h, ...
+    ///
+    /// Defaults to 1s.
+    #[schemars(with = "Option<String>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_delay: Option<PrettyDuration>,
+
+    /// What to do when the task is due while a previous run is still going.
+    ///
+    /// Defaults to `allow`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency_policy: Option<ConcurrencyPolicy>,
+}
+
+/// The action performed by a scheduled task.
+#[derive(
+    serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Debug, PartialEq, Eq,
+)]
+pub enum JobAction {
+    /// Send an HTTP request to the app.
+    #[serde(rename = "fetch")]
+    Fetch(FetchJob),
+    /// Run one of the commands of the app's package.
+    #[serde(rename = "execute")]
+    Execute(ExecuteJob),
+}
+
+/// An HTTP request sent to the app.
+#[derive(
+    serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Debug, PartialEq, Eq,
+)]
+pub struct FetchJob {
+    /// The URL to request, usually a path relative to the app, like
+    /// `/api/do-backup`.
+    pub url: String,
+
+    /// HTTP method.
+    ///
+    /// Defaults to GET.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+
+    /// HTTP headers added to the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+
+    /// Request body as a string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+
+    /// The status codes of a successful run.
+    ///
+    /// Defaults to any 2xx status code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success_status_codes: Option<Vec<u16>>,
+}
+
+/// A command of the app's package, run in the same environment as the app.
+#[derive(
+    serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Debug, PartialEq, Eq,
+)]
+pub struct ExecuteJob {
+    /// The name of the command.
+    pub command: String,
+
+    /// CLI arguments passed to the command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli_args: Option<Vec<String>>,
+
+    /// Environment variables, in addition to the app's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+}
+
+/// What to do when a scheduled task is due while a previous run is still
+/// going.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+)]
+pub enum ConcurrencyPolicy {
+    /// Start a new run alongside the previous ones.
+    #[default]
+    #[serde(rename = "allow")]
+    Allow,
+    /// Skip the new run.
+    #[serde(rename = "forbid")]
+    Forbid,
+    /// Cancel the previous runs, then start the new one.
+    #[serde(rename = "replace")]
+    Replace,
+}
+
+/// When a scheduled task runs.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum JobSchedule {
+    Cron(CronExpression),
+    Interval(PrettyDuration),
+}
+
+impl JobSchedule {
+    /// Get the next time the task is due, strictly after `after`.
+    ///
+    /// Times are in seconds since the Unix epoch. Intervals are counted from
+    /// the epoch, so an hourly task runs on the hour.
+    pub fn next_after(&self, after: i64, timezone: JobTimezone) -> Option<i64> {
+        match self {
+            JobSchedule::Cron(expression) => expression.next_after(after, timezone),
+            JobSchedule::Interval(interval) => {
+                let secs = i64::try_from(interval.0.as_secs()).ok()?.max(1);
+                Some((after.div_euclid(secs) + 1) * secs)
+            }
+        }
+    }
+}
+
+impl FromStr for JobSchedule {
+    type Err = JobSpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Ok(interval) = s.parse() {
+            return Ok(JobSchedule::Interval(interval));
+        }
+
+        match s.parse() {
+            Ok(expression) => Ok(JobSchedule::Cron(expression)),
+            // Cron expressions are made of several fields
+            Err(e) if s.starts_with('@') || s.contains(char::is_whitespace) => Err(e.into()),
+            Err(_) => Err(JobSpecError::Schedule(s.to_string())),
+        }
+    }
+}
+
+impl Display for JobSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobSchedule::Cron(expression) => expression.fmt(f),
+            JobSchedule::Interval(interval) => interval.fmt(f),
+        }
+    }
+}
+
+impl TryFrom<String> for JobSchedule {
+    type Error = JobSpecError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<JobSchedule> for String {
+    fn from(value: JobSchedule) -> Self {
+        value.to_string()
+    }
+}
+
+/// A timezone, either a zone of the IANA time zone database such as
+/// `Europe/Paris`, whose daylight saving time rules are followed, or a fixed
+/// offset from UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum JobTimezone {
+    /// A fixed offset from UTC, in seconds.
+    Fixed(i32),
+    /// A zone of the IANA time zone database.
+    Named(chrono_tz::Tz),
+}
+
+impl JobTimezone {
+    pub const UTC: JobTimezone = JobTimezone::Fixed(0);
+
+    /// Create a timezone from its offset from UTC, in seconds.
+    pub fn from_utc_offset(utc_offset: i32) -> Self {
+        JobTimezone::Fixed(utc_offset)
+    }
+
+    /// The offset from UTC in effect at `time`, in seconds.
+    ///
+    /// Times are in seconds since the Unix epoch.
+    pub fn utc_offset_at(self, time: i64) -> i32 {
+        match self {
+            JobTimezone::Fixed(utc_offset) => utc_offset,
+            JobTimezone::Named(tz) => chrono::DateTime::from_timestamp(time, 0)
+                .map(|time| {
+                    tz.offset_from_utc_datetime(&time.naive_utc())
+                        .fix()
+                        .local_minus_utc()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Get the first time strictly after `after` when clocks in this
+    /// timezone show the local time `local`, if they ever do.
+    ///
+    /// Times are in seconds since the Unix epoch, with `local` counting the
+    /// seconds of the local time as if it were UTC.
+    pub fn first_instant_after(self, local: i64, after: i64) -> Option<i64> {
+        match self {
+            JobTimezone::Fixed(utc_offset) => {
+                Some(local - i64::from(utc_offset)).filter(|&time| time > after)
+            }
+            JobTimezone::Named(tz) => {
+                let local = chrono::DateTime::from_timestamp(local, 0)?.naive_utc();
+                match tz.from_local_datetime(&local) {
+                    LocalResult::Single(time) => {
+                        Some(time.timestamp()).filter(|&time| time > after)
+                    }
+                    // Clocks were turned back, so the local time happens twice
+                    LocalResult::Ambiguous(earliest, latest) => [earliest, latest]
+                        .into_iter()
+                        .map(|time| time.timestamp())
+                        .find(|&time| time > after),
+                    // Clocks were turned forward over the local time
+                    LocalResult::None => None,
+                }
+            }
+        }
+    }
+}
+
+impl Default for JobTimezone {
+    fn default() -> Self {
+        JobTimezone::UTC
+    }
+}
+
+impl FromStr for JobTimezone {
+    type Err = JobSpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let timezone = s.trim();
+        if let Some(utc_offset) = parse_utc_offset(timezone) {
+            return Ok(JobTimezone::from_utc_offset(utc_offset));
+        }
+
+        timezone
+            .parse()
+            .map(JobTimezone::Named)
+            .map_err(|_| JobSpecError::Timezone(s.to_string()))
+    }
+}
+
+/// Parse an offset from UTC such as `UTC+2` or `-05:30`, in seconds.
+fn parse_utc_offset(offset: &str) -> Option<i32> {
+    let offset = offset
+        .strip_prefix("UTC")
+        .or_else(|| offset.strip_prefix("GMT"))
+        .unwrap_or(offset);
+    if offset.is_empty() || offset == "Z" {
+        return Some(0);
+    }
+
+    let (sign, offset) = if let Some(rest) = offset.strip_prefix('+') {
+        (1, rest)
+    } else {
+        (-1, offset.strip_prefix('-')?)
+    };
+    let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+impl Display for JobTimezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let utc_offset = match *self {
+            JobTimezone::Fixed(utc_offset) => utc_offset,
+            JobTimezone::Named(tz) => return f.write_str(tz.name()),
+        };
+        if utc_offset == 0 {
+            return f.write_str("UTC");
+        }
+
+        let sign = if utc_offset < 0 { '-' } else { '+' };
+        let offset = utc_offset.unsigned_abs();
+        write!(f, "UTC{sign}{:02}:{:02}", offset / 3600, offset % 3600 / 60)
+    }
+}
+
+impl TryFrom<String> for JobTimezone {
+    type Error = JobSpecError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<JobTimezone> for String {
+    fn from(value: JobTimezone) -> Self {
+        value.to_string()
+    }
+}
+
+/// A duration written like `30s`, `10m`, `1day` or `1h30m`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PrettyDuration(pub Duration);
+
+impl FromStr for PrettyDuration {
+    type Err = JobSpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || JobSpecError::Duration(s.to_string());
+
+        let mut rest = s.trim();
+        if rest.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut total = Duration::ZERO;
+        while !rest.is_empty() {
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let value: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+            rest = rest[digits..].trim_start();
+
+            let unit_len = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            let unit = match &rest[..unit_len] {
+                "ms" => Duration::from_millis(1),
+                "s" | "sec" | "secs" | "second" | "seconds" => Duration::from_secs(1),
+                "m" | "min" | "mins" | "minute" | "minutes" => Duration::from_secs(60),
+                "h" | "hr" | "hrs" | "hour" | "hours" => Duration::from_secs(60 * 60),
+                "d" | "day" | "days" => Duration::from_secs(24 * 60 * 60),
+                "w" | "week" | "weeks" => Duration::from_secs(7 * 24 * 60 * 60),
+                _ => return Err(invalid()),
+            };
+            rest = rest[unit_len..].trim_start();
+
+            let value = u32::try_from(value).map_err(|_| invalid())?;
+            total += unit * value;
+        }
+
+        Ok(PrettyDuration(total))
+    }
+}
+
+impl Display for PrettyDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let millis = self.0.as_millis();
+        let units = [
+            (7 * 24 * 60 * 60 * 1000, "w"),
+            (24 * 60 * 60 * 1000, "d"),
+            (60 * 60 * 1000, "h"),
+            (60 * 1000, "m"),
+            (1000, "s"),
+            (1, "ms"),
+        ];
+
+        match units.iter().find(|(size, _)| millis % size == 0) {
+            Some((size, unit)) if millis > 0 => write!(f, "{}{unit}", millis / size),
+            _ => f.write_str("0s"),
+        }
+    }
+}
+
+impl TryFrom<String> for PrettyDuration {
+    type Error = JobSpecError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PrettyDuration> for String {
+    fn from(value: PrettyDuration) -> Self {
+        value.to_string()
+    }
+}
+
+/// An invalid value in the spec of a scheduled task.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum JobSpecError {
+    #[error("invalid schedule '{0}', expected a cron expression or an interval such as '10m'")]
+    Schedule(String),
+    #[error(transparent)]
+    Cron(#[from] CronParseError),
+    #[error(
+        "invalid timezone '{0}', expected a zone such as 'Europe/Paris' or an offset from UTC such as 'UTC+2'"
+    )]
+    Timezone(String),
+    #[error("invalid duration '{0}', expected something like '30s', '10m' or '1day'")]
+    Duration(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_schedules() {
+        assert_eq!(
+            "1day".parse::<JobSchedule>().unwrap(),
+            JobSchedule::Interval(PrettyDuration(Duration::from_secs(86400)))
+        );
+        assert_eq!(
+            "1h 30m".parse::<JobSchedule>().unwrap(),
+            JobSchedule::Interval(PrettyDuration(Duration::from_secs(5400)))
+        );
+        assert!(matches!(
+            "*/5 * * * *".parse::<JobSchedule>().unwrap(),
+            JobSchedule::Cron(_)
+        ));
+        assert!(matches!(
+            "@hourly".parse::<JobSchedule>().unwrap(),
+            JobSchedule::Cron(_)
+        ));
+        assert_eq!(
+            "often".parse::<JobSchedule>().unwrap_err(),
+            JobSpecError::Schedule("often".to_string())
+        );
+        assert_eq!(
+            "* * *".parse::<JobSchedule>().unwrap_err(),
+            JobSpecError::Cron("* * *".parse::<CronExpression>().unwrap_err())
+        );
+    }
+
+    #[test]
+    fn intervals_are_aligned_to_the_epoch() {
+        let schedule: JobSchedule = "10m".parse().unwrap();
+
+        assert_eq!(schedule.next_after(0, JobTimezone::UTC), Some(600));
+        assert_eq!(schedule.next_after(601, JobTimezone::UTC), Some(1200));
+    }
+
+    #[test]
+    fn parse_durations() {
+        let inputs = [
+            ("30s", 30),
+            ("10m", 600),
+            ("1day", 86400),
+            ("1h30m", 5400),
+            ("2 weeks", 14 * 86400),
+        ];
+
+        for (input, secs) in inputs {
+            let duration: PrettyDuration = input.parse().unwrap();
+            assert_eq!(duration.0, Duration::from_secs(secs), "{input}");
+        }
+
+        assert!("10".parse::<PrettyDuration>().is_err());
+        assert!("m".parse::<PrettyDuration>().is_err());
+        assert!("5 fortnights".parse::<PrettyDuration>().is_err());
+        assert_eq!(PrettyDuration(Duration::from_secs(5400)).to_string(), "90m");
+        assert_eq!(
+            PrettyDuration(Duration::from_millis(1500)).to_string(),
+            "1500ms"
+        );
+    }
+
+    #[test]
+    fn parse_timezones() {
+        let inputs = [
+            ("UTC", 0),
+            ("Z", 0),
+            ("UTC+2", 7200),
+            ("UTC-05:30", -19800),
+            ("+01:00", 3600),
+        ];
+
+        for (input, offset) in inputs {
+            let timezone: JobTimezone = input.parse().unwrap();
+            assert_eq!(timezone, JobTimezone::from_utc_offset(offset), "{input}");
+        }
+
+        assert_eq!(
+            JobTimezone::from_utc_offset(-19800).to_string(),
+            "UTC-05:30"
+        );
+        assert_eq!(
+            "Europe/Paris".parse::<JobTimezone>().unwrap(),
+            JobTimezone::Named(chrono_tz::Europe::Paris)
+        );
+        assert_eq!(
+            JobTimezone::Named(chrono_tz::Europe::Paris).to_string(),
+            "Europe/Paris"
+        );
+        assert!("Mars/Olympus_Mons".parse::<JobTimezone>().is_err());
+        assert!("UTC+15".parse::<JobTimezone>().is_err());
+    }
+
+    #[test]
+    fn named_timezones_follow_daylight_saving_time() {
+        let paris = JobTimezone::Named(chrono_tz::Europe::Paris);
+        let daily: JobSchedule = "0 9 * * *".parse().unwrap();
+        let at_half_past_two: JobSchedule = "30 2 * * *".parse().unwrap();
+
+        // 2024-03-30T00:00:00Z, the day before clocks go forward
+        let before_dst = 1_711_756_800;
+        assert_eq!(paris.utc_offset_at(before_dst), 3600);
+        // 09:00 is 08:00 UTC, then 07:00 UTC once clocks went forward
+        let first = daily.next_after(before_dst, paris).unwrap();
+        assert_eq!(first, 1_711_785_600);
+        assert_eq!(daily.next_after(first, paris), Some(1_711_868_400));
+        // 02:30 is skipped when clocks go forward, on 2024-03-31
+        assert_eq!(
+            at_half_past_two.next_after(before_dst + 86400, paris),
+            Some(1_711_931_400)
+        );
+
+        // 02:30 happens twice when clocks go back on 2024-10-27, and the
+        // task runs on the first one only
+        let end_of_dst = 1_729_987_200;
+        let first = at_half_past_two.next_after(end_of_dst, paris).unwrap();
+        assert_eq!(first, 1_729_989_000);
+        assert_eq!(
+            at_half_past_two.next_after(first, paris),
+            Some(1_730_079_000)
+        );
+    }
+}
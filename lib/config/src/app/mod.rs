@@ -1,11 +1,18 @@
 //! User-facing app.yaml file config: [`AppConfigV1`].
 
+mod cron;
 mod healthcheck;
 mod http;
+mod job;
 
 pub use self::{
+    cron::{CronExpression, CronParseError},
     healthcheck::{HealthCheckHttpV1, HealthCheckV1},
-    http::HttpRequest,
+    http::{HttpHeader, HttpRequest, HttpRequestExpect},
+    job::{
+        AppScheduledTask, ConcurrencyPolicy, CronJobSpecV1, ExecuteJob, FetchJob, JobAction,
+        JobSchedule, JobSpecError, JobTimezone, PrettyDuration,
+    },
 };
 
 use std::collections::HashMap;
@@ -131,15 +138,6 @@ pub struct AppVolume {
     pub mount: String,
 }
 
-#[derive(
-    serde::Serialize, serde::Deserialize, schemars::JsonSchema, Clone, Debug, PartialEq, Eq,
-)]
-pub struct AppScheduledTask {
-    pub name: String,
-    // #[serde(flatten)]
-    // pub spec: CronJobSpecV1,
-}
-
 impl AppConfigV1 {
     pub const KIND: &'static str = "wasmer.io/App.v0";
     pub const CANONICAL_FILE_NAME: &'static str = "app.yaml";
@@ -327,6 +325,25 @@ scheduled_tasks:
                 scaling: None,
                 scheduled_tasks: Some(vec![AppScheduledTask {
                     name: "backup".to_string(),
+                    spec: CronJobSpecV1 {
+                        schedule: JobSchedule::Interval(PrettyDuration(
+                            std::time::Duration::from_secs(24 * 60 * 60)
+                        )),
+                        timezone: None,
+                        invoke: JobAction::Fetch(FetchJob {
+                            url: "/api/do-backup".to_string(),
+                            method: None,
+                            headers: Some(
+                                [("h1".to_string(), "v1".to_string())].into_iter().collect()
+                            ),
+                            body: None,
+                            success_status_codes: Some(vec![200, 201]),
+                        }),
+                        timeout: Some(PrettyDuration(std::time::Duration::from_secs(600))),
+                        max_retries: Some(3),
+                        retry_delay: None,
+                        concurrency_policy: None,
+                    },
                 }]),
                 health_checks: None,
                 extra: [(
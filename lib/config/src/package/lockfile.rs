@@ -0,0 +1,198 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{PackageHash, PackageId};
+
+/// The name of the file a package's resolved dependencies are recorded in.
+pub static LOCKFILE_NAME: &str = "wasmer.lock";
+
+/// The contents of a `wasmer.lock` file.
+///
+/// A lockfile sits next to a `wasmer.toml` and records exactly which
+/// packages its dependencies were resolved to, so resolving them again
+/// gives the same result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// The version of the lockfile format.
+    pub version: u32,
+    /// The packages the root package's dependencies resolved to, keyed by
+    /// the name they were given in the `wasmer.toml`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, PackageId>,
+    /// Every package in the dependency tree, sorted by [`PackageId`].
+    #[serde(default, rename = "package", skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    /// The lockfile format written by this version of Wasmer.
+    pub const VERSION: u32 = 1;
+
+    /// Create an empty lockfile.
+    pub fn new() -> Self {
+        Lockfile {
+            version: Self::VERSION,
+            dependencies: BTreeMap::new(),
+            packages: Vec::new(),
+        }
+    }
+
+    /// Parse a [`Lockfile`] from its TOML representation.
+    pub fn parse(s: &str) -> Result<Self, LockfileError> {
+        let lockfile: Self = toml::from_str(s)?;
+
+        if lockfile.version != Self::VERSION {
+            return Err(LockfileError::UnsupportedVersion(lockfile.version));
+        }
+
+        Ok(lockfile)
+    }
+
+    /// Serialize this lockfile to TOML.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("a lockfile can always be serialized")
+    }
+
+    /// Load the lockfile in a package's directory, returning `None` if the
+    /// package doesn't have one.
+    pub fn find_in_directory(dir: impl AsRef<Path>) -> Result<Option<Self>, LockfileError> {
+        let path = dir.as_ref().join(LOCKFILE_NAME);
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(LockfileError::Read { path, error }),
+        };
+
+        Lockfile::parse(&contents).map(Some)
+    }
+
+    /// Save this lockfile to a package's directory.
+    pub fn save_to_directory(&self, dir: impl AsRef<Path>) -> Result<(), LockfileError> {
+        let path = dir.as_ref().join(LOCKFILE_NAME);
+
+        std::fs::write(&path, self.to_toml()).map_err(|error| LockfileError::Write { path, error })
+    }
+
+    /// Look up a package by its [`PackageId`].
+    pub fn get(&self, id: &PackageId) -> Option<&LockedPackage> {
+        self.packages.iter().find(|pkg| pkg.id == *id)
+    }
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Lockfile::new()
+    }
+}
+
+/// A package in a [`Lockfile`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub id: PackageId,
+    /// The hash of the package's `*.webc` file.
+    pub hash: PackageHash,
+    /// Where the package's `*.webc` file was downloaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<Url>,
+    /// The packages this package's dependencies resolved to, keyed by the
+    /// name the package uses for them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, PackageId>,
+}
+
+/// Errors that may occur while reading or writing a [`Lockfile`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum LockfileError {
+    #[error("Unable to read \"{}\"", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("Unable to write \"{}\"", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("Could not parse the lockfile because {0}.")]
+    TomlParseError(#[from] toml::de::Error),
+    #[error(
+        "Unsupported lockfile version, {0} (expected version {})",
+        Lockfile::VERSION
+    )]
+    UnsupportedVersion(u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "sha256:c355cd53795b9b481f7eb2b5f4f6c8cf73631bdc343723a579d671e32db70b3c";
+
+    #[test]
+    fn lockfile_toml_roundtrip() {
+        let python: PackageId = "wasmer/python@3.12.0".parse().unwrap();
+        let libc: PackageId = "wasmer/libc@1.0.1".parse().unwrap();
+        let lockfile = Lockfile {
+            version: Lockfile::VERSION,
+            dependencies: [("python".to_string(), python.clone())].into(),
+            packages: vec![
+                LockedPackage {
+                    id: libc.clone(),
+                    hash: HASH.parse().unwrap(),
+                    url: Some("https://cdn.wasmer.io/libc.webc".parse().unwrap()),
+                    dependencies: BTreeMap::new(),
+                },
+                LockedPackage {
+                    id: python.clone(),
+                    hash: HASH.parse().unwrap(),
+                    url: None,
+                    dependencies: [("libc".to_string(), libc.clone())].into(),
+                },
+            ],
+        };
+
+        let toml = lockfile.to_toml();
+
+        assert_eq!(
+            toml,
+            format!(
+                r#"version = 1
+
+[dependencies]
+python = "wasmer/python@3.12.0"
+
+[[package]]
+id = "wasmer/libc@1.0.1"
+hash = "{HASH}"
+url = "https://cdn.wasmer.io/libc.webc"
+
+[[package]]
+id = "wasmer/python@3.12.0"
+hash = "{HASH}"
+
+[package.dependencies]
+libc = "wasmer/libc@1.0.1"
+"#
+            )
+        );
+        let round_tripped = Lockfile::parse(&toml).unwrap();
+        assert_eq!(round_tripped, lockfile);
+        assert_eq!(round_tripped.get(&libc).unwrap().id, libc);
+    }
+
+    #[test]
+    fn reject_unknown_lockfile_versions() {
+        let err = Lockfile::parse("version = 42").unwrap_err();
+
+        assert!(matches!(err, LockfileError::UnsupportedVersion(42)));
+    }
+}
@@ -5,6 +5,7 @@
 #![allow(deprecated)]
 
 mod error;
+mod lockfile;
 mod named_package_ident;
 mod package_hash;
 mod package_id;
@@ -13,6 +14,7 @@ mod package_source;
 
 pub use self::{
     error::PackageParseError,
+    lockfile::{LockedPackage, Lockfile, LockfileError, LOCKFILE_NAME},
     named_package_ident::{NamedPackageIdent, Tag},
     package_hash::PackageHash,
     package_id::{NamedPackageId, PackageId},
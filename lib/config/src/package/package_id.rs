@@ -1,4 +1,4 @@
-use super::PackageHash;
+use super::{PackageHash, PackageParseError};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NamedPackageId {
@@ -76,6 +76,47 @@ impl std::fmt::Display for PackageId {
     }
 }
 
+impl std::str::FromStr for PackageId {
+    type Err = PackageParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("sha256:") {
+            return s.parse().map(Self::Hash);
+        }
+
+        let (name, version) = s.rsplit_once('@').ok_or_else(|| {
+            PackageParseError::new(s, "package ids must be of the form 'name@version'")
+        })?;
+        if name.is_empty() {
+            return Err(PackageParseError::new(s, "package name can not be empty"));
+        }
+
+        NamedPackageId::try_new(name, version)
+            .map(Self::Named)
+            .map_err(|e| PackageParseError::new(s, e.to_string()))
+    }
+}
+
+impl serde::Serialize for PackageId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for PackageId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse::<Self>()
+            .map_err(|e| serde::de::Error::custom(e.to_string()))
+    }
+}
+
 impl schemars::JsonSchema for PackageId {
     fn schema_name() -> String {
         "PackageIdent".to_string()
@@ -85,3 +126,36 @@ impl schemars::JsonSchema for PackageId {
         String::json_schema(gen)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_package_id_roundtrip() {
+        let inputs = [
+            "wasmer/python@3.12.0",
+            "wasmer/python@3.12.0-rc.1",
+            "sha256:c355cd53795b9b481f7eb2b5f4f6c8cf73631bdc343723a579d671e32db70b3c",
+        ];
+
+        for input in inputs {
+            let id: PackageId = input.parse().unwrap();
+            assert_eq!(id.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn invalid_package_ids() {
+        let inputs = [
+            "wasmer/python",
+            "@1.0.0",
+            "wasmer/python@latest",
+            "sha256:1234",
+        ];
+
+        for input in inputs {
+            assert!(input.parse::<PackageId>().is_err(), "{input}");
+        }
+    }
+}
@@ -0,0 +1,829 @@
+//! `coverage` is a middleware for measuring which parts of a module
+//! are executed. It gives each basic block of the module a counter,
+//! which is incremented every time the block is entered.
+//!
+//! After a run, the counters are mapped back to the offsets of the
+//! blocks in the module and, when the module has DWARF line tables, to
+//! source lines. The result can be written as an LCOV tracefile, the
+//! format most coverage tools consume.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::ops::Range;
+use std::sync::Mutex;
+use wasmer::wasmparser::{BinaryReaderError, Operator, Parser, Payload};
+use wasmer::{
+    AsStoreMut, ExportIndex, FunctionMiddleware, GlobalInit, GlobalType, Instance, LineTable,
+    LocalFunctionIndex, MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
+};
+use wasmer_types::{GlobalIndex, ModuleInfo};
+
+use crate::metering::is_accounting;
+
+/// The prefix of the names under which the block counters are exported.
+const COUNTER_EXPORT_PREFIX: &str = "wasmer_coverage_counter_";
+
+/// The module-level coverage middleware.
+///
+/// Unlike the other middlewares, `Coverage` is created from the module
+/// it instruments: the number of counters has to be known before any
+/// function is compiled, so the module is scanned up front.
+///
+/// # Panic
+///
+/// An instance of `Coverage` should _not_ be shared among different
+/// modules. Attempts to use a `Coverage` instance from multiple
+/// modules will result in a panic.
+///
+/// `Coverage` must come before any other middleware which changes the
+/// control flow of functions, like [`Metering`][crate::Metering], in
+/// the middleware chain, as it expects the operators of the module as
+/// they were scanned.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use wasmer::{imports, sys::EngineBuilder, wat2wasm, CompilerConfig, Cranelift, Instance, Module, Store};
+/// use wasmer_middlewares::Coverage;
+///
+/// fn lcov_of_run(wat: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+///     let wasm = wat2wasm(wat)?;
+///     let coverage = Arc::new(Coverage::new(&wasm)?);
+///
+///     let mut compiler_config = Cranelift::default();
+///     compiler_config.push_middleware(coverage.clone());
+///     let mut store = Store::new(EngineBuilder::new(compiler_config));
+///     let module = Module::new(&store, &wasm)?;
+///     let instance = Instance::new(&mut store, &module, &imports! {})?;
+///
+///     instance.exports.get_function("main")?.call(&mut store, &[])?;
+///
+///     Ok(coverage.report(&mut store, &instance).to_lcov())
+/// }
+/// ```
+pub struct Coverage {
+    /// The offset in the module of the first operator of each block.
+    blocks: Vec<u64>,
+
+    /// The blocks of each local function.
+    functions: Vec<Range<usize>>,
+
+    /// The conditional branches of the module.
+    branches: Vec<Branch>,
+
+    /// The module-specific state, set when the module info is transformed.
+    module: Mutex<Option<CoveredModule>>,
+
+    /// The offset of the code section in the module.
+    code_section_offset: u64,
+}
+
+/// A conditional branch, which is taken when the code following it
+/// isn't executed.
+#[derive(Debug, Clone, Copy)]
+struct Branch {
+    /// The offset of the branch in the module.
+    offset: u64,
+
+    /// The block ending with the branch.
+    block: usize,
+}
+
+impl Branch {
+    /// The block which is executed when the branch isn't taken.
+    fn fallthrough(&self) -> usize {
+        self.block + 1
+    }
+}
+
+/// What `Coverage` knows about the module it instruments.
+struct CoveredModule {
+    name: String,
+
+    /// The global holding the counter of the first block.
+    first_counter: GlobalIndex,
+
+    /// The names of the local functions.
+    function_names: Vec<String>,
+
+    line_table: Option<LineTable>,
+}
+
+/// The function-level coverage middleware.
+pub struct FunctionCoverage {
+    /// Finds the operators starting a block.
+    splitter: BlockSplitter,
+
+    /// The global holding the counter of the next block.
+    next_counter: u32,
+
+    /// The global following the counter of the last block.
+    end_counter: u32,
+}
+
+/// Splits functions into basic blocks, both when the module is scanned
+/// and when it is instrumented, so both agree on the blocks.
+#[derive(Debug)]
+struct BlockSplitter {
+    /// Whether the previous operator ended a block.
+    block_ended: bool,
+
+    /// Whether the previous operator never falls through.
+    diverged: bool,
+}
+
+impl BlockSplitter {
+    fn new() -> Self {
+        Self {
+            block_ended: true,
+            diverged: false,
+        }
+    }
+
+    /// Returns `true` if `operator` starts a block which needs a
+    /// counter.
+    ///
+    /// The code following a branch only starts a block when it can be
+    /// reached, so the `end` of a block finishing with a `br` isn't
+    /// reported as uncovered.
+    fn starts_block(&mut self, operator: &Operator) -> bool {
+        let is_target = matches!(
+            operator,
+            Operator::End
+                | Operator::Else
+                | Operator::Catch { .. }
+                | Operator::CatchAll
+                | Operator::Delegate { .. }
+        );
+        let starts_block = self.block_ended && !(self.diverged && is_target);
+
+        self.block_ended = is_accounting(operator);
+        self.diverged = matches!(
+            operator,
+            Operator::Br { .. }
+                | Operator::BrTable { .. }
+                | Operator::Return
+                | Operator::Unreachable
+                | Operator::Throw { .. }
+                | Operator::ThrowRef
+                | Operator::Rethrow { .. }
+                | Operator::ReturnCall { .. }
+                | Operator::ReturnCallIndirect { .. }
+                | Operator::ReturnCallRef { .. }
+        );
+        starts_block
+    }
+}
+
+impl Coverage {
+    /// Creates a `Coverage` middleware for the given module.
+    pub fn new(wasm: &[u8]) -> Result<Self, MiddlewareError> {
+        Self::scan(wasm).map_err(|err| MiddlewareError::new("coverage", err.to_string()))
+    }
+
+    fn scan(wasm: &[u8]) -> Result<Self, BinaryReaderError> {
+        let mut blocks = Vec::new();
+        let mut functions = Vec::new();
+        let mut branches = Vec::new();
+        let mut code_section_offset = 0;
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::CodeSectionStart { range, .. } => {
+                    code_section_offset = range.start as u64;
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let first_block = blocks.len();
+                    let mut splitter = BlockSplitter::new();
+                    for operator in body.get_operators_reader()?.into_iter_with_offsets() {
+                        let (operator, offset) = operator?;
+                        if splitter.starts_block(&operator) {
+                            blocks.push(offset as u64);
+                        }
+                        if let Operator::If { .. } | Operator::BrIf { .. } = operator {
+                            branches.push(Branch {
+                                offset: offset as u64,
+                                block: blocks.len() - 1,
+                            });
+                        }
+                    }
+                    functions.push(first_block..blocks.len());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            blocks,
+            functions,
+            branches,
+            module: Mutex::new(None),
+            code_section_offset,
+        })
+    }
+
+    /// The number of blocks with a counter.
+    pub fn blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Collects the counters of an [`Instance`][wasmer::Instance] of
+    /// the module.
+    ///
+    /// # Panic
+    ///
+    /// The [`Instance`][wasmer::Instance] must be an instance of the
+    /// module this `Coverage` was created for, otherwise this will
+    /// panic.
+    pub fn report(&self, store: &mut impl AsStoreMut, instance: &Instance) -> CoverageReport {
+        let module = self.module.lock().unwrap();
+        let module = module
+            .as_ref()
+            .expect("Coverage::report: the module hasn't been compiled with this middleware");
+
+        let hits: Vec<u64> = (0..self.blocks.len())
+            .map(|block| {
+                let name = format!("{COUNTER_EXPORT_PREFIX}{block}");
+                let hits: i64 = instance
+                    .exports
+                    .get_global(&name)
+                    .unwrap_or_else(|_| panic!("Can't get `{name}` from Instance"))
+                    .get(store)
+                    .try_into()
+                    .unwrap_or_else(|_| panic!("`{name}` from Instance has wrong type"));
+                hits as u64
+            })
+            .collect();
+        let location = |offset: u64| {
+            module
+                .line_table
+                .as_ref()
+                .and_then(|line_table| line_table.location(offset))
+                .map(|(file, line)| SourceLocation {
+                    file: file.to_string(),
+                    line,
+                })
+        };
+
+        let functions = self
+            .functions
+            .iter()
+            .zip(&module.function_names)
+            .map(|(blocks, name)| CoveredFunction {
+                name: name.clone(),
+                offset: self.blocks[blocks.start],
+                location: location(self.blocks[blocks.start]),
+                calls: hits[blocks.start],
+            })
+            .collect();
+        let blocks = self
+            .blocks
+            .iter()
+            .zip(&hits)
+            .map(|(&offset, &hits)| CoveredBlock {
+                offset,
+                location: location(offset),
+                hits,
+            })
+            .collect();
+        let branches = self
+            .branches
+            .iter()
+            .map(|branch| {
+                let not_taken = hits[branch.fallthrough()];
+                CoveredBranch {
+                    offset: branch.offset,
+                    location: location(branch.offset),
+                    taken: hits[branch.block].saturating_sub(not_taken),
+                    not_taken,
+                }
+            })
+            .collect();
+
+        CoverageReport {
+            module_name: module.name.clone(),
+            functions,
+            blocks,
+            branches,
+        }
+    }
+}
+
+impl fmt::Debug for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coverage")
+            .field("blocks", &self.blocks.len())
+            .field("functions", &self.functions.len())
+            .field("branches", &self.branches.len())
+            .field("code_section_offset", &self.code_section_offset)
+            .finish()
+    }
+}
+
+impl ModuleMiddleware for Coverage {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        let first_counter = self.module.lock().unwrap().as_ref().unwrap().first_counter;
+        let blocks = &self.functions[local_function_index.as_u32() as usize];
+        Box::new(FunctionCoverage {
+            splitter: BlockSplitter::new(),
+            next_counter: first_counter.as_u32() + blocks.start as u32,
+            end_counter: first_counter.as_u32() + blocks.end as u32,
+        })
+    }
+
+    /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) -> Result<(), MiddlewareError> {
+        let mut module = self.module.lock().unwrap();
+
+        if module.is_some() {
+            panic!("Coverage::transform_module_info: Attempting to use a `Coverage` middleware from multiple modules.");
+        }
+
+        let local_functions = module_info.functions.len() - module_info.num_imported_functions;
+        if local_functions != self.functions.len() {
+            return Err(MiddlewareError::new(
+                "coverage",
+                format!(
+                    "the module has {local_functions} functions, but the module scanned for coverage had {}",
+                    self.functions.len()
+                ),
+            ));
+        }
+
+        // Append a global counting the hits of each block.
+        let first_counter = GlobalIndex::from_u32(module_info.globals.len() as u32);
+        for block in 0..self.blocks.len() {
+            let counter = module_info
+                .globals
+                .push(GlobalType::new(Type::I64, Mutability::Var));
+            module_info
+                .global_initializers
+                .push(GlobalInit::I64Const(0));
+            module_info.exports.insert(
+                format!("{COUNTER_EXPORT_PREFIX}{block}"),
+                ExportIndex::Global(counter),
+            );
+        }
+
+        let function_names = (0..local_functions)
+            .map(|index| {
+                let index = module_info.func_index(LocalFunctionIndex::from_u32(index as u32));
+                match module_info.function_names.get(&index) {
+                    Some(name) => name.clone(),
+                    None => format!("function[{}]", index.as_u32()),
+                }
+            })
+            .collect();
+
+        *module = Some(CoveredModule {
+            name: module_info
+                .name
+                .clone()
+                .unwrap_or_else(|| "module.wasm".to_string()),
+            first_counter,
+            function_names,
+            line_table: LineTable::new(module_info, self.code_section_offset),
+        });
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for FunctionCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FunctionCoverage")
+            .field("next_counter", &self.next_counter)
+            .field("end_counter", &self.end_counter)
+            .finish()
+    }
+}
+
+impl FunctionMiddleware for FunctionCoverage {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if self.splitter.starts_block(&operator) {
+            if self.next_counter == self.end_counter {
+                return Err(MiddlewareError::new(
+                    "coverage",
+                    "the function has more blocks than when it was scanned",
+                ));
+            }
+
+            // globals[counter] += 1;
+            state.extend(&[
+                Operator::GlobalGet {
+                    global_index: self.next_counter,
+                },
+                Operator::I64Const { value: 1 },
+                Operator::I64Add,
+                Operator::GlobalSet {
+                    global_index: self.next_counter,
+                },
+            ]);
+            self.next_counter += 1;
+        }
+        state.push_operator(operator);
+
+        Ok(())
+    }
+}
+
+/// A position in the source code of a module, from its DWARF line
+/// tables.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+}
+
+/// How many times a function was called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoveredFunction {
+    pub name: String,
+    /// The offset of the function's first operator in the module.
+    pub offset: u64,
+    pub location: Option<SourceLocation>,
+    pub calls: u64,
+}
+
+/// How many times a basic block was entered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoveredBlock {
+    /// The offset of the block's first operator in the module.
+    pub offset: u64,
+    pub location: Option<SourceLocation>,
+    pub hits: u64,
+}
+
+/// How many times an `if` or `br_if` branched.
+///
+/// For an `if`, the branch is taken when the condition is false.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoveredBranch {
+    /// The offset of the branch in the module.
+    pub offset: u64,
+    pub location: Option<SourceLocation>,
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+/// The coverage of a run of a module, as collected by
+/// [`Coverage::report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub module_name: String,
+    pub functions: Vec<CoveredFunction>,
+    pub blocks: Vec<CoveredBlock>,
+    pub branches: Vec<CoveredBranch>,
+}
+
+/// The records of a source file in an LCOV tracefile.
+#[derive(Default)]
+struct LcovFile<'a> {
+    /// The line and calls of each function.
+    functions: Vec<(u32, &'a str, u64)>,
+    /// The hits of each line.
+    lines: BTreeMap<u32, u64>,
+    /// The line, and times taken and not taken, of each branch.
+    branches: Vec<(u32, u64, u64)>,
+}
+
+impl CoverageReport {
+    /// Formats the report as an LCOV tracefile.
+    ///
+    /// Code without DWARF line information is reported in a file named
+    /// after the module, with its offset in the module as line number.
+    pub fn to_lcov(&self) -> String {
+        let mut files: BTreeMap<&str, LcovFile> = BTreeMap::new();
+        for function in &self.functions {
+            let (file, line) = self.position(function.offset, &function.location);
+            let record = files.entry(file).or_default();
+            record
+                .functions
+                .push((line, &function.name, function.calls));
+        }
+        for block in &self.blocks {
+            let (file, line) = self.position(block.offset, &block.location);
+            let hits = files
+                .entry(file)
+                .or_default()
+                .lines
+                .entry(line)
+                .or_default();
+            // A line is covered as soon as one of its blocks is
+            *hits = (*hits).max(block.hits);
+        }
+        for branch in &self.branches {
+            let (file, line) = self.position(branch.offset, &branch.location);
+            let record = files.entry(file).or_default();
+            record.branches.push((line, branch.taken, branch.not_taken));
+        }
+
+        let mut lcov = String::new();
+        for (file, record) in files {
+            let _ = writeln!(lcov, "TN:");
+            let _ = writeln!(lcov, "SF:{file}");
+
+            for (line, name, _) in &record.functions {
+                let _ = writeln!(lcov, "FN:{line},{name}");
+            }
+            for (_, name, calls) in &record.functions {
+                let _ = writeln!(lcov, "FNDA:{calls},{name}");
+            }
+            let called = record.functions.iter().filter(|f| f.2 > 0).count();
+            let _ = writeln!(lcov, "FNF:{}", record.functions.len());
+            let _ = writeln!(lcov, "FNH:{called}");
+
+            let mut branches_hit = 0;
+            for (index, (line, taken, not_taken)) in record.branches.iter().enumerate() {
+                for (branch, count) in [taken, not_taken].into_iter().enumerate() {
+                    if *count > 0 {
+                        branches_hit += 1;
+                    }
+                    // Branches which weren't reached have no count
+                    if taken + not_taken == 0 {
+                        let _ = writeln!(lcov, "BRDA:{line},{index},{branch},-");
+                    } else {
+                        let _ = writeln!(lcov, "BRDA:{line},{index},{branch},{count}");
+                    }
+                }
+            }
+            let _ = writeln!(lcov, "BRF:{}", record.branches.len() * 2);
+            let _ = writeln!(lcov, "BRH:{branches_hit}");
+
+            for (line, hits) in &record.lines {
+                let _ = writeln!(lcov, "DA:{line},{hits}");
+            }
+            let lines_hit = record.lines.values().filter(|hits| **hits > 0).count();
+            let _ = writeln!(lcov, "LF:{}", record.lines.len());
+            let _ = writeln!(lcov, "LH:{lines_hit}");
+
+            let _ = writeln!(lcov, "end_of_record");
+        }
+        lcov
+    }
+
+    /// The file and line code is reported at in LCOV tracefiles.
+    fn position<'a>(&'a self, offset: u64, location: &'a Option<SourceLocation>) -> (&'a str, u32) {
+        match location {
+            Some(location) => (&location.file, location.line),
+            None => (&self.module_name, offset as u32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use wasmer::sys::EngineBuilder;
+    use wasmer::{imports, wat2wasm, CompilerConfig, Cranelift, Module, Store, TypedFunction};
+
+    fn bytecode() -> Vec<u8> {
+        wat2wasm(
+            br#"(module
+            (func $abs (param $value i32) (result i32)
+                (if (result i32) (i32.lt_s (local.get $value) (i32.const 0))
+                    (then (i32.sub (i32.const 0) (local.get $value)))
+                    (else (local.get $value))))
+            (func $count_down (param $n i32)
+                (loop $continue
+                    (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                    (br_if $continue (local.get $n))))
+            (func $unused)
+            (export "abs" (func $abs))
+            (export "count_down" (func $count_down))
+            (export "unused" (func $unused))
+        )"#,
+        )
+        .unwrap()
+        .into()
+    }
+
+    fn instantiate(wasm: &[u8]) -> (Arc<Coverage>, Store, Instance) {
+        let coverage = Arc::new(Coverage::new(wasm).unwrap());
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(coverage.clone());
+        let mut store = Store::new(EngineBuilder::new(compiler_config));
+        let module = Module::new(&store, wasm).unwrap();
+        let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+        (coverage, store, instance)
+    }
+
+    #[test]
+    fn counts_blocks_and_branches() {
+        let (coverage, mut store, instance) = instantiate(&bytecode());
+        assert_eq!(coverage.blocks(), 9);
+
+        let abs: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "abs").unwrap();
+        assert_eq!(abs.call(&mut store, -3).unwrap(), 3);
+        assert_eq!(abs.call(&mut store, 5).unwrap(), 5);
+        let count_down: TypedFunction<i32, ()> = instance
+            .exports
+            .get_typed_function(&store, "count_down")
+            .unwrap();
+        count_down.call(&mut store, 3).unwrap();
+
+        let report = coverage.report(&mut store, &instance);
+
+        let calls: Vec<_> = report
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.calls))
+            .collect();
+        assert_eq!(calls, [("abs", 2), ("count_down", 1), ("unused", 0)]);
+
+        // `abs`: entry, then, else, end; `count_down`: entry, loop body,
+        // loop end, end; `unused`: end
+        let hits: Vec<_> = report.blocks.iter().map(|block| block.hits).collect();
+        assert_eq!(hits, [2, 1, 1, 2, 1, 3, 1, 1, 0]);
+        assert!(report.blocks.windows(2).all(|w| w[0].offset < w[1].offset));
+        assert!(report.blocks.iter().all(|block| block.location.is_none()));
+
+        // The `if` is taken when its condition is false
+        let branches: Vec<_> = report
+            .branches
+            .iter()
+            .map(|branch| (branch.taken, branch.not_taken))
+            .collect();
+        assert_eq!(branches, [(1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn lcov_uses_offsets_without_debug_info() {
+        let (coverage, mut store, instance) = instantiate(&bytecode());
+        let abs: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "abs").unwrap();
+        abs.call(&mut store, 1).unwrap();
+
+        let report = coverage.report(&mut store, &instance);
+        let lcov = report.to_lcov();
+        let lines: Vec<&str> = lcov.lines().collect();
+
+        let abs_offset = report.functions[0].offset;
+        let if_offset = report.branches[0].offset;
+        assert_eq!(lines[..2], ["TN:", "SF:module.wasm"]);
+        assert!(lines.contains(&format!("FN:{abs_offset},abs").as_str()));
+        assert!(lines.contains(&"FNDA:1,abs"));
+        assert!(lines.contains(&"FNDA:0,count_down"));
+        assert!(lines.contains(&format!("BRDA:{if_offset},0,0,1").as_str()));
+        assert!(lines.contains(&format!("BRDA:{if_offset},0,1,0").as_str()));
+        assert!(lines.contains(&format!("BRDA:{},1,0,-", report.branches[1].offset).as_str()));
+        assert!(lines.contains(&format!("DA:{abs_offset},1").as_str()));
+        for summary in ["FNF:3", "FNH:1", "BRF:4", "BRH:1", "LF:9", "LH:3"] {
+            assert!(lines.contains(&summary), "missing {summary}");
+        }
+        assert_eq!(lines.last(), Some(&"end_of_record"));
+    }
+
+    /// Appends DWARF sections to `wasm`, putting the `i`th block of
+    /// `bytecode()` on line `10 + i` of `/src/math.c`, except for the block
+    /// of `unused` which is on line 1 of `/src/unused.c`.
+    fn with_line_tables(mut wasm: Vec<u8>) -> Vec<u8> {
+        use gimli::write::{Address, DwarfUnit, EndianVec, LineProgram, LineString, Sections};
+
+        let coverage = Coverage::new(&wasm).unwrap();
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut program = LineProgram::new(
+            encoding,
+            gimli::LineEncoding::default(),
+            LineString::String(b"/src".to_vec()),
+            LineString::String(b"math.c".to_vec()),
+            None,
+        );
+        let dir = program.default_directory();
+        let math = program.add_file(LineString::String(b"math.c".to_vec()), dir, None);
+        let unused = program.add_file(LineString::String(b"unused.c".to_vec()), dir, None);
+
+        program.begin_sequence(Some(Address::Constant(0)));
+        for (i, &offset) in coverage.blocks.iter().enumerate() {
+            let (file, line) = match i {
+                8 => (unused, 1),
+                i => (math, 10 + i as u64),
+            };
+            let row = program.row();
+            row.address_offset = offset - coverage.code_section_offset;
+            row.file = file;
+            row.line = line;
+            program.generate_row();
+        }
+        program.end_sequence(wasm.len() as u64 - coverage.code_section_offset);
+
+        let mut dwarf = DwarfUnit::new(encoding);
+        dwarf.unit.line_program = program;
+        let mut sections = Sections::new(EndianVec::new(gimli::LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        sections
+            .for_each(|id, data| {
+                if !data.slice().is_empty() {
+                    push_custom_section(&mut wasm, id.name(), data.slice());
+                }
+                Ok::<_, gimli::write::Error>(())
+            })
+            .unwrap();
+        wasm
+    }
+
+    fn push_custom_section(wasm: &mut Vec<u8>, name: &str, data: &[u8]) {
+        fn leb128(out: &mut Vec<u8>, mut value: usize) {
+            loop {
+                let byte = (value & 0x7f) as u8;
+                value >>= 7;
+                if value == 0 {
+                    out.push(byte);
+                    return;
+                }
+                out.push(byte | 0x80);
+            }
+        }
+
+        let mut payload = Vec::new();
+        leb128(&mut payload, name.len());
+        payload.extend_from_slice(name.as_bytes());
+        payload.extend_from_slice(data);
+        wasm.push(0);
+        leb128(wasm, payload.len());
+        wasm.extend(payload);
+    }
+
+    #[test]
+    fn lcov_uses_dwarf_line_tables() {
+        let (coverage, mut store, instance) = instantiate(&with_line_tables(bytecode()));
+        let abs: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "abs").unwrap();
+        assert_eq!(abs.call(&mut store, -3).unwrap(), 3);
+        assert_eq!(abs.call(&mut store, 5).unwrap(), 5);
+        let count_down: TypedFunction<i32, ()> = instance
+            .exports
+            .get_typed_function(&store, "count_down")
+            .unwrap();
+        count_down.call(&mut store, 3).unwrap();
+
+        let report = coverage.report(&mut store, &instance);
+        assert_eq!(
+            report.blocks[1].location,
+            Some(SourceLocation {
+                file: "/src/math.c".to_string(),
+                line: 11,
+            })
+        );
+
+        // The `if` is in the entry block of `abs` and the `br_if` in the
+        // body of the loop of `count_down`, whose blocks are on lines 10
+        // and 15
+        let expected = "\
+TN:
+SF:/src/math.c
+FN:10,abs
+FN:14,count_down
+FNDA:2,abs
+FNDA:1,count_down
+FNF:2
+FNH:2
+BRDA:10,0,0,1
+BRDA:10,0,1,1
+BRDA:15,1,0,2
+BRDA:15,1,1,1
+BRF:4
+BRH:4
+DA:10,2
+DA:11,1
+DA:12,1
+DA:13,2
+DA:14,1
+DA:15,3
+DA:16,1
+DA:17,1
+LF:8
+LH:8
+end_of_record
+TN:
+SF:/src/unused.c
+FN:1,unused
+FNDA:0,unused
+FNF:1
+FNH:0
+BRF:0
+BRH:0
+DA:1,0
+LF:1
+LH:0
+end_of_record
+";
+        assert_eq!(report.to_lcov(), expected);
+    }
+}
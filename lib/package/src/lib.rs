@@ -4,4 +4,5 @@ mod macros;
 
 pub mod convert;
 pub mod package;
+pub mod signature;
 pub mod utils;
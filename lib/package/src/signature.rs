@@ -0,0 +1,393 @@
+//! Detached ed25519 signatures for `*.webc` files.
+//!
+//! Signatures are made over a package's [`PackageHash`] rather than its
+//! contents, and are stored next to the `*.webc` file they sign (see
+//! [`signature_path()`]). Keys and signatures use a [minisign]-style text
+//! format: an optional `untrusted comment:` line followed by a base64 payload
+//! containing the algorithm, an 8-byte key ID, and the key or signature
+//! itself.
+//!
+//! [minisign]: https://jedisct1.github.io/minisign/
+
+use std::{
+    fmt::{Debug, Display},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519},
+};
+use sha2::{Digest, Sha256};
+use wasmer_config::package::PackageHash;
+
+/// The extension added to a `*.webc` file's name to get the name of its
+/// signature.
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+/// The algorithm tag at the start of every encoded key and signature.
+const ED25519_TAG: &[u8; 2] = b"Ed";
+/// Prefixed to the hash being signed so a signature can't be mistaken for
+/// one made for some other purpose.
+const SIGNATURE_CONTEXT: &[u8] = b"wasmer-package-signature\0";
+const UNTRUSTED_COMMENT: &str = "untrusted comment:";
+
+/// Get the path of the detached signature for a `*.webc` file.
+pub fn signature_path(webc: impl AsRef<Path>) -> PathBuf {
+    let mut path = webc.as_ref().as_os_str().to_owned();
+    path.push(".");
+    path.push(SIGNATURE_EXTENSION);
+    PathBuf::from(path)
+}
+
+/// A short identifier derived from a [`PublicKey`], used to tell which key
+/// made a signature.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyId([u8; 8]);
+
+impl KeyId {
+    fn for_public_key(public_key: &[u8]) -> Self {
+        let digest = Sha256::digest(public_key);
+        let mut id = [0; 8];
+        id.copy_from_slice(&digest[..8]);
+        KeyId(id)
+    }
+}
+
+impl Display for KeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for KeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("KeyId").field(&self.to_string()).finish()
+    }
+}
+
+/// An ed25519 key that can verify [`PackageSignature`]s.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PublicKey {
+    id: KeyId,
+    bytes: [u8; 32],
+}
+
+impl PublicKey {
+    pub fn id(&self) -> KeyId {
+        self.id
+    }
+
+    /// Check that `signature` was made by this key for a package with the
+    /// given hash.
+    pub fn verify(
+        &self,
+        hash: &PackageHash,
+        signature: &PackageSignature,
+    ) -> Result<(), SignatureError> {
+        if signature.key_id != self.id {
+            return Err(SignatureError::KeyMismatch {
+                expected: self.id,
+                actual: signature.key_id,
+            });
+        }
+
+        let message = signed_message(hash)?;
+        UnparsedPublicKey::new(&ED25519, self.bytes)
+            .verify(&message, &signature.bytes)
+            .map_err(|_| SignatureError::InvalidSignature)
+    }
+
+    /// Encode this key in the format used for public key files.
+    pub fn to_file_contents(&self) -> String {
+        encode(
+            &format!("wasmer public key {}", self.id),
+            self.id,
+            &self.bytes,
+        )
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = SignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, payload) = decode(s)?;
+        let bytes: [u8; 32] = fixed_length(payload)?;
+
+        if KeyId::for_public_key(&bytes) != id {
+            return Err(SignatureError::KeyIdMismatch);
+        }
+
+        Ok(PublicKey { id, bytes })
+    }
+}
+
+/// An ed25519 key pair used to sign packages.
+pub struct SigningKey {
+    id: KeyId,
+    pkcs8: Vec<u8>,
+    key_pair: Ed25519KeyPair,
+}
+
+impl SigningKey {
+    /// Generate a new random key.
+    pub fn generate() -> Result<Self, SignatureError> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| SignatureError::KeyGeneration)?;
+
+        SigningKey::from_pkcs8(pkcs8.as_ref().to_vec())
+    }
+
+    fn from_pkcs8(pkcs8: Vec<u8>) -> Result<Self, SignatureError> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(&pkcs8)
+            .map_err(|e| SignatureError::KeyRejected(e.to_string()))?;
+        let id = KeyId::for_public_key(key_pair.public_key().as_ref());
+
+        Ok(SigningKey {
+            id,
+            pkcs8,
+            key_pair,
+        })
+    }
+
+    pub fn id(&self) -> KeyId {
+        self.id
+    }
+
+    /// The [`PublicKey`] used to verify this key's signatures.
+    pub fn public_key(&self) -> PublicKey {
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(self.key_pair.public_key().as_ref());
+
+        PublicKey { id: self.id, bytes }
+    }
+
+    /// Sign a package with the given hash.
+    pub fn sign(&self, hash: &PackageHash) -> Result<PackageSignature, SignatureError> {
+        let message = signed_message(hash)?;
+        let mut bytes = [0; 64];
+        bytes.copy_from_slice(self.key_pair.sign(&message).as_ref());
+
+        Ok(PackageSignature {
+            key_id: self.id,
+            bytes,
+        })
+    }
+
+    /// Encode this key in the format used for secret key files.
+    ///
+    /// The key isn't encrypted, so the result should be stored somewhere
+    /// only its owner can read.
+    pub fn to_file_contents(&self) -> String {
+        encode(
+            &format!("wasmer secret key {}", self.id),
+            self.id,
+            &self.pkcs8,
+        )
+    }
+}
+
+impl FromStr for SigningKey {
+    type Err = SignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, payload) = decode(s)?;
+        let key = SigningKey::from_pkcs8(payload)?;
+
+        if key.id != id {
+            return Err(SignatureError::KeyIdMismatch);
+        }
+
+        Ok(key)
+    }
+}
+
+impl Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A detached signature over a package's [`PackageHash`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PackageSignature {
+    key_id: KeyId,
+    bytes: [u8; 64],
+}
+
+impl PackageSignature {
+    /// The ID of the key that made this signature.
+    pub fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
+    /// Encode this signature in the format used for signature files.
+    pub fn to_file_contents(&self, hash: &PackageHash) -> String {
+        encode(
+            &format!("signature of {hash} from key {}", self.key_id),
+            self.key_id,
+            &self.bytes,
+        )
+    }
+}
+
+impl FromStr for PackageSignature {
+    type Err = SignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key_id, payload) = decode(s)?;
+        let bytes = fixed_length(payload)?;
+
+        Ok(PackageSignature { key_id, bytes })
+    }
+}
+
+/// Errors that may occur while signing packages or verifying signatures.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum SignatureError {
+    #[error("Unable to generate a new key")]
+    KeyGeneration,
+    #[error("The key was rejected: {0}")]
+    KeyRejected(String),
+    #[error("Invalid base64")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Expected {expected} bytes, but found {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("Only ed25519 keys and signatures are supported")]
+    UnsupportedAlgorithm,
+    #[error("The key ID doesn't match the key")]
+    KeyIdMismatch,
+    #[error("Only sha256 package hashes can be signed")]
+    UnsupportedHash,
+    #[error("The signature was made by key {actual}, not {expected}")]
+    KeyMismatch { expected: KeyId, actual: KeyId },
+    #[error("The signature doesn't match the package")]
+    InvalidSignature,
+}
+
+fn signed_message(hash: &PackageHash) -> Result<Vec<u8>, SignatureError> {
+    let sha256 = hash.as_sha256().ok_or(SignatureError::UnsupportedHash)?;
+
+    Ok([SIGNATURE_CONTEXT, sha256.as_bytes().as_slice()].concat())
+}
+
+fn encode(comment: &str, id: KeyId, payload: &[u8]) -> String {
+    let data = [ED25519_TAG.as_slice(), &id.0, payload].concat();
+    format!("{UNTRUSTED_COMMENT} {comment}\n{}\n", STANDARD.encode(data))
+}
+
+fn decode(s: &str) -> Result<(KeyId, Vec<u8>), SignatureError> {
+    let encoded = s
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_COMMENT))
+        .unwrap_or_default();
+    let data = STANDARD.decode(encoded)?;
+
+    let Some(rest) = data.strip_prefix(ED25519_TAG) else {
+        return Err(SignatureError::UnsupportedAlgorithm);
+    };
+    if rest.len() < 8 {
+        return Err(SignatureError::InvalidLength {
+            expected: ED25519_TAG.len() + 8,
+            actual: data.len(),
+        });
+    }
+
+    let (id, payload) = rest.split_at(8);
+    let id = KeyId(id.try_into().expect("checked above"));
+
+    Ok((id, payload.to_vec()))
+}
+
+fn fixed_length<const N: usize>(payload: Vec<u8>) -> Result<[u8; N], SignatureError> {
+    payload
+        .try_into()
+        .map_err(|payload: Vec<u8>| SignatureError::InvalidLength {
+            expected: N,
+            actual: payload.len(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(byte: u8) -> PackageHash {
+        PackageHash::from_sha256_bytes([byte; 32])
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let key = SigningKey::generate().unwrap();
+        let public_key = key.public_key();
+
+        let signature = key.sign(&hash(1)).unwrap();
+
+        public_key.verify(&hash(1), &signature).unwrap();
+        assert!(matches!(
+            public_key.verify(&hash(2), &signature),
+            Err(SignatureError::InvalidSignature)
+        ));
+        let other = SigningKey::generate().unwrap().public_key();
+        assert!(matches!(
+            other.verify(&hash(1), &signature),
+            Err(SignatureError::KeyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn keys_and_signatures_round_trip_through_their_files() {
+        let key = SigningKey::generate().unwrap();
+        let signature = key.sign(&hash(1)).unwrap();
+
+        let secret_file = key.to_file_contents();
+        let public_file = key.public_key().to_file_contents();
+        let signature_file = signature.to_file_contents(&hash(1));
+
+        assert!(public_file.starts_with(&format!(
+            "untrusted comment: wasmer public key {}\n",
+            key.id()
+        )));
+        let round_tripped: SigningKey = secret_file.parse().unwrap();
+        assert_eq!(round_tripped.public_key(), key.public_key());
+        let public_key: PublicKey = public_file.parse().unwrap();
+        assert_eq!(public_key, key.public_key());
+        let parsed_signature: PackageSignature = signature_file.parse().unwrap();
+        assert_eq!(parsed_signature, signature);
+        public_key.verify(&hash(1), &parsed_signature).unwrap();
+    }
+
+    #[test]
+    fn reject_malformed_keys() {
+        let key = SigningKey::generate().unwrap().public_key();
+        let mut data = STANDARD
+            .decode(key.to_file_contents().lines().nth(1).unwrap())
+            .unwrap();
+        // Corrupt the key ID
+        data[2] ^= 0xFF;
+
+        let err = STANDARD.encode(&data).parse::<PublicKey>().unwrap_err();
+        assert!(matches!(err, SignatureError::KeyIdMismatch));
+
+        let err = "not base64!".parse::<PublicKey>().unwrap_err();
+        assert!(matches!(err, SignatureError::Base64(_)));
+    }
+
+    #[test]
+    fn signatures_live_next_to_the_webc() {
+        assert_eq!(
+            signature_path("/tmp/python@3.12.0.webc"),
+            PathBuf::from("/tmp/python@3.12.0.webc.sig")
+        );
+    }
+}
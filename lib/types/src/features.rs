@@ -169,6 +169,9 @@ impl Features {
     ///
     /// This feature gates tail-call functions in WebAssembly.
     ///
+    /// A tail call to a host function is a regular call followed by a
+    /// return, so it doesn't replace the frame of the caller.
+    ///
     /// This is `false` by default.
     ///
     /// [proposal]: https://github.com/webassembly/tail-call
@@ -233,6 +236,47 @@ impl Features {
         self.memory64 = enable;
         self
     }
+
+    /// Configures whether the WebAssembly exception handling proposal will
+    /// be enabled.
+    ///
+    /// The [WebAssembly exception handling proposal][proposal] is not
+    /// currently fully standardized and is undergoing development.
+    /// Support for this feature can be enabled through this method for
+    /// appropriate WebAssembly modules.
+    ///
+    /// This feature gates tags, `throw`, `throw_ref` and `try_table`, as
+    /// well as the legacy `try`, `catch`, `delegate` and `rethrow`
+    /// instructions.
+    ///
+    /// This is `false` by default.
+    ///
+    /// [proposal]: https://github.com/WebAssembly/exception-handling
+    pub fn exceptions(&mut self, enable: bool) -> &mut Self {
+        self.exceptions = enable;
+        self
+    }
+
+    /// Configures whether the WebAssembly relaxed SIMD proposal will be
+    /// enabled.
+    ///
+    /// The [WebAssembly relaxed SIMD proposal][proposal] adds SIMD
+    /// operators whose results may differ between platforms, so they can
+    /// be lowered to the fastest native instructions.
+    ///
+    /// This feature gates all the `relaxed` operators, and requires SIMD to
+    /// be enabled.
+    ///
+    /// This is `false` by default.
+    ///
+    /// [proposal]: https://github.com/WebAssembly/relaxed-simd
+    pub fn relaxed_simd(&mut self, enable: bool) -> &mut Self {
+        self.relaxed_simd = enable;
+        if enable {
+            self.simd(true);
+        }
+        self
+    }
 }
 
 impl Default for Features {
@@ -341,4 +385,12 @@ mod test_features {
         features.memory64(true);
         assert!(features.memory64);
     }
+
+    #[test]
+    fn enable_relaxed_simd() {
+        let mut features = Features::new();
+        features.simd(false).relaxed_simd(true);
+        assert!(features.relaxed_simd);
+        assert!(features.simd);
+    }
 }
@@ -159,6 +159,26 @@ entity_impl!(GlobalIndex);
 pub struct MemoryIndex(pub(crate) u32);
 entity_impl!(MemoryIndex);
 
+/// Index type of a tag (imported or local) inside the WebAssembly module.
+#[derive(
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Debug,
+    RkyvSerialize,
+    RkyvDeserialize,
+    Archive,
+)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
+#[rkyv(derive(Debug), compare(PartialOrd, PartialEq))]
+pub struct TagIndex(u32);
+entity_impl!(TagIndex);
+
 /// Index type of a signature (imported or local) inside the WebAssembly module.
 #[derive(
     Copy,
@@ -272,6 +292,8 @@ pub enum ExportIndex {
     Memory(MemoryIndex),
     /// Global export.
     Global(GlobalIndex),
+    /// Tag export.
+    Tag(TagIndex),
 }
 
 /// An entity to import.
@@ -291,4 +313,6 @@ pub enum ImportIndex {
     Memory(MemoryIndex),
     /// Global import.
     Global(GlobalIndex),
+    /// Tag import.
+    Tag(TagIndex),
 }
@@ -135,6 +135,27 @@ pub enum LibCall {
 
     /// memory.atomic.botify for imported memories
     ImportedMemory32AtomicNotify,
+
+    /// throw
+    Throw,
+
+    /// throw_ref
+    ThrowRef,
+
+    /// Fused multiply-add for f32, used by `f32x4.relaxed_madd`
+    FmaF32,
+
+    /// Fused multiply-add for f64, used by `f64x2.relaxed_madd`
+    FmaF64,
+
+    /// Called when the epoch deadline of the store is reached
+    EpochDeadlineReached,
+
+    /// Releases an exception caught without keeping its `exnref`
+    DropException,
+
+    /// Marks an exception whose `exnref` was handed out to Wasm code
+    ExposeException,
 }
 
 impl LibCall {
@@ -183,6 +204,13 @@ impl LibCall {
             Self::ImportedMemory32AtomicWait64 => "wasmer_vm_imported_memory32_atomic_wait64",
             Self::Memory32AtomicNotify => "wasmer_vm_memory32_atomic_notify",
             Self::ImportedMemory32AtomicNotify => "wasmer_vm_imported_memory32_atomic_notify",
+            Self::Throw => "wasmer_vm_throw",
+            Self::ThrowRef => "wasmer_vm_throw_ref",
+            Self::FmaF32 => "wasmer_vm_f32_fma",
+            Self::FmaF64 => "wasmer_vm_f64_fma",
+            Self::EpochDeadlineReached => "wasmer_vm_epoch_deadline_reached",
+            Self::DropException => "wasmer_vm_drop_exception",
+            Self::ExposeException => "wasmer_vm_expose_exception",
         }
     }
 }
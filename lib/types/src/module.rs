@@ -9,7 +9,7 @@ use crate::{
     CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, ExportType, ExternType, FunctionIndex,
     FunctionType, GlobalIndex, GlobalInit, GlobalType, ImportIndex, ImportType, LocalFunctionIndex,
     LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, MemoryType, ModuleHash,
-    SignatureIndex, TableIndex, TableInitializer, TableType,
+    SignatureIndex, TableIndex, TableInitializer, TableType, TagIndex, TagType,
 };
 
 use indexmap::IndexMap;
@@ -165,6 +165,9 @@ pub struct ModuleInfo {
     /// WebAssembly global variables (imported and local).
     pub globals: PrimaryMap<GlobalIndex, GlobalType>,
 
+    /// WebAssembly exception tags (imported and local).
+    pub tags: PrimaryMap<TagIndex, SignatureIndex>,
+
     /// Custom sections in the module.
     pub custom_sections: IndexMap<String, CustomSectionIndex>,
 
@@ -182,6 +185,9 @@ pub struct ModuleInfo {
 
     /// Number of imported globals in the module.
     pub num_imported_globals: usize,
+
+    /// Number of imported tags in the module.
+    pub num_imported_tags: usize,
 }
 
 /// Mirror version of ModuleInfo that can derive rkyv traits
@@ -203,12 +209,14 @@ pub struct ArchivableModuleInfo {
     tables: PrimaryMap<TableIndex, TableType>,
     memories: PrimaryMap<MemoryIndex, MemoryType>,
     globals: PrimaryMap<GlobalIndex, GlobalType>,
+    tags: PrimaryMap<TagIndex, SignatureIndex>,
     custom_sections: IndexMap<String, CustomSectionIndex>,
     custom_sections_data: PrimaryMap<CustomSectionIndex, Box<[u8]>>,
     num_imported_functions: usize,
     num_imported_tables: usize,
     num_imported_memories: usize,
     num_imported_globals: usize,
+    num_imported_tags: usize,
 }
 
 impl From<ModuleInfo> for ArchivableModuleInfo {
@@ -229,12 +237,14 @@ impl From<ModuleInfo> for ArchivableModuleInfo {
             tables: it.tables,
             memories: it.memories,
             globals: it.globals,
+            tags: it.tags,
             custom_sections: it.custom_sections,
             custom_sections_data: it.custom_sections_data,
             num_imported_functions: it.num_imported_functions,
             num_imported_tables: it.num_imported_tables,
             num_imported_memories: it.num_imported_memories,
             num_imported_globals: it.num_imported_globals,
+            num_imported_tags: it.num_imported_tags,
         }
     }
 }
@@ -258,12 +268,14 @@ impl From<ArchivableModuleInfo> for ModuleInfo {
             tables: it.tables,
             memories: it.memories,
             globals: it.globals,
+            tags: it.tags,
             custom_sections: it.custom_sections,
             custom_sections_data: it.custom_sections_data,
             num_imported_functions: it.num_imported_functions,
             num_imported_tables: it.num_imported_tables,
             num_imported_memories: it.num_imported_memories,
             num_imported_globals: it.num_imported_globals,
+            num_imported_tags: it.num_imported_tags,
         }
     }
 }
@@ -320,12 +332,14 @@ impl PartialEq for ModuleInfo {
             && self.tables == other.tables
             && self.memories == other.memories
             && self.globals == other.globals
+            && self.tags == other.tags
             && self.custom_sections == other.custom_sections
             && self.custom_sections_data == other.custom_sections_data
             && self.num_imported_functions == other.num_imported_functions
             && self.num_imported_tables == other.num_imported_tables
             && self.num_imported_memories == other.num_imported_memories
             && self.num_imported_globals == other.num_imported_globals
+            && self.num_imported_tags == other.num_imported_tags
     }
 }
 
@@ -383,6 +397,7 @@ impl ModuleInfo {
                     let global_type = self.globals.get(*i).unwrap();
                     ExternType::Global(*global_type)
                 }
+                ExportIndex::Tag(i) => ExternType::Tag(self.tag_type(*i)),
             };
             ExportType::new(name, extern_type)
         });
@@ -413,6 +428,7 @@ impl ModuleInfo {
                             let global_type = self.globals.get(*i).unwrap();
                             ExternType::Global(*global_type)
                         }
+                        ImportIndex::Tag(i) => ExternType::Tag(self.tag_type(*i)),
                     };
                     ImportType::new(module, field, extern_type)
                 });
@@ -506,6 +522,17 @@ impl ModuleInfo {
         index.index() < self.num_imported_globals
     }
 
+    /// Test whether the given tag index is for an imported tag.
+    pub fn is_imported_tag(&self, index: TagIndex) -> bool {
+        index.index() < self.num_imported_tags
+    }
+
+    /// Get the type of the given tag.
+    pub fn tag_type(&self, index: TagIndex) -> TagType {
+        let signature = self.tags[index];
+        TagType::new(self.signatures[signature].params())
+    }
+
     /// Get the Module name
     pub fn name(&self) -> String {
         match self.name {
@@ -60,6 +60,12 @@ pub enum TrapCode {
 
     /// An atomic memory access was attempted with an unaligned pointer.
     UnalignedAtomic = 10,
+
+    /// A null reference was used where a non-null one was expected.
+    NullReference = 11,
+
+    /// The epoch deadline of the store was reached.
+    Interrupt = 12,
 }
 
 impl TrapCode {
@@ -77,6 +83,8 @@ impl TrapCode {
             Self::BadConversionToInteger => "invalid conversion to integer",
             Self::UnreachableCodeReached => "unreachable",
             Self::UnalignedAtomic => "unaligned atomic access",
+            Self::NullReference => "null reference",
+            Self::Interrupt => "interrupt",
         }
     }
 }
@@ -95,6 +103,8 @@ impl Display for TrapCode {
             Self::BadConversionToInteger => "bad_toint",
             Self::UnreachableCodeReached => "unreachable",
             Self::UnalignedAtomic => "unalign_atom",
+            Self::NullReference => "null_ref",
+            Self::Interrupt => "interrupt",
         };
         f.write_str(identifier)
     }
@@ -116,6 +126,8 @@ impl FromStr for TrapCode {
             "bad_toint" => Ok(Self::BadConversionToInteger),
             "unreachable" => Ok(Self::UnreachableCodeReached),
             "unalign_atom" => Ok(Self::UnalignedAtomic),
+            "null_ref" => Ok(Self::NullReference),
+            "interrupt" => Ok(Self::Interrupt),
             _ => Err(()),
         }
     }
@@ -139,7 +151,7 @@ mod tests {
     use super::*;
 
     // Everything but user-defined codes.
-    const CODES: [TrapCode; 11] = [
+    const CODES: [TrapCode; 13] = [
         TrapCode::StackOverflow,
         TrapCode::HeapAccessOutOfBounds,
         TrapCode::HeapMisaligned,
@@ -151,6 +163,8 @@ mod tests {
         TrapCode::BadConversionToInteger,
         TrapCode::UnreachableCodeReached,
         TrapCode::UnalignedAtomic,
+        TrapCode::NullReference,
+        TrapCode::Interrupt,
     ];
 
     #[test]
@@ -8,7 +8,7 @@
 
 use crate::{
     FunctionIndex, GlobalIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
-    ModuleInfo, SignatureIndex, TableIndex,
+    ModuleInfo, SignatureIndex, TableIndex, TagIndex,
 };
 use more_asserts::assert_lt;
 use std::convert::TryFrom;
@@ -140,9 +140,29 @@ impl VMBuiltinFunctionIndex {
     pub const fn get_imported_memory_atomic_notify_index() -> Self {
         Self(29)
     }
+    /// Returns an index for wasm's `throw` instruction.
+    pub const fn get_throw_index() -> Self {
+        Self(30)
+    }
+    /// Returns an index for wasm's `throw_ref` instruction.
+    pub const fn get_throw_ref_index() -> Self {
+        Self(31)
+    }
+    /// Returns an index for the builtin called when the epoch deadline is reached.
+    pub const fn get_epoch_deadline_reached_index() -> Self {
+        Self(32)
+    }
+    /// Returns an index for the builtin releasing an exception caught without its `exnref`.
+    pub const fn get_drop_exception_index() -> Self {
+        Self(33)
+    }
+    /// Returns an index for the builtin marking an exception whose `exnref` was handed out.
+    pub const fn get_expose_exception_index() -> Self {
+        Self(34)
+    }
     /// Returns the total number of builtin functions.
     pub const fn builtin_functions_total_number() -> u32 {
-        30
+        35
     }
 
     /// Return the index as an u32 number.
@@ -188,6 +208,8 @@ pub struct VMOffsets {
     num_local_memories: u32,
     /// The number of defined globals in the module.
     num_local_globals: u32,
+    /// The number of tags (imported and defined) in the module.
+    num_tags: u32,
 
     vmctx_signature_ids_begin: u32,
     vmctx_imported_functions_begin: u32,
@@ -198,6 +220,9 @@ pub struct VMOffsets {
     vmctx_memories_begin: u32,
     vmctx_globals_begin: u32,
     vmctx_builtin_functions_begin: u32,
+    vmctx_tags_begin: u32,
+    vmctx_exception_state: u32,
+    vmctx_epoch_state: u32,
     vmctx_trap_handler_begin: u32,
     vmctx_gas_limiter_pointer: u32,
     vmctx_stack_limit_begin: u32,
@@ -218,6 +243,7 @@ impl VMOffsets {
             num_local_tables: cast_to_u32(module.tables.len()),
             num_local_memories: cast_to_u32(module.memories.len()),
             num_local_globals: cast_to_u32(module.globals.len()),
+            num_tags: cast_to_u32(module.tags.len()),
             vmctx_signature_ids_begin: 0,
             vmctx_imported_functions_begin: 0,
             vmctx_imported_tables_begin: 0,
@@ -227,6 +253,9 @@ impl VMOffsets {
             vmctx_memories_begin: 0,
             vmctx_globals_begin: 0,
             vmctx_builtin_functions_begin: 0,
+            vmctx_tags_begin: 0,
+            vmctx_exception_state: 0,
+            vmctx_epoch_state: 0,
             vmctx_trap_handler_begin: 0,
             vmctx_gas_limiter_pointer: 0,
             vmctx_stack_limit_begin: 0,
@@ -252,6 +281,7 @@ impl VMOffsets {
             num_local_tables: 0,
             num_local_memories: 0,
             num_local_globals: 0,
+            num_tags: 0,
             vmctx_signature_ids_begin: 0,
             vmctx_imported_functions_begin: 0,
             vmctx_imported_tables_begin: 0,
@@ -261,6 +291,9 @@ impl VMOffsets {
             vmctx_memories_begin: 0,
             vmctx_globals_begin: 0,
             vmctx_builtin_functions_begin: 0,
+            vmctx_tags_begin: 0,
+            vmctx_exception_state: 0,
+            vmctx_epoch_state: 0,
             vmctx_trap_handler_begin: 0,
             vmctx_gas_limiter_pointer: 0,
             vmctx_stack_limit_begin: 0,
@@ -340,11 +373,20 @@ impl VMOffsets {
             self.num_local_globals,
             u32::from(self.size_of_vmglobal_local()),
         );
-        self.vmctx_trap_handler_begin = offset_by(
+        self.vmctx_tags_begin = offset_by(
             self.vmctx_builtin_functions_begin,
             VMBuiltinFunctionIndex::builtin_functions_total_number(),
             u32::from(self.pointer_size),
         );
+        self.vmctx_exception_state = offset_by_aligned(
+            self.vmctx_tags_begin,
+            self.num_tags,
+            u32::from(self.size_of_vmshared_tag_index()),
+        );
+        self.vmctx_epoch_state =
+            offset_by(self.vmctx_exception_state, 1, u32::from(self.pointer_size));
+        self.vmctx_trap_handler_begin =
+            offset_by(self.vmctx_epoch_state, 1, u32::from(self.pointer_size));
         self.vmctx_gas_limiter_pointer = offset_by(
             self.vmctx_trap_handler_begin,
             1,
@@ -379,9 +421,14 @@ impl VMOffsets {
         2 * self.pointer_size
     }
 
+    /// The offset of the `tail_body` field.
+    pub const fn vmfunction_import_tail_body(&self) -> u8 {
+        3 * self.pointer_size
+    }
+
     /// Return the size of `VMFunctionImport`.
     pub const fn size_of_vmfunction_import(&self) -> u8 {
-        3 * self.pointer_size
+        4 * self.pointer_size
     }
 }
 
@@ -544,6 +591,47 @@ impl VMOffsets {
     }
 }
 
+/// Offsets for `VMSharedTagIndex`.
+impl VMOffsets {
+    /// Return the size of `VMSharedTagIndex`.
+    pub const fn size_of_vmshared_tag_index(&self) -> u8 {
+        4
+    }
+}
+
+/// Offsets for `VMExceptionState`.
+impl VMOffsets {
+    /// The offset of the `exnref` field.
+    #[allow(clippy::erasing_op)]
+    pub const fn vmexception_state_exnref(&self) -> u8 {
+        0 * self.pointer_size
+    }
+
+    /// The offset of the `payload` field.
+    #[allow(clippy::identity_op)]
+    pub const fn vmexception_state_payload(&self) -> u8 {
+        1 * self.pointer_size
+    }
+
+    /// The offset of the `tag` field.
+    pub const fn vmexception_state_tag(&self) -> u8 {
+        2 * self.pointer_size
+    }
+}
+
+/// Offsets for `VMEpochState`.
+impl VMOffsets {
+    /// The offset of the `deadline` field.
+    pub const fn vmepoch_state_deadline(&self) -> u8 {
+        0
+    }
+
+    /// The offset of the `counter` field.
+    pub const fn vmepoch_state_counter(&self) -> u8 {
+        8
+    }
+}
+
 /// Offsets for `VMCallerCheckedAnyfunc`.
 impl VMOffsets {
     /// The offset of the `func_ptr` field.
@@ -568,9 +656,14 @@ impl VMOffsets {
         3 * self.pointer_size
     }
 
+    /// The offset of the `tail_func_ptr` field.
+    pub const fn vmcaller_checked_anyfunc_tail_func_ptr(&self) -> u8 {
+        4 * self.pointer_size
+    }
+
     /// Return the size of `VMCallerCheckedAnyfunc`.
     pub const fn size_of_vmcaller_checked_anyfunc(&self) -> u8 {
-        4 * self.pointer_size
+        5 * self.pointer_size
     }
 }
 
@@ -638,6 +731,21 @@ impl VMOffsets {
         self.vmctx_builtin_functions_begin
     }
 
+    /// The offset of the tags array.
+    pub fn vmctx_tags_begin(&self) -> u32 {
+        self.vmctx_tags_begin
+    }
+
+    /// The offset of the pointer to the store's `VMExceptionState`.
+    pub fn vmctx_exception_state(&self) -> u32 {
+        self.vmctx_exception_state
+    }
+
+    /// The offset of the pointer to the store's `VMEpochState`.
+    pub fn vmctx_epoch_state(&self) -> u32 {
+        self.vmctx_epoch_state
+    }
+
     /// Return the size of the `VMContext` allocation.
     pub fn size_of_vmctx(&self) -> u32 {
         self.size_of_vmctx
@@ -650,6 +758,12 @@ impl VMOffsets {
             + index.as_u32() * u32::from(self.size_of_vmshared_signature_index())
     }
 
+    /// Return the offset to `VMSharedTagIndex` index `index`.
+    pub fn vmctx_vmshared_tag_id(&self, index: TagIndex) -> u32 {
+        assert_lt!(index.as_u32(), self.num_tags);
+        self.vmctx_tags_begin + index.as_u32() * u32::from(self.size_of_vmshared_tag_index())
+    }
+
     /// Return the offset to `VMFunctionImport` index `index`.
     pub fn vmctx_vmfunction_import(&self, index: FunctionIndex) -> u32 {
         assert_lt!(index.as_u32(), self.num_imported_functions);
@@ -707,6 +821,12 @@ impl VMOffsets {
         self.vmctx_vmfunction_import(index) + u32::from(self.vmfunction_import_vmctx())
     }
 
+    /// Return the offset to the `tail_body` field in `*const VMFunctionBody` index `index`.
+    /// Remember updating precompute upon changes
+    pub fn vmctx_vmfunction_import_tail_body(&self, index: FunctionIndex) -> u32 {
+        self.vmctx_vmfunction_import(index) + u32::from(self.vmfunction_import_tail_body())
+    }
+
     /// Return the offset to the `definition` field in `VMTableImport` index `index`.
     /// Remember updating precompute upon changes
     pub fn vmctx_vmtable_import_definition(&self, index: TableIndex) -> u32 {
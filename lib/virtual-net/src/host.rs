@@ -1,6 +1,8 @@
 #![allow(unused_variables)]
 use crate::ruleset::{Direction, Ruleset};
-use crate::{io_err_into_net_error, VirtualIoSource};
+use crate::tcp_pair::TcpSocketHalf;
+use crate::unix::{UnixHostMappings, UnixListener, UnixSockets, UnixStream};
+use crate::{io_err_into_net_error, VirtualIoSource, VirtualUnixDatagramSocket};
 #[allow(unused_imports)]
 use crate::{
     IpCidr, IpRoute, NetworkError, Result, SocketStatus, StreamSecurity, VirtualConnectedSocket,
@@ -18,6 +20,7 @@ use std::os::fd::AsRawFd;
 use std::os::fd::RawFd;
 #[cfg(windows)]
 use std::os::windows::io::AsRawSocket;
+use std::path::{Path, PathBuf};
 
 use std::sync::Arc;
 use std::task::Poll;
@@ -34,6 +37,7 @@ pub struct LocalNetworking {
     selector: Arc<Selector>,
     handle: Handle,
     ruleset: Option<Ruleset>,
+    unix_mappings: UnixHostMappings,
 }
 
 impl LocalNetworking {
@@ -42,6 +46,7 @@ impl LocalNetworking {
             selector: Selector::new(),
             handle: Handle::current(),
             ruleset: None,
+            unix_mappings: UnixHostMappings::new(),
         }
     }
 
@@ -50,8 +55,18 @@ impl LocalNetworking {
             selector: Selector::new(),
             handle: Handle::current(),
             ruleset: Some(ruleset),
+            unix_mappings: UnixHostMappings::new(),
         }
     }
+
+    /// Maps a guest path to a host Unix domain socket, so that guests
+    /// listening on or connecting to it reach the host. Unix domain sockets
+    /// are otherwise only visible within the namespace they are bound in.
+    ///
+    /// Only stream sockets can be mapped.
+    pub fn map_unix_socket(&mut self, guest: impl Into<PathBuf>, host: impl Into<PathBuf>) {
+        self.unix_mappings.map_to_host(guest, host);
+    }
 }
 
 impl Drop for LocalNetworking {
@@ -196,6 +211,171 @@ impl VirtualNetworking for LocalNetworking {
 
         Ok(addrs)
     }
+
+    async fn listen_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualTcpListener + Sync>> {
+        match self.unix_mappings.host_path(path) {
+            #[cfg(unix)]
+            Some(host_path) => {
+                let listener = std::os::unix::net::UnixListener::bind(&host_path)
+                    .map_err(io_err_into_net_error)?;
+                listener
+                    .set_nonblocking(true)
+                    .map_err(io_err_into_net_error)?;
+
+                let inner = UnixListener::new(path.to_path_buf());
+                let accepting = inner.clone();
+                let accept_task = self.handle.spawn(async move {
+                    let listener = match tokio::net::UnixListener::from_std(listener) {
+                        Ok(listener) => listener,
+                        Err(err) => {
+                            tracing::warn!(
+                                error = &err as &dyn std::error::Error,
+                                "unable to accept connections on a host Unix domain socket"
+                            );
+                            return;
+                        }
+                    };
+                    while let Ok((stream, _)) = listener.accept().await {
+                        let guest = accepting.connect_to(None).into_inner();
+                        tokio::spawn(bridge_unix_stream(guest, stream));
+                    }
+                });
+                Ok(Box::new(LocalUnixListener {
+                    inner,
+                    host_path,
+                    accept_task,
+                }))
+            }
+            #[cfg(not(unix))]
+            Some(_) => Err(NetworkError::Unsupported),
+            None => Ok(Box::new(sockets.listen(path)?)),
+        }
+    }
+
+    async fn connect_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualTcpSocket + Sync>> {
+        match self.unix_mappings.host_path(path) {
+            #[cfg(unix)]
+            Some(host_path) => {
+                let stream = std::os::unix::net::UnixStream::connect(host_path)
+                    .map_err(io_err_into_net_error)?;
+                stream
+                    .set_nonblocking(true)
+                    .map_err(io_err_into_net_error)?;
+
+                let (guest, bridged) = UnixStream::pair(None, Some(path.to_path_buf()));
+                let _guard = self.handle.enter();
+                let stream =
+                    tokio::net::UnixStream::from_std(stream).map_err(io_err_into_net_error)?;
+                self.handle
+                    .spawn(bridge_unix_stream(bridged.into_inner(), stream));
+                Ok(Box::new(guest))
+            }
+            #[cfg(not(unix))]
+            Some(_) => Err(NetworkError::Unsupported),
+            None => Ok(Box::new(sockets.connect(path)?)),
+        }
+    }
+
+    async fn bind_unix_datagram(
+        &self,
+        sockets: &UnixSockets,
+        path: Option<&Path>,
+    ) -> Result<Box<dyn VirtualUnixDatagramSocket + Sync>> {
+        if path
+            .and_then(|path| self.unix_mappings.host_path(path))
+            .is_some()
+        {
+            tracing::warn!(?path, "datagram sockets can't be mapped to the host");
+            return Err(NetworkError::Unsupported);
+        }
+        Ok(Box::new(sockets.bind_datagram(path)?))
+    }
+}
+
+/// Copies the bytes between a guest Unix domain socket and a host one,
+/// until either end closes the connection.
+#[cfg(unix)]
+async fn bridge_unix_stream(guest: TcpSocketHalf, host: tokio::net::UnixStream) {
+    use tokio::io::AsyncWriteExt;
+
+    let (mut host_rx, mut host_tx) = host.into_split();
+    let (mut guest_tx, mut guest_rx) = guest.split();
+    let inbound = async move {
+        tokio::io::copy(&mut host_rx, &mut guest_tx).await.ok();
+        guest_tx.close().ok();
+    };
+    let outbound = async move {
+        tokio::io::copy(&mut guest_rx, &mut host_tx).await.ok();
+        host_tx.shutdown().await.ok();
+    };
+    futures_util::future::join(inbound, outbound).await;
+}
+
+/// A Unix domain socket listener mapped to a host Unix domain socket.
+#[cfg(unix)]
+#[derive(Debug)]
+struct LocalUnixListener {
+    inner: UnixListener,
+    host_path: PathBuf,
+    accept_task: tokio::task::JoinHandle<()>,
+}
+
+#[cfg(unix)]
+impl Drop for LocalUnixListener {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+        std::fs::remove_file(&self.host_path).ok();
+    }
+}
+
+#[cfg(unix)]
+impl VirtualIoSource for LocalUnixListener {
+    fn remove_handler(&mut self) {
+        self.inner.remove_handler()
+    }
+
+    fn poll_read_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<usize>> {
+        self.inner.poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<usize>> {
+        self.inner.poll_write_ready(cx)
+    }
+}
+
+#[cfg(unix)]
+impl VirtualTcpListener for LocalUnixListener {
+    fn try_accept(&mut self) -> Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
+        self.inner.try_accept()
+    }
+
+    fn set_handler(&mut self, handler: Box<dyn InterestHandler + Send + Sync>) -> Result<()> {
+        self.inner.set_handler(handler)
+    }
+
+    fn addr_local(&self) -> Result<SocketAddr> {
+        self.inner.addr_local()
+    }
+
+    fn addr_local_unix(&self) -> Result<Option<PathBuf>> {
+        self.inner.addr_local_unix()
+    }
+
+    fn set_ttl(&mut self, ttl: u8) -> Result<()> {
+        self.inner.set_ttl(ttl)
+    }
+
+    fn ttl(&self) -> Result<u8> {
+        self.inner.ttl()
+    }
 }
 
 #[derive(Debug)]
@@ -984,3 +1164,72 @@ impl VirtualIoSource for LocalUdpSocket {
         Poll::Pending
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn recv(socket: &mut (dyn VirtualTcpSocket + Sync)) -> Vec<u8> {
+        let mut buf = [MaybeUninit::new(0); 64];
+        let amt = futures_util::future::poll_fn(|cx| match socket.try_recv(&mut buf) {
+            Err(NetworkError::WouldBlock) => {
+                socket.set_handler(cx.waker().into()).unwrap();
+                Poll::Pending
+            }
+            res => Poll::Ready(res),
+        })
+        .await
+        .unwrap();
+        buf[..amt]
+            .iter()
+            .map(|b| unsafe { b.assume_init() })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unix_sockets_mapped_to_the_host() {
+        let dir = std::env::temp_dir().join(format!("virtual-net-unix-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut net = LocalNetworking::new();
+        net.map_unix_socket("/run", &dir);
+        let sockets = UnixSockets::new();
+
+        // A guest connecting to a host server
+        let server = tokio::net::UnixListener::bind(dir.join("host.sock")).unwrap();
+        let mut client = net
+            .connect_unix(&sockets, Path::new("/run/host.sock"))
+            .await
+            .unwrap();
+        let (mut accepted, _) = server.accept().await.unwrap();
+        client.try_send(b"hello host").unwrap();
+        let mut buf = [0; 10];
+        accepted.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello host");
+        accepted.write_all(b"hello guest").await.unwrap();
+        assert_eq!(recv(client.as_mut()).await, b"hello guest");
+
+        // A host client connecting to a guest server
+        let mut listener = net
+            .listen_unix(&sockets, Path::new("/run/guest.sock"))
+            .await
+            .unwrap();
+        assert_eq!(
+            listener.addr_local_unix().unwrap(),
+            Some(PathBuf::from("/run/guest.sock"))
+        );
+        let mut host_client = tokio::net::UnixStream::connect(dir.join("guest.sock"))
+            .await
+            .unwrap();
+        host_client.write_all(b"ping").await.unwrap();
+        let (mut accepted, _) = crate::VirtualTcpListenerExt::accept(listener.as_mut())
+            .await
+            .unwrap();
+        assert_eq!(recv(accepted.as_mut()).await, b"ping");
+
+        // The host socket is removed along with the listener
+        drop(listener);
+        assert!(!dir.join("guest.sock").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
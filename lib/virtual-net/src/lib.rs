@@ -16,6 +16,7 @@ pub mod tcp_pair;
 #[cfg(feature = "tokio")]
 #[cfg(test)]
 mod tests;
+pub mod unix;
 
 #[cfg(feature = "remote")]
 pub use client::{RemoteNetworkingClient, RemoteNetworkingClientDriver};
@@ -33,6 +34,8 @@ use std::net::Ipv4Addr;
 use std::net::Ipv6Addr;
 use std::net::Shutdown;
 use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::Context;
@@ -43,6 +46,7 @@ use thiserror::Error;
 use tokio::io::AsyncRead;
 #[cfg(feature = "tokio")]
 use tokio::io::AsyncWrite;
+pub use unix::{UnixHostMappings, UnixSockets};
 
 pub use bytes::Bytes;
 pub use bytes::BytesMut;
@@ -219,6 +223,36 @@ pub trait VirtualNetworking: fmt::Debug + Send + Sync + 'static {
     ) -> Result<Vec<IpAddr>> {
         Err(NetworkError::Unsupported)
     }
+
+    /// Listens for connections on a Unix domain socket bound to a path
+    /// within the `sockets` namespace
+    async fn listen_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualTcpListener + Sync>> {
+        Err(NetworkError::Unsupported)
+    }
+
+    /// Opens a connection to the Unix domain socket bound to a path within
+    /// the `sockets` namespace
+    async fn connect_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualTcpSocket + Sync>> {
+        Err(NetworkError::Unsupported)
+    }
+
+    /// Opens a datagram Unix domain socket, bound to a path within the
+    /// `sockets` namespace unless it is `None`
+    async fn bind_unix_datagram(
+        &self,
+        sockets: &UnixSockets,
+        path: Option<&Path>,
+    ) -> Result<Box<dyn VirtualUnixDatagramSocket + Sync>> {
+        Err(NetworkError::Unsupported)
+    }
 }
 
 pub type DynVirtualNetworking = Arc<dyn VirtualNetworking>;
@@ -234,6 +268,12 @@ pub trait VirtualTcpListener: VirtualIoSource + fmt::Debug + Send + Sync + 'stat
     /// Returns the local address of this TCP listener
     fn addr_local(&self) -> Result<SocketAddr>;
 
+    /// Returns the path this listener is bound to, when it listens on a
+    /// Unix domain socket
+    fn addr_local_unix(&self) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
+
     /// Sets how many network hops the packets are permitted for new connections
     fn set_ttl(&mut self, ttl: u8) -> Result<()>;
 
@@ -287,6 +327,12 @@ pub trait VirtualSocket: VirtualIoSource + fmt::Debug + Send + Sync + 'static {
     /// Returns the local address for this socket
     fn addr_local(&self) -> Result<SocketAddr>;
 
+    /// Returns the path this socket is bound to, when it is a Unix domain
+    /// socket
+    fn addr_local_unix(&self) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
+
     /// Returns the status/state of the socket
     fn status(&self) -> Result<SocketStatus>;
 
@@ -611,6 +657,12 @@ pub trait VirtualTcpSocket: VirtualConnectedSocket + fmt::Debug + Send + Sync +
     /// is conencted to
     fn addr_peer(&self) -> Result<SocketAddr>;
 
+    /// Returns the path the peer socket is bound to, when this is a Unix
+    /// domain socket
+    fn addr_peer_unix(&self) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
+
     /// Shuts down either the READER or WRITER sides of the socket
     /// connection.
     fn shutdown(&mut self, how: Shutdown) -> Result<()>;
@@ -745,6 +797,34 @@ pub trait VirtualUdpSocket:
     fn addr_peer(&self) -> Result<Option<SocketAddr>>;
 }
 
+/// Datagram Unix domain sockets send messages to the sockets bound to
+/// paths, rather than to IP addresses
+pub trait VirtualUnixDatagramSocket: VirtualIoSource + fmt::Debug + Send + Sync + 'static {
+    /// Registers a waker for when datagrams arrive or can be sent. Uses a
+    /// stack machine which means more than one waker can be registered
+    fn set_handler(&mut self, handler: Box<dyn InterestHandler + Send + Sync>) -> Result<()>;
+
+    /// Returns the path this socket is bound to, if any
+    fn addr_local(&self) -> Result<Option<PathBuf>>;
+
+    /// Returns the path of the socket that datagrams are sent to by
+    /// default, if it has been connected
+    fn addr_peer(&self) -> Result<Option<PathBuf>>;
+
+    /// Sets the socket that datagrams are sent to by default
+    fn connect(&mut self, peer: &Path) -> Result<()>;
+
+    /// Sends a datagram to the socket this socket is connected to
+    fn try_send(&mut self, data: &[u8]) -> Result<usize>;
+
+    /// Sends a datagram to the socket bound to a specific path
+    fn try_send_to(&mut self, data: &[u8], peer: &Path) -> Result<usize>;
+
+    /// Receives a datagram, along with the path of the socket that sent it
+    /// if that socket is bound
+    fn try_recv_from(&mut self, buf: &mut [MaybeUninit<u8>]) -> Result<(usize, Option<PathBuf>)>;
+}
+
 #[derive(Debug, Default)]
 pub struct UnsupportedVirtualNetworking {}
 
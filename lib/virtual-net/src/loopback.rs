@@ -1,13 +1,15 @@
 use std::collections::VecDeque;
 use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Mutex;
 use std::task::{Context, Poll, Waker};
 use std::{collections::HashMap, sync::Arc};
 
 use crate::tcp_pair::TcpSocketHalf;
+use crate::unix::UnixSockets;
 use crate::{
     InterestHandler, IpAddr, IpCidr, Ipv4Addr, Ipv6Addr, NetworkError, VirtualIoSource,
-    VirtualNetworking, VirtualTcpListener, VirtualTcpSocket,
+    VirtualNetworking, VirtualTcpListener, VirtualTcpSocket, VirtualUnixDatagramSocket,
 };
 use virtual_mio::InterestType;
 
@@ -133,6 +135,30 @@ impl VirtualNetworking for LoopbackNetworking {
 
         Ok(Box::new(listener))
     }
+
+    async fn listen_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> crate::Result<Box<dyn VirtualTcpListener + Sync>> {
+        Ok(Box::new(sockets.listen(path)?))
+    }
+
+    async fn connect_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> crate::Result<Box<dyn VirtualTcpSocket + Sync>> {
+        Ok(Box::new(sockets.connect(path)?))
+    }
+
+    async fn bind_unix_datagram(
+        &self,
+        sockets: &UnixSockets,
+        path: Option<&Path>,
+    ) -> crate::Result<Box<dyn VirtualUnixDatagramSocket + Sync>> {
+        Ok(Box::new(sockets.bind_datagram(path)?))
+    }
 }
 
 #[derive(Debug)]
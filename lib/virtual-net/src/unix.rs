@@ -0,0 +1,649 @@
+//! Unix domain sockets.
+//!
+//! Unix domain sockets are bound to paths rather than to IP addresses. The
+//! sockets bound within the same [`UnixSockets`] namespace can reach each
+//! other without leaving the process. The namespace belongs to the caller
+//! rather than to the networking, e.g. WASIX has one per root file system,
+//! so that guests which don't share a file system can't reach each other's
+//! sockets. Paths can also be explicitly mapped to host Unix domain sockets
+//! with [`UnixHostMappings`], in which case the networking implementation is
+//! in charge of bridging them.
+
+use std::collections::{HashMap, VecDeque};
+use std::mem::MaybeUninit;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use bytes::Bytes;
+use virtual_mio::InterestType;
+
+use crate::tcp_pair::TcpSocketHalf;
+use crate::{
+    InterestHandler, NetworkError, Result, SocketStatus, VirtualConnectedSocket, VirtualIoSource,
+    VirtualSocket, VirtualTcpListener, VirtualTcpSocket, VirtualUnixDatagramSocket,
+};
+
+const DEFAULT_MAX_BUFFER_SIZE: usize = 1_048_576;
+
+/// The number of datagrams that can be queued on a socket before the
+/// senders have to wait for it to catch up.
+const DEFAULT_MAX_DATAGRAMS: usize = 256;
+
+/// The address reported by the methods that expect an IP address, as Unix
+/// domain sockets don't have one.
+const UNSPECIFIED_ADDR: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+
+#[derive(Debug, Default)]
+struct UnixSocketsState {
+    listeners: HashMap<PathBuf, Weak<Mutex<UnixListenerState>>>,
+    datagrams: HashMap<PathBuf, Weak<Mutex<UnixDatagramState>>>,
+}
+
+impl UnixSocketsState {
+    fn is_bound(&self, path: &Path) -> bool {
+        let listening = self
+            .listeners
+            .get(path)
+            .map_or(false, |listener| listener.strong_count() > 0);
+        let bound = self
+            .datagrams
+            .get(path)
+            .map_or(false, |socket| socket.strong_count() > 0);
+        listening || bound
+    }
+}
+
+/// A namespace of Unix domain sockets, shared by all its clones.
+#[derive(Debug, Clone, Default)]
+pub struct UnixSockets {
+    state: Arc<Mutex<UnixSocketsState>>,
+}
+
+impl UnixSockets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Listens for connections on a path.
+    pub fn listen(&self, path: &Path) -> Result<UnixListener> {
+        let mut state = self.state.lock().unwrap();
+        if state.is_bound(path) {
+            return Err(NetworkError::AddressInUse);
+        }
+        let listener = UnixListener::new(path.to_path_buf());
+        state
+            .listeners
+            .insert(path.to_path_buf(), Arc::downgrade(&listener.state));
+        Ok(listener)
+    }
+
+    /// Connects to the listener bound to a path.
+    pub fn connect(&self, path: &Path) -> Result<UnixStream> {
+        let mut state = self.state.lock().unwrap();
+        let listener = match state.listeners.get(path).and_then(Weak::upgrade) {
+            Some(listener) => listener,
+            None => {
+                state.listeners.remove(path);
+                return Err(NetworkError::ConnectionRefused);
+            }
+        };
+        drop(state);
+
+        let listener = UnixListener {
+            path: path.to_path_buf(),
+            state: listener,
+        };
+        Ok(listener.connect_to(None))
+    }
+
+    /// Opens a datagram socket, bound to a path unless it is `None`.
+    pub fn bind_datagram(&self, path: Option<&Path>) -> Result<UnixDatagram> {
+        let socket = UnixDatagram {
+            sockets: self.clone(),
+            state: Arc::new(Mutex::new(UnixDatagramState::default())),
+            addr_local: path.map(Path::to_path_buf),
+            addr_peer: None,
+        };
+        if let Some(path) = path {
+            let mut state = self.state.lock().unwrap();
+            if state.is_bound(path) {
+                return Err(NetworkError::AddressInUse);
+            }
+            state
+                .datagrams
+                .insert(path.to_path_buf(), Arc::downgrade(&socket.state));
+        }
+        Ok(socket)
+    }
+
+    fn datagram_socket(&self, path: &Path) -> Result<Arc<Mutex<UnixDatagramState>>> {
+        let mut state = self.state.lock().unwrap();
+        match state.datagrams.get(path).and_then(Weak::upgrade) {
+            Some(socket) => Ok(socket),
+            None => {
+                state.datagrams.remove(path);
+                Err(NetworkError::ConnectionRefused)
+            }
+        }
+    }
+}
+
+/// The guest paths mapped to host Unix domain sockets.
+#[derive(Debug, Clone, Default)]
+pub struct UnixHostMappings {
+    mappings: Vec<(PathBuf, PathBuf)>,
+}
+
+impl UnixHostMappings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps a guest path to a host Unix domain socket. When the guest path
+    /// is a directory, all the sockets within it are mapped to the same
+    /// relative paths within the host directory.
+    pub fn map_to_host(&mut self, guest: impl Into<PathBuf>, host: impl Into<PathBuf>) {
+        self.mappings.push((guest.into(), host.into()));
+    }
+
+    /// Returns the host Unix domain socket a guest path is mapped to, if any.
+    pub fn host_path(&self, path: &Path) -> Option<PathBuf> {
+        self.mappings
+            .iter()
+            .rev()
+            .find_map(|(guest, host)| match path.strip_prefix(guest) {
+                Ok(rest) if rest.as_os_str().is_empty() => Some(host.clone()),
+                Ok(rest) => Some(host.join(rest)),
+                Err(_) => None,
+            })
+    }
+}
+
+#[derive(Debug, Default)]
+struct UnixListenerState {
+    handler: Option<Box<dyn InterestHandler + Send + Sync>>,
+    backlog: VecDeque<UnixStream>,
+    wakers: Vec<Waker>,
+}
+
+/// A stream Unix domain socket listening for connections.
+#[derive(Debug, Clone)]
+pub struct UnixListener {
+    path: PathBuf,
+    state: Arc<Mutex<UnixListenerState>>,
+}
+
+impl UnixListener {
+    /// Creates a listener which isn't bound in any namespace, and only
+    /// receives the connections made with [`UnixListener::connect_to()`].
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            state: Default::default(),
+        }
+    }
+
+    /// Queues a new connection to this listener and returns the connecting
+    /// end of it.
+    pub fn connect_to(&self, addr_local: Option<PathBuf>) -> UnixStream {
+        let (accepted, connecting) = UnixStream::pair(Some(self.path.clone()), addr_local);
+
+        let mut state = self.state.lock().unwrap();
+        state.backlog.push_back(accepted);
+        if let Some(handler) = state.handler.as_mut() {
+            handler.push_interest(InterestType::Readable);
+        }
+        state.wakers.drain(..).for_each(|w| w.wake());
+
+        connecting
+    }
+}
+
+impl VirtualIoSource for UnixListener {
+    fn remove_handler(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.handler.take();
+    }
+
+    fn poll_read_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<usize>> {
+        let mut state = self.state.lock().unwrap();
+        if !state.backlog.is_empty() {
+            return Poll::Ready(Ok(state.backlog.len()));
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    fn poll_write_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<usize>> {
+        Poll::Pending
+    }
+}
+
+impl VirtualTcpListener for UnixListener {
+    fn try_accept(&mut self) -> Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
+        let mut state = self.state.lock().unwrap();
+        match state.backlog.pop_front() {
+            Some(next) => Ok((Box::new(next), UNSPECIFIED_ADDR)),
+            None => Err(NetworkError::WouldBlock),
+        }
+    }
+
+    fn set_handler(&mut self, mut handler: Box<dyn InterestHandler + Send + Sync>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.backlog.is_empty() {
+            handler.push_interest(InterestType::Readable);
+        }
+        state.handler.replace(handler);
+        Ok(())
+    }
+
+    fn addr_local(&self) -> Result<SocketAddr> {
+        Ok(UNSPECIFIED_ADDR)
+    }
+
+    fn addr_local_unix(&self) -> Result<Option<PathBuf>> {
+        Ok(Some(self.path.clone()))
+    }
+
+    fn set_ttl(&mut self, _ttl: u8) -> Result<()> {
+        Ok(())
+    }
+
+    fn ttl(&self) -> Result<u8> {
+        Ok(64)
+    }
+}
+
+/// A connected stream Unix domain socket.
+#[derive(Debug)]
+pub struct UnixStream {
+    half: TcpSocketHalf,
+    addr_local: Option<PathBuf>,
+    addr_peer: Option<PathBuf>,
+}
+
+impl UnixStream {
+    /// Creates both ends of a connection between two sockets.
+    pub fn pair(addr1: Option<PathBuf>, addr2: Option<PathBuf>) -> (UnixStream, UnixStream) {
+        let (half1, half2) =
+            TcpSocketHalf::channel(DEFAULT_MAX_BUFFER_SIZE, UNSPECIFIED_ADDR, UNSPECIFIED_ADDR);
+        let stream1 = UnixStream {
+            half: half1,
+            addr_local: addr1.clone(),
+            addr_peer: addr2.clone(),
+        };
+        let stream2 = UnixStream {
+            half: half2,
+            addr_local: addr2,
+            addr_peer: addr1,
+        };
+        (stream1, stream2)
+    }
+
+    /// Returns the in-memory pipe carrying the bytes of this connection.
+    pub fn into_inner(self) -> TcpSocketHalf {
+        self.half
+    }
+}
+
+impl VirtualIoSource for UnixStream {
+    fn remove_handler(&mut self) {
+        self.half.remove_handler()
+    }
+
+    fn poll_read_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<usize>> {
+        self.half.poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<usize>> {
+        self.half.poll_write_ready(cx)
+    }
+}
+
+impl VirtualSocket for UnixStream {
+    fn set_ttl(&mut self, ttl: u32) -> Result<()> {
+        self.half.set_ttl(ttl)
+    }
+
+    fn ttl(&self) -> Result<u32> {
+        self.half.ttl()
+    }
+
+    fn addr_local(&self) -> Result<SocketAddr> {
+        Ok(UNSPECIFIED_ADDR)
+    }
+
+    fn addr_local_unix(&self) -> Result<Option<PathBuf>> {
+        Ok(self.addr_local.clone())
+    }
+
+    fn status(&self) -> Result<SocketStatus> {
+        self.half.status()
+    }
+
+    fn set_handler(&mut self, handler: Box<dyn InterestHandler + Send + Sync>) -> Result<()> {
+        self.half.set_handler(handler)
+    }
+}
+
+impl VirtualConnectedSocket for UnixStream {
+    fn set_linger(&mut self, linger: Option<Duration>) -> Result<()> {
+        self.half.set_linger(linger)
+    }
+
+    fn linger(&self) -> Result<Option<Duration>> {
+        self.half.linger()
+    }
+
+    fn try_send(&mut self, data: &[u8]) -> Result<usize> {
+        self.half.try_send(data)
+    }
+
+    fn try_flush(&mut self) -> Result<()> {
+        self.half.try_flush()
+    }
+
+    fn close(&mut self) -> Result<()> {
+        VirtualConnectedSocket::close(&mut self.half)
+    }
+
+    fn try_recv(&mut self, buf: &mut [MaybeUninit<u8>]) -> Result<usize> {
+        self.half.try_recv(buf)
+    }
+}
+
+impl VirtualTcpSocket for UnixStream {
+    fn set_recv_buf_size(&mut self, size: usize) -> Result<()> {
+        self.half.set_recv_buf_size(size)
+    }
+
+    fn recv_buf_size(&self) -> Result<usize> {
+        self.half.recv_buf_size()
+    }
+
+    fn set_send_buf_size(&mut self, size: usize) -> Result<()> {
+        self.half.set_send_buf_size(size)
+    }
+
+    fn send_buf_size(&self) -> Result<usize> {
+        self.half.send_buf_size()
+    }
+
+    fn set_nodelay(&mut self, _nodelay: bool) -> Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn nodelay(&self) -> Result<bool> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn set_keepalive(&mut self, _keepalive: bool) -> Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn keepalive(&self) -> Result<bool> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn set_dontroute(&mut self, _dontroute: bool) -> Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn dontroute(&self) -> Result<bool> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn addr_peer(&self) -> Result<SocketAddr> {
+        Ok(UNSPECIFIED_ADDR)
+    }
+
+    fn addr_peer_unix(&self) -> Result<Option<PathBuf>> {
+        Ok(self.addr_peer.clone())
+    }
+
+    fn shutdown(&mut self, how: std::net::Shutdown) -> Result<()> {
+        self.half.shutdown(how)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.half.is_closed()
+    }
+}
+
+#[derive(Debug, Default)]
+struct UnixDatagramState {
+    datagrams: VecDeque<(Bytes, Option<PathBuf>)>,
+    handler: Option<Box<dyn InterestHandler + Send + Sync>>,
+    wakers: Vec<Waker>,
+    /// The senders waiting for room in the queue.
+    blocked_senders: Vec<Weak<Mutex<UnixDatagramState>>>,
+}
+
+impl UnixDatagramState {
+    fn notify(&mut self, interest: InterestType) {
+        if let Some(handler) = self.handler.as_mut() {
+            handler.push_interest(interest);
+        }
+        self.wakers.drain(..).for_each(|w| w.wake());
+    }
+}
+
+/// A datagram Unix domain socket.
+#[derive(Debug)]
+pub struct UnixDatagram {
+    sockets: UnixSockets,
+    state: Arc<Mutex<UnixDatagramState>>,
+    addr_local: Option<PathBuf>,
+    addr_peer: Option<PathBuf>,
+}
+
+impl VirtualIoSource for UnixDatagram {
+    fn remove_handler(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.handler.take();
+    }
+
+    fn poll_read_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<usize>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some((datagram, _)) = state.datagrams.front() {
+            return Poll::Ready(Ok(datagram.len()));
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    fn poll_write_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<usize>> {
+        Poll::Ready(Ok(DEFAULT_MAX_BUFFER_SIZE))
+    }
+}
+
+impl VirtualUnixDatagramSocket for UnixDatagram {
+    fn set_handler(&mut self, mut handler: Box<dyn InterestHandler + Send + Sync>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.datagrams.is_empty() {
+            handler.push_interest(InterestType::Readable);
+        }
+        handler.push_interest(InterestType::Writable);
+        state.handler.replace(handler);
+        Ok(())
+    }
+
+    fn addr_local(&self) -> Result<Option<PathBuf>> {
+        Ok(self.addr_local.clone())
+    }
+
+    fn addr_peer(&self) -> Result<Option<PathBuf>> {
+        Ok(self.addr_peer.clone())
+    }
+
+    fn connect(&mut self, peer: &Path) -> Result<()> {
+        self.sockets.datagram_socket(peer)?;
+        self.addr_peer = Some(peer.to_path_buf());
+        Ok(())
+    }
+
+    fn try_send(&mut self, data: &[u8]) -> Result<usize> {
+        let peer = self.addr_peer.clone().ok_or(NetworkError::NotConnected)?;
+        self.try_send_to(data, &peer)
+    }
+
+    fn try_send_to(&mut self, data: &[u8], peer: &Path) -> Result<usize> {
+        let receiver = self.sockets.datagram_socket(peer)?;
+        let mut receiver = receiver.lock().unwrap();
+        if receiver.datagrams.len() >= DEFAULT_MAX_DATAGRAMS {
+            let sender = Arc::downgrade(&self.state);
+            if !receiver.blocked_senders.iter().any(|s| s.ptr_eq(&sender)) {
+                receiver.blocked_senders.push(sender);
+            }
+            return Err(NetworkError::WouldBlock);
+        }
+        receiver
+            .datagrams
+            .push_back((Bytes::copy_from_slice(data), self.addr_local.clone()));
+        receiver.notify(InterestType::Readable);
+        Ok(data.len())
+    }
+
+    fn try_recv_from(&mut self, buf: &mut [MaybeUninit<u8>]) -> Result<(usize, Option<PathBuf>)> {
+        let mut state = self.state.lock().unwrap();
+        let (datagram, sender) = state
+            .datagrams
+            .pop_front()
+            .ok_or(NetworkError::WouldBlock)?;
+        let blocked_senders = std::mem::take(&mut state.blocked_senders);
+        drop(state);
+
+        // Like for other datagram sockets, the end of a datagram which
+        // doesn't fit in the buffer is discarded.
+        let amt = datagram.len().min(buf.len());
+        for (dst, src) in buf.iter_mut().zip(&datagram[..amt]) {
+            dst.write(*src);
+        }
+
+        for sender in blocked_senders.iter().filter_map(Weak::upgrade) {
+            sender.lock().unwrap().notify(InterestType::Writable);
+        }
+        Ok((amt, sender))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recv<S: VirtualConnectedSocket + ?Sized>(socket: &mut S) -> Vec<u8> {
+        let mut buf = [MaybeUninit::new(0); 64];
+        let amt = socket.try_recv(&mut buf).unwrap();
+        buf[..amt]
+            .iter()
+            .map(|b| unsafe { b.assume_init() })
+            .collect()
+    }
+
+    #[test]
+    fn stream_sockets_connect_by_path() {
+        let sockets = UnixSockets::new();
+        let path = Path::new("/run/app.sock");
+        assert_eq!(
+            sockets.connect(path).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+
+        let mut listener = sockets.listen(path).unwrap();
+        assert_eq!(
+            sockets.listen(path).unwrap_err(),
+            NetworkError::AddressInUse
+        );
+        let mut client = sockets.connect(path).unwrap();
+        let (mut server, _) = listener.try_accept().unwrap();
+        assert_eq!(client.addr_peer_unix().unwrap().as_deref(), Some(path));
+        assert_eq!(server.addr_local_unix().unwrap().as_deref(), Some(path));
+
+        client.try_send(b"ping").unwrap();
+        assert_eq!(recv(server.as_mut()), b"ping");
+        server.try_send(b"pong").unwrap();
+        assert_eq!(recv(&mut client), b"pong");
+
+        // The path can be bound again once the listener is closed.
+        drop(listener);
+        assert_eq!(
+            sockets.connect(path).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+        sockets.listen(path).unwrap();
+    }
+
+    #[test]
+    fn datagram_sockets_send_to_paths() {
+        let sockets = UnixSockets::new();
+        let path = Path::new("/dev/log");
+        let mut server = sockets.bind_datagram(Some(path)).unwrap();
+        let mut client = sockets
+            .bind_datagram(Some(Path::new("/tmp/client")))
+            .unwrap();
+        let mut unbound = sockets.bind_datagram(None).unwrap();
+
+        assert_eq!(
+            unbound.try_send(b"nowhere").unwrap_err(),
+            NetworkError::NotConnected
+        );
+        unbound.connect(path).unwrap();
+        unbound.try_send(b"first").unwrap();
+        client.try_send_to(b"second", path).unwrap();
+
+        let mut buf = [MaybeUninit::new(0); 64];
+        assert_eq!(server.try_recv_from(&mut buf).unwrap(), (5, None));
+        assert_eq!(
+            server.try_recv_from(&mut buf).unwrap(),
+            (6, Some(PathBuf::from("/tmp/client")))
+        );
+        assert_eq!(
+            server.try_recv_from(&mut buf).unwrap_err(),
+            NetworkError::WouldBlock
+        );
+    }
+
+    #[test]
+    fn namespaces_are_separate() {
+        let sockets = UnixSockets::new();
+        let other = UnixSockets::new();
+        let path = Path::new("/run/app.sock");
+        let _listener = sockets.listen(path).unwrap();
+        let _datagram = sockets.bind_datagram(Some(Path::new("/dev/log"))).unwrap();
+
+        assert_eq!(
+            other.connect(path).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+        other.listen(path).unwrap();
+        let mut unbound = other.bind_datagram(None).unwrap();
+        assert_eq!(
+            unbound.connect(Path::new("/dev/log")).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+    }
+
+    #[test]
+    fn host_mappings() {
+        let mut mappings = UnixHostMappings::new();
+        mappings.map_to_host("/var/run/postgresql", "/tmp/pg");
+        mappings.map_to_host("/run/redis.sock", "/tmp/redis.sock");
+
+        assert_eq!(
+            mappings.host_path(Path::new("/var/run/postgresql/.s.PGSQL.5432")),
+            Some(PathBuf::from("/tmp/pg/.s.PGSQL.5432"))
+        );
+        assert_eq!(
+            mappings.host_path(Path::new("/run/redis.sock")),
+            Some(PathBuf::from("/tmp/redis.sock"))
+        );
+        assert_eq!(mappings.host_path(Path::new("/run/other.sock")), None);
+    }
+}
@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::trap::{Trap, TrapCode};
+
+/// The counter used by stores that are not attached to an engine epoch.
+///
+/// It is never incremented, so the default deadline is never reached.
+static NO_EPOCH: AtomicU64 = AtomicU64::new(0);
+
+/// A callback invoked when the epoch deadline of a store is reached.
+///
+/// On success it returns the number of epochs by which the deadline is
+/// extended. An error is raised as a trap in the running WebAssembly code.
+pub type EpochDeadlineCallback =
+    Box<dyn FnMut() -> Result<u64, Box<dyn Error + Send + Sync>> + Send + Sync>;
+
+/// The epoch deadline of a store.
+///
+/// There is a single instance of this struct per store. When epoch
+/// interruption is enabled in the compiler, compiled code reads the
+/// current epoch through `counter` at function entries and loop headers,
+/// and calls the `epoch_deadline_reached` builtin once it is greater or
+/// equal to `deadline`.
+#[repr(C)]
+pub struct VMEpochState {
+    /// The epoch at which the deadline is reached.
+    deadline: u64,
+    /// Pointer to the epoch counter of the engine.
+    counter: *const AtomicU64,
+    /// Keeps the epoch counter alive.
+    engine_epoch: Option<Arc<AtomicU64>>,
+    /// Called when the deadline is reached. If there is none, a trap is raised.
+    callback: Option<EpochDeadlineCallback>,
+}
+
+/// # Safety
+/// The counter pointer is either a static or owned by `engine_epoch`,
+/// and only read atomically.
+unsafe impl Send for VMEpochState {}
+/// # Safety
+/// The counter pointer is either a static or owned by `engine_epoch`,
+/// and only read atomically.
+unsafe impl Sync for VMEpochState {}
+
+impl Default for VMEpochState {
+    fn default() -> Self {
+        Self {
+            deadline: u64::MAX,
+            counter: &NO_EPOCH,
+            engine_epoch: None,
+            callback: None,
+        }
+    }
+}
+
+impl fmt::Debug for VMEpochState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VMEpochState")
+            .field("deadline", &self.deadline)
+            .field("current_epoch", &self.current_epoch())
+            .field("has_callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+impl VMEpochState {
+    /// Attaches the store to the given epoch counter.
+    pub fn set_counter(&mut self, counter: Arc<AtomicU64>) {
+        self.counter = Arc::as_ptr(&counter);
+        self.engine_epoch = Some(counter);
+    }
+
+    /// Returns the current epoch of the engine.
+    pub fn current_epoch(&self) -> u64 {
+        unsafe { (*self.counter).load(Ordering::Relaxed) }
+    }
+
+    /// Returns the epoch at which the deadline is reached.
+    pub fn deadline(&self) -> u64 {
+        self.deadline
+    }
+
+    /// Sets the deadline to `delta` epochs after the current one.
+    pub fn set_deadline(&mut self, delta: u64) {
+        self.deadline = self.current_epoch().saturating_add(delta);
+    }
+
+    /// Sets the callback invoked when the deadline is reached.
+    ///
+    /// With no callback, reaching the deadline raises an interrupt trap.
+    pub fn set_callback(&mut self, callback: Option<EpochDeadlineCallback>) {
+        self.callback = callback;
+    }
+
+    /// Handles a reached deadline, either by calling the callback or by
+    /// returning an interrupt trap.
+    ///
+    /// A panic of the callback is resumed once the callback is put back.
+    ///
+    /// # Safety
+    /// `this` must be a valid pointer. The callback may access the store
+    /// that owns the state, so no reference to it is held while it runs.
+    pub(crate) unsafe fn deadline_reached(this: *mut Self) -> Result<(), Trap> {
+        let Some(mut callback) = (*this).callback.take() else {
+            return Err(Trap::lib(TrapCode::Interrupt));
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(&mut callback));
+        // The callback may have installed a new callback in the meantime.
+        if (*this).callback.is_none() {
+            (*this).callback = Some(callback);
+        }
+        let result = result.unwrap_or_else(|panic| panic::resume_unwind(panic));
+        match result {
+            Ok(delta) => {
+                (*this).set_deadline(delta);
+                Ok(())
+            }
+            Err(err) => Err(Trap::user(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_vmepoch_state {
+    use super::VMEpochState;
+    use memoffset::offset_of;
+    use std::mem::size_of;
+    use wasmer_types::ModuleInfo;
+    use wasmer_types::VMOffsets;
+
+    #[test]
+    fn check_vmepoch_state_offsets() {
+        let module = ModuleInfo::new();
+        let offsets = VMOffsets::new(size_of::<*mut u8>() as u8, &module);
+        assert_eq!(
+            offset_of!(VMEpochState, deadline),
+            usize::from(offsets.vmepoch_state_deadline())
+        );
+        assert_eq!(
+            offset_of!(VMEpochState, counter),
+            usize::from(offsets.vmepoch_state_counter())
+        );
+    }
+}
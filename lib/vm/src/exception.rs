@@ -0,0 +1,171 @@
+use wasmer_types::{RawValue, TagType};
+
+use crate::store::InternalStoreHandle;
+
+/// A tag, as defined by the exception handling proposal.
+///
+/// Tags are compared by identity: two tags with the same type are still
+/// different tags, unless one is an import of the other.
+#[derive(Debug)]
+pub struct VMTag {
+    ty: TagType,
+}
+
+impl VMTag {
+    /// Creates a new tag carrying values of the given type.
+    pub fn new(ty: TagType) -> Self {
+        Self { ty }
+    }
+
+    /// Returns the type of this tag.
+    pub fn ty(&self) -> &TagType {
+        &self.ty
+    }
+}
+
+/// An exception thrown by WebAssembly code.
+#[derive(Debug)]
+pub struct VMException {
+    tag: InternalStoreHandle<VMTag>,
+    pub(crate) payload: Box<[RawValue]>,
+    /// Whether its `exnref` was handed out to Wasm code or to the host,
+    /// meaning it may still be held somewhere.
+    pub(crate) exposed: bool,
+    /// Whether its `exnref` was handed out to the host, which may hold it
+    /// for as long as the store lives.
+    pub(crate) held_by_host: bool,
+}
+
+impl VMException {
+    /// Creates a new exception for the given tag, carrying the given values.
+    pub fn new(tag: InternalStoreHandle<VMTag>, payload: Box<[RawValue]>) -> Self {
+        Self {
+            tag,
+            payload,
+            exposed: false,
+            held_by_host: false,
+        }
+    }
+
+    /// Returns the tag this exception was thrown with.
+    pub fn tag(&self) -> InternalStoreHandle<VMTag> {
+        self.tag
+    }
+
+    /// Returns the values carried by this exception.
+    pub fn payload(&self) -> &[RawValue] {
+        &self.payload
+    }
+}
+
+/// Represents a reference to an exception within WebAssembly (an `exnref`).
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VMExceptionRef(pub InternalStoreHandle<VMException>);
+
+impl VMExceptionRef {
+    /// Converts the `VMExceptionRef` into a `RawValue`.
+    pub fn into_raw(self) -> RawValue {
+        RawValue {
+            externref: self.0.index(),
+        }
+    }
+
+    /// Extracts a `VMExceptionRef` from a `RawValue`.
+    ///
+    /// # Safety
+    /// `raw` must be a valid `VMExceptionRef` instance.
+    pub unsafe fn from_raw(raw: RawValue) -> Option<Self> {
+        InternalStoreHandle::from_index(raw.externref).map(Self)
+    }
+}
+
+/// The exception currently being propagated through the WebAssembly
+/// stack of a store, if any.
+///
+/// There is a single instance of this struct per store. Compiled code
+/// accesses it through a pointer stored in the `VMContext` of every
+/// instance: after each call it checks whether `exnref` is set, and if so
+/// it either jumps to a matching handler or returns to its own caller.
+#[derive(Debug)]
+#[repr(C)]
+pub struct VMExceptionState {
+    /// The raw `VMExceptionRef` being thrown, or 0 if there is none.
+    exnref: usize,
+    /// Pointer to the values carried by the exception.
+    payload: *const RawValue,
+    /// The store index of the tag the exception was thrown with.
+    tag: u32,
+}
+
+/// # Safety
+/// The payload pointer is only dereferenced by code running on the thread
+/// that currently owns the store.
+unsafe impl Send for VMExceptionState {}
+/// # Safety
+/// The payload pointer is only dereferenced by code running on the thread
+/// that currently owns the store.
+unsafe impl Sync for VMExceptionState {}
+
+impl Default for VMExceptionState {
+    fn default() -> Self {
+        Self {
+            exnref: 0,
+            payload: std::ptr::null(),
+            tag: 0,
+        }
+    }
+}
+
+impl VMExceptionState {
+    /// Starts propagating the given exception.
+    pub(crate) fn raise(&mut self, exnref: VMExceptionRef, exception: &VMException) {
+        self.exnref = exnref.0.index();
+        self.payload = exception.payload.as_ptr();
+        self.tag = exception.tag.index() as u32;
+    }
+
+    /// Stops propagating the current exception, returning it if there was one.
+    pub fn take(&mut self) -> Option<VMExceptionRef> {
+        let exnref = InternalStoreHandle::from_index(self.exnref).map(VMExceptionRef);
+        *self = Self::default();
+        exnref
+    }
+
+    /// Returns the exception currently being propagated, if any.
+    pub fn exnref(&self) -> Option<VMExceptionRef> {
+        InternalStoreHandle::from_index(self.exnref).map(VMExceptionRef)
+    }
+
+    /// Returns true if an exception is currently being propagated.
+    pub fn is_pending(&self) -> bool {
+        self.exnref != 0
+    }
+}
+
+#[cfg(test)]
+mod test_vmexception_state {
+    use super::VMExceptionState;
+    use memoffset::offset_of;
+    use std::mem::size_of;
+    use wasmer_types::ModuleInfo;
+    use wasmer_types::VMOffsets;
+
+    #[test]
+    fn check_vmexception_state_offsets() {
+        let module = ModuleInfo::new();
+        let offsets = VMOffsets::new(size_of::<*mut u8>() as u8, &module);
+        assert_eq!(
+            offset_of!(VMExceptionState, exnref),
+            usize::from(offsets.vmexception_state_exnref())
+        );
+        assert_eq!(
+            offset_of!(VMExceptionState, payload),
+            usize::from(offsets.vmexception_state_payload())
+        );
+        assert_eq!(
+            offset_of!(VMExceptionState, tag),
+            usize::from(offsets.vmexception_state_tag())
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Async fibers: host code running on a separate stack, which can be
+//! suspended while it waits on a future and resumed by the task polling it.
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::pin;
+use std::ptr::NonNull;
+use std::task::{Context, Poll};
+
+use corosensei::stack::DefaultStack;
+use corosensei::{Coroutine, CoroutineResult, Yielder};
+
+use crate::trap::{lazy_per_thread_init, TlsState, Trap};
+
+/// The default stack size of async fibers.
+///
+/// Only host code runs on this stack: Wasm code still runs on its own stack.
+pub const DEFAULT_ASYNC_STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// The value a fiber is resumed with: the context of the task polling it,
+/// or `None` if the fiber is cancelled.
+type Resume = Option<NonNull<Context<'static>>>;
+
+/// The state of the fiber running on the current thread.
+struct FiberState {
+    yielder: NonNull<Yielder<Resume, ()>>,
+    cx: Resume,
+}
+
+thread_local! {
+    static CURRENT_FIBER: Cell<Option<NonNull<FiberState>>> = const { Cell::new(None) };
+}
+
+/// An error returned by [`block_on`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FiberError {
+    /// The caller is not running on an async fiber, i.e. the Wasm code was
+    /// not called with one of the `call_async` methods.
+    NotOnFiber,
+    /// The async call running the fiber was dropped before completing.
+    Cancelled,
+}
+
+impl fmt::Display for FiberError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotOnFiber => write!(f, "async host function called outside of an async call"),
+            Self::Cancelled => write!(f, "async call cancelled"),
+        }
+    }
+}
+
+impl Error for FiberError {}
+
+/// The thread-local state that follows a fiber across threads.
+#[derive(Default)]
+struct FiberTls {
+    trap: TlsState,
+    fiber: Option<NonNull<FiberState>>,
+}
+
+impl FiberTls {
+    fn swap(&mut self) {
+        self.trap.swap();
+        self.fiber = CURRENT_FIBER.with(|cell| cell.replace(self.fiber));
+    }
+}
+
+/// A computation running on its own stack, which can be suspended while it
+/// waits on a future with [`block_on`].
+///
+/// The fiber is driven by calling [`AsyncFiber::resume`] from the `poll`
+/// method of a future. It may be resumed on a different thread than the one
+/// it was suspended on.
+pub struct AsyncFiber<R: 'static> {
+    start: Option<Box<dyn FnOnce() -> R + Send>>,
+    coro: Option<Coroutine<Resume, (), R, DefaultStack>>,
+    stack_size: usize,
+    tls: FiberTls,
+}
+
+/// # Safety
+/// The function running on the fiber is `Send`, as are the futures it
+/// waits on with [`block_on`], and the thread-local state of the fiber is
+/// swapped in and out on each resume.
+unsafe impl<R: Send + 'static> Send for AsyncFiber<R> {}
+
+impl<R: 'static> AsyncFiber<R> {
+    /// Creates a fiber that runs `f` on a stack of `stack_size` bytes.
+    ///
+    /// Nothing runs, and the stack isn't allocated, until the fiber is first
+    /// resumed.
+    pub fn new<F>(stack_size: usize, f: F) -> Self
+    where
+        F: FnOnce() -> R + Send + 'static,
+    {
+        Self {
+            start: Some(Box::new(f)),
+            coro: None,
+            stack_size,
+            tls: FiberTls::default(),
+        }
+    }
+
+    /// Resumes the fiber until it either completes or waits on a pending
+    /// future, in which case the task of `cx` is woken once it can make
+    /// progress.
+    ///
+    /// Returns a trap if the stack of the fiber can't be allocated.
+    ///
+    /// # Panics
+    /// Panics if the fiber has already completed.
+    pub fn resume(&mut self, cx: &mut Context<'_>) -> Poll<Result<R, Trap>> {
+        // The fiber may be resumed on a thread that never ran Wasm code.
+        if let Err(trap) = lazy_per_thread_init() {
+            return Poll::Ready(Err(trap));
+        }
+        if let Some(f) = self.start.take() {
+            // Allocating a stack involves several system calls, so stacks
+            // are reused as for the Wasm stacks.
+            let stack = match pool::take(self.stack_size) {
+                Ok(stack) => stack,
+                Err(err) => return Poll::Ready(Err(Trap::user(Box::new(err)))),
+            };
+            self.coro = Some(Coroutine::with_stack(stack, move |yielder, cx: Resume| {
+                let mut state = FiberState {
+                    yielder: NonNull::from(yielder),
+                    cx,
+                };
+                CURRENT_FIBER.with(|cell| cell.set(Some(NonNull::from(&mut state))));
+                f()
+            }));
+        }
+        let cx = NonNull::from(cx).cast::<Context<'static>>();
+        match self.switch(Some(cx)) {
+            Some(result) => Poll::Ready(Ok(result)),
+            None => Poll::Pending,
+        }
+    }
+
+    fn switch(&mut self, resume: Resume) -> Option<R> {
+        let coro = self.coro.as_mut().expect("fiber already completed");
+        self.tls.swap();
+        let result = {
+            let _restore = scopeguard::guard(&mut self.tls, |tls| tls.swap());
+            coro.resume(resume)
+        };
+        match result {
+            CoroutineResult::Yield(()) => None,
+            CoroutineResult::Return(result) => {
+                if let Some(coro) = self.coro.take() {
+                    pool::put(self.stack_size, coro.into_stack());
+                }
+                Some(result)
+            }
+        }
+    }
+}
+
+impl<R: 'static> Drop for AsyncFiber<R> {
+    fn drop(&mut self) {
+        // Forcibly unwinding the fiber would also unwind through Wasm frames.
+        // Instead, it is resumed without a context until it completes:
+        // `block_on` reports the cancellation, which turns into a trap.
+        while self
+            .coro
+            .as_ref()
+            .is_some_and(|coro| coro.started() && !coro.done())
+        {
+            self.switch(None);
+        }
+    }
+}
+
+/// Runs `future` to completion on the current async fiber.
+///
+/// Whenever the future is pending, the fiber is suspended and the task
+/// driving it is woken up once the future can make progress.
+///
+/// The future must be `Send`, since the fiber may be resumed on another
+/// thread while the future is pending.
+///
+/// Returns an error if the caller is not running on an [`AsyncFiber`], or
+/// if the fiber was cancelled while waiting.
+pub fn block_on<F: Future + Send>(future: F) -> Result<F::Output, FiberError> {
+    let state = CURRENT_FIBER
+        .with(|cell| cell.get())
+        .ok_or(FiberError::NotOnFiber)?;
+    let mut future = pin!(future);
+    loop {
+        // Safety: the state lives on the stack of the fiber we are running
+        // on, and the context is valid for the duration of the resume.
+        unsafe {
+            let mut cx = (*state.as_ptr()).cx.ok_or(FiberError::Cancelled)?;
+            if let Poll::Ready(output) = future.as_mut().poll(cx.as_mut()) {
+                return Ok(output);
+            }
+            let yielder = (*state.as_ptr()).yielder;
+            (*state.as_ptr()).cx = yielder.as_ref().suspend(());
+        }
+    }
+}
+
+mod pool {
+    use corosensei::stack::DefaultStack;
+
+    lazy_static::lazy_static! {
+        static ref STACK_POOL: crossbeam_queue::SegQueue<(usize, DefaultStack)> =
+            crossbeam_queue::SegQueue::new();
+    }
+
+    pub(super) fn take(size: usize) -> std::io::Result<DefaultStack> {
+        // Stacks of other sizes are dropped: the size is rarely changed.
+        while let Some((stack_size, stack)) = STACK_POOL.pop() {
+            if stack_size == size {
+                return Ok(stack);
+            }
+        }
+        DefaultStack::new(size)
+    }
+
+    pub(super) fn put(size: usize, stack: DefaultStack) {
+        STACK_POOL.push((size, stack));
+    }
+}
@@ -8,6 +8,8 @@
 
 mod allocator;
 
+use crate::epoch::VMEpochState;
+use crate::exception::{VMException, VMExceptionRef, VMExceptionState, VMTag};
 use crate::export::VMExtern;
 use crate::imports::Imports;
 use crate::store::{InternalStoreHandle, StoreObjects};
@@ -38,7 +40,8 @@ use wasmer_types::entity::{packed_option::ReservedValue, BoxedSlice, EntityRef,
 use wasmer_types::{
     DataIndex, DataInitializer, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit,
     LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryError,
-    MemoryIndex, ModuleInfo, Pages, SignatureIndex, TableIndex, TableInitializer, VMOffsets,
+    MemoryIndex, ModuleInfo, Pages, RawValue, SignatureIndex, TableIndex, TableInitializer,
+    TagIndex, VMOffsets,
 };
 
 /// A WebAssembly instance.
@@ -68,6 +71,9 @@ pub(crate) struct Instance {
     /// WebAssembly global data.
     globals: BoxedSlice<LocalGlobalIndex, InternalStoreHandle<VMGlobal>>,
 
+    /// WebAssembly tags (imported and local).
+    tags: BoxedSlice<TagIndex, InternalStoreHandle<VMTag>>,
+
     /// Pointers to functions in executable memory.
     functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
 
@@ -313,6 +319,21 @@ impl Instance {
         unsafe { self.vmctx_plus_offset(self.offsets.vmctx_builtin_functions_begin()) }
     }
 
+    /// Return a pointer to the store indices of the tags.
+    fn tags_ptr(&self) -> *mut u32 {
+        unsafe { self.vmctx_plus_offset(self.offsets.vmctx_tags_begin()) }
+    }
+
+    /// Return a pointer to the slot holding the store's `VMExceptionState`.
+    fn exception_state_ptr(&self) -> *mut *mut VMExceptionState {
+        unsafe { self.vmctx_plus_offset(self.offsets.vmctx_exception_state()) }
+    }
+
+    /// Return a pointer to the slot holding the store's `VMEpochState`.
+    fn epoch_state_ptr(&self) -> *mut *mut VMEpochState {
+        unsafe { self.vmctx_plus_offset(self.offsets.vmctx_epoch_state()) }
+    }
+
     /// Return a reference to the vmctx used by compiled wasm code.
     fn vmctx(&self) -> &VMContext {
         &self.vmctx
@@ -357,14 +378,61 @@ impl Instance {
 
         // Make the call.
         unsafe {
-            catch_traps(trap_handler, config, move || {
+            (*self.context).enter_wasm();
+            let result = catch_traps(trap_handler, config, move || {
                 mem::transmute::<*const VMFunctionBody, unsafe extern "C" fn(VMFunctionContext)>(
                     callee_address,
                 )(callee_vmctx)
-            })
+            });
+            (*self.context).leave_wasm();
+            result?;
+            // An exception escaping the start function aborts the instantiation.
+            match (*self.context).take_exception() {
+                Some(exnref) => Err(Trap::uncaught_exception(exnref)),
+                None => Ok(()),
+            }
         }
     }
 
+    /// Throw a new exception with the given tag, carrying the values
+    /// stored at `values`.
+    ///
+    /// # Safety
+    ///
+    /// `values` must point to as many values as the tag carries.
+    pub(crate) unsafe fn throw(&mut self, tag_index: TagIndex, values: *const RawValue) {
+        let tag = self.tags[tag_index];
+        let len = self.module.tag_type(tag_index).params().len();
+        let payload = if len == 0 {
+            Box::default()
+        } else {
+            slice::from_raw_parts(values, len).into()
+        };
+        let context = self.context_mut();
+        let exnref = context.new_exception(VMException::new(tag, payload));
+        context.raise_exception(exnref);
+    }
+
+    /// Throw an existing exception again.
+    pub(crate) fn throw_ref(&mut self, exnref: VMExceptionRef) {
+        self.context_mut().raise_exception(exnref);
+    }
+
+    /// Release an exception caught without keeping its `exnref`.
+    pub(crate) fn drop_exception(&mut self, exnref: VMExceptionRef) {
+        self.context_mut().drop_exception(exnref);
+    }
+
+    /// Mark an exception whose `exnref` was handed out to Wasm code.
+    pub(crate) fn expose_exception(&mut self, exnref: VMExceptionRef) {
+        self.context_mut().expose_exception(exnref);
+    }
+
+    /// Handle a reached epoch deadline.
+    pub(crate) unsafe fn epoch_deadline_reached(&mut self) -> Result<(), Trap> {
+        VMEpochState::deadline_reached(*self.epoch_state_ptr())
+    }
+
     /// Return the offset from the vmctx pointer to its containing `Instance`.
     #[inline]
     pub(crate) fn vmctx_offset() -> isize {
@@ -397,13 +465,13 @@ impl Instance {
 
     /// Grow memory by the specified amount of pages.
     ///
-    /// Returns `None` if memory can't be grown by the specified amount
-    /// of pages.
+    /// Returns an error if memory can't be grown by the specified amount
+    /// of pages, and a trap if the resource limiter of the store raised one.
     pub(crate) fn memory_grow<IntoPages>(
         &mut self,
         memory_index: LocalMemoryIndex,
         delta: IntoPages,
-    ) -> Result<Pages, MemoryError>
+    ) -> Result<Result<Pages, MemoryError>, Trap>
     where
         IntoPages: Into<Pages>,
     {
@@ -411,13 +479,13 @@ impl Instance {
             .memories
             .get(memory_index)
             .unwrap_or_else(|| panic!("no memory for index {}", memory_index.index()));
-        mem.get_mut(self.context_mut()).grow(delta.into())
+        self.context_mut().grow_memory(mem, delta.into())
     }
 
     /// Grow imported memory by the specified amount of pages.
     ///
-    /// Returns `None` if memory can't be grown by the specified amount
-    /// of pages.
+    /// Returns an error if memory can't be grown by the specified amount
+    /// of pages, and a trap if the resource limiter of the store raised one.
     ///
     /// # Safety
     /// This and `imported_memory_size` are currently unsafe because they
@@ -426,13 +494,13 @@ impl Instance {
         &mut self,
         memory_index: MemoryIndex,
         delta: IntoPages,
-    ) -> Result<Pages, MemoryError>
+    ) -> Result<Result<Pages, MemoryError>, Trap>
     where
         IntoPages: Into<Pages>,
     {
         let import = self.imported_memory(memory_index);
         let mem = import.handle;
-        mem.get_mut(self.context_mut()).grow(delta.into())
+        self.context_mut().grow_memory(mem, delta.into())
     }
 
     /// Returns the number of allocated wasm pages.
@@ -477,18 +545,19 @@ impl Instance {
     /// Grow table by the specified amount of elements.
     ///
     /// Returns `None` if table can't be grown by the specified amount
-    /// of elements.
+    /// of elements, and a trap if the resource limiter of the store
+    /// raised one.
     pub(crate) fn table_grow(
         &mut self,
         table_index: LocalTableIndex,
         delta: u32,
         init_value: TableElement,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, Trap> {
         let table = *self
             .tables
             .get(table_index)
             .unwrap_or_else(|| panic!("no table for index {}", table_index.index()));
-        table.get_mut(self.context_mut()).grow(delta, init_value)
+        self.context_mut().grow_table(table, delta, init_value)
     }
 
     /// Grow table by the specified amount of elements.
@@ -500,10 +569,10 @@ impl Instance {
         table_index: TableIndex,
         delta: u32,
         init_value: TableElement,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, Trap> {
         let import = self.imported_table(table_index);
         let table = import.handle;
-        table.get_mut(self.context_mut()).grow(delta, init_value)
+        self.context_mut().grow_table(table, delta, init_value)
     }
 
     /// Get table element by index.
@@ -1018,6 +1087,7 @@ impl VMInstance {
         module: Arc<ModuleInfo>,
         context: &mut StoreObjects,
         finished_functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
+        finished_function_tail_entries: BoxedSlice<LocalFunctionIndex, Option<FunctionBodyPtr>>,
         finished_function_call_trampolines: BoxedSlice<SignatureIndex, VMTrampoline>,
         finished_memories: BoxedSlice<LocalMemoryIndex, InternalStoreHandle<VMMemory>>,
         finished_tables: BoxedSlice<LocalTableIndex, InternalStoreHandle<VMTable>>,
@@ -1030,6 +1100,23 @@ impl VMInstance {
             .map(|m| m.get(context).vmglobal())
             .collect::<PrimaryMap<LocalGlobalIndex, _>>()
             .into_boxed_slice();
+        let tags = imports
+            .tags
+            .values()
+            .copied()
+            .chain(
+                module
+                    .tags
+                    .keys()
+                    .skip(module.num_imported_tags)
+                    .map(|index| {
+                        InternalStoreHandle::new(context, VMTag::new(module.tag_type(index)))
+                    }),
+            )
+            .collect::<PrimaryMap<TagIndex, _>>()
+            .into_boxed_slice();
+        let exception_state = context.exception_state_ptr();
+        let epoch_state = context.epoch_state_ptr();
         let passive_data = RefCell::new(
             module
                 .passive_data
@@ -1052,6 +1139,7 @@ impl VMInstance {
                 memories: finished_memories,
                 tables: finished_tables,
                 globals: finished_globals,
+                tags,
                 functions: finished_functions,
                 function_call_trampolines: finished_function_call_trampolines,
                 passive_elements: Default::default(),
@@ -1072,6 +1160,7 @@ impl VMInstance {
                     context,
                     &imports,
                     &instance.functions,
+                    &finished_function_tail_entries,
                     &vmshared_signatures,
                     &instance.function_call_trampolines,
                     vmctx_ptr,
@@ -1119,6 +1208,11 @@ impl VMInstance {
             instance.builtin_functions_ptr(),
             VMBuiltinFunctionsArray::initialized(),
         );
+        for (index, tag) in instance.tags.iter() {
+            ptr::write(instance.tags_ptr().add(index.index()), tag.index() as u32);
+        }
+        ptr::write(instance.exception_state_ptr(), exception_state);
+        ptr::write(instance.epoch_state_ptr(), epoch_state);
 
         // Perform infallible initialization in this constructor, while fallible
         // initialization is deferred to the `initialize` method.
@@ -1253,6 +1347,7 @@ impl VMInstance {
                 };
                 VMExtern::Global(handle)
             }
+            ExportIndex::Tag(index) => VMExtern::Tag(instance.tags[index]),
         }
     }
 
@@ -1282,7 +1377,9 @@ impl VMInstance {
     where
         IntoPages: Into<Pages>,
     {
-        self.instance_mut().memory_grow(memory_index, delta)
+        self.instance_mut()
+            .memory_grow(memory_index, delta)
+            .unwrap_or_else(|trap| Err(MemoryError::Generic(trap.to_string())))
     }
 
     /// Return the table index for the given `VMTableDefinition` in this instance.
@@ -1302,6 +1399,7 @@ impl VMInstance {
     ) -> Option<u32> {
         self.instance_mut()
             .table_grow(table_index, delta, init_value)
+            .unwrap_or(None)
     }
 
     /// Get table element reference.
@@ -1505,11 +1603,13 @@ fn initialize_globals(instance: &Instance) {
 
 /// Eagerly builds all the `VMFuncRef`s for imported and local functions so that all
 /// future funcref operations are just looking up this data.
+#[allow(clippy::too_many_arguments)]
 fn build_funcrefs(
     module_info: &ModuleInfo,
     ctx: &StoreObjects,
     imports: &Imports,
     finished_functions: &BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
+    finished_function_tail_entries: &BoxedSlice<LocalFunctionIndex, Option<FunctionBodyPtr>>,
     vmshared_signatures: &BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
     function_call_trampolines: &BoxedSlice<SignatureIndex, VMTrampoline>,
     vmctx_ptr: *mut VMContext,
@@ -1532,11 +1632,14 @@ fn build_funcrefs(
         let sig_index = module_info.functions[index];
         let type_index = vmshared_signatures[sig_index];
         let call_trampoline = function_call_trampolines[sig_index];
+        let tail_func_ptr =
+            finished_function_tail_entries[local_index].map_or(ptr::null(), |entry| entry.0);
         let anyfunc = VMCallerCheckedAnyfunc {
             func_ptr: func_ptr.0,
             type_index,
             vmctx: VMFunctionContext { vmctx: vmctx_ptr },
             call_trampoline,
+            tail_func_ptr,
         };
         func_refs.push(anyfunc);
     }
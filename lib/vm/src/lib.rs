@@ -14,12 +14,16 @@
 )]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+mod epoch;
+mod exception;
 mod export;
 mod extern_ref;
+mod fiber;
 mod function_env;
 mod global;
 mod imports;
 mod instance;
+mod limiter;
 mod memory;
 mod mmap;
 mod probestack;
@@ -34,12 +38,19 @@ pub mod libcalls;
 
 use std::ptr::NonNull;
 
+pub use crate::epoch::{EpochDeadlineCallback, VMEpochState};
+pub use crate::exception::{VMException, VMExceptionRef, VMExceptionState, VMTag};
 pub use crate::export::*;
 pub use crate::extern_ref::{VMExternObj, VMExternRef};
+pub use crate::fiber::{block_on, AsyncFiber, FiberError, DEFAULT_ASYNC_STACK_SIZE};
 pub use crate::function_env::VMFunctionEnvironment;
 pub use crate::global::*;
 pub use crate::imports::Imports;
 pub use crate::instance::{InstanceAllocator, VMInstance};
+pub use crate::limiter::{
+    ResourceLimitExceeded, ResourceLimiter, StoreLimits, StoreLimitsBuilder,
+    DEFAULT_INSTANCE_LIMIT, DEFAULT_MEMORY_LIMIT, DEFAULT_TABLE_LIMIT,
+};
 pub use crate::memory::{
     initialize_memory_with_data, LinearMemory, NotifyLocation, VMMemory, VMOwnedMemory,
     VMSharedMemory,
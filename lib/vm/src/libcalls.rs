@@ -39,13 +39,14 @@
 
 use crate::probestack::PROBESTACK;
 use crate::table::{RawTableElement, TableElement};
-use crate::trap::{raise_lib_trap, Trap, TrapCode};
+use crate::trap::{raise_lib_trap, resume_panic, Trap, TrapCode};
 use crate::vmcontext::VMContext;
-use crate::{on_host_stack, VMFuncRef};
+use crate::{on_host_stack, VMExceptionRef, VMFuncRef};
+use std::panic::{self, AssertUnwindSafe};
 pub use wasmer_types::LibCall;
 use wasmer_types::{
-    DataIndex, ElemIndex, FunctionIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
-    TableIndex, Type,
+    DataIndex, ElemIndex, FunctionIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, RawValue,
+    TableIndex, TagIndex, Type,
 };
 
 /// Implementation of f32.ceil
@@ -138,6 +139,20 @@ pub extern "C" fn wasmer_vm_f64_nearest(x: f64) -> f64 {
     }
 }
 
+/// Fused multiply-add of f32 values, with a single rounding
+#[allow(clippy::float_arithmetic)]
+#[no_mangle]
+pub extern "C" fn wasmer_vm_f32_fma(x: f32, y: f32, z: f32) -> f32 {
+    x.mul_add(y, z)
+}
+
+/// Fused multiply-add of f64 values, with a single rounding
+#[allow(clippy::float_arithmetic)]
+#[no_mangle]
+pub extern "C" fn wasmer_vm_f64_fma(x: f64, y: f64, z: f64) -> f64 {
+    x.mul_add(y, z)
+}
+
 /// Implementation of memory.grow for locally-defined 32-bit memories.
 ///
 /// # Safety
@@ -149,15 +164,19 @@ pub unsafe extern "C" fn wasmer_vm_memory32_grow(
     delta: u32,
     memory_index: u32,
 ) -> u32 {
-    on_host_stack(|| {
+    let result = on_host_stack(|| {
         let instance = (*vmctx).instance_mut();
         let memory_index = LocalMemoryIndex::from_u32(memory_index);
 
         instance
             .memory_grow(memory_index, delta)
-            .map(|pages| pages.0)
-            .unwrap_or(u32::MAX)
-    })
+            .map(|grown| grown.map(|pages| pages.0).unwrap_or(u32::MAX))
+    });
+
+    match result {
+        Ok(pages) => pages,
+        Err(trap) => raise_lib_trap(trap),
+    }
 }
 
 /// Implementation of memory.grow for imported 32-bit memories.
@@ -171,15 +190,19 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_grow(
     delta: u32,
     memory_index: u32,
 ) -> u32 {
-    on_host_stack(|| {
+    let result = on_host_stack(|| {
         let instance = (*vmctx).instance_mut();
         let memory_index = MemoryIndex::from_u32(memory_index);
 
         instance
             .imported_memory_grow(memory_index, delta)
-            .map(|pages| pages.0)
-            .unwrap_or(u32::MAX)
-    })
+            .map(|grown| grown.map(|pages| pages.0).unwrap_or(u32::MAX))
+    });
+
+    match result {
+        Ok(pages) => pages,
+        Err(trap) => raise_lib_trap(trap),
+    }
 }
 
 /// Implementation of memory.size for locally-defined 32-bit memories.
@@ -442,7 +465,7 @@ pub unsafe extern "C" fn wasmer_vm_table_grow(
     delta: u32,
     table_index: u32,
 ) -> u32 {
-    on_host_stack(|| {
+    let result = on_host_stack(|| {
         let instance = (*vmctx).instance_mut();
         let table_index = LocalTableIndex::from_u32(table_index);
 
@@ -454,8 +477,13 @@ pub unsafe extern "C" fn wasmer_vm_table_grow(
 
         instance
             .table_grow(table_index, delta, init_value)
-            .unwrap_or(u32::MAX)
-    })
+            .map(|grown| grown.unwrap_or(u32::MAX))
+    });
+
+    match result {
+        Ok(size) => size,
+        Err(trap) => raise_lib_trap(trap),
+    }
 }
 
 /// Implementation of `table.grow` for imported tables.
@@ -470,7 +498,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_table_grow(
     delta: u32,
     table_index: u32,
 ) -> u32 {
-    on_host_stack(|| {
+    let result = on_host_stack(|| {
         let instance = (*vmctx).instance_mut();
         let table_index = TableIndex::from_u32(table_index);
         let init_value = match instance.get_table(table_index).ty().ty {
@@ -481,8 +509,13 @@ pub unsafe extern "C" fn wasmer_vm_imported_table_grow(
 
         instance
             .imported_table_grow(table_index, delta, init_value)
-            .unwrap_or(u32::MAX)
-    })
+            .map(|grown| grown.unwrap_or(u32::MAX))
+    });
+
+    match result {
+        Ok(size) => size,
+        Err(trap) => raise_lib_trap(trap),
+    }
 }
 
 /// Implementation of `func.ref`.
@@ -658,6 +691,106 @@ pub unsafe extern "C" fn wasmer_vm_raise_trap(trap_code: TrapCode) -> ! {
     raise_lib_trap(trap)
 }
 
+/// Implementation of `throw`.
+///
+/// The exception is not unwound here: it is recorded in the store, and the
+/// compiled code is responsible for jumping to the matching handler or
+/// returning to its caller.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `values` must point to as many
+/// values as the tag carries.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_throw(
+    vmctx: *mut VMContext,
+    tag_index: u32,
+    values: *const RawValue,
+) {
+    let instance = (*vmctx).instance_mut();
+    let tag_index = TagIndex::from_u32(tag_index);
+
+    instance.throw(tag_index, values)
+}
+
+/// Implementation of `throw_ref`.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_throw_ref(
+    vmctx: *mut VMContext,
+    exnref: Option<VMExceptionRef>,
+) {
+    let instance = (*vmctx).instance_mut();
+
+    match exnref {
+        Some(exnref) => instance.throw_ref(exnref),
+        None => raise_lib_trap(Trap::lib(TrapCode::NullReference)),
+    }
+}
+
+/// Releases an exception once it was caught by a clause that doesn't keep
+/// its `exnref`, so that its slot in the store can be reused.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_drop_exception(
+    vmctx: *mut VMContext,
+    exnref: Option<VMExceptionRef>,
+) {
+    let instance = (*vmctx).instance_mut();
+
+    if let Some(exnref) = exnref {
+        instance.drop_exception(exnref);
+    }
+}
+
+/// Marks an exception caught by a clause that passes its `exnref` on, so
+/// that it isn't released while that `exnref` may still be held.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_expose_exception(
+    vmctx: *mut VMContext,
+    exnref: Option<VMExceptionRef>,
+) {
+    let instance = (*vmctx).instance_mut();
+
+    if let Some(exnref) = exnref {
+        instance.expose_exception(exnref);
+    }
+}
+
+/// Implementation of the epoch deadline check, called by compiled code
+/// once the current epoch reaches the deadline of the store.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_epoch_deadline_reached(vmctx: *mut VMContext) {
+    // The callback is user code: a panic can't unwind through the Wasm
+    // frames, so it is carried over them and resumed on the host side, as
+    // for host functions.
+    let result = on_host_stack(|| {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            let instance = (*vmctx).instance_mut();
+            instance.epoch_deadline_reached()
+        }))
+    });
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(trap)) => raise_lib_trap(trap),
+        Err(panic) => resume_panic(panic),
+    }
+}
+
 /// Probestack check
 ///
 /// # Safety
@@ -855,5 +988,12 @@ pub fn function_pointer(libcall: LibCall) -> usize {
         LibCall::ImportedMemory32AtomicWait64 => wasmer_vm_imported_memory32_atomic_wait64 as usize,
         LibCall::Memory32AtomicNotify => wasmer_vm_memory32_atomic_notify as usize,
         LibCall::ImportedMemory32AtomicNotify => wasmer_vm_imported_memory32_atomic_notify as usize,
+        LibCall::Throw => wasmer_vm_throw as usize,
+        LibCall::ThrowRef => wasmer_vm_throw_ref as usize,
+        LibCall::DropException => wasmer_vm_drop_exception as usize,
+        LibCall::ExposeException => wasmer_vm_expose_exception as usize,
+        LibCall::FmaF32 => wasmer_vm_f32_fma as usize,
+        LibCall::FmaF64 => wasmer_vm_f64_fma as usize,
+        LibCall::EpochDeadlineReached => wasmer_vm_epoch_deadline_reached as usize,
     }
 }
@@ -0,0 +1,369 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use wasmer_types::MemoryError;
+
+/// The default maximum number of instances in a store.
+pub const DEFAULT_INSTANCE_LIMIT: usize = 10000;
+/// The default maximum number of tables in a store.
+pub const DEFAULT_TABLE_LIMIT: usize = 10000;
+/// The default maximum number of memories in a store.
+pub const DEFAULT_MEMORY_LIMIT: usize = 10000;
+
+/// Limits the resources used by the memories, tables and instances of a
+/// store.
+///
+/// The limiter is consulted before a memory or a table grows, either
+/// through `memory.grow`/`table.grow` or from the host, and before the
+/// memories and tables of a new instance are created (as a growth from
+/// zero to their minimum size). Once a memory that grew through the
+/// limiter is dropped, the bytes it grew by are given back with
+/// [`ResourceLimiter::memory_released`].
+///
+/// Growth can be allowed (`Ok(true)`), denied (`Ok(false)`), in which case
+/// the grow operation fails as if the maximum size was reached, or turned
+/// into a trap by returning an error.
+///
+/// The same limiter may be shared by several stores, e.g. by the stores
+/// of the threads spawned by a WASIX program, so its methods take `&self`.
+pub trait ResourceLimiter: fmt::Debug + Send + Sync {
+    /// Called before a memory grows from `current` to `desired` bytes.
+    ///
+    /// `maximum` is the maximum size of the memory in bytes, if any. The
+    /// `desired` size can exceed it, in which case the growth will fail
+    /// even if it is allowed here.
+    fn memory_growing(
+        &self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>>;
+
+    /// Called when a memory failed to grow after the growth was allowed
+    /// by [`ResourceLimiter::memory_growing`].
+    fn memory_grow_failed(&self, _current: usize, _desired: usize) {}
+
+    /// Called when a memory is dropped with the number of bytes it grew by
+    /// after the growth was allowed by [`ResourceLimiter::memory_growing`].
+    fn memory_released(&self, _size: usize) {}
+
+    /// Called before a table grows from `current` to `desired` elements.
+    ///
+    /// `maximum` is the maximum number of elements of the table, if any.
+    fn table_growing(
+        &self,
+        current: u32,
+        desired: u32,
+        maximum: Option<u32>,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>>;
+
+    /// Called when a table failed to grow after the growth was allowed by
+    /// [`ResourceLimiter::table_growing`].
+    fn table_grow_failed(&self, _current: u32, _desired: u32) {}
+
+    /// The maximum number of instances in a store.
+    fn instances(&self) -> usize {
+        DEFAULT_INSTANCE_LIMIT
+    }
+
+    /// The maximum number of tables in a store.
+    fn tables(&self) -> usize {
+        DEFAULT_TABLE_LIMIT
+    }
+
+    /// The maximum number of memories in a store.
+    fn memories(&self) -> usize {
+        DEFAULT_MEMORY_LIMIT
+    }
+}
+
+/// The error returned by [`StoreLimits`] when `trap_on_grow_failure` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceLimitExceeded(String);
+
+impl fmt::Display for ResourceLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "resource limit exceeded: {}", self.0)
+    }
+}
+
+impl Error for ResourceLimitExceeded {}
+
+/// A [`ResourceLimiter`] with static limits.
+///
+/// Besides the size of each memory and table, it limits the total size of
+/// all the memories that grow through it, so a single `StoreLimits` can
+/// cap the memory usage of every instance of a store. The memory of an
+/// instance counts towards that total until the memory is dropped.
+///
+/// Use [`StoreLimitsBuilder`] to create one.
+#[derive(Debug, Default)]
+pub struct StoreLimits {
+    memory_size: Option<usize>,
+    total_memory: Option<usize>,
+    table_elements: Option<u32>,
+    instances: Option<usize>,
+    tables: Option<usize>,
+    memories: Option<usize>,
+    trap_on_grow_failure: bool,
+    /// The total size in bytes of the live memories grown through this
+    /// limiter.
+    memory_used: AtomicUsize,
+}
+
+impl StoreLimits {
+    /// Returns the total size in bytes of the live memories grown through
+    /// this limiter.
+    pub fn memory_used(&self) -> usize {
+        self.memory_used.load(Ordering::SeqCst)
+    }
+
+    fn deny(&self, reason: String) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        if self.trap_on_grow_failure {
+            Err(Box::new(ResourceLimitExceeded(reason)))
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl ResourceLimiter for StoreLimits {
+    fn memory_growing(
+        &self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        if let Some(limit) = self.memory_size {
+            if desired > limit {
+                return self.deny(format!(
+                    "memory of {desired} bytes exceeds the limit of {limit} bytes"
+                ));
+            }
+        }
+        let delta = desired.saturating_sub(current);
+        let reserved = self
+            .memory_used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                let total = used.checked_add(delta)?;
+                match self.total_memory {
+                    Some(limit) if total > limit => None,
+                    _ => Some(total),
+                }
+            });
+        match reserved {
+            Ok(_) => Ok(true),
+            Err(used) => self.deny(format!(
+                "growing the memories to {} bytes exceeds the limit of {} bytes",
+                used.saturating_add(delta),
+                self.total_memory.unwrap_or(usize::MAX)
+            )),
+        }
+    }
+
+    fn memory_grow_failed(&self, current: usize, desired: usize) {
+        let delta = desired.saturating_sub(current);
+        self.memory_used.fetch_sub(delta, Ordering::SeqCst);
+    }
+
+    fn memory_released(&self, size: usize) {
+        self.memory_used.fetch_sub(size, Ordering::SeqCst);
+    }
+
+    fn table_growing(
+        &self,
+        _current: u32,
+        desired: u32,
+        _maximum: Option<u32>,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        match self.table_elements {
+            Some(limit) if desired > limit => self.deny(format!(
+                "table of {desired} elements exceeds the limit of {limit} elements"
+            )),
+            _ => Ok(true),
+        }
+    }
+
+    fn instances(&self) -> usize {
+        self.instances.unwrap_or(DEFAULT_INSTANCE_LIMIT)
+    }
+
+    fn tables(&self) -> usize {
+        self.tables.unwrap_or(DEFAULT_TABLE_LIMIT)
+    }
+
+    fn memories(&self) -> usize {
+        self.memories.unwrap_or(DEFAULT_MEMORY_LIMIT)
+    }
+}
+
+/// The bytes a memory grew by through each resource limiter, given back
+/// to the limiters when the memory is dropped.
+#[derive(Debug, Default)]
+pub(crate) struct MemoryReservations(Vec<(Arc<dyn ResourceLimiter>, usize)>);
+
+impl MemoryReservations {
+    /// Records that the memory grew by `size` bytes through `limiter`.
+    pub(crate) fn add(&mut self, limiter: &Arc<dyn ResourceLimiter>, size: usize) {
+        let existing = self.0.iter_mut().find(|(reserved_from, _)| {
+            std::ptr::eq(
+                Arc::as_ptr(reserved_from).cast::<()>(),
+                Arc::as_ptr(limiter).cast::<()>(),
+            )
+        });
+        match existing {
+            Some((_, reserved)) => *reserved += size,
+            None => self.0.push((limiter.clone(), size)),
+        }
+    }
+
+    /// Reserves the same number of bytes again from each limiter, for a
+    /// copy of the memory.
+    ///
+    /// Fails if a limiter denies it, releasing what was already reserved.
+    pub(crate) fn copy(&self) -> Result<Self, MemoryError> {
+        let mut copy = Self::default();
+        for (limiter, size) in &self.0 {
+            match limiter.memory_growing(0, *size, None) {
+                Ok(true) => copy.add(limiter, *size),
+                Ok(false) => {
+                    return Err(MemoryError::Generic(format!(
+                        "the resource limiter denied copying a memory of {size} bytes"
+                    )))
+                }
+                Err(err) => return Err(MemoryError::Generic(err.to_string())),
+            }
+        }
+        Ok(copy)
+    }
+}
+
+impl Drop for MemoryReservations {
+    fn drop(&mut self) {
+        for (limiter, size) in self.0.drain(..) {
+            limiter.memory_released(size);
+        }
+    }
+}
+
+/// Builder for [`StoreLimits`].
+#[derive(Debug, Default)]
+pub struct StoreLimitsBuilder(StoreLimits);
+
+impl StoreLimitsBuilder {
+    /// Creates a builder with no limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the size in bytes of each memory.
+    pub fn memory_size(mut self, limit: usize) -> Self {
+        self.0.memory_size = Some(limit);
+        self
+    }
+
+    /// Limits the total size in bytes of all the memories.
+    pub fn total_memory(mut self, limit: usize) -> Self {
+        self.0.total_memory = Some(limit);
+        self
+    }
+
+    /// Limits the number of elements of each table.
+    pub fn table_elements(mut self, limit: u32) -> Self {
+        self.0.table_elements = Some(limit);
+        self
+    }
+
+    /// Limits the number of instances in a store.
+    pub fn instances(mut self, limit: usize) -> Self {
+        self.0.instances = Some(limit);
+        self
+    }
+
+    /// Limits the number of tables in a store.
+    pub fn tables(mut self, limit: usize) -> Self {
+        self.0.tables = Some(limit);
+        self
+    }
+
+    /// Limits the number of memories in a store.
+    pub fn memories(mut self, limit: usize) -> Self {
+        self.0.memories = Some(limit);
+        self
+    }
+
+    /// Traps instead of failing the grow operation when a limit is exceeded.
+    pub fn trap_on_grow_failure(mut self, trap: bool) -> Self {
+        self.0.trap_on_grow_failure = trap;
+        self
+    }
+
+    /// Builds the [`StoreLimits`].
+    pub fn build(self) -> StoreLimits {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn total_memory_is_shared() {
+        let limits = StoreLimitsBuilder::new().total_memory(100).build();
+        assert!(limits.memory_growing(0, 60, None).unwrap());
+        assert!(limits.memory_growing(0, 40, None).unwrap());
+        assert!(!limits.memory_growing(40, 41, None).unwrap());
+        assert_eq!(limits.memory_used(), 100);
+
+        limits.memory_grow_failed(0, 40);
+        assert_eq!(limits.memory_used(), 60);
+        assert!(limits.memory_growing(0, 40, None).unwrap());
+    }
+
+    #[test]
+    fn reservations_are_released_on_drop() {
+        let limits: Arc<dyn ResourceLimiter> =
+            Arc::new(StoreLimitsBuilder::new().total_memory(100).build());
+        assert!(limits.memory_growing(0, 60, None).unwrap());
+        let mut reservations = MemoryReservations::default();
+        reservations.add(&limits, 20);
+        reservations.add(&limits, 40);
+        assert_eq!(reservations.0.len(), 1);
+        assert!(!limits.memory_growing(0, 41, None).unwrap());
+
+        drop(reservations);
+        assert!(limits.memory_growing(0, 100, None).unwrap());
+    }
+
+    #[test]
+    fn reservations_are_copied() {
+        let limits = Arc::new(StoreLimitsBuilder::new().total_memory(100).build());
+        let limiter: Arc<dyn ResourceLimiter> = limits.clone();
+        let mut reservations = MemoryReservations::default();
+        assert!(limiter.memory_growing(0, 40, None).unwrap());
+        reservations.add(&limiter, 40);
+
+        let copy = reservations.copy().unwrap();
+        assert_eq!(limits.memory_used(), 80);
+        assert!(reservations.copy().is_err());
+        assert_eq!(limits.memory_used(), 80);
+
+        drop(copy);
+        assert_eq!(limits.memory_used(), 40);
+    }
+
+    #[test]
+    fn trap_on_grow_failure() {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(10)
+            .table_elements(5)
+            .trap_on_grow_failure(true)
+            .build();
+        assert!(limits.memory_growing(0, 10, None).unwrap());
+        assert!(limits.memory_growing(10, 11, None).is_err());
+        assert!(limits.table_growing(0, 5, None).unwrap());
+        assert!(limits.table_growing(5, 6, None).is_err());
+    }
+}
@@ -5,6 +5,7 @@
 //!
 //! `Memory` is to WebAssembly linear memories what `Table` is to WebAssembly tables.
 
+use crate::limiter::{MemoryReservations, ResourceLimiter};
 use crate::mmap::MmapType;
 use crate::threadconditions::ThreadConditions;
 pub use crate::threadconditions::{NotifyLocation, WaiterError};
@@ -16,7 +17,7 @@ use std::convert::TryInto;
 use std::ptr::NonNull;
 use std::rc::Rc;
 use std::slice;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use wasmer_types::{Bytes, MemoryError, MemoryStyle, MemoryType, Pages, WASM_PAGE_SIZE};
 
@@ -29,6 +30,9 @@ struct WasmMmap {
     size: Pages,
     /// The owned memory definition used by the generated code
     vm_memory_definition: MaybeInstanceOwned<VMMemoryDefinition>,
+    /// The bytes this memory grew by through resource limiters, given
+    /// back to them when it is dropped.
+    reservations: MemoryReservations,
 }
 
 impl WasmMmap {
@@ -145,6 +149,8 @@ impl WasmMmap {
     /// Copies the memory
     /// (in this case it performs a copy-on-write to save memory)
     pub fn copy(&mut self) -> Result<Self, MemoryError> {
+        // The copy counts towards the limits of the original memory.
+        let reservations = self.reservations.copy()?;
         let mem_length = self.size.bytes().0;
         let mut alloc = self
             .alloc
@@ -160,6 +166,7 @@ impl WasmMmap {
             ))),
             alloc,
             size: self.size,
+            reservations,
         })
     }
 }
@@ -346,6 +353,7 @@ impl VMOwnedMemory {
             },
             alloc,
             size: Bytes::from(mem_length).try_into().unwrap(),
+            reservations: MemoryReservations::default(),
         };
 
         Ok(Self {
@@ -424,6 +432,11 @@ impl LinearMemory for VMOwnedMemory {
         Err(MemoryError::MemoryNotShared)
     }
 
+    /// Records that the memory grew by `size` bytes through `limiter`
+    fn reserve(&mut self, limiter: &Arc<dyn ResourceLimiter>, size: usize) {
+        self.mmap.reservations.add(limiter, size);
+    }
+
     /// Copies this memory to a new memory
     fn copy(&mut self) -> Result<Box<dyn LinearMemory + 'static>, MemoryError> {
         let forked = Self::copy(self)?;
@@ -576,6 +589,12 @@ impl LinearMemory for VMSharedMemory {
         Ok(Box::new(self.clone()))
     }
 
+    /// Records that the memory grew by `size` bytes through `limiter`
+    fn reserve(&mut self, limiter: &Arc<dyn ResourceLimiter>, size: usize) {
+        let mut guard = self.mmap.write().unwrap();
+        guard.reservations.add(limiter, size);
+    }
+
     /// Copies this memory to a new memory
     fn copy(&mut self) -> Result<Box<dyn LinearMemory + 'static>, MemoryError> {
         let forked = Self::copy(self)?;
@@ -669,6 +688,11 @@ impl LinearMemory for VMMemory {
         self.0.try_clone()
     }
 
+    /// Records that the memory grew by `size` bytes through `limiter`
+    fn reserve(&mut self, limiter: &Arc<dyn ResourceLimiter>, size: usize) {
+        self.0.reserve(limiter, size)
+    }
+
     /// Initialize memory with data
     unsafe fn initialize_with_data(&self, start: usize, data: &[u8]) -> Result<(), Trap> {
         self.0.initialize_with_data(start, data)
@@ -817,6 +841,13 @@ where
     /// Attempts to clone this memory (if its clonable)
     fn try_clone(&self) -> Result<Box<dyn LinearMemory + 'static>, MemoryError>;
 
+    /// Records that the memory grew by `size` bytes through `limiter`, so
+    /// they are given back with [`ResourceLimiter::memory_released`] when
+    /// the memory is dropped.
+    ///
+    /// Memories that don't implement this never give back what they grew by.
+    fn reserve(&mut self, _limiter: &Arc<dyn ResourceLimiter>, _size: usize) {}
+
     #[doc(hidden)]
     /// # Safety
     /// This function is unsafe because WebAssembly specification requires that data is always set at initialization time.
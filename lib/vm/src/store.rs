@@ -1,9 +1,12 @@
 use crate::{
-    VMExternObj, VMFunction, VMFunctionEnvironment, VMGlobal, VMInstance, VMMemory, VMTable,
+    LinearMemory, ResourceLimiter, TableElement, Trap, VMEpochState, VMException, VMExceptionRef,
+    VMExceptionState, VMExternObj, VMFunction, VMFunctionEnvironment, VMGlobal, VMInstance,
+    VMMemory, VMTable, VMTag,
 };
 use core::slice::Iter;
+use std::sync::Arc;
 use std::{cell::UnsafeCell, fmt, marker::PhantomData, num::NonZeroUsize, ptr::NonNull};
-use wasmer_types::StoreId;
+use wasmer_types::{MemoryError, Pages, StoreId, Type, WASM_PAGE_SIZE};
 
 /// Trait to represent an object managed by a context. This is implemented on
 /// the VM types managed by the context.
@@ -33,6 +36,8 @@ impl_context_object! {
     memories => VMMemory,
     extern_objs => VMExternObj,
     function_environments => VMFunctionEnvironment,
+    tags => VMTag,
+    exceptions => VMException,
 }
 
 /// Set of objects managed by a context.
@@ -46,6 +51,18 @@ pub struct StoreObjects {
     instances: Vec<VMInstance>,
     extern_objs: Vec<VMExternObj>,
     function_environments: Vec<VMFunctionEnvironment>,
+    tags: Vec<VMTag>,
+    exceptions: Vec<VMException>,
+    /// Slots of `exceptions` that were freed and can be reused.
+    free_exceptions: Vec<InternalStoreHandle<VMException>>,
+    /// Whether exceptions were handed out to Wasm code since unreachable
+    /// exceptions were last collected.
+    exceptions_exposed: bool,
+    /// The number of calls into Wasm code currently running in this store.
+    wasm_calls: usize,
+    exception_state: Box<VMExceptionState>,
+    epoch_state: Box<VMEpochState>,
+    resource_limiter: Option<Arc<dyn ResourceLimiter>>,
 }
 
 impl StoreObjects {
@@ -59,6 +76,270 @@ impl StoreObjects {
         self.id = id;
     }
 
+    /// Returns the exception currently being propagated in this store.
+    pub fn exception_state(&self) -> &VMExceptionState {
+        &self.exception_state
+    }
+
+    /// Returns a pointer to the exception state, for use by compiled code.
+    pub(crate) fn exception_state_ptr(&mut self) -> *mut VMExceptionState {
+        &mut *self.exception_state
+    }
+
+    /// Starts propagating the given exception through the Wasm stack.
+    pub fn raise_exception(&mut self, exnref: VMExceptionRef) {
+        let exception = &self.exceptions[exnref.0.index() - 1];
+        self.exception_state.raise(exnref, exception);
+    }
+
+    /// Stores a new exception, reusing the slot of a freed one if possible.
+    pub(crate) fn new_exception(&mut self, exception: VMException) -> VMExceptionRef {
+        match self.free_exceptions.pop() {
+            Some(handle) => {
+                *handle.get_mut(self) = exception;
+                VMExceptionRef(handle)
+            }
+            None => VMExceptionRef(InternalStoreHandle::new(self, exception)),
+        }
+    }
+
+    /// Frees an exception that was caught without keeping its `exnref`.
+    ///
+    /// An exception whose `exnref` was never handed out is only referenced
+    /// by the compiled code that caught it, so it can be freed right away.
+    /// Exposed exceptions are kept until they are collected, and so is the
+    /// exception being propagated, e.g. when a catch clause rethrew it.
+    pub(crate) fn drop_exception(&mut self, exnref: VMExceptionRef) {
+        if self.exception_state.exnref() == Some(exnref) || exnref.0.get(self).exposed {
+            return;
+        }
+        self.free_exception(exnref.0);
+    }
+
+    /// Frees an exception, letting the next one thrown reuse its slot.
+    fn free_exception(&mut self, handle: InternalStoreHandle<VMException>) {
+        handle.get_mut(self).payload = Box::default();
+        self.free_exceptions.push(handle);
+    }
+
+    /// Marks an exception whose `exnref` was handed out to Wasm code, so that
+    /// it is only freed once it can't be reached anymore.
+    pub(crate) fn expose_exception(&mut self, exnref: VMExceptionRef) {
+        exnref.0.get_mut(self).exposed = true;
+        self.exceptions_exposed = true;
+    }
+
+    /// Marks an exception whose `exnref` was handed out to the host, so that
+    /// it is never freed.
+    pub fn hold_exception(&mut self, exnref: VMExceptionRef) {
+        let exception = exnref.0.get_mut(self);
+        exception.exposed = true;
+        exception.held_by_host = true;
+    }
+
+    /// Records that Wasm code of this store starts running.
+    ///
+    /// When no Wasm code runs yet, no Wasm frame can hold an `exnref`, so
+    /// the exceptions that can't be reached from the globals or the host
+    /// anymore are freed first.
+    pub fn enter_wasm(&mut self) {
+        if self.wasm_calls == 0 && self.exceptions_exposed {
+            self.collect_exceptions();
+        }
+        self.wasm_calls += 1;
+    }
+
+    /// Records that Wasm code of this store stopped running.
+    pub fn leave_wasm(&mut self) {
+        self.wasm_calls -= 1;
+    }
+
+    /// Frees the exceptions that can't be reached anymore.
+    ///
+    /// Must only be called while no Wasm code runs in this store.
+    fn collect_exceptions(&mut self) {
+        let mut reachable = vec![false; self.exceptions.len()];
+        let mut pending: Vec<VMExceptionRef> = self
+            .exceptions
+            .iter()
+            .enumerate()
+            .filter(|(_, exception)| exception.held_by_host)
+            .filter_map(|(index, _)| InternalStoreHandle::from_index(index + 1))
+            .map(VMExceptionRef)
+            .collect();
+        pending.extend(self.exception_state.exnref());
+        for global in &self.globals {
+            if global.ty().ty == Type::ExceptionRef {
+                pending.extend(unsafe { VMExceptionRef::from_raw(global.vmglobal().as_ref().val) });
+            }
+        }
+
+        // Exceptions can carry `exnref`s too.
+        while let Some(exnref) = pending.pop() {
+            let index = exnref.0.index() - 1;
+            if std::mem::replace(&mut reachable[index], true) {
+                continue;
+            }
+            let exception = exnref.0.get(self);
+            let params = exception.tag().get(self).ty().params();
+            for (ty, raw) in params.iter().zip(exception.payload()) {
+                if *ty == Type::ExceptionRef {
+                    pending.extend(unsafe { VMExceptionRef::from_raw(*raw) });
+                }
+            }
+        }
+
+        for handle in &self.free_exceptions {
+            reachable[handle.index() - 1] = true;
+        }
+        for (index, reachable) in reachable.into_iter().enumerate() {
+            if !reachable {
+                self.free_exception(InternalStoreHandle::from_index(index + 1).unwrap());
+            }
+        }
+        self.exceptions_exposed = false;
+    }
+
+    /// Returns the number of exceptions stored in this store, including the
+    /// freed ones waiting to be reused.
+    pub fn num_exceptions(&self) -> usize {
+        self.exceptions.len()
+    }
+
+    /// Stops propagating the current exception, if any, and returns it.
+    ///
+    /// The exception is handed out to the host, and is never freed.
+    pub fn take_exception(&mut self) -> Option<VMExceptionRef> {
+        let exnref = self.exception_state.take()?;
+        self.hold_exception(exnref);
+        Some(exnref)
+    }
+
+    /// Returns the epoch deadline of this store.
+    pub fn epoch_state(&self) -> &VMEpochState {
+        &self.epoch_state
+    }
+
+    /// Returns the epoch deadline of this store, mutably.
+    pub fn epoch_state_mut(&mut self) -> &mut VMEpochState {
+        &mut self.epoch_state
+    }
+
+    /// Returns a pointer to the epoch state, for use by compiled code.
+    pub(crate) fn epoch_state_ptr(&mut self) -> *mut VMEpochState {
+        &mut *self.epoch_state
+    }
+
+    /// Returns the resource limiter of this store, if any.
+    pub fn resource_limiter(&self) -> Option<&Arc<dyn ResourceLimiter>> {
+        self.resource_limiter.as_ref()
+    }
+
+    /// Sets the resource limiter consulted when the memories and tables of
+    /// this store grow, and when instances are created in it.
+    pub fn set_resource_limiter(&mut self, limiter: Option<Arc<dyn ResourceLimiter>>) {
+        self.resource_limiter = limiter;
+    }
+
+    /// Returns the number of instances in this store.
+    pub fn num_instances(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Returns the number of memories in this store.
+    pub fn num_memories(&self) -> usize {
+        self.memories.len()
+    }
+
+    /// Returns the number of tables in this store.
+    pub fn num_tables(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Grows a memory of this store by `delta` pages, after checking with
+    /// the resource limiter.
+    ///
+    /// The outer error is a trap requested by the limiter, the inner one
+    /// the failure to grow the memory, including when the limiter denied it.
+    pub fn grow_memory(
+        &mut self,
+        handle: InternalStoreHandle<VMMemory>,
+        delta: Pages,
+    ) -> Result<Result<Pages, MemoryError>, Trap> {
+        let limiter = self.resource_limiter.clone();
+        let memory = handle.get_mut(self);
+        let Some(limiter) = limiter else {
+            return Ok(memory.grow(delta));
+        };
+
+        let current = memory.size();
+        let pages_to_bytes = |pages: usize| pages.checked_mul(WASM_PAGE_SIZE);
+        let (Some(current_bytes), Some(desired_bytes)) = (
+            pages_to_bytes(current.0 as usize),
+            (current.0 as usize)
+                .checked_add(delta.0 as usize)
+                .and_then(pages_to_bytes),
+        ) else {
+            return Ok(Err(MemoryError::CouldNotGrow {
+                current,
+                attempted_delta: delta,
+            }));
+        };
+        let maximum_bytes = memory
+            .ty()
+            .maximum
+            .and_then(|maximum| pages_to_bytes(maximum.0 as usize));
+        if !limiter
+            .memory_growing(current_bytes, desired_bytes, maximum_bytes)
+            .map_err(Trap::user)?
+        {
+            return Ok(Err(MemoryError::CouldNotGrow {
+                current,
+                attempted_delta: delta,
+            }));
+        }
+
+        let result = memory.grow(delta);
+        match result {
+            Ok(_) => memory.reserve(&limiter, desired_bytes - current_bytes),
+            Err(_) => limiter.memory_grow_failed(current_bytes, desired_bytes),
+        }
+        Ok(result)
+    }
+
+    /// Grows a table of this store by `delta` elements, after checking with
+    /// the resource limiter.
+    ///
+    /// Returns the previous size of the table, or `None` if it could not
+    /// grow. The error is a trap requested by the limiter.
+    pub fn grow_table(
+        &mut self,
+        handle: InternalStoreHandle<VMTable>,
+        delta: u32,
+        init_value: TableElement,
+    ) -> Result<Option<u32>, Trap> {
+        let limiter = self.resource_limiter.clone();
+        let table = handle.get_mut(self);
+        let Some(limiter) = limiter else {
+            return Ok(table.grow(delta, init_value));
+        };
+
+        let current = table.size();
+        let desired = current.saturating_add(delta);
+        if !limiter
+            .table_growing(current, desired, table.ty().maximum)
+            .map_err(Trap::user)?
+        {
+            return Ok(None);
+        }
+
+        let result = table.grow(delta, init_value);
+        if result.is_none() {
+            limiter.table_grow_failed(current, desired);
+        }
+        Ok(result)
+    }
+
     /// Returns a pair of mutable references from two handles.
     ///
     /// Panics if both handles point to the same object.
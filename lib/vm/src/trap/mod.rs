@@ -14,4 +14,5 @@ pub use traphandlers::{
     wasmer_call_trampoline, TrapHandlerFn, VMConfig,
 };
 pub use traphandlers::{init_traps, resume_panic};
+pub(crate) use traphandlers::{lazy_per_thread_init, TlsState};
 pub use wasmer_types::TrapCode;
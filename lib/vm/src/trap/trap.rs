@@ -3,6 +3,8 @@ use std::error::Error;
 use std::fmt;
 use wasmer_types::TrapCode;
 
+use crate::VMExceptionRef;
+
 /// Stores trace message with backtrace.
 #[derive(Debug)]
 pub enum Trap {
@@ -38,6 +40,15 @@ pub enum Trap {
         /// Native stack backtrace at the time the OOM occurred
         backtrace: Backtrace,
     },
+
+    /// An exception thrown by Wasm code that was not caught before reaching
+    /// the host.
+    UncaughtException {
+        /// The exception that was thrown
+        exnref: VMExceptionRef,
+        /// Native stack backtrace at the time the exception reached the host
+        backtrace: Backtrace,
+    },
 }
 
 fn _assert_trap_is_sync_and_send(t: &Trap) -> (&dyn Sync, &dyn Send) {
@@ -87,6 +98,22 @@ impl Trap {
         Self::OOM { backtrace }
     }
 
+    /// Construct a new trap for an exception that was not caught by Wasm code.
+    ///
+    /// Internally saves a backtrace when constructed.
+    pub fn uncaught_exception(exnref: VMExceptionRef) -> Self {
+        let backtrace = Backtrace::new_unresolved();
+        Self::UncaughtException { exnref, backtrace }
+    }
+
+    /// Returns the uncaught exception, if this trap was caused by one.
+    pub fn exception(&self) -> Option<VMExceptionRef> {
+        match self {
+            Self::UncaughtException { exnref, .. } => Some(*exnref),
+            _ => None,
+        }
+    }
+
     /// Attempts to downcast the `Trap` to a concrete type.
     pub fn downcast<T: Error + 'static>(self) -> Result<T, Self> {
         match self {
@@ -130,6 +157,7 @@ impl fmt::Display for Trap {
             Self::Lib { .. } => write!(f, "lib"),
             Self::Wasm { .. } => write!(f, "wasm"),
             Self::OOM { .. } => write!(f, "Wasmer VM out of memory"),
+            Self::UncaughtException { .. } => write!(f, "uncaught exception"),
         }
     }
 }
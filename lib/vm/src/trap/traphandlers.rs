@@ -132,6 +132,8 @@ unsafe fn process_illegal_op(addr: usize) -> Option<TrapCode> {
             8 => Some(TrapCode::BadConversionToInteger),
             9 => Some(TrapCode::UnreachableCodeReached),
             10 => Some(TrapCode::UnalignedAtomic),
+            11 => Some(TrapCode::NullReference),
+            12 => Some(TrapCode::Interrupt),
             _ => None,
         },
     }
@@ -732,6 +734,36 @@ thread_local! {
     static TRAP_HANDLER: AtomicPtr<TrapHandlerContext> = const { AtomicPtr::new(ptr::null_mut()) };
 }
 
+/// The thread-local state of the Wasm code running on a thread.
+///
+/// An async fiber can be suspended while Wasm code is on its stack and later
+/// resumed on another thread. The state is swapped out when the fiber is
+/// suspended and swapped back in when it is resumed, so that it follows the
+/// fiber instead of the thread.
+pub(crate) struct TlsState {
+    yielder: Option<NonNull<Yielder<(), UnwindReason>>>,
+    trap_handler: *mut TrapHandlerContext,
+}
+
+impl Default for TlsState {
+    fn default() -> Self {
+        Self {
+            yielder: None,
+            trap_handler: ptr::null_mut(),
+        }
+    }
+}
+
+impl TlsState {
+    /// Swaps the saved state with the state of the current thread.
+    pub(crate) fn swap(&mut self) {
+        compiler_fence(Ordering::Release);
+        self.yielder = YIELDER.with(|cell| cell.replace(self.yielder));
+        self.trap_handler = TRAP_HANDLER.with(|ptr| ptr.swap(self.trap_handler, Ordering::Relaxed));
+        compiler_fence(Ordering::Acquire);
+    }
+}
+
 /// Read-only information that is used by signal handlers to handle and recover
 /// from traps.
 #[allow(clippy::type_complexity)]
@@ -71,6 +71,11 @@ pub struct VMFunctionImport {
 
     /// Handle to the `VMFunction` in the context.
     pub handle: InternalStoreHandle<VMFunction>,
+
+    /// A pointer to the entry point of the imported function used by tail
+    /// calls, or null if the function can only be called with the native
+    /// calling convention through `body`.
+    pub tail_body: *const VMFunctionBody,
 }
 
 #[cfg(test)]
@@ -97,6 +102,14 @@ mod test_vmfunction_import {
             offset_of!(VMFunctionImport, environment),
             usize::from(offsets.vmfunction_import_vmctx())
         );
+        assert_eq!(
+            offset_of!(VMFunctionImport, handle),
+            usize::from(offsets.vmfunction_import_handle())
+        );
+        assert_eq!(
+            offset_of!(VMFunctionImport, tail_body),
+            usize::from(offsets.vmfunction_import_tail_body())
+        );
     }
 }
 
@@ -594,6 +607,14 @@ pub struct VMCallerCheckedAnyfunc {
     /// Address of the function call trampoline to invoke this function using
     /// a dynamic argument list.
     pub call_trampoline: VMTrampoline,
+    /// Entry point of the function used by tail calls, or null if the
+    /// function can only be called through `func_ptr`.
+    ///
+    /// Compilers may use a calling convention other than the native one
+    /// for the bodies of functions that can be tail called, in which case
+    /// `func_ptr` points to a trampoline adapting the native calling
+    /// convention to the one of the body.
+    pub tail_func_ptr: *const VMFunctionBody,
     // If more elements are added here, remember to add offset_of tests below!
 }
 
@@ -625,6 +646,14 @@ mod test_vmcaller_checked_anyfunc {
             offset_of!(VMCallerCheckedAnyfunc, vmctx),
             usize::from(offsets.vmcaller_checked_anyfunc_vmctx())
         );
+        assert_eq!(
+            offset_of!(VMCallerCheckedAnyfunc, call_trampoline),
+            usize::from(offsets.vmcaller_checked_anyfunc_call_trampoline())
+        );
+        assert_eq!(
+            offset_of!(VMCallerCheckedAnyfunc, tail_func_ptr),
+            usize::from(offsets.vmcaller_checked_anyfunc_tail_func_ptr())
+        );
     }
 }
 
@@ -711,6 +740,16 @@ impl VMBuiltinFunctionsArray {
         ptrs[VMBuiltinFunctionIndex::get_imported_memory_atomic_notify_index().index() as usize] =
             wasmer_vm_imported_memory32_atomic_notify as usize;
 
+        ptrs[VMBuiltinFunctionIndex::get_throw_index().index() as usize] = wasmer_vm_throw as usize;
+        ptrs[VMBuiltinFunctionIndex::get_throw_ref_index().index() as usize] =
+            wasmer_vm_throw_ref as usize;
+        ptrs[VMBuiltinFunctionIndex::get_drop_exception_index().index() as usize] =
+            wasmer_vm_drop_exception as usize;
+        ptrs[VMBuiltinFunctionIndex::get_expose_exception_index().index() as usize] =
+            wasmer_vm_expose_exception as usize;
+        ptrs[VMBuiltinFunctionIndex::get_epoch_deadline_reached_index().index() as usize] =
+            wasmer_vm_epoch_deadline_reached as usize;
+
         debug_assert!(ptrs.iter().cloned().all(|p| p != 0));
 
         Self { ptrs }
@@ -0,0 +1,813 @@
+//! The `bindgen!` macro of `wai-bindgen-wasmer`.
+//!
+//! Unlike the `import!` and `export!` macros, which generate glue for core
+//! modules following the WAI canonical ABI, `bindgen!` generates bindings
+//! to the component model API of Wasmer (`wasmer::component`). Each WAI
+//! interface is mapped to an instance of the component with the name of
+//! the interface, and its resources, types and functions follow the
+//! naming of the component model (`[method]counter.inc`, ...).
+
+use std::path::{Path, PathBuf};
+
+use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{braced, bracketed, Error, Ident, LitStr, Result, Token};
+use wai_parser::{Docs, Function, FunctionKind, Interface, ResourceId, Type, TypeDefKind};
+
+/// Generates bindings to run components with the given WAI interfaces.
+///
+/// ```ignore
+/// wai_bindgen_wasmer::bindgen!({
+///     imports: ["host.wai"],
+///     exports: ["plugin.wai"],
+/// });
+/// ```
+///
+/// Paths are relative to the directory of the manifest of the crate. For
+/// each interface, a module with the name of the interface is generated,
+/// containing its types and:
+///
+/// * for the `imports` of the component, a trait implemented by the host
+///   and an `add_to_linker` function defining the interface in a
+///   `wasmer::component::Linker`;
+/// * for the `exports` of the component, a struct with a method calling
+///   each function of the interface.
+#[proc_macro]
+pub fn bindgen(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let opts = syn::parse_macro_input!(input as Opts);
+    let mut tokens = TokenStream::new();
+    for (direction, iface, span) in &opts.interfaces {
+        let gen = Generator {
+            iface,
+            direction: *direction,
+            span: *span,
+        };
+        match gen.generate() {
+            Ok(module) => tokens.extend(module),
+            Err(err) => tokens.extend(err.to_compile_error()),
+        }
+    }
+
+    // Include a dummy `include_str!` for any files we read so rustc knows that
+    // we depend on the contents of those files.
+    for file in &opts.files {
+        let file = file.display().to_string();
+        tokens.extend(quote!(
+            const _: &str = include_str!(#file);
+        ));
+    }
+    tokens.into()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    /// The interface is imported by the component and implemented by the
+    /// host.
+    Import,
+    /// The interface is exported by the component and called by the host.
+    Export,
+}
+
+struct Opts {
+    interfaces: Vec<(Direction, Interface, Span)>,
+    files: Vec<PathBuf>,
+}
+
+struct ConfigField {
+    direction: Direction,
+    paths: Vec<LitStr>,
+}
+
+impl Parse for ConfigField {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let name = input.parse::<Ident>()?;
+        let direction = match name.to_string().as_str() {
+            "imports" => Direction::Import,
+            "exports" => Direction::Export,
+            _ => return Err(Error::new(name.span(), "expected `imports` or `exports`")),
+        };
+        input.parse::<Token![:]>()?;
+        let content;
+        bracketed!(content in input);
+        let paths = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+        Ok(Self {
+            direction,
+            paths: paths.into_iter().collect(),
+        })
+    }
+}
+
+impl Parse for Opts {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let content;
+        braced!(content in input);
+        let fields = Punctuated::<ConfigField, Token![,]>::parse_terminated(&content)?;
+
+        let cwd = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let mut interfaces = Vec::new();
+        let mut files = Vec::new();
+        for field in fields {
+            for path in field.paths {
+                let file = Path::new(&cwd).join(path.value());
+                let iface = Interface::parse_file(&file)
+                    .map_err(|err| Error::new(path.span(), format!("{err:?}")))?;
+                interfaces.push((field.direction, iface, path.span()));
+                files.push(file);
+            }
+        }
+        Ok(Self { interfaces, files })
+    }
+}
+
+/// How handles to resources are mapped to Rust types.
+enum Handles<'a> {
+    /// Handles are not supported, as in named types.
+    Forbidden,
+    /// `Resource<#owner::Name>`, the resources being associated types of
+    /// the trait implemented by the host.
+    Host(&'a TokenStream),
+    /// `ResourceAny`, for the resources defined by the component.
+    Guest,
+}
+
+struct Generator<'a> {
+    iface: &'a Interface,
+    direction: Direction,
+    span: Span,
+}
+
+impl Generator<'_> {
+    fn generate(&self) -> Result<TokenStream> {
+        if let Some(global) = self.iface.globals.first() {
+            return Err(self.error(format!("global `{}` is not supported", global.name)));
+        }
+
+        let mut items = Vec::new();
+        for (_, def) in self.iface.types.iter() {
+            if let Some(name) = &def.name {
+                items.push(self.type_def(name, &def.kind, &def.docs)?);
+            }
+        }
+        items.push(match self.direction {
+            Direction::Import => self.import()?,
+            Direction::Export => self.export()?,
+        });
+
+        let module = ident(&self.iface.name);
+        Ok(quote! {
+            #[allow(clippy::all, unused_imports, unused_mut)]
+            pub mod #module {
+                use wai_bindgen_wasmer::wasmer;
+
+                #(#items)*
+            }
+        })
+    }
+
+    fn error(&self, msg: impl std::fmt::Display) -> Error {
+        Error::new(
+            self.span,
+            format!("in interface `{}`: {msg}", self.iface.name),
+        )
+    }
+
+    /// The Rust type of a WAI type.
+    fn ty(&self, ty: &Type, handles: &Handles<'_>) -> Result<TokenStream> {
+        Ok(match ty {
+            Type::Unit => quote!(()),
+            Type::Bool => quote!(bool),
+            Type::U8 => quote!(u8),
+            Type::U16 => quote!(u16),
+            Type::U32 => quote!(u32),
+            Type::U64 => quote!(u64),
+            Type::S8 => quote!(i8),
+            Type::S16 => quote!(i16),
+            Type::S32 => quote!(i32),
+            Type::S64 => quote!(i64),
+            Type::Float32 => quote!(f32),
+            Type::Float64 => quote!(f64),
+            Type::Char => quote!(char),
+            Type::String => quote!(String),
+            Type::Handle(resource) => match handles {
+                Handles::Forbidden => {
+                    let name = &self.iface.resources[*resource].name;
+                    return Err(self.error(format!(
+                        "handle to `{name}` in a named type, handles are only supported in \
+                         the parameters and results of functions"
+                    )));
+                }
+                Handles::Host(owner) => {
+                    let name = type_ident(&self.iface.resources[*resource].name);
+                    quote!(wasmer::component::Resource<#owner::#name>)
+                }
+                Handles::Guest => quote!(wasmer::component::ResourceAny),
+            },
+            Type::Id(id) => {
+                let def = &self.iface.types[*id];
+                if let Some(name) = &def.name {
+                    let name = type_ident(name);
+                    return Ok(quote!(#name));
+                }
+                self.anonymous_ty(&def.kind, handles)?
+            }
+        })
+    }
+
+    /// The Rust type of the definition of an anonymous type.
+    fn anonymous_ty(&self, kind: &TypeDefKind, handles: &Handles<'_>) -> Result<TokenStream> {
+        Ok(match kind {
+            TypeDefKind::Option(ty) => {
+                let ty = self.ty(ty, handles)?;
+                quote!(Option<#ty>)
+            }
+            TypeDefKind::Expected(expected) => {
+                let ok = self.ty(&expected.ok, handles)?;
+                let err = self.ty(&expected.err, handles)?;
+                quote!(Result<#ok, #err>)
+            }
+            TypeDefKind::List(ty) => {
+                let ty = self.ty(ty, handles)?;
+                quote!(Vec<#ty>)
+            }
+            TypeDefKind::Tuple(tuple) => {
+                let types = tuple
+                    .types
+                    .iter()
+                    .map(|ty| self.ty(ty, handles))
+                    .collect::<Result<Vec<_>>>()?;
+                quote!((#(#types,)*))
+            }
+            TypeDefKind::Type(ty) => self.ty(ty, handles)?,
+            kind => return Err(self.error(format!("unsupported type {kind:?}"))),
+        })
+    }
+
+    fn type_def(&self, name: &str, kind: &TypeDefKind, docs: &Docs) -> Result<TokenStream> {
+        let docs = doc_attrs(docs);
+        let rust_name = type_ident(name);
+        let component = quote!(wasmer::component);
+        let check_names = |names: &[String]| {
+            let len = names.len();
+            quote!(eq::<[&str; #len]>([#(#names),*]))
+        };
+
+        Ok(match kind {
+            TypeDefKind::Record(record) => {
+                let names = record
+                    .fields
+                    .iter()
+                    .map(|field| field.name.clone())
+                    .collect::<Vec<_>>();
+                let fields = names.iter().map(|name| ident(name)).collect::<Vec<_>>();
+                let field_docs = record.fields.iter().map(|field| doc_attrs(&field.docs));
+                let types = record
+                    .fields
+                    .iter()
+                    .map(|field| self.ty(&field.ty, &Handles::Forbidden))
+                    .collect::<Result<Vec<_>>>()?;
+                let indices = 0..fields.len();
+                let check_names = check_names(&names);
+                quote! {
+                    #docs
+                    #[derive(Clone, Debug, PartialEq)]
+                    pub struct #rust_name {
+                        #(#field_docs pub #fields: #types,)*
+                    }
+
+                    impl #component::ComponentType for #rust_name {
+                        fn typecheck(ty: &#component::Type) -> Result<(), String> {
+                            match ty {
+                                #component::Type::Record(fields)
+                                    if fields.iter().map(|(name, _)| name.as_str()).#check_names =>
+                                {
+                                    #(<#types as #component::ComponentType>::typecheck(&fields[#indices].1)?;)*
+                                    Ok(())
+                                }
+                                ty => Err(#component::__internal::type_mismatch(#name, ty)),
+                            }
+                        }
+
+                        fn into_val(self) -> #component::Val {
+                            #component::Val::Record(vec![
+                                #((#names.to_string(), #component::ComponentType::into_val(self.#fields)),)*
+                            ])
+                        }
+
+                        fn from_val(val: #component::Val) -> Result<Self, wasmer::RuntimeError> {
+                            match val {
+                                #component::Val::Record(fields)
+                                    if fields.iter().map(|(name, _)| name.as_str()).#check_names =>
+                                {
+                                    let mut fields = fields.into_iter().map(|(_, val)| val);
+                                    Ok(Self {
+                                        #(#fields: #component::ComponentType::from_val(fields.next().unwrap())?,)*
+                                    })
+                                }
+                                val => Err(#component::__internal::mismatch(#name, &val)),
+                            }
+                        }
+                    }
+                }
+            }
+            TypeDefKind::Variant(variant) => {
+                let names = variant
+                    .cases
+                    .iter()
+                    .map(|case| case.name.clone())
+                    .collect::<Vec<_>>();
+                let check_names = check_names(&names);
+                let mut cases = Vec::new();
+                let mut typechecks = Vec::new();
+                let mut into_vals = Vec::new();
+                let mut from_vals = Vec::new();
+                for (index, case) in variant.cases.iter().enumerate() {
+                    let docs = doc_attrs(&case.docs);
+                    let case_name = &case.name;
+                    let rust_case = type_ident(case_name);
+                    let ty = self.ty(&case.ty, &Handles::Forbidden)?;
+                    typechecks.push(quote! {
+                        <#ty as #component::ComponentType>::typecheck_payload(cases[#index].1.as_ref())?;
+                    });
+                    if case.ty == Type::Unit {
+                        cases.push(quote!(#docs #rust_case));
+                        into_vals.push(quote! {
+                            Self::#rust_case => #component::Val::Variant(#case_name.to_string(), None)
+                        });
+                        from_vals.push(quote! {
+                            #case_name => {
+                                <() as #component::ComponentType>::from_payload(payload)?;
+                                Ok(Self::#rust_case)
+                            }
+                        });
+                    } else {
+                        cases.push(quote!(#docs #rust_case(#ty)));
+                        into_vals.push(quote! {
+                            Self::#rust_case(payload) => #component::Val::Variant(
+                                #case_name.to_string(),
+                                #component::ComponentType::into_payload(payload),
+                            )
+                        });
+                        from_vals.push(quote! {
+                            #case_name => Ok(Self::#rust_case(
+                                #component::ComponentType::from_payload(payload)?,
+                            ))
+                        });
+                    }
+                }
+                quote! {
+                    #docs
+                    #[derive(Clone, Debug, PartialEq)]
+                    pub enum #rust_name {
+                        #(#cases,)*
+                    }
+
+                    impl #component::ComponentType for #rust_name {
+                        fn typecheck(ty: &#component::Type) -> Result<(), String> {
+                            match ty {
+                                #component::Type::Variant(cases)
+                                    if cases.iter().map(|(name, _)| name.as_str()).#check_names =>
+                                {
+                                    #(#typechecks)*
+                                    Ok(())
+                                }
+                                ty => Err(#component::__internal::type_mismatch(#name, ty)),
+                            }
+                        }
+
+                        fn into_val(self) -> #component::Val {
+                            match self {
+                                #(#into_vals,)*
+                            }
+                        }
+
+                        fn from_val(val: #component::Val) -> Result<Self, wasmer::RuntimeError> {
+                            match val {
+                                #component::Val::Variant(case, payload) => match case.as_str() {
+                                    #(#from_vals,)*
+                                    _ => Err(wasmer::RuntimeError::new(format!(
+                                        "unknown case `{case}` of `{}`",
+                                        #name,
+                                    ))),
+                                },
+                                val => Err(#component::__internal::mismatch(#name, &val)),
+                            }
+                        }
+                    }
+                }
+            }
+            TypeDefKind::Enum(enum_) => {
+                let names = enum_
+                    .cases
+                    .iter()
+                    .map(|case| case.name.clone())
+                    .collect::<Vec<_>>();
+                let check_names = check_names(&names);
+                let cases = names
+                    .iter()
+                    .map(|name| type_ident(name))
+                    .collect::<Vec<_>>();
+                let case_docs = enum_.cases.iter().map(|case| doc_attrs(&case.docs));
+                quote! {
+                    #docs
+                    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+                    pub enum #rust_name {
+                        #(#case_docs #cases,)*
+                    }
+
+                    impl #component::ComponentType for #rust_name {
+                        fn typecheck(ty: &#component::Type) -> Result<(), String> {
+                            match ty {
+                                #component::Type::Enum(cases)
+                                    if cases.iter().map(String::as_str).#check_names =>
+                                {
+                                    Ok(())
+                                }
+                                ty => Err(#component::__internal::type_mismatch(#name, ty)),
+                            }
+                        }
+
+                        fn into_val(self) -> #component::Val {
+                            match self {
+                                #(Self::#cases => #component::Val::Enum(#names.to_string()),)*
+                            }
+                        }
+
+                        fn from_val(val: #component::Val) -> Result<Self, wasmer::RuntimeError> {
+                            match val {
+                                #component::Val::Enum(case) => match case.as_str() {
+                                    #(#names => Ok(Self::#cases),)*
+                                    _ => Err(wasmer::RuntimeError::new(format!(
+                                        "unknown case `{case}` of `{}`",
+                                        #name,
+                                    ))),
+                                },
+                                val => Err(#component::__internal::mismatch(#name, &val)),
+                            }
+                        }
+                    }
+                }
+            }
+            TypeDefKind::Flags(flags) => {
+                let names = flags
+                    .flags
+                    .iter()
+                    .map(|flag| flag.name.clone())
+                    .collect::<Vec<_>>();
+                let check_names = check_names(&names);
+                let repr = match names.len() {
+                    n if n <= 8 => quote!(u8),
+                    n if n <= 16 => quote!(u16),
+                    n if n <= 32 => quote!(u32),
+                    n if n <= 64 => quote!(u64),
+                    _ => return Err(self.error(format!("too many flags in `{name}`"))),
+                };
+                let consts = names
+                    .iter()
+                    .map(|name| format_ident!("{}", name.to_shouty_snake_case()))
+                    .collect::<Vec<_>>();
+                let flag_docs = flags.flags.iter().map(|flag| doc_attrs(&flag.docs));
+                let bits = 0..names.len();
+                quote! {
+                    wai_bindgen_wasmer::bitflags::bitflags! {
+                        #docs
+                        #[derive(Default)]
+                        pub struct #rust_name: #repr {
+                            #(#flag_docs const #consts = 1 << #bits;)*
+                        }
+                    }
+
+                    impl #component::ComponentType for #rust_name {
+                        fn typecheck(ty: &#component::Type) -> Result<(), String> {
+                            match ty {
+                                #component::Type::Flags(flags)
+                                    if flags.iter().map(String::as_str).#check_names =>
+                                {
+                                    Ok(())
+                                }
+                                ty => Err(#component::__internal::type_mismatch(#name, ty)),
+                            }
+                        }
+
+                        fn into_val(self) -> #component::Val {
+                            let mut flags = Vec::new();
+                            #(
+                                if self.contains(Self::#consts) {
+                                    flags.push(#names.to_string());
+                                }
+                            )*
+                            #component::Val::Flags(flags)
+                        }
+
+                        fn from_val(val: #component::Val) -> Result<Self, wasmer::RuntimeError> {
+                            match val {
+                                #component::Val::Flags(flags) => {
+                                    let mut value = Self::empty();
+                                    for flag in &flags {
+                                        value |= match flag.as_str() {
+                                            #(#names => Self::#consts,)*
+                                            _ => return Err(wasmer::RuntimeError::new(format!(
+                                                "unknown flag `{flag}` of `{}`",
+                                                #name,
+                                            ))),
+                                        };
+                                    }
+                                    Ok(value)
+                                }
+                                val => Err(#component::__internal::mismatch(#name, &val)),
+                            }
+                        }
+                    }
+                }
+            }
+            TypeDefKind::Tuple(_)
+            | TypeDefKind::Option(_)
+            | TypeDefKind::Expected(_)
+            | TypeDefKind::List(_)
+            | TypeDefKind::Type(_) => {
+                let ty = self.anonymous_ty(kind, &Handles::Forbidden)?;
+                quote! {
+                    #docs
+                    pub type #rust_name = #ty;
+                }
+            }
+            TypeDefKind::Union(_) | TypeDefKind::Future(_) | TypeDefKind::Stream(_) => {
+                return Err(self.error(format!(
+                    "type `{name}` is not supported by the component model"
+                )));
+            }
+        })
+    }
+
+    /// The name of a function in the instance of the interface.
+    fn component_name(&self, func: &Function) -> String {
+        match &func.kind {
+            FunctionKind::Freestanding => func.name.clone(),
+            FunctionKind::Static { resource, name } => {
+                format!("[static]{}.{name}", self.iface.resources[*resource].name)
+            }
+            FunctionKind::Method { resource, name } => {
+                format!("[method]{}.{name}", self.iface.resources[*resource].name)
+            }
+        }
+    }
+
+    /// The name of the Rust function or method of a function.
+    fn function_ident(&self, func: &Function) -> Ident {
+        match &func.kind {
+            FunctionKind::Freestanding => ident(&func.name),
+            FunctionKind::Static { resource, name } | FunctionKind::Method { resource, name } => {
+                ident(&format!("{}-{name}", self.iface.resources[*resource].name))
+            }
+        }
+    }
+
+    fn signature(&self, func: &Function, handles: &Handles<'_>) -> Result<Signature> {
+        if func.is_async {
+            return Err(self.error(format!("async function `{}` is not supported", func.name)));
+        }
+        let mut params = Vec::new();
+        let mut types = Vec::new();
+        for (name, ty) in &func.params {
+            params.push(param_ident(name));
+            types.push(self.ty(ty, handles)?);
+        }
+        let result = if func.result == Type::Unit {
+            None
+        } else {
+            Some(self.ty(&func.result, handles)?)
+        };
+        Ok(Signature {
+            params,
+            types,
+            result,
+        })
+    }
+
+    fn resources(&self) -> impl Iterator<Item = (ResourceId, &str)> {
+        self.iface
+            .resources
+            .iter()
+            .map(|(id, resource)| (id, resource.name.as_str()))
+    }
+
+    /// The trait implemented by the host and its `add_to_linker` function.
+    fn import(&self) -> Result<TokenStream> {
+        let trait_name = type_ident(&self.iface.name);
+        let iface_name = &self.iface.name;
+        let self_ty = quote!(Self);
+        let host_ty = quote!(U);
+
+        let mut assoc_types = Vec::new();
+        let mut drops = Vec::new();
+        let mut resources = Vec::new();
+        for (id, name) in self.resources() {
+            let docs = doc_attrs(&self.iface.resources[id].docs);
+            let rust_name = type_ident(name);
+            let drop = format_ident!("drop_{}", name.to_snake_case());
+            let param = ident(name);
+            assoc_types.push(quote! {
+                #docs
+                type #rust_name: 'static;
+            });
+            drops.push(quote! {
+                /// Drops the resource of an owned handle dropped by the
+                /// component.
+                fn #drop(
+                    &mut self,
+                    #param: wasmer::component::Resource<Self::#rust_name>,
+                ) -> Result<(), wasmer::RuntimeError>;
+            });
+            resources.push(quote! {
+                instance.resource(
+                    #name,
+                    wasmer::component::ResourceType::host::<U::#rust_name>(),
+                    move |mut __env: wasmer::FunctionEnvMut<T>, rep| {
+                        get(__env.data_mut()).#drop(wasmer::component::Resource::new_own(rep))
+                    },
+                );
+            });
+        }
+
+        let mut methods = Vec::new();
+        let mut funcs = Vec::new();
+        for func in &self.iface.functions {
+            let docs = doc_attrs(&func.docs);
+            let name = self.function_ident(func);
+            let component_name = self.component_name(func);
+            let Signature {
+                params,
+                types,
+                result,
+            } = self.signature(func, &Handles::Host(&self_ty))?;
+            let result_ty = result.clone().unwrap_or_else(|| quote!(()));
+            methods.push(quote! {
+                #docs
+                fn #name(&mut self, #(#params: #types),*) -> Result<#result_ty, wasmer::RuntimeError>;
+            });
+
+            let Signature { types, .. } = self.signature(func, &Handles::Host(&host_ty))?;
+            let call = quote!(get(__env.data_mut()).#name(#(#params),*)?);
+            let ret = match result {
+                Some(_) => quote!(Ok((#call,))),
+                None => quote! {
+                    #call;
+                    Ok(())
+                },
+            };
+            funcs.push(quote! {
+                instance.func_wrap(
+                    #component_name,
+                    move |mut __env: wasmer::FunctionEnvMut<T>, (#(#params,)*): (#(#types,)*)| {
+                        #ret
+                    },
+                );
+            });
+        }
+
+        Ok(quote! {
+            /// The functions of the interface, implemented by the host.
+            pub trait #trait_name: Send + 'static {
+                #(#assoc_types)*
+                #(#methods)*
+                #(#drops)*
+            }
+
+            /// Defines the interface in `linker`, using the implementation
+            /// returned by `get` for the data of its environment.
+            pub fn add_to_linker<T, U>(
+                linker: &mut wasmer::component::Linker<T>,
+                get: fn(&mut T) -> &mut U,
+            ) where
+                T: Send + 'static,
+                U: #trait_name,
+            {
+                let mut instance = linker.instance(#iface_name);
+                #(#resources)*
+                #(#funcs)*
+            }
+        })
+    }
+
+    /// The struct calling the functions of the interface.
+    fn export(&self) -> Result<TokenStream> {
+        let struct_name = type_ident(&self.iface.name);
+        let iface_name = &self.iface.name;
+
+        let mut fields = Vec::new();
+        let mut inits = Vec::new();
+        let mut methods = Vec::new();
+        for func in &self.iface.functions {
+            let docs = doc_attrs(&func.docs);
+            let name = self.function_ident(func);
+            let component_name = self.component_name(func);
+            let Signature {
+                params,
+                types,
+                result,
+            } = self.signature(func, &Handles::Guest)?;
+            let (results, result_ty, ret) = match result {
+                Some(ty) => (
+                    quote!((#ty,)),
+                    ty,
+                    quote! {
+                        let (result,) = self.#name.call(store, (#(#params,)*))?;
+                        Ok(result)
+                    },
+                ),
+                None => (
+                    quote!(()),
+                    quote!(()),
+                    quote!(self.#name.call(store, (#(#params,)*))),
+                ),
+            };
+            fields.push(quote! {
+                #name: wasmer::component::TypedFunc<(#(#types,)*), #results>
+            });
+            inits.push(quote! {
+                #name: exports.get_typed_func(#component_name)?
+            });
+            methods.push(quote! {
+                #docs
+                pub fn #name(
+                    &self,
+                    store: &mut impl wasmer::AsStoreMut,
+                    #(#params: #types),*
+                ) -> Result<#result_ty, wasmer::RuntimeError> {
+                    #ret
+                }
+            });
+        }
+
+        Ok(quote! {
+            /// The functions of the interface, exported by a component.
+            #[derive(Clone, Debug)]
+            pub struct #struct_name {
+                #(#fields,)*
+            }
+
+            impl #struct_name {
+                /// Looks up the functions of the interface in the exports
+                /// of `instance`, checking their types.
+                pub fn new(
+                    instance: &wasmer::component::Instance,
+                ) -> Result<Self, wasmer::ExportError> {
+                    let exports = instance.exports.get_instance(#iface_name)?;
+                    Ok(Self {
+                        #(#inits,)*
+                    })
+                }
+
+                #(#methods)*
+            }
+        })
+    }
+}
+
+struct Signature {
+    params: Vec<Ident>,
+    types: Vec<TokenStream>,
+    result: Option<TokenStream>,
+}
+
+fn doc_attrs(docs: &Docs) -> TokenStream {
+    let lines = docs
+        .contents
+        .iter()
+        .flat_map(|contents| contents.lines())
+        .map(|line| format!(" {}", line.trim_end()));
+    quote!(#(#[doc = #lines])*)
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// A snake case identifier, with a trailing underscore for keywords.
+fn ident(name: &str) -> Ident {
+    let name = name.to_snake_case();
+    if KEYWORDS.contains(&name.as_str()) {
+        format_ident!("{name}_")
+    } else {
+        format_ident!("{name}")
+    }
+}
+
+/// The identifier of a parameter, which must not shadow the names used by
+/// the generated code.
+fn param_ident(name: &str) -> Ident {
+    match name.to_snake_case().as_str() {
+        "store" | "get" => format_ident!("{}_", name.to_snake_case()),
+        _ => ident(name),
+    }
+}
+
+fn type_ident(name: &str) -> Ident {
+    format_ident!("{}", name.to_upper_camel_case())
+}
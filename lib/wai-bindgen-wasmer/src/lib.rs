@@ -1,6 +1,8 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
 pub use wai_bindgen_wasmer_impl::{export, import};
+#[cfg(feature = "component-model")]
+pub use wai_bindgen_wasmer_macro::bindgen;
 
 #[cfg(feature = "async")]
 pub use async_trait::async_trait;
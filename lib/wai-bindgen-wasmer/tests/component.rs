@@ -0,0 +1,217 @@
+#![cfg(all(feature = "sys", feature = "cranelift", feature = "component-model"))]
+
+use anyhow::Result;
+use wasmer::component::{Component, Linker, Resource, ResourceTable};
+use wasmer::{FunctionEnv, RuntimeError, Store};
+
+wai_bindgen_wasmer::bindgen!({
+    imports: ["tests/component/host.wai"],
+    exports: ["tests/component/plugin.wai"],
+});
+
+use host::Level;
+use plugin::{Perms, Point, Shape};
+
+const COMPONENT: &str = r#"(component
+    (import "host" (instance $host
+        (export "counter" (type (sub resource)))
+        (export "[static]counter.new" (func (param "init" u32) (result (own 0))))
+        (export "[method]counter.inc" (func (param "self" (borrow 0)) (result u32)))
+        (type $level' (enum "info" "warn"))
+        (export $level "level" (type (eq $level')))
+        (export "log" (func (param "level" $level) (param "msg" string)))))
+    (alias export $host "counter" (type $counter))
+
+    (core module $libc
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr
+                (i32.and
+                    (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+                    (i32.sub (i32.const 0) (local.get 2))))
+            (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+            (local.get $ptr)))
+    (core instance $libc (instantiate $libc))
+
+    (core func $new (canon lower (func $host "[static]counter.new")))
+    (core func $inc (canon lower (func $host "[method]counter.inc")))
+    (core func $log (canon lower (func $host "log")
+        (memory $libc "memory") (realloc (func $libc "realloc"))))
+    (core func $drop (canon resource.drop $counter))
+    (core module $m
+        (import "libc" "memory" (memory 1))
+        (import "host" "new" (func $new (param i32) (result i32)))
+        (import "host" "inc" (func $inc (param i32) (result i32)))
+        (import "host" "log" (func $log (param i32 i32 i32)))
+        (import "host" "drop" (func $drop (param i32)))
+        (data (i32.const 0) "done")
+        (data (i32.const 64) "division by zero")
+        (func (export "run") (param i32) (result i32)
+            (local $h i32)
+            (local $n i32)
+            (local.set $h (call $new (local.get 0)))
+            (drop (call $inc (local.get $h)))
+            (local.set $n (call $inc (local.get $h)))
+            (call $log (i32.const 1) (i32.const 0) (i32.const 4))
+            (call $drop (local.get $h))
+            (local.get $n))
+        (func (export "swap") (param i32 i32) (result i32)
+            (i32.store (i32.const 16) (local.get 1))
+            (i32.store (i32.const 20) (local.get 0))
+            (i32.const 16))
+        (func (export "flip") (param i32) (result i32)
+            (i32.xor (local.get 0) (i32.const 7)))
+        (func (export "area") (param i32 i32) (result i32)
+            (if (result i32) (i32.eqz (local.get 0))
+                (then (i32.mul (i32.const 3) (i32.mul (local.get 1) (local.get 1))))
+                (else (if (result i32) (i32.eq (local.get 0) (i32.const 1))
+                    (then (i32.mul (local.get 1) (local.get 1)))
+                    (else (i32.const 0))))))
+        (func (export "divide") (param i32 i32) (result i32)
+            (if (i32.eqz (local.get 1))
+                (then
+                    (i32.store8 (i32.const 32) (i32.const 1))
+                    (i32.store (i32.const 36) (i32.const 64))
+                    (i32.store (i32.const 40) (i32.const 16)))
+                (else
+                    (i32.store8 (i32.const 32) (i32.const 0))
+                    (i32.store (i32.const 36) (i32.div_u (local.get 0) (local.get 1)))))
+            (i32.const 32)))
+    (core instance $i (instantiate $m
+        (with "libc" (instance $libc))
+        (with "host" (instance
+            (export "new" (func $new))
+            (export "inc" (func $inc))
+            (export "log" (func $log))
+            (export "drop" (func $drop))))))
+
+    (type $point' (record (field "x" s32) (field "y" s32)))
+    (type $perms' (flags "read" "write" "exec"))
+    (type $shape' (variant (case "circle" u32) (case "square" u32) (case "empty")))
+    (export $point "point" (type $point'))
+    (export $perms "perms" (type $perms'))
+    (export $shape "shape" (type $shape'))
+    (func $run (param "init" u32) (result u32)
+        (canon lift (core func $i "run")))
+    (func $swap (param "p" $point) (result $point)
+        (canon lift (core func $i "swap") (memory $libc "memory")))
+    (func $flip (param "p" $perms) (result $perms)
+        (canon lift (core func $i "flip")))
+    (func $area (param "s" $shape) (result u32)
+        (canon lift (core func $i "area")))
+    (func $divide (param "a" u32) (param "b" u32) (result (result u32 (error string)))
+        (canon lift (core func $i "divide") (memory $libc "memory")))
+    (instance $plugin
+        (export "point" (type $point))
+        (export "perms" (type $perms))
+        (export "shape" (type $shape))
+        (export "run" (func $run))
+        (export "swap" (func $swap))
+        (export "flip" (func $flip))
+        (export "area" (func $area))
+        (export "divide" (func $divide)))
+    (export "plugin" (instance $plugin))
+)"#;
+
+struct Counter(u32);
+
+#[derive(Default)]
+struct Host {
+    table: ResourceTable,
+    dropped: Vec<u32>,
+    logs: Vec<(Level, String)>,
+}
+
+impl host::Host for Host {
+    type Counter = Counter;
+
+    fn counter_new(&mut self, init: u32) -> Result<Resource<Counter>, RuntimeError> {
+        Ok(self.table.push(Counter(init)))
+    }
+
+    fn counter_inc(&mut self, counter: Resource<Counter>) -> Result<u32, RuntimeError> {
+        let counter = self
+            .table
+            .get_mut(&counter)
+            .map_err(|err| RuntimeError::new(err.to_string()))?;
+        counter.0 += 1;
+        Ok(counter.0)
+    }
+
+    fn log(&mut self, level: Level, msg: String) -> Result<(), RuntimeError> {
+        self.logs.push((level, msg));
+        Ok(())
+    }
+
+    fn drop_counter(&mut self, counter: Resource<Counter>) -> Result<(), RuntimeError> {
+        let Counter(value) = self
+            .table
+            .delete(counter)
+            .map_err(|err| RuntimeError::new(err.to_string()))?;
+        self.dropped.push(value);
+        Ok(())
+    }
+}
+
+fn instantiate(store: &mut Store) -> Result<(FunctionEnv<Host>, plugin::Plugin)> {
+    let component = Component::new(store, COMPONENT)?;
+    let env = FunctionEnv::new(store, Host::default());
+    let mut linker = Linker::new(&env);
+    host::add_to_linker(&mut linker, |host: &mut Host| host);
+    let instance = linker.instantiate(store, &component)?;
+    Ok((env, plugin::Plugin::new(&instance)?))
+}
+
+#[test]
+fn imports() -> Result<()> {
+    let mut store = Store::default();
+    let (env, plugin) = instantiate(&mut store)?;
+    assert_eq!(plugin.run(&mut store, 10)?, 12);
+    let host = env.as_ref(&store);
+    assert_eq!(host.dropped, [12]);
+    assert_eq!(host.logs, [(Level::Warn, "done".to_string())]);
+    Ok(())
+}
+
+#[test]
+fn exports() -> Result<()> {
+    let mut store = Store::default();
+    let (_, plugin) = instantiate(&mut store)?;
+    assert_eq!(
+        plugin.swap(&mut store, Point { x: 1, y: -2 })?,
+        Point { x: -2, y: 1 }
+    );
+    assert_eq!(
+        plugin.flip(&mut store, Perms::WRITE)?,
+        Perms::READ | Perms::EXEC
+    );
+    assert_eq!(plugin.area(&mut store, Shape::Circle(2))?, 12);
+    assert_eq!(plugin.area(&mut store, Shape::Square(3))?, 9);
+    assert_eq!(plugin.area(&mut store, Shape::Empty)?, 0);
+    assert_eq!(plugin.divide(&mut store, 7, 2)?, Ok(3));
+    assert_eq!(
+        plugin.divide(&mut store, 7, 0)?,
+        Err("division by zero".to_string())
+    );
+    Ok(())
+}
+
+#[test]
+fn mismatched_imports() -> Result<()> {
+    let mut store = Store::default();
+    let component = Component::new(
+        &store,
+        r#"(component
+            (import "host" (instance
+                (export "log" (func (param "level" u32) (param "msg" string)))))
+        )"#,
+    )?;
+    let env = FunctionEnv::new(&mut store, Host::default());
+    let mut linker = Linker::new(&env);
+    host::add_to_linker(&mut linker, |host: &mut Host| host);
+    let err = linker.instantiate(&mut store, &component).unwrap_err();
+    assert!(err.to_string().contains("host/log"), "{err}");
+    Ok(())
+}
@@ -17,6 +17,8 @@ pub type TlVal = u64;
 pub type TlUser = u64;
 /// Long size used by checkpoints
 pub type Longsize = u64;
+/// Handle to a dynamically loaded library
+pub type DlHandle = u32;
 
 /// The contents of a `subscription`, snapshot0 version.
 #[repr(C)]
@@ -505,3 +507,39 @@ where
     #[inline]
     fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
 }
+
+wai_bindgen_rust::bitflags::bitflags! {
+    #[doc = " Flags that control how `dlopen` loads a library."]
+    #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+    pub struct DlFlags : u32 {
+        #[doc = " Resolve undefined symbols as the code that references them is"]
+        #[doc = " executed. Symbols are always resolved when the library is"]
+        #[doc = " loaded, so this behaves like `NOW`."]
+        const LAZY = 1 << 0;
+        #[doc = " Resolve all undefined symbols before `dlopen` returns."]
+        const NOW = 1 << 1;
+        #[doc = " Make the symbols of the library available to the libraries"]
+        #[doc = " that are loaded after it."]
+        const GLOBAL = 1 << 2;
+        #[doc = " Only return a handle if the library is already loaded."]
+        const NOLOAD = 1 << 3;
+        #[doc = " Keep the library loaded when `dlclose` is called."]
+        const NODELETE = 1 << 4;
+    }
+}
+
+unsafe impl wasmer::FromToNativeWasmType for DlFlags {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self.bits() as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        Self::from_bits_truncate(n as u32)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        false
+    }
+}
@@ -4,14 +4,14 @@ use anyhow::Context;
 use once_cell::sync::OnceCell;
 use sha2::Digest;
 use virtual_fs::FileSystem;
-use wasmer_config::package::{PackageHash, PackageId, PackageSource};
+use wasmer_config::package::{Lockfile, PackageHash, PackageId, PackageSource, LOCKFILE_NAME};
 use wasmer_package::package::Package;
 use webc::compat::SharedBytes;
 use webc::Container;
 
 use crate::{
     runners::MappedDirectory,
-    runtime::resolver::{PackageInfo, ResolveError},
+    runtime::resolver::{LockedSource, PackageInfo, Resolution, ResolveError, Source},
     Runtime,
 };
 use wasmer_types::ModuleHash;
@@ -61,6 +61,20 @@ impl BinaryPackageCommand {
     }
 }
 
+/// How [`BinaryPackage::from_dir_with_lockfile()`] uses the package's
+/// `wasmer.lock`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockfileMode {
+    /// Prefer the versions in the lockfile, but never write to it.
+    #[default]
+    ReadOnly,
+    /// Prefer the versions in the lockfile, and update it when the
+    /// dependencies resolve differently.
+    Update,
+    /// Fail if the dependencies would resolve differently from the lockfile.
+    Locked,
+}
+
 /// A WebAssembly package that has been loaded into memory.
 #[derive(Debug, Clone)]
 pub struct BinaryPackage {
@@ -82,27 +96,27 @@ pub struct BinaryPackage {
 }
 
 impl BinaryPackage {
+    /// Load a package from a directory containing a `wasmer.toml`, preferring
+    /// the versions in its `wasmer.lock` without ever writing to it.
     #[tracing::instrument(level = "debug", skip_all)]
     pub async fn from_dir(
         dir: &Path,
         rt: &(dyn Runtime + Send + Sync),
     ) -> Result<Self, anyhow::Error> {
-        let source = rt.source();
-
-        // since each package must be in its own directory, hash of the `dir` should provide a good enough
-        // unique identifier for the package
-        let hash = sha2::Sha256::digest(dir.display().to_string().as_bytes()).into();
-        let id = PackageId::Hash(PackageHash::from_sha256_bytes(hash));
+        Self::from_dir_with_lockfile(dir, LockfileMode::default(), rt).await
+    }
 
+    /// Load a package from a directory containing a `wasmer.toml`, resolving
+    /// its dependencies against the directory's `wasmer.lock`.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn from_dir_with_lockfile(
+        dir: &Path,
+        mode: LockfileMode,
+        rt: &(dyn Runtime + Send + Sync),
+    ) -> Result<Self, anyhow::Error> {
         let manifest_path = dir.join("wasmer.toml");
-        let webc = Package::from_manifest(&manifest_path)?;
-        let container = Container::from(webc);
-        let manifest = container.manifest();
+        let (container, resolution) = Self::resolve_dir(dir, mode, rt.source()).await?;
 
-        let root = PackageInfo::from_manifest(id, manifest, container.version())?;
-        let root_id = root.id.clone();
-
-        let resolution = crate::runtime::resolver::resolve(&root_id, &root, &*source).await?;
         let mut pkg = rt
             .package_loader()
             .load_package_tree(&container, &resolution, true)
@@ -130,6 +144,70 @@ impl BinaryPackage {
         Ok(pkg)
     }
 
+    /// Resolve the dependencies of the package in a directory containing a
+    /// `wasmer.toml`, using the directory's `wasmer.lock` as described by
+    /// `mode`.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn resolve_dir(
+        dir: &Path,
+        mode: LockfileMode,
+        source: Arc<dyn Source + Send + Sync>,
+    ) -> Result<(Container, Resolution), anyhow::Error> {
+        // since each package must be in its own directory, hash of the `dir` should provide a good enough
+        // unique identifier for the package
+        let hash = sha2::Sha256::digest(dir.display().to_string().as_bytes()).into();
+        let id = PackageId::Hash(PackageHash::from_sha256_bytes(hash));
+
+        let webc = Package::from_manifest(dir.join("wasmer.toml"))?;
+        let container = Container::from(webc);
+        let manifest = container.manifest();
+
+        let root = PackageInfo::from_manifest(id, manifest, container.version())?;
+        let root_id = root.id.clone();
+
+        let lockfile = Lockfile::find_in_directory(dir)?.unwrap_or_default();
+        let locked_source =
+            LockedSource::new(&source, lockfile.clone()).locked(mode == LockfileMode::Locked);
+
+        let resolution =
+            match crate::runtime::resolver::resolve(&root_id, &root, &locked_source).await {
+                Err(ResolveError::Conflict { .. }) if mode != LockfileMode::Locked => {
+                    // The locked versions no longer satisfy the manifest, so
+                    // start from scratch.
+                    crate::runtime::resolver::resolve(&root_id, &root, &*source).await?
+                }
+                resolution => resolution?,
+            };
+
+        let mut updated = resolution.graph.to_lockfile();
+        for pkg in &mut updated.packages {
+            // Where a package was downloaded from (e.g. a vendor directory
+            // instead of the registry) doesn't change what it resolved to
+            if let Some(locked) = lockfile.get(&pkg.id).filter(|l| l.hash == pkg.hash) {
+                pkg.url.clone_from(&locked.url);
+            }
+        }
+        if updated != lockfile {
+            match mode {
+                LockfileMode::Locked => anyhow::bail!(
+                    "The dependencies of \"{}\" don't match its {LOCKFILE_NAME}, and it can't be updated because the lockfile is locked",
+                    dir.display(),
+                ),
+                LockfileMode::ReadOnly => {}
+                LockfileMode::Update => {
+                    if let Err(e) = updated.save_to_directory(dir) {
+                        tracing::warn!(
+                            error = &e as &dyn std::error::Error,
+                            "Unable to update the lockfile",
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok((container, resolution))
+    }
+
     /// Load a [`webc::Container`] and all its dependencies into a
     /// [`BinaryPackage`].
     #[tracing::instrument(level = "debug", skip_all)]
@@ -366,4 +444,42 @@ mod tests {
         let module_hash = ModuleHash::sha256_from_bytes(atom_sha256_hash);
         assert_eq!(command.hash(), &module_hash);
     }
+
+    #[tokio::test]
+    #[cfg_attr(
+        not(feature = "sys-thread"),
+        ignore = "The tokio task manager isn't available on this platform"
+    )]
+    async fn loading_a_directory_leaves_its_lockfile_alone() {
+        let temp = TempDir::new().unwrap();
+        let wasmer_toml = r#"
+            [package]
+            name = "some/package"
+            version = "0.0.0"
+            description = "a dummy package"
+        "#;
+        std::fs::write(temp.path().join("wasmer.toml"), wasmer_toml).unwrap();
+        // A dependency that was removed from the manifest since
+        let mut stale = Lockfile::new();
+        stale.dependencies.insert(
+            "old".to_string(),
+            PackageId::Hash(PackageHash::from_sha256_bytes([0; 32])),
+        );
+        stale.save_to_directory(temp.path()).unwrap();
+        let lockfile_path = temp.path().join(LOCKFILE_NAME);
+        let before = std::fs::read_to_string(&lockfile_path).unwrap();
+
+        let tasks = task_manager();
+        let mut runtime = PluggableRuntime::new(tasks);
+        runtime.set_package_loader(
+            BuiltinPackageLoader::new()
+                .with_shared_http_client(runtime.http_client().unwrap().clone()),
+        );
+
+        BinaryPackage::from_dir(temp.path(), &runtime)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&lockfile_path).unwrap(), before);
+    }
 }
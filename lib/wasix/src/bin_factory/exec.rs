@@ -277,6 +277,7 @@ fn call_module(
         };
 
         if let Err(err) = call_ret {
+            crate::coredump::on_thread_error(&ctx, &mut store, &err);
             match err.downcast::<WasiError>() {
                 Ok(WasiError::Exit(code)) if code.is_success() => Ok(Errno::Success),
                 Ok(WasiError::ThreadExit) => Ok(Errno::Success),
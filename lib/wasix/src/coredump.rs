@@ -0,0 +1,597 @@
+//! Coredumps of WebAssembly programs that trap.
+//!
+//! The dumps follow the [tool-conventions coredump format], which is a wasm
+//! module with a `core` section naming the program, one `corestack` section
+//! per thread and the memory and globals of the program at the time it
+//! trapped. This lets post-mortem debuggers such as `wasmgdb` inspect them.
+//!
+//! The compilers don't keep track of the locals and the operand stack of
+//! the frames, so frames only record where each function was executing.
+//!
+//! [tool-conventions coredump format]: https://github.com/WebAssembly/tool-conventions/blob/main/Coredump.md
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use wasmer::{AsStoreMut, Instance, Memory, RuntimeError, Type, Value};
+
+use crate::{WasiError, WasiFunctionEnv};
+
+/// A coredump of a WebAssembly program.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coredump {
+    /// Name of the program that trapped.
+    pub executable_name: String,
+    /// The call stack of each thread, starting with the one that trapped.
+    pub stacks: Vec<CoreStack>,
+    /// The contents of the linear memory.
+    pub memory: Option<CoreMemory>,
+    /// The values of the globals of the module, in the order they are
+    /// declared.
+    pub globals: Vec<CoreGlobal>,
+}
+
+/// The call stack of a thread.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoreStack {
+    pub thread_name: String,
+    /// The frames of the stack, starting with the innermost one.
+    pub frames: Vec<CoreFrame>,
+}
+
+/// A frame of a call stack.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoreFrame {
+    /// Index of the function in the module.
+    pub func_index: u32,
+    /// Offset of the instruction that was executing, relative to the start
+    /// of the function.
+    pub code_offset: u32,
+    pub locals: Vec<CoreValue>,
+    pub stack: Vec<CoreValue>,
+}
+
+/// A snapshot of a linear memory.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoreMemory {
+    /// Maximum number of pages the memory could grow to.
+    pub maximum: Option<u32>,
+    pub shared: bool,
+    /// Whether the memory is indexed with 64-bit addresses, as for wasm64
+    /// programs.
+    pub memory64: bool,
+    /// The whole memory, which is a multiple of the page size.
+    pub data: Vec<u8>,
+}
+
+/// The value of a global.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreGlobal {
+    pub mutable: bool,
+    pub value: CoreValue,
+}
+
+/// A value in a coredump. References can't be dumped, so they are recorded as
+/// `null`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoreValue {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    V128(u128),
+    NullFuncRef,
+    NullExternRef,
+    NullExceptionRef,
+}
+
+impl Coredump {
+    pub fn new(executable_name: impl Into<String>) -> Self {
+        Coredump {
+            executable_name: executable_name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Takes a snapshot of a memory and of the globals of an instance.
+    ///
+    /// The globals that can't be read, which are the ones that aren't
+    /// exported on backends other than `sys`, are recorded as zeros.
+    pub fn capture(
+        &mut self,
+        store: &mut impl AsStoreMut,
+        instance: &Instance,
+        memory: Option<&Memory>,
+    ) {
+        if let Some(memory) = memory {
+            let ty = memory.ty(store);
+            let data = memory.view(store).copy_to_vec().unwrap_or_default();
+            self.memory = Some(CoreMemory {
+                maximum: ty.maximum.map(|pages| pages.0),
+                shared: ty.shared,
+                // The WASI version doesn't tell how the memory is indexed, and
+                // `MemoryType` has no index type as modules with 64-bit
+                // memories are rejected when they are compiled
+                memory64: false,
+                data,
+            });
+        }
+
+        self.globals = instance
+            .module()
+            .info()
+            .globals
+            .iter()
+            .map(|(index, ty)| {
+                let value = match instance.global(store, index.as_u32()) {
+                    Some(global) => CoreValue::from_value(&global.get(store)),
+                    None => CoreValue::from_raw(ty.ty, 0),
+                };
+                CoreGlobal {
+                    mutable: ty.mutability.is_mutable(),
+                    value,
+                }
+            })
+            .collect();
+    }
+
+    /// Adds the call stack of a thread, as it was when `error` was raised.
+    pub fn add_stack(&mut self, thread_name: impl Into<String>, error: &RuntimeError) {
+        let frames = error
+            .trace()
+            .iter()
+            .map(|frame| CoreFrame {
+                func_index: frame.func_index(),
+                code_offset: frame.func_offset() as u32,
+                locals: Vec::new(),
+                stack: Vec::new(),
+            })
+            .collect();
+
+        self.stacks.push(CoreStack {
+            thread_name: thread_name.into(),
+            frames,
+        });
+    }
+
+    /// Encodes the coredump as a WebAssembly module.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+
+        let mut core = vec![0x00];
+        write_name(&mut core, &self.executable_name);
+        write_section(&mut module, 0, &custom_section("core", &core));
+
+        for stack in &self.stacks {
+            let mut corestack = vec![0x00];
+            write_name(&mut corestack, &stack.thread_name);
+            write_u64(&mut corestack, stack.frames.len() as u64);
+            for frame in &stack.frames {
+                corestack.push(0x00);
+                write_u64(&mut corestack, frame.func_index.into());
+                write_u64(&mut corestack, frame.code_offset.into());
+                for values in [&frame.locals, &frame.stack] {
+                    write_u64(&mut corestack, values.len() as u64);
+                    for value in values {
+                        write_frame_value(&mut corestack, value);
+                    }
+                }
+            }
+            write_section(&mut module, 0, &custom_section("corestack", &corestack));
+        }
+
+        if let Some(memory) = &self.memory {
+            let pages = (memory.data.len() / wasmer::WASM_PAGE_SIZE) as u64;
+            let mut section = vec![1];
+            let mut flags = 0;
+            if memory.maximum.is_some() {
+                flags |= 0x01;
+            }
+            if memory.shared {
+                flags |= 0x02;
+            }
+            if memory.memory64 {
+                flags |= 0x04;
+            }
+            section.push(flags);
+            write_u64(&mut section, pages);
+            if let Some(maximum) = memory.maximum {
+                write_u64(&mut section, maximum.into());
+            }
+            write_section(&mut module, 5, &section);
+        }
+
+        if !self.globals.is_empty() {
+            let mut section = Vec::new();
+            write_u64(&mut section, self.globals.len() as u64);
+            for global in &self.globals {
+                section.push(global.value.type_byte());
+                section.push(global.mutable as u8);
+                write_const_expr(&mut section, &global.value);
+            }
+            write_section(&mut module, 6, &section);
+        }
+
+        if let Some(memory) = &self.memory {
+            // A single active segment at address 0 of memory 0
+            let mut section = vec![1, 0x00];
+            let offset = if memory.memory64 {
+                CoreValue::I64(0)
+            } else {
+                CoreValue::I32(0)
+            };
+            write_const_expr(&mut section, &offset);
+            write_u64(&mut section, memory.data.len() as u64);
+            section.extend_from_slice(&memory.data);
+            write_section(&mut module, 11, &section);
+        }
+
+        module
+    }
+
+    /// Writes the coredump to a file.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.serialize())
+    }
+}
+
+impl CoreValue {
+    fn from_raw(ty: Type, raw: u128) -> Self {
+        match ty {
+            Type::I32 => CoreValue::I32(raw as u32 as i32),
+            Type::I64 => CoreValue::I64(raw as u64 as i64),
+            Type::F32 => CoreValue::F32(f32::from_bits(raw as u32)),
+            Type::F64 => CoreValue::F64(f64::from_bits(raw as u64)),
+            Type::V128 => CoreValue::V128(raw),
+            Type::FuncRef => CoreValue::NullFuncRef,
+            Type::ExternRef => CoreValue::NullExternRef,
+            Type::ExceptionRef => CoreValue::NullExceptionRef,
+        }
+    }
+
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::I32(v) => CoreValue::I32(*v),
+            Value::I64(v) => CoreValue::I64(*v),
+            Value::F32(v) => CoreValue::F32(*v),
+            Value::F64(v) => CoreValue::F64(*v),
+            Value::V128(v) => CoreValue::V128(*v),
+            Value::FuncRef(_) => CoreValue::NullFuncRef,
+            Value::ExternRef(_) => CoreValue::NullExternRef,
+            Value::ExceptionRef(_) => CoreValue::NullExceptionRef,
+        }
+    }
+
+    fn type_byte(&self) -> u8 {
+        match self {
+            CoreValue::I32(_) => 0x7f,
+            CoreValue::I64(_) => 0x7e,
+            CoreValue::F32(_) => 0x7d,
+            CoreValue::F64(_) => 0x7c,
+            CoreValue::V128(_) => 0x7b,
+            CoreValue::NullFuncRef => 0x70,
+            CoreValue::NullExternRef => 0x6f,
+            CoreValue::NullExceptionRef => 0x69,
+        }
+    }
+}
+
+/// Collects the coredump of a WASIX process, which is written when one of its
+/// threads traps.
+///
+/// The thread that traps records the memory, its globals and its stack. The
+/// other threads add their stacks as they are terminated, and the file is
+/// rewritten each time so it always holds everything that was collected.
+#[derive(Debug)]
+pub(crate) struct CoredumpCollector {
+    path: PathBuf,
+    coredump: Mutex<Option<(Coredump, Vec<u32>)>>,
+}
+
+impl CoredumpCollector {
+    pub fn new(path: PathBuf) -> Self {
+        CoredumpCollector {
+            path,
+            coredump: Mutex::new(None),
+        }
+    }
+
+    /// Records a thread that stopped with an error, which starts the
+    /// coredump if the thread trapped.
+    pub fn on_thread_error(
+        &self,
+        env: &WasiFunctionEnv,
+        store: &mut impl AsStoreMut,
+        error: &RuntimeError,
+    ) {
+        let trapped = match error.downcast_ref::<WasiError>() {
+            // The thread will resume later on
+            Some(WasiError::DeepSleep(_)) => return,
+            Some(_) => false,
+            None => true,
+        };
+
+        let mut guard = self.coredump.lock().unwrap();
+        let thread = env.data(store).thread.clone();
+        let tid = thread.tid().raw();
+        let thread_name = if thread.is_main() {
+            "main".to_string()
+        } else {
+            format!("thread-{tid}")
+        };
+
+        match guard.as_mut() {
+            Some((coredump, tids)) => {
+                if tids.contains(&tid) {
+                    return;
+                }
+                coredump.add_stack(thread_name, error);
+                tids.push(tid);
+            }
+            None if trapped => {
+                let executable_name = env
+                    .data(store)
+                    .state
+                    .args
+                    .lock()
+                    .unwrap()
+                    .first()
+                    .cloned()
+                    .unwrap_or_default();
+                let mut coredump = Coredump::new(executable_name);
+
+                if let Some((instance, memory)) = env
+                    .data(store)
+                    .try_inner()
+                    .map(|inner| (inner.instance.clone(), inner.memory.clone()))
+                {
+                    coredump.capture(store, &instance, Some(&memory));
+                }
+                coredump.add_stack(thread_name, error);
+                *guard = Some((coredump, vec![tid]));
+            }
+            None => return,
+        }
+
+        let (coredump, _) = guard.as_ref().unwrap();
+        if let Err(e) = coredump.save(&self.path) {
+            tracing::warn!(
+                error = &e as &dyn std::error::Error,
+                path = %self.path.display(),
+                "Unable to save the coredump",
+            );
+        }
+    }
+}
+
+/// Records a thread of a WASIX process that stopped with an error in the
+/// coredump of the process, if coredumps are enabled for it.
+pub(crate) fn on_thread_error(
+    env: &WasiFunctionEnv,
+    store: &mut impl AsStoreMut,
+    error: &RuntimeError,
+) {
+    let collector = env.data(store).process.lock().coredump.clone();
+    if let Some(collector) = collector {
+        collector.on_thread_error(env, store, error);
+    }
+}
+
+fn custom_section(name: &str, payload: &[u8]) -> Vec<u8> {
+    let mut section = Vec::new();
+    write_name(&mut section, name);
+    section.extend_from_slice(payload);
+    section
+}
+
+fn write_section(module: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    module.push(id);
+    write_u64(module, contents.len() as u64);
+    module.extend_from_slice(contents);
+}
+
+fn write_name(buffer: &mut Vec<u8>, name: &str) {
+    write_u64(buffer, name.len() as u64);
+    buffer.extend_from_slice(name.as_bytes());
+}
+
+fn write_u64(buffer: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+fn write_i64(buffer: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+fn write_const_expr(buffer: &mut Vec<u8>, value: &CoreValue) {
+    match value {
+        CoreValue::I32(v) => {
+            buffer.push(0x41);
+            write_i64(buffer, (*v).into());
+        }
+        CoreValue::I64(v) => {
+            buffer.push(0x42);
+            write_i64(buffer, *v);
+        }
+        CoreValue::F32(v) => {
+            buffer.push(0x43);
+            buffer.extend_from_slice(&v.to_bits().to_le_bytes());
+        }
+        CoreValue::F64(v) => {
+            buffer.push(0x44);
+            buffer.extend_from_slice(&v.to_bits().to_le_bytes());
+        }
+        CoreValue::V128(v) => {
+            buffer.extend_from_slice(&[0xfd, 0x0c]);
+            buffer.extend_from_slice(&v.to_le_bytes());
+        }
+        CoreValue::NullFuncRef | CoreValue::NullExternRef | CoreValue::NullExceptionRef => {
+            buffer.extend_from_slice(&[0xd0, value.type_byte()]);
+        }
+    }
+    buffer.push(0x0b);
+}
+
+/// Writes a local or operand stack value, which can only be a number.
+fn write_frame_value(buffer: &mut Vec<u8>, value: &CoreValue) {
+    match value {
+        CoreValue::I32(v) => {
+            buffer.push(0x7f);
+            write_i64(buffer, (*v).into());
+        }
+        CoreValue::I64(v) => {
+            buffer.push(0x7e);
+            write_i64(buffer, *v);
+        }
+        CoreValue::F32(v) => {
+            buffer.push(0x7d);
+            buffer.extend_from_slice(&v.to_bits().to_le_bytes());
+        }
+        CoreValue::F64(v) => {
+            buffer.push(0x7c);
+            buffer.extend_from_slice(&v.to_bits().to_le_bytes());
+        }
+        // Missing
+        _ => buffer.push(0x01),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_a_coredump() {
+        let coredump = Coredump {
+            executable_name: "app.wasm".to_string(),
+            stacks: vec![CoreStack {
+                thread_name: "main".to_string(),
+                frames: vec![CoreFrame {
+                    func_index: 3,
+                    code_offset: 200,
+                    locals: vec![CoreValue::I32(-1)],
+                    stack: vec![CoreValue::V128(0)],
+                }],
+            }],
+            memory: Some(CoreMemory {
+                maximum: Some(2),
+                shared: false,
+                memory64: false,
+                data: vec![0; wasmer::WASM_PAGE_SIZE],
+            }),
+            globals: vec![
+                CoreGlobal {
+                    mutable: true,
+                    value: CoreValue::I32(65536),
+                },
+                CoreGlobal {
+                    mutable: false,
+                    value: CoreValue::NullFuncRef,
+                },
+            ],
+        };
+
+        let wasm = coredump.serialize();
+
+        // A coredump is a valid module
+        let module = wasmer::Module::new(&wasmer::Store::default(), &wasm).unwrap();
+        let core: Vec<_> = module.custom_sections("core").collect();
+        assert_eq!(&*core[0], b"\x00\x08app.wasm");
+        let corestack: Vec<_> = module.custom_sections("corestack").collect();
+        assert_eq!(
+            &*corestack[0],
+            b"\x00\x04main\x01\x00\x03\xc8\x01\x01\x7f\x7f\x01\x01"
+        );
+        assert_eq!(module.info().globals.len(), 2);
+        let memory = module.info().memories.values().next().unwrap();
+        assert_eq!(memory.minimum, wasmer::Pages(1));
+        assert_eq!(memory.maximum, Some(wasmer::Pages(2)));
+    }
+
+    #[test]
+    fn serialize_a_memory64() {
+        let coredump = Coredump {
+            memory: Some(CoreMemory {
+                maximum: None,
+                shared: false,
+                memory64: true,
+                data: vec![0; wasmer::WASM_PAGE_SIZE],
+            }),
+            ..Default::default()
+        };
+
+        let wasm = coredump.serialize();
+
+        // The header and the `core` section come first
+        let sections = &wasm[17..];
+        // A memory of one page, indexed with 64-bit addresses
+        assert_eq!(&sections[..5], &[5, 3, 1, 0x04, 1]);
+        // A segment of the whole memory, at `i64.const 0`
+        assert_eq!(
+            &sections[5..15],
+            &[11, 0x88, 0x80, 0x04, 1, 0, 0x42, 0, 0x0b, 0x80]
+        );
+    }
+
+    #[test]
+    fn capture_the_globals_of_the_instance() {
+        let mut store = wasmer::Store::default();
+        // Globals created before the instance aren't part of it
+        wasmer::Global::new(&mut store, Value::I64(-1));
+        let imported = wasmer::Global::new_mut(&mut store, Value::I32(1));
+        let module = wasmer::Module::new(
+            &store,
+            r#"(module
+                (import "env" "imported" (global (mut i32)))
+                (global (mut i64) (i64.const 2))
+                (global f32 (f32.const 3)))"#,
+        )
+        .unwrap();
+        let imports = wasmer::imports! {
+            "env" => {
+                "imported" => imported,
+            },
+        };
+        let instance = Instance::new(&mut store, &module, &imports).unwrap();
+
+        let mut coredump = Coredump::new("app.wasm");
+        coredump.capture(&mut store, &instance, None);
+
+        assert_eq!(
+            coredump.globals,
+            vec![
+                CoreGlobal {
+                    mutable: true,
+                    value: CoreValue::I32(1),
+                },
+                CoreGlobal {
+                    mutable: true,
+                    value: CoreValue::I64(2),
+                },
+                CoreGlobal {
+                    mutable: false,
+                    value: CoreValue::F32(3.0),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::HashSet,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use futures::future::BoxFuture;
+use virtual_fs::{DirEntry, FileSystem, FsError, Metadata, OpenOptions, ReadDir};
+
+/// A [`FileSystem`] exposing the sub-tree at `root` of another file system as
+/// its root, with other directories of that file system mounted on top of it.
+///
+/// Every path is forwarded to the original file system, so changes made on
+/// either side are visible on the other one. The parents of the mount points
+/// that don't exist in the sub-tree appear as empty directories.
+#[derive(Debug)]
+pub(crate) struct ChrootFileSystem {
+    inner: Arc<dyn FileSystem + Send + Sync>,
+    root: PathBuf,
+    /// The `(guest, host)` mounts, the deepest guest paths first.
+    mounts: Vec<(PathBuf, PathBuf)>,
+}
+
+impl ChrootFileSystem {
+    pub(crate) fn new(
+        inner: Arc<dyn FileSystem + Send + Sync>,
+        root: PathBuf,
+        mounts: &[(PathBuf, PathBuf)],
+    ) -> Self {
+        let mut mounts: Vec<_> = mounts
+            .iter()
+            .map(|(guest, host)| (normalize(guest), host.clone()))
+            .collect();
+        mounts.sort_by_key(|(guest, _)| std::cmp::Reverse(guest.components().count()));
+        Self {
+            inner,
+            root,
+            mounts,
+        }
+    }
+
+    /// Path of `path` in the original file system.
+    fn path(&self, path: &Path) -> PathBuf {
+        let path = normalize(path);
+        for (guest, host) in &self.mounts {
+            if let Ok(rest) = path.strip_prefix(guest) {
+                return host.join(rest);
+            }
+        }
+        self.root.join(path.strip_prefix("/").unwrap_or(&path))
+    }
+
+    /// Whether `path` is a parent of a mount point.
+    fn is_mount_parent(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        self.mounts
+            .iter()
+            .any(|(guest, _)| guest != &path && guest.starts_with(&path))
+    }
+
+    fn mount_parent_metadata<T>(
+        &self,
+        path: &Path,
+        result: virtual_fs::Result<T>,
+        metadata: impl FnOnce() -> virtual_fs::Result<T>,
+    ) -> virtual_fs::Result<T> {
+        match result {
+            Err(FsError::EntryNotFound) if self.is_mount_parent(path) => metadata(),
+            result => result,
+        }
+    }
+}
+
+/// Makes `path` absolute and resolves its `.` and `..` components, without
+/// ever going above the root.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+    normalized
+}
+
+impl FileSystem for ChrootFileSystem {
+    fn readlink(&self, path: &Path) -> virtual_fs::Result<PathBuf> {
+        self.inner.readlink(&self.path(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> virtual_fs::Result<ReadDir> {
+        let dir = normalize(path);
+        let result = self.inner.read_dir(&self.path(&dir)).map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| DirEntry {
+                    path: dir.join(entry.file_name()),
+                    metadata: entry.metadata,
+                })
+                .collect::<Vec<_>>()
+        });
+        let mut entries = self.mount_parent_metadata(&dir, result, || Ok(Vec::new()))?;
+
+        // Mount points, or their parents, directly in this directory
+        let mut names: HashSet<_> = entries.iter().map(|entry| entry.file_name()).collect();
+        for (guest, _) in &self.mounts {
+            let Ok(rest) = guest.strip_prefix(&dir) else {
+                continue;
+            };
+            if let Some(Component::Normal(name)) = rest.components().next() {
+                if names.insert(name.to_owned()) {
+                    let path = dir.join(name);
+                    entries.push(DirEntry {
+                        metadata: self.metadata(&path),
+                        path,
+                    });
+                }
+            }
+        }
+        Ok(ReadDir::new(entries))
+    }
+
+    fn create_dir(&self, path: &Path) -> virtual_fs::Result<()> {
+        self.inner.create_dir(&self.path(path))
+    }
+
+    fn remove_dir(&self, path: &Path) -> virtual_fs::Result<()> {
+        self.inner.remove_dir(&self.path(path))
+    }
+
+    fn rename<'a>(&'a self, from: &Path, to: &Path) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        let from = self.path(from);
+        let to = self.path(to);
+        Box::pin(async move { self.inner.rename(&from, &to).await })
+    }
+
+    fn metadata(&self, path: &Path) -> virtual_fs::Result<Metadata> {
+        let result = self.inner.metadata(&self.path(path));
+        self.mount_parent_metadata(path, result, || self.inner.metadata(&self.root))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> virtual_fs::Result<Metadata> {
+        let result = self.inner.symlink_metadata(&self.path(path));
+        self.mount_parent_metadata(path, result, || self.inner.metadata(&self.root))
+    }
+
+    fn remove_file(&self, path: &Path) -> virtual_fs::Result<()> {
+        self.inner.remove_file(&self.path(path))
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(self)
+    }
+
+    fn mount(
+        &self,
+        name: String,
+        path: &Path,
+        fs: Box<dyn FileSystem + Send + Sync>,
+    ) -> virtual_fs::Result<()> {
+        self.inner.mount(name, &self.path(path), fs)
+    }
+}
+
+impl virtual_fs::FileOpener for ChrootFileSystem {
+    fn open(
+        &self,
+        path: &Path,
+        conf: &virtual_fs::OpenOptionsConfig,
+    ) -> virtual_fs::Result<Box<dyn virtual_fs::VirtualFile + Send + Sync + 'static>> {
+        self.inner
+            .new_open_options()
+            .options(conf.clone())
+            .open(self.path(path))
+    }
+}
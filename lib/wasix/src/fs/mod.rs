@@ -1,3 +1,4 @@
+mod chroot;
 mod fd;
 mod fd_list;
 mod inode_guard;
@@ -27,6 +28,7 @@ use serde_derive::{Deserialize, Serialize};
 use tokio::{io::AsyncWriteExt, runtime::Handle};
 use tracing::{debug, trace};
 use virtual_fs::{copy_reference, FileSystem, FsError, OpenOptions, VirtualFile};
+use virtual_net::UnixSockets;
 use wasmer_config::package::PackageId;
 use wasmer_wasix_types::{
     types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO},
@@ -298,6 +300,32 @@ impl WasiFsRoot {
             }
         }
     }
+
+    /// Creates a new root file system that only exposes the sub-tree at
+    /// `root`, with the `(guest, host)` mounts of other directories of this
+    /// file system layered on top of it.
+    ///
+    /// Every path of the new root is forwarded to this file system, so
+    /// changes made through the new root are visible in this file system and
+    /// vice versa, including the entries created directly in the new root.
+    pub(crate) fn chroot(
+        &self,
+        root: &Path,
+        mounts: &[(PathBuf, PathBuf)],
+    ) -> Result<WasiFsRoot, virtual_fs::FsError> {
+        let this: Arc<dyn FileSystem + Send + Sync> = Arc::new(self.clone());
+        if !this.metadata(root)?.is_dir() {
+            return Err(virtual_fs::FsError::BaseNotDirectory);
+        }
+        for (_, host) in mounts {
+            if !this.metadata(host)?.is_dir() {
+                return Err(virtual_fs::FsError::BaseNotDirectory);
+            }
+        }
+
+        let fs = chroot::ChrootFileSystem::new(this, root.to_path_buf(), mounts);
+        Ok(WasiFsRoot::Backing(Arc::new(Box::new(fs))))
+    }
 }
 
 impl FileSystem for WasiFsRoot {
@@ -435,6 +463,23 @@ fn create_dir_all(fs: &dyn FileSystem, path: &Path) -> Result<(), virtual_fs::Fs
     Ok(())
 }
 
+/// Resolves `path` against the absolute directory `base`, normalizing away
+/// any `.` and `..` lexically, without following symlinks. Going above the
+/// root stays at the root.
+pub(crate) fn resolve_path(base: &str, path: &str) -> PathBuf {
+    let mut resolved = PathBuf::from("/");
+    for component in Path::new(base).join(path).components() {
+        match component {
+            Component::Normal(name) => resolved.push(name),
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    resolved
+}
+
 /// Warning, modifying these fields directly may cause invariants to break and
 /// should be considered unsafe.  These fields may be made private in a future release
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -447,6 +492,10 @@ pub struct WasiFs {
     pub root_fs: WasiFsRoot,
     pub root_inode: InodeGuard,
     pub has_unioned: Arc<Mutex<HashSet<PackageId>>>,
+    /// The namespace of the Unix domain sockets bound in this file system,
+    /// shared with the forks that keep the same root.
+    #[cfg_attr(feature = "enable-serde", serde(skip, default))]
+    pub(crate) unix_sockets: UnixSockets,
 
     // TODO: remove
     // using an atomic is a hack to enable customization after construction,
@@ -482,11 +531,53 @@ impl WasiFs {
             root_fs: self.root_fs.clone(),
             root_inode: self.root_inode.clone(),
             has_unioned: Arc::new(Mutex::new(HashSet::new())),
+            unix_sockets: self.unix_sockets.clone(),
             init_preopens: self.init_preopens.clone(),
             init_vfs_preopens: self.init_vfs_preopens.clone(),
         }
     }
 
+    /// Forks the file system for a spawned process that gets its own root
+    /// file system and preopened directories.
+    ///
+    /// The stdio handles and any descriptors that are not backed by the file
+    /// system (pipes, sockets, etc) are inherited, everything else would let
+    /// the child escape from its new root and is closed instead.
+    pub(crate) fn fork_with_root(
+        &self,
+        inodes: &WasiInodes,
+        root_fs: WasiFsRoot,
+        preopens: Vec<PreopenedDir>,
+    ) -> Result<Self, String> {
+        let mut fd_map = FdList::new();
+        for (idx, fd) in self.fd_map.read().unwrap().iter() {
+            let inherit = fd.is_stdio
+                || !matches!(
+                    fd.inode.read().deref(),
+                    Kind::File { .. } | Kind::Dir { .. } | Kind::Root { .. } | Kind::Symlink { .. }
+                );
+            if inherit {
+                fd_map.insert(true, idx, fd.clone());
+            }
+        }
+
+        let wasi_fs = Self {
+            preopen_fds: RwLock::new(vec![]),
+            fd_map: Arc::new(RwLock::new(fd_map)),
+            current_dir: Mutex::new("/".to_string()),
+            is_wasix: AtomicBool::new(self.is_wasix.load(Ordering::Acquire)),
+            root_fs,
+            root_inode: Self::create_root_inode(inodes, FS_ROOT_INO),
+            has_unioned: Arc::new(Mutex::new(HashSet::new())),
+            unix_sockets: UnixSockets::new(),
+            init_preopens: preopens,
+            init_vfs_preopens: Default::default(),
+        };
+        wasi_fs.create_rootfd()?;
+        wasi_fs.create_preopens(inodes, false)?;
+        Ok(wasi_fs)
+    }
+
     /// Closes all the file handles.
     #[allow(clippy::await_holding_lock)]
     pub async fn close_all(&self) {
@@ -564,20 +655,7 @@ impl WasiFs {
     ) -> Result<Self, String> {
         debug!("Initializing WASI filesystem");
 
-        let stat = Filestat {
-            st_filetype: Filetype::Directory,
-            st_ino: st_ino.as_u64(),
-            ..Filestat::default()
-        };
-        let root_kind = Kind::Root {
-            entries: HashMap::new(),
-        };
-        let root_inode = inodes.add_inode_val(InodeVal {
-            stat: RwLock::new(stat),
-            is_preopened: true,
-            name: "/".into(),
-            kind: RwLock::new(root_kind),
-        });
+        let root_inode = Self::create_root_inode(inodes, st_ino);
 
         let wasi_fs = Self {
             preopen_fds: RwLock::new(vec![]),
@@ -587,6 +665,7 @@ impl WasiFs {
             root_fs: fs_backing,
             root_inode,
             has_unioned: Arc::new(Mutex::new(HashSet::new())),
+            unix_sockets: UnixSockets::new(),
             init_preopens: Default::default(),
             init_vfs_preopens: Default::default(),
         };
@@ -598,6 +677,23 @@ impl WasiFs {
         Ok(wasi_fs)
     }
 
+    fn create_root_inode(inodes: &WasiInodes, st_ino: Inode) -> InodeGuard {
+        let stat = Filestat {
+            st_filetype: Filetype::Directory,
+            st_ino: st_ino.as_u64(),
+            ..Filestat::default()
+        };
+        let root_kind = Kind::Root {
+            entries: HashMap::new(),
+        };
+        inodes.add_inode_val(InodeVal {
+            stat: RwLock::new(stat),
+            is_preopened: true,
+            name: "/".into(),
+            kind: RwLock::new(root_kind),
+        })
+    }
+
     /// This function is like create dir all, but it also opens it.
     /// Function is unsafe because it may break invariants and hasn't been tested.
     /// This is an experimental function and may be removed
@@ -1380,6 +1476,7 @@ impl WasiFs {
                 Kind::Symlink { .. } => Filetype::SymbolicLink,
                 Kind::Socket { socket } => match &socket.inner.protected.read().unwrap().kind {
                     InodeSocketKind::TcpStream { .. } => Filetype::SocketStream,
+                    InodeSocketKind::UnixDatagram { .. } => Filetype::SocketDgram,
                     InodeSocketKind::Raw { .. } => Filetype::SocketRaw,
                     InodeSocketKind::PreSocket { props, .. } => match props.ty {
                         Socktype::Stream => Filetype::SocketStream,
@@ -2163,3 +2260,58 @@ pub fn fs_error_into_wasi_err(fs_error: FsError) -> Errno {
         FsError::Unsupported => Errno::Notsup,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chroot_only_exposes_the_sub_tree() {
+        let fs = virtual_fs::TmpFileSystem::new();
+        create_dir_all(&fs, Path::new("/work/project/src")).unwrap();
+        create_dir_all(&fs, Path::new("/cache")).unwrap();
+        fs.create_dir(Path::new("/secret")).unwrap();
+        let fs = WasiFsRoot::Sandbox(Arc::new(fs));
+
+        let mounts = [(PathBuf::from("/deps/cache"), PathBuf::from("/cache"))];
+        let chroot = fs.chroot(Path::new("/work/project"), &mounts).unwrap();
+
+        assert!(chroot.metadata(Path::new("/src")).unwrap().is_dir());
+        assert!(chroot.metadata(Path::new("/deps/cache")).unwrap().is_dir());
+        assert!(chroot.metadata(Path::new("/secret")).is_err());
+        assert!(chroot.metadata(Path::new("/work")).is_err());
+
+        // Changes made through the new root are visible in the original one
+        chroot.create_dir(Path::new("/src/gen")).unwrap();
+        chroot.create_dir(Path::new("/deps/cache/objs")).unwrap();
+        assert!(fs.metadata(Path::new("/work/project/src/gen")).is_ok());
+        assert!(fs.metadata(Path::new("/cache/objs")).is_ok());
+
+        // Including the entries created at the top of the new root
+        chroot.create_dir(Path::new("/out")).unwrap();
+        chroot
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .open(Path::new("/build.log"))
+            .unwrap();
+        assert!(fs
+            .metadata(Path::new("/work/project/out"))
+            .unwrap()
+            .is_dir());
+        assert!(fs
+            .metadata(Path::new("/work/project/build.log"))
+            .unwrap()
+            .is_file());
+
+        // Parents of the mount points are listed in the new root
+        let mut names: Vec<_> = chroot
+            .read_dir(Path::new("/"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["build.log", "deps", "out", "src"]);
+        assert!(chroot.metadata(Path::new("/deps")).unwrap().is_dir());
+    }
+}
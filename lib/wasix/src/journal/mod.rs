@@ -4,5 +4,7 @@ mod effector;
 #[path = "effector/unimplemented.rs"]
 mod effector;
 
+#[cfg(feature = "journal")]
+pub use crate::syscalls::journal::JournalReplayer;
 pub use effector::*;
 pub use wasmer_journal::*;
@@ -45,6 +45,7 @@ pub mod os;
 pub mod net;
 // TODO: should this be pub?
 pub mod capabilities;
+pub mod coredump;
 pub mod fs;
 pub mod http;
 pub mod journal;
@@ -556,6 +557,10 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "getcwd" => Function::new_typed_with_env(&mut store, env, getcwd::<Memory32>),
         "chdir" => Function::new_typed_with_env(&mut store, env, chdir::<Memory32>),
         "callback_signal" => Function::new_typed_with_env(&mut store, env, callback_signal::<Memory32>),
+        "dlopen" => Function::new_typed_with_env(&mut store, env, dlopen::<Memory32>),
+        "dlsym" => Function::new_typed_with_env(&mut store, env, dlsym::<Memory32>),
+        "dlclose" => Function::new_typed_with_env(&mut store, env, dlclose),
+        "dlerror" => Function::new_typed_with_env(&mut store, env, dlerror::<Memory32>),
         "thread_spawn" => Function::new_typed_with_env(&mut store, env, thread_spawn_v2::<Memory32>),
         "thread_spawn_v2" => Function::new_typed_with_env(&mut store, env, thread_spawn_v2::<Memory32>),
         "thread_sleep" => Function::new_typed_with_env(&mut store, env, thread_sleep::<Memory32>),
@@ -585,7 +590,9 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "port_route_list" => Function::new_typed_with_env(&mut store, env, port_route_list::<Memory32>),
         "sock_status" => Function::new_typed_with_env(&mut store, env, sock_status::<Memory32>),
         "sock_addr_local" => Function::new_typed_with_env(&mut store, env, sock_addr_local::<Memory32>),
+        "sock_addr_local_unix" => Function::new_typed_with_env(&mut store, env, sock_addr_local_unix::<Memory32>),
         "sock_addr_peer" => Function::new_typed_with_env(&mut store, env, sock_addr_peer::<Memory32>),
+        "sock_addr_peer_unix" => Function::new_typed_with_env(&mut store, env, sock_addr_peer_unix::<Memory32>),
         "sock_open" => Function::new_typed_with_env(&mut store, env, sock_open::<Memory32>),
         "sock_set_opt_flag" => Function::new_typed_with_env(&mut store, env, sock_set_opt_flag),
         "sock_get_opt_flag" => Function::new_typed_with_env(&mut store, env, sock_get_opt_flag::<Memory32>),
@@ -598,14 +605,18 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_join_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_join_multicast_v6::<Memory32>),
         "sock_leave_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_leave_multicast_v6::<Memory32>),
         "sock_bind" => Function::new_typed_with_env(&mut store, env, sock_bind::<Memory32>),
+        "sock_bind_unix" => Function::new_typed_with_env(&mut store, env, sock_bind_unix::<Memory32>),
         "sock_listen" => Function::new_typed_with_env(&mut store, env, sock_listen::<Memory32>),
         "sock_accept" => Function::new_typed_with_env(&mut store, env, sock_accept_v2::<Memory32>),
         "sock_accept_v2" => Function::new_typed_with_env(&mut store, env, sock_accept_v2::<Memory32>),
         "sock_connect" => Function::new_typed_with_env(&mut store, env, sock_connect::<Memory32>),
+        "sock_connect_unix" => Function::new_typed_with_env(&mut store, env, sock_connect_unix::<Memory32>),
         "sock_recv" => Function::new_typed_with_env(&mut store, env, sock_recv::<Memory32>),
         "sock_recv_from" => Function::new_typed_with_env(&mut store, env, sock_recv_from::<Memory32>),
+        "sock_recv_from_unix" => Function::new_typed_with_env(&mut store, env, sock_recv_from_unix::<Memory32>),
         "sock_send" => Function::new_typed_with_env(&mut store, env, sock_send::<Memory32>),
         "sock_send_to" => Function::new_typed_with_env(&mut store, env, sock_send_to::<Memory32>),
+        "sock_send_to_unix" => Function::new_typed_with_env(&mut store, env, sock_send_to_unix::<Memory32>),
         "sock_send_file" => Function::new_typed_with_env(&mut store, env, sock_send_file::<Memory32>),
         "sock_shutdown" => Function::new_typed_with_env(&mut store, env, sock_shutdown),
         "resolve" => Function::new_typed_with_env(&mut store, env, resolve::<Memory32>),
@@ -678,6 +689,10 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "getcwd" => Function::new_typed_with_env(&mut store, env, getcwd::<Memory64>),
         "chdir" => Function::new_typed_with_env(&mut store, env, chdir::<Memory64>),
         "callback_signal" => Function::new_typed_with_env(&mut store, env, callback_signal::<Memory64>),
+        "dlopen" => Function::new_typed_with_env(&mut store, env, dlopen::<Memory64>),
+        "dlsym" => Function::new_typed_with_env(&mut store, env, dlsym::<Memory64>),
+        "dlclose" => Function::new_typed_with_env(&mut store, env, dlclose),
+        "dlerror" => Function::new_typed_with_env(&mut store, env, dlerror::<Memory64>),
         "thread_spawn" => Function::new_typed_with_env(&mut store, env, thread_spawn_v2::<Memory64>),
         "thread_spawn_v2" => Function::new_typed_with_env(&mut store, env, thread_spawn_v2::<Memory64>),
         "thread_sleep" => Function::new_typed_with_env(&mut store, env, thread_sleep::<Memory64>),
@@ -707,7 +722,9 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "port_route_list" => Function::new_typed_with_env(&mut store, env, port_route_list::<Memory64>),
         "sock_status" => Function::new_typed_with_env(&mut store, env, sock_status::<Memory64>),
         "sock_addr_local" => Function::new_typed_with_env(&mut store, env, sock_addr_local::<Memory64>),
+        "sock_addr_local_unix" => Function::new_typed_with_env(&mut store, env, sock_addr_local_unix::<Memory64>),
         "sock_addr_peer" => Function::new_typed_with_env(&mut store, env, sock_addr_peer::<Memory64>),
+        "sock_addr_peer_unix" => Function::new_typed_with_env(&mut store, env, sock_addr_peer_unix::<Memory64>),
         "sock_open" => Function::new_typed_with_env(&mut store, env, sock_open::<Memory64>),
         "sock_set_opt_flag" => Function::new_typed_with_env(&mut store, env, sock_set_opt_flag),
         "sock_get_opt_flag" => Function::new_typed_with_env(&mut store, env, sock_get_opt_flag::<Memory64>),
@@ -720,14 +737,18 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_join_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_join_multicast_v6::<Memory64>),
         "sock_leave_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_leave_multicast_v6::<Memory64>),
         "sock_bind" => Function::new_typed_with_env(&mut store, env, sock_bind::<Memory64>),
+        "sock_bind_unix" => Function::new_typed_with_env(&mut store, env, sock_bind_unix::<Memory64>),
         "sock_listen" => Function::new_typed_with_env(&mut store, env, sock_listen::<Memory64>),
         "sock_accept" => Function::new_typed_with_env(&mut store, env, sock_accept_v2::<Memory64>),
         "sock_accept_v2" => Function::new_typed_with_env(&mut store, env, sock_accept_v2::<Memory64>),
         "sock_connect" => Function::new_typed_with_env(&mut store, env, sock_connect::<Memory64>),
+        "sock_connect_unix" => Function::new_typed_with_env(&mut store, env, sock_connect_unix::<Memory64>),
         "sock_recv" => Function::new_typed_with_env(&mut store, env, sock_recv::<Memory64>),
         "sock_recv_from" => Function::new_typed_with_env(&mut store, env, sock_recv_from::<Memory64>),
+        "sock_recv_from_unix" => Function::new_typed_with_env(&mut store, env, sock_recv_from_unix::<Memory64>),
         "sock_send" => Function::new_typed_with_env(&mut store, env, sock_send::<Memory64>),
         "sock_send_to" => Function::new_typed_with_env(&mut store, env, sock_send_to::<Memory64>),
+        "sock_send_to_unix" => Function::new_typed_with_env(&mut store, env, sock_send_to_unix::<Memory64>),
         "sock_send_file" => Function::new_typed_with_env(&mut store, env, sock_send_file::<Memory64>),
         "sock_shutdown" => Function::new_typed_with_env(&mut store, env, sock_shutdown),
         "resolve" => Function::new_typed_with_env(&mut store, env, resolve::<Memory64>),
@@ -772,6 +793,11 @@ fn import_object_for_all_wasi_versions(
         "wasix_64v1" => exports_wasix_64v1,
     };
 
+    // Syscalls are only wrapped when the store is being profiled
+    if store.as_store_ref().host_profiler().is_some() {
+        imports = imports.profiled(store);
+    }
+
     let init = Box::new(stub_initializer) as ModuleInitializer;
 
     (imports, init)
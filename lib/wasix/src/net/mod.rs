@@ -1,6 +1,7 @@
 use std::{
     intrinsics::transmute,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::{Path, PathBuf},
     time::Duration,
 };
 
@@ -370,6 +371,42 @@ pub(crate) fn write_route<M: MemorySize>(
     Ok(())
 }
 
+/// Resolves the path of a Unix domain socket against the current directory
+pub(crate) fn resolve_unix_path(current_dir: &str, path: &str) -> Result<PathBuf, Errno> {
+    if path.is_empty() {
+        return Err(Errno::Noent);
+    }
+    Ok(crate::fs::resolve_path(current_dir, path))
+}
+
+/// Writes the path of a Unix domain socket, which is empty when the socket
+/// isn't bound, along with its length. The buffer length is both read and
+/// written.
+pub(crate) fn write_unix_path<M: MemorySize>(
+    memory: &MemoryView,
+    buf: WasmPtr<u8, M>,
+    buf_len: WasmPtr<M::Offset, M>,
+    path: Option<&Path>,
+) -> Result<(), Errno> {
+    let path = path.map(|path| path.to_string_lossy().into_owned());
+    let path = path.as_deref().unwrap_or_default().as_bytes();
+
+    let max_len: u64 = buf_len
+        .read(memory)
+        .map_err(crate::mem_error_to_wasi)?
+        .into();
+    let len = M::Offset::try_from(path.len()).map_err(|_| Errno::Overflow)?;
+    buf_len
+        .write(memory, len)
+        .map_err(crate::mem_error_to_wasi)?;
+    if path.len() as u64 > max_len {
+        return Err(Errno::Range);
+    }
+    buf.slice(memory, len)
+        .and_then(|slice| slice.write_slice(path))
+        .map_err(crate::mem_error_to_wasi)
+}
+
 pub fn net_error_into_wasi_err(net_error: NetworkError) -> Errno {
     match net_error {
         NetworkError::InvalidFd => Errno::Badf,
@@ -3,6 +3,7 @@ use std::{
     io,
     mem::MaybeUninit,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
     pin::Pin,
     sync::{Arc, RwLock},
     task::{Context, Poll},
@@ -13,8 +14,9 @@ use std::{
 use serde_derive::{Deserialize, Serialize};
 use virtual_mio::InterestHandler;
 use virtual_net::{
-    net_error_into_io_err, NetworkError, VirtualIcmpSocket, VirtualNetworking, VirtualRawSocket,
-    VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket,
+    net_error_into_io_err, NetworkError, UnixSockets, VirtualIcmpSocket, VirtualNetworking,
+    VirtualRawSocket, VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket,
+    VirtualUnixDatagramSocket,
 };
 use wasmer_types::MemorySize;
 use wasmer_wasix_types::wasi::{Addressfamily, Errno, Rights, SockProto, Sockoption, Socktype};
@@ -58,6 +60,9 @@ pub enum InodeSocketKind {
     PreSocket {
         props: SocketProperties,
         addr: Option<SocketAddr>,
+        /// The path a Unix domain socket is bound to, along with the
+        /// namespace it is bound in
+        unix_addr: Option<(UnixSockets, PathBuf)>,
     },
     Icmp(Box<dyn VirtualIcmpSocket + Sync>),
     Raw(Box<dyn VirtualRawSocket + Sync>),
@@ -74,6 +79,9 @@ pub enum InodeSocketKind {
         socket: Box<dyn VirtualUdpSocket + Sync>,
         peer: Option<SocketAddr>,
     },
+    UnixDatagram {
+        socket: Box<dyn VirtualUnixDatagramSocket + Sync>,
+    },
     RemoteSocket {
         props: SocketProperties,
         local_addr: SocketAddr,
@@ -349,6 +357,57 @@ impl InodeSocket {
         }
     }
 
+    pub async fn bind_unix(
+        &self,
+        tasks: &dyn VirtualTaskManager,
+        net: &dyn VirtualNetworking,
+        sockets: UnixSockets,
+        path: PathBuf,
+    ) -> Result<Option<InodeSocket>, Errno> {
+        let timeout = self
+            .opt_time(TimeType::BindTimeout)
+            .ok()
+            .flatten()
+            .unwrap_or(Duration::from_secs(30));
+
+        {
+            let mut inner = self.inner.protected.write().unwrap();
+            match &mut inner.kind {
+                InodeSocketKind::PreSocket {
+                    props, unix_addr, ..
+                } => {
+                    if props.family != Addressfamily::Unix {
+                        tracing::debug!("Unix path ({path:?}) vs {:?} family", props.family);
+                        return Err(Errno::Inval);
+                    }
+                    if unix_addr.is_some() {
+                        return Err(Errno::Inval);
+                    }
+
+                    match props.ty {
+                        Socktype::Stream => {
+                            // the path is bound by the following listen
+                            unix_addr.replace((sockets, path));
+                            return Ok(None);
+                        }
+                        Socktype::Dgram => {}
+                        _ => return Err(Errno::Inval),
+                    }
+                }
+                InodeSocketKind::UnixDatagram { .. } => return Err(Errno::Inval),
+                _ => return Err(Errno::Notsup),
+            }
+        }
+
+        tokio::select! {
+            socket = net.bind_unix_datagram(&sockets, Some(&path)) => {
+                let socket = socket.map_err(net_error_into_wasi_err)?;
+                Ok(Some(InodeSocket::new(InodeSocketKind::UnixDatagram { socket })))
+            },
+            _ = tasks.sleep_now(timeout) => Err(Errno::Timedout)
+        }
+    }
+
     pub async fn listen(
         &self,
         tasks: &dyn VirtualTaskManager,
@@ -361,9 +420,29 @@ impl InodeSocket {
             .flatten()
             .unwrap_or(Duration::from_secs(30));
 
+        let (unix_sockets, unix_path);
         let socket = {
             let inner = self.inner.protected.read().unwrap();
             match &inner.kind {
+                InodeSocketKind::PreSocket {
+                    props,
+                    unix_addr: Some(addr),
+                    ..
+                } if props.family == Addressfamily::Unix => match props.ty {
+                    Socktype::Stream => {
+                        (unix_sockets, unix_path) = addr.clone();
+                        drop(inner);
+
+                        net.listen_unix(&unix_sockets, &unix_path)
+                    }
+                    ty => {
+                        tracing::warn!(
+                            "wasi[?]::sock_listen - failed - not supported(unix-socket:{:?})",
+                            ty
+                        );
+                        return Err(Errno::Notsup);
+                    }
+                },
                 InodeSocketKind::PreSocket { props, addr, .. } => match props.ty {
                     Socktype::Stream => {
                         if addr.is_none() {
@@ -430,6 +509,10 @@ impl InodeSocket {
                     tracing::warn!("wasi[?]::sock_listen - failed - not supported(udp-socket)");
                     return Err(Errno::Notsup);
                 }
+                InodeSocketKind::UnixDatagram { .. } => {
+                    tracing::warn!("wasi[?]::sock_listen - failed - not supported(unix-datagram)");
+                    return Err(Errno::Notsup);
+                }
             }
         };
 
@@ -521,6 +604,7 @@ impl InodeSocket {
             }
             InodeSocketKind::Icmp(_) => {}
             InodeSocketKind::UdpSocket { .. } => {}
+            InodeSocketKind::UnixDatagram { .. } => {}
             InodeSocketKind::Raw(_) => {}
             InodeSocketKind::PreSocket { .. } => return Err(Errno::Notconn),
             InodeSocketKind::RemoteSocket { .. } => {}
@@ -619,6 +703,83 @@ impl InodeSocket {
         Ok(Some(socket))
     }
 
+    pub async fn connect_unix(
+        &mut self,
+        tasks: &dyn VirtualTaskManager,
+        net: &dyn VirtualNetworking,
+        sockets: UnixSockets,
+        peer: PathBuf,
+        timeout: Option<std::time::Duration>,
+        nonblocking: bool,
+    ) -> Result<Option<InodeSocket>, Errno> {
+        let timeout = timeout.unwrap_or(Duration::from_secs(30));
+
+        let (ty, handler, write_timeout, read_timeout) = {
+            let mut inner = self.inner.protected.write().unwrap();
+            match &mut inner.kind {
+                InodeSocketKind::PreSocket { props, .. } => {
+                    if props.family != Addressfamily::Unix {
+                        tracing::debug!("Unix path ({peer:?}) vs {:?} family", props.family);
+                        return Err(Errno::Inval);
+                    }
+                    (
+                        props.ty,
+                        props.handler.take(),
+                        props.write_timeout,
+                        props.read_timeout,
+                    )
+                }
+                InodeSocketKind::UnixDatagram { socket } => {
+                    socket.connect(&peer).map_err(net_error_into_wasi_err)?;
+                    return Ok(None);
+                }
+                _ => return Err(Errno::Notsup),
+            }
+        };
+
+        let socket = match ty {
+            Socktype::Stream => {
+                let connect = async {
+                    let mut socket = net.connect_unix(&sockets, &peer).await?;
+                    if !nonblocking {
+                        futures::future::poll_fn(|cx| socket.poll_write_ready(cx)).await?;
+                    }
+                    Ok(socket)
+                };
+                let mut socket = tokio::select! {
+                    res = connect => res.map_err(net_error_into_wasi_err)?,
+                    _ = tasks.sleep_now(timeout) => return Err(Errno::Timedout)
+                };
+                if let Some(handler) = handler {
+                    socket
+                        .set_handler(handler)
+                        .map_err(net_error_into_wasi_err)?;
+                }
+                InodeSocketKind::TcpStream {
+                    socket,
+                    write_timeout,
+                    read_timeout,
+                }
+            }
+            Socktype::Dgram => {
+                let mut socket = tokio::select! {
+                    res = net.bind_unix_datagram(&sockets, None) => res.map_err(net_error_into_wasi_err)?,
+                    _ = tasks.sleep_now(timeout) => return Err(Errno::Timedout)
+                };
+                socket.connect(&peer).map_err(net_error_into_wasi_err)?;
+                if let Some(handler) = handler {
+                    socket
+                        .set_handler(handler)
+                        .map_err(net_error_into_wasi_err)?;
+                }
+                InodeSocketKind::UnixDatagram { socket }
+            }
+            _ => return Err(Errno::Notsup),
+        };
+
+        Ok(Some(InodeSocket::new(socket)))
+    }
+
     pub fn status(&self) -> Result<WasiSocketStatus, Errno> {
         let inner = self.inner.protected.read().unwrap();
         Ok(match &inner.kind {
@@ -626,6 +787,7 @@ impl InodeSocket {
             InodeSocketKind::TcpListener { .. } => WasiSocketStatus::Opened,
             InodeSocketKind::TcpStream { .. } => WasiSocketStatus::Opened,
             InodeSocketKind::UdpSocket { .. } => WasiSocketStatus::Opened,
+            InodeSocketKind::UnixDatagram { .. } => WasiSocketStatus::Opened,
             InodeSocketKind::RemoteSocket { is_dead, .. } => match is_dead {
                 true => WasiSocketStatus::Closed,
                 false => WasiSocketStatus::Opened,
@@ -705,6 +867,38 @@ impl InodeSocket {
         })
     }
 
+    pub fn addr_local_unix(&self) -> Result<Option<PathBuf>, Errno> {
+        let inner = self.inner.protected.read().unwrap();
+        match &inner.kind {
+            InodeSocketKind::PreSocket {
+                props, unix_addr, ..
+            } if props.family == Addressfamily::Unix => {
+                Ok(unix_addr.as_ref().map(|(_, path)| path.clone()))
+            }
+            InodeSocketKind::TcpListener { socket, .. } => socket.addr_local_unix(),
+            InodeSocketKind::TcpStream { socket, .. } => socket.addr_local_unix(),
+            InodeSocketKind::UnixDatagram { socket } => socket.addr_local(),
+            _ => return Err(Errno::Notsup),
+        }
+        .map_err(net_error_into_wasi_err)
+    }
+
+    pub fn addr_peer_unix(&self) -> Result<Option<PathBuf>, Errno> {
+        let inner = self.inner.protected.read().unwrap();
+        match &inner.kind {
+            InodeSocketKind::PreSocket { props, .. } if props.family == Addressfamily::Unix => {
+                Err(Errno::Notconn)
+            }
+            InodeSocketKind::TcpStream { socket, .. } => {
+                socket.addr_peer_unix().map_err(net_error_into_wasi_err)
+            }
+            InodeSocketKind::UnixDatagram { socket } => {
+                socket.addr_peer().map_err(net_error_into_wasi_err)
+            }
+            _ => Err(Errno::Notsup),
+        }
+    }
+
     pub fn set_opt_flag(&mut self, option: WasiSocketOption, val: bool) -> Result<(), Errno> {
         let mut inner = self.inner.protected.write().unwrap();
         match &mut inner.kind {
@@ -1104,6 +1298,7 @@ impl InodeSocket {
                                 Err(NetworkError::NotConnected)
                             }
                         }
+                        InodeSocketKind::UnixDatagram { socket } => socket.try_send(self.data),
                         InodeSocketKind::PreSocket { .. } => {
                             return Poll::Ready(Err(Errno::Notconn))
                         }
@@ -1281,6 +1476,9 @@ impl InodeSocket {
                                 }
                             }
                         }
+                        InodeSocketKind::UnixDatagram { socket } => {
+                            socket.try_recv_from(self.data).map(|(amt, _)| amt)
+                        }
                         InodeSocketKind::RemoteSocket { is_dead, .. } => {
                             return match is_dead {
                                 true => Poll::Ready(Ok(0)),
@@ -1363,6 +1561,12 @@ impl InodeSocket {
                         InodeSocketKind::UdpSocket { socket, .. } => {
                             socket.try_recv_from(self.data)
                         }
+                        // The sender is bound to a path rather than an address,
+                        // which `recv_from` can't report, so these sockets are
+                        // read with `recv` or `recv_from_unix` instead
+                        InodeSocketKind::UnixDatagram { .. } => {
+                            return Poll::Ready(Err(Errno::Afnosupport))
+                        }
                         InodeSocketKind::RemoteSocket {
                             is_dead, peer_addr, ..
                         } => {
@@ -1411,6 +1615,157 @@ impl InodeSocket {
         }
     }
 
+    /// Sends a datagram on a Unix domain socket to the socket bound to `peer`
+    pub async fn send_to_unix(
+        &self,
+        tasks: &dyn VirtualTaskManager,
+        buf: &[u8],
+        peer: &Path,
+        timeout: Option<Duration>,
+        nonblocking: bool,
+    ) -> Result<usize, Errno> {
+        struct SocketSender<'a, 'b> {
+            inner: &'a InodeSocketInner,
+            data: &'b [u8],
+            peer: &'b Path,
+            nonblocking: bool,
+            handler_registered: bool,
+        }
+        impl<'a, 'b> Drop for SocketSender<'a, 'b> {
+            fn drop(&mut self) {
+                if self.handler_registered {
+                    let mut inner = self.inner.protected.write().unwrap();
+                    inner.remove_handler();
+                }
+            }
+        }
+        impl<'a, 'b> Future for SocketSender<'a, 'b> {
+            type Output = Result<usize, Errno>;
+            fn poll(
+                mut self: Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> Poll<Self::Output> {
+                loop {
+                    let mut inner = self.inner.protected.write().unwrap();
+                    let res = match &mut inner.kind {
+                        InodeSocketKind::UnixDatagram { socket } => {
+                            socket.try_send_to(self.data, self.peer)
+                        }
+                        InodeSocketKind::PreSocket { .. } => {
+                            return Poll::Ready(Err(Errno::Notconn))
+                        }
+                        _ => return Poll::Ready(Err(Errno::Notsup)),
+                    };
+                    return match res {
+                        Ok(amt) => Poll::Ready(Ok(amt)),
+                        Err(NetworkError::WouldBlock) if self.nonblocking => {
+                            Poll::Ready(Err(Errno::Again))
+                        }
+                        Err(NetworkError::WouldBlock) if !self.handler_registered => {
+                            inner
+                                .set_handler(cx.waker().into())
+                                .map_err(net_error_into_wasi_err)?;
+                            self.handler_registered = true;
+                            drop(inner);
+                            continue;
+                        }
+                        Err(NetworkError::WouldBlock) => Poll::Pending,
+                        Err(err) => Poll::Ready(Err(net_error_into_wasi_err(err))),
+                    };
+                }
+            }
+        }
+
+        let poller = SocketSender {
+            inner: &self.inner,
+            data: buf,
+            peer,
+            nonblocking,
+            handler_registered: false,
+        };
+        if let Some(timeout) = timeout {
+            tokio::select! {
+                res = poller => res,
+                _ = tasks.sleep_now(timeout) => Err(Errno::Timedout)
+            }
+        } else {
+            poller.await
+        }
+    }
+
+    /// Receives a datagram on a Unix domain socket, along with the path of
+    /// the socket that sent it if that socket is bound
+    pub async fn recv_from_unix(
+        &self,
+        tasks: &dyn VirtualTaskManager,
+        buf: &mut [MaybeUninit<u8>],
+        timeout: Option<Duration>,
+        nonblocking: bool,
+    ) -> Result<(usize, Option<PathBuf>), Errno> {
+        struct SocketReceiver<'a, 'b> {
+            inner: &'a InodeSocketInner,
+            data: &'b mut [MaybeUninit<u8>],
+            nonblocking: bool,
+            handler_registered: bool,
+        }
+        impl<'a, 'b> Drop for SocketReceiver<'a, 'b> {
+            fn drop(&mut self) {
+                if self.handler_registered {
+                    let mut inner = self.inner.protected.write().unwrap();
+                    inner.remove_handler();
+                }
+            }
+        }
+        impl<'a, 'b> Future for SocketReceiver<'a, 'b> {
+            type Output = Result<(usize, Option<PathBuf>), Errno>;
+            fn poll(
+                mut self: Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> Poll<Self::Output> {
+                let mut inner = self.inner.protected.write().unwrap();
+                loop {
+                    let res = match &mut inner.kind {
+                        InodeSocketKind::UnixDatagram { socket } => socket.try_recv_from(self.data),
+                        InodeSocketKind::PreSocket { .. } => {
+                            return Poll::Ready(Err(Errno::Notconn))
+                        }
+                        _ => return Poll::Ready(Err(Errno::Notsup)),
+                    };
+                    return match res {
+                        Ok((amt, peer)) => Poll::Ready(Ok((amt, peer))),
+                        Err(NetworkError::WouldBlock) if self.nonblocking => {
+                            Poll::Ready(Err(Errno::Again))
+                        }
+                        Err(NetworkError::WouldBlock) if !self.handler_registered => {
+                            inner
+                                .set_handler(cx.waker().into())
+                                .map_err(net_error_into_wasi_err)?;
+                            self.handler_registered = true;
+                            continue;
+                        }
+                        Err(NetworkError::WouldBlock) => Poll::Pending,
+                        Err(err) => Poll::Ready(Err(net_error_into_wasi_err(err))),
+                    };
+                }
+            }
+        }
+
+        let poller = SocketReceiver {
+            inner: &self.inner,
+            data: buf,
+            nonblocking,
+            handler_registered: false,
+        };
+        if let Some(timeout) = timeout {
+            tokio::select! {
+                res = poller => res,
+                _ = tasks.sleep_now(timeout) => Err(Errno::Timedout)
+            }
+        } else {
+            poller.await
+        }
+    }
+
     pub fn shutdown(&mut self, how: std::net::Shutdown) -> Result<(), Errno> {
         let mut inner = self.inner.protected.write().unwrap();
         match &mut inner.kind {
@@ -1430,6 +1785,7 @@ impl InodeSocket {
             match &mut guard.kind {
                 InodeSocketKind::TcpStream { .. }
                 | InodeSocketKind::UdpSocket { .. }
+                | InodeSocketKind::UnixDatagram { .. }
                 | InodeSocketKind::Raw(..) => true,
                 InodeSocketKind::RemoteSocket { is_dead, .. } => !(*is_dead),
                 _ => false,
@@ -1446,6 +1802,7 @@ impl InodeSocketProtected {
             InodeSocketKind::TcpListener { socket, .. } => socket.remove_handler(),
             InodeSocketKind::TcpStream { socket, .. } => socket.remove_handler(),
             InodeSocketKind::UdpSocket { socket, .. } => socket.remove_handler(),
+            InodeSocketKind::UnixDatagram { socket } => socket.remove_handler(),
             InodeSocketKind::Raw(socket) => socket.remove_handler(),
             InodeSocketKind::Icmp(socket) => socket.remove_handler(),
             InodeSocketKind::PreSocket { props, .. } => {
@@ -1462,6 +1819,7 @@ impl InodeSocketProtected {
             InodeSocketKind::TcpListener { socket, .. } => socket.poll_read_ready(cx),
             InodeSocketKind::TcpStream { socket, .. } => socket.poll_read_ready(cx),
             InodeSocketKind::UdpSocket { socket, .. } => socket.poll_read_ready(cx),
+            InodeSocketKind::UnixDatagram { socket } => socket.poll_read_ready(cx),
             InodeSocketKind::Raw(socket) => socket.poll_read_ready(cx),
             InodeSocketKind::Icmp(socket) => socket.poll_read_ready(cx),
             InodeSocketKind::PreSocket { .. } => Poll::Pending,
@@ -1478,6 +1836,7 @@ impl InodeSocketProtected {
             InodeSocketKind::TcpListener { socket, .. } => socket.poll_write_ready(cx),
             InodeSocketKind::TcpStream { socket, .. } => socket.poll_write_ready(cx),
             InodeSocketKind::UdpSocket { socket, .. } => socket.poll_write_ready(cx),
+            InodeSocketKind::UnixDatagram { socket } => socket.poll_write_ready(cx),
             InodeSocketKind::Raw(socket) => socket.poll_write_ready(cx),
             InodeSocketKind::Icmp(socket) => socket.poll_write_ready(cx),
             InodeSocketKind::PreSocket { .. } => Poll::Pending,
@@ -1497,6 +1856,7 @@ impl InodeSocketProtected {
             InodeSocketKind::TcpListener { socket, .. } => socket.set_handler(handler),
             InodeSocketKind::TcpStream { socket, .. } => socket.set_handler(handler),
             InodeSocketKind::UdpSocket { socket, .. } => socket.set_handler(handler),
+            InodeSocketKind::UnixDatagram { socket } => socket.set_handler(handler),
             InodeSocketKind::Raw(socket) => socket.set_handler(handler),
             InodeSocketKind::Icmp(socket) => socket.set_handler(handler),
             InodeSocketKind::PreSocket { props, .. }
@@ -25,8 +25,8 @@ use wasmer_wasix_types::{
 };
 
 use crate::{
-    os::task::signal::WasiSignalInterval, syscalls::platform_clock_time_get, WasiThread,
-    WasiThreadHandle, WasiThreadId,
+    coredump::CoredumpCollector, os::task::signal::WasiSignalInterval,
+    syscalls::platform_clock_time_get, WasiThread, WasiThreadHandle, WasiThreadId,
 };
 
 use super::{
@@ -180,6 +180,8 @@ pub struct WasiProcessInner {
     /// duplicate entries in the journal for memory that has not changed
     #[cfg(feature = "journal")]
     pub snapshot_memory_hash: HashMap<MemorySnapshotRegion, u64>,
+    /// Collects a coredump of the process if one of its threads traps
+    pub(crate) coredump: Option<Arc<CoredumpCollector>>,
     /// Represents all the backoff properties for this process
     /// which will be used to determine if the CPU should be
     /// throttled or not
@@ -427,6 +429,7 @@ impl WasiProcess {
                 #[cfg(feature = "journal")]
                 snapshot_memory_hash: Default::default(),
                 disable_journaling_after_checkpoint: false,
+                coredump: None,
                 backoff: WasiProcessCpuBackoff::new(max_cpu_backoff_time, max_cpu_cool_off_time),
             }),
             Condvar::new(),
@@ -1,5 +1,6 @@
 use std::{
     net::{IpAddr, SocketAddr},
+    path::Path,
     sync::{Arc, Mutex},
     task::{Context, Poll, Waker},
     time::Duration,
@@ -7,8 +8,8 @@ use std::{
 
 use virtual_net::{
     host::LocalNetworking, loopback::LoopbackNetworking, IpCidr, IpRoute, NetworkError,
-    StreamSecurity, VirtualIcmpSocket, VirtualNetworking, VirtualRawSocket, VirtualTcpListener,
-    VirtualTcpSocket, VirtualUdpSocket,
+    StreamSecurity, UnixSockets, VirtualIcmpSocket, VirtualNetworking, VirtualRawSocket,
+    VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket, VirtualUnixDatagramSocket,
 };
 
 #[derive(Debug, Default)]
@@ -220,4 +221,34 @@ impl VirtualNetworking for LocalWithLoopbackNetworking {
     ) -> Result<Vec<IpAddr>, NetworkError> {
         self.inner_networking.resolve(host, port, dns_server).await
     }
+
+    /// Listens for connections on a Unix domain socket bound to a path
+    async fn listen_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualTcpListener + Sync>, NetworkError> {
+        self.inner_networking.listen_unix(sockets, path).await
+    }
+
+    /// Opens a connection to the Unix domain socket bound to a path
+    async fn connect_unix(
+        &self,
+        sockets: &UnixSockets,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualTcpSocket + Sync>, NetworkError> {
+        self.inner_networking.connect_unix(sockets, path).await
+    }
+
+    /// Opens a datagram Unix domain socket, bound to a path unless it is
+    /// `None`
+    async fn bind_unix_datagram(
+        &self,
+        sockets: &UnixSockets,
+        path: Option<&Path>,
+    ) -> Result<Box<dyn VirtualUnixDatagramSocket + Sync>, NetworkError> {
+        self.inner_networking
+            .bind_unix_datagram(sockets, path)
+            .await
+    }
 }
@@ -4,6 +4,7 @@ mod runner;
 pub mod dcgi;
 #[cfg(feature = "webc_runner_rt_dproxy")]
 pub mod dproxy;
+pub mod scheduler;
 pub mod wasi;
 mod wasi_common;
 #[cfg(feature = "webc_runner_rt_wcgi")]
@@ -0,0 +1,584 @@
+//! Run an app's scheduled tasks alongside it.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Error};
+use futures::future::{AbortHandle, Abortable, Either};
+use http::{HeaderName, HeaderValue, Method};
+use url::Url;
+use wasmer_config::app::{
+    AppScheduledTask, ConcurrencyPolicy, ExecuteJob, FetchJob, JobAction, JobTimezone,
+};
+use wasmer_wasix_types::wasi::Signal;
+use webc::metadata::annotations::Wasi;
+
+use crate::{
+    bin_factory::BinaryPackage, http::HttpRequest, os::task::process::WasiProcess,
+    runners::wasi::WasiRunner, runtime::task_manager::VirtualTaskManager, Runtime, WasiThreadError,
+};
+
+/// How long to wait before retrying a failed run when the task doesn't say.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Something that can carry out the action of a scheduled task.
+#[async_trait::async_trait]
+pub trait JobExecutor: std::fmt::Debug + Send + Sync {
+    async fn execute(&self, action: &JobAction) -> Result<(), Error>;
+}
+
+/// Runs [`AppScheduledTask`]s on their schedule.
+///
+/// Every task gets a loop of its own which sleeps until the task is due, then
+/// hands its action to a [`JobExecutor`], retrying, timing out and
+/// cancelling runs as the task's spec asks.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    tasks: Vec<AppScheduledTask>,
+    executor: Arc<dyn JobExecutor>,
+    task_manager: Arc<dyn VirtualTaskManager>,
+}
+
+impl Scheduler {
+    pub fn new(executor: Arc<dyn JobExecutor>, task_manager: Arc<dyn VirtualTaskManager>) -> Self {
+        Scheduler {
+            tasks: Vec::new(),
+            executor,
+            task_manager,
+        }
+    }
+
+    pub fn with_task(&mut self, task: AppScheduledTask) -> &mut Self {
+        self.tasks.push(task);
+        self
+    }
+
+    pub fn with_tasks(&mut self, tasks: impl IntoIterator<Item = AppScheduledTask>) -> &mut Self {
+        self.tasks.extend(tasks);
+        self
+    }
+
+    /// Run every task in the background, on the task manager's thread pool.
+    pub fn spawn(self) -> Result<(), WasiThreadError> {
+        let task_manager = Arc::clone(&self.task_manager);
+        task_manager.task_shared(Box::new(move || Box::pin(self.run())))
+    }
+
+    /// Run every task until its schedule runs out.
+    ///
+    /// Schedules rarely run out, so this usually never returns.
+    pub async fn run(self) {
+        let Scheduler {
+            tasks,
+            executor,
+            task_manager,
+        } = self;
+
+        let loops = tasks.into_iter().map(|task| {
+            TaskLoop {
+                task: Arc::new(task),
+                executor: Arc::clone(&executor),
+                task_manager: Arc::clone(&task_manager),
+                running: Arc::new(AtomicUsize::new(0)),
+                handles: Arc::new(Mutex::new(Vec::new())),
+            }
+            .run()
+        });
+
+        futures::future::join_all(loops).await;
+    }
+}
+
+/// The state kept for a single scheduled task.
+#[derive(Debug)]
+struct TaskLoop {
+    task: Arc<AppScheduledTask>,
+    executor: Arc<dyn JobExecutor>,
+    task_manager: Arc<dyn VirtualTaskManager>,
+    /// How many runs are in progress.
+    running: Arc<AtomicUsize>,
+    /// Handles for cancelling the runs in progress.
+    handles: Arc<Mutex<Vec<AbortHandle>>>,
+}
+
+impl TaskLoop {
+    async fn run(self) {
+        let name = &self.task.name;
+        let timezone = self.task.spec.timezone.unwrap_or(JobTimezone::UTC);
+        let mut last_run = i64::MIN;
+
+        loop {
+            let after = unix_now().as_secs() as i64;
+            let Some(next) = self
+                .task
+                .spec
+                .schedule
+                .next_after(after.max(last_run), timezone)
+            else {
+                tracing::warn!(task = %name, "The task will never run again");
+                return;
+            };
+
+            let due = Duration::from_secs(next.max(0) as u64);
+            if let Some(delay) = due.checked_sub(unix_now()) {
+                self.task_manager.sleep_now(delay).await;
+            }
+            last_run = next;
+
+            if let Err(e) = self.start_run() {
+                tracing::error!(
+                    task = %name,
+                    error = &e as &dyn std::error::Error,
+                    "Unable to start the task",
+                );
+            }
+        }
+    }
+
+    fn start_run(&self) -> Result<(), WasiThreadError> {
+        let name = &self.task.name;
+
+        match self.task.spec.concurrency_policy.unwrap_or_default() {
+            ConcurrencyPolicy::Allow => {}
+            ConcurrencyPolicy::Forbid => {
+                if self.running.load(Ordering::SeqCst) > 0 {
+                    tracing::info!(task = %name, "Skipping a run, the previous one is still going");
+                    return Ok(());
+                }
+            }
+            ConcurrencyPolicy::Replace => {
+                for handle in self.handles.lock().unwrap().drain(..) {
+                    tracing::info!(task = %name, "Cancelling the previous run");
+                    handle.abort();
+                }
+            }
+        }
+
+        let (handle, registration) = AbortHandle::new_pair();
+        let run = Abortable::new(
+            run_with_retries(
+                Arc::clone(&self.task),
+                Arc::clone(&self.executor),
+                Arc::clone(&self.task_manager),
+            ),
+            registration,
+        );
+
+        let running = Arc::clone(&self.running);
+        running.fetch_add(1, Ordering::SeqCst);
+        {
+            let mut handles = self.handles.lock().unwrap();
+            handles.retain(|h| !h.is_aborted());
+            handles.push(handle.clone());
+        }
+        let handles = Arc::clone(&self.handles);
+
+        let result = self.task_manager.task_shared(Box::new(move || {
+            Box::pin(async move {
+                let _ = run.await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                // Marks the run as finished so it gets cleaned up
+                handle.abort();
+                handles.lock().unwrap().retain(|h| !h.is_aborted());
+            })
+        }));
+
+        if result.is_err() {
+            self.running.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        result
+    }
+}
+
+/// Run a task once, retrying it as often as its spec allows.
+async fn run_with_retries(
+    task: Arc<AppScheduledTask>,
+    executor: Arc<dyn JobExecutor>,
+    task_manager: Arc<dyn VirtualTaskManager>,
+) -> Result<(), Error> {
+    let name = &task.name;
+    let max_retries = task.spec.max_retries.unwrap_or(0);
+    let mut delay = task
+        .spec
+        .retry_delay
+        .map(|d| d.0)
+        .unwrap_or(DEFAULT_RETRY_DELAY);
+
+    let mut attempt = 0;
+    loop {
+        tracing::info!(task = %name, attempt, "Running a scheduled task");
+
+        match run_once(&task, executor.as_ref(), task_manager.as_ref()).await {
+            Ok(()) => {
+                tracing::info!(task = %name, "The scheduled task finished");
+                return Ok(());
+            }
+            Err(e) if attempt < max_retries => {
+                tracing::warn!(
+                    task = %name,
+                    error = &*e as &dyn std::error::Error,
+                    retry_in = ?delay,
+                    "The scheduled task failed",
+                );
+                task_manager.sleep_now(delay).await;
+                delay = delay.saturating_mul(2);
+                attempt += 1;
+            }
+            Err(e) => {
+                tracing::error!(
+                    task = %name,
+                    error = &*e as &dyn std::error::Error,
+                    "The scheduled task failed",
+                );
+                return Err(e);
+            }
+        }
+    }
+}
+
+async fn run_once(
+    task: &AppScheduledTask,
+    executor: &dyn JobExecutor,
+    task_manager: &dyn VirtualTaskManager,
+) -> Result<(), Error> {
+    let execution = executor.execute(&task.spec.invoke);
+
+    match task.spec.timeout {
+        Some(timeout) => {
+            let timed_out = task_manager.sleep_now(timeout.0);
+            match futures::future::select(execution, timed_out).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => Err(anyhow::anyhow!("Timed out after {timeout}")),
+            }
+        }
+        None => execution.await,
+    }
+}
+
+fn unix_now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// A [`JobExecutor`] which runs tasks against a WASIX app.
+///
+/// `fetch` actions send a request to the app, and `execute` actions run one
+/// of the package's commands with the same environment the app was given.
+#[derive(Debug, Clone)]
+pub struct AppJobExecutor {
+    pkg: BinaryPackage,
+    runner: WasiRunner,
+    runtime: Arc<dyn Runtime + Send + Sync>,
+    base_url: Option<Url>,
+    force_https: bool,
+}
+
+impl AppJobExecutor {
+    pub fn new(
+        pkg: BinaryPackage,
+        runner: WasiRunner,
+        runtime: Arc<dyn Runtime + Send + Sync>,
+    ) -> Self {
+        AppJobExecutor {
+            pkg,
+            runner,
+            runtime,
+            base_url: None,
+            force_https: false,
+        }
+    }
+
+    /// Set the URL the app is served on, which `fetch` actions are relative
+    /// to.
+    pub fn with_base_url(&mut self, url: Url) -> &mut Self {
+        self.base_url = Some(url);
+        self
+    }
+
+    /// Tell the executor the app redirects plain HTTP requests to HTTPS, so
+    /// `fetch` requests claim to have come through HTTPS like the requests
+    /// of a TLS-terminating proxy.
+    pub fn with_force_https(&mut self, enabled: bool) -> &mut Self {
+        self.force_https = enabled;
+        self
+    }
+
+    async fn fetch(&self, job: &FetchJob) -> Result<(), Error> {
+        let url = match &self.base_url {
+            Some(base) => base.join(&job.url),
+            None => Url::parse(&job.url),
+        }
+        .with_context(|| format!("Invalid URL \"{}\"", job.url))?;
+
+        let method = job.method.as_deref().unwrap_or("GET");
+        let method = Method::from_bytes(method.as_bytes())
+            .with_context(|| format!("Invalid HTTP method \"{method}\""))?;
+
+        let mut headers = http::HeaderMap::new();
+        if self.force_https {
+            headers.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+        }
+        for (name, value) in job.headers.iter().flatten() {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name \"{name}\""))?;
+            let value = HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for the \"{name}\" header"))?;
+            headers.insert(name, value);
+        }
+
+        let client = self
+            .runtime
+            .http_client()
+            .context("The runtime doesn't have an HTTP client")?;
+        let request = HttpRequest {
+            url,
+            method,
+            headers,
+            body: job.body.clone().map(String::into_bytes),
+            options: Default::default(),
+        };
+        let response = client
+            .request(request)
+            .await
+            .context("The request failed")?;
+
+        let status = response.status.as_u16();
+        match &job.success_status_codes {
+            Some(codes) => anyhow::ensure!(
+                codes.contains(&status),
+                "Expected a status code in {codes:?}, but got {status}"
+            ),
+            None => anyhow::ensure!(
+                response.status.is_success(),
+                "Expected a successful status code, but got {status}"
+            ),
+        }
+
+        Ok(())
+    }
+
+    async fn run_command(&self, job: &ExecuteJob) -> Result<(), Error> {
+        let command_name = job.command.as_str();
+        let cmd = self
+            .pkg
+            .get_command(command_name)
+            .with_context(|| format!("The package doesn't contain a \"{command_name}\" command"))?;
+        let wasi = cmd
+            .metadata()
+            .annotation("wasi")?
+            .unwrap_or_else(|| Wasi::new(command_name));
+        let exec_name = wasi.exec_name.as_deref().unwrap_or(command_name);
+
+        let mut runner = self.runner.clone();
+        if let Some(args) = &job.cli_args {
+            runner.with_args(args);
+        }
+        if let Some(env) = &job.env {
+            runner.with_envs(env);
+        }
+
+        let env = runner
+            .prepare_webc_env(
+                exec_name,
+                &wasi,
+                Some(&self.pkg),
+                Arc::clone(&self.runtime),
+                None,
+            )
+            .context("Unable to prepare the WASI environment")?
+            .build()?;
+
+        // Runs that time out or get replaced are dropped, and their process
+        // shouldn't outlive them
+        let mut guard = KillOnDrop(Some(env.process.clone()));
+
+        let store = self.runtime.new_store();
+        let mut handle = crate::bin_factory::spawn_exec(
+            self.pkg.clone(),
+            command_name,
+            store,
+            env,
+            &self.runtime,
+        )
+        .await
+        .context("Spawn failed")?;
+
+        let exit_code = handle
+            .wait_finished()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        guard.0 = None;
+
+        anyhow::ensure!(
+            exit_code.is_success(),
+            "The \"{command_name}\" command exited with {}",
+            exit_code.raw()
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl JobExecutor for AppJobExecutor {
+    async fn execute(&self, action: &JobAction) -> Result<(), Error> {
+        match action {
+            JobAction::Fetch(job) => self.fetch(job).await,
+            JobAction::Execute(job) => self.run_command(job).await,
+        }
+    }
+}
+
+struct KillOnDrop(Option<WasiProcess>);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        if let Some(process) = self.0.take() {
+            process.signal_process(Signal::Sigkill);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::BoxFuture;
+    use wasmer_config::package::PackageId;
+
+    use super::*;
+    use crate::{
+        http::{HttpClient, HttpResponse},
+        runtime::task_manager::tokio::TokioTaskManager,
+        PluggableRuntime,
+    };
+
+    #[derive(Debug, Default)]
+    struct FlakyExecutor {
+        failures: usize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl JobExecutor for FlakyExecutor {
+        async fn execute(&self, _action: &JobAction) -> Result<(), Error> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            anyhow::ensure!(call >= self.failures, "Attempt {call} failed");
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct HangingExecutor;
+
+    #[async_trait::async_trait]
+    impl JobExecutor for HangingExecutor {
+        async fn execute(&self, _action: &JobAction) -> Result<(), Error> {
+            futures::future::pending().await
+        }
+    }
+
+    fn task(yaml: &str) -> Arc<AppScheduledTask> {
+        Arc::new(serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failed_runs_are_retried() {
+        let task_manager: Arc<dyn VirtualTaskManager> = Arc::new(TokioTaskManager::default());
+        let task = task(
+            "name: test\nschedule: 1h\nmax_retries: 2\nretry_delay: 1ms\ninvoke:\n  fetch:\n    url: /\n",
+        );
+
+        let executor = Arc::new(FlakyExecutor {
+            failures: 2,
+            ..Default::default()
+        });
+        run_with_retries(Arc::clone(&task), executor.clone(), task_manager.clone())
+            .await
+            .unwrap();
+        assert_eq!(executor.calls.load(Ordering::SeqCst), 3);
+
+        let executor = Arc::new(FlakyExecutor {
+            failures: 5,
+            ..Default::default()
+        });
+        let err = run_with_retries(task, executor.clone(), task_manager)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Attempt 2 failed");
+        assert_eq!(executor.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_time_out() {
+        let task_manager: Arc<dyn VirtualTaskManager> = Arc::new(TokioTaskManager::default());
+        let task = task("name: test\nschedule: 1h\ntimeout: 10ms\ninvoke:\n  fetch:\n    url: /\n");
+
+        let err = run_with_retries(task, Arc::new(HangingExecutor), task_manager)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Timed out after 10ms");
+    }
+
+    /// Answers like an app that sets `redirect.force_https`.
+    #[derive(Debug)]
+    struct ForceHttpsApp;
+
+    impl HttpClient for ForceHttpsApp {
+        fn request(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, Error>> {
+            let is_https = request
+                .headers
+                .get("x-forwarded-proto")
+                .is_some_and(|proto| proto == "https");
+            let status = if is_https {
+                http::StatusCode::OK
+            } else {
+                http::StatusCode::PERMANENT_REDIRECT
+            };
+            Box::pin(async move {
+                Ok(HttpResponse {
+                    body: None,
+                    redirected: false,
+                    status,
+                    headers: http::HeaderMap::new(),
+                })
+            })
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn fetch_apps_that_force_https() {
+        let mut runtime = PluggableRuntime::new(Arc::new(TokioTaskManager::default()));
+        runtime.set_http_client(ForceHttpsApp);
+        let pkg = BinaryPackage {
+            id: PackageId::new_named("test/app", "1.0.0".parse().unwrap()),
+            package_ids: Vec::new(),
+            when_cached: None,
+            entrypoint_cmd: None,
+            hash: Default::default(),
+            webc_fs: Arc::new(virtual_fs::EmptyFileSystem::default()),
+            commands: Vec::new(),
+            uses: Vec::new(),
+            file_system_memory_footprint: 0,
+            additional_host_mapped_directories: Vec::new(),
+        };
+        let mut executor = AppJobExecutor::new(pkg, WasiRunner::new(), Arc::new(runtime));
+        executor.with_base_url("http://localhost:8080/".parse().unwrap());
+        let fetch = JobAction::Fetch(serde_yaml::from_str("url: /cron").unwrap());
+
+        let err = executor.execute(&fetch).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected a successful status code, but got 308"
+        );
+
+        executor.with_force_https(true);
+        executor.execute(&fetch).await.unwrap();
+    }
+}
@@ -169,6 +169,12 @@ impl WasiRunner {
         self
     }
 
+    /// Write a coredump of the program to `path` if it traps.
+    pub fn with_coredump_on_trap(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.wasi.coredump_on_trap = Some(path.into());
+        self
+    }
+
     pub fn with_snapshot_trigger(&mut self, on: SnapshotTrigger) -> &mut Self {
         self.wasi.snapshot_on.push(on);
         self
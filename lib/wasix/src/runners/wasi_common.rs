@@ -45,6 +45,7 @@ pub(crate) struct CommonWasiOptions {
     pub(crate) snapshot_interval: Option<std::time::Duration>,
     pub(crate) current_dir: Option<PathBuf>,
     pub(crate) additional_imports: Imports,
+    pub(crate) coredump_on_trap: Option<PathBuf>,
 }
 
 impl CommonWasiOptions {
@@ -93,6 +94,10 @@ impl CommonWasiOptions {
 
         builder.add_imports(&self.additional_imports);
 
+        if let Some(path) = &self.coredump_on_trap {
+            builder.set_coredump_on_trap(path);
+        }
+
         Ok(())
     }
 
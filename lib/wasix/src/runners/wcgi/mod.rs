@@ -1,6 +1,7 @@
 mod callbacks;
 mod create_env;
 mod handler;
+mod redirect;
 mod runner;
 
 pub use self::runner::{Config, WcgiRunner};
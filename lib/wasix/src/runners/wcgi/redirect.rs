@@ -0,0 +1,115 @@
+use std::{pin::Pin, task::Poll};
+
+use anyhow::Error;
+use futures::{Future, FutureExt};
+use http::{header, Request, Response, StatusCode};
+
+use super::super::Body;
+use crate::runners::body_from_data;
+
+/// A service that redirects plain HTTP requests to HTTPS, the way Wasmer Edge
+/// does for apps that set `redirect.force_https`.
+///
+/// The runner only speaks plain HTTP, so a request is considered secure when
+/// a TLS-terminating proxy in front of it set `X-Forwarded-Proto: https`.
+#[derive(Clone, Debug)]
+pub(crate) struct ForceHttps<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S> ForceHttps<S> {
+    pub(crate) fn new(inner: S, enabled: bool) -> Self {
+        ForceHttps { inner, enabled }
+    }
+}
+
+impl<S, B> tower::Service<Request<B>> for ForceHttps<S>
+where
+    S: tower::Service<
+        Request<B>,
+        Response = Response<Body>,
+        Error = Error,
+        Future = Pin<Box<dyn Future<Output = Result<Response<Body>, Error>> + Send>>,
+    >,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        if self.enabled {
+            if let Some(response) = https_redirect(&request) {
+                return futures::future::ready(Ok(response)).boxed();
+            }
+        }
+
+        self.inner.call(request)
+    }
+}
+
+/// Get the response redirecting a plain HTTP request to HTTPS, or [`None`]
+/// if the request was already made over HTTPS.
+///
+/// Requests without a `Host` header can't be redirected, so they are let
+/// through.
+pub(crate) fn https_redirect<B>(request: &Request<B>) -> Option<Response<Body>> {
+    let headers = request.headers();
+
+    let is_https = headers
+        .get("x-forwarded-proto")
+        .and_then(|proto| proto.to_str().ok())
+        .is_some_and(|proto| proto.eq_ignore_ascii_case("https"));
+    if is_https {
+        return None;
+    }
+
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+
+    Response::builder()
+        .status(StatusCode::PERMANENT_REDIRECT)
+        .header(header::LOCATION, format!("https://{host}{path}"))
+        .body(body_from_data(Vec::new()))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirect_plain_http_requests() {
+        let request = Request::get("/api/items?page=2")
+            .header(header::HOST, "example.com")
+            .body(())
+            .unwrap();
+
+        let response = https_redirect(&request).unwrap();
+
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "https://example.com/api/items?page=2"
+        );
+    }
+
+    #[test]
+    fn let_forwarded_https_requests_through() {
+        let request = Request::get("/")
+            .header(header::HOST, "example.com")
+            .header("x-forwarded-proto", "HTTPS")
+            .body(())
+            .unwrap();
+
+        assert!(https_redirect(&request).is_none());
+    }
+}
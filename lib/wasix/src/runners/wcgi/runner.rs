@@ -17,7 +17,10 @@ use crate::{
     capabilities::Capabilities,
     runners::{
         wasi_common::CommonWasiOptions,
-        wcgi::handler::{Handler, SharedState},
+        wcgi::{
+            handler::{Handler, SharedState},
+            redirect::ForceHttps,
+        },
         MappedDirectory,
     },
     runtime::task_manager::VirtualTaskManagerExt,
@@ -125,7 +128,7 @@ impl WcgiRunner {
             )
             .layer(CatchPanicLayer::new())
             .layer(CorsLayer::permissive())
-            .service(handler);
+            .service(ForceHttps::new(handler, self.config.force_https));
 
         let address = self.config.addr;
         tracing::info!(%address, "Starting the server");
@@ -204,6 +207,7 @@ impl crate::runners::Runner for WcgiRunner {
 pub struct Config {
     pub(crate) wasi: CommonWasiOptions,
     pub(crate) addr: SocketAddr,
+    pub(crate) force_https: bool,
     pub(crate) callbacks: Arc<dyn Callbacks>,
 }
 
@@ -213,6 +217,15 @@ impl Config {
         self
     }
 
+    /// Redirect requests that weren't made over HTTPS to HTTPS.
+    ///
+    /// The server itself only speaks plain HTTP, so requests are expected to
+    /// come through a TLS-terminating proxy that sets `X-Forwarded-Proto`.
+    pub fn force_https(&mut self, enabled: bool) -> &mut Self {
+        self.force_https = enabled;
+        self
+    }
+
     /// Add an argument to the WASI executable's command-line arguments.
     pub fn arg(&mut self, arg: impl Into<String>) -> &mut Self {
         self.wasi.args.push(arg.into());
@@ -338,6 +351,7 @@ impl Config {
     {
         Self {
             addr: ([127, 0, 0, 1], 8000).into(),
+            force_https: false,
             wasi: CommonWasiOptions::default(),
             callbacks: Arc::new(callbacks),
         }
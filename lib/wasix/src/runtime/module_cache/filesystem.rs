@@ -4,6 +4,7 @@ use std::sync::Arc;
 use tempfile::NamedTempFile;
 use tokio::io::AsyncWriteExt;
 use wasmer::{Engine, Module};
+use wasmer_cache::eviction::{self, CacheLimits};
 
 use crate::runtime::module_cache::{CacheError, ModuleCache, ModuleHash};
 use crate::runtime::task_manager::tokio::TokioTaskManager;
@@ -11,10 +12,13 @@ use crate::runtime::task_manager::VirtualTaskManagerExt;
 
 /// A cache that saves modules to a folder on the host filesystem using
 /// [`Module::serialize()`].
+///
+/// The cache is unbounded by default, see [`FileSystemCache::with_limits()`].
 #[derive(Debug, Clone)]
 pub struct FileSystemCache {
     cache_dir: PathBuf,
     task_manager: Arc<TokioTaskManager>,
+    limits: CacheLimits,
 }
 
 impl FileSystemCache {
@@ -22,9 +26,19 @@ impl FileSystemCache {
         FileSystemCache {
             cache_dir: cache_dir.into(),
             task_manager,
+            limits: CacheLimits::default(),
         }
     }
 
+    /// Bound the disk usage of the cache.
+    ///
+    /// Whenever a module is saved, the least recently used modules are
+    /// evicted until the cache fits in the limits again.
+    pub fn with_limits(mut self, limits: CacheLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     pub fn cache_dir(&self) -> &Path {
         &self.cache_dir
     }
@@ -59,6 +73,13 @@ impl ModuleCache for FileSystemCache {
                         move || match deserialize(&bytes, &engine) {
                             Ok(m) => {
                                 tracing::debug!("Cache hit!");
+                                if let Err(e) = eviction::mark_accessed(&path) {
+                                    tracing::debug!(
+                                        path=%path.display(),
+                                        error=&e as &dyn std::error::Error,
+                                        "Unable to update the access time of the cache file",
+                                    );
+                                }
                                 Ok(m)
                             }
                             Err(e) => {
@@ -104,6 +125,8 @@ impl ModuleCache for FileSystemCache {
             .spawn({
                 let task_manager = self.task_manager.clone();
                 let module = module.clone();
+                let cache_dir = self.cache_dir.clone();
+                let limits = self.limits;
 
                 async move {
                     let parent = path
@@ -142,6 +165,25 @@ impl ModuleCache for FileSystemCache {
                     temp.persist(&path).map_err(CacheError::other)?;
                     tracing::debug!(path=%path.display(), "Saved to disk");
 
+                    if !limits.is_unbounded() {
+                        let stats = task_manager
+                            .spawn_await(move || eviction::prune(&cache_dir, &limits))
+                            .await
+                            .unwrap();
+                        match stats {
+                            Ok(stats) if stats.removed > 0 => tracing::debug!(
+                                removed = stats.removed,
+                                freed = stats.freed,
+                                "Evicted the least recently used modules",
+                            ),
+                            Ok(_) => {}
+                            Err(e) => tracing::warn!(
+                                error = &e as &dyn std::error::Error,
+                                "Unable to evict modules from the cache",
+                            ),
+                        }
+                    }
+
                     Ok(())
                 }
             })
@@ -243,6 +285,29 @@ mod tests {
         assert!(cache_dir.is_dir());
     }
 
+    #[tokio::test]
+    async fn evict_least_recently_used() {
+        let temp = TempDir::new().unwrap();
+        let engine = Engine::default();
+        let module = Module::new(&engine, ADD_WAT).unwrap();
+        let first = ModuleHash::xxhash_from_bytes([0; 8]);
+        let second = ModuleHash::xxhash_from_bytes([1; 8]);
+        let cache = FileSystemCache::new(temp.path(), create_tokio_task_manager());
+        cache.save(first, &engine, &module).await.unwrap();
+        let first_path = cache.path(first, engine.deterministic_id());
+        let size = std::fs::metadata(&first_path).unwrap().len();
+        let cache = cache.with_limits(CacheLimits {
+            max_size: Some(size + size / 2),
+            max_age: None,
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        cache.save(second, &engine, &module).await.unwrap();
+
+        assert!(!first_path.exists());
+        assert!(cache.path(second, engine.deterministic_id()).exists());
+    }
+
     #[tokio::test]
     async fn missing_file() {
         let temp = TempDir::new().unwrap();
@@ -11,8 +11,12 @@ use bytes::Bytes;
 use http::{HeaderMap, Method};
 use tempfile::NamedTempFile;
 use url::Url;
+use wasmer_config::package::PackageHash;
 use wasmer_package::{
     package::WasmerPackageError,
+    signature::{
+        signature_path, KeyId, PackageSignature, PublicKey, SignatureError, SIGNATURE_EXTENSION,
+    },
     utils::{from_bytes, from_disk},
 };
 use webc::DetectError;
@@ -38,6 +42,7 @@ pub struct BuiltinPackageLoader {
     tokens: HashMap<String, String>,
 
     hash_validation: HashIntegrityValidationMode,
+    trust_policy: TrustPolicy,
 }
 
 /// Defines how to validate package hash integrity.
@@ -52,6 +57,98 @@ pub enum HashIntegrityValidationMode {
     FailOnHashMismatch,
 }
 
+/// Decides which packages may be loaded, based on their signatures.
+///
+/// Packages are signed with detached ed25519 signatures over their webc hash
+/// (see [`wasmer_package::signature`]). A package's signature is looked for
+/// next to its `*.webc` file, at the same path or URL with a `.sig` extension.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TrustPolicy {
+    /// Load packages without checking their signatures.
+    #[default]
+    AllowUnsigned,
+    /// Check signatures, but only emit a warning when a package is unsigned
+    /// or isn't signed by one of the trusted keys.
+    WarnUntrusted { trusted_keys: Vec<PublicKey> },
+    /// Refuse to load packages that aren't signed by one of the trusted keys.
+    RequireTrusted { trusted_keys: Vec<PublicKey> },
+}
+
+impl TrustPolicy {
+    /// Are signatures checked at all?
+    pub fn is_enforced(&self) -> bool {
+        !matches!(self, TrustPolicy::AllowUnsigned)
+    }
+
+    /// Check whether a package with the given hash and signature may be
+    /// loaded.
+    pub fn check(
+        &self,
+        package: &str,
+        hash: &PackageHash,
+        signature: Option<&PackageSignature>,
+    ) -> Result<(), UntrustedPackageError> {
+        let trusted_keys = match self {
+            TrustPolicy::AllowUnsigned => return Ok(()),
+            TrustPolicy::WarnUntrusted { trusted_keys }
+            | TrustPolicy::RequireTrusted { trusted_keys } => trusted_keys,
+        };
+
+        match Self::verify(package, hash, signature, trusted_keys) {
+            Err(error) if matches!(self, TrustPolicy::WarnUntrusted { .. }) => {
+                tracing::warn!(
+                    error = &error as &dyn std::error::Error,
+                    "Loading an untrusted package",
+                );
+                Ok(())
+            }
+            other => other,
+        }
+    }
+
+    fn verify(
+        package: &str,
+        hash: &PackageHash,
+        signature: Option<&PackageSignature>,
+        trusted_keys: &[PublicKey],
+    ) -> Result<(), UntrustedPackageError> {
+        let Some(signature) = signature else {
+            return Err(UntrustedPackageError::Unsigned {
+                package: package.to_string(),
+            });
+        };
+
+        let key = trusted_keys
+            .iter()
+            .find(|key| key.id() == signature.key_id())
+            .ok_or_else(|| UntrustedPackageError::UntrustedKey {
+                package: package.to_string(),
+                key_id: signature.key_id(),
+            })?;
+
+        key.verify(hash, signature)
+            .map_err(|error| UntrustedPackageError::InvalidSignature {
+                package: package.to_string(),
+                error,
+            })
+    }
+}
+
+/// The reasons a [`TrustPolicy`] may refuse to load a package.
+#[derive(Debug, thiserror::Error)]
+pub enum UntrustedPackageError {
+    #[error("\"{package}\" isn't signed")]
+    Unsigned { package: String },
+    #[error("\"{package}\" is signed by key {key_id}, which isn't trusted")]
+    UntrustedKey { package: String, key_id: KeyId },
+    #[error("The signature of \"{package}\" is invalid")]
+    InvalidSignature {
+        package: String,
+        #[source]
+        error: SignatureError,
+    },
+}
+
 impl BuiltinPackageLoader {
     pub fn new() -> Self {
         BuiltinPackageLoader {
@@ -59,6 +156,7 @@ impl BuiltinPackageLoader {
             client: Arc::new(crate::http::default_http_client().unwrap()),
             cache: None,
             hash_validation: HashIntegrityValidationMode::NoValidate,
+            trust_policy: TrustPolicy::default(),
             tokens: HashMap::new(),
         }
     }
@@ -71,6 +169,16 @@ impl BuiltinPackageLoader {
         self
     }
 
+    /// Set the policy used to decide which packages may be loaded.
+    ///
+    /// When the policy is [enforced][TrustPolicy::is_enforced], downloaded
+    /// packages always have their hashes checked, regardless of the
+    /// [`HashIntegrityValidationMode`].
+    pub fn with_trust_policy(mut self, policy: TrustPolicy) -> Self {
+        self.trust_policy = policy;
+        self
+    }
+
     pub fn with_cache_dir(self, cache_dir: impl Into<PathBuf>) -> Self {
         BuiltinPackageLoader {
             cache: Some(FileSystemCache {
@@ -163,6 +271,16 @@ impl BuiltinPackageLoader {
 
         if let Some(cache) = self.cache.as_ref() {
             if let Some(cached) = cache.lookup(hash).await? {
+                // A signature only vouches for the hash, so make sure nobody
+                // has swapped out the cached file
+                if self.trust_policy.is_enforced() && !cache.is_intact(hash).await? {
+                    tracing::warn!(
+                        pkg.hash=%hash,
+                        "The cached package doesn't match its hash, downloading it again",
+                    );
+                    return Ok(None);
+                }
+
                 // Note: We want to propagate it to the in-memory cache, too
                 tracing::debug!("Copying from the filesystem cache to the in-memory cache");
                 self.in_memory.save(&cached, *hash);
@@ -220,8 +338,10 @@ impl BuiltinPackageLoader {
         }
     }
 
+    /// Download a `*.webc` file, checking its hash according to the
+    /// [`HashIntegrityValidationMode`].
     #[tracing::instrument(level = "debug", skip_all, fields(%dist.webc, %dist.webc_sha256))]
-    async fn download(&self, dist: &DistributionInfo) -> Result<Bytes, Error> {
+    pub async fn download(&self, dist: &DistributionInfo) -> Result<Bytes, Error> {
         if dist.webc.scheme() == "file" {
             match crate::runtime::resolver::utils::file_path_from_url(&dist.webc) {
                 Ok(path) => {
@@ -234,7 +354,7 @@ impl BuiltinPackageLoader {
 
                     let bytes = bytes::Bytes::from(bytes);
 
-                    Self::validate_hash(&bytes, self.hash_validation, dist).await?;
+                    Self::validate_hash(&bytes, self.hash_validation_mode(), dist).await?;
 
                     return Ok(bytes);
                 }
@@ -284,11 +404,99 @@ impl BuiltinPackageLoader {
 
         let body = bytes::Bytes::from(body);
 
-        Self::validate_hash(&body, self.hash_validation, dist).await?;
+        Self::validate_hash(&body, self.hash_validation_mode(), dist).await?;
 
         Ok(body)
     }
 
+    fn hash_validation_mode(&self) -> HashIntegrityValidationMode {
+        if self.trust_policy.is_enforced() {
+            HashIntegrityValidationMode::FailOnHashMismatch
+        } else {
+            self.hash_validation
+        }
+    }
+
+    /// Check a package against the [`TrustPolicy`].
+    async fn check_trust(&self, summary: &PackageSummary) -> Result<(), Error> {
+        let hash = PackageHash::from_sha256_bytes(summary.dist.webc_sha256.as_bytes());
+        let signature = self
+            .signature(&summary.dist)
+            .await
+            .with_context(|| format!("Unable to get the signature of \"{}\"", summary.pkg.id))?;
+
+        self.trust_policy
+            .check(&summary.pkg.id.to_string(), &hash, signature.as_ref())?;
+
+        Ok(())
+    }
+
+    /// Look up the detached signature for a package, if it has one.
+    #[tracing::instrument(level = "debug", skip_all, fields(%dist.webc))]
+    async fn signature(&self, dist: &DistributionInfo) -> Result<Option<PackageSignature>, Error> {
+        if let Some(cache) = &self.cache {
+            if let Some(signature) = cache.lookup_signature(&dist.webc_sha256).await? {
+                return Ok(Some(signature));
+            }
+        }
+
+        let Some(contents) = self.download_signature(&dist.webc).await? else {
+            return Ok(None);
+        };
+        let signature: PackageSignature = contents.parse()?;
+
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.save_signature(&dist.webc_sha256, contents).await {
+                tracing::warn!(error = &*e, "Unable to cache the package's signature");
+            }
+        }
+
+        Ok(Some(signature))
+    }
+
+    async fn download_signature(&self, webc: &Url) -> Result<Option<String>, Error> {
+        if webc.scheme() == "file" {
+            if let Ok(path) = crate::runtime::resolver::utils::file_path_from_url(webc) {
+                let path = signature_path(path);
+                return match crate::spawn_blocking({
+                    let path = path.clone();
+                    move || std::fs::read_to_string(path)
+                })
+                .await?
+                {
+                    Ok(contents) => Ok(Some(contents)),
+                    Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                    Err(e) => {
+                        Err(Error::new(e).context(format!("Unable to read \"{}\"", path.display())))
+                    }
+                };
+            }
+        }
+
+        let mut url = webc.clone();
+        url.set_path(&format!("{}.{SIGNATURE_EXTENSION}", webc.path()));
+
+        let request = HttpRequest {
+            headers: self.headers(&url),
+            url,
+            method: Method::GET,
+            body: None,
+            options: Default::default(),
+        };
+        let response = self.client.request(request).await?;
+
+        if !response.is_ok() {
+            // Anything other than a signature means the package is unsigned
+            tracing::debug!(%response.status, "No signature found");
+            return Ok(None);
+        }
+
+        let body = response.body.unwrap_or_default();
+        let contents = String::from_utf8(body).context("The signature isn't valid UTF-8")?;
+
+        Ok(Some(contents))
+    }
+
     fn headers(&self, url: &Url) -> HeaderMap {
         let mut headers = HeaderMap::new();
         headers.insert("Accept", "application/webc".parse().unwrap());
@@ -331,6 +539,10 @@ impl PackageLoader for BuiltinPackageLoader {
         ),
     )]
     async fn load(&self, summary: &PackageSummary) -> Result<Container, Error> {
+        if self.trust_policy.is_enforced() {
+            self.check_trust(summary).await?;
+        }
+
         if let Some(container) = self.get_cached(&summary.dist.webc_sha256).await? {
             tracing::debug!("Cache hit!");
             return Ok(container);
@@ -425,6 +637,7 @@ struct FileSystemCache {
 
 impl FileSystemCache {
     const FILE_SUFFIX: &'static str = ".bin";
+    const SIGNATURE_SUFFIX: &'static str = ".sig";
 
     /// Validate that the cached image file names correspond to their actual
     /// file content hashes.
@@ -558,13 +771,64 @@ impl FileSystemCache {
         }
     }
 
+    /// Check that the cached file for `hash` really has that hash.
+    async fn is_intact(&self, hash: &WebcHash) -> Result<bool, Error> {
+        let path = self.path(hash);
+        let expected = *hash;
+
+        crate::spawn_blocking(move || {
+            let webc = std::fs::read(&path)
+                .with_context(|| format!("Unable to read \"{}\"", path.display()))?;
+            Ok(WebcHash::sha256(webc) == expected)
+        })
+        .await?
+    }
+
+    async fn lookup_signature(&self, hash: &WebcHash) -> Result<Option<PackageSignature>, Error> {
+        let path = self.path_with_suffix(hash, Self::SIGNATURE_SUFFIX);
+
+        let contents = crate::spawn_blocking({
+            let path = path.clone();
+            move || std::fs::read_to_string(path)
+        })
+        .await?;
+
+        match contents {
+            Ok(contents) => {
+                let signature = contents
+                    .parse()
+                    .with_context(|| format!("Unable to parse \"{}\"", path.display()))?;
+                Ok(Some(signature))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::new(e).context(format!("Unable to read \"{}\"", path.display()))),
+        }
+    }
+
+    async fn save_signature(&self, hash: &WebcHash, contents: String) -> Result<(), Error> {
+        let path = self.path_with_suffix(hash, Self::SIGNATURE_SUFFIX);
+
+        crate::spawn_blocking(move || {
+            let parent = path.parent().expect("Always within cache_dir");
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Unable to create \"{}\"", parent.display()))?;
+            std::fs::write(&path, contents)
+                .with_context(|| format!("Unable to write \"{}\"", path.display()))
+        })
+        .await?
+    }
+
     fn path(&self, hash: &WebcHash) -> PathBuf {
+        self.path_with_suffix(hash, Self::FILE_SUFFIX)
+    }
+
+    fn path_with_suffix(&self, hash: &WebcHash, suffix: &str) -> PathBuf {
         let hash = hash.as_bytes();
         let mut filename = String::with_capacity(hash.len() * 2);
         for b in hash {
             write!(filename, "{b:02x}").unwrap();
         }
-        filename.push_str(Self::FILE_SUFFIX);
+        filename.push_str(suffix);
 
         self.cache_dir.join(filename)
     }
@@ -690,6 +954,84 @@ mod tests {
     async fn cache_misses_will_trigger_a_download() {
         cache_misses_will_trigger_a_download_internal().await
     }
+
+    fn local_package(dir: &std::path::Path) -> PackageSummary {
+        std::fs::write(
+            dir.join("wasmer.toml"),
+            "[package]\nname = \"test/pkg\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        let webc = wasmer_package::package::Package::from_manifest(dir.join("wasmer.toml"))
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let path = dir.join("pkg.webc");
+        std::fs::write(&path, &webc).unwrap();
+
+        PackageSummary {
+            pkg: PackageInfo {
+                id: PackageId::new_named("test/pkg", "1.0.0".parse().unwrap()),
+                dependencies: Vec::new(),
+                commands: Vec::new(),
+                entrypoint: None,
+                filesystem: Vec::new(),
+            },
+            dist: DistributionInfo {
+                webc: crate::runtime::resolver::utils::url_from_file_path(path).unwrap(),
+                webc_sha256: WebcHash::sha256(&webc),
+            },
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn trust_policy_refuses_unsigned_and_untrusted_packages() {
+        use wasmer_package::signature::SigningKey;
+
+        let temp = TempDir::new().unwrap();
+        let summary = local_package(temp.path());
+        let hash = PackageHash::from_sha256_bytes(summary.dist.webc_sha256.as_bytes());
+        let trusted = SigningKey::generate().unwrap();
+        let untrusted = SigningKey::generate().unwrap();
+        let loader = || {
+            BuiltinPackageLoader::new().with_trust_policy(TrustPolicy::RequireTrusted {
+                trusted_keys: vec![trusted.public_key()],
+            })
+        };
+        let sign = |key: &SigningKey| {
+            let signature = key.sign(&hash).unwrap();
+            std::fs::write(
+                signature_path(temp.path().join("pkg.webc")),
+                signature.to_file_contents(&hash),
+            )
+            .unwrap();
+        };
+
+        let err = loader().load(&summary).await.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref(),
+                Some(UntrustedPackageError::Unsigned { .. })
+            ),
+            "{err:?}"
+        );
+
+        sign(&untrusted);
+        let err = loader().load(&summary).await.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref(),
+                Some(UntrustedPackageError::UntrustedKey { key_id, .. }) if *key_id == untrusted.id()
+            ),
+            "{err:?}"
+        );
+
+        sign(&trusted);
+        loader().load(&summary).await.unwrap();
+        // Unsigned packages are still fine by default
+        std::fs::remove_file(signature_path(temp.path().join("pkg.webc"))).unwrap();
+        BuiltinPackageLoader::new().load(&summary).await.unwrap();
+    }
 }
 
 #[cfg(test)]
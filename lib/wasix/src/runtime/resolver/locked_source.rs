@@ -0,0 +1,91 @@
+use wasmer_config::package::{Lockfile, PackageHash, PackageSource, LOCKFILE_NAME};
+
+use crate::runtime::resolver::{PackageSummary, QueryError, Source};
+
+/// A [`Source`] that pins packages to the versions recorded in a
+/// [`Lockfile`].
+///
+/// When a query matches packages from the lockfile, only those are returned,
+/// and their webc hashes are checked against the lockfile so a package can't
+/// silently change underneath it. Otherwise, every match is returned unless
+/// the source is [locked][LockedSource::locked], in which case the query
+/// fails.
+#[derive(Debug, Clone)]
+pub struct LockedSource<S> {
+    inner: S,
+    lockfile: Lockfile,
+    locked: bool,
+}
+
+impl<S> LockedSource<S> {
+    pub fn new(inner: S, lockfile: Lockfile) -> Self {
+        LockedSource {
+            inner,
+            lockfile,
+            locked: false,
+        }
+    }
+
+    /// Refuse to resolve any package that isn't in the lockfile.
+    pub fn locked(self, locked: bool) -> Self {
+        LockedSource { locked, ..self }
+    }
+
+    pub fn lockfile(&self) -> &Lockfile {
+        &self.lockfile
+    }
+
+    fn check_hash(&self, summary: &PackageSummary) -> Result<(), anyhow::Error> {
+        let Some(locked) = self.lockfile.get(&summary.pkg.id) else {
+            return Ok(());
+        };
+
+        let actual = PackageHash::from_sha256_bytes(summary.dist.webc_sha256.as_bytes());
+        if actual != locked.hash {
+            anyhow::bail!(
+                "the hash of \"{}\" is {actual}, but {LOCKFILE_NAME} expected {}",
+                summary.pkg.id,
+                locked.hash,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> Source for LockedSource<S>
+where
+    S: Source + Send + Sync,
+{
+    #[tracing::instrument(level = "debug", skip_all, fields(%package))]
+    async fn query(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+        let candidates = self.inner.query(package).await?;
+
+        let pinned: Vec<PackageSummary> = candidates
+            .iter()
+            .filter(|summary| self.lockfile.get(&summary.pkg.id).is_some())
+            .cloned()
+            .collect();
+
+        let candidates = if !pinned.is_empty() {
+            pinned
+        } else if self.locked {
+            return Err(QueryError::new_other(
+                anyhow::anyhow!(
+                    "no package in {LOCKFILE_NAME} satisfies this constraint and the lockfile can't be updated"
+                ),
+                package,
+            ));
+        } else {
+            candidates
+        };
+
+        for summary in &candidates {
+            self.check_hash(summary)
+                .map_err(|e| QueryError::new_other(e, package))?;
+        }
+
+        Ok(candidates)
+    }
+}
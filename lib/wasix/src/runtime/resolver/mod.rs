@@ -2,11 +2,14 @@ mod backend_source;
 mod filesystem_source;
 mod in_memory_source;
 mod inputs;
+mod locked_source;
 mod multi_source;
 mod outputs;
 mod resolve;
+mod solver;
 mod source;
 pub(crate) mod utils;
+mod vendored_source;
 mod web_source;
 
 pub use self::{
@@ -17,6 +20,7 @@ pub use self::{
         Command, Dependency, DistributionInfo, FileSystemMapping, PackageInfo, PackageSummary,
         WebcHash,
     },
+    locked_source::LockedSource,
     multi_source::{MultiSource, MultiSourceStrategy},
     outputs::{
         DependencyGraph, Edge, ItemLocation, Node, Resolution, ResolvedFileSystemMapping,
@@ -24,5 +28,6 @@ pub use self::{
     },
     resolve::{resolve, ResolveError},
     source::{QueryError, Source},
+    vendored_source::{VendorIndex, VendoredPackage, VendoredSource, VENDOR_INDEX_FILE_NAME},
     web_source::WebSource,
 };
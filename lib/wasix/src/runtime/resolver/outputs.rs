@@ -4,7 +4,7 @@ use petgraph::{
     graph::{DiGraph, NodeIndex},
     visit::EdgeRef,
 };
-use wasmer_config::package::PackageId;
+use wasmer_config::package::{LockedPackage, Lockfile, PackageHash, PackageId};
 
 use crate::runtime::resolver::{DistributionInfo, PackageInfo};
 
@@ -99,6 +99,38 @@ impl DependencyGraph {
         })
     }
 
+    /// Record the packages this graph resolved to in a [`Lockfile`].
+    ///
+    /// The root package is left out, but the packages its dependencies
+    /// resolved to are recorded in [`Lockfile::dependencies`].
+    pub fn to_lockfile(&self) -> Lockfile {
+        let mut lockfile = Lockfile::new();
+
+        for (id, dependencies) in self.iter_dependencies() {
+            let dependencies = dependencies
+                .into_iter()
+                .map(|(alias, dep)| (alias.to_string(), dep.clone()))
+                .collect();
+
+            if id == self.id() {
+                lockfile.dependencies = dependencies;
+                continue;
+            }
+
+            let Some(dist) = &self[id].dist else {
+                continue;
+            };
+            lockfile.packages.push(LockedPackage {
+                id: id.clone(),
+                hash: PackageHash::from_sha256_bytes(dist.webc_sha256.as_bytes()),
+                url: Some(dist.webc.clone()),
+                dependencies,
+            });
+        }
+
+        lockfile
+    }
+
     /// Visualise this graph as a DOT program.
     pub fn visualise(&self) -> String {
         let graph = self.graph.map(|_, node| &node.id, |_, edge| &edge.alias);
@@ -1,19 +1,15 @@
-use std::{
-    collections::{BTreeMap, HashSet, VecDeque},
-    path::PathBuf,
-};
+use std::{collections::BTreeMap, path::PathBuf};
 
 use petgraph::{
     graph::{DiGraph, NodeIndex},
     visit::EdgeRef,
 };
-use semver::Version;
 use wasmer_config::package::{PackageId, PackageSource};
 
 use crate::runtime::resolver::{
     outputs::{Edge, Node},
-    DependencyGraph, ItemLocation, PackageInfo, PackageSummary, QueryError, Resolution,
-    ResolvedPackage, Source,
+    solver::{self, SolvedPackage},
+    DependencyGraph, ItemLocation, PackageInfo, QueryError, Resolution, ResolvedPackage, Source,
 };
 
 use super::ResolvedFileSystemMapping;
@@ -42,14 +38,8 @@ pub enum ResolveError {
     },
     #[error("Dependency cycle detected: {}", print_cycle(_0))]
     Cycle(Vec<PackageId>),
-    #[error(
-        "Multiple versions of {package_name} were found {}",
-        versions.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "),
-    )]
-    DuplicateVersions {
-        package_name: String,
-        versions: Vec<Version>,
-    },
+    #[error("Unable to find versions of the dependencies which satisfy every constraint.\n{explanation}")]
+    Conflict { explanation: String },
 }
 
 fn registry_error_message(specifier: &PackageSource) -> String {
@@ -89,11 +79,9 @@ async fn resolve_dependency_graph(
     let DiscoveredPackages {
         root,
         graph,
-        indices,
         packages,
     } = discover_dependencies(root_id, root, source).await?;
 
-    check_for_duplicate_versions(indices.iter().copied().map(|ix| &graph[ix].id))?;
     log_dependencies(&graph, root);
 
     let graph = DependencyGraph::new(root, graph, packages);
@@ -106,75 +94,31 @@ async fn discover_dependencies(
     root: &PackageInfo,
     source: &dyn Source,
 ) -> Result<DiscoveredPackages, ResolveError> {
+    let solution = solver::solve(root_id, root, source).await?;
+
     let mut nodes: BTreeMap<PackageId, NodeIndex> = BTreeMap::new();
     let mut graph: DiGraph<Node, Edge> = DiGraph::new();
-
-    let root_index = graph.add_node(Node {
-        id: root_id.clone(),
-        pkg: root.clone(),
-        dist: None,
-    });
-    nodes.insert(root_id.clone(), root_index);
-
-    let mut to_visit = VecDeque::new();
-    to_visit.push_back(root_index);
-
-    while let Some(index) = to_visit.pop_front() {
-        let mut to_add = Vec::new();
-
-        for dep in &graph[index].pkg.dependencies {
-            // Get the latest version that satisfies our requirement. If we were
-            // doing this more rigorously, we would be narrowing the version
-            // down using existing requirements and trying to reuse the same
-            // dependency when possible.
-            let dep_summary =
-                source
-                    .latest(&dep.pkg)
-                    .await
-                    .map_err(|error| ResolveError::Registry {
-                        package: dep.pkg.clone(),
-                        error,
-                    })?;
-            let dep_id = dep_summary.package_id().clone();
-
-            let PackageSummary { pkg, dist } = dep_summary;
-
-            let alias = dep.alias().to_string();
-            let node = Node {
-                id: dep_id.clone(),
-                pkg,
-                dist: Some(dist),
-            };
-            // Note: We can't add the node to the graph directly because we're
-            // still iterating over it.
-            to_add.push((alias, node));
-        }
-
-        for (alias, node) in to_add {
-            let dep_id = node.id.clone();
-
-            let dep_index = match nodes.get(&dep_id) {
-                Some(&ix) => ix,
-                None => {
-                    // Create a new node and schedule its dependencies to be
-                    // retrieved
-                    let ix = graph.add_node(node);
-                    nodes.insert(dep_id, ix);
-                    to_visit.push_back(ix);
-                    ix
-                }
-            };
-
-            graph.add_edge(index, dep_index, Edge { alias });
+    for SolvedPackage { node, .. } in &solution {
+        let index = graph.add_node(node.clone());
+        nodes.insert(node.id.clone(), index);
+    }
+    for SolvedPackage { node, dependencies } in &solution {
+        for (alias, dep_id) in dependencies {
+            graph.add_edge(
+                nodes[&node.id],
+                nodes[dep_id],
+                Edge {
+                    alias: alias.clone(),
+                },
+            );
         }
     }
 
-    let sorted_indices = petgraph::algo::toposort(&graph, None).map_err(|_| cycle_error(&graph))?;
+    petgraph::algo::toposort(&graph, None).map_err(|_| cycle_error(&graph))?;
 
     Ok(DiscoveredPackages {
-        root: root_index,
+        root: nodes[root_id],
         graph,
-        indices: sorted_indices,
         packages: nodes,
     })
 }
@@ -210,8 +154,6 @@ fn cycle_error(graph: &petgraph::Graph<Node, Edge>) -> ResolveError {
 struct DiscoveredPackages {
     root: NodeIndex,
     graph: DiGraph<Node, Edge>,
-    /// All node indices, in topologically sorted order.
-    indices: Vec<NodeIndex>,
     packages: BTreeMap<PackageId, NodeIndex>,
 }
 
@@ -238,40 +180,6 @@ fn log_dependencies(graph: &DiGraph<Node, Edge>, root: NodeIndex) {
     }
 }
 
-/// As a workaround for the lack of "proper" dependency merging, we'll make sure
-/// only one copy of each package is in the dependency tree. If the same package
-/// is included in the tree multiple times, they all need to use the exact same
-/// version otherwise it's an error.
-fn check_for_duplicate_versions<'a, I>(package_ids: I) -> Result<(), ResolveError>
-where
-    I: Iterator<Item = &'a PackageId>,
-{
-    let mut package_versions: BTreeMap<&str, HashSet<&Version>> = BTreeMap::new();
-
-    for id in package_ids {
-        let Some(id) = id.as_named() else {
-            continue;
-        };
-        package_versions
-            .entry(&id.full_name)
-            .or_default()
-            .insert(&id.version);
-    }
-
-    for (package_name, versions) in package_versions {
-        if versions.len() > 1 {
-            let mut versions: Vec<_> = versions.into_iter().cloned().collect();
-            versions.sort();
-            return Err(ResolveError::DuplicateVersions {
-                package_name: package_name.to_string(),
-                versions,
-            });
-        }
-    }
-
-    Ok(())
-}
-
 /// Given some [`DiscoveredPackages`], figure out how the resulting "package"
 /// would look when loaded at runtime.
 fn resolve_package(dependency_graph: &DependencyGraph) -> Result<ResolvedPackage, ResolveError> {
@@ -382,11 +290,13 @@ fn resolve_package(dependency_graph: &DependencyGraph) -> Result<ResolvedPackage
 mod tests {
     use std::path::PathBuf;
 
-    use wasmer_config::package::NamedPackageIdent;
+    use semver::Version;
+
+    use wasmer_config::package::{LockedPackage, Lockfile, NamedPackageIdent, PackageHash};
 
     use crate::runtime::resolver::{
-        inputs::{DistributionInfo, FileSystemMapping, PackageInfo},
-        Dependency, InMemorySource, MultiSource,
+        inputs::{DistributionInfo, FileSystemMapping, PackageInfo, PackageSummary},
+        Dependency, InMemorySource, LockedSource, MultiSource,
     };
 
     use super::*;
@@ -807,7 +717,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn version_merging_isnt_implemented_yet() {
+    async fn incompatible_versions_are_explained() {
         let root_id = PackageId::new_named("root", "1.0.0".parse().unwrap());
         let mut builder = RegistryBuilder::new();
         builder
@@ -819,36 +729,72 @@ mod tests {
             .with_dependency("common", "^1.0.0");
         builder
             .register("second", "1.0.0")
-            .with_dependency("common", ">1.1,<1.3");
+            .with_dependency("common", "^2.0.0");
         builder.register("common", "1.0.0");
-        builder.register("common", "1.1.0");
-        builder.register("common", "1.2.0");
         builder.register("common", "1.5.0");
+        builder.register("common", "2.0.0");
         let registry = builder.finish();
         let root = builder.get(&root_id);
 
-        let result = resolve(&root.package_id(), &root.pkg, &registry).await;
-
-        match result {
-            Err(ResolveError::DuplicateVersions {
-                package_name,
-                versions,
-            }) => {
-                assert_eq!(package_name, "common");
-                assert_eq!(
-                    versions,
-                    [
-                        Version::parse("1.2.0").unwrap(),
-                        Version::parse("1.5.0").unwrap(),
-                    ]
-                );
-            }
-            _ => unreachable!("Expected a duplicate versions error, found {:?}", result),
-        }
+        let err = resolve(&root.package_id(), &root.pkg, &registry)
+            .await
+            .unwrap_err();
+
+        let ResolveError::Conflict { explanation } = err else {
+            unreachable!("Expected a conflict, found {err:?}");
+        };
+        assert_eq!(
+            explanation,
+            [
+                "Because second@1.0.0 depends on common@^2.0.0 and first@1.0.0 depends on common@^1.0.0, first@1.0.0 and second@1.0.0 are incompatible.",
+                "And because root@1.0.0 depends on first@=1.0.0, root@1.0.0 and second@1.0.0 are incompatible.",
+                "And because root@1.0.0 depends on second@=1.0.0, version solving failed.",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn backtrack_to_an_older_version() {
+        let root_id = PackageId::new_named("root", "1.0.0".parse().unwrap());
+        let dep_id = PackageId::new_named("dep", "1.0.0".parse().unwrap());
+        let common_id = PackageId::new_named("common", "1.0.0".parse().unwrap());
+        let mut builder = RegistryBuilder::new();
+        builder
+            .register("root", "1.0.0")
+            .with_dependency("dep", "^1.0.0")
+            .with_dependency("common", "^1.0.0");
+        builder
+            .register("dep", "1.0.0")
+            .with_dependency("common", "^1.0.0");
+        builder
+            .register("dep", "1.1.0")
+            .with_dependency("common", "^2.0.0");
+        builder
+            .register("dep", "1.2.0")
+            .with_dependency("missing", "^1.0.0");
+        builder.register("common", "1.0.0");
+        builder.register("common", "2.0.0");
+        let registry = builder.finish();
+        let root = builder.get(&root_id);
+
+        let resolution = resolve(&root.package_id(), &root.pkg, &registry)
+            .await
+            .unwrap();
+
+        let mut dependency_graph = builder.start_dependency_graph();
+        dependency_graph
+            .insert(root_id.clone())
+            .with_dependency(&dep_id)
+            .with_dependency(&common_id);
+        dependency_graph
+            .insert(dep_id.clone())
+            .with_dependency(&common_id);
+        dependency_graph.insert(common_id.clone());
+        assert_eq!(deps(&resolution), dependency_graph.finish());
     }
 
     #[tokio::test]
-    #[ignore = "Version merging isn't implemented"]
     async fn merge_compatible_versions() {
         let root_id = PackageId::new_named("root", "1.0.0".parse().unwrap());
         let first_id = PackageId::new_named("first", "1.0.0".parse().unwrap());
@@ -1213,4 +1159,93 @@ mod tests {
             }]
         );
     }
+
+    fn dep_registry() -> RegistryBuilder {
+        let mut builder = RegistryBuilder::new();
+        builder
+            .register("root", "1.0.0")
+            .with_dependency("dep", "^1.0.0");
+        builder.register("dep", "1.0.0");
+        builder.register("dep", "1.1.0");
+        builder
+    }
+
+    fn locked_dep(version: &str, hash: [u8; 32]) -> Lockfile {
+        let dep_id = PackageId::new_named("dep", version.parse().unwrap());
+        let mut lockfile = Lockfile::new();
+        lockfile
+            .dependencies
+            .insert("dep".to_string(), dep_id.clone());
+        lockfile.packages.push(LockedPackage {
+            id: dep_id,
+            hash: PackageHash::from_sha256_bytes(hash),
+            url: Some(format!("http://localhost/dep@{version}").parse().unwrap()),
+            dependencies: BTreeMap::new(),
+        });
+        lockfile
+    }
+
+    #[tokio::test]
+    async fn record_the_resolution_in_a_lockfile() {
+        let builder = dep_registry();
+        let registry = builder.finish();
+        let root = builder.get(&PackageId::new_named("root", "1.0.0".parse().unwrap()));
+
+        let resolution = resolve(&root.package_id(), &root.pkg, &registry)
+            .await
+            .unwrap();
+
+        assert_eq!(resolution.graph.to_lockfile(), locked_dep("1.1.0", [0; 32]));
+    }
+
+    #[tokio::test]
+    async fn lockfile_pins_dependency_versions() {
+        let builder = dep_registry();
+        let registry = LockedSource::new(builder.finish(), locked_dep("1.0.0", [0; 32]));
+        let root = builder.get(&PackageId::new_named("root", "1.0.0".parse().unwrap()));
+
+        let resolution = resolve(&root.package_id(), &root.pkg, &registry)
+            .await
+            .unwrap();
+
+        assert_eq!(resolution.graph.to_lockfile(), *registry.lockfile());
+    }
+
+    #[tokio::test]
+    async fn locked_source_rejects_packages_missing_from_the_lockfile() {
+        let builder = dep_registry();
+        let registry = LockedSource::new(builder.finish(), Lockfile::new()).locked(true);
+        let root = builder.get(&PackageId::new_named("root", "1.0.0".parse().unwrap()));
+
+        let err = resolve(&root.package_id(), &root.pkg, &registry)
+            .await
+            .unwrap_err();
+
+        let ResolveError::Registry { package, error } = err else {
+            panic!("Unexpected error: {err:?}");
+        };
+        assert_eq!(package.to_string(), "dep@^1.0.0");
+        assert!(matches!(error, QueryError::Other { .. }));
+    }
+
+    #[tokio::test]
+    async fn locked_source_checks_webc_hashes() {
+        let builder = dep_registry();
+        let registry = LockedSource::new(builder.finish(), locked_dep("1.0.0", [1; 32]));
+        let root = builder.get(&PackageId::new_named("root", "1.0.0".parse().unwrap()));
+
+        let err = resolve(&root.package_id(), &root.pkg, &registry)
+            .await
+            .unwrap_err();
+
+        let ResolveError::Registry { error, .. } = err else {
+            panic!("Unexpected error: {err:?}");
+        };
+        let expected = format!(
+            "failed to query package 'dep@^1.0.0': the hash of \"dep@1.0.0\" is {}, but wasmer.lock expected {}",
+            PackageHash::from_sha256_bytes([0; 32]),
+            PackageHash::from_sha256_bytes([1; 32]),
+        );
+        assert!(error.to_string().starts_with(&expected), "{error}");
+    }
 }
@@ -0,0 +1,872 @@
+//! A [PubGrub]-style version solver, which picks a version of every package
+//! in a dependency tree so that all of their constraints are satisfied.
+//!
+//! The solver alternates between deriving facts from the constraints it knows
+//! about ("unit propagation") and picking the newest allowed version of a
+//! package ("decision making"). When a decision leads to a conflict, the
+//! conflict is turned into a new constraint explaining its root cause, and
+//! the solver backtracks to the point where that constraint would have
+//! prevented it. If the root cause involves the root package alone, there is
+//! no solution, and the chain of constraints which led to it explains why.
+//!
+//! Versions are only ever picked from the results of [`Source::query()`], so
+//! sets of versions are either a finite set of known versions or all versions
+//! but a finite set.
+//!
+//! [PubGrub]: https://github.com/dart-lang/pub/blob/master/doc/solver.md
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Display},
+};
+
+use semver::Version;
+use wasmer_config::package::{PackageId, PackageIdent, PackageSource};
+
+use crate::runtime::resolver::{
+    outputs::Node, Dependency, PackageInfo, PackageSummary, QueryError, ResolveError, Source,
+};
+
+/// The version used for packages which are only known by their hash.
+const UNVERSIONED: Version = Version::new(0, 0, 0);
+
+/// A package which has been selected, along with the packages selected for
+/// its dependencies.
+#[derive(Debug, Clone)]
+pub(crate) struct SolvedPackage {
+    pub node: Node,
+    /// The alias and ID of each dependency.
+    pub dependencies: Vec<(String, PackageId)>,
+}
+
+/// Pick a version of every package the root package depends on, directly or
+/// indirectly.
+///
+/// The root package comes first in the result, and packages only appear in
+/// it when something depends on them.
+pub(crate) async fn solve(
+    root_id: &PackageId,
+    root: &PackageInfo,
+    source: &dyn Source,
+) -> Result<Vec<SolvedPackage>, ResolveError> {
+    let (root_package, root_version) = Package::of(root_id);
+    let mut solver = Solver {
+        source,
+        root: root_package.clone(),
+        incompatibilities: Vec::new(),
+        by_package: HashMap::new(),
+        assignments: Vec::new(),
+        level: 0,
+        versions: HashMap::new(),
+        queries: HashMap::new(),
+    };
+    solver
+        .versions
+        .entry(root_package.clone())
+        .or_default()
+        .insert(
+            root_version.clone(),
+            Node {
+                id: root_id.clone(),
+                pkg: root.clone(),
+                dist: None,
+            },
+        );
+
+    let id = solver.push(Incompatibility {
+        terms: BTreeMap::from([(
+            root_package.clone(),
+            Term::Negative(VersionSet::singleton(root_version)),
+        )]),
+        cause: Cause::Root,
+    });
+    solver.index(id);
+
+    let mut next = root_package;
+    loop {
+        solver.propagate(next)?;
+        match solver.choose_package() {
+            Some(package) => next = solver.decide(package).await?,
+            None => break,
+        }
+    }
+
+    solver.solution().await
+}
+
+/// The name under which versions of a package are solved for.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Package {
+    /// A package from a registry, which may have several versions.
+    Named(String),
+    /// A package only known by its hash, which has a single version.
+    Hashed(PackageId),
+}
+
+impl Package {
+    fn of(id: &PackageId) -> (Self, Version) {
+        match id {
+            PackageId::Named(named) => (
+                Package::Named(named.full_name.clone()),
+                named.version.clone(),
+            ),
+            PackageId::Hash(_) => (Package::Hashed(id.clone()), UNVERSIONED),
+        }
+    }
+}
+
+impl Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Package::Named(name) => f.write_str(name),
+            Package::Hashed(id) => Display::fmt(id, f),
+        }
+    }
+}
+
+/// A set of versions: either a finite set, or all versions but a finite set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VersionSet {
+    Only(BTreeSet<Version>),
+    AllExcept(BTreeSet<Version>),
+}
+
+impl VersionSet {
+    fn empty() -> Self {
+        VersionSet::Only(BTreeSet::new())
+    }
+
+    fn singleton(version: Version) -> Self {
+        VersionSet::Only(BTreeSet::from([version]))
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, VersionSet::Only(versions) if versions.is_empty())
+    }
+
+    fn contains(&self, version: &Version) -> bool {
+        match self {
+            VersionSet::Only(versions) => versions.contains(version),
+            VersionSet::AllExcept(versions) => !versions.contains(version),
+        }
+    }
+
+    fn complement(&self) -> Self {
+        match self {
+            VersionSet::Only(versions) => VersionSet::AllExcept(versions.clone()),
+            VersionSet::AllExcept(versions) => VersionSet::Only(versions.clone()),
+        }
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        match (self, other) {
+            (VersionSet::Only(a), VersionSet::Only(b)) => {
+                VersionSet::Only(a.intersection(b).cloned().collect())
+            }
+            (VersionSet::Only(a), VersionSet::AllExcept(b))
+            | (VersionSet::AllExcept(b), VersionSet::Only(a)) => {
+                VersionSet::Only(a.difference(b).cloned().collect())
+            }
+            (VersionSet::AllExcept(a), VersionSet::AllExcept(b)) => {
+                VersionSet::AllExcept(a.union(b).cloned().collect())
+            }
+        }
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        self.complement()
+            .intersection(&other.complement())
+            .complement()
+    }
+}
+
+/// A statement about the version of a package.
+///
+/// A positive term is satisfied when the package is selected with a version
+/// in the set. A negative term is satisfied when the package isn't selected,
+/// or is selected with a version outside of the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Positive(VersionSet),
+    Negative(VersionSet),
+}
+
+impl Term {
+    /// The term satisfied by anything.
+    fn any() -> Self {
+        Term::Negative(VersionSet::empty())
+    }
+
+    fn negate(&self) -> Self {
+        match self {
+            Term::Positive(versions) => Term::Negative(versions.clone()),
+            Term::Negative(versions) => Term::Positive(versions.clone()),
+        }
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Term::Positive(a), Term::Positive(b)) => Term::Positive(a.intersection(b)),
+            (Term::Positive(a), Term::Negative(b)) | (Term::Negative(b), Term::Positive(a)) => {
+                Term::Positive(a.intersection(&b.complement()))
+            }
+            (Term::Negative(a), Term::Negative(b)) => Term::Negative(a.union(b)),
+        }
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        self.negate().intersection(&other.negate()).negate()
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Term::Positive(versions) if versions.is_empty())
+    }
+
+    /// Whether every package selection allowed by `other` satisfies this term.
+    fn is_satisfied_by(&self, other: &Term) -> bool {
+        other.intersection(self) == *other
+    }
+
+    /// Whether no package selection allowed by `other` satisfies this term.
+    fn is_contradicted_by(&self, other: &Term) -> bool {
+        other.intersection(self).is_empty()
+    }
+}
+
+/// A set of terms which can't all be true at the same time.
+#[derive(Debug, Clone)]
+struct Incompatibility {
+    terms: BTreeMap<Package, Term>,
+    cause: Cause,
+}
+
+impl Incompatibility {
+    fn dependency(
+        package: Package,
+        version: Version,
+        dependency: Package,
+        versions: VersionSet,
+        cause: Cause,
+    ) -> Self {
+        let mut terms = BTreeMap::from([(package, Term::Positive(VersionSet::singleton(version)))]);
+        let term = Term::Negative(versions);
+        // A package depending on itself
+        terms
+            .entry(dependency)
+            .and_modify(|existing| *existing = existing.intersection(&term))
+            .or_insert(term);
+
+        Incompatibility { terms, cause }
+    }
+}
+
+/// Why an incompatibility exists.
+#[derive(Debug, Clone)]
+enum Cause {
+    /// The root package has to be selected.
+    Root,
+    /// A version of a package depends on another package.
+    Dependency {
+        dependent: PackageId,
+        dependency: PackageSource,
+    },
+    /// A dependency of a version of a package doesn't exist.
+    Unavailable {
+        dependent: PackageId,
+        error: QueryError,
+    },
+    /// None of the known versions of a package are allowed.
+    NoVersions,
+    /// Derived from two other incompatibilities while resolving a conflict.
+    Derived(usize, usize),
+}
+
+/// A term which was decided or derived, in the order it was added.
+#[derive(Debug, Clone)]
+struct Assignment {
+    package: Package,
+    term: Term,
+    /// The number of decisions made before this assignment.
+    level: usize,
+    /// The incompatibility this assignment was derived from, or `None` for
+    /// decisions.
+    cause: Option<usize>,
+}
+
+/// How an incompatibility relates to the current assignments.
+enum Relation {
+    /// All of its terms are satisfied.
+    Satisfied,
+    /// All of its terms but the one for this package are satisfied.
+    AlmostSatisfied(Package),
+    /// One of its terms is contradicted, so it can't be satisfied.
+    Contradicted,
+    Inconclusive,
+}
+
+struct Solver<'a> {
+    source: &'a dyn Source,
+    root: Package,
+    incompatibilities: Vec<Incompatibility>,
+    /// The incompatibilities taken into account for each package, by index.
+    by_package: HashMap<Package, Vec<usize>>,
+    assignments: Vec<Assignment>,
+    level: usize,
+    /// Every known version of each package.
+    versions: HashMap<Package, BTreeMap<Version, Node>>,
+    /// The package and versions matching each dependency seen so far.
+    queries: HashMap<PackageSource, Result<(Package, VersionSet), QueryError>>,
+}
+
+impl<'a> Solver<'a> {
+    fn push(&mut self, incompatibility: Incompatibility) -> usize {
+        self.incompatibilities.push(incompatibility);
+        self.incompatibilities.len() - 1
+    }
+
+    /// Take an incompatibility into account during propagation.
+    fn index(&mut self, id: usize) {
+        for package in self.incompatibilities[id].terms.keys() {
+            self.by_package.entry(package.clone()).or_default().push(id);
+        }
+    }
+
+    /// The intersection of all the terms assigned to a package.
+    fn accumulated(&self, package: &Package) -> Term {
+        self.assignments
+            .iter()
+            .filter(|assignment| assignment.package == *package)
+            .fold(Term::any(), |term, assignment| {
+                term.intersection(&assignment.term)
+            })
+    }
+
+    fn relation(&self, id: usize) -> Relation {
+        let mut unsatisfied = None;
+
+        for (package, term) in &self.incompatibilities[id].terms {
+            let accumulated = self.accumulated(package);
+            if term.is_satisfied_by(&accumulated) {
+                continue;
+            }
+            if term.is_contradicted_by(&accumulated) {
+                return Relation::Contradicted;
+            }
+            if unsatisfied.is_some() {
+                return Relation::Inconclusive;
+            }
+            unsatisfied = Some(package.clone());
+        }
+
+        match unsatisfied {
+            Some(package) => Relation::AlmostSatisfied(package),
+            None => Relation::Satisfied,
+        }
+    }
+
+    /// Derive everything that follows from the terms assigned to a package.
+    fn propagate(&mut self, package: Package) -> Result<(), ResolveError> {
+        let mut changed = vec![package];
+
+        while let Some(package) = changed.pop() {
+            let ids = self.by_package.get(&package).cloned().unwrap_or_default();
+
+            // Newer incompatibilities usually lead to conflicts sooner
+            for id in ids.into_iter().rev() {
+                match self.relation(id) {
+                    Relation::Satisfied => {
+                        let root_cause = self.resolve_conflict(id)?;
+                        let Relation::AlmostSatisfied(package) = self.relation(root_cause) else {
+                            unreachable!("The root cause of a conflict is almost satisfied");
+                        };
+                        self.derive(&package, root_cause);
+                        changed.clear();
+                        changed.push(package);
+                        break;
+                    }
+                    Relation::AlmostSatisfied(package) => {
+                        self.derive(&package, id);
+                        if !changed.contains(&package) {
+                            changed.push(package);
+                        }
+                    }
+                    Relation::Contradicted | Relation::Inconclusive => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Assign the only term of an almost satisfied incompatibility which
+    /// keeps it from being satisfied.
+    fn derive(&mut self, package: &Package, cause: usize) {
+        let term = self.incompatibilities[cause].terms[package].negate();
+        self.assignments.push(Assignment {
+            package: package.clone(),
+            term,
+            level: self.level,
+            cause: Some(cause),
+        });
+    }
+
+    /// Find the root cause of a satisfied incompatibility, and backtrack to
+    /// the point where it is almost satisfied.
+    fn resolve_conflict(&mut self, mut id: usize) -> Result<usize, ResolveError> {
+        tracing::trace!(conflict = %self.describe(id), "Resolving a conflict");
+        let mut is_new = false;
+
+        loop {
+            if self.is_terminal(id) {
+                return Err(ResolveError::Conflict {
+                    explanation: self.explain(id),
+                });
+            }
+
+            let (satisfier, previous_level) = self.find_satisfier(id);
+            let satisfier = self.assignments[satisfier].clone();
+
+            let Some(cause) = satisfier
+                .cause
+                .filter(|_| previous_level == satisfier.level)
+            else {
+                self.assignments
+                    .retain(|assignment| assignment.level <= previous_level);
+                self.level = previous_level;
+                if is_new {
+                    self.index(id);
+                }
+                return Ok(id);
+            };
+
+            // Combine the incompatibility with the one the satisfier was
+            // derived from, which no longer mentions the satisfier's package
+            let package = &satisfier.package;
+            let mut terms = self.incompatibilities[id].terms.clone();
+            let term = terms.remove(package).expect("The satisfier's package");
+            let cause_terms = &self.incompatibilities[cause].terms;
+            for (other, other_term) in cause_terms {
+                if other != package {
+                    terms
+                        .entry(other.clone())
+                        .and_modify(|term| *term = term.intersection(other_term))
+                        .or_insert_with(|| other_term.clone());
+                }
+            }
+            let term = term.union(&cause_terms[package]);
+            if term != Term::any() {
+                terms.insert(package.clone(), term);
+            }
+
+            id = self.push(Incompatibility {
+                terms,
+                cause: Cause::Derived(id, cause),
+            });
+            is_new = true;
+            tracing::trace!(cause = %self.describe(id), "Derived a cause of the conflict");
+        }
+    }
+
+    /// Whether an incompatibility means there is no solution.
+    fn is_terminal(&self, id: usize) -> bool {
+        let terms = &self.incompatibilities[id].terms;
+        match terms.iter().next() {
+            None => true,
+            Some((package, term)) => {
+                terms.len() == 1 && *package == self.root && matches!(term, Term::Positive(_))
+            }
+        }
+    }
+
+    /// Find the earliest assignment which satisfies a satisfied
+    /// incompatibility, and the decision level at which the incompatibility
+    /// would be almost satisfied without it.
+    fn find_satisfier(&self, id: usize) -> (usize, usize) {
+        let terms = &self.incompatibilities[id].terms;
+
+        let mut satisfiers = BTreeMap::new();
+        for (package, term) in terms {
+            let mut accumulated = Term::any();
+            for (index, assignment) in self.assignments.iter().enumerate() {
+                if assignment.package != *package {
+                    continue;
+                }
+                accumulated = accumulated.intersection(&assignment.term);
+                if term.is_satisfied_by(&accumulated) {
+                    satisfiers.insert(package, index);
+                    break;
+                }
+            }
+        }
+
+        let (&package, &satisfier) = satisfiers
+            .iter()
+            .max_by_key(|(_, &index)| index)
+            .expect("The incompatibility is satisfied");
+        let mut previous_level = satisfiers
+            .iter()
+            .filter(|(other, _)| **other != package)
+            .map(|(_, &index)| self.assignments[index].level)
+            .max()
+            .unwrap_or(0);
+
+        // The satisfier may only satisfy the term together with an earlier
+        // assignment to the same package
+        let term = &terms[package];
+        let satisfier_term = &self.assignments[satisfier].term;
+        if !term.is_satisfied_by(satisfier_term) {
+            let mut accumulated = Term::any();
+            for assignment in &self.assignments[..satisfier] {
+                if assignment.package != *package {
+                    continue;
+                }
+                accumulated = accumulated.intersection(&assignment.term);
+                if term.is_satisfied_by(&accumulated.intersection(satisfier_term)) {
+                    previous_level = previous_level.max(assignment.level);
+                    break;
+                }
+            }
+        }
+
+        (satisfier, previous_level)
+    }
+
+    /// Pick the next package to decide on: one which has to be selected, but
+    /// hasn't been yet. The one with the fewest allowed versions goes first,
+    /// as it is the most likely to conflict.
+    fn choose_package(&self) -> Option<Package> {
+        let mut packages: Vec<&Package> = Vec::new();
+        for assignment in &self.assignments {
+            if !packages.contains(&&assignment.package) {
+                packages.push(&assignment.package);
+            }
+        }
+
+        packages
+            .into_iter()
+            .filter(|package| !self.is_decided(package))
+            .filter_map(|package| match self.accumulated(package) {
+                Term::Positive(allowed) => {
+                    Some((package, self.allowed_versions(package, &allowed).len()))
+                }
+                Term::Negative(_) => None,
+            })
+            .min_by_key(|(_, versions)| *versions)
+            .map(|(package, _)| package.clone())
+    }
+
+    fn is_decided(&self, package: &Package) -> bool {
+        self.assignments
+            .iter()
+            .any(|assignment| assignment.cause.is_none() && assignment.package == *package)
+    }
+
+    fn allowed_versions(&self, package: &Package, allowed: &VersionSet) -> Vec<Version> {
+        self.versions
+            .get(package)
+            .into_iter()
+            .flat_map(|versions| versions.keys())
+            .filter(|version| allowed.contains(version))
+            .cloned()
+            .collect()
+    }
+
+    /// Select the newest allowed version of a package, after adding the
+    /// incompatibilities for its dependencies.
+    async fn decide(&mut self, package: Package) -> Result<Package, ResolveError> {
+        let term = self.accumulated(&package);
+        let Term::Positive(allowed) = &term else {
+            unreachable!("Only packages which have to be selected are decided on");
+        };
+
+        let Some(version) = self.allowed_versions(&package, allowed).pop() else {
+            let id = self.push(Incompatibility {
+                terms: BTreeMap::from([(package.clone(), term)]),
+                cause: Cause::NoVersions,
+            });
+            self.index(id);
+            return Ok(package);
+        };
+
+        let node = self.versions[&package][&version].clone();
+        let mut conflicts = false;
+        for dependency in &node.pkg.dependencies {
+            let incompatibility = self
+                .dependency_incompatibility(&package, &version, &node.id, dependency)
+                .await?;
+            let id = self.push(incompatibility);
+            self.index(id);
+
+            // Let propagation deal with versions which are already known to
+            // conflict
+            conflicts |= self.incompatibilities[id]
+                .terms
+                .iter()
+                .filter(|(other, _)| **other != package)
+                .all(|(other, term)| term.is_satisfied_by(&self.accumulated(other)));
+        }
+
+        if !conflicts {
+            tracing::trace!(%package, %version, "Selecting a version");
+            self.level += 1;
+            self.assignments.push(Assignment {
+                package: package.clone(),
+                term: Term::Positive(VersionSet::singleton(version)),
+                level: self.level,
+                cause: None,
+            });
+        }
+
+        Ok(package)
+    }
+
+    async fn dependency_incompatibility(
+        &mut self,
+        package: &Package,
+        version: &Version,
+        dependent: &PackageId,
+        dependency: &Dependency,
+    ) -> Result<Incompatibility, ResolveError> {
+        match self.query(&dependency.pkg).await {
+            Ok((dependency_package, versions)) => Ok(Incompatibility::dependency(
+                package.clone(),
+                version.clone(),
+                dependency_package,
+                versions,
+                Cause::Dependency {
+                    dependent: dependent.clone(),
+                    dependency: dependency.pkg.clone(),
+                },
+            )),
+            // Other versions of a dependency may not need the missing package,
+            // but there is nothing to fall back to for the root package
+            Err(error @ (QueryError::NotFound { .. } | QueryError::NoMatches { .. }))
+                if *package != self.root =>
+            {
+                Ok(Incompatibility {
+                    terms: BTreeMap::from([(
+                        package.clone(),
+                        Term::Positive(VersionSet::singleton(version.clone())),
+                    )]),
+                    cause: Cause::Unavailable {
+                        dependent: dependent.clone(),
+                        error,
+                    },
+                })
+            }
+            Err(error) => Err(ResolveError::Registry {
+                package: dependency.pkg.clone(),
+                error,
+            }),
+        }
+    }
+
+    /// Find the package and versions matching a dependency, remembering the
+    /// versions which were found.
+    async fn query(&mut self, pkg: &PackageSource) -> Result<(Package, VersionSet), QueryError> {
+        if let Some(result) = self.queries.get(pkg) {
+            return result.clone();
+        }
+
+        let result = self.source.query(pkg).await.and_then(|summaries| {
+            let summaries: Vec<PackageSummary> = match pkg {
+                PackageSource::Ident(PackageIdent::Named(named)) => {
+                    let name = named.full_name();
+                    summaries
+                        .into_iter()
+                        .filter(|summary| {
+                            summary
+                                .pkg
+                                .id
+                                .as_named()
+                                .is_some_and(|id| id.full_name == name)
+                        })
+                        .collect()
+                }
+                // Anything else refers to a single package
+                _ => summaries.into_iter().take(1).collect(),
+            };
+
+            let mut matches = None;
+            for PackageSummary { pkg, dist } in summaries {
+                let (package, version) = Package::of(&pkg.id);
+                let (_, versions) =
+                    matches.get_or_insert_with(|| (package.clone(), BTreeSet::new()));
+                versions.insert(version.clone());
+                self.versions
+                    .entry(package)
+                    .or_default()
+                    .entry(version)
+                    .or_insert_with(|| Node {
+                        id: pkg.id.clone(),
+                        pkg,
+                        dist: Some(dist),
+                    });
+            }
+
+            matches
+                .map(|(package, versions)| (package, VersionSet::Only(versions)))
+                .ok_or_else(|| QueryError::NoMatches {
+                    query: pkg.clone(),
+                    archived_versions: Vec::new(),
+                })
+        });
+
+        self.queries.insert(pkg.clone(), result.clone());
+        result
+    }
+
+    /// The selected version of each package, and what their dependencies
+    /// resolved to.
+    async fn solution(&mut self) -> Result<Vec<SolvedPackage>, ResolveError> {
+        let selected: HashMap<Package, Version> = self
+            .assignments
+            .iter()
+            .filter(|assignment| assignment.cause.is_none())
+            .filter_map(|assignment| match &assignment.term {
+                Term::Positive(VersionSet::Only(versions)) => versions
+                    .first()
+                    .map(|version| (assignment.package.clone(), version.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let mut solution = Vec::new();
+        let mut to_visit = vec![self.root.clone()];
+        let mut visited = BTreeSet::new();
+
+        while let Some(package) = to_visit.pop() {
+            if !visited.insert(package.clone()) {
+                continue;
+            }
+            let node = self.versions[&package][&selected[&package]].clone();
+
+            let mut dependencies = Vec::new();
+            for dependency in &node.pkg.dependencies {
+                let (dependency_package, _) =
+                    self.query(&dependency.pkg)
+                        .await
+                        .map_err(|error| ResolveError::Registry {
+                            package: dependency.pkg.clone(),
+                            error,
+                        })?;
+                let version = &selected[&dependency_package];
+                let id = self.versions[&dependency_package][version].id.clone();
+                dependencies.push((dependency.alias().to_string(), id));
+                to_visit.push(dependency_package);
+            }
+
+            solution.push(SolvedPackage { node, dependencies });
+        }
+
+        Ok(solution)
+    }
+
+    /// Explain why an incompatibility exists, one line per step.
+    fn explain(&self, id: usize) -> String {
+        let mut lines = Vec::new();
+        self.explain_into(id, &mut lines);
+        lines.join("\n")
+    }
+
+    fn explain_into(&self, id: usize, lines: &mut Vec<String>) {
+        let Cause::Derived(left, right) = self.incompatibilities[id].cause else {
+            lines.push(format!("{}.", self.describe(id)));
+            return;
+        };
+        let is_derived = |id: usize| matches!(self.incompatibilities[id].cause, Cause::Derived(..));
+
+        match (is_derived(left), is_derived(right)) {
+            (true, false) => {
+                self.explain_into(left, lines);
+                lines.push(format!(
+                    "And because {}, {}.",
+                    self.describe(right),
+                    self.describe(id)
+                ));
+            }
+            (false, true) => {
+                self.explain_into(right, lines);
+                lines.push(format!(
+                    "And because {}, {}.",
+                    self.describe(left),
+                    self.describe(id)
+                ));
+            }
+            (both_derived, _) => {
+                if both_derived {
+                    self.explain_into(left, lines);
+                    self.explain_into(right, lines);
+                }
+                lines.push(format!(
+                    "Because {} and {}, {}.",
+                    self.describe(left),
+                    self.describe(right),
+                    self.describe(id)
+                ));
+            }
+        }
+    }
+
+    /// Describe an incompatibility as a sentence.
+    fn describe(&self, id: usize) -> String {
+        let incompatibility = &self.incompatibilities[id];
+        match &incompatibility.cause {
+            Cause::Root => format!("{} is being resolved", self.root),
+            Cause::Dependency {
+                dependent,
+                dependency,
+            } => format!("{dependent} depends on {dependency}"),
+            Cause::Unavailable { dependent, error } => {
+                format!("{dependent} can't be used ({error})")
+            }
+            Cause::NoVersions | Cause::Derived(..) => self.describe_terms(&incompatibility.terms),
+        }
+    }
+
+    fn describe_terms(&self, terms: &BTreeMap<Package, Term>) -> String {
+        let mut positive = Vec::new();
+        let mut negative = Vec::new();
+        for (package, term) in terms {
+            match term {
+                Term::Positive(versions) => positive.push(describe_versions(package, versions)),
+                Term::Negative(versions) => negative.push(describe_versions(package, versions)),
+            }
+        }
+
+        match (positive.as_slice(), negative.as_slice()) {
+            ([], []) => "version solving failed".to_string(),
+            ([_], []) if terms.contains_key(&self.root) => "version solving failed".to_string(),
+            ([package], []) => format!("{package} can't be used"),
+            (_, []) => format!("{} are incompatible", positive.join(" and ")),
+            ([], _) => format!("{} is required", negative.join(" or ")),
+            (_, _) => format!(
+                "{} requires {}",
+                positive.join(" and "),
+                negative.join(" or ")
+            ),
+        }
+    }
+}
+
+fn describe_versions(package: &Package, versions: &VersionSet) -> String {
+    let list = |versions: &BTreeSet<Version>| {
+        versions
+            .iter()
+            .map(|version| version.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match (package, versions) {
+        (Package::Hashed(_), _) => package.to_string(),
+        (_, VersionSet::Only(versions)) if versions.len() == 1 => {
+            format!("{package}@{}", list(versions))
+        }
+        (_, VersionSet::Only(versions)) => format!("{package}@{{{}}}", list(versions)),
+        (_, VersionSet::AllExcept(versions)) if versions.is_empty() => package.to_string(),
+        (_, VersionSet::AllExcept(versions)) => {
+            format!("{package} (except {})", list(versions))
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error};
+use serde::{Deserialize, Serialize};
+use wasmer_config::package::{PackageHash, PackageId, PackageIdent, PackageSource};
+
+use wasmer_package::utils::from_bytes;
+
+use crate::runtime::resolver::{
+    DistributionInfo, PackageInfo, PackageSummary, QueryError, Source, WebcHash,
+};
+
+/// The name of the index at the root of a vendor directory.
+pub static VENDOR_INDEX_FILE_NAME: &str = "index.toml";
+
+/// A [`Source`] that resolves packages from a vendor directory (as created by
+/// `wasmer package vendor`) without needing network access.
+///
+/// A vendor directory contains the `*.webc` files of every package in a
+/// dependency tree, plus a [`VendorIndex`] so queries only need to open the
+/// files for matching packages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VendoredSource {
+    dir: PathBuf,
+    index: VendorIndex,
+}
+
+impl VendoredSource {
+    /// Open the vendor directory at `dir`.
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        let dir = dir
+            .canonicalize()
+            .with_context(|| format!("Unable to find \"{}\"", dir.display()))?;
+        let index = VendorIndex::load(&dir)?;
+
+        Ok(VendoredSource { dir, index })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn index(&self) -> &VendorIndex {
+        &self.index
+    }
+
+    async fn load(&self, pkg: &VendoredPackage) -> Result<PackageSummary, Error> {
+        let path = self.dir.join(&pkg.path);
+        let webc = crate::block_in_place(|| std::fs::read(&path))
+            .with_context(|| format!("Unable to read \"{}\"", path.display()))?;
+
+        let webc_sha256 = WebcHash::sha256(&webc);
+        let hash = PackageHash::from_sha256_bytes(webc_sha256.as_bytes());
+        if hash != pkg.hash {
+            anyhow::bail!(
+                "the hash of \"{}\" is {hash}, but the vendor index expected {}",
+                path.display(),
+                pkg.hash,
+            );
+        }
+
+        let container = crate::block_in_place(|| from_bytes(webc))
+            .with_context(|| format!("Unable to parse \"{}\"", path.display()))?;
+        let url = crate::runtime::resolver::utils::url_from_file_path(&path)
+            .ok_or_else(|| anyhow::anyhow!("Unable to turn \"{}\" into a URL", path.display()))?;
+
+        // The index records the id the package was resolved as, which may
+        // not be derivable from the webc itself
+        let pkg =
+            PackageInfo::from_manifest(pkg.id.clone(), container.manifest(), container.version())
+                .context("Unable to determine the package's metadata")?;
+
+        Ok(PackageSummary {
+            pkg,
+            dist: DistributionInfo {
+                webc: url,
+                webc_sha256,
+            },
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Source for VendoredSource {
+    #[tracing::instrument(level = "debug", skip_all, fields(%package))]
+    async fn query(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+        let matches: Vec<&VendoredPackage> = match package {
+            PackageSource::Ident(PackageIdent::Named(named)) => {
+                let full_name = named.full_name();
+                let versions: Vec<_> = self
+                    .index
+                    .packages
+                    .iter()
+                    .filter_map(|pkg| {
+                        let id = pkg.id.as_named()?;
+                        (id.full_name == full_name).then_some((pkg, &id.version))
+                    })
+                    .collect();
+
+                if versions.is_empty() {
+                    return Err(QueryError::NotFound {
+                        query: package.clone(),
+                    });
+                }
+
+                let constraint = named.version_or_default();
+                let matches: Vec<_> = versions
+                    .into_iter()
+                    .filter(|(_, version)| constraint.matches(version))
+                    .map(|(pkg, _)| pkg)
+                    .collect();
+
+                if matches.is_empty() {
+                    return Err(QueryError::NoMatches {
+                        query: package.clone(),
+                        archived_versions: Vec::new(),
+                    });
+                }
+
+                matches
+            }
+            PackageSource::Ident(PackageIdent::Hash(hash)) => {
+                let matches: Vec<_> = self
+                    .index
+                    .packages
+                    .iter()
+                    .filter(|pkg| pkg.hash == *hash)
+                    .collect();
+
+                if matches.is_empty() {
+                    return Err(QueryError::NotFound {
+                        query: package.clone(),
+                    });
+                }
+
+                matches
+            }
+            PackageSource::Url(_) | PackageSource::Path(_) => {
+                return Err(QueryError::Unsupported {
+                    query: package.clone(),
+                })
+            }
+        };
+
+        let mut summaries = Vec::new();
+
+        for pkg in matches {
+            let summary = self
+                .load(pkg)
+                .await
+                .map_err(|error| QueryError::new_other(error, package))?;
+            summaries.push(summary);
+        }
+
+        Ok(summaries)
+    }
+}
+
+/// The index of the packages in a vendor directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VendorIndex {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<VendoredPackage>,
+}
+
+impl VendorIndex {
+    /// Read the index of the vendor directory at `dir`.
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let path = dir.join(VENDOR_INDEX_FILE_NAME);
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read \"{}\"", path.display()))?;
+
+        toml::from_str(&contents).with_context(|| format!("Unable to parse \"{}\"", path.display()))
+    }
+
+    /// Write the index to the vendor directory at `dir`.
+    pub fn save(&self, dir: &Path) -> Result<(), Error> {
+        let path = dir.join(VENDOR_INDEX_FILE_NAME);
+        let contents = toml::to_string(self).context("Unable to serialize the vendor index")?;
+
+        std::fs::write(&path, contents)
+            .with_context(|| format!("Unable to write \"{}\"", path.display()))
+    }
+}
+
+/// A package in a [`VendorIndex`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VendoredPackage {
+    pub id: PackageId,
+    /// The hash of the package's `*.webc` file.
+    pub hash: PackageHash,
+    /// The package's `*.webc` file, relative to the vendor directory.
+    pub path: PathBuf,
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use wasmer_package::package::Package;
+
+    use super::*;
+
+    fn vendor(dir: &Path, name: &str, version: &str) -> VendoredPackage {
+        let pkg_dir = TempDir::new().unwrap();
+        let manifest = pkg_dir.path().join("wasmer.toml");
+        std::fs::write(
+            &manifest,
+            format!(
+                r#"
+                [package]
+                name = "{name}"
+                version = "{version}"
+
+                [[module]]
+                name = "main"
+                source = "main.wasm"
+                "#
+            ),
+        )
+        .unwrap();
+        // Packages don't record their name or version, so give each version
+        // a different module to tell their webcs apart
+        let custom_section = [&[0, 2 + version.len() as u8, 1, b'v'], version.as_bytes()].concat();
+        let wasm = [b"\0asm\x01\0\0\0".as_slice(), &custom_section].concat();
+        std::fs::write(pkg_dir.path().join("main.wasm"), wasm).unwrap();
+        let webc = Package::from_manifest(&manifest)
+            .unwrap()
+            .serialize()
+            .unwrap();
+
+        let path = PathBuf::from(format!("{}@{version}.webc", name.replace('/', "--")));
+        std::fs::write(dir.join(&path), &webc).unwrap();
+
+        VendoredPackage {
+            id: PackageId::new_named(name, version.parse().unwrap()),
+            hash: PackageHash::from_sha256_bytes(WebcHash::sha256(&webc).as_bytes()),
+            path,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn query_vendored_packages() {
+        let temp = TempDir::new().unwrap();
+        let index = VendorIndex {
+            packages: vec![
+                vendor(temp.path(), "wasmer/dep", "1.0.0"),
+                vendor(temp.path(), "wasmer/dep", "2.0.0"),
+            ],
+        };
+        index.save(temp.path()).unwrap();
+        let source = VendoredSource::new(temp.path()).unwrap();
+        assert_eq!(*source.index(), index);
+
+        let summaries = source
+            .query(&"wasmer/dep@^1.0".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].pkg.id, index.packages[0].id);
+
+        let by_hash = PackageSource::from(PackageIdent::Hash(index.packages[1].hash.clone()));
+        let summaries = source.query(&by_hash).await.unwrap();
+        assert_eq!(summaries[0].pkg.id, index.packages[1].id);
+
+        let err = source
+            .query(&"wasmer/dep@^3.0".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, QueryError::NoMatches { .. }));
+        let err = source
+            .query(&"wasmer/missing".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, QueryError::NotFound { .. }));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn detect_modified_webcs() {
+        let temp = TempDir::new().unwrap();
+        let mut pkg = vendor(temp.path(), "wasmer/dep", "1.0.0");
+        pkg.hash = PackageHash::from_sha256_bytes([0; 32]);
+        VendorIndex {
+            packages: vec![pkg],
+        }
+        .save(temp.path())
+        .unwrap();
+        let source = VendoredSource::new(temp.path()).unwrap();
+
+        let err = source
+            .query(&"wasmer/dep@1.0.0".parse().unwrap())
+            .await
+            .unwrap_err();
+
+        let QueryError::Other { error, .. } = err else {
+            panic!("Unexpected error: {err:?}");
+        };
+        assert!(
+            error.to_string().contains("vendor index expected"),
+            "{error}"
+        );
+    }
+}
@@ -82,6 +82,9 @@ pub struct WasiEnvBuilder {
     pub(super) capabilites: Capabilities,
     pub(super) additional_imports: Imports,
 
+    /// Where to write a coredump if the program traps.
+    pub(super) coredump_on_trap: Option<PathBuf>,
+
     #[cfg(feature = "journal")]
     pub(super) snapshot_on: Vec<SnapshotTrigger>,
 
@@ -685,6 +688,17 @@ impl WasiEnvBuilder {
         self.capabilites = capabilities;
     }
 
+    /// Write a coredump of the program to `path` if it traps.
+    pub fn coredump_on_trap(mut self, path: impl Into<PathBuf>) -> Self {
+        self.set_coredump_on_trap(path);
+        self
+    }
+
+    /// Write a coredump of the program to `path` if it traps.
+    pub fn set_coredump_on_trap(&mut self, path: impl Into<PathBuf>) {
+        self.coredump_on_trap = Some(path.into());
+    }
+
     #[cfg(feature = "journal")]
     pub fn add_snapshot_trigger(&mut self, on: SnapshotTrigger) {
         self.snapshot_on.push(on);
@@ -948,6 +962,7 @@ impl WasiEnvBuilder {
             #[cfg(feature = "journal")]
             snapshot_on: self.snapshot_on,
             additional_imports: self.additional_imports,
+            coredump_on_trap: self.coredump_on_trap,
         };
 
         Ok(init)
@@ -1065,7 +1080,16 @@ impl WasiEnvBuilder {
             .get_function(entry_function.as_deref().unwrap_or("_start"))?;
         env.data(&store).thread.set_status_running();
 
-        let result = crate::run_wasi_func_start(start, store);
+        let result = start.call(store, &[]);
+        if let Err(err) = &result {
+            crate::coredump::on_thread_error(&env, store, err);
+        }
+        let result = result
+            .map(|_| ())
+            .map_err(|err| match err.downcast::<WasiError>() {
+                Ok(err) => WasiRuntimeError::Wasi(err),
+                Err(err) => WasiRuntimeError::Runtime(err),
+            });
         let (result, exit_code) = super::wasi_exit_code(result);
 
         let pid = env.data(&store).pid();
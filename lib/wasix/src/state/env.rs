@@ -27,6 +27,7 @@ use crate::journal::{DynJournal, JournalEffector, SnapshotTrigger};
 use crate::{
     bin_factory::{BinFactory, BinaryPackage, BinaryPackageCommand},
     capabilities::Capabilities,
+    coredump::CoredumpCollector,
     fs::{WasiFsRoot, WasiInodes},
     import_object_for_all_wasi_versions,
     os::task::{
@@ -42,7 +43,7 @@ use crate::{
 use wasmer_types::ModuleHash;
 
 pub(crate) use super::handles::*;
-use super::{conv_env_vars, WasiState};
+use super::{conv_env_vars, Linker, WasiState};
 
 /// Various [`TypedFunction`] and [`Global`] handles for an active WASI(X) instance.
 ///
@@ -126,6 +127,9 @@ pub struct WasiInstanceHandles {
     #[allow(dead_code)]
     pub(crate) asyncify_stop_rewind: Option<TypedFunction<(), ()>>,
 
+    /// The shared libraries that were loaded into this instance
+    pub(crate) linker: Linker,
+
     /// asyncify_get_state(): call this to get the current value of the
     /// internal "__asyncify_state" variable as described above.
     /// It can be used to distinguish between unwinding/rewinding and normal
@@ -162,6 +166,7 @@ impl WasiInstanceHandles {
                 .ok(),
             has_stack_checkpoint,
             signal_set: false,
+            linker: Linker::new(),
             asyncify_start_unwind: instance
                 .exports
                 .get_typed_function(store, "asyncify_start_unwind")
@@ -249,6 +254,9 @@ pub struct WasiEnvInit {
     /// Indicates triggers that will cause a snapshot to be taken
     #[cfg(feature = "journal")]
     pub snapshot_on: Vec<SnapshotTrigger>,
+
+    /// Where to write a coredump of the process if it traps
+    pub coredump_on_trap: Option<PathBuf>,
 }
 
 impl WasiEnvInit {
@@ -288,6 +296,7 @@ impl WasiEnvInit {
             #[cfg(feature = "journal")]
             snapshot_on: self.snapshot_on.clone(),
             additional_imports: self.additional_imports.clone(),
+            coredump_on_trap: self.coredump_on_trap.clone(),
         }
     }
 }
@@ -513,6 +522,10 @@ impl WasiEnv {
             process.inner.0.lock().unwrap().snapshot_on = init.snapshot_on.into_iter().collect();
         }
 
+        if let Some(path) = init.coredump_on_trap {
+            process.lock().coredump = Some(Arc::new(CoredumpCollector::new(path)));
+        }
+
         let layout = WasiMemoryLayout::default();
         let thread = if let Some(t) = init.thread {
             t
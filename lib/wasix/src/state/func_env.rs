@@ -48,6 +48,12 @@ impl WasiFunctionEnv {
         // Create a new store and put the memory object in it
         // (but only if it has imported memory)
         let mut store = env.runtime.new_store();
+        if let SpawnMemoryType::ShareMemory(_, parent) | SpawnMemoryType::CopyMemory(_, parent) =
+            &spawn_type
+        {
+            store.as_store_mut().inherit_resource_limiter(parent);
+            store.as_store_mut().inherit_host_profiler(parent);
+        }
         let memory = env
             .tasks()
             .build_memory(&mut store.as_store_mut(), spawn_type)?;
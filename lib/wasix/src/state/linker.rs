@@ -0,0 +1,862 @@
+//! Loading of shared libraries at runtime (`dlopen` and friends).
+//!
+//! Shared libraries follow the WebAssembly [dynamic linking convention]: they
+//! carry a `dylink.0` custom section that describes how much memory and how
+//! many table slots they need, they import the memory, the indirect function
+//! table and the stack pointer of the main module, and they reference the
+//! addresses of data and functions through `GOT.mem` and `GOT.func` globals.
+//!
+//! Libraries are instantiated in the store of the thread that loads them.
+//! Their memory is shared with every other thread, but instances that already
+//! exist (or are created by `fork`) don't see their functions. Neither the
+//! memory nor the table slots of a library are reclaimed when it is closed.
+//!
+//! [dynamic linking convention]: https://github.com/WebAssembly/tool-conventions/blob/main/DynamicLinking.md
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use futures::future::LocalBoxFuture;
+use virtual_fs::{AsyncReadExt, FileSystem};
+use wasmer::{
+    AsStoreMut, Engine, Extern, Function, FunctionEnv, Global, Imports, Instance, Memory, Module,
+    Table, Value,
+};
+use wasmer_types::ModuleHash;
+use wasmer_wasix_types::wasi::{DlFlags, DlHandle, Errno};
+
+use crate::{import_object_for_all_wasi_versions, runtime::load_module, SpawnError, WasiEnv};
+
+/// Directories that are searched for libraries that are referenced by name,
+/// after the ones listed in `LD_LIBRARY_PATH`.
+const LIBRARY_PATH: &[&str] = &["/lib", "/usr/lib", "/usr/local/lib"];
+
+/// The handle that refers to the main module and to all the libraries that
+/// were loaded with [`DlFlags::GLOBAL`].
+pub const MAIN_HANDLE: DlHandle = 0;
+
+const WASM_DYLINK_MEM_INFO: u8 = 1;
+const WASM_DYLINK_NEEDED: u8 = 2;
+const WASM_DYLINK_IMPORT_INFO: u8 = 4;
+const WASM_SYMBOL_BINDING_WEAK: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LinkError {
+    #[error("library not found: {0}")]
+    NotFound(String),
+    #[error("unable to read \"{}\"", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("\"{}\" is not a shared library", .0.display())]
+    NotSharedLibrary(PathBuf),
+    #[error("invalid dylink.0 section: {0}")]
+    InvalidDylinkSection(&'static str),
+    #[error("unable to compile \"{}\"", path.display())]
+    Compile {
+        path: PathBuf,
+        #[source]
+        error: SpawnError,
+    },
+    #[error("the main module must export \"{0}\" to load shared libraries")]
+    MissingMainExport(&'static str),
+    #[error("unresolved import \"{module}.{name}\"")]
+    UnresolvedImport { module: String, name: String },
+    #[error("out of memory")]
+    OutOfMemory,
+    #[error("shared libraries can only be loaded while the process has a single thread")]
+    MultipleThreads,
+    #[error("invalid library handle {0}")]
+    InvalidHandle(DlHandle),
+    #[error("undefined symbol: {0}")]
+    UndefinedSymbol(String),
+    #[error(transparent)]
+    Instantiation(Box<wasmer::InstantiationError>),
+    #[error(transparent)]
+    Runtime(#[from] wasmer::RuntimeError),
+    #[error(transparent)]
+    Memory(#[from] wasmer::MemoryAccessError),
+}
+
+impl From<wasmer::InstantiationError> for LinkError {
+    fn from(error: wasmer::InstantiationError) -> Self {
+        LinkError::Instantiation(Box::new(error))
+    }
+}
+
+impl LinkError {
+    pub fn as_errno(&self) -> Errno {
+        match self {
+            LinkError::NotFound(_) | LinkError::UndefinedSymbol(_) => Errno::Noent,
+            LinkError::Read { .. } => Errno::Io,
+            LinkError::NotSharedLibrary(_)
+            | LinkError::InvalidDylinkSection(_)
+            | LinkError::Compile { .. }
+            | LinkError::UnresolvedImport { .. }
+            | LinkError::Instantiation(_) => Errno::Noexec,
+            LinkError::MissingMainExport(_) | LinkError::MultipleThreads => Errno::Notsup,
+            LinkError::OutOfMemory => Errno::Nomem,
+            LinkError::InvalidHandle(_) => Errno::Inval,
+            LinkError::Runtime(_) | LinkError::Memory(_) => Errno::Fault,
+        }
+    }
+}
+
+/// The contents of the `dylink.0` custom section of a shared library.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DylinkInfo {
+    /// Size of the data of the library, in bytes.
+    pub memory_size: u32,
+    /// Alignment of the data of the library, as a power of two.
+    pub memory_align: u32,
+    /// Number of slots the library needs in the indirect function table.
+    pub table_size: u32,
+    /// Alignment of the table slots of the library, as a power of two.
+    pub table_align: u32,
+    /// The libraries that need to be loaded before this one.
+    pub needed: Vec<String>,
+    /// The `(module, name)` imports that may stay undefined.
+    pub weak_imports: HashSet<(String, String)>,
+}
+
+impl DylinkInfo {
+    /// Reads the `dylink.0` section of a module, if it has one.
+    pub fn from_module(module: &Module) -> Result<Option<Self>, LinkError> {
+        module
+            .custom_sections("dylink.0")
+            .next()
+            .map(|section| Self::parse(&section))
+            .transpose()
+    }
+
+    /// Parses the contents of a `dylink.0` section.
+    pub fn parse(mut data: &[u8]) -> Result<Self, LinkError> {
+        let mut info = DylinkInfo::default();
+
+        while !data.is_empty() {
+            let kind = read_u8(&mut data)?;
+            let len = read_u32(&mut data)? as usize;
+            if len > data.len() {
+                return Err(LinkError::InvalidDylinkSection("truncated subsection"));
+            }
+            let (mut payload, rest) = data.split_at(len);
+            data = rest;
+
+            match kind {
+                WASM_DYLINK_MEM_INFO => {
+                    info.memory_size = read_u32(&mut payload)?;
+                    info.memory_align = read_u32(&mut payload)?;
+                    info.table_size = read_u32(&mut payload)?;
+                    info.table_align = read_u32(&mut payload)?;
+                }
+                WASM_DYLINK_NEEDED => {
+                    for _ in 0..read_u32(&mut payload)? {
+                        info.needed.push(read_string(&mut payload)?);
+                    }
+                }
+                WASM_DYLINK_IMPORT_INFO => {
+                    for _ in 0..read_u32(&mut payload)? {
+                        let module = read_string(&mut payload)?;
+                        let name = read_string(&mut payload)?;
+                        let flags = read_u32(&mut payload)?;
+                        if flags & WASM_SYMBOL_BINDING_WEAK != 0 {
+                            info.weak_imports.insert((module, name));
+                        }
+                    }
+                }
+                // The export info and runtime path don't affect linking
+                _ => {}
+            }
+        }
+
+        Ok(info)
+    }
+}
+
+fn read_u8(data: &mut &[u8]) -> Result<u8, LinkError> {
+    let (byte, rest) = data
+        .split_first()
+        .ok_or(LinkError::InvalidDylinkSection("unexpected end of section"))?;
+    *data = rest;
+    Ok(*byte)
+}
+
+fn read_u32(data: &mut &[u8]) -> Result<u32, LinkError> {
+    let mut result = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = read_u8(data)?;
+        result |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+    Err(LinkError::InvalidDylinkSection("integer is too large"))
+}
+
+fn read_string(data: &mut &[u8]) -> Result<String, LinkError> {
+    let len = read_u32(data)? as usize;
+    if len > data.len() {
+        return Err(LinkError::InvalidDylinkSection("unexpected end of section"));
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    String::from_utf8(bytes.to_vec()).map_err(|_| LinkError::InvalidDylinkSection("invalid string"))
+}
+
+/// A library that was compiled but isn't linked yet.
+#[derive(Debug)]
+pub struct LibraryModule {
+    path: PathBuf,
+    module: Module,
+    dylink: DylinkInfo,
+    needed: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+struct LoadedLibrary {
+    path: PathBuf,
+    instance: Instance,
+    memory_base: u64,
+    needed: Vec<DlHandle>,
+    ref_count: usize,
+    global: bool,
+    no_delete: bool,
+}
+
+enum Symbol {
+    Function(Function),
+    Data(u64),
+}
+
+#[derive(Debug)]
+struct LinkerState {
+    libraries: HashMap<DlHandle, LoadedLibrary>,
+    by_path: HashMap<PathBuf, DlHandle>,
+    /// The libraries in the order they were loaded, which is the order their
+    /// symbols are looked up in.
+    load_order: Vec<DlHandle>,
+    /// Table slots that were handed out for the functions of a library
+    function_slots: HashMap<(DlHandle, String), u64>,
+    next_handle: DlHandle,
+    last_error: Option<String>,
+}
+
+/// Keeps track of the shared libraries loaded into an instance.
+#[derive(Debug, Clone)]
+pub struct Linker {
+    state: Arc<Mutex<LinkerState>>,
+}
+
+impl Default for Linker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linker {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(LinkerState {
+                libraries: HashMap::new(),
+                by_path: HashMap::new(),
+                load_order: Vec::new(),
+                function_slots: HashMap::new(),
+                next_handle: MAIN_HANDLE + 1,
+                last_error: None,
+            })),
+        }
+    }
+
+    /// Records the error that the next `dlerror` call returns.
+    pub fn set_error(&self, error: &LinkError) {
+        let mut state = self.state.lock().unwrap();
+        state.last_error = Some(error.to_string());
+    }
+
+    /// Returns the last error and clears it.
+    pub fn take_error(&self) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        state.last_error.take()
+    }
+
+    /// Puts back an error that was taken but couldn't be reported.
+    pub fn restore_error(&self, error: String) {
+        let mut state = self.state.lock().unwrap();
+        state.last_error.get_or_insert(error);
+    }
+
+    /// Returns true if no shared library is loaded.
+    pub fn is_empty(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.libraries.is_empty()
+    }
+
+    fn is_loaded(&self, path: &Path) -> bool {
+        let state = self.state.lock().unwrap();
+        state.by_path.contains_key(path)
+    }
+
+    /// Finds and compiles a library and all the libraries it needs which
+    /// aren't loaded yet. The libraries are returned in the order they need to
+    /// be linked, along with the path of the requested library.
+    ///
+    /// The libraries are compiled with the engine of the store they will be
+    /// linked into, as their code must live as long as that store.
+    pub async fn load(
+        &self,
+        env: &WasiEnv,
+        engine: &Engine,
+        name: &str,
+        flags: DlFlags,
+    ) -> Result<(PathBuf, Vec<LibraryModule>), LinkError> {
+        let (fs, path) =
+            find_library(env, name).ok_or_else(|| LinkError::NotFound(name.to_string()))?;
+        if flags.contains(DlFlags::NOLOAD) && !self.is_loaded(&path) {
+            return Err(LinkError::NotFound(name.to_string()));
+        }
+
+        let mut modules = Vec::new();
+        let mut visited = HashSet::new();
+        self.load_tree(env, engine, fs, path.clone(), &mut visited, &mut modules)
+            .await?;
+        Ok((path, modules))
+    }
+
+    fn load_tree<'a>(
+        &'a self,
+        env: &'a WasiEnv,
+        engine: &'a Engine,
+        fs: Arc<dyn FileSystem + Send + Sync>,
+        path: PathBuf,
+        visited: &'a mut HashSet<PathBuf>,
+        modules: &'a mut Vec<LibraryModule>,
+    ) -> LocalBoxFuture<'a, Result<(), LinkError>> {
+        Box::pin(async move {
+            if self.is_loaded(&path) || !visited.insert(path.clone()) {
+                return Ok(());
+            }
+
+            let wasm = read_library(fs.as_ref(), &path).await?;
+            let module_cache = env.runtime.module_cache();
+            let module = load_module(engine, &module_cache, &wasm, ModuleHash::xxhash(&wasm))
+                .await
+                .map_err(|error| LinkError::Compile {
+                    path: path.clone(),
+                    error,
+                })?;
+            let dylink = DylinkInfo::from_module(&module)?
+                .ok_or_else(|| LinkError::NotSharedLibrary(path.clone()))?;
+
+            let mut needed = Vec::new();
+            for name in &dylink.needed {
+                let (fs, path) =
+                    find_library(env, name).ok_or_else(|| LinkError::NotFound(name.clone()))?;
+                self.load_tree(env, engine, fs, path.clone(), visited, modules)
+                    .await?;
+                needed.push(path);
+            }
+
+            modules.push(LibraryModule {
+                path,
+                module,
+                dylink,
+                needed,
+            });
+            Ok(())
+        })
+    }
+
+    /// Links the libraries returned by [`Linker::load`] into the instance of
+    /// the main module and runs their constructors.
+    #[allow(clippy::too_many_arguments)]
+    pub fn link(
+        &self,
+        store: &mut impl AsStoreMut,
+        env: &FunctionEnv<WasiEnv>,
+        main: &Instance,
+        memory: &Memory,
+        memory64: bool,
+        path: &Path,
+        modules: Vec<LibraryModule>,
+        flags: DlFlags,
+    ) -> Result<DlHandle, LinkError> {
+        let mut initializers = Vec::new();
+        let mut batch = Vec::new();
+        for library in modules {
+            // Linking runs guest code, which may load other libraries, so it
+            // happens without holding the lock
+            let loaded = self.instantiate(store, env, main, memory, memory64, library, &batch);
+            let mut state = self.state.lock().unwrap();
+            let mut loaded = match loaded {
+                Ok(loaded) => loaded,
+                Err(err) => {
+                    // Unload the libraries linked so far, although the
+                    // memory they were given can't be reclaimed
+                    for handle in batch {
+                        state.close(handle);
+                    }
+                    return Err(err);
+                }
+            };
+            loaded.global = flags.contains(DlFlags::GLOBAL);
+            for dep in &loaded.needed {
+                if let Some(dep) = state.libraries.get_mut(dep) {
+                    dep.ref_count += 1;
+                }
+            }
+            initializers.push(loaded.instance.clone());
+
+            let handle = state.next_handle;
+            state.next_handle += 1;
+            state.by_path.insert(loaded.path.clone(), handle);
+            state.load_order.push(handle);
+            state.libraries.insert(handle, loaded);
+            batch.push(handle);
+        }
+
+        let handle = {
+            let mut state = self.state.lock().unwrap();
+            let handle = *state
+                .by_path
+                .get(path)
+                .ok_or_else(|| LinkError::NotFound(path.display().to_string()))?;
+            let library = state.libraries.get_mut(&handle).unwrap();
+            // A library that was just loaded holds the reference of this call
+            if !batch.contains(&handle) {
+                library.ref_count += 1;
+            }
+            library.global |= flags.contains(DlFlags::GLOBAL);
+            library.no_delete |= flags.contains(DlFlags::NODELETE);
+            handle
+        };
+
+        // The constructors run once the whole batch is registered, as they
+        // may call into any library of it
+        for instance in initializers {
+            for name in ["__wasm_apply_data_relocs", "__wasm_call_ctors"] {
+                if let Ok(init) = instance.exports.get_function(name) {
+                    init.call(store, &[])?;
+                }
+            }
+        }
+
+        Ok(handle)
+    }
+
+    /// Looks up the address of a symbol, which is a table index for functions.
+    pub fn symbol(
+        &self,
+        store: &mut impl AsStoreMut,
+        main: &Instance,
+        memory64: bool,
+        handle: DlHandle,
+        name: &str,
+    ) -> Result<u64, LinkError> {
+        let mut state = self.state.lock().unwrap();
+        let scope = if handle == MAIN_HANDLE {
+            state.global_scope()
+        } else if state.libraries.contains_key(&handle) {
+            state.dependency_scope(handle)
+        } else {
+            return Err(LinkError::InvalidHandle(handle));
+        };
+
+        let (owner, symbol) = state
+            .find_symbol(store, main, &scope, name)
+            .ok_or_else(|| LinkError::UndefinedSymbol(name.to_string()))?;
+        match symbol {
+            Symbol::Function(func) => state.function_slot(store, main, memory64, owner, name, func),
+            Symbol::Data(addr) => Ok(addr),
+        }
+    }
+
+    /// Releases a reference to a library, which is unloaded once it isn't
+    /// referenced anymore.
+    pub fn close(&self, handle: DlHandle) -> Result<(), LinkError> {
+        let mut state = self.state.lock().unwrap();
+        if handle != MAIN_HANDLE && !state.libraries.contains_key(&handle) {
+            return Err(LinkError::InvalidHandle(handle));
+        }
+        state.close(handle);
+        Ok(())
+    }
+
+    /// Instantiates a library. The lock is only held while its imports and its
+    /// GOT are resolved, as `malloc` and its start function are guest code.
+    #[allow(clippy::too_many_arguments)]
+    fn instantiate(
+        &self,
+        store: &mut impl AsStoreMut,
+        env: &FunctionEnv<WasiEnv>,
+        main: &Instance,
+        memory: &Memory,
+        memory64: bool,
+        library: LibraryModule,
+        batch: &[DlHandle],
+    ) -> Result<LoadedLibrary, LinkError> {
+        let LibraryModule {
+            path,
+            module,
+            dylink,
+            needed,
+        } = library;
+        let table = indirect_function_table(main)?;
+
+        let memory_base = if dylink.memory_size > 0 {
+            let align = 1u64 << dylink.memory_align.min(16);
+            let malloc = main
+                .exports
+                .get_function("malloc")
+                .map_err(|_| LinkError::MissingMainExport("malloc"))?;
+            let size = dylink.memory_size as u64 + align - 1;
+            let ptr = malloc
+                .call(store, &[address(size, memory64)])?
+                .first()
+                .and_then(value_to_u64)
+                .unwrap_or_default();
+            if ptr == 0 {
+                return Err(LinkError::OutOfMemory);
+            }
+            let base = (ptr + align - 1) & !(align - 1);
+            memory
+                .view(store)
+                .write(base, &vec![0; dylink.memory_size as usize])?;
+            base
+        } else {
+            0
+        };
+        let table_base = if dylink.table_size > 0 {
+            let align = 1u32 << dylink.table_align.min(16);
+            let padding = (align - table.size(store) % align) % align;
+            let size = dylink.table_size.saturating_add(padding);
+            (table.grow(store, size, Value::FuncRef(None))? + padding) as u64
+        } else {
+            table.size(store) as u64
+        };
+
+        let state = self.state.lock().unwrap();
+
+        // Symbols are looked up in the main module, the global libraries and
+        // the dependencies that are loaded along with this library
+        let mut scope = state.global_scope();
+        for handle in batch {
+            if !scope.contains(handle) {
+                scope.push(*handle);
+            }
+        }
+
+        let (wasi_imports, _) = import_object_for_all_wasi_versions(&module, store, env);
+        let mut imports = Imports::new();
+        let mut got = Vec::new();
+        for import in module.imports() {
+            let (ns, name) = (import.module(), import.name());
+            let export: Extern = match (ns, name) {
+                ("env", "memory") => memory.clone().into(),
+                ("env", "__indirect_function_table") => table.clone().into(),
+                ("env", "__memory_base") => {
+                    Global::new(store, address(memory_base, memory64)).into()
+                }
+                ("env", "__table_base") => Global::new(store, address(table_base, memory64)).into(),
+                ("env", "__stack_pointer") => main
+                    .exports
+                    .get_global("__stack_pointer")
+                    .map_err(|_| LinkError::MissingMainExport("__stack_pointer"))?
+                    .clone()
+                    .into(),
+                ("GOT.mem", _) | ("GOT.func", _) => {
+                    let global = Global::new_mut(store, address(0, memory64));
+                    let weak = dylink
+                        .weak_imports
+                        .contains(&(ns.to_string(), name.to_string()));
+                    got.push((ns == "GOT.func", name.to_string(), global.clone(), weak));
+                    global.into()
+                }
+                _ => match wasi_imports.get_export(ns, name) {
+                    Some(export) => export,
+                    None => match state.find_symbol(store, main, &scope, name) {
+                        Some((_, Symbol::Function(func))) if ns == "env" => func.into(),
+                        _ => {
+                            return Err(LinkError::UnresolvedImport {
+                                module: ns.to_string(),
+                                name: name.to_string(),
+                            })
+                        }
+                    },
+                },
+            };
+            imports.define(ns, name, export);
+        }
+
+        drop(state);
+
+        let instance = Instance::new(store, &module, &imports)?;
+        let mut state = self.state.lock().unwrap();
+        let needed = needed
+            .iter()
+            .filter_map(|path| state.by_path.get(path).copied())
+            .collect();
+        let loaded = LoadedLibrary {
+            path,
+            instance,
+            memory_base,
+            needed,
+            ref_count: 1,
+            global: false,
+            no_delete: false,
+        };
+
+        // The library can reference its own symbols through the GOT, and those
+        // only exist now that it's instantiated
+        for (is_function, name, global, weak) in got {
+            let symbol = match state.find_symbol(store, main, &scope, &name) {
+                Some(symbol) => Some(symbol),
+                None => find_export(store, &loaded.instance, loaded.memory_base, &name)
+                    .map(|symbol| (None, symbol)),
+            };
+            let addr = match symbol {
+                Some((owner, Symbol::Function(func))) if is_function => {
+                    state.function_slot(store, main, memory64, owner, &name, func)?
+                }
+                Some((_, Symbol::Data(addr))) if !is_function => addr,
+                _ if weak => continue,
+                _ => return Err(LinkError::UndefinedSymbol(name)),
+            };
+            global.set(store, address(addr, memory64))?;
+        }
+
+        Ok(loaded)
+    }
+}
+
+impl LinkerState {
+    /// The main module followed by the global libraries.
+    fn global_scope(&self) -> Vec<DlHandle> {
+        let mut scope = vec![MAIN_HANDLE];
+        scope.extend(
+            self.load_order
+                .iter()
+                .copied()
+                .filter(|handle| self.libraries[handle].global),
+        );
+        scope
+    }
+
+    /// A library followed by all the libraries it depends on.
+    fn dependency_scope(&self, handle: DlHandle) -> Vec<DlHandle> {
+        let mut scope = vec![handle];
+        let mut next = 0;
+        while let Some(handle) = scope.get(next).copied() {
+            next += 1;
+            for dep in &self.libraries[&handle].needed {
+                if !scope.contains(dep) {
+                    scope.push(*dep);
+                }
+            }
+        }
+        scope
+    }
+
+    /// Finds a symbol in a list of libraries, returning which library
+    /// defines it (`None` being the main module).
+    fn find_symbol(
+        &self,
+        store: &mut impl AsStoreMut,
+        main: &Instance,
+        scope: &[DlHandle],
+        name: &str,
+    ) -> Option<(Option<DlHandle>, Symbol)> {
+        scope.iter().find_map(|handle| {
+            if *handle == MAIN_HANDLE {
+                find_export(store, main, 0, name).map(|symbol| (None, symbol))
+            } else {
+                let library = self.libraries.get(handle)?;
+                find_export(store, &library.instance, library.memory_base, name)
+                    .map(|symbol| (Some(*handle), symbol))
+            }
+        })
+    }
+
+    /// Returns the table index of a function, adding it to the table the
+    /// first time its address is taken.
+    fn function_slot(
+        &mut self,
+        store: &mut impl AsStoreMut,
+        main: &Instance,
+        memory64: bool,
+        owner: Option<DlHandle>,
+        name: &str,
+        func: Function,
+    ) -> Result<u64, LinkError> {
+        let key = (owner.unwrap_or(MAIN_HANDLE), name.to_string());
+        if let Some(slot) = self.function_slots.get(&key) {
+            return Ok(*slot);
+        }
+        let table = indirect_function_table(main)?;
+        let slot = table.grow(store, 1, Value::FuncRef(Some(func)))? as u64;
+        if !memory64 && slot > u32::MAX as u64 {
+            return Err(LinkError::OutOfMemory);
+        }
+        self.function_slots.insert(key, slot);
+        Ok(slot)
+    }
+
+    fn close(&mut self, handle: DlHandle) {
+        let Some(library) = self.libraries.get_mut(&handle) else {
+            return;
+        };
+        library.ref_count = library.ref_count.saturating_sub(1);
+        if library.ref_count > 0 || library.no_delete {
+            return;
+        }
+
+        let library = self.libraries.remove(&handle).unwrap();
+        self.by_path.remove(&library.path);
+        self.load_order.retain(|h| *h != handle);
+        self.function_slots.retain(|(owner, _), _| *owner != handle);
+        for dep in library.needed {
+            self.close(dep);
+        }
+    }
+}
+
+fn indirect_function_table(main: &Instance) -> Result<&Table, LinkError> {
+    main.exports
+        .get_table("__indirect_function_table")
+        .map_err(|_| LinkError::MissingMainExport("__indirect_function_table"))
+}
+
+/// Looks up an export of an instance, the data exports of libraries being
+/// relative to their memory base.
+fn find_export(
+    store: &mut impl AsStoreMut,
+    instance: &Instance,
+    memory_base: u64,
+    name: &str,
+) -> Option<Symbol> {
+    match instance.exports.get_extern(name)? {
+        Extern::Function(func) => Some(Symbol::Function(func.clone())),
+        Extern::Global(global) => {
+            let offset = value_to_u64(&global.get(store))?;
+            Some(Symbol::Data(memory_base + offset))
+        }
+        _ => None,
+    }
+}
+
+fn address(addr: u64, memory64: bool) -> Value {
+    if memory64 {
+        Value::I64(addr as i64)
+    } else {
+        Value::I32(addr as u32 as i32)
+    }
+}
+
+fn value_to_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::I32(v) => Some(*v as u32 as u64),
+        Value::I64(v) => Some(*v as u64),
+        _ => None,
+    }
+}
+
+/// Finds a library in the file system or, for bare names, in the library
+/// search path and then in the packages used by the process.
+fn find_library(env: &WasiEnv, name: &str) -> Option<(Arc<dyn FileSystem + Send + Sync>, PathBuf)> {
+    let root: Arc<dyn FileSystem + Send + Sync> = Arc::new(env.state.fs.root_fs.clone());
+    let is_file = |fs: &dyn FileSystem, path: &Path| {
+        fs.metadata(path)
+            .map(|metadata| metadata.is_file())
+            .unwrap_or(false)
+    };
+
+    if name.contains('/') {
+        let current_dir = env.state.fs.current_dir.lock().unwrap().clone();
+        let path = Path::new(&current_dir).join(name);
+        return is_file(root.as_ref(), &path).then_some((root, path));
+    }
+
+    let envs = env.state.envs.lock().unwrap().clone();
+    let ld_library_path = envs
+        .iter()
+        .find_map(|var| var.strip_prefix(b"LD_LIBRARY_PATH="))
+        .map(|value| String::from_utf8_lossy(value).into_owned())
+        .unwrap_or_default();
+    let search_path = ld_library_path
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .chain(LIBRARY_PATH.iter().copied());
+    for dir in search_path {
+        let path = Path::new(dir).join(name);
+        if is_file(root.as_ref(), &path) {
+            return Some((root, path));
+        }
+    }
+
+    let packages: Vec<_> = env
+        .bin_factory
+        .local
+        .read()
+        .unwrap()
+        .values()
+        .flatten()
+        .cloned()
+        .collect();
+    packages.into_iter().find_map(|pkg| {
+        LIBRARY_PATH.iter().find_map(|dir| {
+            let path = Path::new(dir).join(name);
+            is_file(pkg.webc_fs.as_ref(), &path).then(|| (pkg.webc_fs.clone(), path))
+        })
+    })
+}
+
+async fn read_library(fs: &dyn FileSystem, path: &Path) -> Result<Vec<u8>, LinkError> {
+    let read_error = |error: std::io::Error| LinkError::Read {
+        path: path.to_path_buf(),
+        error,
+    };
+    let mut file = fs
+        .new_open_options()
+        .read(true)
+        .open(path)
+        .map_err(|err| read_error(err.into()))?;
+    let mut data = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut data).await.map_err(read_error)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dylink_section() {
+        let section = [
+            // WASM_DYLINK_MEM_INFO: 300 bytes aligned to 4, 2 table slots
+            1, 5, 0xac, 0x02, 2, 2, 0, //
+            // WASM_DYLINK_NEEDED: "libc.so"
+            2, 9, 1, 7, b'l', b'i', b'b', b'c', b'.', b's', b'o', //
+            // WASM_DYLINK_EXPORT_INFO is skipped
+            3, 4, 1, 1, b'f', 0, //
+            // WASM_DYLINK_IMPORT_INFO: weak "env.g" and strong "env.h"
+            4, 15, 2, 3, b'e', b'n', b'v', 1, b'g', 1, 3, b'e', b'n', b'v', 1, b'h', 0,
+        ];
+
+        let info = DylinkInfo::parse(&section).unwrap();
+
+        assert_eq!(info.memory_size, 300);
+        assert_eq!(info.memory_align, 2);
+        assert_eq!(info.table_size, 2);
+        assert_eq!(info.table_align, 0);
+        assert_eq!(info.needed, ["libc.so"]);
+        assert_eq!(
+            info.weak_imports,
+            HashSet::from([("env".to_string(), "g".to_string())])
+        );
+        assert!(DylinkInfo::parse(&section[..section.len() - 1]).is_err());
+    }
+}
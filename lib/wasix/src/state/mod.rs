@@ -19,6 +19,7 @@ mod builder;
 mod env;
 mod func_env;
 mod handles;
+mod linker;
 mod run;
 mod types;
 
@@ -49,6 +50,7 @@ use crate::{
     utils::WasiParkingLot,
 };
 pub(crate) use handles::*;
+pub(crate) use linker::{LinkError, Linker, MAIN_HANDLE};
 
 /// all the rights enabled
 pub const ALL_RIGHTS: Rights = Rights::all();
@@ -150,6 +150,10 @@ fn handle_result(
     result: Result<Box<[wasmer::Value]>, RuntimeError>,
     sender: tokio::sync::mpsc::UnboundedSender<Result<Store, WasiRuntimeError>>,
 ) {
+    if let Err(err) = &result {
+        crate::coredump::on_thread_error(&env, &mut store, err);
+    }
+
     let result: Result<_, WasiRuntimeError> = match result.map_err(|e| e.downcast::<WasiError>()) {
         Err(Ok(WasiError::DeepSleep(work))) => {
             let pid = env.data(&store).pid();
@@ -7,6 +7,8 @@ mod maybe_snapshot_many;
 mod maybe_snapshot_once;
 #[cfg(feature = "journal")]
 mod play_event;
+#[cfg(feature = "journal")]
+mod replayer;
 mod restore_snapshot;
 mod wait_for_snapshot;
 
@@ -19,6 +21,8 @@ pub use do_checkpoint_from_outside::*;
 pub use maybe_snapshot::*;
 pub use maybe_snapshot_many::*;
 pub use maybe_snapshot_once::*;
+#[cfg(feature = "journal")]
+pub use replayer::*;
 pub use restore_snapshot::*;
 pub use wait_for_snapshot::*;
 
@@ -0,0 +1,111 @@
+use crate::fs::Fd;
+
+use super::*;
+
+/// Replays a journal one event at a time, so the state of the process can be
+/// inspected in between (e.g. by `wasmer journal replay`).
+///
+/// Unlike [`restore_snapshot()`], memory updates are applied as soon as they
+/// are replayed and threads are never spawned, so after each event the
+/// process reflects exactly what the journal recorded up to that point.
+pub struct JournalReplayer<'a, 'c> {
+    player: JournalSyscallPlayer<'a, 'c>,
+    events_replayed: usize,
+}
+
+impl<'a, 'c> JournalReplayer<'a, 'c> {
+    pub fn new(mut ctx: FunctionEnvMut<'c, WasiEnv>) -> Self {
+        ctx.data_mut().replaying_journal = true;
+
+        // Bootstrapping stops the player from spawning threads or exiting
+        // the process when it sees them in the journal
+        JournalReplayer {
+            player: JournalSyscallPlayer::new(ctx, true),
+            events_replayed: 0,
+        }
+    }
+
+    /// Replay the next event of the journal.
+    ///
+    /// # Safety
+    ///
+    /// This manipulates the memory of the process, so it must be called by
+    /// the thread that owns the process' store.
+    #[allow(clippy::result_large_err)]
+    pub unsafe fn play(&mut self, event: JournalEntry<'a>) -> Result<(), WasiRuntimeError> {
+        self.player.play_event(event, None)?;
+        self.events_replayed += 1;
+
+        let player = &mut self.player;
+        let memory: Vec<_> = player
+            .differ_memory
+            .drain(..)
+            .chain(player.staged_differ_memory.drain(..))
+            .collect();
+        for (region, data) in memory {
+            JournalEffector::apply_compressed_memory(&mut player.ctx, region, &data)
+                .map_err(anyhow_err_to_runtime_err)?;
+        }
+
+        Ok(())
+    }
+
+    /// The number of events replayed so far.
+    pub fn events_replayed(&self) -> usize {
+        self.events_replayed
+    }
+
+    /// Whether the journal was recorded by the module being replayed, or
+    /// `None` if the journal hasn't said which module recorded it yet.
+    ///
+    /// Memory and thread events are skipped when the modules differ.
+    pub fn module_hash_matches(&self) -> Option<bool> {
+        self.player
+            .journal_module_hash
+            .as_ref()
+            .map(|hash| *hash == self.player.cur_module_hash)
+    }
+
+    pub fn ctx(&self) -> &FunctionEnvMut<'c, WasiEnv> {
+        &self.player.ctx
+    }
+
+    /// Read a region of the process' memory.
+    pub fn read_memory(&self, offset: u64, buf: &mut [u8]) -> Result<(), MemoryAccessError> {
+        let ctx = &self.player.ctx;
+        // Safety: the replayer has exclusive access to the store
+        unsafe { ctx.data().memory_view(ctx) }.read(offset, buf)
+    }
+
+    /// The size of the process' memory, in bytes.
+    pub fn memory_size(&self) -> u64 {
+        let ctx = &self.player.ctx;
+        // Safety: the replayer has exclusive access to the store
+        unsafe { ctx.data().memory_view(ctx) }.data_size()
+    }
+
+    /// The state of the main thread, if the journal recorded one.
+    pub fn main_thread(&self) -> Option<&RewindState> {
+        self.player.rewind.as_ref()
+    }
+
+    /// The state of every other thread the journal recorded.
+    pub fn threads(&self) -> &BTreeMap<WasiThreadId, RewindState> {
+        &self.player.spawn_threads
+    }
+
+    /// The file descriptors currently open in the process.
+    pub fn file_descriptors(&self) -> Vec<(WasiFd, Fd)> {
+        self.player
+            .ctx
+            .data()
+            .state
+            .fs
+            .fd_map
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(fd, entry)| (fd, entry.clone()))
+            .collect()
+    }
+}
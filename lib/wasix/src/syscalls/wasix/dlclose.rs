@@ -0,0 +1,21 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `dlclose()`
+/// Releases a library returned by `dlopen`, which is unloaded along with the
+/// libraries it needs once nothing references it anymore.
+///
+/// ## Parameters
+///
+/// * `handle` - Library to release
+#[instrument(level = "trace", skip_all, fields(%handle), ret)]
+pub fn dlclose(ctx: FunctionEnvMut<'_, WasiEnv>, handle: DlHandle) -> Errno {
+    let linker = &unsafe { ctx.data().inner() }.linker;
+    match linker.close(handle) {
+        Ok(()) => Errno::Success,
+        Err(err) => {
+            linker.set_error(&err);
+            err.as_errno()
+        }
+    }
+}
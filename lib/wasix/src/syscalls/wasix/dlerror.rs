@@ -0,0 +1,39 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `dlerror()`
+/// Returns a description of the last error of `dlopen`, `dlsym` or
+/// `dlclose` and clears it.
+///
+/// The description is empty when no error occurred since the last call. If
+/// it exceeds the size of the buffer then this function will return ERANGE
+/// and the error is kept.
+///
+/// ## Parameters
+///
+/// * `buf` - Buffer the description is written to
+/// * `buf_len` - Size of the buffer, which receives the length of the
+///   description
+#[instrument(level = "trace", skip_all, ret)]
+pub fn dlerror<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    buf: WasmPtr<u8, M>,
+    buf_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let linker = &unsafe { env.inner() }.linker;
+    let error = linker.take_error().unwrap_or_default();
+
+    let max_len: u64 = wasi_try_mem!(buf_len.read(&memory)).into();
+    let len: M::Offset = wasi_try!(error.len().try_into().map_err(|_| Errno::Overflow));
+    wasi_try_mem!(buf_len.write(&memory, len));
+    if error.len() as u64 > max_len {
+        linker.restore_error(error);
+        return Errno::Range;
+    }
+    wasi_try_mem!(buf
+        .slice(&memory, len)
+        .and_then(|slice| slice.write_slice(error.as_bytes())));
+    Errno::Success
+}
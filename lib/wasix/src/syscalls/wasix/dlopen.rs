@@ -0,0 +1,81 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `dlopen()`
+/// Loads a shared library, along with the libraries it needs, and links it
+/// into the running module.
+///
+/// Library names without a `/` are searched for in `LD_LIBRARY_PATH`, the
+/// standard library directories and then in the packages used by the
+/// process. An empty name returns the handle of the main module.
+///
+/// The main module must export `malloc`, `__stack_pointer` and its indirect
+/// function table for libraries to be linked into it.
+///
+/// Libraries are linked into the instance of the calling thread only, so
+/// they can't be loaded while the process has more than one thread, in which
+/// case `ENOTSUP` is returned.
+///
+/// ## Parameters
+///
+/// * `path` - Name or path of the library
+/// * `flags` - Flags that control how the library is loaded
+/// * `ret_handle` - Receives the handle of the library
+#[instrument(level = "trace", skip_all, fields(path = field::Empty, ?flags), ret)]
+pub fn dlopen<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+    flags: DlFlags,
+    ret_handle: WasmPtr<DlHandle, M>,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let path = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    Span::current().record("path", path.as_str());
+
+    let handle = if path.is_empty() {
+        Ok(MAIN_HANDLE)
+    } else if env.process.active_threads() > 1 {
+        Err(crate::state::LinkError::MultipleThreads)
+    } else {
+        let linker = unsafe { env.inner() }.linker.clone();
+        let engine = ctx.as_store_ref().engine().clone();
+        let loaded = wasi_try_ok!(__asyncify_light(env, None, async {
+            Ok(linker.load(env, &engine, &path, flags).await)
+        })?);
+
+        let (main, memory) = {
+            let inner = unsafe { env.inner() };
+            (inner.instance.clone(), inner.memory.clone())
+        };
+        loaded.and_then(|(path, modules)| {
+            let func_env = ctx.as_ref();
+            let mut store = ctx.as_store_mut();
+            linker.link(
+                &mut store,
+                &func_env,
+                &main,
+                &memory,
+                M::is_64bit(),
+                &path,
+                modules,
+                flags,
+            )
+        })
+    };
+
+    let env = ctx.data();
+    let handle = match handle {
+        Ok(handle) => handle,
+        Err(err) => {
+            debug!("dlopen failed - {err}");
+            unsafe { env.inner() }.linker.set_error(&err);
+            return Ok(err.as_errno());
+        }
+    };
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem_ok!(ret_handle.write(&memory, handle));
+    Ok(Errno::Success)
+}
@@ -0,0 +1,45 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `dlsym()`
+/// Looks up the address of a symbol in a library and the libraries it
+/// depends on. Functions are returned as indexes into the indirect function
+/// table.
+///
+/// ## Parameters
+///
+/// * `handle` - Library returned by `dlopen`, where the main module's handle
+///   searches the main module and all the libraries loaded with `GLOBAL`
+/// * `symbol` - Name of the symbol
+/// * `ret_addr` - Receives the address of the symbol
+#[instrument(level = "trace", skip_all, fields(%handle, symbol = field::Empty), ret)]
+pub fn dlsym<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    handle: DlHandle,
+    symbol: WasmPtr<u8, M>,
+    symbol_len: M::Offset,
+    ret_addr: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let symbol = unsafe { get_input_str!(&memory, symbol, symbol_len) };
+    Span::current().record("symbol", symbol.as_str());
+
+    let (linker, main) = {
+        let inner = unsafe { env.inner() };
+        (inner.linker.clone(), inner.instance.clone())
+    };
+    let mut store = ctx.as_store_mut();
+    let addr = match linker.symbol(&mut store, &main, M::is_64bit(), handle, &symbol) {
+        Ok(addr) => addr,
+        Err(err) => {
+            linker.set_error(&err);
+            return err.as_errno();
+        }
+    };
+    let addr: M::Offset = wasi_try!(addr.try_into().map_err(|_| Errno::Overflow));
+
+    let memory = unsafe { ctx.data().memory_view(&ctx) };
+    wasi_try_mem!(ret_addr.write(&memory, addr));
+    Errno::Success
+}
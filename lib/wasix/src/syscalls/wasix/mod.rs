@@ -1,5 +1,9 @@
 mod callback_signal;
 mod chdir;
+mod dlclose;
+mod dlerror;
+mod dlopen;
+mod dlsym;
 mod epoll_create;
 mod epoll_ctl;
 mod epoll_wait;
@@ -33,9 +37,13 @@ mod resolve;
 mod sched_yield;
 mod sock_accept;
 mod sock_addr_local;
+mod sock_addr_local_unix;
 mod sock_addr_peer;
+mod sock_addr_peer_unix;
 mod sock_bind;
+mod sock_bind_unix;
 mod sock_connect;
+mod sock_connect_unix;
 mod sock_get_opt_flag;
 mod sock_get_opt_size;
 mod sock_get_opt_time;
@@ -47,9 +55,11 @@ mod sock_listen;
 mod sock_open;
 mod sock_recv;
 mod sock_recv_from;
+mod sock_recv_from_unix;
 mod sock_send;
 mod sock_send_file;
 mod sock_send_to;
+mod sock_send_to_unix;
 mod sock_set_opt_flag;
 mod sock_set_opt_size;
 mod sock_set_opt_time;
@@ -69,6 +79,10 @@ mod tty_set;
 
 pub use callback_signal::*;
 pub use chdir::*;
+pub use dlclose::*;
+pub use dlerror::*;
+pub use dlopen::*;
+pub use dlsym::*;
 pub use epoll_create::*;
 pub use epoll_ctl::*;
 pub use epoll_wait::*;
@@ -102,9 +116,13 @@ pub use resolve::*;
 pub use sched_yield::*;
 pub use sock_accept::*;
 pub use sock_addr_local::*;
+pub use sock_addr_local_unix::*;
 pub use sock_addr_peer::*;
+pub use sock_addr_peer_unix::*;
 pub use sock_bind::*;
+pub use sock_bind_unix::*;
 pub use sock_connect::*;
+pub use sock_connect_unix::*;
 pub use sock_get_opt_flag::*;
 pub use sock_get_opt_size::*;
 pub use sock_get_opt_time::*;
@@ -116,9 +134,11 @@ pub use sock_listen::*;
 pub use sock_open::*;
 pub use sock_recv::*;
 pub use sock_recv_from::*;
+pub use sock_recv_from_unix::*;
 pub use sock_send::*;
 pub use sock_send_file::*;
 pub use sock_send_to::*;
+pub use sock_send_to_unix::*;
 pub use sock_set_opt_flag::*;
 pub use sock_set_opt_size::*;
 pub use sock_set_opt_time::*;
@@ -44,6 +44,13 @@ pub fn proc_fork<M: MemorySize>(
     }
     trace!(%copy_memory, "capturing");
 
+    // The shared libraries are only linked into the instance of this process,
+    // so the copy of its memory would refer to functions the child doesn't have
+    if copy_memory == Bool::True && !unsafe { ctx.data().inner() }.linker.is_empty() {
+        warn!("unable to fork while shared libraries are loaded");
+        return Ok(Errno::Notsup);
+    }
+
     // Fork the environment which will copy all the open file handlers
     // and associate a new context but otherwise shares things like the
     // file system interface. The handle to the forked process is stored
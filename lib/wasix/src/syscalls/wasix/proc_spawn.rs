@@ -1,19 +1,28 @@
-use virtual_fs::Pipe;
+use std::path::PathBuf;
+
+use virtual_fs::{FileSystem, Pipe};
 use wasmer_wasix_types::wasi::ProcessHandles;
 
 use super::*;
-use crate::syscalls::*;
+use crate::{
+    fs::{resolve_path, WasiFs},
+    state::PreopenedDir,
+    syscalls::*,
+};
 
 /// Spawns a new process within the context of this machine
 ///
 /// ## Parameters
 ///
 /// * `name` - Name of the process to be spawned
-/// * `chroot` - Indicates if the process will chroot or not
+/// * `chroot` - Indicates if the process will chroot into its working
+///   directory, so that it only sees the sub-tree below it
 /// * `args` - List of the arguments to pass the process
 ///   (entries are separated by line feeds)
-/// * `preopen` - List of the preopens for this process
-///   (entries are separated by line feeds)
+/// * `preopen` - List of the preopens for this process, which replace the
+///   preopens of the parent (entries are separated by line feeds). An entry
+///   is either a directory as seen by the process or `guest:host`, which
+///   mounts the `host` directory of this process at `guest`
 /// * `stdin` - How will stdin be handled
 /// * `stdout` - How will stdout be handled
 /// * `stderr` - How will stderr be handled
@@ -52,11 +61,6 @@ pub fn proc_spawn<M: MemorySize>(
         .record("name", name.as_str())
         .record("working_dir", working_dir.as_str());
 
-    if chroot == Bool::True {
-        warn!("chroot is not currently supported",);
-        return Ok(Errno::Notsup);
-    }
-
     let args: Vec<_> = args
         .split(&['\n', '\r'])
         .map(|a| a.to_string())
@@ -72,6 +76,7 @@ pub fn proc_spawn<M: MemorySize>(
     let (handles, ctx) = match proc_spawn_internal(
         ctx,
         name,
+        chroot == Bool::True,
         Some(args),
         Some(preopen),
         Some(working_dir),
@@ -94,6 +99,7 @@ pub fn proc_spawn<M: MemorySize>(
 pub fn proc_spawn_internal(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     name: String,
+    chroot: bool,
     args: Option<Vec<String>>,
     preopen: Option<Vec<String>>,
     working_dir: Option<String>,
@@ -115,9 +121,26 @@ pub fn proc_spawn_internal(
         }
     };
     let child_process = child_env.process.clone();
-    if let Some(args) = args {
+
+    // Give the child its own root file system and preopens
+    let preopen = preopen.unwrap_or_default();
+    let child_fs = if chroot || !preopen.is_empty() {
+        match spawn_fs(&env.state, chroot, working_dir.as_deref(), &preopen) {
+            Ok(fs) => Some(fs),
+            Err(err) => return Ok(Err(err)),
+        }
+    } else {
+        None
+    };
+
+    if args.is_some() || child_fs.is_some() {
         let mut child_state = env.state.fork();
-        child_state.args = std::sync::Mutex::new(args);
+        if let Some(args) = args {
+            child_state.args = std::sync::Mutex::new(args);
+        }
+        if let Some(child_fs) = child_fs {
+            child_state.fs = child_fs;
+        }
         child_env.state = Arc::new(child_state);
     }
 
@@ -125,21 +148,8 @@ pub fn proc_spawn_internal(
     ctx.data_mut().owned_handles.push(handle);
     let env = ctx.data();
 
-    // Preopen
-    if let Some(preopen) = preopen {
-        if !preopen.is_empty() {
-            for preopen in preopen {
-                warn!(
-                    "preopens are not yet supported for spawned processes [{}]",
-                    preopen
-                );
-            }
-            return Ok(Err(Errno::Notsup));
-        }
-    }
-
-    // Change the current directory
-    if let Some(working_dir) = working_dir {
+    // Change the current directory (a chroot starts at its new root)
+    if let Some(working_dir) = working_dir.filter(|_| !chroot) {
         child_env.state.fs.set_current_dir(working_dir.as_str());
     }
 
@@ -260,3 +270,194 @@ pub fn proc_spawn_internal(
     };
     Ok(Ok((handles, ctx)))
 }
+
+/// Builds the file system of a spawned process that either chroots into its
+/// working directory or receives its own preopens.
+fn spawn_fs(
+    state: &WasiState,
+    chroot: bool,
+    working_dir: Option<&str>,
+    preopen: &[String],
+) -> Result<WasiFs, Errno> {
+    let current_dir = state.fs.current_dir.lock().unwrap().clone();
+    let root = match working_dir {
+        Some(working_dir) if chroot => resolve_path(&current_dir, working_dir),
+        _ => PathBuf::from("/"),
+    };
+    // Directories without a mapping are relative to where the child starts
+    let child_dir = if chroot { "/" } else { current_dir.as_str() };
+
+    let mut mounts = Vec::new();
+    let mut preopens = Vec::new();
+    for entry in preopen {
+        let guest = match entry.split_once(':') {
+            Some((guest, host)) => {
+                let guest = resolve_path("/", guest);
+                mounts.push((guest.clone(), resolve_path(&current_dir, host)));
+                guest
+            }
+            None => resolve_path(child_dir, entry),
+        };
+        preopens.push(PreopenedDir {
+            path: guest,
+            alias: None,
+            read: true,
+            write: true,
+            create: true,
+        });
+    }
+
+    let root_fs = state
+        .fs
+        .root_fs
+        .chroot(&root, &mounts)
+        .map_err(fs_error_into_wasi_err)?;
+    for preopen in &preopens {
+        let metadata = root_fs
+            .metadata(&preopen.path)
+            .map_err(fs_error_into_wasi_err)?;
+        if !metadata.is_dir() {
+            return Err(Errno::Notdir);
+        }
+    }
+
+    state
+        .fs
+        .fork_with_root(&state.inodes, root_fs, preopens)
+        .map_err(|err| {
+            warn!("failed to create the file system of the spawned process - {err}");
+            Errno::Io
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+
+    use virtual_fs::{FileSystem, TmpFileSystem};
+    use wasmer::{imports, FunctionEnvMut, Instance, Module, Store};
+    use wasmer_wasix_types::wasi::Errno;
+
+    use super::proc_spawn_internal;
+    use crate::{
+        os::{
+            command::VirtualCommand,
+            task::{OwnedTaskStatus, TaskJoinHandle, TaskStatus},
+        },
+        syscalls::WasiStdioMode,
+        SpawnError, WasiEnv,
+    };
+
+    /// A command that records which files the spawned process can open.
+    #[derive(Debug)]
+    struct ProbeCommand {
+        opened: Arc<Mutex<Vec<(&'static str, bool)>>>,
+    }
+
+    impl VirtualCommand for ProbeCommand {
+        fn name(&self) -> &str {
+            "probe"
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn exec(
+            &self,
+            _parent_ctx: &FunctionEnvMut<'_, WasiEnv>,
+            _path: &str,
+            _store: &mut Option<Store>,
+            config: &mut Option<WasiEnv>,
+        ) -> Result<TaskJoinHandle, SpawnError> {
+            let env = config.take().unwrap();
+            let fs = &env.state.fs.root_fs;
+            let mut opened = self.opened.lock().unwrap();
+            for path in [
+                "/inside.txt",
+                "/data/shared.txt",
+                "/secret.txt",
+                "/../secret.txt",
+            ] {
+                let open = fs.new_open_options().read(true).open(Path::new(path));
+                opened.push((path, open.is_ok()));
+            }
+            Ok(OwnedTaskStatus::new(TaskStatus::Finished(Ok(Errno::Success.into()))).handle())
+        }
+    }
+
+    fn write_file(fs: &TmpFileSystem, path: &str) {
+        fs.new_open_options()
+            .write(true)
+            .create(true)
+            .open(Path::new(path))
+            .unwrap();
+    }
+
+    #[test]
+    fn chroot_hides_the_files_outside_the_root() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let _guard = runtime.enter();
+
+        let fs = TmpFileSystem::new();
+        fs.create_dir(Path::new("/sandbox")).unwrap();
+        fs.create_dir(Path::new("/shared")).unwrap();
+        write_file(&fs, "/sandbox/inside.txt");
+        write_file(&fs, "/shared/shared.txt");
+        write_file(&fs, "/secret.txt");
+
+        let mut store = Store::default();
+        let mut func_env = WasiEnv::builder("parent")
+            .sandbox_fs(fs)
+            .preopen_dir("/")
+            .unwrap()
+            .finalize(&mut store)
+            .unwrap();
+        let module = Module::new(&store, r#"(module (memory (export "memory") 1))"#).unwrap();
+        let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+        func_env.initialize(&mut store, instance).unwrap();
+
+        let opened = Arc::new(Mutex::new(Vec::new()));
+        func_env
+            .data_mut(&mut store)
+            .bin_factory
+            .commands
+            .register_command_with_path(
+                ProbeCommand {
+                    opened: opened.clone(),
+                },
+                "probe".to_string(),
+            );
+
+        let ctx = func_env.env.clone().into_mut(&mut store);
+        let (_handles, _ctx) = proc_spawn_internal(
+            ctx,
+            "probe".to_string(),
+            true,
+            None,
+            Some(vec!["/data:/shared".to_string()]),
+            Some("/sandbox".to_string()),
+            WasiStdioMode::Inherit,
+            WasiStdioMode::Inherit,
+            WasiStdioMode::Inherit,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            *opened.lock().unwrap(),
+            [
+                ("/inside.txt", true),
+                ("/data/shared.txt", true),
+                ("/secret.txt", false),
+                ("/../secret.txt", false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_addr_local_unix()`
+/// Returns the path to which a Unix domain socket is bound.
+///
+/// Note: This is similar to `getsockname` in POSIX using PF_UNIX
+///
+/// When the socket isn't bound, the returned path is empty. If the path
+/// exceeds the size of the buffer then this function will return ERANGE
+///
+/// ## Parameters
+///
+/// * `fd` - Socket that the path is bound to
+/// * `path` - Buffer the path is written to
+/// * `path_len` - Size of the buffer, which receives the length of the path
+#[instrument(level = "trace", skip_all, fields(%sock, path = field::Empty), ret)]
+pub fn sock_addr_local_unix<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: WasmPtr<u8, M>,
+    path_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let addr = wasi_try!(__sock_actor(
+        &mut ctx,
+        sock,
+        Rights::empty(),
+        |socket, _| socket.addr_local_unix()
+    ));
+
+    Span::current().record("path", format!("{:?}", addr));
+
+    let memory = unsafe { ctx.data().memory_view(&ctx) };
+    wasi_try!(crate::net::write_unix_path(
+        &memory,
+        path,
+        path_len,
+        addr.as_deref()
+    ));
+    Errno::Success
+}
@@ -0,0 +1,57 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_bind_unix()`
+/// Bind a Unix domain socket to a path
+/// Note: This is similar to `bind` in POSIX using PF_UNIX
+///
+/// ## Parameters
+///
+/// * `fd` - File descriptor of the socket to be bind
+/// * `path` - Path to bind the socket to, relative to the current directory.
+///   The socket is only visible to the processes sharing the root file
+///   system of this one
+#[instrument(level = "trace", skip_all, fields(%sock, path = field::Empty), ret)]
+pub fn sock_bind_unix<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let (memory, state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
+    let path = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    let path = wasi_try_ok!(crate::net::resolve_unix_path(
+        &state.fs.current_dir.lock().unwrap(),
+        &path
+    ));
+    Span::current().record("path", format!("{:?}", path));
+
+    // Like on POSIX, sockets can only be bound within existing directories
+    if let Some(parent) = path.parent() {
+        let metadata = wasi_try_ok!(state
+            .fs
+            .root_fs
+            .metadata(parent)
+            .map_err(fs_error_into_wasi_err));
+        if !metadata.is_dir() {
+            return Ok(Errno::Notdir);
+        }
+    }
+
+    let sockets = state.fs.unix_sockets.clone();
+    let net = env.net().clone();
+    let tasks = env.tasks().clone();
+    wasi_try_ok!(__sock_upgrade(
+        &mut ctx,
+        sock,
+        Rights::SOCK_BIND,
+        move |socket, _| async move {
+            socket
+                .bind_unix(tasks.deref(), net.deref(), sockets, path)
+                .await
+        }
+    ));
+
+    Ok(Errno::Success)
+}
@@ -0,0 +1,55 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_connect_unix()`
+/// Initiate a connection on a Unix domain socket to the socket bound to
+/// the specified path
+///
+/// Polling the socket handle will wait for data to arrive or for
+/// the socket status to change which can be queried via 'sock_status'
+///
+/// Note: This is similar to `connect` in POSIX using PF_UNIX
+///
+/// ## Parameters
+///
+/// * `fd` - Socket descriptor
+/// * `path` - Path of the socket to connect to, relative to the current directory
+#[instrument(level = "trace", skip_all, fields(%sock, path = field::Empty), ret)]
+pub fn sock_connect_unix<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let (memory, state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
+    let path = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    let peer = wasi_try_ok!(crate::net::resolve_unix_path(
+        &state.fs.current_dir.lock().unwrap(),
+        &path
+    ));
+    Span::current().record("path", format!("{:?}", peer));
+
+    let sockets = state.fs.unix_sockets.clone();
+    let net = env.net().clone();
+    let tasks = env.tasks().clone();
+    wasi_try_ok!(__sock_upgrade(
+        &mut ctx,
+        sock,
+        Rights::SOCK_CONNECT,
+        move |mut socket, flags| async move {
+            socket
+                .connect_unix(
+                    tasks.deref(),
+                    net.deref(),
+                    sockets,
+                    peer,
+                    None,
+                    flags.contains(Fdflags::NONBLOCK),
+                )
+                .await
+        }
+    ));
+
+    Ok(Errno::Success)
+}
@@ -92,6 +92,7 @@ pub(crate) fn sock_open_internal(
                     handler: None,
                 },
                 addr: None,
+                unix_addr: None,
             }),
         },
         _ => return Ok(Err(Errno::Notsup)),
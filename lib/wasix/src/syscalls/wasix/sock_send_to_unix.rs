@@ -0,0 +1,94 @@
+use super::*;
+use crate::{net::socket::TimeType, syscalls::*};
+
+/// ### `sock_send_to_unix()`
+/// Send a message on a Unix domain datagram socket to the socket bound to
+/// a specific path.
+/// Note: This is similar to `sendto` in POSIX using PF_UNIX, though it also
+/// supports writing the data from multiple buffers in the manner of `writev`.
+///
+/// ## Parameters
+///
+/// * `si_data` - List of scatter/gather vectors to which to retrieve data
+/// * `si_flags` - Message flags.
+/// * `path` - Path of the socket to send the message to, relative to the
+///   current directory
+///
+/// ## Return
+///
+/// Number of bytes transmitted.
+#[instrument(level = "trace", skip_all, fields(%sock, path = field::Empty, nsent = field::Empty), ret)]
+pub fn sock_send_to_unix<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    si_data: WasmPtr<__wasi_ciovec_t<M>, M>,
+    si_data_len: M::Offset,
+    _si_flags: SiFlags,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+    ret_data_len: WasmPtr<M::Offset, M>,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let (memory, state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
+    let path = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    let peer = wasi_try_ok!(crate::net::resolve_unix_path(
+        &state.fs.current_dir.lock().unwrap(),
+        &path
+    ));
+    Span::current().record("path", format!("{:?}", peer));
+
+    let bytes_written = wasi_try_ok!(__sock_asyncify(
+        env,
+        sock,
+        Rights::SOCK_SEND_TO,
+        |socket, fd| async move {
+            let nonblocking = fd.flags.contains(Fdflags::NONBLOCK);
+            let timeout = socket
+                .opt_time(TimeType::WriteTimeout)
+                .ok()
+                .flatten()
+                .unwrap_or(Duration::from_secs(30));
+
+            let iovs_arr = si_data
+                .slice(&memory, si_data_len)
+                .map_err(mem_error_to_wasi)?;
+            let iovs_arr = iovs_arr.access().map_err(mem_error_to_wasi)?;
+
+            let mut sent = 0usize;
+            for iovs in iovs_arr.iter() {
+                let buf = WasmPtr::<u8, M>::new(iovs.buf)
+                    .slice(&memory, iovs.buf_len)
+                    .map_err(mem_error_to_wasi)?
+                    .access()
+                    .map_err(mem_error_to_wasi)?;
+                let local_sent = match socket
+                    .send_to_unix(
+                        env.tasks().deref(),
+                        buf.as_ref(),
+                        &peer,
+                        Some(timeout),
+                        nonblocking,
+                    )
+                    .await
+                {
+                    Ok(s) => s,
+                    Err(_) if sent > 0 => break,
+                    Err(err) => return Err(err),
+                };
+                sent += local_sent;
+                if local_sent != buf.len() {
+                    break;
+                }
+            }
+            Ok(sent)
+        },
+    ));
+    Span::current().record("nsent", bytes_written);
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    let bytes_written: M::Offset =
+        wasi_try_ok!(bytes_written.try_into().map_err(|_| Errno::Overflow));
+    wasi_try_mem_ok!(ret_data_len.write(&memory, bytes_written));
+
+    Ok(Errno::Success)
+}
@@ -58,6 +58,14 @@ pub fn thread_spawn_internal_from_wasi<M: MemorySize>(
 ) -> Result<Tid, Errno> {
     // Now we use the environment and memory references
     let env = ctx.data();
+
+    // The shared libraries are only linked into the instance of this thread,
+    // so their functions would be missing from the new one
+    if !unsafe { env.inner() }.linker.is_empty() {
+        warn!("unable to spawn a thread while shared libraries are loaded");
+        return Err(Errno::Notsup);
+    }
+
     let memory = unsafe { env.memory_view(&ctx) };
     let runtime = env.runtime.clone();
     let tasks = env.tasks().clone();
@@ -208,6 +216,7 @@ fn call_module<M: MemorySize>(
         let mut ret = Errno::Success;
         let mut exit_code = None;
         if let Err(err) = call_ret {
+            crate::coredump::on_thread_error(env, store, &err);
             match err.downcast::<WasiError>() {
                 Ok(WasiError::ThreadExit) => {
                     trace!("thread exited cleanly");
@@ -0,0 +1,54 @@
+use wasmer::{Module, Store};
+use wasmer_wasix::WasiEnv;
+
+/// A program that traps leaves behind a coredump with its memory, globals
+/// and call stack.
+#[test]
+fn trap_writes_a_coredump() {
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        br#"
+        (module
+            (memory (export "memory") 1)
+            (global $counter (mut i32) (i32.const 0))
+
+            (func $crash
+                unreachable)
+
+            (func (export "_start")
+                (global.set $counter (i32.const 7))
+                (i32.store (i32.const 16) (i32.const 42))
+                (call $crash))
+        )
+        "#,
+    )
+    .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.coredump");
+    let builder = WasiEnv::builder("app").coredump_on_trap(&path);
+    std::thread::spawn(move || builder.run_with_store(module, &mut store))
+        .join()
+        .unwrap()
+        .unwrap_err();
+
+    let bytes = std::fs::read(&path).unwrap();
+    let coredump = Module::new(&Store::default(), &bytes).unwrap();
+
+    let core: Vec<_> = coredump.custom_sections("core").collect();
+    assert_eq!(&*core[0], b"\x00\x03app");
+
+    // The trap happened in $crash (function 0), which was called by _start
+    let corestack: Vec<_> = coredump.custom_sections("corestack").collect();
+    assert_eq!(corestack.len(), 1);
+    assert_eq!(&corestack[0][..9], b"\x00\x04main\x02\x00\x00");
+
+    let info = coredump.info();
+    let globals: Vec<_> = info.global_initializers.values().cloned().collect();
+    assert_eq!(globals, [wasmer_types::GlobalInit::I32Const(7)]);
+
+    // The data section comes last and holds the whole memory
+    let memory = &bytes[bytes.len() - wasmer::WASM_PAGE_SIZE..];
+    assert_eq!(memory[16], 42);
+}
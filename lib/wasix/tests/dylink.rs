@@ -0,0 +1,249 @@
+use std::path::Path;
+
+use virtual_fs::{AsyncWriteExt, FileSystem, TmpFileSystem};
+use wasmer::{Module, Store};
+use wasmer_wasix::WasiEnv;
+
+/// Loads a shared library that keeps a counter in its own data, reads it
+/// through its GOT and exposes a function that the main module calls
+/// through its table.
+#[tokio::test]
+async fn dlopen_and_call_a_shared_library() {
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        br#"
+        (module
+            (import "wasix_32v1" "dlopen" (func $dlopen (param i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "dlsym" (func $dlsym (param i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "proc_exit" (func $proc_exit (param i32)))
+
+            (type $binop (func (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (table (export "__indirect_function_table") 1 funcref)
+            (global (export "__stack_pointer") (mut i32) (i32.const 4096))
+            (global $heap (mut i32) (i32.const 1024))
+
+            (data (i32.const 100) "libadd.so")
+            (data (i32.const 120) "add")
+            (data (i32.const 130) "counter")
+
+            (func (export "malloc") (param i32) (result i32)
+                (global.get $heap)
+                (global.set $heap (i32.add (global.get $heap) (local.get 0))))
+
+            (func (export "_start")
+                ;; handle = dlopen("libadd.so", NOW)
+                (if (call $dlopen (i32.const 100) (i32.const 9) (i32.const 2) (i32.const 0))
+                    (then unreachable))
+                (if (call $dlsym (i32.load (i32.const 0)) (i32.const 120) (i32.const 3) (i32.const 4))
+                    (then unreachable))
+                (if (call $dlsym (i32.load (i32.const 0)) (i32.const 130) (i32.const 7) (i32.const 8))
+                    (then unreachable))
+                ;; exit(add(2, 3) + *counter)
+                (call $proc_exit
+                    (i32.add
+                        (call_indirect (type $binop) (i32.const 2) (i32.const 3) (i32.load (i32.const 4)))
+                        (i32.load (i32.load (i32.const 8))))))
+        )
+        "#,
+    )
+    .unwrap();
+
+    let builder = WasiEnv::builder("dylink").sandbox_fs(library_fs().await);
+    let err = std::thread::spawn(move || builder.run_with_store(module, &mut store))
+        .join()
+        .unwrap()
+        .unwrap_err();
+
+    // 2 + 3 + 42 from `add`, and 42 read through the address of `counter`
+    assert_eq!(err.as_exit_code().map(|code| code.raw()), Some(89));
+}
+
+/// The linker calls `malloc` to allocate the memory of a library, and that
+/// guest code can itself use the linker.
+#[tokio::test]
+async fn malloc_can_look_up_symbols_while_a_library_is_linked() {
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        br#"
+        (module
+            (import "wasix_32v1" "dlopen" (func $dlopen (param i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "dlsym" (func $dlsym (param i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "proc_exit" (func $proc_exit (param i32)))
+
+            (memory (export "memory") 1)
+            (table (export "__indirect_function_table") 1 funcref)
+            (global (export "__stack_pointer") (mut i32) (i32.const 4096))
+            (global $heap (mut i32) (i32.const 1024))
+
+            (data (i32.const 100) "libadd.so")
+            (data (i32.const 120) "malloc")
+
+            (func (export "malloc") (param i32) (result i32)
+                ;; *20 = dlsym(MAIN, "malloc", 16)
+                (i32.store (i32.const 20)
+                    (call $dlsym (i32.const 0) (i32.const 120) (i32.const 6) (i32.const 16)))
+                (global.get $heap)
+                (global.set $heap (i32.add (global.get $heap) (local.get 0))))
+
+            (func (export "_start")
+                (if (call $dlopen (i32.const 100) (i32.const 9) (i32.const 2) (i32.const 0))
+                    (then unreachable))
+                (if (i32.eqz (i32.load (i32.const 16)))
+                    (then unreachable))
+                (call $proc_exit (i32.add (i32.const 42) (i32.load (i32.const 20)))))
+        )
+        "#,
+    )
+    .unwrap();
+
+    let builder = WasiEnv::builder("dylink").sandbox_fs(library_fs().await);
+    let err = std::thread::spawn(move || builder.run_with_store(module, &mut store))
+        .join()
+        .unwrap()
+        .unwrap_err();
+
+    assert_eq!(err.as_exit_code().map(|code| code.raw()), Some(42));
+}
+
+/// Libraries are only linked into the instance of the thread that loaded
+/// them, so no other thread can be spawned while they are loaded.
+#[tokio::test]
+async fn threads_cant_be_spawned_while_a_library_is_loaded() {
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        br#"
+        (module
+            (import "wasix_32v1" "dlopen" (func $dlopen (param i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "thread_spawn_v2" (func $thread_spawn (param i32 i32) (result i32)))
+            (import "wasix_32v1" "proc_exit" (func $proc_exit (param i32)))
+
+            (memory (export "memory") 1)
+            (table (export "__indirect_function_table") 1 funcref)
+            (global (export "__stack_pointer") (mut i32) (i32.const 4096))
+            (global $heap (mut i32) (i32.const 1024))
+
+            (data (i32.const 100) "libadd.so")
+
+            (func (export "malloc") (param i32) (result i32)
+                (global.get $heap)
+                (global.set $heap (i32.add (global.get $heap) (local.get 0))))
+
+            (func (export "_start")
+                (if (call $dlopen (i32.const 100) (i32.const 9) (i32.const 2) (i32.const 0))
+                    (then unreachable))
+                ;; exit(thread_spawn(...))
+                (call $proc_exit (call $thread_spawn (i32.const 200) (i32.const 4))))
+        )
+        "#,
+    )
+    .unwrap();
+
+    let builder = WasiEnv::builder("dylink").sandbox_fs(library_fs().await);
+    let err = std::thread::spawn(move || builder.run_with_store(module, &mut store))
+        .join()
+        .unwrap()
+        .unwrap_err();
+
+    assert_eq!(
+        err.as_exit_code().map(|code| code.raw()),
+        Some(wasmer_wasix::wasmer_wasix_types::wasi::Errno::Notsup as i32)
+    );
+}
+
+/// The child of a fork gets a copy of the memory of its parent but none of
+/// its libraries, so forking is refused while they are loaded.
+#[tokio::test]
+async fn processes_cant_be_forked_while_a_library_is_loaded() {
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        br#"
+        (module
+            (import "wasix_32v1" "dlopen" (func $dlopen (param i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "proc_fork" (func $proc_fork (param i32 i32) (result i32)))
+            (import "wasix_32v1" "proc_exit" (func $proc_exit (param i32)))
+
+            (memory (export "memory") 1)
+            (table (export "__indirect_function_table") 1 funcref)
+            (global (export "__stack_pointer") (mut i32) (i32.const 4096))
+            (global $heap (mut i32) (i32.const 1024))
+
+            (data (i32.const 100) "libadd.so")
+
+            (func (export "malloc") (param i32) (result i32)
+                (global.get $heap)
+                (global.set $heap (i32.add (global.get $heap) (local.get 0))))
+
+            (func (export "_start")
+                (if (call $dlopen (i32.const 100) (i32.const 9) (i32.const 2) (i32.const 0))
+                    (then unreachable))
+                ;; exit(fork(copy_memory, &pid))
+                (call $proc_exit (call $proc_fork (i32.const 1) (i32.const 4))))
+        )
+        "#,
+    )
+    .unwrap();
+
+    let builder = WasiEnv::builder("dylink").sandbox_fs(library_fs().await);
+    let err = std::thread::spawn(move || builder.run_with_store(module, &mut store))
+        .join()
+        .unwrap()
+        .unwrap_err();
+
+    assert_eq!(
+        err.as_exit_code().map(|code| code.raw()),
+        Some(wasmer_wasix::wasmer_wasix_types::wasi::Errno::Notsup as i32)
+    );
+}
+
+/// A file system with the library of [`side_module`] at `/lib/libadd.so`.
+async fn library_fs() -> TmpFileSystem {
+    let fs = TmpFileSystem::new();
+    fs.create_dir(Path::new("/lib")).unwrap();
+    let mut file = fs
+        .new_open_options()
+        .write(true)
+        .create(true)
+        .open("/lib/libadd.so")
+        .unwrap();
+    file.write_all(&side_module()).await.unwrap();
+    fs
+}
+
+/// A shared library with 4 bytes of data holding the number 42.
+fn side_module() -> Vec<u8> {
+    let mut wasm = wasmer::wat2wasm(
+        r#"
+        (module
+            (import "env" "memory" (memory 1))
+            (import "env" "__memory_base" (global $memory_base i32))
+            (import "GOT.mem" "counter" (global $counter (mut i32)))
+
+            (data (global.get $memory_base) "\2a\00\00\00")
+            (global (export "counter") i32 (i32.const 0))
+
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add
+                    (i32.add (local.get 0) (local.get 1))
+                    (i32.load (global.get $counter))))
+        )
+        "#
+        .as_bytes(),
+    )
+    .unwrap()
+    .into_owned();
+
+    // WASM_DYLINK_MEM_INFO: 4 bytes of memory aligned to 4, no table slots
+    let payload = [1, 4, 4, 2, 0, 0];
+    let name = b"dylink.0";
+    wasm.push(0);
+    wasm.push((1 + name.len() + payload.len()) as u8);
+    wasm.push(name.len() as u8);
+    wasm.extend_from_slice(name);
+    wasm.extend_from_slice(&payload);
+    wasm
+}
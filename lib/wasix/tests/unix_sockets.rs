@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use tokio::runtime::Handle;
+use virtual_net::LoopbackNetworking;
+use wasmer::{Module, Store};
+use wasmer_wasix::{runtime::task_manager::tokio::TokioTaskManager, PluggableRuntime, WasiEnv};
+
+/// Binds two datagram sockets to `/a.sock` and `/b.sock`, sends "hi" from
+/// the second one to the first, and receives it along with the path of the
+/// sender, in a buffer of `path_buf_len` bytes.
+///
+/// Exits with the length of the sender's path times 10 plus the number of
+/// bytes received, after checking the datagram and as much of the path as
+/// fits in the buffer.
+fn datagram_module(path_buf_len: u32) -> String {
+    format!(
+        r#"
+        (module
+            (import "wasix_32v1" "sock_open" (func $sock_open (param i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "sock_bind_unix" (func $sock_bind_unix (param i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "sock_send_to_unix"
+                (func $sock_send_to_unix (param i32 i32 i32 i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "sock_recv_from_unix"
+                (func $sock_recv_from_unix (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+            (import "wasix_32v1" "proc_exit" (func $proc_exit (param i32)))
+
+            (memory (export "memory") 1)
+
+            (data (i32.const 100) "/a.sock")
+            (data (i32.const 110) "/b.sock")
+            (data (i32.const 120) "hi")
+            ;; the ciovec of the datagram that is sent
+            (data (i32.const 200) "\78\00\00\00\02\00\00\00")
+            ;; the iovec the datagram is received in, at 300
+            (data (i32.const 208) "\2c\01\00\00\10\00\00\00")
+
+            (func (export "_start")
+                ;; a = socket(AF_UNIX, SOCK_DGRAM), b = socket(AF_UNIX, SOCK_DGRAM)
+                (if (call $sock_open (i32.const 3) (i32.const 2) (i32.const 0) (i32.const 0))
+                    (then unreachable))
+                (if (call $sock_open (i32.const 3) (i32.const 2) (i32.const 0) (i32.const 4))
+                    (then unreachable))
+                (if (call $sock_bind_unix (i32.load (i32.const 0)) (i32.const 100) (i32.const 7))
+                    (then unreachable))
+                (if (call $sock_bind_unix (i32.load (i32.const 4)) (i32.const 110) (i32.const 7))
+                    (then unreachable))
+
+                (if (call $sock_send_to_unix (i32.load (i32.const 4)) (i32.const 200) (i32.const 1)
+                        (i32.const 0) (i32.const 100) (i32.const 7) (i32.const 216))
+                    (then unreachable))
+                (if (i32.ne (i32.load (i32.const 216)) (i32.const 2))
+                    (then unreachable))
+
+                (i32.store (i32.const 228) (i32.const {path_buf_len}))
+                (if (call $sock_recv_from_unix (i32.load (i32.const 0)) (i32.const 208) (i32.const 1)
+                        (i32.const 0) (i32.const 220) (i32.const 224) (i32.const 400) (i32.const 228))
+                    (then unreachable))
+                (if (i32.ne (i32.load16_u (i32.const 300)) (i32.load16_u (i32.const 120)))
+                    (then unreachable))
+                ;; only the part of the path that fits in the buffer is written
+                (if (i64.ne
+                        (i64.load (i32.const 400))
+                        (i64.and
+                            (i64.load (i32.const 110))
+                            (i64.shr_u (i64.const -1) (i64.const {unwritten_bits}))))
+                    (then unreachable))
+
+                (call $proc_exit
+                    (i32.add
+                        (i32.mul (i32.load (i32.const 228)) (i32.const 10))
+                        (i32.load (i32.const 220)))))
+        )
+        "#,
+        // The path is 7 bytes long, and is followed by zeros in memory
+        unwritten_bits = 64 - 8 * path_buf_len.min(8),
+    )
+}
+
+fn run(wat: String) -> Option<i32> {
+    let mut store = Store::default();
+    let module = Module::new(&store, wat).unwrap();
+
+    let mut runtime = PluggableRuntime::new(Arc::new(TokioTaskManager::new(Handle::current())));
+    runtime.set_networking_implementation(LoopbackNetworking::new());
+    let builder = WasiEnv::builder("unix-sockets").runtime(Arc::new(runtime));
+    let err = std::thread::spawn(move || builder.run_with_store(module, &mut store))
+        .join()
+        .unwrap()
+        .unwrap_err();
+
+    err.as_exit_code().map(|code| code.raw())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn datagrams_are_sent_to_and_received_from_paths() {
+    // "/b.sock" is 7 bytes long, and "hi" is 2
+    assert_eq!(run(datagram_module(64)), Some(72));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn the_path_of_the_sender_is_truncated_to_fit_its_buffer() {
+    assert_eq!(run(datagram_module(3)), Some(72));
+}
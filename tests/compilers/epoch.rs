@@ -0,0 +1,92 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use wasmer::sys::NativeEngineExt;
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+fn instance(config: &crate::Config) -> Result<(Engine, Store, Instance)> {
+    let mut compiler_config = config.compiler_config(config.canonicalize_nans);
+    compiler_config.epoch_interruption(true);
+    let engine = config.engine(compiler_config);
+    let mut store = Store::new(engine.clone());
+    let wat = r#"(module
+        (import "env" "tick" (func $tick))
+        (func (export "count") (param $n i32) (result i32)
+            (local $i i32)
+            (loop $l
+                (call $tick)
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $l (i32.lt_u (local.get $i) (local.get $n))))
+            (local.get $i))
+        (func (export "nop"))
+    )"#;
+    let module = Module::new(&store, wat)?;
+    let tick_engine = engine.clone();
+    let tick = Function::new_typed(&mut store, move || tick_engine.increment_epoch());
+    let imports = imports! {
+        "env" => {
+            "tick" => tick,
+        },
+    };
+    let instance = Instance::new(&mut store, &module, &imports)?;
+    Ok((engine, store, instance))
+}
+
+#[compiler_test(epoch)]
+fn epoch_function_entry(config: crate::Config) -> Result<()> {
+    let (engine, mut store, instance) = instance(&config)?;
+    let nop: TypedFunction<(), ()> = instance.exports.get_typed_function(&store, "nop")?;
+
+    store.set_epoch_deadline(1);
+    nop.call(&mut store)?;
+    engine.increment_epoch();
+    let err = nop.call(&mut store).unwrap_err();
+    assert_eq!(err.to_trap(), Some(TrapCode::Interrupt));
+    Ok(())
+}
+
+#[compiler_test(epoch)]
+fn epoch_loop_header(config: crate::Config) -> Result<()> {
+    let (_engine, mut store, instance) = instance(&config)?;
+    let count: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "count")?;
+
+    store.set_epoch_deadline(3);
+    let err = count.call(&mut store, 10).unwrap_err();
+    assert_eq!(err.to_trap(), Some(TrapCode::Interrupt));
+    Ok(())
+}
+
+#[compiler_test(epoch)]
+fn epoch_callback(config: crate::Config) -> Result<()> {
+    let (_engine, mut store, instance) = instance(&config)?;
+    let count: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "count")?;
+
+    let calls = Arc::new(AtomicU32::new(0));
+    let callback_calls = calls.clone();
+    store.epoch_deadline_callback(move |_store| {
+        callback_calls.fetch_add(1, Ordering::SeqCst);
+        Ok(2)
+    });
+    store.set_epoch_deadline(2);
+    assert_eq!(count.call(&mut store, 10)?, 10);
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+    Ok(())
+}
+
+#[compiler_test(epoch)]
+fn epoch_callback_panic(config: crate::Config) -> Result<()> {
+    let (_engine, mut store, instance) = instance(&config)?;
+    let count: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "count")?;
+
+    store.epoch_deadline_callback(|_store| panic!("deadline callback panicked"));
+    store.set_epoch_deadline(2);
+    let panic =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| count.call(&mut store, 10)))
+            .unwrap_err();
+    assert_eq!(
+        panic.downcast_ref::<&str>(),
+        Some(&"deadline callback panicked")
+    );
+    Ok(())
+}
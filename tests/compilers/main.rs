@@ -7,13 +7,16 @@ extern crate compiler_test_derive;
 
 mod config;
 mod deterministic;
+mod epoch;
 mod imports;
 mod issues;
 mod metering;
 mod middlewares;
 // mod multi_value_imports;
 mod artifact;
+mod resource_limiter;
 mod serialize;
+mod tail_calls;
 mod traps;
 mod typed_functions;
 mod wasi;
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use wasmer::*;
+
+const PAGE: usize = 0x10000;
+
+fn instance(config: &crate::Config, limits: StoreLimits) -> Result<(Store, Instance)> {
+    let mut store = config.store();
+    store.set_resource_limiter(limits);
+    let wat = r#"(module
+        (memory 1 10)
+        (table 1 10 funcref)
+        (func (export "grow_memory") (param $delta i32) (result i32)
+            (memory.grow (local.get $delta)))
+        (func (export "grow_table") (param $delta i32) (result i32)
+            (table.grow (ref.null func) (local.get $delta)))
+    )"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&mut store, &module, &imports! {})?;
+    Ok((store, instance))
+}
+
+#[compiler_test(resource_limiter)]
+fn limiter_denies_memory_grow(config: crate::Config) -> Result<()> {
+    let limits = StoreLimitsBuilder::new().memory_size(2 * PAGE).build();
+    let (mut store, instance) = instance(&config, limits)?;
+    let grow: TypedFunction<i32, i32> =
+        instance.exports.get_typed_function(&store, "grow_memory")?;
+
+    assert_eq!(grow.call(&mut store, 1)?, 1);
+    assert_eq!(grow.call(&mut store, 1)?, -1);
+    Ok(())
+}
+
+#[compiler_test(resource_limiter)]
+fn limiter_denies_imported_memory_grow(config: crate::Config) -> Result<()> {
+    let mut store = config.store();
+    store.set_resource_limiter(StoreLimitsBuilder::new().memory_size(2 * PAGE).build());
+    let wat = r#"(module
+        (import "env" "memory" (memory 1))
+        (func (export "grow_memory") (param $delta i32) (result i32)
+            (memory.grow (local.get $delta)))
+    )"#;
+    let module = Module::new(&store, wat)?;
+    let memory = Memory::new(&mut store, MemoryType::new(1, None, false))?;
+    let imports = imports! {
+        "env" => {
+            "memory" => memory,
+        },
+    };
+    let instance = Instance::new(&mut store, &module, &imports)?;
+    let grow: TypedFunction<i32, i32> =
+        instance.exports.get_typed_function(&store, "grow_memory")?;
+
+    assert_eq!(grow.call(&mut store, 1)?, 1);
+    assert_eq!(grow.call(&mut store, 1)?, -1);
+    Ok(())
+}
+
+#[compiler_test(resource_limiter)]
+fn limiter_traps_on_grow(config: crate::Config) -> Result<()> {
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(2 * PAGE)
+        .table_elements(2)
+        .trap_on_grow_failure(true)
+        .build();
+    let (mut store, instance) = instance(&config, limits)?;
+    let grow: TypedFunction<i32, i32> =
+        instance.exports.get_typed_function(&store, "grow_memory")?;
+    let grow_table: TypedFunction<i32, i32> =
+        instance.exports.get_typed_function(&store, "grow_table")?;
+
+    let err = grow.call(&mut store, 2).unwrap_err();
+    assert!(err.downcast::<ResourceLimitExceeded>().is_ok());
+    assert_eq!(grow_table.call(&mut store, 1)?, 1);
+    let err = grow_table.call(&mut store, 1).unwrap_err();
+    assert!(err.downcast::<ResourceLimitExceeded>().is_ok());
+    Ok(())
+}
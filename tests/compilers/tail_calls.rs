@@ -0,0 +1,161 @@
+use anyhow::Result;
+use wasmer::sys::Features;
+use wasmer::*;
+
+/// A function with enough parameters to pass some of them on the stack
+/// rotates them through a million tail calls to itself, then tail calls a
+/// function with fewer parameters that weighs them by position.
+const ROTATE: &str = r#"(module
+    (type $rotate (func (param i64 i64 i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+    (table 1 funcref)
+    (elem (i32.const 0) $rotate-indirect)
+
+    (func $rotate (export "rotate") (type $rotate)
+        (if (i64.eqz (local.get 0))
+            (then
+                (return_call $weigh
+                    (local.get 1) (local.get 2) (local.get 3) (local.get 4) (local.get 5)
+                    (local.get 6) (local.get 7) (local.get 8) (local.get 9))))
+        (return_call $rotate
+            (i64.sub (local.get 0) (i64.const 1))
+            (local.get 2) (local.get 3) (local.get 4) (local.get 5) (local.get 6)
+            (local.get 7) (local.get 8) (local.get 9)
+            (i64.add (local.get 1) (local.get 0))))
+
+    (func $rotate-indirect (export "rotate_indirect") (type $rotate)
+        (if (i64.eqz (local.get 0))
+            (then
+                (return_call $weigh
+                    (local.get 1) (local.get 2) (local.get 3) (local.get 4) (local.get 5)
+                    (local.get 6) (local.get 7) (local.get 8) (local.get 9))))
+        (return_call_indirect (type $rotate)
+            (i64.sub (local.get 0) (i64.const 1))
+            (local.get 2) (local.get 3) (local.get 4) (local.get 5) (local.get 6)
+            (local.get 7) (local.get 8) (local.get 9)
+            (i64.add (local.get 1) (local.get 0))
+            (i32.const 0)))
+
+    (func $weigh (param i64 i64 i64 i64 i64 i64 i64 i64 i64) (result i64)
+        (i64.add (local.get 0)
+        (i64.add (i64.mul (local.get 1) (i64.const 2))
+        (i64.add (i64.mul (local.get 2) (i64.const 3))
+        (i64.add (i64.mul (local.get 3) (i64.const 4))
+        (i64.add (i64.mul (local.get 4) (i64.const 5))
+        (i64.add (i64.mul (local.get 5) (i64.const 6))
+        (i64.add (i64.mul (local.get 6) (i64.const 7))
+        (i64.add (i64.mul (local.get 7) (i64.const 8))
+                 (i64.mul (local.get 8) (i64.const 9)))))))))))
+)"#;
+
+type Rotate = TypedFunction<(i64, i64, i64, i64, i64, i64, i64, i64, i64, i64), i64>;
+
+fn store(config: &mut crate::Config) -> Store {
+    let mut features = Features::default();
+    features.tail_call(true);
+    config.set_features(features);
+    config.store()
+}
+
+fn expected_rotation(n: i64) -> i64 {
+    let mut values: Vec<i64> = (1..=9).collect();
+    for n in (1..=n).rev() {
+        let first = values.remove(0);
+        values.push(first + n);
+    }
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (i as i64 + 1) * value)
+        .sum()
+}
+
+#[compiler_test(tail_calls)]
+fn tail_calls_pass_stack_parameters(mut config: crate::Config) -> Result<()> {
+    let mut store = store(&mut config);
+    let module = Module::new(&store, ROTATE)?;
+    let instance = Instance::new(&mut store, &module, &imports! {})?;
+
+    for name in ["rotate", "rotate_indirect"] {
+        let rotate: Rotate = instance.exports.get_typed_function(&store, name)?;
+        for n in [0, 1, 7, 1_000_000] {
+            assert_eq!(
+                rotate.call(&mut store, n, 1, 2, 3, 4, 5, 6, 7, 8, 9)?,
+                expected_rotation(n),
+                "{name}({n})"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The stack parameters of `$big` don't fit in the area the caller of
+/// `$small` reserved for its single parameter, and they keep tail calling
+/// each other far deeper than the stack would allow without replacing frames.
+#[compiler_test(tail_calls)]
+fn tail_calls_growing_stack_parameters(mut config: crate::Config) -> Result<()> {
+    let mut store = store(&mut config);
+    let wats = [
+        "(return_call $big (i64.sub (local.get 0) (i64.const 1)) (local.get 1)
+            (i64.const 0) (i64.const 0) (i64.const 0) (i64.const 0) (i64.const 0)
+            (i64.const 0) (i64.const 0) (i64.const 2))",
+        "(return_call_indirect (type $big) (i64.sub (local.get 0) (i64.const 1)) (local.get 1)
+            (i64.const 0) (i64.const 0) (i64.const 0) (i64.const 0) (i64.const 0)
+            (i64.const 0) (i64.const 0) (i64.const 2)
+            (i32.const 0))",
+    ];
+    for body in wats {
+        let wat = format!(
+            r#"(module
+                (type $big (func (param i64 i64 i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+                (table 1 funcref)
+                (elem (i32.const 0) $big)
+                (func $small (export "small") (param i64 i64) (result i64)
+                    (if (i64.eqz (local.get 0))
+                        (then (return (local.get 1))))
+                    {body})
+                (func $big (type $big)
+                    (return_call $small (local.get 0) (i64.add (local.get 1) (local.get 9))))
+            )"#
+        );
+        let module = Module::new(&store, &wat)?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+        let small: TypedFunction<(i64, i64), i64> =
+            instance.exports.get_typed_function(&store, "small")?;
+        assert_eq!(small.call(&mut store, 21, 0)?, 42);
+        assert_eq!(small.call(&mut store, 1_000_000, 0)?, 2_000_000);
+    }
+    Ok(())
+}
+
+/// Host functions can't replace the frame of their caller, so tail calling
+/// them falls back to a call followed by a return.
+#[compiler_test(tail_calls)]
+fn tail_calls_to_host_functions(mut config: crate::Config) -> Result<()> {
+    let mut store = store(&mut config);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (type $double (func (param i64) (result i64)))
+            (import "host" "double" (func $double (type $double)))
+            (table 1 funcref)
+            (elem (i32.const 0) $double)
+            (func (export "direct") (param i64) (result i64)
+                (return_call $double (local.get 0)))
+            (func (export "indirect") (param i64) (result i64)
+                (return_call_indirect (type $double) (local.get 0) (i32.const 0)))
+        )"#,
+    )?;
+    let double = Function::new_typed(&mut store, |x: i64| x * 2);
+    let imports = imports! {
+        "host" => {
+            "double" => double,
+        },
+    };
+    let instance = Instance::new(&mut store, &module, &imports)?;
+
+    for name in ["direct", "indirect"] {
+        let call: TypedFunction<i64, i64> = instance.exports.get_typed_function(&store, name)?;
+        assert_eq!(call.call(&mut store, 21)?, 42, "{name}");
+    }
+    Ok(())
+}
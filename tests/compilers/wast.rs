@@ -23,6 +23,10 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
     let is_bulkmemory = wast_path.contains("bulk-memory");
     let is_simd = wast_path.contains("simd");
     let is_threads = wast_path.contains("threads");
+    let is_exceptions = wast_path.contains("exception-handling");
+    let is_relaxed_simd = wast_path.contains("relaxed-simd");
+    // The exception handling tests also use tail calls.
+    let is_tail_call = wast_path.contains("tail-call") || is_exceptions;
     if is_bulkmemory {
         features.bulk_memory(true);
     }
@@ -32,6 +36,15 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
     if is_threads {
         features.threads(true);
     }
+    if is_exceptions {
+        features.exceptions(true);
+    }
+    if is_tail_call {
+        features.tail_call(true);
+    }
+    if is_relaxed_simd {
+        features.relaxed_simd(true);
+    }
     if config.compiler == crate::Compiler::Singlepass {
         features.multi_value(false);
     }
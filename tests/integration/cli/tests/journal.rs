@@ -0,0 +1,171 @@
+//! Basic tests for the `journal` subcommand
+
+use std::process::Command;
+
+use assert_cmd::prelude::OutputAssertExt;
+use predicates::{prelude::PredicateBooleanExt, str::contains};
+use tempfile::TempDir;
+use wasmer_integration_tests_cli::get_wasmer_path;
+
+/// Opens `/data/hello.txt` through the preopened root directory, and exits
+/// with the error code.
+const OPEN_FILE: &str = r#"(module
+    (import "wasi_snapshot_preview1" "path_open"
+        (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 16) "data/hello.txt")
+    (func (export "_start")
+        (call $proc_exit
+            (call $path_open
+                (i32.const 3) (i32.const 0) (i32.const 16) (i32.const 14) (i32.const 0)
+                (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 0))))
+)"#;
+
+#[test]
+fn replay_a_journal_with_mapped_directories() {
+    let temp = TempDir::new().unwrap();
+    let data = temp.path().join("data");
+    std::fs::create_dir(&data).unwrap();
+    std::fs::write(data.join("hello.txt"), "Hello, World!").unwrap();
+    let module = temp.path().join("open-file.wat");
+    std::fs::write(&module, OPEN_FILE).unwrap();
+    let journal = temp.path().join("open-file.journal");
+    let mapdir = format!("/data:{}", data.display());
+
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--journal")
+        .arg(&journal)
+        .arg("--without-compact-on-drop")
+        .arg("--mapdir")
+        .arg(&mapdir)
+        .arg(&module)
+        .assert()
+        .success();
+
+    Command::new(get_wasmer_path())
+        .arg("journal")
+        .arg("replay")
+        .arg("--mapdir")
+        .arg(&mapdir)
+        .arg("--fds")
+        .arg("--memory")
+        .arg("16:14")
+        .arg(&journal)
+        .arg(&module)
+        .assert()
+        .success()
+        .stdout(contains("hello.txt"))
+        .stdout(contains("|data/hello.txt|"));
+}
+
+/// Removes `/data/hello.txt` through the preopened root directory, and exits
+/// with the error code.
+const UNLINK_FILE: &str = r#"(module
+    (import "wasi_snapshot_preview1" "path_unlink_file"
+        (func $path_unlink_file (param i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 16) "data/hello.txt")
+    (func (export "_start")
+        (call $proc_exit
+            (call $path_unlink_file (i32.const 3) (i32.const 16) (i32.const 14))))
+)"#;
+
+#[test]
+fn seeking_backwards_over_an_unlink_keeps_the_mapped_directories() {
+    let temp = TempDir::new().unwrap();
+    let data = temp.path().join("data");
+    std::fs::create_dir(&data).unwrap();
+    let hello = data.join("hello.txt");
+    std::fs::write(&hello, "Hello, World!").unwrap();
+    let module = temp.path().join("unlink-file.wat");
+    std::fs::write(&module, UNLINK_FILE).unwrap();
+    let journal = temp.path().join("unlink-file.journal");
+    let mapdir = format!("/data:{}", data.display());
+
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--journal")
+        .arg(&journal)
+        .arg("--without-compact-on-drop")
+        .arg("--mapdir")
+        .arg(&mapdir)
+        .arg(&module)
+        .assert()
+        .success();
+    assert!(!hello.exists());
+    std::fs::write(&hello, "Hello, World!").unwrap();
+
+    // Replay the unlink, go back to the start and replay it again
+    assert_cmd::Command::new(get_wasmer_path())
+        .arg("journal")
+        .arg("replay")
+        .arg("--interactive")
+        .arg("--mapdir")
+        .arg(&mapdir)
+        .arg(&journal)
+        .arg(&module)
+        .write_stdin("seek 1000\nseek 0\nseek 1000\nquit\n")
+        .assert()
+        .success()
+        .stderr(contains("Error").not());
+
+    assert_eq!(std::fs::read_to_string(&hello).unwrap(), "Hello, World!");
+}
+
+/// Listens on `127.0.0.1:{port}`, and exits with the error code.
+fn listen_module(port: u16) -> String {
+    format!(
+        r#"(module
+    (import "wasix_32v1" "sock_open" (func $sock_open (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_bind" (func $sock_bind (param i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_listen" (func $sock_listen (param i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 16) "\01\00\00\00\7f\00\00\01")
+    (func $check (param $errno i32)
+        (if (local.get $errno) (then (call $proc_exit (local.get $errno)))))
+    (func (export "_start")
+        (i32.store16 (i32.const 18) (i32.const {port}))
+        (call $check (call $sock_open (i32.const 1) (i32.const 1) (i32.const 6) (i32.const 0)))
+        (call $check (call $sock_bind (i32.load (i32.const 0)) (i32.const 16)))
+        (call $proc_exit (call $sock_listen (i32.load (i32.const 0)) (i32.const 1))))
+)"#
+    )
+}
+
+#[test]
+fn replaying_sockets_leaves_the_host_network_alone() {
+    let temp = TempDir::new().unwrap();
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let module = temp.path().join("listen.wat");
+    std::fs::write(&module, listen_module(port)).unwrap();
+    let journal = temp.path().join("listen.journal");
+
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--net")
+        .arg("--journal")
+        .arg(&journal)
+        .arg("--without-compact-on-drop")
+        .arg(&module)
+        .assert()
+        .success();
+
+    // The replay would fail to listen if it tried to use the host's port
+    let _listener = std::net::TcpListener::bind(("127.0.0.1", port)).unwrap();
+    Command::new(get_wasmer_path())
+        .arg("journal")
+        .arg("replay")
+        .arg("--fds")
+        .arg(&journal)
+        .arg(&module)
+        .assert()
+        .success();
+}
@@ -283,6 +283,23 @@ fn run_wasi_works() {
     assert.stdout("27\n");
 }
 
+#[test]
+fn run_prints_syscall_stats() {
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--syscall-stats")
+        .arg(fixtures::qjs())
+        .arg("--")
+        .arg("-e")
+        .arg("print(3 * (4 + 5))")
+        .assert()
+        .success()
+        .stdout("27\n")
+        .stderr(contains("% time     seconds  usecs/call"))
+        .stderr(is_match(r"\n *\d+\.\d+ .* fd_write\n").unwrap())
+        .stderr(contains(" total\n"));
+}
+
 #[test]
 fn test_wasmer_run_pirita_works() {
     let temp_dir = tempfile::TempDir::new().unwrap();
@@ -589,7 +606,58 @@ fn run_invoke_works_with_nomain_wasi() {
         .arg("_start")
         .arg(&module_file)
         .assert()
-        .success();
+        .success()
+        // Functions without results print nothing, not an empty line
+        .stdout("");
+
+    std::fs::remove_file(&module_file).unwrap();
+}
+
+#[test]
+fn run_invoke_parses_and_prints_typed_values() {
+    let wat = r#"
+    (module
+        (func (export "echo") (param i32 f64 v128 externref) (result i32 f64 v128 externref)
+            local.get 0
+            local.get 1
+            local.get 2
+            local.get 3))
+    "#;
+
+    let random = rand::random::<u64>();
+    let module_file = std::env::temp_dir().join(format!("{random}.wat"));
+    std::fs::write(&module_file, wat.as_bytes()).unwrap();
+
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--invoke")
+        .arg("echo")
+        .arg(&module_file)
+        .arg("--")
+        .arg("0xffffffff")
+        .arg("-0x1.8p1")
+        .arg("i32x4:1,2,3,4")
+        .arg("null")
+        .assert()
+        .success()
+        .stdout("i32 -1\nf64 -3.0\nv128 0x00000004000000030000000200000001\nexternref null\n");
+
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--invoke")
+        .arg("echo")
+        .arg("--json")
+        .arg(&module_file)
+        .arg("--")
+        .arg("7")
+        .arg("nan:0x1")
+        .arg("0")
+        .arg("null")
+        .assert()
+        .success()
+        .stdout(contains(
+            r#"[{"type":"i32","value":7},{"type":"f64","value":"nan:0x1"},"#,
+        ));
 
     std::fs::remove_file(&module_file).unwrap();
 }
@@ -940,6 +1008,20 @@ fn wasm_file_with_no_abi() {
     assert.success();
 }
 
+#[test]
+fn locked_is_rejected_for_files() {
+    let assert = Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--locked")
+        .arg(fixtures::fib())
+        .env("RUST_LOG", &*RUST_LOG)
+        .assert();
+
+    assert.failure().stderr(contains(
+        "--locked can only be used with a package directory",
+    ));
+}
+
 #[test]
 #[cfg_attr(
     all(target_env = "musl", target_os = "linux"),
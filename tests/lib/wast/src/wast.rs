@@ -177,6 +177,16 @@ impl Wast {
         bail!("expected '{}', got '{}'", expected, actual)
     }
 
+    fn assert_exception(&self, result: Result<Vec<Value>>) -> Result<()> {
+        match result {
+            Ok(values) => bail!("expected exception, got {:?}", values),
+            Err(e) => match e.downcast_ref::<RuntimeError>() {
+                Some(error) if error.is_exception() => Ok(()),
+                _ => bail!("expected exception, got '{}'", e),
+            },
+        }
+    }
+
     fn run_directive(&mut self, _test: &Path, directive: wast::WastDirective) -> Result<()> {
         use wast::WastDirective::*;
 
@@ -238,8 +248,9 @@ impl Wast {
                     )
                 }
             }
-            AssertException { .. } => {
-                // Do nothing for now
+            AssertException { span: _, exec } => {
+                let result = self.perform_execute(exec);
+                self.assert_exception(result)?;
             }
             AssertMalformed {
                 module,
@@ -462,6 +473,10 @@ impl Wast {
                 ty: AbstractHeapType::Extern,
                 ..
             }) => Value::null(),
+            RefNull(HeapType::Abstract {
+                ty: AbstractHeapType::Exn,
+                ..
+            }) => Value::ExceptionRef(None),
             RefExtern(number) => Value::ExternRef(Some(ExternRef::new(&mut self.store, *number))),
             other => bail!("couldn't convert {:?} to a runtime value", other),
         })
@@ -489,6 +504,12 @@ impl Wast {
             || (expected.contains("unknown memory") && actual.contains("unknown memory"))
             || (expected.contains("unknown memory") && actual.contains("Data segment extends past end of the data section"))
             || (expected.contains("unknown elem segment") && actual.contains("unknown element segment"))
+            // `exception-handling/throw.wast` expects the wording of the reference
+            // interpreter for the operands of `throw`, which wasmparser reports as
+            // `expected i32 but nothing on stack` and `expected i32, found i64`.
+            || ((expected == "type mismatch: instruction requires [i32] but stack has []"
+                || expected == "type mismatch: instruction requires [i32] but stack has [i64]")
+                && actual.contains("type mismatch: expected i32"))
             // The same test here is asserted to have one error message in
             // `memory.wast` and a different error message in
             // `memory64/memory.wast`, so we equate these two error messages to get
@@ -542,6 +563,14 @@ impl Wast {
                 let x = extern_ref.downcast::<u32>(&self.store).cloned();
                 x == *num
             }
+            (
+                Value::ExceptionRef(None),
+                WastRetCore::RefNull(Some(wast::core::HeapType::Abstract {
+                    ty: AbstractHeapType::Exn,
+                    ..
+                })),
+            ) => true,
+            (Value::ExceptionRef(Some(_)), WastRetCore::RefNull(_)) => false,
             _ => bail!(
                 "don't know how to compare {:?} and {:?} yet",
                 actual,
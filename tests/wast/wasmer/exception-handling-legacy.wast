@@ -0,0 +1,163 @@
+;; Tests for the legacy exception handling instructions
+;; (`try`, `catch`, `catch_all`, `delegate` and `rethrow`).
+
+(module
+  (tag $e0)
+  (tag $e1 (param i32))
+  (tag $e2 (param i32 i64))
+
+  (func $throw-if (param i32) (result i32)
+    (local.get 0)
+    (i32.const 0) (if (i32.ne) (then (throw $e1 (i32.const 42))))
+    (i32.const 0)
+  )
+
+  (func (export "catch-empty") (result i32)
+    try (result i32)
+      throw $e0
+    catch $e0
+      i32.const 1
+    end
+  )
+
+  (func (export "catch-param") (param i32) (result i32)
+    try (result i32)
+      local.get 0
+      call $throw-if
+    catch $e1
+    end
+  )
+
+  (func (export "catch-multi-param") (result i64) (local i64)
+    try (result i64)
+      i32.const 1
+      i64.const 7
+      throw $e2
+    catch $e2
+      local.set 0
+      i64.extend_i32_u
+      local.get 0
+      i64.add
+    end
+  )
+
+  (func (export "catch-all") (result i32)
+    try (result i32)
+      i32.const 1
+      i64.const 2
+      throw $e2
+    catch $e0
+      i32.const 1
+    catch_all
+      i32.const 2
+    end
+  )
+
+  (func (export "catch-second") (result i32)
+    try (result i32)
+      i32.const 5
+      throw $e1
+    catch $e0
+      i32.const 1
+    catch $e1
+      i32.const 10
+      i32.add
+    end
+  )
+
+  (func (export "uncaught") (result i32)
+    try (result i32)
+      throw $e0
+    catch $e1
+    end
+  )
+
+  (func (export "delegate") (result i32)
+    try $outer (result i32)
+      try (result i32)
+        i32.const 3
+        throw $e1
+      delegate $outer
+    catch $e1
+      i32.const 100
+      i32.add
+    end
+  )
+
+  (func (export "delegate-skips-catch") (result i32)
+    try $outer (result i32)
+      try (result i32)
+        try (result i32)
+          throw $e0
+        delegate $outer
+      catch $e0
+        i32.const 1
+      end
+    catch $e0
+      i32.const 2
+    end
+  )
+
+  (func (export "delegate-to-caller") (result i32)
+    try (result i32)
+      try (result i32)
+        throw $e0
+      delegate 1
+    catch $e0
+      i32.const 1
+    end
+  )
+
+  (func (export "rethrow") (result i32)
+    try (result i32)
+      try (result i32)
+        i32.const 7
+        throw $e1
+      catch $e1
+        drop
+        rethrow 0
+      end
+    catch $e1
+    end
+  )
+
+  (func (export "rethrow-outer") (result i32)
+    try (result i32)
+      try (result i32)
+        i32.const 9
+        throw $e1
+      catch_all
+        try (result i32)
+          throw $e0
+        catch $e0
+          rethrow 1
+        end
+      end
+    catch $e1
+    catch $e0
+      i32.const 0
+    end
+  )
+
+  (func (export "no-throw") (result i32)
+    try (result i32)
+      i32.const 11
+    catch_all
+      i32.const 0
+    end
+  )
+)
+
+(assert_return (invoke "catch-empty") (i32.const 1))
+(assert_return (invoke "catch-param" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "catch-param" (i32.const 1)) (i32.const 42))
+(assert_return (invoke "catch-multi-param") (i64.const 8))
+(assert_return (invoke "catch-all") (i32.const 2))
+(assert_return (invoke "catch-second") (i32.const 15))
+(assert_exception (invoke "uncaught"))
+(assert_return (invoke "delegate") (i32.const 103))
+(assert_return (invoke "delegate-skips-catch") (i32.const 2))
+(assert_exception (invoke "delegate-to-caller"))
+(assert_return (invoke "rethrow") (i32.const 7))
+(assert_return (invoke "rethrow-outer") (i32.const 9))
+(assert_return (invoke "no-throw") (i32.const 11))
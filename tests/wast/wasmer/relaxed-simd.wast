@@ -0,0 +1,167 @@
+;; The relaxed SIMD operators must behave like their deterministic
+;; counterparts by default.
+
+(module
+  (func (export "i8x16.relaxed_swizzle") (param v128 v128) (result v128)
+    (i8x16.relaxed_swizzle (local.get 0) (local.get 1)))
+  (func (export "i32x4.relaxed_trunc_f32x4_s") (param v128) (result v128)
+    (i32x4.relaxed_trunc_f32x4_s (local.get 0)))
+  (func (export "i32x4.relaxed_trunc_f32x4_u") (param v128) (result v128)
+    (i32x4.relaxed_trunc_f32x4_u (local.get 0)))
+  (func (export "i32x4.relaxed_trunc_f64x2_s_zero") (param v128) (result v128)
+    (i32x4.relaxed_trunc_f64x2_s_zero (local.get 0)))
+  (func (export "i32x4.relaxed_trunc_f64x2_u_zero") (param v128) (result v128)
+    (i32x4.relaxed_trunc_f64x2_u_zero (local.get 0)))
+  (func (export "f32x4.relaxed_madd") (param v128 v128 v128) (result v128)
+    (f32x4.relaxed_madd (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "f32x4.relaxed_nmadd") (param v128 v128 v128) (result v128)
+    (f32x4.relaxed_nmadd (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "f64x2.relaxed_madd") (param v128 v128 v128) (result v128)
+    (f64x2.relaxed_madd (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "f64x2.relaxed_nmadd") (param v128 v128 v128) (result v128)
+    (f64x2.relaxed_nmadd (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "i8x16.relaxed_laneselect") (param v128 v128 v128) (result v128)
+    (i8x16.relaxed_laneselect (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "i16x8.relaxed_laneselect") (param v128 v128 v128) (result v128)
+    (i16x8.relaxed_laneselect (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "i32x4.relaxed_laneselect") (param v128 v128 v128) (result v128)
+    (i32x4.relaxed_laneselect (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "i64x2.relaxed_laneselect") (param v128 v128 v128) (result v128)
+    (i64x2.relaxed_laneselect (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "f32x4.relaxed_min") (param v128 v128) (result v128)
+    (f32x4.relaxed_min (local.get 0) (local.get 1)))
+  (func (export "f32x4.relaxed_max") (param v128 v128) (result v128)
+    (f32x4.relaxed_max (local.get 0) (local.get 1)))
+  (func (export "f64x2.relaxed_min") (param v128 v128) (result v128)
+    (f64x2.relaxed_min (local.get 0) (local.get 1)))
+  (func (export "f64x2.relaxed_max") (param v128 v128) (result v128)
+    (f64x2.relaxed_max (local.get 0) (local.get 1)))
+  (func (export "i16x8.relaxed_q15mulr_s") (param v128 v128) (result v128)
+    (i16x8.relaxed_q15mulr_s (local.get 0) (local.get 1)))
+  (func (export "i16x8.relaxed_dot_i8x16_i7x16_s") (param v128 v128) (result v128)
+    (i16x8.relaxed_dot_i8x16_i7x16_s (local.get 0) (local.get 1)))
+  (func (export "i32x4.relaxed_dot_i8x16_i7x16_add_s") (param v128 v128 v128) (result v128)
+    (i32x4.relaxed_dot_i8x16_i7x16_add_s (local.get 0) (local.get 1) (local.get 2)))
+)
+
+;; Out of range indices select 0, like `i8x16.swizzle`.
+(assert_return
+  (invoke "i8x16.relaxed_swizzle"
+    (v128.const i8x16 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15)
+    (v128.const i8x16 15 0 16 17 127 128 255 1 2 3 4 5 6 7 8 9))
+  (v128.const i8x16 15 0 0 0 0 0 0 1 2 3 4 5 6 7 8 9))
+
+;; NaNs convert to 0 and out of range values saturate.
+(assert_return
+  (invoke "i32x4.relaxed_trunc_f32x4_s" (v128.const f32x4 nan -1e10 1e10 -1.5))
+  (v128.const i32x4 0 0x80000000 0x7fffffff -1))
+(assert_return
+  (invoke "i32x4.relaxed_trunc_f32x4_u" (v128.const f32x4 nan -1.5 1e10 4294967040))
+  (v128.const i32x4 0 0 0xffffffff 4294967040))
+(assert_return
+  (invoke "i32x4.relaxed_trunc_f64x2_s_zero" (v128.const f64x2 nan -1e10))
+  (v128.const i32x4 0 0x80000000 0 0))
+(assert_return
+  (invoke "i32x4.relaxed_trunc_f64x2_s_zero" (v128.const f64x2 1e10 -2.5))
+  (v128.const i32x4 0x7fffffff -2 0 0))
+(assert_return
+  (invoke "i32x4.relaxed_trunc_f64x2_u_zero" (v128.const f64x2 nan 1e10))
+  (v128.const i32x4 0 0xffffffff 0 0))
+
+;; The multiply-adds are fused: the product isn't rounded before the addition.
+(assert_return
+  (invoke "f32x4.relaxed_madd"
+    (v128.const f32x4 0x1.000002p+0 0x1.000002p+0 2 -3)
+    (v128.const f32x4 0x1.000002p+0 0x1.000002p+0 3 4)
+    (v128.const f32x4 -0x1.000004p+0 -0x1.000004p+0 1 0.5))
+  (v128.const f32x4 0x1p-46 0x1p-46 7 -11.5))
+(assert_return
+  (invoke "f32x4.relaxed_nmadd"
+    (v128.const f32x4 0x1.000002p+0 0x1.000002p+0 2 -3)
+    (v128.const f32x4 0x1.000002p+0 0x1.000002p+0 3 4)
+    (v128.const f32x4 0x1.000004p+0 0x1.000004p+0 1 0.5))
+  (v128.const f32x4 -0x1p-46 -0x1p-46 -5 12.5))
+(assert_return
+  (invoke "f64x2.relaxed_madd"
+    (v128.const f64x2 0x1.0000000000001p+0 2)
+    (v128.const f64x2 0x1.0000000000001p+0 3)
+    (v128.const f64x2 -0x1.0000000000002p+0 1))
+  (v128.const f64x2 0x1p-104 7))
+(assert_return
+  (invoke "f64x2.relaxed_nmadd"
+    (v128.const f64x2 0x1.0000000000001p+0 2)
+    (v128.const f64x2 0x1.0000000000001p+0 3)
+    (v128.const f64x2 0x1.0000000000002p+0 1))
+  (v128.const f64x2 -0x1p-104 -5))
+
+;; Every bit of the mask is used, like `v128.bitselect`.
+(assert_return
+  (invoke "i8x16.relaxed_laneselect"
+    (v128.const i8x16 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff)
+    (v128.const i8x16 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0)
+    (v128.const i8x16 0x0f 0xf0 0x80 0x7f 0 0xff 1 2 3 4 5 6 7 8 9 10))
+  (v128.const i8x16 0x0f 0xf0 0x80 0x7f 0 0xff 1 2 3 4 5 6 7 8 9 10))
+(assert_return
+  (invoke "i16x8.relaxed_laneselect"
+    (v128.const i16x8 0x1234 0x1234 0x1234 0x1234 0x1234 0x1234 0x1234 0x1234)
+    (v128.const i16x8 0xabcd 0xabcd 0xabcd 0xabcd 0xabcd 0xabcd 0xabcd 0xabcd)
+    (v128.const i16x8 0xff00 0x00ff 0x8000 0x7fff 0 0xffff 0xf0f0 0x0f0f))
+  (v128.const i16x8 0x12cd 0xab34 0x2bcd 0x9234 0xabcd 0x1234 0x1b3d 0xa2c4))
+(assert_return
+  (invoke "i32x4.relaxed_laneselect"
+    (v128.const i32x4 0x12345678 0x12345678 0x12345678 0x12345678)
+    (v128.const i32x4 0xabcdef01 0xabcdef01 0xabcdef01 0xabcdef01)
+    (v128.const i32x4 0xffff0000 0x0000ffff 0x80000000 0x7fffffff))
+  (v128.const i32x4 0x1234ef01 0xabcd5678 0x2bcdef01 0x92345678))
+(assert_return
+  (invoke "i64x2.relaxed_laneselect"
+    (v128.const i64x2 0x123456789abcdef0 0x123456789abcdef0)
+    (v128.const i64x2 0xfedcba9876543210 0xfedcba9876543210)
+    (v128.const i64x2 0xffffffff00000000 0x8000000000000000))
+  (v128.const i64x2 0x1234567876543210 0x7edcba9876543210))
+
+;; NaNs propagate and -0 is below +0, like `fmin` and `fmax`.
+(assert_return
+  (invoke "f32x4.relaxed_min"
+    (v128.const f32x4 -0.0 0.0 1 2)
+    (v128.const f32x4 0.0 -0.0 2 1))
+  (v128.const f32x4 -0.0 -0.0 1 1))
+(assert_return
+  (invoke "f32x4.relaxed_max"
+    (v128.const f32x4 -0.0 0.0 1 2)
+    (v128.const f32x4 0.0 -0.0 2 1))
+  (v128.const f32x4 0.0 0.0 2 2))
+(assert_return
+  (invoke "f32x4.relaxed_min"
+    (v128.const f32x4 nan 0 nan 1)
+    (v128.const f32x4 0 nan nan 1))
+  (v128.const f32x4 nan:canonical nan:canonical nan:canonical 1))
+(assert_return
+  (invoke "f64x2.relaxed_min"
+    (v128.const f64x2 -0.0 nan)
+    (v128.const f64x2 0.0 1))
+  (v128.const f64x2 -0.0 nan:canonical))
+(assert_return
+  (invoke "f64x2.relaxed_max"
+    (v128.const f64x2 -0.0 1)
+    (v128.const f64x2 0.0 nan))
+  (v128.const f64x2 0.0 nan:canonical))
+
+;; `-32768 * -32768` saturates, like `i16x8.q15mulr_sat_s`.
+(assert_return
+  (invoke "i16x8.relaxed_q15mulr_s"
+    (v128.const i16x8 -32768 -32768 16384 16384 -16384 32767 1 0)
+    (v128.const i16x8 -32768 32767 16384 -16384 -16384 32767 1 0))
+  (v128.const i16x8 32767 -32767 8192 -8192 8192 32766 0 0))
+
+(assert_return
+  (invoke "i16x8.relaxed_dot_i8x16_i7x16_s"
+    (v128.const i8x16 1 2 3 4 5 6 7 8 -128 -128 127 127 -1 -1 0 0)
+    (v128.const i8x16 1 2 3 4 5 6 7 8 127 127 127 127 127 0 127 127))
+  (v128.const i16x8 5 25 61 113 -32512 32258 -127 0))
+(assert_return
+  (invoke "i32x4.relaxed_dot_i8x16_i7x16_add_s"
+    (v128.const i8x16 1 2 3 4 5 6 7 8 -128 -128 -128 -128 -1 -1 0 0)
+    (v128.const i8x16 1 2 3 4 5 6 7 8 127 127 127 127 127 0 127 127)
+    (v128.const i32x4 1 -1 0 0x7fffffff))
+  (v128.const i32x4 31 173 -65024 0x7fffff80))